@@ -3,12 +3,17 @@ use std::{iter::FromIterator, path::PathBuf, time::Duration};
 use anyhow::{bail, format_err, Context, Result};
 use hash_engine::{
     experiment::controller::config::{OutputPersistenceConfig, OUTPUT_PERSISTENCE_KEY},
+    hash_types::worker::RunnerError,
     output::local::config::LocalPersistenceConfig,
-    proto::{self, ExecutionEnvironment},
+    proto::{self, ExecutionEnvironment, ExperimentRunTrait},
+    simulation::status::StopSource,
     utils::parse_env_duration,
 };
 use serde_json::json;
-use tokio::time::{self, timeout};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    time::{self, timeout},
+};
 
 use super::process;
 use crate::{exsrv::Handler, manifest::read_manifest, Args};
@@ -23,7 +28,10 @@ lazy_static::lazy_static! {
 /// The simulations will run to completion and the connection will finish once the last run is done,
 /// or if there is an error.
 pub async fn run_experiment(args: Args, handler: Handler) -> Result<()> {
-    let project = &args.project;
+    let project = args
+        .project
+        .as_ref()
+        .context("A project is required to run an experiment")?;
     let absolute_project_path = PathBuf::from(project)
         .canonicalize()
         .with_context(|| format!("Could not canonicalize project path: {project:?}"))?;
@@ -35,7 +43,12 @@ pub async fn run_experiment(args: Args, handler: Handler) -> Result<()> {
             .to_string(),
     );
 
-    let experiment_run = read_manifest(&absolute_project_path, &args.r#type)?;
+    let experiment_run = read_manifest(
+        &absolute_project_path,
+        &args.r#type,
+        args.seed,
+        &args.step_datasets,
+    )?;
     run_experiment_with_manifest(args, experiment_run, project_name, handler).await?;
     Ok(())
 }
@@ -45,20 +58,32 @@ fn create_engine_command(
     experiment_id: &str,
     controller_url: &str,
 ) -> Result<Box<dyn process::Command + Send>> {
+    let options = process::EngineOptions {
+        continue_on_error: args.continue_on_error,
+        checkpoint_interval: args.checkpoint_interval,
+        restore_checkpoint: args.restore_checkpoint.clone(),
+        task_timeout: args.task_timeout,
+        step_timeout: args.step_timeout,
+        max_runner_restarts: args.max_runner_restarts,
+        memory_dir: args.memory_dir.clone(),
+        target_batch_size: args.target_batch_size,
+        compaction_interval: args.compaction_interval,
+    };
     Ok(Box::new(process::LocalCommand::new(
         experiment_id,
         args.num_workers as usize,
         controller_url,
+        options,
     )?))
 }
 
 async fn run_experiment_with_manifest(
     args: Args,
-    experiment_run: proto::ExperimentRun,
+    experiment_run: proto::ExperimentRunRepr,
     project_name: String,
     mut handler: Handler,
 ) -> Result<()> {
-    let experiment_id = experiment_run.base.id.clone();
+    let experiment_id = experiment_run.base().id.clone();
     let mut engine_handle = handler
         .register_experiment(&experiment_id)
         .await
@@ -101,9 +126,14 @@ async fn run_experiment_with_manifest(
             output_folder
         })),
     )];
+    // The first Ctrl-C asks the engine to stop the experiment, so the output of the simulation
+    // runs is still persisted, a second one exits right away. The stream listens from before the
+    // experiment starts until it finished, so no interrupt is missed.
+    let mut interrupts = signal(SignalKind::interrupt()).context("Could not listen for Ctrl-C")?;
+
     // Now we can send the init message
     let init_message = proto::InitMessage {
-        experiment: experiment_run.clone().into(),
+        experiment: experiment_run.clone(),
         env: ExecutionEnvironment::None, // We don't connect to the API
         dyn_payloads: serde_json::Map::from_iter(map_iter),
     };
@@ -113,6 +143,7 @@ async fn run_experiment_with_manifest(
         .context("Could not send `Init` message")?;
     debug!("Sent init message to {experiment_id}");
 
+    let mut stop_requested = false;
     loop {
         let msg: Option<proto::EngineStatus>;
         tokio::select! {
@@ -120,6 +151,19 @@ async fn run_experiment_with_manifest(
                 error!("Did not receive status from experiment {experiment_id} for over {:?}. Exiting now.", *ENGINE_WAIT_TIMEOUT);
                 break;
             }
+            _ = interrupts.recv() => {
+                if stop_requested {
+                    warn!("Exiting without waiting for experiment {experiment_id} to stop");
+                    break;
+                }
+                info!("Stopping experiment {experiment_id}, press Ctrl-C again to exit immediately");
+                engine_process
+                    .send(&proto::EngineMsg::StopExperiment)
+                    .await
+                    .context("Could not send `StopExperiment` message")?;
+                stop_requested = true;
+                continue;
+            }
             m = engine_handle.recv() => { msg = Some(m) },
         }
         let msg = msg.unwrap();
@@ -134,16 +178,42 @@ async fn run_experiment_with_manifest(
             }
             proto::EngineStatus::SimStatus(status) => {
                 debug!("Got simulation run status: {status:?}");
-                // TODO: OS - handle status fields
+                let stop_msg = status.stop_msg.as_ref().unwrap_or(&serde_json::Value::Null);
+                match &status.stop_source {
+                    Some(StopSource::Agent) => info!(
+                        "Simulation [{}] was stopped by an agent after {} steps: {stop_msg}",
+                        status.sim_id, status.steps_taken
+                    ),
+                    Some(StopSource::Termination { criterion }) => info!(
+                        "Simulation [{}] met the termination criterion {criterion} after {} steps",
+                        status.sim_id, status.steps_taken
+                    ),
+                    Some(StopSource::Experiment) => info!(
+                        "Simulation [{}] was stopped after {} steps",
+                        status.sim_id, status.steps_taken
+                    ),
+                    None => {}
+                }
+                // TODO: OS - handle remaining status fields
             }
             proto::EngineStatus::SimStop(sim_id) => {
                 debug!("Simulation stopped: {sim_id}");
             }
             proto::EngineStatus::Errors(sim_id, errs) => {
-                error!("There were errors when running simulation [{sim_id}]: {errs:?}");
+                for err in errs {
+                    error!(
+                        "There was an error when running simulation [{sim_id}]: {}",
+                        format_runner_error(&err)
+                    );
+                }
             }
             proto::EngineStatus::Warnings(sim_id, warnings) => {
-                warn!("There were warnings when running simulation [{sim_id}]: {warnings:?}");
+                for warning in warnings {
+                    warn!(
+                        "There was a warning when running simulation [{sim_id}]: {}",
+                        format_runner_error(&warning)
+                    );
+                }
             }
             proto::EngineStatus::Logs(sim_id, logs) => {
                 for log in logs {
@@ -177,3 +247,18 @@ async fn run_experiment_with_manifest(
         .context("Could not cleanup after finish")?;
     Ok(())
 }
+
+/// Formats an error or warning reported by the engine as `file:line: message`, followed by its
+/// details if there are any.
+fn format_runner_error(error: &RunnerError) -> String {
+    let location = match (&error.file_name, error.line_number) {
+        (Some(file_name), Some(line_number)) => format!("{file_name}:{line_number}: "),
+        (Some(file_name), None) => format!("{file_name}: "),
+        _ => String::new(),
+    };
+    let message = error.message.as_deref().unwrap_or("Unknown error");
+    match &error.details {
+        Some(details) => format!("{location}{message}\n{details}"),
+        None => format!("{location}{message}"),
+    }
+}
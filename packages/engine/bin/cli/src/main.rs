@@ -11,7 +11,7 @@ pub mod exsrv;
 pub mod manifest;
 pub mod process;
 
-use anyhow::Result;
+use anyhow::{format_err, Result};
 use experiment::run_experiment;
 use structopt::StructOpt;
 
@@ -19,12 +19,14 @@ use crate::exsrv::create_server;
 
 /// The hEngine Command line interface.
 ///
-/// Can run single or simple experiments.
+/// Can run single, simple or optimization experiments.
 #[derive(Debug, StructOpt)]
 pub struct Args {
     /// Path to the project to be run.
+    ///
+    /// Required for every experiment type except `clean`.
     #[structopt(short, long, env = "HASH_PROJECT")]
-    project: String,
+    project: Option<String>,
 
     /// The Project Name.
     ///
@@ -45,6 +47,77 @@ pub struct Args {
     /// Max number of parallel workers (must be power of 2).
     #[structopt(short = "w", long, default_value = "4", env = "HASH_WORKERS")]
     num_workers: u16,
+
+    /// Keep simulation runs going when behaviors raise errors.
+    ///
+    /// By default, a simulation run is stopped after the errors were reported.
+    #[structopt(long, env = "HASH_CONTINUE_ON_ERROR")]
+    continue_on_error: bool,
+
+    /// Seed of the experiment run.
+    ///
+    /// Runs with the same project and seed give identical outputs. If not provided, a random seed
+    /// is chosen.
+    #[structopt(long, env = "HASH_SEED")]
+    seed: Option<u64>,
+
+    /// Write a checkpoint of the simulation state every N steps.
+    ///
+    /// Checkpoints are written to `checkpoints/<step>` in the output folder of a simulation run.
+    #[structopt(long, env = "HASH_CHECKPOINT_INTERVAL")]
+    checkpoint_interval: Option<usize>,
+
+    /// Path to a checkpoint to restore the simulation runs from.
+    ///
+    /// The simulation runs continue stepping from the step the checkpoint was written at.
+    #[structopt(long, env = "HASH_RESTORE_CHECKPOINT")]
+    restore_checkpoint: Option<String>,
+
+    /// Time limit in seconds for a single task running user code, e.g. the behaviors of a batch.
+    ///
+    /// A task which runs over is stopped and reported as an error of the behavior it was running,
+    /// which fails the simulation run.
+    #[structopt(long, env = "HASH_TASK_TIMEOUT")]
+    task_timeout: Option<u64>,
+
+    /// Time limit in seconds for a single step of a simulation run.
+    ///
+    /// A simulation run with a step running over is marked as failed, the other simulation runs
+    /// of the experiment keep going.
+    #[structopt(long, env = "HASH_STEP_TIMEOUT")]
+    step_timeout: Option<u64>,
+
+    /// Number of times a crashed runner of a worker is respawned before the experiment fails.
+    ///
+    /// Only the simulation runs with tasks on the runner when it crashed fail. Defaults to 3.
+    #[structopt(long, env = "HASH_MAX_RUNNER_RESTARTS")]
+    max_runner_restarts: Option<usize>,
+
+    /// Directory to store batches in as memory-mapped files, instead of in shared memory.
+    ///
+    /// Useful when `/dev/shm` is too small for the experiment, e.g. in containers.
+    #[structopt(long, env = "HASH_MEMORY_DIR")]
+    memory_dir: Option<String>,
+
+    /// Number of agents a batch of agents is kept at or below, if possible.
+    ///
+    /// Defaults to 100000.
+    #[structopt(long, env = "HASH_TARGET_BATCH_SIZE")]
+    target_batch_size: Option<usize>,
+
+    /// Rebalance the batches of agents every N steps.
+    ///
+    /// Merges small batches, splits large ones and spreads them evenly across the workers, which
+    /// keeps the workers busy evenly when many agents are created and removed.
+    #[structopt(long, env = "HASH_COMPACTION_INTERVAL")]
+    compaction_interval: Option<usize>,
+
+    /// Name of a dataset with one row per step, in its `step` column.
+    ///
+    /// Behaviors get the row of the current step with `context.step_data(<name>)`. Can be passed
+    /// multiple times, in addition to the step-indexed datasets in the project's `datasets.json`.
+    #[structopt(long = "step-dataset")]
+    step_datasets: Vec<String>,
 }
 
 /// Type of experiment to be run.
@@ -56,6 +129,15 @@ pub enum ExperimentType {
     /// Run a simple experiment.
     #[structopt(name = "simple")]
     SimpleExperiment(SimpleExperimentArgs),
+    /// Run an optimization experiment.
+    #[structopt(name = "optimization")]
+    OptimizationExperiment(OptimizationExperimentArgs),
+    /// Run a fork experiment.
+    #[structopt(name = "fork")]
+    ForkExperiment(ForkExperimentArgs),
+    /// Remove memory segments and output parts left behind by killed experiment runs.
+    #[structopt(name = "clean")]
+    Clean,
 }
 
 /// Single Run Experiment.
@@ -74,11 +156,47 @@ pub struct SimpleExperimentArgs {
     experiment_name: String,
 }
 
+/// Optimization Experiment.
+#[derive(PartialEq, Debug, StructOpt)]
+pub struct OptimizationExperimentArgs {
+    /// Name of the experiment to be run.
+    #[structopt(short = "n", long, env = "HASH_EXPERIMENT")]
+    experiment_name: String,
+
+    /// Number of simulation runs to run in parallel.
+    #[structopt(short = "r", long, default_value = "1", env = "HASH_PARALLEL_RUNS")]
+    num_parallel_runs: usize,
+}
+
+/// Fork Experiment.
+///
+/// Runs the simulation up to the fork step once and then continues from there in one branch per
+/// set of changed properties.
+#[derive(PartialEq, Debug, StructOpt)]
+pub struct ForkExperimentArgs {
+    /// Name of the experiment to be run.
+    #[structopt(short = "n", long, env = "HASH_EXPERIMENT")]
+    experiment_name: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     pretty_env_logger::init();
     let args = Args::from_args();
 
+    if let ExperimentType::Clean = args.r#type {
+        let cleaned = hash_engine::experiment::cleanup::clean_orphans()
+            .map_err(|e| format_err!("Could not clean up experiment runs: {}", e))?;
+        for record in &cleaned {
+            info!(
+                "Cleaned up experiment run {} of experiment {}",
+                record.experiment_run_id, record.experiment_id
+            );
+        }
+        info!("Cleaned up {} experiment runs", cleaned.len());
+        return Ok(());
+    }
+
     let nng_listen_url = {
         use std::time::{SystemTime, UNIX_EPOCH};
         let now = SystemTime::now()
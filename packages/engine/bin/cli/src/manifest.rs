@@ -9,21 +9,33 @@ use anyhow::{bail, format_err, Context, Result};
 use hash_engine::{
     fetch::parse_raw_csv_into_json,
     proto::{
-        ExperimentPackageConfig, ExperimentRun, ExperimentRunBase, InitialState, InitialStateName,
-        ProjectBase, SharedBehavior, SharedDataset, SimPackageArgs, SimpleExperimentConfig,
+        ExperimentPackageConfig, ExperimentRun, ExperimentRunBase, ExperimentRunRepr,
+        ExtendedExperimentPackageConfig, ExtendedExperimentRun, ExternalRunnerConfig,
+        ForkExperimentConfig, ForkExperimentConfigPayload, InitialState, InitialStateName,
+        OptimizationExperimentConfig, OptimizationExperimentConfigPayload, ProjectBase,
+        SharedBehavior, SharedDataset, SimPackageArgs, SimpleExperimentConfig,
         SingleRunExperimentConfig,
     },
+    simulation::seed::derive_seed,
 };
-use rand::{Rng, RngCore};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use rand_distr::{Beta, Distribution, LogNormal, Normal, Poisson};
 use serde::{self, Deserialize, Serialize};
 use serde_json::{json, Map as SerdeMap, Value as SerdeValue};
 
-use crate::{ExperimentType, SimpleExperimentArgs};
+use crate::{ExperimentType, ForkExperimentArgs, OptimizationExperimentArgs, SimpleExperimentArgs};
+
+/// Column holding the step of each row of a step-indexed dataset, unless `datasets.json` names
+/// another one
+const STEP_COLUMN: &str = "step";
 
 lazy_static! {
-    static ref BEHAVIOR_FILE_EXTENSIONS: [&'static OsStr; 3] =
-        [OsStr::new("js"), OsStr::new("py"), OsStr::new("rs")];
+    static ref BEHAVIOR_FILE_EXTENSIONS: [&'static OsStr; 4] = [
+        OsStr::new("js"),
+        OsStr::new("py"),
+        OsStr::new("rs"),
+        OsStr::new("wasm"),
+    ];
     static ref DATASET_FILE_EXTENSIONS: [&'static OsStr; 2] =
         [OsStr::new("csv"), OsStr::new("json")];
 }
@@ -31,20 +43,39 @@ lazy_static! {
 pub fn read_manifest(
     project_path: &Path,
     experiment_type: &ExperimentType,
-) -> Result<ExperimentRun> {
-    let project_base = read_project(project_path)
+    seed: Option<u64>,
+    step_datasets: &[String],
+) -> Result<ExperimentRunRepr> {
+    let mut project_base = read_project(project_path)
         .with_context(|| format!("Could not read project: {project_path:?}"))?;
+    for name in step_datasets {
+        let dataset = project_base
+            .datasets
+            .iter_mut()
+            .find(|dataset| &dataset.filename == name || &dataset.shortname == name)
+            .with_context(|| format!("Couldn't find step-indexed dataset in project: {name}"))?;
+        dataset.step_column = Some(STEP_COLUMN.to_string());
+    }
     let experiment_run_id = create_experiment_run_id(experiment_type);
     let base = ExperimentRunBase {
         id: experiment_run_id,
         project_base,
+        seed: Some(seed.unwrap_or_else(rand::random)),
     };
 
     let package_config = get_package_config(&base, experiment_type)
         .with_context(|| format!("Could not read package config: {project_path:?}"))?;
-    let experiment_run = ExperimentRun {
-        base,
-        package_config,
+    let experiment_run = match package_config {
+        ExtendedExperimentPackageConfig::Basic(package_config) => ExperimentRun {
+            base,
+            package_config,
+        }
+        .into(),
+        package_config => ExtendedExperimentRun {
+            base,
+            package_config,
+        }
+        .into(),
     };
     Ok(experiment_run)
 }
@@ -57,6 +88,9 @@ fn create_experiment_run_id(experiment_type: &ExperimentType) -> String {
     let name = match experiment_type {
         ExperimentType::SingleRunExperiment(_) => "single_run",
         ExperimentType::SimpleExperiment(simple) => &simple.experiment_name,
+        ExperimentType::OptimizationExperiment(optimization) => &optimization.experiment_name,
+        ExperimentType::ForkExperiment(fork) => &fork.experiment_name,
+        ExperimentType::Clean => "clean",
     };
     return format!("{name}-{num:06x}");
 }
@@ -71,6 +105,7 @@ struct Project {
     experiments_json: Option<String>,
     dependencies_json: Option<String>,
     datasets: Vec<SharedDataset>,
+    external_runners: HashMap<String, ExternalRunnerConfig>,
 }
 
 fn get_file_contents(path: &Path) -> Result<String> {
@@ -86,6 +121,74 @@ fn get_file_contents_opt(path: &Path) -> Result<Option<String>> {
     }
 }
 
+/// Reads the source of a behavior. WebAssembly modules are binary, so they are passed to the
+/// engine base64-encoded.
+fn get_behavior_src_opt(path: &Path) -> Result<Option<String>> {
+    if path.extension() != Some(OsStr::new("wasm")) {
+        return get_file_contents_opt(path);
+    }
+    if !path.exists() {
+        return Ok(None);
+    }
+    debug!("Reading binary contents at path: {path:?}");
+    let binary = fs::read(path).with_context(|| format!("Could not read file: {path:?}"))?;
+    Ok(Some(base64::encode(binary)))
+}
+
+/// Configuration of a dataset in the project's `datasets.json`, by the file name of the dataset.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DatasetConfig {
+    /// Column holding the step of each row, if the dataset is step-indexed.
+    step_column: Option<String>,
+}
+
+/// Applies the configuration in the project's `datasets.json` to the datasets in its `data`
+/// folder.
+fn configure_local_datasets(project_path: &Path, datasets: &mut [SharedDataset]) -> Result<()> {
+    let datasets_json = project_path.join("datasets.json");
+    let configs = match get_file_contents_opt(&datasets_json)? {
+        Some(configs) => configs,
+        None => return Ok(()),
+    };
+    let configs: HashMap<String, DatasetConfig> = serde_json::from_str(&configs)
+        .with_context(|| format!("Could not parse dataset configuration: {datasets_json:?}"))?;
+    for (name, config) in configs {
+        let dataset = datasets
+            .iter_mut()
+            .find(|dataset| dataset.filename == name)
+            .with_context(|| format!("datasets.json configures a missing dataset: {name}"))?;
+        dataset.step_column = config.step_column;
+    }
+    Ok(())
+}
+
+/// Reads the external runners of a project from `runners.json`, keyed by the extension of the
+/// behaviors they execute. A runner is started in the project folder unless it specifies another
+/// working directory.
+fn read_local_external_runners(
+    project_path: &Path,
+) -> Result<HashMap<String, ExternalRunnerConfig>> {
+    let runners_json = project_path.join("runners.json");
+    let runners = match get_file_contents_opt(&runners_json)? {
+        Some(runners) => runners,
+        None => return Ok(HashMap::new()),
+    };
+    let mut runners: HashMap<String, ExternalRunnerConfig> = serde_json::from_str(&runners)
+        .with_context(|| format!("Could not parse external runners: {runners_json:?}"))?;
+    for (extension, runner) in &mut runners {
+        if BEHAVIOR_FILE_EXTENSIONS.contains(&OsStr::new(extension)) {
+            bail!("runners.json configures a runner for a built-in language: {extension}");
+        }
+        let working_dir = match runner.working_dir.take() {
+            Some(working_dir) => project_path.join(working_dir),
+            None => project_path.to_path_buf(),
+        };
+        runner.working_dir = Some(working_dir);
+    }
+    Ok(runners)
+}
+
 fn read_local_project(project_path: &Path) -> Result<Project> {
     debug!(
         "Reading local project at: {}",
@@ -102,10 +205,15 @@ fn read_local_project(project_path: &Path) -> Result<Project> {
     let views_folder = project_path.join("views");
     let analysis_json = views_folder.join("analysis.json");
     let data_folder = project_path.join("data");
+    let external_runners =
+        read_local_external_runners(project_path).context("Could not read external runners")?;
+    let mut datasets = read_local_datasets(data_folder).context("Could not read local datasets")?;
+    configure_local_datasets(project_path, &mut datasets)
+        .context("Could not configure local datasets")?;
 
     Ok(Project {
         path: project_path.into(),
-        behaviors: read_local_behaviors(&behaviors_folder)
+        behaviors: read_local_behaviors(&behaviors_folder, &external_runners)
             .context("Could not read local behaviors")?,
         initial_state: read_local_init_file(init_json, init_js, init_py)
             .context("Could not read local init file")?,
@@ -116,7 +224,8 @@ fn read_local_project(project_path: &Path) -> Result<Project> {
             .context("Could not read experiments")?,
         dependencies_json: get_file_contents_opt(&dependencies_json)
             .context("Could not read dependencies")?,
-        datasets: read_local_datasets(data_folder).context("Could not read local datasets")?,
+        datasets,
+        external_runners,
     })
 }
 
@@ -208,6 +317,7 @@ fn read_local_datasets(data_folder: PathBuf) -> Result<Vec<SharedDataset>> {
                         url: None,
                         raw_csv: lossy_file_name.ends_with(".csv"),
                         data: Some(data),
+                        step_column: None,
                     }))
                 }
                 Err(err) => {
@@ -219,7 +329,12 @@ fn read_local_datasets(data_folder: PathBuf) -> Result<Vec<SharedDataset>> {
     }
 }
 
-fn read_local_behaviors(behaviors_folder: &Path) -> Result<Vec<SharedBehavior>> {
+/// Reads the behaviors in `behaviors_folder` which are either written in a language supported by
+/// the engine or have an extension one of the `external_runners` of the project is configured for.
+fn read_local_behaviors(
+    behaviors_folder: &Path,
+    external_runners: &HashMap<String, ExternalRunnerConfig>,
+) -> Result<Vec<SharedBehavior>> {
     debug!("Reading local behaviors");
     let mut behavior_files = vec![];
 
@@ -236,6 +351,11 @@ fn read_local_behaviors(behaviors_folder: &Path) -> Result<Vec<SharedBehavior>>
                 } else {
                     behavior_files.push(path);
                 }
+            } else if extension
+                .to_str()
+                .map_or(false, |extension| external_runners.contains_key(extension))
+            {
+                behavior_files.push(path);
             }
         };
     }
@@ -256,7 +376,7 @@ fn read_local_behaviors(behaviors_folder: &Path) -> Result<Vec<SharedBehavior>>
             id: behavior_file_name.clone(),
             name: behavior_file_name,
             shortnames: vec![], // if this is a dependency, then these will be updated later
-            behavior_src: get_file_contents_opt(&behavior_file_path)
+            behavior_src: get_behavior_src_opt(&behavior_file_path)
                 .context("Could not read behavior")?,
             // this may not return anything if file doesn't exist
             behavior_keys_src: get_file_contents_opt(&behavior_key_file_path)
@@ -523,6 +643,7 @@ fn read_project(project_path: &Path) -> Result<ProjectBase> {
         experiments_src: local_project.experiments_json,
         behaviors: local_project.behaviors,
         datasets: local_project.datasets,
+        external_runners: local_project.external_runners,
         // TODO: allow packages themselves to implement resolvers for local projects to build this
         // field
         packages: vec![SimPackageArgs {
@@ -537,31 +658,103 @@ fn read_project(project_path: &Path) -> Result<ProjectBase> {
 fn get_package_config(
     base: &ExperimentRunBase,
     experiment_type: &ExperimentType,
-) -> Result<ExperimentPackageConfig> {
+) -> Result<ExtendedExperimentPackageConfig> {
     match experiment_type {
-        ExperimentType::SingleRunExperiment(single) => Ok(ExperimentPackageConfig::SingleRun(
-            SingleRunExperimentConfig {
+        ExperimentType::SingleRunExperiment(single) => Ok(ExtendedExperimentPackageConfig::Basic(
+            ExperimentPackageConfig::SingleRun(SingleRunExperimentConfig {
                 num_steps: single.num_steps,
-            },
+            }),
         )),
-        ExperimentType::SimpleExperiment(simple) => Ok(ExperimentPackageConfig::Simple(
-            get_simple_experiment_config(base, simple)?,
+        ExperimentType::SimpleExperiment(simple) => Ok(ExtendedExperimentPackageConfig::Basic(
+            ExperimentPackageConfig::Simple(get_simple_experiment_config(base, simple)?),
+        )),
+        ExperimentType::OptimizationExperiment(optimization) => {
+            Ok(ExtendedExperimentPackageConfig::Optimization(
+                get_optimization_experiment_config(base, optimization)?,
+            ))
+        }
+        ExperimentType::ForkExperiment(fork) => Ok(ExtendedExperimentPackageConfig::Fork(
+            get_fork_experiment_config(base, fork)?,
         )),
+        ExperimentType::Clean => bail!("`clean` doesn't run an experiment"),
     }
 }
 
-fn get_simple_experiment_config(
-    base: &ExperimentRunBase,
-    args: &SimpleExperimentArgs,
-) -> Result<SimpleExperimentConfig> {
+fn read_experiments_manifest(base: &ExperimentRunBase) -> Result<SerdeMap<String, SerdeValue>> {
     let experiments_manifest = base
         .project_base
         .experiments_src
         .clone()
         .ok_or_else(|| format_err!("Experiment configuration not found: experiments.json"))?;
-    let parsed = serde_json::from_str::<SerdeMap<String, SerdeValue>>(&experiments_manifest)
-        .context("Could not parse experiment manifest")?;
-    let plan = create_experiment_plan(&parsed, &args.experiment_name)
+    serde_json::from_str::<SerdeMap<String, SerdeValue>>(&experiments_manifest)
+        .context("Could not parse experiment manifest")
+}
+
+fn get_optimization_experiment_config(
+    base: &ExperimentRunBase,
+    args: &OptimizationExperimentArgs,
+) -> Result<OptimizationExperimentConfig> {
+    let parsed = read_experiments_manifest(base)?;
+    let selected_experiment = parsed.get(&args.experiment_name).ok_or_else(|| {
+        format_err!(
+            "Expected experiments.json to contain the specified experiment definition for \
+             experiment with name: {}",
+            args.experiment_name
+        )
+    })?;
+    let experiment_type = selected_experiment.get("type").and_then(SerdeValue::as_str);
+    if experiment_type != Some("optimization") {
+        bail!(
+            "Expected experiment {} to be an optimization experiment, but its type is {:?}",
+            args.experiment_name,
+            experiment_type
+        );
+    }
+    let payload: OptimizationExperimentConfigPayload =
+        serde_json::from_value(selected_experiment.clone())
+            .context("Could not parse optimization experiment")?;
+    Ok(OptimizationExperimentConfig {
+        experiment_name: args.experiment_name.clone(),
+        payload,
+        num_parallel_runs: args.num_parallel_runs,
+    })
+}
+
+fn get_fork_experiment_config(
+    base: &ExperimentRunBase,
+    args: &ForkExperimentArgs,
+) -> Result<ForkExperimentConfig> {
+    let parsed = read_experiments_manifest(base)?;
+    let selected_experiment = parsed.get(&args.experiment_name).ok_or_else(|| {
+        format_err!(
+            "Expected experiments.json to contain the specified experiment definition for \
+             experiment with name: {}",
+            args.experiment_name
+        )
+    })?;
+    let experiment_type = selected_experiment.get("type").and_then(SerdeValue::as_str);
+    if experiment_type != Some("fork") {
+        bail!(
+            "Expected experiment {} to be a fork experiment, but its type is {:?}",
+            args.experiment_name,
+            experiment_type
+        );
+    }
+    let payload: ForkExperimentConfigPayload = serde_json::from_value(selected_experiment.clone())
+        .context("Could not parse fork experiment")?;
+    Ok(ForkExperimentConfig {
+        experiment_name: args.experiment_name.clone(),
+        payload,
+    })
+}
+
+fn get_simple_experiment_config(
+    base: &ExperimentRunBase,
+    args: &SimpleExperimentArgs,
+) -> Result<SimpleExperimentConfig> {
+    let parsed = read_experiments_manifest(base)?;
+    let seed = base.seed.unwrap_or_default();
+    let plan = create_experiment_plan(&parsed, &args.experiment_name, seed)
         .context("Could not read experiment plan")?;
     let config = SimpleExperimentConfig {
         experiment_name: args.experiment_name.clone(),
@@ -579,9 +772,17 @@ fn get_simple_experiment_config(
     Ok(config)
 }
 
+/// Derives the seed for sampling the experiment called `name` from `seed`, so experiments of a
+/// group or multiparameter experiment are sampled independently.
+fn derive_experiment_seed(seed: u64, name: &str) -> u64 {
+    name.bytes()
+        .fold(seed, |seed, byte| derive_seed(seed, u64::from(byte)))
+}
+
 fn create_experiment_plan(
     experiments: &SerdeMap<String, SerdeValue>,
     experiment_name: &str,
+    seed: u64,
 ) -> Result<SimpleExperimentPlan> {
     let selected_experiment = experiments.get(experiment_name).ok_or_else(|| {
         format_err!(
@@ -594,11 +795,19 @@ fn create_experiment_plan(
         .ok_or_else(|| format_err!("Expected experiment definition to contain an experiment type"))?
         .as_str()
         .ok_or_else(|| format_err!("Expected experiment definition type to have a string value"))?;
+    let seed = derive_experiment_seed(seed, experiment_name);
     match experiment_type {
-        "group" => create_group_variant(selected_experiment, experiments),
-        "multiparameter" => create_multiparameter_variant(selected_experiment, experiments),
-        "optimization" => bail!("Not implemented for optimization experiment types"),
-        _ => create_basic_variant(selected_experiment, experiment_type)
+        "group" => create_group_variant(selected_experiment, experiments, seed),
+        "multiparameter" => create_multiparameter_variant(selected_experiment, experiments, seed),
+        "optimization" => bail!(
+            "Optimization experiments can't be run as simple experiments, use the `optimization` \
+             subcommand instead"
+        ),
+        "fork" => bail!(
+            "Fork experiments can't be run as simple experiments, use the `fork` subcommand \
+             instead"
+        ),
+        _ => create_basic_variant(selected_experiment, experiment_type, seed)
             .context("Could not parse basic variant"),
     }
 }
@@ -606,6 +815,7 @@ fn create_experiment_plan(
 fn create_multiparameter_variant(
     selected_experiment: &SerdeValue,
     experiments: &SerdeMap<String, SerdeValue>,
+    seed: u64,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct MultiparameterVariant {
@@ -629,7 +839,8 @@ fn create_multiparameter_variant(
                     )
                 })
                 .context("Could not parse experiment file")?;
-            create_basic_variant(selected, run_name).context("Could not parse basic variant")
+            create_basic_variant(selected, run_name, derive_experiment_seed(seed, run_name))
+                .context("Could not parse basic variant")
         })
         .collect::<Result<Vec<SimpleExperimentPlan>>>()
         .context("Unable to create sub plans")?;
@@ -661,6 +872,7 @@ fn create_multiparameter_variant(
 fn create_group_variant(
     selected_experiment: &SerdeValue,
     experiments: &SerdeMap<String, SerdeValue>,
+    seed: u64,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct GroupVariant {
@@ -674,7 +886,7 @@ fn create_group_variant(
     var.runs.iter().try_fold(
         SimpleExperimentPlan::new(var.steps as usize),
         |mut acc, name| {
-            let variants = create_experiment_plan(experiments, name)
+            let variants = create_experiment_plan(experiments, name, seed)
                 .context("Could not read experiment plan")?;
             variants.inner.into_iter().for_each(|v| {
                 acc.push(v);
@@ -687,9 +899,10 @@ fn create_group_variant(
 fn create_basic_variant(
     selected_experiment: &SerdeValue,
     experiment_type: &str,
+    seed: u64,
 ) -> Result<SimpleExperimentPlan> {
     match experiment_type {
-        "monte-carlo" => create_monte_carlo_variant_plan(selected_experiment),
+        "monte-carlo" => create_monte_carlo_variant_plan(selected_experiment, seed),
         "values" => create_value_variant_plan(selected_experiment),
         "linspace" => create_linspace_variant_plan(selected_experiment),
         "arange" => create_arange_variant_plan(selected_experiment),
@@ -718,6 +931,7 @@ fn create_variant_with_mapped_value(
 
 fn create_monte_carlo_variant_plan(
     selected_experiment: &SerdeValue,
+    seed: u64,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct MonteCarloVariant {
@@ -760,7 +974,8 @@ fn create_monte_carlo_variant_plan(
     }
 
     impl MonteCarloVariant {
-        fn sample_distribution_fn(&self) -> Result<Mapper> {
+        /// Every sample is drawn with its own generator, seeded by `seed` and the sample's index.
+        fn sample_distribution_fn(&self, seed: u64) -> Result<Mapper> {
             let distribution = match self.distribution.as_str() {
                 "normal" => Box::new(
                     Normal::new(self.mean.unwrap_or(1.0), self.std.unwrap_or(1.0))
@@ -786,8 +1001,8 @@ fn create_monte_carlo_variant_plan(
                     Box::new(Normal::new(1.0, 1.0).context("Unable to create normal distribution")?)
                 }
             };
-            Ok(Box::new(move |_, _| {
-                let mut rng = rand::thread_rng();
+            Ok(Box::new(move |_, index| {
+                let mut rng = StdRng::seed_from_u64(derive_seed(seed, index as u64));
                 distribution.sample(&mut rng).into()
             }))
         }
@@ -798,7 +1013,7 @@ fn create_monte_carlo_variant_plan(
     Ok(create_variant_with_mapped_value(
         &var.field,
         &values,
-        &var.sample_distribution_fn()?,
+        &var.sample_distribution_fn(seed)?,
         var.steps as usize,
     ))
 }
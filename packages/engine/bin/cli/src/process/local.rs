@@ -1,6 +1,9 @@
+use std::os::unix::process::CommandExt;
+
 use anyhow::{format_err, Context, Error, Result};
 use async_trait::async_trait;
 use hash_engine::{nano, proto::EngineMsg};
+use nix::unistd::{setpgid, Pid};
 
 use super::process;
 
@@ -12,7 +15,7 @@ const PROCESS_PATH_DEFAULT: &str = "./target/release/hash_engine";
 
 pub struct LocalProcess {
     experiment_id: String,
-    child: std::process::Child,
+    child: tokio::process::Child,
     client: Option<nano::Client>,
     engine_url: String,
 }
@@ -21,12 +24,30 @@ pub struct LocalProcess {
 impl process::Process for LocalProcess {
     async fn exit_and_cleanup(mut self: Box<Self>) -> Result<()> {
         self.child
-            .kill()
+            .start_kill()
             .or_else(|e| match e.kind() {
                 std::io::ErrorKind::InvalidInput => Ok(()),
                 _ => Err(format_err!("{}", e)),
             })
             .context("Could not kill the process")?;
+        // The engine can't clean up after itself when it's killed
+        self.child
+            .wait()
+            .await
+            .context("Could not wait for the process to exit")?;
+        let experiment_id = self.experiment_id.clone();
+        let cleaned = tokio::task::spawn_blocking(move || {
+            hash_engine::experiment::cleanup::clean_experiment(&experiment_id)
+        })
+        .await?
+        .map_err(|e| format_err!("{}", e))
+        .context("Could not clean up after the process")?;
+        if !cleaned.is_empty() {
+            debug!(
+                "Removed what {} killed experiment runs left behind",
+                cleaned.len()
+            );
+        }
 
         debug!(
             "Cleaned up local engine process for experiment {}",
@@ -50,15 +71,35 @@ impl process::Process for LocalProcess {
     }
 }
 
+/// Settings of the experiment run which are passed on to the engine process as arguments.
+#[derive(Debug, Clone, Default)]
+pub struct EngineOptions {
+    pub continue_on_error: bool,
+    pub checkpoint_interval: Option<usize>,
+    pub restore_checkpoint: Option<String>,
+    pub task_timeout: Option<u64>,
+    pub step_timeout: Option<u64>,
+    pub max_runner_restarts: Option<usize>,
+    pub memory_dir: Option<String>,
+    pub target_batch_size: Option<usize>,
+    pub compaction_interval: Option<usize>,
+}
+
 pub struct LocalCommand {
     engine_url: String,
     experiment_id: String,
     controller_url: String,
     max_num_workers: usize,
+    options: EngineOptions,
 }
 
 impl LocalCommand {
-    pub fn new(experiment_id: &str, max_num_workers: usize, controller_url: &str) -> Result<Self> {
+    pub fn new(
+        experiment_id: &str,
+        max_num_workers: usize,
+        controller_url: &str,
+        options: EngineOptions,
+    ) -> Result<Self> {
         // The NNG URL that the engine process will listen on
         let engine_url = format!("ipc://run-{experiment_id}");
 
@@ -67,6 +108,7 @@ impl LocalCommand {
             experiment_id: experiment_id.to_string(),
             controller_url: controller_url.to_string(),
             max_num_workers,
+            options,
         })
     }
 }
@@ -92,9 +134,49 @@ impl process::Command for LocalCommand {
             .arg(self.max_num_workers.to_string())
             .stdout(std::process::Stdio::inherit())
             .stderr(std::process::Stdio::inherit());
+        // The engine gets a process group of its own, so pressing Ctrl-C in a terminal only
+        // interrupts the CLI, which then stops the experiment
+        unsafe {
+            cmd.pre_exec(|| {
+                setpgid(Pid::from_raw(0), Pid::from_raw(0))
+                    .map_err(|errno| std::io::Error::from_raw_os_error(errno as i32))
+            });
+        }
+        let options = &self.options;
+        if options.continue_on_error {
+            cmd.arg("--continue-on-error");
+        }
+        if let Some(checkpoint_interval) = options.checkpoint_interval {
+            cmd.arg("--checkpoint-interval")
+                .arg(checkpoint_interval.to_string());
+        }
+        if let Some(restore_checkpoint) = &options.restore_checkpoint {
+            cmd.arg("--restore-checkpoint").arg(restore_checkpoint);
+        }
+        if let Some(task_timeout) = options.task_timeout {
+            cmd.arg("--task-timeout").arg(task_timeout.to_string());
+        }
+        if let Some(step_timeout) = options.step_timeout {
+            cmd.arg("--step-timeout").arg(step_timeout.to_string());
+        }
+        if let Some(max_runner_restarts) = options.max_runner_restarts {
+            cmd.arg("--max-runner-restarts")
+                .arg(max_runner_restarts.to_string());
+        }
+        if let Some(memory_dir) = &options.memory_dir {
+            cmd.arg("--memory-dir").arg(memory_dir);
+        }
+        if let Some(target_batch_size) = options.target_batch_size {
+            cmd.arg("--target-batch-size")
+                .arg(target_batch_size.to_string());
+        }
+        if let Some(compaction_interval) = options.compaction_interval {
+            cmd.arg("--compaction-interval")
+                .arg(compaction_interval.to_string());
+        }
         debug!("Running `{cmd:?}`");
 
-        let child = cmd
+        let child = tokio::process::Command::from(cmd)
             .spawn()
             .with_context(|| format!("Could not run command: {process_path:?}"))?;
         debug!(
@@ -1,5 +1,5 @@
 mod local;
 mod process;
 
-pub use local::{LocalCommand, LocalProcess};
+pub use local::{EngineOptions, LocalCommand, LocalProcess};
 pub use process::{Command, Process};
@@ -0,0 +1,69 @@
+mod common;
+
+use serde_json::{json, Value};
+
+/// The agents ordered by `agent_name`, as the order of agents across batches isn't fixed.
+fn by_name(mut agents: Vec<Value>) -> Vec<Value> {
+    agents.sort_by(|a, b| {
+        a["agent_name"]
+            .as_str()
+            .unwrap_or_default()
+            .cmp(b["agent_name"].as_str().unwrap_or_default())
+    });
+    agents
+}
+
+#[test]
+fn restored_run_continues_like_the_original_run() {
+    let original = common::run_project("checkpoint", &[
+        "--seed",
+        "42",
+        "--checkpoint-interval",
+        "7",
+        "single-run",
+        "--num-steps",
+        "20",
+    ]);
+    let original_run = original.single_run();
+    let checkpoint = original_run.path.join("checkpoints").join("7");
+    assert!(checkpoint.is_dir(), "No checkpoint was written at step 7");
+
+    // The seed is taken from the checkpoint
+    let restored = common::run_project("checkpoint", &[
+        "--restore-checkpoint",
+        checkpoint.to_str().unwrap(),
+        "single-run",
+        "--num-steps",
+        "20",
+    ]);
+    let restored_run = restored.single_run();
+
+    // The agent count criterion held on steps 6 and 7 before the checkpoint, so both runs stop
+    // after step 9
+    let original_states = original_run.json_state();
+    let restored_states = restored_run.json_state();
+    assert_eq!(
+        original_states.len(),
+        10,
+        "Initial state and 9 steps expected"
+    );
+    assert_eq!(
+        restored_states.len(),
+        3,
+        "Restored state and 2 steps expected"
+    );
+    assert_eq!(
+        restored_run.stop_message().unwrap()["step"],
+        json!(9),
+        "The termination streaks weren't restored"
+    );
+
+    // Children are created without an id, so they get the same generated ids in both runs. The
+    // interventions before and after the checkpoint change the growth of the parent.
+    for (original_state, restored_state) in original_states[7..].iter().zip(restored_states) {
+        assert_eq!(by_name(original_state.clone()), by_name(restored_state));
+    }
+    let sizes = common::field_by_name(&restored_run.final_agents(), "size");
+    let (_, parent_size) = sizes.iter().find(|(name, _)| name == "parent").unwrap();
+    assert_eq!(parent_size.as_f64(), Some(3.0 + 5.0 * 10.0 + 100.0));
+}
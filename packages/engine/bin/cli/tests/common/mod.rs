@@ -0,0 +1,306 @@
+//! Runs the CLI on the projects in `tests/projects` and reads back the output of the experiment.
+//!
+//! The engine binary is started by the CLI, so it has to be built before running these tests, e.g.
+//! with `cargo build`.
+
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+static NEXT_OUTPUT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// How long to wait for the CLI when it's run in the background
+const CLI_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Root of the engine package, the engine resolves the runner sources relative to it.
+pub fn engine_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+pub fn project_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("projects")
+        .join(name)
+}
+
+/// Runs the project with the given name in `tests/projects`.
+///
+/// `args` are passed to the CLI after the project and output options, so they may contain further
+/// options followed by the experiment type, e.g. `["single-run", "--num-steps", "5"]`.
+pub fn run_project(name: &str, args: &[&str]) -> ExperimentOutput {
+    run_project_at(&project_path(name), args)
+}
+
+pub fn run_project_at(project: &Path, args: &[&str]) -> ExperimentOutput {
+    let output_folder = new_output_folder();
+    let output = cli_command(project, &output_folder, args)
+        .output()
+        .expect("Could not run the CLI");
+    let log = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.status.success(), "The CLI failed:\n{log}");
+
+    ExperimentOutput {
+        log,
+        output_folder,
+        project_name: project.file_name().unwrap().to_string_lossy().to_string(),
+    }
+}
+
+/// Runs the project with the given name like [`run_project`], but interrupts the CLI like Ctrl-C
+/// does once it logged a line containing `interrupt_after`.
+pub fn run_project_interrupted(
+    name: &str,
+    args: &[&str],
+    interrupt_after: &str,
+) -> ExperimentOutput {
+    let project = project_path(name);
+    let output_folder = new_output_folder();
+    let mut cli = cli_command(&project, &output_folder, args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Could not run the CLI");
+
+    // The engine logs to the standard error of the CLI as well
+    let (line_send, line_recv) = mpsc::channel();
+    let stderr = BufReader::new(cli.stderr.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stderr.lines().flatten() {
+            if line_send.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let mut stdout = cli.stdout.take().unwrap();
+    let stdout = std::thread::spawn(move || {
+        let mut contents = String::new();
+        let _ = stdout.read_to_string(&mut contents);
+        contents
+    });
+
+    let mut log = String::new();
+    loop {
+        let line = line_recv
+            .recv_timeout(CLI_TIMEOUT)
+            .unwrap_or_else(|_| panic!("{interrupt_after:?} wasn't logged, log:\n{log}"));
+        log.push_str(&line);
+        log.push('\n');
+        if line.contains(interrupt_after) {
+            break;
+        }
+    }
+    let interrupted = Command::new("kill")
+        .arg("-INT")
+        .arg(cli.id().to_string())
+        .status()
+        .expect("Could not interrupt the CLI");
+    assert!(interrupted.success(), "Could not interrupt the CLI");
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = cli.try_wait().unwrap() {
+            break status;
+        }
+        if started.elapsed() > CLI_TIMEOUT {
+            let _ = cli.kill();
+            panic!("The CLI didn't exit after being interrupted, log:\n{log}");
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    log.extend(line_recv.iter().map(|line| line + "\n"));
+    log.push_str(&stdout.join().unwrap());
+    assert!(status.success(), "The CLI failed:\n{log}");
+
+    ExperimentOutput {
+        log,
+        output_folder,
+        project_name: name.to_string(),
+    }
+}
+
+/// A copy of a project in `tests/projects`, which is removed when this is dropped.
+pub struct ProjectCopy {
+    pub path: PathBuf,
+    folder: PathBuf,
+}
+
+impl Drop for ProjectCopy {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.folder);
+    }
+}
+
+/// Copies the project with the given name in `tests/projects` to a temporary folder and configures
+/// the example runner, which is built next to the CLI, for behaviors with the extension `example`.
+/// `args` are passed to the runner.
+pub fn project_with_example_runner(name: &str, args: &[&str]) -> ProjectCopy {
+    let folder = new_output_folder();
+    let path = folder.join(name);
+    copy_dir(&project_path(name), &path);
+
+    let runner = Path::new(env!("CARGO_BIN_EXE_cli")).with_file_name("example_runner");
+    let runners = serde_json::json!({ "example": { "command": runner, "args": args } });
+    std::fs::write(path.join("runners.json"), runners.to_string()).unwrap();
+    ProjectCopy { path, folder }
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            std::fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
+fn new_output_folder() -> PathBuf {
+    let output_folder = std::env::temp_dir().join(format!(
+        "hash-cli-test-{}-{}",
+        std::process::id(),
+        NEXT_OUTPUT_ID.fetch_add(1, Ordering::SeqCst)
+    ));
+    if output_folder.exists() {
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+    output_folder
+}
+
+fn cli_command(project: &Path, output_folder: &Path, args: &[&str]) -> Command {
+    let cli = Path::new(env!("CARGO_BIN_EXE_cli"));
+    let engine = cli.with_file_name("hash_engine");
+    assert!(
+        engine.is_file(),
+        "The engine binary wasn't found at {engine:?}, build it with `cargo build` first"
+    );
+
+    let mut command = Command::new(cli);
+    command
+        .current_dir(engine_root())
+        .env("ENGINE_PATH", &engine)
+        .env("RUST_LOG", "info")
+        .arg("--project")
+        .arg(project)
+        .arg("--output")
+        .arg(output_folder)
+        .args(args);
+    command
+}
+
+/// The output of an experiment run, which is removed when this is dropped.
+pub struct ExperimentOutput {
+    /// Everything the CLI and the engine logged
+    pub log: String,
+    output_folder: PathBuf,
+    project_name: String,
+}
+
+impl ExperimentOutput {
+    /// The outputs of all simulation runs of the experiment, by simulation run id.
+    pub fn runs(&self) -> BTreeMap<u32, RunOutput> {
+        let project_folder = self.output_folder.join(&self.project_name);
+        let mut experiments = std::fs::read_dir(&project_folder)
+            .unwrap_or_else(|_| panic!("No output was written, log:\n{}", self.log))
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(experiments.len(), 1, "Expected a single experiment run");
+
+        std::fs::read_dir(experiments.remove(0))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .map(|path| {
+                let sim_id = path.file_name().unwrap().to_string_lossy().parse().unwrap();
+                (sim_id, RunOutput { path })
+            })
+            .collect()
+    }
+
+    /// The output of the only simulation run of the experiment.
+    pub fn single_run(&self) -> RunOutput {
+        let mut runs = self.runs();
+        assert_eq!(runs.len(), 1, "Expected a single simulation run");
+        runs.remove(&1).expect("Expected simulation run 1")
+    }
+
+    /// Whether any line logged by the CLI or the engine contains `text`.
+    pub fn logged(&self, text: &str) -> bool {
+        self.log.lines().any(|line| line.contains(text))
+    }
+}
+
+impl Drop for ExperimentOutput {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.output_folder);
+    }
+}
+
+pub struct RunOutput {
+    pub path: PathBuf,
+}
+
+impl RunOutput {
+    fn read_json(&self, file_name: &str) -> Option<Value> {
+        let contents = std::fs::read_to_string(self.path.join(file_name)).ok()?;
+        Some(serde_json::from_str(&contents).unwrap())
+    }
+
+    /// The agents of every step, starting with the initial state.
+    pub fn json_state(&self) -> Vec<Vec<Value>> {
+        serde_json::from_value(
+            self.read_json("json_state.json")
+                .expect("No JSON state was written"),
+        )
+        .unwrap()
+    }
+
+    /// The agents of the last step.
+    pub fn final_agents(&self) -> Vec<Value> {
+        self.json_state().pop().unwrap()
+    }
+
+    pub fn analysis_outputs(&self) -> Value {
+        self.read_json("analysis_outputs.json")
+            .expect("No analysis outputs were written")
+    }
+
+    pub fn stop_message(&self) -> Option<Value> {
+        self.read_json("stop_message.json")
+    }
+}
+
+/// The field `field` of every agent, ordered by `agent_name`.
+pub fn field_by_name(agents: &[Value], field: &str) -> Vec<(String, Value)> {
+    let mut values: Vec<_> = agents
+        .iter()
+        .map(|agent| {
+            (
+                agent["agent_name"].as_str().unwrap_or_default().to_string(),
+                agent[field].clone(),
+            )
+        })
+        .collect();
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+    values
+}
@@ -0,0 +1,19 @@
+mod common;
+
+#[test]
+fn step_data_has_the_row_of_the_current_step() {
+    // `prices.csv` is declared step-indexed in the project's `datasets.json` and has rows for
+    // steps 1 and 3
+    let output = common::run_project("datasets", &["single-run", "--num-steps", "4"]);
+    let run = output.single_run();
+
+    let prices: Vec<_> = run
+        .json_state()
+        .iter()
+        .map(|agents| agents[0]["price"].as_f64().unwrap())
+        .collect();
+    assert_eq!(prices, [0.0, 10.0, -1.0, 30.0]);
+
+    // `context.data()` still has the whole dataset, including the header row of the CSV
+    assert_eq!(run.final_agents()[0]["rows"].as_f64(), Some(3.0));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+#[test]
+fn behavior_error_is_reported_with_its_location() {
+    let output = common::run_project("errors", &["single-run", "--num-steps", "10"]);
+
+    assert!(
+        output.logged("error when running simulation [1]: broken.js:4: Error: counter broke"),
+        "The behavior error wasn't reported:\n{}",
+        output.log
+    );
+    // The error stops the simulation run
+    assert!(output.single_run().json_state().len() < 10);
+}
+
+#[test]
+fn behavior_error_does_not_stop_the_run_with_continue_on_error() {
+    let output = common::run_project("errors", &[
+        "--continue-on-error",
+        "single-run",
+        "--num-steps",
+        "10",
+    ]);
+
+    assert!(output.logged("broken.js:4: Error: counter broke"));
+    let run = output.single_run();
+    assert_eq!(
+        run.json_state().len(),
+        10,
+        "Initial state and 9 steps expected"
+    );
+    // Only the agent whose behavior failed skipped its behaviors in the step of the error
+    let counters = common::field_by_name(&run.final_agents(), "counter");
+    assert_eq!(counters[1].0, "working");
+    assert_eq!(counters[1].1.as_f64(), Some(9.0));
+}
@@ -0,0 +1,21 @@
+mod common;
+
+#[test]
+fn tasks_pass_through_the_example_runner() {
+    let project = common::project_with_example_runner("external_runner", &[]);
+    let output = common::run_project_at(&project.path, &["single-run", "--num-steps", "3"]);
+
+    // The chains continue on the example runner after `count.js`, which finishes the task
+    assert!(
+        output.logged("The example runner doesn't execute behaviors"),
+        "The example runner didn't run:\n{}",
+        output.log
+    );
+    let counters: Vec<_> = output
+        .single_run()
+        .json_state()
+        .iter()
+        .map(|agents| agents[0]["counter"].as_f64().unwrap())
+        .collect();
+    assert_eq!(counters, [0.0, 1.0, 2.0]);
+}
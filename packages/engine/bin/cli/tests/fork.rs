@@ -0,0 +1,50 @@
+mod common;
+
+use serde_json::{json, Value};
+
+/// The size of the only agent on every step of the run.
+fn sizes(run: &common::RunOutput) -> Vec<f64> {
+    run.json_state()
+        .iter()
+        .map(|agents| agents[0]["size"].as_f64().unwrap())
+        .collect()
+}
+
+/// The `total_size` analysis output of every step of the run.
+fn total_sizes(run: &common::RunOutput) -> Vec<Value> {
+    let analysis = run.analysis_outputs();
+    analysis["buffers"]["total_size"]
+        .as_array()
+        .expect("No `total_size` analysis output")
+        .iter()
+        .map(|output| output["Number"].clone())
+        .collect()
+}
+
+#[test]
+fn branches_continue_the_shared_run() {
+    let output = common::run_project("fork", &["fork", "--experiment-name", "branches"]);
+    let runs = output.runs();
+    assert_eq!(runs.len(), 3, "Expected the shared run and two branches");
+
+    // The agent count criterion holds on every step, so the shared run meets it on its fork step.
+    // It's forked nevertheless.
+    let shared = &runs[&1];
+    assert_eq!(sizes(shared), [0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(shared.stop_message().unwrap()["step"], json!(3));
+
+    // The branches start with the history of the shared run
+    let without_termination = &runs[&2];
+    assert_eq!(sizes(without_termination), [0.0, 1.0, 2.0, 3.0, 13.0, 23.0]);
+    assert_eq!(
+        total_sizes(without_termination),
+        [0.0, 1.0, 2.0, 3.0, 13.0, 23.0].map(|size| json!(size))
+    );
+    assert!(without_termination.stop_message().is_none());
+
+    // The branch with the same criteria continues their streaks, so it stops after a step
+    let with_termination = &runs[&3];
+    assert_eq!(sizes(with_termination), [0.0, 1.0, 2.0, 3.0, 103.0]);
+    assert_eq!(total_sizes(with_termination).len(), 5);
+    assert_eq!(with_termination.stop_message().unwrap()["step"], json!(4));
+}
@@ -0,0 +1,29 @@
+mod common;
+
+/// With 40 agents, a single worker runs all agents in one group, while four workers each run one of
+/// four groups, so every Python task only runs on a subset of the groups of the simulation run.
+#[test]
+#[ignore = "needs the Python runner"]
+fn python_behaviors_on_group_subsets_match_a_single_worker() {
+    let single = common::run_project("groups", &[
+        "--num-workers",
+        "1",
+        "single-run",
+        "--num-steps",
+        "5",
+    ]);
+    let multiple = common::run_project("groups", &[
+        "--num-workers",
+        "4",
+        "single-run",
+        "--num-steps",
+        "5",
+    ]);
+
+    let single_totals = common::field_by_name(&single.single_run().final_agents(), "total");
+    let multiple_totals = common::field_by_name(&multiple.single_run().final_agents(), "total");
+    assert_eq!(single_totals.len(), 40);
+    assert_eq!(single_totals, multiple_totals);
+    // Every agent ran its behavior in every step
+    assert_eq!(single_totals[39].1.as_f64(), Some(39.0 * 3.0 * 4.0));
+}
@@ -0,0 +1,17 @@
+mod common;
+
+#[test]
+fn interventions_change_the_topology() {
+    let output = common::run_project("interventions", &["single-run", "--num-steps", "5"]);
+    let run = output.single_run();
+
+    // The x bounds shrink to 10 before step 3, so the walker wraps around from 12 to 2
+    let positions: Vec<_> = run
+        .json_state()
+        .iter()
+        .map(|agents| agents[0]["position"][0].as_f64().unwrap())
+        .collect();
+    assert_eq!(positions, [0.0, 4.0, 8.0, 2.0, 6.0]);
+
+    assert_eq!(run.final_agents()[0]["engine_keys"].as_f64(), Some(0.0));
+}
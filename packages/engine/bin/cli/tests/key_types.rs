@@ -0,0 +1,39 @@
+mod common;
+
+use serde_json::{json, Value};
+
+/// The values of the key types which aren't plain JSON values after each step, as written by the
+/// `types` behavior.
+fn assert_key_types(states: &[Vec<Value>]) {
+    let keys: Vec<_> = states
+        .iter()
+        .map(|agents| {
+            let agent = &agents[0];
+            json!([
+                agent["count"],
+                agent["total"],
+                agent["size"],
+                agent["infection"],
+                agent["born"]
+            ])
+        })
+        .collect();
+    assert_eq!(keys, [
+        json!([1_i64 << 32, 0, 0, "S", 0]),
+        json!([2_i64 << 32, (2_i64 << 56) + 1, 1, "I", 1000]),
+        json!([3_i64 << 32, (3_i64 << 56) + 1, 2, "R", 2000]),
+    ]);
+}
+
+#[test]
+fn javascript_key_types_round_trip() {
+    let output = common::run_project("key_types", &["single-run", "--num-steps", "3"]);
+    assert_key_types(&output.single_run().json_state());
+}
+
+#[test]
+#[ignore = "needs the Python runner"]
+fn python_key_types_round_trip() {
+    let output = common::run_project("python_key_types", &["single-run", "--num-steps", "3"]);
+    assert_key_types(&output.single_run().json_state());
+}
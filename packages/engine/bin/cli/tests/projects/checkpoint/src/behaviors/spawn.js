@@ -0,0 +1,10 @@
+/**
+ * Grows by the `growth` of the globals and creates a child without an id on every step.
+ */
+const behavior = (state, context) => {
+  state.size += context.globals().growth;
+  state.addMessage("hash", "create_agent", {
+    agent_name: `child-${context.step()}`,
+    size: 0,
+  });
+};
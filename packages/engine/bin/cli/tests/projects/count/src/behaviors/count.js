@@ -0,0 +1,6 @@
+/**
+ * Counts the steps.
+ */
+const behavior = (state, context) => {
+  state.counter += 1;
+};
@@ -0,0 +1,6 @@
+const behavior = (state, context) => {
+  state.counter += 1;
+  if (context.step() === 2) {
+    throw new Error("counter broke");
+  }
+};
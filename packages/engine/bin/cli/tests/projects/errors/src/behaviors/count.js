@@ -0,0 +1,3 @@
+const behavior = (state, context) => {
+  state.counter += 1;
+};
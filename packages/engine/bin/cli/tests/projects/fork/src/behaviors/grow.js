@@ -0,0 +1,6 @@
+/**
+ * Grows by the `growth` of the globals on every step.
+ */
+const behavior = (state, context) => {
+  state.size += context.globals().growth;
+};
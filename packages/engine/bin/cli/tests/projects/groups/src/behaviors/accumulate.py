@@ -0,0 +1,7 @@
+"""
+Adds a multiple of the agent's index to its total, so every agent ends up with a different total.
+"""
+
+
+def behavior(state, context):
+    state.total = state.total + state.index * context.globals()["increment"]
@@ -0,0 +1,9 @@
+/**
+ * Moves right by 4 and counts the keys of the globals which only configure the engine.
+ */
+const behavior = (state, context) => {
+  state.position = [state.position[0] + 4, 0, 0];
+  state.engine_keys = ["interventions", "termination"].filter(
+    (key) => key in context.globals()
+  ).length;
+};
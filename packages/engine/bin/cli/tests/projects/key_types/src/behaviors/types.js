@@ -0,0 +1,13 @@
+/**
+ * Changes a key of every type which isn't a plain JSON value.
+ */
+const behavior = (state, context) => {
+  // Needs the upper 32 bits
+  state.count += 2 ** 32;
+  // Integers are read as numbers, but can be written as `BigInt`s, which
+  // keeps values above 2^53 exact
+  state.total = BigInt(state.count) * 2n ** 24n + 1n;
+  state.size += 1;
+  state.infection = state.infection === "S" ? "I" : "R";
+  state.born += 1000;
+};
@@ -0,0 +1,7 @@
+"""
+Grows the agent by the global growth every step.
+"""
+
+
+def behavior(state, context):
+    state.size = state.size + context.globals()["growth"]
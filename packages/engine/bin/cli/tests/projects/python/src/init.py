@@ -0,0 +1,14 @@
+"""
+Creates the agents in Python, so the Python runner also runs the init package.
+"""
+
+
+def init(context):
+    return [
+        {
+            "agent_name": "agent-{}".format(i),
+            "behaviors": ["grow.py"],
+            "size": i,
+        }
+        for i in range(context.globals()["n_agents"])
+    ]
@@ -0,0 +1,13 @@
+"""
+Changes a key of every type which isn't a plain JSON value.
+"""
+
+
+def behavior(state, context):
+    # Needs the upper 32 bits
+    state.count += 2 ** 32
+    # Python integers are exact above 2^53
+    state.total = state.count * 2 ** 24 + 1
+    state.size += 1
+    state.infection = "I" if state.infection == "S" else "R"
+    state.born += 1000
@@ -0,0 +1,10 @@
+"""
+Counts the steps and gets stuck in the second one.
+"""
+
+
+def behavior(state, context):
+    state.counter = state.counter + 1
+    if context.step() == 2:
+        while True:
+            pass
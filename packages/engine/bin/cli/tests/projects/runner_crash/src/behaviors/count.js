@@ -0,0 +1,12 @@
+/**
+ * Counts the steps and passes the agent to the example runner from the step given by the run.
+ */
+const behavior = (state, context) => {
+  state.counter += 1;
+  // The first run crashes the example runner right away, the second run only needs it after the
+  // runner was respawned
+  const start = context.globals().run === 1 ? 1 : 3;
+  if (context.step() === start) {
+    state.behaviors = ["count.js", "skipped.example"];
+  }
+};
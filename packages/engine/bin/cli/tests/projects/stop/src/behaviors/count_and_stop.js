@@ -0,0 +1,9 @@
+/**
+ * Counts the steps and asks the engine to stop the simulation run at step 3.
+ */
+const behavior = (state, context) => {
+  state.counter += 1;
+  if (context.step() === 3) {
+    state.addMessage("hash", "stop", { reason: "counted to three" });
+  }
+};
@@ -0,0 +1,6 @@
+/**
+ * Counts the steps in JavaScript.
+ */
+const behavior = (state, context) => {
+  state.js_count += 1;
+};
@@ -0,0 +1,7 @@
+"""
+Counts the steps in Python.
+"""
+
+
+def behavior(state, context):
+    state.py_count = state.py_count + 1
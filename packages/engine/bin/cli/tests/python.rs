@@ -0,0 +1,22 @@
+mod common;
+
+/// The agents are created by `init.py` and run a Python behavior, so both the init package and the
+/// behavior execution package run in the Python runner.
+#[test]
+#[ignore = "needs the Python runner"]
+fn python_init_and_behavior() {
+    let output = common::run_project("python", &["single-run", "--num-steps", "4"]);
+    let agents = output.single_run().final_agents();
+
+    let sizes = common::field_by_name(&agents, "size");
+    assert_eq!(sizes.len(), 5);
+    for (i, (name, size)) in sizes.iter().enumerate() {
+        assert_eq!(name, &format!("agent-{i}"));
+        // The behavior ran in every step after the first one
+        assert_eq!(
+            size.as_f64(),
+            Some(i as f64 + 3.0 * 2.0),
+            "{name} didn't grow"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+mod common;
+
+#[test]
+fn only_runs_with_tasks_on_a_crashed_runner_fail() {
+    let marker = std::env::temp_dir().join(format!("hash-cli-test-{}-crashed", std::process::id()));
+    let _ = std::fs::remove_file(&marker);
+    let project = common::project_with_example_runner("runner_crash", &[
+        "--crash-once",
+        marker.to_str().unwrap(),
+    ]);
+    // A single worker runs both simulation runs, so they share the example runner
+    let output = common::run_project_at(&project.path, &[
+        "--num-workers",
+        "1",
+        "simple",
+        "--experiment-name",
+        "two runs",
+    ]);
+    let _ = std::fs::remove_file(&marker);
+
+    assert!(
+        output.logged("runner crashed, respawning it"),
+        "The example runner didn't crash:\n{}",
+        output.log
+    );
+    let steps: Vec<_> = output
+        .runs()
+        .values()
+        .map(|run| run.json_state().len())
+        .collect();
+    assert_eq!(steps.len(), 2);
+    // The first run had a task on the runner when it crashed, the second run only needed the
+    // runner after it was respawned and caught up with the state of the run
+    assert!(steps[0] < 6, "The first run didn't fail:\n{}", output.log);
+    assert_eq!(steps[1], 6, "The second run failed:\n{}", output.log);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use serde_json::json;
+
+#[test]
+fn stop_message_ends_the_run_on_the_next_step() {
+    let output = common::run_project("stop", &["single-run", "--num-steps", "10"]);
+    let run = output.single_run();
+
+    // Messages are handled at the start of the step after they were sent, so the stop message
+    // sent in step 3 ends the simulation run after step 4 was run.
+    let json_state = run.json_state();
+    assert_eq!(json_state.len(), 5, "Initial state and 4 steps expected");
+    assert_eq!(run.final_agents()[0]["counter"].as_f64(), Some(4.0));
+    assert_eq!(
+        run.stop_message(),
+        Some(json!({ "reason": "counted to three" }))
+    );
+    assert!(output.logged("was stopped by an agent"));
+}
+
+#[test]
+fn run_without_stop_message_takes_all_steps() {
+    let output = common::run_project("stop", &["single-run", "--num-steps", "3"]);
+    let run = output.single_run();
+
+    assert_eq!(run.final_agents()[0]["counter"].as_f64(), Some(2.0));
+    assert_eq!(run.stop_message(), None);
+}
+
+#[test]
+fn interrupt_stops_the_experiment_and_keeps_the_output() {
+    let output = common::run_project_interrupted(
+        "count",
+        &["single-run", "--num-steps", "1000000"],
+        "Beginning simulation run",
+    );
+    let run = output.single_run();
+
+    let json_state = run.json_state();
+    assert!(
+        json_state.len() < 1000000,
+        "The simulation run wasn't stopped"
+    );
+    let steps_taken = json_state.len() - 1;
+    assert_eq!(
+        run.final_agents()[0]["counter"].as_f64(),
+        Some(steps_taken as f64)
+    );
+    assert_eq!(run.stop_message(), None);
+    assert!(output.logged("Stopping experiment"));
+}
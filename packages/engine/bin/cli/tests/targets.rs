@@ -0,0 +1,38 @@
+mod common;
+
+/// The batches are compacted to two groups of ten agents on a single worker, so the JavaScript
+/// runner runs the behavior task on both groups. Only the agents of the second group continue in
+/// Python, which decides where the task continues for both groups.
+#[test]
+#[ignore = "needs the Python runner"]
+fn group_continuing_in_another_language_decides_the_target() {
+    let output = common::run_project("targets", &[
+        "--num-workers",
+        "1",
+        "--target-batch-size",
+        "10",
+        "--compaction-interval",
+        "1",
+        "single-run",
+        "--num-steps",
+        "5",
+    ]);
+    let agents = output.single_run().final_agents();
+
+    let js_counts = common::field_by_name(&agents, "js_count");
+    let py_counts = common::field_by_name(&agents, "py_count");
+    assert_eq!(js_counts.len(), 20);
+    for ((name, js_count), (_, py_count)) in js_counts.iter().zip(&py_counts) {
+        assert_eq!(
+            js_count.as_f64(),
+            Some(4.0),
+            "{name} didn't run in JavaScript"
+        );
+        let expected = if name.starts_with("mixed") { 4.0 } else { 0.0 };
+        assert_eq!(
+            py_count.as_f64(),
+            Some(expected),
+            "{name} didn't run in Python as expected"
+        );
+    }
+}
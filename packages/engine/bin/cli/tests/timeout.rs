@@ -0,0 +1,82 @@
+mod common;
+
+#[test]
+fn looping_behavior_is_stopped_by_the_task_timeout() {
+    let output = common::run_project("timeout", &[
+        "--task-timeout",
+        "1",
+        "single-run",
+        "--num-steps",
+        "10",
+    ]);
+
+    assert!(
+        output
+            .logged("Behavior loop.js was stopped after running for longer than the task timeout"),
+        "The task timeout wasn't reported:\n{}",
+        output.log
+    );
+    // The timeout fails the simulation run in the step the behavior got stuck in
+    assert_eq!(output.single_run().json_state().len(), 2);
+}
+
+#[test]
+fn looping_behavior_is_stopped_by_the_step_timeout() {
+    // Without a task timeout, the task still running when the step times out has to be cancelled
+    // and its behavior interrupted, otherwise the experiment wouldn't finish.
+    let output = common::run_project("timeout", &[
+        "--step-timeout",
+        "1",
+        "single-run",
+        "--num-steps",
+        "10",
+    ]);
+
+    assert!(
+        output.logged("Step 2 failed: Step exceeded the time limit of 1s"),
+        "The step timeout wasn't reported:\n{}",
+        output.log
+    );
+    assert_eq!(output.single_run().json_state().len(), 2);
+}
+
+#[test]
+#[ignore = "needs the Python runner"]
+fn looping_python_behavior_is_stopped_by_the_task_timeout() {
+    let output = common::run_project("python_timeout", &[
+        "--task-timeout",
+        "1",
+        "single-run",
+        "--num-steps",
+        "10",
+    ]);
+
+    assert!(
+        output
+            .logged("Behavior loop.py was stopped after running for longer than the task timeout"),
+        "The task timeout wasn't reported:\n{}",
+        output.log
+    );
+    assert_eq!(output.single_run().json_state().len(), 2);
+}
+
+#[test]
+#[ignore = "needs the Python runner"]
+fn looping_python_behavior_is_stopped_by_the_step_timeout() {
+    // The Python runner has to interrupt the running task when it's cancelled, as it only confirms
+    // the cancellation afterwards
+    let output = common::run_project("python_timeout", &[
+        "--step-timeout",
+        "1",
+        "single-run",
+        "--num-steps",
+        "10",
+    ]);
+
+    assert!(
+        output.logged("Step 2 failed: Step exceeded the time limit of 1s"),
+        "The step timeout wasn't reported:\n{}",
+        output.log
+    );
+    assert_eq!(output.single_run().json_state().len(), 2);
+}
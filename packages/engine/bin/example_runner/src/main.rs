@@ -0,0 +1,168 @@
+//! Minimal external runner which speaks the runner protocol (see `format/README.md`) without
+//! executing behaviors.
+//!
+//! It finishes every task without changing the state and returns it to the main loop, so it's a
+//! starting point for runners of other languages and is used to test the protocol. Once per
+//! simulation run it warns that its behaviors were skipped.
+//!
+//! With `--crash-once <marker file>`, the runner exits on its first task unless the marker file
+//! exists, which it creates beforehand. The respawned runner then keeps going, which tests how
+//! workers recover from crashed runners.
+
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
+use flatbuffers_gen::{
+    runner_inbound_msg_generated::{root_as_runner_inbound_msg, RunnerInboundMsgPayload},
+    runner_outbound_msg_generated::{
+        RunnerOutboundMsg, RunnerOutboundMsgArgs, RunnerOutboundMsgPayload, StateSyncCompleted,
+        StateSyncCompletedArgs, TaskCancelled, TaskCancelledArgs, TaskId, TaskMsg, TaskMsgArgs,
+    },
+    runner_warning_generated::{RunnerWarning, RunnerWarningArgs},
+    serialized_generated::{Serialized, SerializedArgs},
+    target_generated::Target,
+};
+use nng::{Protocol, Socket};
+
+/// Version of the runner protocol this runner speaks
+const PROTOCOL_VERSION: u8 = 3;
+
+fn env(name: &str) -> Result<String> {
+    std::env::var(name).with_context(|| format!("{name} is not set"))
+}
+
+/// Sends a `RunnerOutboundMsg` with the payload built by `payload` to the worker.
+fn send(
+    to_worker: &Socket,
+    sim_sid: u32,
+    payload_type: RunnerOutboundMsgPayload,
+    payload: impl FnOnce(&mut FlatBufferBuilder<'_>) -> WIPOffset<UnionWIPOffset>,
+) -> Result<()> {
+    let mut fbb = FlatBufferBuilder::new();
+    let payload = payload(&mut fbb);
+    let msg = RunnerOutboundMsg::create(&mut fbb, &RunnerOutboundMsgArgs {
+        sim_sid,
+        payload_type,
+        payload: Some(payload),
+    });
+    fbb.finish(msg, None);
+    to_worker
+        .send(fbb.finished_data())
+        .map_err(|(_, err)| err)
+        .context("Could not send message to the worker")
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut crash_marker = match (args.next().as_deref(), args.next()) {
+        (None, _) => None,
+        (Some("--crash-once"), Some(marker)) => Some(PathBuf::from(marker)),
+        _ => bail!("Usage: example_runner [--crash-once <marker file>]"),
+    };
+
+    let to_worker = Socket::new(Protocol::Pair0)?;
+    to_worker
+        .dial(&env("HASH_RUNNER_SEND_URL")?)
+        .context("Could not connect to the worker")?;
+    let from_worker = Socket::new(Protocol::Pair0)?;
+    from_worker
+        .listen(&env("HASH_RUNNER_RECV_URL")?)
+        .context("Could not listen for messages of the worker")?;
+
+    // Handshake: the protocol version, then the `Init` message, which is acknowledged with an
+    // arbitrary message
+    to_worker
+        .send([PROTOCOL_VERSION].as_slice())
+        .map_err(|(_, err)| err)?;
+    let _init = to_worker
+        .recv()
+        .context("Could not receive the init message")?;
+    to_worker.send([0_u8].as_slice()).map_err(|(_, err)| err)?;
+
+    let mut warned = HashSet::new();
+    loop {
+        let msg = from_worker.recv().context("Could not receive message")?;
+        let msg = root_as_runner_inbound_msg(&msg).context("Could not parse message")?;
+        let sim_sid = msg.sim_sid();
+        match msg.payload_type() {
+            RunnerInboundMsgPayload::TaskMsg => {
+                if let Some(marker) = crash_marker.take().filter(|marker| !marker.exists()) {
+                    std::fs::write(&marker, "")?;
+                    bail!("Crashing on the first task as told by --crash-once");
+                }
+                let task = msg.payload_as_task_msg().context("Missing task")?;
+                let task_id = TaskId(task.task_id().context("Missing task id")?.0);
+                if warned.insert(sim_sid) {
+                    send(
+                        &to_worker,
+                        sim_sid,
+                        RunnerOutboundMsgPayload::RunnerWarning,
+                        |fbb| {
+                            let msg =
+                                fbb.create_string("The example runner doesn't execute behaviors");
+                            RunnerWarning::create(fbb, &RunnerWarningArgs {
+                                msg: Some(msg),
+                                details: None,
+                            })
+                            .as_union_value()
+                        },
+                    )?;
+                }
+                // The state is left unchanged and the payload is returned as is, which finishes
+                // the task
+                send(
+                    &to_worker,
+                    sim_sid,
+                    RunnerOutboundMsgPayload::TaskMsg,
+                    |fbb| {
+                        let inner = fbb.create_vector(task.payload().inner());
+                        let payload =
+                            Serialized::create(fbb, &SerializedArgs { inner: Some(inner) });
+                        TaskMsg::create(fbb, &TaskMsgArgs {
+                            package_sid: task.package_sid(),
+                            task_id: Some(&task_id),
+                            target: Target::Main,
+                            metaversioning: None,
+                            payload: Some(payload),
+                        })
+                        .as_union_value()
+                    },
+                )?;
+            }
+            RunnerInboundMsgPayload::CancelTask => {
+                // Tasks are finished as soon as they arrive, so there is nothing to stop, but
+                // cancellations are confirmed all the same
+                let cancel = msg
+                    .payload_as_cancel_task()
+                    .context("Missing cancellation")?;
+                let task_id = TaskId(cancel.task_id().context("Missing task id")?.0);
+                send(
+                    &to_worker,
+                    sim_sid,
+                    RunnerOutboundMsgPayload::TaskCancelled,
+                    |fbb| {
+                        TaskCancelled::create(fbb, &TaskCancelledArgs {
+                            task_id: Some(&task_id),
+                        })
+                        .as_union_value()
+                    },
+                )?;
+            }
+            RunnerInboundMsgPayload::StateSync => {
+                send(
+                    &to_worker,
+                    sim_sid,
+                    RunnerOutboundMsgPayload::StateSyncCompleted,
+                    |fbb| {
+                        StateSyncCompleted::create(fbb, &StateSyncCompletedArgs {}).as_union_value()
+                    },
+                )?;
+            }
+            RunnerInboundMsgPayload::TerminateRunner => return Ok(()),
+            RunnerInboundMsgPayload::NONE => bail!("Message from the worker had no payload"),
+            // The runner doesn't keep any state, so other messages are ignored
+            _ => {}
+        }
+    }
+}
@@ -1,8 +1,8 @@
 #! /usr/bin/env python3
 """
 Runs flatc on the flatbuffer definition files, generating Rust & Python files.
-Saves Rust files to packages/engine/src/gen.
-Saves Python files to packages/engine/runner/py/fbs.
+Saves Rust files to packages/engine/lib/flatbuffers_gen/src.
+Saves Python files to packages/engine/src/worker/runner/python/fbs.
 
 Example:
     cd packages/engine
@@ -18,7 +18,7 @@ from pathlib import Path
 
 DIR = Path(os.path.dirname(os.path.abspath(__file__)))
 FORMAT_DIR = DIR.joinpath(".", "format")
-RUST_TARGET_DIR = DIR.joinpath(".", "src", "gen")
+RUST_TARGET_DIR = DIR.joinpath(".", "lib", "flatbuffers_gen", "src")
 PYTHON_TARGET_DIR = DIR.joinpath(".", "src", "worker", "runner", "python", "fbs")
 
 RUST_HEADER = """#![allow(
@@ -62,7 +62,10 @@ def rust(target_dir):
 
         rust_files = [name for name in os.listdir() if name.endswith(".rs")]
 
-        subprocess.run(["rustfmt"] + rust_files, check=True)
+        # Format with the settings of the repository, so the files don't change on `cargo fmt`
+        subprocess.run(
+            ["rustfmt", "--edition", "2021", "--config-path", str(DIR)] + rust_files, check=True
+        )
 
         for name in rust_files:
             process_rust_file(name, target_dir)
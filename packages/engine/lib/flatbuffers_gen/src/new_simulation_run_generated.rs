@@ -257,6 +257,7 @@ impl<'a> NewSimulationRun<'a> {
     pub const VT_DATASTORE_INIT: flatbuffers::VOffsetT = 12;
     pub const VT_PACKAGE_CONFIG: flatbuffers::VOffsetT = 10;
     pub const VT_PROPERTIES: flatbuffers::VOffsetT = 8;
+    pub const VT_SEED: flatbuffers::VOffsetT = 14;
     pub const VT_SID: flatbuffers::VOffsetT = 6;
     pub const VT_SIM_ID: flatbuffers::VOffsetT = 4;
 
@@ -271,6 +272,7 @@ impl<'a> NewSimulationRun<'a> {
         args: &'args NewSimulationRunArgs<'args>,
     ) -> flatbuffers::WIPOffset<NewSimulationRun<'bldr>> {
         let mut builder = NewSimulationRunBuilder::new(_fbb);
+        builder.add_seed(args.seed);
         if let Some(x) = args.datastore_init {
             builder.add_datastore_init(x);
         }
@@ -327,6 +329,13 @@ impl<'a> NewSimulationRun<'a> {
             )
             .unwrap()
     }
+
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self._tab
+            .get::<u64>(NewSimulationRun::VT_SEED, Some(0))
+            .unwrap()
+    }
 }
 
 impl flatbuffers::Verifiable for NewSimulationRun<'_> {
@@ -354,6 +363,7 @@ impl flatbuffers::Verifiable for NewSimulationRun<'_> {
                 Self::VT_DATASTORE_INIT,
                 true,
             )?
+            .visit_field::<u64>(&"seed", Self::VT_SEED, false)?
             .finish();
         Ok(())
     }
@@ -364,6 +374,7 @@ pub struct NewSimulationRunArgs<'a> {
     pub properties: Option<flatbuffers::WIPOffset<&'a str>>,
     pub package_config: Option<flatbuffers::WIPOffset<PackageConfig<'a>>>,
     pub datastore_init: Option<flatbuffers::WIPOffset<DatastoreInit<'a>>>,
+    pub seed: u64,
 }
 impl<'a> Default for NewSimulationRunArgs<'a> {
     #[inline]
@@ -374,6 +385,7 @@ impl<'a> Default for NewSimulationRunArgs<'a> {
             properties: None,     // required field
             package_config: None, // required field
             datastore_init: None, // required field
+            seed: 0,
         }
     }
 }
@@ -425,6 +437,12 @@ impl<'a: 'b, 'b> NewSimulationRunBuilder<'a, 'b> {
             );
     }
 
+    #[inline]
+    pub fn add_seed(&mut self, seed: u64) {
+        self.fbb_
+            .push_slot::<u64>(NewSimulationRun::VT_SEED, seed, 0);
+    }
+
     #[inline]
     pub fn new(
         _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
@@ -458,6 +476,7 @@ impl std::fmt::Debug for NewSimulationRun<'_> {
         ds.field("properties", &self.properties());
         ds.field("package_config", &self.package_config());
         ds.field("datastore_init", &self.datastore_init());
+        ds.field("seed", &self.seed());
         ds.finish()
     }
 }
@@ -31,13 +31,13 @@ pub const ENUM_MIN_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 0;
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
-pub const ENUM_MAX_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 9;
+pub const ENUM_MAX_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 10;
 #[deprecated(
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
 #[allow(non_camel_case_types)]
-pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 10] = [
+pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 11] = [
     RunnerInboundMsgPayload::NONE,
     RunnerInboundMsgPayload::TaskMsg,
     RunnerInboundMsgPayload::CancelTask,
@@ -48,6 +48,7 @@ pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 10]
     RunnerInboundMsgPayload::TerminateSimulationRun,
     RunnerInboundMsgPayload::TerminateRunner,
     RunnerInboundMsgPayload::NewSimulationRun,
+    RunnerInboundMsgPayload::GlobalsSync,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -57,7 +58,7 @@ pub struct RunnerInboundMsgPayload(pub u8);
 impl RunnerInboundMsgPayload {
     pub const CancelTask: Self = Self(2);
     pub const ContextBatchSync: Self = Self(5);
-    pub const ENUM_MAX: u8 = 9;
+    pub const ENUM_MAX: u8 = 10;
     pub const ENUM_MIN: u8 = 0;
     pub const ENUM_VALUES: &'static [Self] = &[
         Self::NONE,
@@ -70,7 +71,9 @@ impl RunnerInboundMsgPayload {
         Self::TerminateSimulationRun,
         Self::TerminateRunner,
         Self::NewSimulationRun,
+        Self::GlobalsSync,
     ];
+    pub const GlobalsSync: Self = Self(10);
     pub const NONE: Self = Self(0);
     pub const NewSimulationRun: Self = Self(9);
     pub const StateInterimSync: Self = Self(6);
@@ -93,6 +96,7 @@ impl RunnerInboundMsgPayload {
             Self::TerminateSimulationRun => Some("TerminateSimulationRun"),
             Self::TerminateRunner => Some("TerminateRunner"),
             Self::NewSimulationRun => Some("NewSimulationRun"),
+            Self::GlobalsSync => Some("GlobalsSync"),
             _ => None,
         }
     }
@@ -678,6 +682,111 @@ impl std::fmt::Debug for TerminateSimulationRun<'_> {
         ds.finish()
     }
 }
+pub enum GlobalsSyncOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct GlobalsSync<'a> {
+    pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GlobalsSync<'a> {
+    type Inner = GlobalsSync<'a>;
+
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf, loc },
+        }
+    }
+}
+
+impl<'a> GlobalsSync<'a> {
+    pub const VT_GLOBALS: flatbuffers::VOffsetT = 4;
+
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GlobalsSync { _tab: table }
+    }
+
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args GlobalsSyncArgs<'args>,
+    ) -> flatbuffers::WIPOffset<GlobalsSync<'bldr>> {
+        let mut builder = GlobalsSyncBuilder::new(_fbb);
+        if let Some(x) = args.globals {
+            builder.add_globals(x);
+        }
+        builder.finish()
+    }
+
+    #[inline]
+    pub fn globals(&self) -> &'a str {
+        self._tab
+            .get::<flatbuffers::ForwardsUOffset<&str>>(GlobalsSync::VT_GLOBALS, None)
+            .unwrap()
+    }
+}
+
+impl flatbuffers::Verifiable for GlobalsSync<'_> {
+    #[inline]
+    fn run_verifier(
+        v: &mut flatbuffers::Verifier,
+        pos: usize,
+    ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+        use self::flatbuffers::Verifiable;
+        v.visit_table(pos)?
+            .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"globals", Self::VT_GLOBALS, true)?
+            .finish();
+        Ok(())
+    }
+}
+pub struct GlobalsSyncArgs<'a> {
+    pub globals: Option<flatbuffers::WIPOffset<&'a str>>,
+}
+impl<'a> Default for GlobalsSyncArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        GlobalsSyncArgs {
+            globals: None, // required field
+        }
+    }
+}
+pub struct GlobalsSyncBuilder<'a: 'b, 'b> {
+    fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+    start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> GlobalsSyncBuilder<'a, 'b> {
+    #[inline]
+    pub fn add_globals(&mut self, globals: flatbuffers::WIPOffset<&'b str>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(GlobalsSync::VT_GLOBALS, globals);
+    }
+
+    #[inline]
+    pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> GlobalsSyncBuilder<'a, 'b> {
+        let start = _fbb.start_table();
+        GlobalsSyncBuilder {
+            fbb_: _fbb,
+            start_: start,
+        }
+    }
+
+    #[inline]
+    pub fn finish(self) -> flatbuffers::WIPOffset<GlobalsSync<'a>> {
+        let o = self.fbb_.end_table(self.start_);
+        self.fbb_.required(o, GlobalsSync::VT_GLOBALS, "globals");
+        flatbuffers::WIPOffset::new(o.value())
+    }
+}
+
+impl std::fmt::Debug for GlobalsSync<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ds = f.debug_struct("GlobalsSync");
+        ds.field("globals", &self.globals());
+        ds.finish()
+    }
+}
 pub enum RunnerInboundMsgOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
@@ -845,6 +954,17 @@ impl<'a> RunnerInboundMsg<'a> {
             None
         }
     }
+
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn payload_as_globals_sync(&self) -> Option<GlobalsSync<'a>> {
+        if self.payload_type() == RunnerInboundMsgPayload::GlobalsSync {
+            let u = self.payload();
+            Some(GlobalsSync::init_from_table(u))
+        } else {
+            None
+        }
+    }
 }
 
 impl flatbuffers::Verifiable for RunnerInboundMsg<'_> {
@@ -910,6 +1030,11 @@ impl flatbuffers::Verifiable for RunnerInboundMsg<'_> {
                             "RunnerInboundMsgPayload::NewSimulationRun",
                             pos,
                         ),
+                    RunnerInboundMsgPayload::GlobalsSync => v
+                        .verify_union_variant::<flatbuffers::ForwardsUOffset<GlobalsSync>>(
+                            "RunnerInboundMsgPayload::GlobalsSync",
+                            pos,
+                        ),
                     _ => Ok(()),
                 },
             )?
@@ -1074,6 +1199,16 @@ impl std::fmt::Debug for RunnerInboundMsg<'_> {
                     )
                 }
             }
+            RunnerInboundMsgPayload::GlobalsSync => {
+                if let Some(x) = self.payload_as_globals_sync() {
+                    ds.field("payload", &x)
+                } else {
+                    ds.field(
+                        "payload",
+                        &"InvalidFlatbuffer: Union discriminant does not match value.",
+                    )
+                }
+            }
             _ => {
                 let x: Option<()> = None;
                 ds.field("payload", &x)
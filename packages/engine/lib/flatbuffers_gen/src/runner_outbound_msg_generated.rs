@@ -32,13 +32,13 @@ pub const ENUM_MIN_RUNNER_OUTBOUND_MSG_PAYLOAD: u8 = 0;
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
-pub const ENUM_MAX_RUNNER_OUTBOUND_MSG_PAYLOAD: u8 = 9;
+pub const ENUM_MAX_RUNNER_OUTBOUND_MSG_PAYLOAD: u8 = 10;
 #[deprecated(
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
 #[allow(non_camel_case_types)]
-pub const ENUM_VALUES_RUNNER_OUTBOUND_MSG_PAYLOAD: [RunnerOutboundMsgPayload; 10] = [
+pub const ENUM_VALUES_RUNNER_OUTBOUND_MSG_PAYLOAD: [RunnerOutboundMsgPayload; 11] = [
     RunnerOutboundMsgPayload::NONE,
     RunnerOutboundMsgPayload::TaskMsg,
     RunnerOutboundMsgPayload::TaskCancelled,
@@ -49,6 +49,7 @@ pub const ENUM_VALUES_RUNNER_OUTBOUND_MSG_PAYLOAD: [RunnerOutboundMsgPayload; 10
     RunnerOutboundMsgPayload::PackageError,
     RunnerOutboundMsgPayload::UserErrors,
     RunnerOutboundMsgPayload::UserWarnings,
+    RunnerOutboundMsgPayload::StateSyncCompleted,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -56,7 +57,7 @@ pub const ENUM_VALUES_RUNNER_OUTBOUND_MSG_PAYLOAD: [RunnerOutboundMsgPayload; 10
 pub struct RunnerOutboundMsgPayload(pub u8);
 #[allow(non_upper_case_globals)]
 impl RunnerOutboundMsgPayload {
-    pub const ENUM_MAX: u8 = 9;
+    pub const ENUM_MAX: u8 = 10;
     pub const ENUM_MIN: u8 = 0;
     pub const ENUM_VALUES: &'static [Self] = &[
         Self::NONE,
@@ -69,6 +70,7 @@ impl RunnerOutboundMsgPayload {
         Self::PackageError,
         Self::UserErrors,
         Self::UserWarnings,
+        Self::StateSyncCompleted,
     ];
     pub const NONE: Self = Self(0);
     pub const PackageError: Self = Self(7);
@@ -76,6 +78,7 @@ impl RunnerOutboundMsgPayload {
     pub const RunnerErrors: Self = Self(4);
     pub const RunnerWarning: Self = Self(5);
     pub const RunnerWarnings: Self = Self(6);
+    pub const StateSyncCompleted: Self = Self(10);
     pub const TaskCancelled: Self = Self(2);
     pub const TaskMsg: Self = Self(1);
     pub const UserErrors: Self = Self(8);
@@ -94,6 +97,7 @@ impl RunnerOutboundMsgPayload {
             Self::PackageError => Some("PackageError"),
             Self::UserErrors => Some("UserErrors"),
             Self::UserWarnings => Some("UserWarnings"),
+            Self::StateSyncCompleted => Some("StateSyncCompleted"),
             _ => None,
         }
     }
@@ -538,6 +542,87 @@ impl std::fmt::Debug for TaskCancelled<'_> {
         ds.finish()
     }
 }
+pub enum StateSyncCompletedOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct StateSyncCompleted<'a> {
+    pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for StateSyncCompleted<'a> {
+    type Inner = StateSyncCompleted<'a>;
+
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf, loc },
+        }
+    }
+}
+
+impl<'a> StateSyncCompleted<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        StateSyncCompleted { _tab: table }
+    }
+
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        _args: &'args StateSyncCompletedArgs,
+    ) -> flatbuffers::WIPOffset<StateSyncCompleted<'bldr>> {
+        let mut builder = StateSyncCompletedBuilder::new(_fbb);
+        builder.finish()
+    }
+}
+
+impl flatbuffers::Verifiable for StateSyncCompleted<'_> {
+    #[inline]
+    fn run_verifier(
+        v: &mut flatbuffers::Verifier,
+        pos: usize,
+    ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+        use self::flatbuffers::Verifiable;
+        v.visit_table(pos)?.finish();
+        Ok(())
+    }
+}
+pub struct StateSyncCompletedArgs {}
+impl<'a> Default for StateSyncCompletedArgs {
+    #[inline]
+    fn default() -> Self {
+        StateSyncCompletedArgs {}
+    }
+}
+pub struct StateSyncCompletedBuilder<'a: 'b, 'b> {
+    fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+    start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> StateSyncCompletedBuilder<'a, 'b> {
+    #[inline]
+    pub fn new(
+        _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+    ) -> StateSyncCompletedBuilder<'a, 'b> {
+        let start = _fbb.start_table();
+        StateSyncCompletedBuilder {
+            fbb_: _fbb,
+            start_: start,
+        }
+    }
+
+    #[inline]
+    pub fn finish(self) -> flatbuffers::WIPOffset<StateSyncCompleted<'a>> {
+        let o = self.fbb_.end_table(self.start_);
+        flatbuffers::WIPOffset::new(o.value())
+    }
+}
+
+impl std::fmt::Debug for StateSyncCompleted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ds = f.debug_struct("StateSyncCompleted");
+        ds.finish()
+    }
+}
 pub enum RunnerOutboundMsgOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
@@ -705,6 +790,17 @@ impl<'a> RunnerOutboundMsg<'a> {
             None
         }
     }
+
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn payload_as_state_sync_completed(&self) -> Option<StateSyncCompleted<'a>> {
+        if self.payload_type() == RunnerOutboundMsgPayload::StateSyncCompleted {
+            let u = self.payload();
+            Some(StateSyncCompleted::init_from_table(u))
+        } else {
+            None
+        }
+    }
 }
 
 impl flatbuffers::Verifiable for RunnerOutboundMsg<'_> {
@@ -768,6 +864,11 @@ impl flatbuffers::Verifiable for RunnerOutboundMsg<'_> {
                             "RunnerOutboundMsgPayload::UserWarnings",
                             pos,
                         ),
+                    RunnerOutboundMsgPayload::StateSyncCompleted => v
+                        .verify_union_variant::<flatbuffers::ForwardsUOffset<StateSyncCompleted>>(
+                            "RunnerOutboundMsgPayload::StateSyncCompleted",
+                            pos,
+                        ),
                     _ => Ok(()),
                 },
             )?
@@ -932,6 +1033,16 @@ impl std::fmt::Debug for RunnerOutboundMsg<'_> {
                     )
                 }
             }
+            RunnerOutboundMsgPayload::StateSyncCompleted => {
+                if let Some(x) = self.payload_as_state_sync_completed() {
+                    ds.field("payload", &x)
+                } else {
+                    ds.field(
+                        "payload",
+                        &"InvalidFlatbuffer: Union discriminant does not match value.",
+                    )
+                }
+            }
             _ => {
                 let x: Option<()> = None;
                 ds.field("payload", &x)
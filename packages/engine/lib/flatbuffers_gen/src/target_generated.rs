@@ -25,18 +25,20 @@ pub const ENUM_MIN_TARGET: i8 = 0;
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
-pub const ENUM_MAX_TARGET: i8 = 4;
+pub const ENUM_MAX_TARGET: i8 = 6;
 #[deprecated(
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
 #[allow(non_camel_case_types)]
-pub const ENUM_VALUES_TARGET: [Target; 5] = [
+pub const ENUM_VALUES_TARGET: [Target; 7] = [
     Target::Python,
     Target::JavaScript,
     Target::Rust,
     Target::Main,
     Target::Dynamic,
+    Target::Wasm,
+    Target::External,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -46,19 +48,23 @@ pub struct Target(pub i8);
 #[allow(non_upper_case_globals)]
 impl Target {
     pub const Dynamic: Self = Self(4);
-    pub const ENUM_MAX: i8 = 4;
+    pub const ENUM_MAX: i8 = 6;
     pub const ENUM_MIN: i8 = 0;
+    pub const External: Self = Self(6);
     pub const ENUM_VALUES: &'static [Self] = &[
         Self::Python,
         Self::JavaScript,
         Self::Rust,
         Self::Main,
         Self::Dynamic,
+        Self::Wasm,
+        Self::External,
     ];
     pub const JavaScript: Self = Self(1);
     pub const Main: Self = Self(3);
     pub const Python: Self = Self(0);
     pub const Rust: Self = Self(2);
+    pub const Wasm: Self = Self(5);
 
     /// Returns the variant's name or "" if unknown.
     pub fn variant_name(self) -> Option<&'static str> {
@@ -68,6 +74,8 @@ impl Target {
             Self::Rust => Some("Rust"),
             Self::Main => Some("Main"),
             Self::Dynamic => Some("Dynamic"),
+            Self::Wasm => Some("Wasm"),
+            Self::External => Some("External"),
             _ => None,
         }
     }
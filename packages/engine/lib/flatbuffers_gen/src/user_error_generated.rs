@@ -35,6 +35,9 @@ impl<'a> flatbuffers::Follow<'a> for UserError<'a> {
 }
 
 impl<'a> UserError<'a> {
+    pub const VT_DETAILS: flatbuffers::VOffsetT = 6;
+    pub const VT_FILE_NAME: flatbuffers::VOffsetT = 8;
+    pub const VT_LINE_NUMBER: flatbuffers::VOffsetT = 10;
     pub const VT_MSG: flatbuffers::VOffsetT = 4;
 
     #[inline]
@@ -48,6 +51,15 @@ impl<'a> UserError<'a> {
         args: &'args UserErrorArgs<'args>,
     ) -> flatbuffers::WIPOffset<UserError<'bldr>> {
         let mut builder = UserErrorBuilder::new(_fbb);
+        if let Some(x) = args.line_number {
+            builder.add_line_number(x);
+        }
+        if let Some(x) = args.file_name {
+            builder.add_file_name(x);
+        }
+        if let Some(x) = args.details {
+            builder.add_details(x);
+        }
         if let Some(x) = args.msg {
             builder.add_msg(x);
         }
@@ -59,6 +71,23 @@ impl<'a> UserError<'a> {
         self._tab
             .get::<flatbuffers::ForwardsUOffset<&str>>(UserError::VT_MSG, None)
     }
+
+    #[inline]
+    pub fn details(&self) -> Option<&'a str> {
+        self._tab
+            .get::<flatbuffers::ForwardsUOffset<&str>>(UserError::VT_DETAILS, None)
+    }
+
+    #[inline]
+    pub fn file_name(&self) -> Option<&'a str> {
+        self._tab
+            .get::<flatbuffers::ForwardsUOffset<&str>>(UserError::VT_FILE_NAME, None)
+    }
+
+    #[inline]
+    pub fn line_number(&self) -> Option<i32> {
+        self._tab.get::<i32>(UserError::VT_LINE_NUMBER, None)
+    }
 }
 
 impl flatbuffers::Verifiable for UserError<'_> {
@@ -70,6 +99,13 @@ impl flatbuffers::Verifiable for UserError<'_> {
         use self::flatbuffers::Verifiable;
         v.visit_table(pos)?
             .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"msg", Self::VT_MSG, false)?
+            .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"details", Self::VT_DETAILS, false)?
+            .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                &"file_name",
+                Self::VT_FILE_NAME,
+                false,
+            )?
+            .visit_field::<i32>(&"line_number", Self::VT_LINE_NUMBER, false)?
             .finish();
         Ok(())
     }
@@ -77,12 +113,20 @@ impl flatbuffers::Verifiable for UserError<'_> {
 
 pub struct UserErrorArgs<'a> {
     pub msg: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub details: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub file_name: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub line_number: Option<i32>,
 }
 
 impl<'a> Default for UserErrorArgs<'a> {
     #[inline]
     fn default() -> Self {
-        UserErrorArgs { msg: None }
+        UserErrorArgs {
+            msg: None,
+            details: None,
+            file_name: None,
+            line_number: None,
+        }
     }
 }
 
@@ -98,6 +142,24 @@ impl<'a: 'b, 'b> UserErrorBuilder<'a, 'b> {
             .push_slot_always::<flatbuffers::WIPOffset<_>>(UserError::VT_MSG, msg);
     }
 
+    #[inline]
+    pub fn add_details(&mut self, details: flatbuffers::WIPOffset<&'b str>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(UserError::VT_DETAILS, details);
+    }
+
+    #[inline]
+    pub fn add_file_name(&mut self, file_name: flatbuffers::WIPOffset<&'b str>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(UserError::VT_FILE_NAME, file_name);
+    }
+
+    #[inline]
+    pub fn add_line_number(&mut self, line_number: i32) {
+        self.fbb_
+            .push_slot_always::<i32>(UserError::VT_LINE_NUMBER, line_number);
+    }
+
     #[inline]
     pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UserErrorBuilder<'a, 'b> {
         let start = _fbb.start_table();
@@ -118,6 +180,9 @@ impl std::fmt::Debug for UserError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut ds = f.debug_struct("UserError");
         ds.field("msg", &self.msg());
+        ds.field("details", &self.details());
+        ds.field("file_name", &self.file_name());
+        ds.field("line_number", &self.line_number());
         ds.finish()
     }
 }
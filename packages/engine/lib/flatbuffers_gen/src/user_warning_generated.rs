@@ -36,6 +36,8 @@ impl<'a> flatbuffers::Follow<'a> for UserWarning<'a> {
 
 impl<'a> UserWarning<'a> {
     pub const VT_DETAILS: flatbuffers::VOffsetT = 6;
+    pub const VT_FILE_NAME: flatbuffers::VOffsetT = 8;
+    pub const VT_LINE_NUMBER: flatbuffers::VOffsetT = 10;
     pub const VT_MSG: flatbuffers::VOffsetT = 4;
 
     #[inline]
@@ -49,6 +51,12 @@ impl<'a> UserWarning<'a> {
         args: &'args UserWarningArgs<'args>,
     ) -> flatbuffers::WIPOffset<UserWarning<'bldr>> {
         let mut builder = UserWarningBuilder::new(_fbb);
+        if let Some(x) = args.line_number {
+            builder.add_line_number(x);
+        }
+        if let Some(x) = args.file_name {
+            builder.add_file_name(x);
+        }
         if let Some(x) = args.details {
             builder.add_details(x);
         }
@@ -70,6 +78,17 @@ impl<'a> UserWarning<'a> {
         self._tab
             .get::<flatbuffers::ForwardsUOffset<&str>>(UserWarning::VT_DETAILS, None)
     }
+
+    #[inline]
+    pub fn file_name(&self) -> Option<&'a str> {
+        self._tab
+            .get::<flatbuffers::ForwardsUOffset<&str>>(UserWarning::VT_FILE_NAME, None)
+    }
+
+    #[inline]
+    pub fn line_number(&self) -> Option<i32> {
+        self._tab.get::<i32>(UserWarning::VT_LINE_NUMBER, None)
+    }
 }
 
 impl flatbuffers::Verifiable for UserWarning<'_> {
@@ -82,6 +101,12 @@ impl flatbuffers::Verifiable for UserWarning<'_> {
         v.visit_table(pos)?
             .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"msg", Self::VT_MSG, true)?
             .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"details", Self::VT_DETAILS, false)?
+            .visit_field::<flatbuffers::ForwardsUOffset<&str>>(
+                &"file_name",
+                Self::VT_FILE_NAME,
+                false,
+            )?
+            .visit_field::<i32>(&"line_number", Self::VT_LINE_NUMBER, false)?
             .finish();
         Ok(())
     }
@@ -90,6 +115,8 @@ impl flatbuffers::Verifiable for UserWarning<'_> {
 pub struct UserWarningArgs<'a> {
     pub msg: Option<flatbuffers::WIPOffset<&'a str>>,
     pub details: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub file_name: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub line_number: Option<i32>,
 }
 
 impl<'a> Default for UserWarningArgs<'a> {
@@ -98,6 +125,8 @@ impl<'a> Default for UserWarningArgs<'a> {
         UserWarningArgs {
             msg: None, // required field
             details: None,
+            file_name: None,
+            line_number: None,
         }
     }
 }
@@ -120,6 +149,18 @@ impl<'a: 'b, 'b> UserWarningBuilder<'a, 'b> {
             .push_slot_always::<flatbuffers::WIPOffset<_>>(UserWarning::VT_DETAILS, details);
     }
 
+    #[inline]
+    pub fn add_file_name(&mut self, file_name: flatbuffers::WIPOffset<&'b str>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(UserWarning::VT_FILE_NAME, file_name);
+    }
+
+    #[inline]
+    pub fn add_line_number(&mut self, line_number: i32) {
+        self.fbb_
+            .push_slot_always::<i32>(UserWarning::VT_LINE_NUMBER, line_number);
+    }
+
     #[inline]
     pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UserWarningBuilder<'a, 'b> {
         let start = _fbb.start_table();
@@ -142,6 +183,8 @@ impl std::fmt::Debug for UserWarning<'_> {
         let mut ds = f.debug_struct("UserWarning");
         ds.field("msg", &self.msg());
         ds.field("details", &self.details());
+        ds.field("file_name", &self.file_name());
+        ds.field("line_number", &self.line_number());
         ds.finish()
     }
 }
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[derive(argh::FromArgs)]
 /// Run the engine.
 pub struct Args {
@@ -16,6 +18,44 @@ pub struct Args {
     /// max number of workers per simulation run (optional).
     #[argh(option)]
     pub max_workers: Option<usize>,
+
+    /// keep simulation runs going when user code raises errors instead of stopping them.
+    #[argh(switch)]
+    pub continue_on_error: bool,
+
+    /// write a checkpoint of the simulation state every N steps (optional).
+    #[argh(option)]
+    pub checkpoint_interval: Option<usize>,
+
+    /// directory of a checkpoint to restore simulation runs from (optional).
+    #[argh(option)]
+    pub restore_checkpoint: Option<PathBuf>,
+
+    /// stop a single task running user code after this many seconds (optional).
+    #[argh(option)]
+    pub task_timeout: Option<u64>,
+
+    /// fail a simulation run when one of its steps takes longer than this many seconds
+    /// (optional).
+    #[argh(option)]
+    pub step_timeout: Option<u64>,
+
+    /// respawn a crashed runner of a worker at most this many times (optional).
+    #[argh(option)]
+    pub max_runner_restarts: Option<usize>,
+
+    /// store batches in memory-mapped files in this directory instead of in shared memory
+    /// (optional).
+    #[argh(option)]
+    pub memory_dir: Option<PathBuf>,
+
+    /// keep batches at or below this many agents, if possible (optional).
+    #[argh(option)]
+    pub target_batch_size: Option<usize>,
+
+    /// rebalance the batches of agents every N steps (optional).
+    #[argh(option)]
+    pub compaction_interval: Option<usize>,
 }
 
 pub fn args() -> Args {
@@ -0,0 +1,109 @@
+//! Checkpoints of the state of a simulation run, which runs can be restored or forked from.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Globals, Result, SimRunConfig};
+use crate::{
+    datastore::{
+        arrow::ipc::record_batch_stream_to_bytes,
+        batch::ArrowBatch,
+        table::state::{ReadState, State},
+        Result as DatastoreResult,
+    },
+    simulation::seed::AgentIdGenerator,
+};
+
+/// Name of the file holding the [`CheckpointMetadata`] of a checkpoint
+pub const METADATA_FILE_NAME: &str = "checkpoint.json";
+/// Name of the file holding the agent batches of a checkpoint as an Arrow IPC stream
+pub const AGENTS_FILE_NAME: &str = "agents.arrow";
+/// Name of the file holding the message batches of a checkpoint as an Arrow IPC stream
+pub const MESSAGES_FILE_NAME: &str = "messages.arrow";
+
+/// Everything of a checkpoint besides the agent and message batches.
+///
+/// Together with the batches, this is all state a simulation run carries from one step to the
+/// next, so a run restored from a checkpoint continues exactly like the run which wrote it. The
+/// random number generators of behaviors are seeded by the seed and the step alone, so they needn't
+/// be stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointMetadata {
+    /// Number of steps taken when the checkpoint was written.
+    pub step: usize,
+    /// The globals of the step, including the changes of the interventions up to it.
+    pub globals: Globals,
+    /// Seed of the experiment run which wrote the checkpoint.
+    pub seed: u64,
+    /// Generator of the ids of agents which are created without one.
+    pub agent_ids: AgentIdGenerator,
+    /// Number of consecutive steps each termination criterion held for.
+    #[serde(default)]
+    pub termination_streaks: Vec<usize>,
+}
+
+impl CheckpointMetadata {
+    /// Reads the metadata of the checkpoint in the directory at `path`.
+    pub fn read(path: &Path) -> Result<CheckpointMetadata> {
+        let metadata_path = path.join(METADATA_FILE_NAME);
+        let metadata = std::fs::read(&metadata_path).map_err(|e| {
+            Error::from(format!(
+                "Couldn't read checkpoint metadata at {:?}: {}",
+                metadata_path, e
+            ))
+        })?;
+        Ok(serde_json::from_slice(&metadata)?)
+    }
+}
+
+/// A checkpoint of the simulation state, written every `checkpoint_interval` steps.
+///
+/// Forked simulation runs continue from an in-memory checkpoint of the run they were forked from.
+#[derive(PartialEq)]
+pub struct CheckpointData {
+    pub metadata: CheckpointMetadata,
+    /// Agent batches as an Arrow IPC stream
+    pub agents: Vec<u8>,
+    /// Message batches as an Arrow IPC stream
+    pub messages: Vec<u8>,
+}
+
+impl CheckpointData {
+    /// Copies the agent and message batches of `state`, which `metadata` belongs to.
+    pub fn from_state(
+        state: &State,
+        metadata: CheckpointMetadata,
+        sim_run_config: &SimRunConfig,
+    ) -> DatastoreResult<CheckpointData> {
+        let store = &sim_run_config.sim.store;
+        let agent_batches: Vec<_> = state
+            .agent_pool()
+            .read_batches()?
+            .iter()
+            .map(|batch| batch.record_batch().clone())
+            .collect();
+        let message_batches: Vec<_> = state
+            .message_pool()
+            .read_batches()?
+            .iter()
+            .map(|batch| batch.record_batch().clone())
+            .collect();
+
+        Ok(CheckpointData {
+            metadata,
+            agents: record_batch_stream_to_bytes(&store.agent_schema.arrow, &agent_batches),
+            messages: record_batch_stream_to_bytes(&store.message_schema.arrow, &message_batches),
+        })
+    }
+}
+
+impl std::fmt::Debug for CheckpointData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckpointData")
+            .field("metadata", &self.metadata)
+            .field("agents", &format_args!("{} bytes", self.agents.len()))
+            .field("messages", &format_args!("{} bytes", self.messages.len()))
+            .finish()
+    }
+}
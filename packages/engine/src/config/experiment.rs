@@ -1,11 +1,28 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use super::{package, worker, worker_pool, Result};
+use super::{package, worker, worker_pool, CheckpointMetadata, Error, Result};
 use crate::{
     config::globals::Globals,
-    proto::{ExperimentId, ExperimentRegisteredId, ExperimentRunRepr, ExperimentRunTrait},
+    datastore::storage::segment::Backend as MemoryBackend,
+    proto::{
+        ExperimentId, ExperimentRegisteredId, ExperimentRunRepr, ExperimentRunTrait,
+        InitialStateName, ProjectBase,
+    },
+    simulation::package::init,
+    Args, Language,
 };
 
+/// Number of agents a batch is kept at or below, unless configured otherwise.
+const DEFAULT_TARGET_BATCH_SIZE: usize = 100_000;
+
+/// A checkpoint which simulation runs are restored from instead of creating their initial state.
+#[derive(Clone, Debug)]
+pub struct RestoreConfig {
+    /// Directory the checkpoint was written to
+    pub path: PathBuf,
+    pub metadata: CheckpointMetadata,
+}
+
 #[derive(Clone)]
 /// Experiment level configuration
 pub struct Config {
@@ -15,27 +32,123 @@ pub struct Config {
     pub run: Arc<ExperimentRunRepr>,
     pub worker_pool: Arc<worker_pool::Config>,
     pub base_globals: Globals,
+    /// Whether simulation runs keep going after user code raised errors, rather than being
+    /// stopped.
+    pub continue_on_error: bool,
+    /// Seed of the experiment run, from which the seeds of its simulation runs are derived.
+    pub seed: u64,
+    /// Number of steps between two checkpoints of the simulation state, if any are written.
+    pub checkpoint_interval: Option<usize>,
+    /// The checkpoint simulation runs are restored from, if any.
+    pub restore: Option<RestoreConfig>,
+    /// Time a single step of a simulation run may take before the simulation run is failed.
+    pub step_timeout: Option<Duration>,
+    /// Where the memory segments of batches are stored.
+    pub memory_backend: MemoryBackend,
+    /// Number of agents a batch is kept at or below, if possible.
+    pub target_batch_size: usize,
+    /// Number of steps between two rebalancings of the batches of agents, if they are rebalanced.
+    pub compaction_interval: Option<usize>,
+}
+
+/// Settings of an experiment run which are given when starting the engine rather than by the
+/// project.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub continue_on_error: bool,
+    pub checkpoint_interval: Option<usize>,
+    pub restore_checkpoint: Option<PathBuf>,
+    pub task_timeout: Option<Duration>,
+    pub step_timeout: Option<Duration>,
+    pub max_runner_restarts: Option<usize>,
+    pub memory_dir: Option<PathBuf>,
+    pub target_batch_size: Option<usize>,
+    pub compaction_interval: Option<usize>,
+}
+
+impl From<&Args> for RunOptions {
+    fn from(args: &Args) -> Self {
+        RunOptions {
+            continue_on_error: args.continue_on_error,
+            checkpoint_interval: args.checkpoint_interval,
+            restore_checkpoint: args.restore_checkpoint.clone(),
+            task_timeout: args.task_timeout.map(Duration::from_secs),
+            step_timeout: args.step_timeout.map(Duration::from_secs),
+            max_runner_restarts: args.max_runner_restarts,
+            memory_dir: args.memory_dir.clone(),
+            target_batch_size: args.target_batch_size,
+            compaction_interval: args.compaction_interval,
+        }
+    }
 }
 
 impl Config {
-    pub(super) fn new(experiment_run: ExperimentRunRepr, max_num_workers: usize) -> Result<Config> {
+    pub(super) fn new(
+        experiment_run: ExperimentRunRepr,
+        max_num_workers: usize,
+        options: RunOptions,
+    ) -> Result<Config> {
+        let RunOptions {
+            continue_on_error,
+            checkpoint_interval,
+            restore_checkpoint,
+            task_timeout,
+            step_timeout,
+            max_runner_restarts,
+            memory_dir,
+            target_batch_size,
+            compaction_interval,
+        } = options;
+        if target_batch_size == Some(0) {
+            return Err(Error::from("The target batch size has to be at least 1"));
+        }
+        if compaction_interval == Some(0) {
+            return Err(Error::from("The compaction interval has to be at least 1"));
+        }
+        if checkpoint_interval == Some(0) {
+            return Err(Error::from("The checkpoint interval has to be at least 1"));
+        }
+
         // For differentiation purposes when multiple experiment runs are active in the same system
         let run_id = uuid::Uuid::new_v4().to_string();
-        let packages = Arc::new(package::ConfigBuilder::new().build()?);
-        let base_globals = Globals::from_json(serde_json::from_str(
+
+        let mut package_config = package::ConfigBuilder::new();
+
+        let mut base_globals = Globals::from_json(serde_json::from_str(
             &experiment_run.base().project_base.globals_src,
         )?)?;
+        let restore = match restore_checkpoint {
+            Some(path) => {
+                let metadata = CheckpointMetadata::read(&path)?;
+                log::info!(
+                    "Restoring simulation runs from the checkpoint at {:?} (step {})",
+                    path,
+                    metadata.step
+                );
+                // The globals of the checkpoint replace the ones of the project
+                base_globals = metadata.globals.clone();
+                package_config = package_config.set_init_packages(&[init::Name::Checkpoint]);
+                Some(RestoreConfig { path, metadata })
+            }
+            None => None,
+        };
+        let packages = Arc::new(package_config.build()?);
+
+        // Restored runs keep the seed of the run which wrote the checkpoint, so they continue
+        // like it unless another seed is given
+        let seed = experiment_run
+            .base()
+            .seed
+            .or_else(|| restore.as_ref().map(|restore| restore.metadata.seed))
+            .unwrap_or_else(rand::random);
+        log::info!("Experiment run seed: {seed}");
 
         let run = Arc::new(experiment_run);
 
-        // TODO: Rust, Python
-        // TODO: Ask packages for what language execution they require.
         let worker_base_config = worker::Config {
-            spawn: worker::SpawnConfig {
-                python: false,
-                rust: false,
-                javascript: true,
-            },
+            spawn: spawn_config(&run.base().project_base)?,
+            task_timeout,
+            max_runner_restarts: max_runner_restarts.unwrap_or(worker::DEFAULT_MAX_RUNNER_RESTARTS),
         };
         let worker_pool = Arc::new(worker_pool::Config::new(
             worker_base_config,
@@ -48,9 +161,27 @@ impl Config {
             run,
             base_globals,
             worker_pool,
+            continue_on_error,
+            seed,
+            checkpoint_interval,
+            restore,
+            step_timeout,
+            memory_backend: memory_dir.map_or(MemoryBackend::SharedMemory, |directory| {
+                MemoryBackend::File { directory }
+            }),
+            target_batch_size: target_batch_size.unwrap_or(DEFAULT_TARGET_BATCH_SIZE),
+            compaction_interval,
         })
     }
 
+    /// The number of steps taken before the simulation runs start, which is only non-zero when
+    /// restoring from a checkpoint.
+    pub fn start_step(&self) -> usize {
+        self.restore
+            .as_ref()
+            .map_or(0, |restore| restore.metadata.step)
+    }
+
     // Downcast this config
     pub fn to_base(&self) -> Result<Config> {
         let run_base = self.run.base().clone();
@@ -60,6 +191,14 @@ impl Config {
             run: Arc::new(run_base.into()),
             worker_pool: self.worker_pool.clone(),
             base_globals: self.base_globals.clone(),
+            continue_on_error: self.continue_on_error,
+            seed: self.seed,
+            checkpoint_interval: self.checkpoint_interval,
+            restore: self.restore.clone(),
+            step_timeout: self.step_timeout,
+            memory_backend: self.memory_backend.clone(),
+            target_batch_size: self.target_batch_size,
+            compaction_interval: self.compaction_interval,
         })
     }
 
@@ -76,6 +215,54 @@ impl From<&Config> for Config {
             run: Arc::clone(&value.run),
             worker_pool: value.worker_pool.clone(),
             base_globals: value.base_globals.clone(),
+            continue_on_error: value.continue_on_error,
+            seed: value.seed,
+            checkpoint_interval: value.checkpoint_interval,
+            restore: value.restore.clone(),
+            step_timeout: value.step_timeout,
+            memory_backend: value.memory_backend.clone(),
+            target_batch_size: value.target_batch_size,
+            compaction_interval: value.compaction_interval,
+        }
+    }
+}
+
+/// Only spawns the runners for the languages the behaviors and the initial state are written in.
+fn spawn_config(project_base: &ProjectBase) -> Result<worker::SpawnConfig> {
+    let mut spawn = worker::SpawnConfig {
+        python: false,
+        javascript: false,
+        rust: false,
+        wasm: false,
+        external: None,
+    };
+    match project_base.initial_state.name {
+        InitialStateName::InitPy => spawn.python = true,
+        InitialStateName::InitJs => spawn.javascript = true,
+        InitialStateName::InitJson => {}
+    }
+    for behavior in &project_base.behaviors {
+        match Language::from_file_name(&behavior.name, &project_base.external_runners)? {
+            Language::Python => spawn.python = true,
+            Language::JavaScript => spawn.javascript = true,
+            Language::Rust => spawn.rust = true,
+            Language::Wasm => spawn.wasm = true,
+            Language::External => {
+                let extension = behavior.name.rsplit('.').next().unwrap_or_default();
+                let runner = &project_base.external_runners[extension];
+                // There is only one slot for external runners in a worker
+                match &spawn.external {
+                    Some(spawned) if spawned != runner => {
+                        return Err(Error::from(format!(
+                            "Behavior \"{}\" needs a different external runner than the other \
+                             behaviors, only one external runner per experiment is supported",
+                            behavior.name
+                        )));
+                    }
+                    _ => spawn.external = Some(runner.clone()),
+                }
+            }
         }
     }
+    Ok(spawn)
 }
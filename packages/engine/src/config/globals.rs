@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 use super::Result;
+use crate::simulation::{intervention::INTERVENTIONS_KEY, termination::TERMINATION_KEY};
+
+/// Keys of the globals which configure the engine rather than the behaviors
+const ENGINE_KEYS: [&str; 2] = [INTERVENTIONS_KEY, TERMINATION_KEY];
 
 // TODO: OS - Go through code-base and verify that out-dated references to "properties" are now
 // "globals". We also have some consts that come in along with our initial world state.
@@ -42,6 +46,18 @@ impl Globals {
     {
         self.0.get(key.as_ref()).cloned()
     }
+
+    /// The globals as behaviors see them, i.e. without the interventions and termination criteria.
+    #[must_use]
+    pub fn for_behaviors(&self) -> Globals {
+        let mut globals = self.clone();
+        if let Some(properties) = globals.0.as_object_mut() {
+            for key in ENGINE_KEYS {
+                properties.remove(key);
+            }
+        }
+        globals
+    }
 }
 
 impl Default for Globals {
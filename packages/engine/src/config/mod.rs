@@ -1,3 +1,4 @@
+pub mod checkpoint;
 mod engine;
 mod error;
 mod experiment;
@@ -13,20 +14,23 @@ mod worker_pool;
 
 use std::sync::Arc;
 
+pub use checkpoint::{CheckpointData, CheckpointMetadata};
 pub use engine::{Config as EngineConfig, Worker, WorkerAllocation};
 pub use error::{Error, Result};
-pub use experiment::Config as ExperimentConfig;
+pub use experiment::{
+    Config as ExperimentConfig, RestoreConfig, RunOptions as ExperimentRunOptions,
+};
 pub use globals::Globals;
 pub use package::{Config as PackageConfig, ConfigBuilder as PackageConfigBuilder};
 pub use persistence::Config as PersistenceConfig;
-pub use simulation::Config as SimulationConfig;
+pub use simulation::{Config as SimulationConfig, ForkConfig, ForkParent};
 pub use store::Config as StoreConfig;
 pub use task_distribution::{Config as TaskDistributionConfig, Distribution};
 pub use topology::Config as TopologyConfig;
 pub use worker::{Config as WorkerConfig, SpawnConfig as WorkerSpawnConfig};
 pub use worker_pool::Config as WorkerPoolConfig;
 
-use crate::{proto::SimulationShortId, Args, Environment};
+use crate::{proto::SimulationShortId, simulation::seed::derive_seed, Args, Environment};
 
 #[derive(Clone)]
 pub struct SimRunConfig {
@@ -38,10 +42,12 @@ pub async fn experiment_config(args: &Args, env: &Environment) -> Result<Experim
     ExperimentConfig::new(
         env.experiment.clone(),
         args.max_workers.unwrap_or_else(num_cpus::get),
+        ExperimentRunOptions::from(args),
     )
 }
 
 impl SimRunConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         global: &Arc<ExperimentConfig>,
         id: SimulationShortId,
@@ -50,6 +56,7 @@ impl SimRunConfig {
         store: StoreConfig,
         persistence: PersistenceConfig,
         max_num_steps: usize,
+        fork: ForkConfig,
     ) -> Result<SimRunConfig> {
         let local = simulation_config(
             id,
@@ -59,23 +66,43 @@ impl SimRunConfig {
             store,
             persistence,
             max_num_steps,
+            fork,
         )?;
         Ok(SimRunConfig {
             exp: global.clone(),
             sim: Arc::new(local),
         })
     }
+
+    /// The metadata of the checkpoint the simulation run continues from, if it was forked from
+    /// another run or restored from a checkpoint.
+    pub fn resumed_from(&self) -> Option<&CheckpointMetadata> {
+        resumed_from(&self.exp, &self.sim.fork)
+    }
+}
+
+fn resumed_from<'c>(
+    global: &'c ExperimentConfig,
+    fork: &'c ForkConfig,
+) -> Option<&'c CheckpointMetadata> {
+    match &fork.parent {
+        Some(parent) => Some(&parent.state.metadata),
+        None => global.restore.as_ref().map(|restore| &restore.metadata),
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn simulation_config(
     id: SimulationShortId,
     globals: Globals,
     engine: EngineConfig,
-    _global: &ExperimentConfig,
+    global: &ExperimentConfig,
     store: StoreConfig,
     persistence: PersistenceConfig,
     max_num_steps: usize,
+    fork: ForkConfig,
 ) -> Result<SimulationConfig> {
+    let start_step = resumed_from(global, &fork).map_or(0, |metadata| metadata.step);
     Ok(SimulationConfig {
         id,
         globals: Arc::new(globals),
@@ -83,5 +110,8 @@ fn simulation_config(
         store: Arc::new(store),
         persistence,
         max_num_steps,
+        seed: derive_seed(global.seed, id as u64),
+        start_step,
+        fork,
     })
 }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use super::{EngineConfig, PersistenceConfig, StoreConfig};
+use super::{CheckpointData, EngineConfig, PersistenceConfig, StoreConfig};
 use crate::{config::Globals, proto::SimulationShortId};
 
 pub struct Config {
@@ -10,4 +10,30 @@ pub struct Config {
     pub engine: Arc<EngineConfig>,
     pub max_num_steps: usize,
     pub persistence: PersistenceConfig,
+    /// Seed of the simulation run, derived from the seed of the experiment run.
+    pub seed: u64,
+    /// Number of steps taken before the simulation run starts, which is only non-zero for runs
+    /// restored from a checkpoint or forked from another run.
+    pub start_step: usize,
+    pub fork: ForkConfig,
+}
+
+/// How a simulation run takes part in forking, see [`ExperimentControl::ForkSim`].
+///
+/// [`ExperimentControl::ForkSim`]: crate::experiment::ExperimentControl::ForkSim
+#[derive(Clone, Default)]
+pub struct ForkConfig {
+    /// The simulation run this run was forked from. The run continues from its state instead of
+    /// running the init packages.
+    pub parent: Option<ForkParent>,
+    /// Whether to keep the state this run ends with, so other runs can be forked from it.
+    pub keep_final_state: bool,
+}
+
+/// The simulation run a forked run continues from.
+#[derive(Clone)]
+pub struct ForkParent {
+    pub sim_id: SimulationShortId,
+    /// The state the parent ended with
+    pub state: Arc<CheckpointData>,
 }
@@ -1,8 +1,15 @@
+use std::time::Duration;
+
+use crate::proto::ExternalRunnerConfig;
+
 #[derive(Debug, Clone)]
 pub struct SpawnConfig {
     pub python: bool,
     pub javascript: bool,
     pub rust: bool,
+    pub wasm: bool,
+    /// The external runner to spawn, if any.
+    pub external: Option<ExternalRunnerConfig>,
 }
 
 impl Default for SpawnConfig {
@@ -11,11 +18,30 @@ impl Default for SpawnConfig {
             python: true,
             javascript: true,
             rust: true,
+            wasm: true,
+            external: None,
         }
     }
 }
 
-#[derive(Debug, Default, Clone)]
+/// Number of times a runner of a worker is respawned after crashing, unless configured otherwise.
+pub const DEFAULT_MAX_RUNNER_RESTARTS: usize = 3;
+
+#[derive(Debug, Clone)]
 pub struct Config {
     pub spawn: SpawnConfig,
+    /// Time a runner may spend on a single task before it's stopped.
+    pub task_timeout: Option<Duration>,
+    /// Number of times a runner is respawned after crashing before the worker gives up.
+    pub max_runner_restarts: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            spawn: SpawnConfig::default(),
+            task_timeout: None,
+            max_runner_restarts: DEFAULT_MAX_RUNNER_RESTARTS,
+        }
+    }
 }
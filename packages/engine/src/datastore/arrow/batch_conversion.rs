@@ -8,13 +8,17 @@ use std::{collections::HashSet, sync::Arc};
 
 use arrow::{
     array::{self, Array, ArrayDataBuilder, ArrayRef, PrimitiveBuilder},
-    buffer::MutableBuffer,
+    buffer::{Buffer, MutableBuffer},
     datatypes::{self, ArrowNativeType, ArrowNumericType, ArrowPrimitiveType, DataType, Field},
 };
 use serde::de::DeserializeOwned;
 use serde_json::value::Value;
 
-use super::prelude::*;
+use super::{
+    field_conversion::{categorical_array, categorical_codes},
+    ipc::make_array,
+    prelude::*,
+};
 use crate::{
     datastore::{
         prelude::*,
@@ -81,12 +85,29 @@ pub fn new_buffer<T>(n_elem: usize) -> MutableBuffer {
     buffer
 }
 
+/// Get the offsets buffer of a `List`/`Utf8` column, or of a `LargeList`/`LargeUtf8` column if
+/// `large`, with the given offsets
+fn offsets_to_buffer(offsets: &[usize], large: bool) -> Result<Buffer> {
+    if large {
+        let mut buffer = new_buffer::<i64>(offsets.len());
+        for (dst, offset) in buffer.typed_data_mut::<i64>().iter_mut().zip(offsets) {
+            *dst = *offset as i64;
+        }
+        Ok(buffer.freeze())
+    } else {
+        let mut buffer = new_buffer::<i32>(offsets.len());
+        for (dst, offset) in buffer.typed_data_mut::<i32>().iter_mut().zip(offsets) {
+            *dst = i32::try_from(*offset).map_err(|_| Error::OffsetOverflow)?;
+        }
+        Ok(buffer.freeze())
+    }
+}
+
 fn builder_add_id(builder: &mut array::FixedSizeBinaryBuilder, id: &str) -> Result<()> {
     if id.is_empty() {
-        // Generates UUID if it does not exist
-        let uuid = uuid::Uuid::new_v4();
-        let bytes = uuid.as_bytes();
-        builder.append_value(bytes)?;
+        // Ids are generated from the seed of the simulation run (see `AgentIdGenerator`), so
+        // generating one here would make the run irreproducible
+        return Err(Error::MissingAgentId);
     } else if let Ok(uuid) = uuid::Uuid::parse_str(id) {
         builder.append_value(uuid.as_bytes())?;
     } else {
@@ -209,15 +230,59 @@ fn json_vals_to_utf8(vals: Vec<Value>, nullable: bool) -> Result<ArrayRef> {
     Ok(Arc::new(builder.finish()))
 }
 
-fn json_vals_to_list(vals: Vec<Value>, _nullable: bool, inner_dt: &DataType) -> Result<ArrayRef> {
+/// Builds a `LargeUtf8` column, which can't use a `StringBuilder` as its offsets are i64
+fn json_vals_to_large_utf8(vals: Vec<Value>, nullable: bool) -> Result<ArrayRef> {
+    let mut null_count = 0;
+    let n_elem = vals.len();
+    let mut null_bits = new_zero_bits(n_elem);
+    let mut_null_bits = null_bits.data_mut();
+
+    let mut offsets = Vec::with_capacity(n_elem + 1);
+    offsets.push(0);
+    let mut bytes: Vec<u8> = vec![];
+
+    for (i_val, val) in vals.into_iter().enumerate() {
+        let string: Option<String> = if nullable {
+            serde_json::from_value(val)?
+        } else {
+            Some(serde_json::from_value(val)?)
+        };
+        match string {
+            Some(string) => {
+                arrow_bit_util::set_bit(mut_null_bits, i_val);
+                bytes.extend_from_slice(string.as_bytes());
+            }
+            None => null_count += 1,
+        }
+        offsets.push(bytes.len());
+    }
+
+    let string_data = ArrayDataBuilder::new(ArrowDataType::LargeUtf8)
+        .len(n_elem)
+        .null_count(null_count)
+        .null_bit_buffer(null_bits.freeze())
+        .buffers(vec![
+            offsets_to_buffer(&offsets, true)?,
+            Buffer::from(bytes),
+        ])
+        .build();
+    Ok(make_array(string_data))
+}
+
+/// Builds a `List` column, or a `LargeList` column if `large`
+fn json_vals_to_list(
+    vals: Vec<Value>,
+    _nullable: bool,
+    inner_dt: &DataType,
+    large: bool,
+) -> Result<ArrayRef> {
     let mut null_count = 0;
     let n_elem = vals.len();
     let mut null_bits = new_zero_bits(n_elem);
     let mut_null_bits = null_bits.data_mut();
 
-    let mut offsets = new_offsets_buffer(n_elem);
-    let mut_offsets = offsets.typed_data_mut::<i32>();
-    debug_assert!(mut_offsets.iter().all(|v| *v == 0));
+    let mut offsets = Vec::with_capacity(n_elem + 1);
+    offsets.push(0);
 
     let mut combined_vals = vec![];
 
@@ -225,30 +290,31 @@ fn json_vals_to_list(vals: Vec<Value>, _nullable: bool, inner_dt: &DataType) ->
         match val {
             Value::Array(mut inner_vals) => {
                 arrow_bit_util::set_bit(mut_null_bits, i_val);
-                let prev_offset = mut_offsets[i_val];
-                mut_offsets[i_val + 1] = prev_offset + inner_vals.len() as i32;
                 combined_vals.append(&mut inner_vals);
             }
             Value::Null => {
-                mut_offsets[i_val + 1] = mut_offsets[i_val];
                 null_count += 1;
             }
             _ => return Err(Error::ChildDataExpected),
         }
+        offsets.push(combined_vals.len());
     }
     let inner_nullable = true; // Nested values are always nullable.
     let child_data = json_vals_to_col(combined_vals, inner_dt, inner_nullable)?.data();
 
-    let dt = ArrowDataType::List(Box::new(inner_dt.clone()));
+    let dt = if large {
+        ArrowDataType::LargeList(Box::new(inner_dt.clone()))
+    } else {
+        ArrowDataType::List(Box::new(inner_dt.clone()))
+    };
     let list_data = ArrayDataBuilder::new(dt)
         .len(n_elem)
         .null_count(null_count)
         .null_bit_buffer(null_bits.freeze())
-        .buffers(vec![offsets.freeze()])
+        .buffers(vec![offsets_to_buffer(&offsets, large)?])
         .child_data(vec![child_data])
         .build();
-    let list: array::ListArray = list_data.into();
-    Ok(Arc::new(list))
+    Ok(make_array(list_data))
 }
 
 fn json_vals_to_fixed_size_list(
@@ -385,8 +451,13 @@ fn json_vals_to_col(vals: Vec<Value>, dt: &DataType, nullable: bool) -> Result<A
         ArrowDataType::UInt16 => json_vals_to_primitive::<datatypes::UInt16Type>(vals, nullable),
         ArrowDataType::UInt8 => json_vals_to_primitive::<datatypes::UInt8Type>(vals, nullable),
         ArrowDataType::Boolean => json_vals_to_primitive::<datatypes::BooleanType>(vals, nullable),
+        ArrowDataType::Timestamp(ArrowTimeUnit::Millisecond, _) => {
+            json_vals_to_primitive::<datatypes::TimestampMillisecondType>(vals, nullable)
+        }
         ArrowDataType::Utf8 => json_vals_to_utf8(vals, nullable),
-        ArrowDataType::List(inner_dt) => json_vals_to_list(vals, nullable, &*inner_dt),
+        ArrowDataType::LargeUtf8 => json_vals_to_large_utf8(vals, nullable),
+        ArrowDataType::List(inner_dt) => json_vals_to_list(vals, nullable, &*inner_dt, false),
+        ArrowDataType::LargeList(inner_dt) => json_vals_to_list(vals, nullable, &*inner_dt, true),
         ArrowDataType::FixedSizeList(inner_dt, size) => {
             json_vals_to_fixed_size_list(vals, nullable, &*inner_dt, size)
         }
@@ -408,6 +479,32 @@ fn json_vals_to_any_type_col(vals: Vec<Value>, dt: &DataType) -> Result<ArrayRef
     Ok(Arc::new(builder.finish()))
 }
 
+fn json_vals_to_categorical_col(
+    vals: Vec<Value>,
+    categories: &[String],
+    nullable: bool,
+) -> Result<ArrayRef> {
+    let mut builder = PrimitiveBuilder::<datatypes::UInt32Type>::new(vals.len());
+    for val in vals {
+        let category: Option<String> = if nullable {
+            serde_json::from_value(val)?
+        } else {
+            Some(serde_json::from_value(val)?)
+        };
+        match category {
+            Some(category) => {
+                let code = categories
+                    .iter()
+                    .position(|c| *c == category)
+                    .ok_or(Error::UnknownCategory(category))?;
+                builder.append_value(code as u32)?;
+            }
+            None => builder.append_null()?,
+        }
+    }
+    Ok(categorical_array(&builder.finish(), categories))
+}
+
 fn previous_index_to_empty_col(num_agents: usize, dt: &ArrowDataType) -> Result<ArrayRef> {
     if let ArrowDataType::FixedSizeList(inner_type, inner_len) = dt.clone() {
         debug_assert!(matches!(*inner_type, DataType::UInt32));
@@ -523,19 +620,21 @@ impl IntoRecordBatch for &[&AgentState] {
                 json_vals_to_bool(vals)
             } else if name == PREVIOUS_INDEX_FIELD_KEY {
                 previous_index_to_empty_col(self.len(), field.data_type())
-            } else if matches!(
-                schema
+            } else {
+                match &schema
                     .field_spec_map
                     .get_field_spec(&FieldKey::new(&name))?
                     .inner
                     .field_type
-                    .variant,
-                FieldTypeVariant::AnyType
-            ) {
-                // Any-type (JSON string) column
-                json_vals_to_any_type_col(vals, field.data_type())
-            } else {
-                json_vals_to_col(vals, field.data_type(), field.is_nullable())
+                    .variant
+                {
+                    // Any-type (JSON string) column
+                    FieldTypeVariant::AnyType => json_vals_to_any_type_col(vals, field.data_type()),
+                    FieldTypeVariant::Categorical(categories) => {
+                        json_vals_to_categorical_col(vals, categories, field.is_nullable())
+                    }
+                    _ => json_vals_to_col(vals, field.data_type(), field.is_nullable()),
+                }
             })?;
             cols.push(col);
         }
@@ -543,6 +642,81 @@ impl IntoRecordBatch for &[&AgentState] {
     }
 }
 
+/// Converts the column `field_name` of an agent batch to one JSON value per agent, the same way
+/// [`IntoAgentStates`] converts the fields of agents. Null values are kept as `null`.
+///
+/// This lets runners pass single columns to behaviors without converting whole agents.
+pub fn agent_column_to_json_vals(
+    agents: &RecordBatch,
+    agent_schema: &AgentSchema,
+    field_name: &str,
+) -> Result<Vec<Value>> {
+    let schema = agents.schema();
+    let (i_col, field) = schema
+        .column_with_name(field_name)
+        .ok_or_else(|| Error::ColumnNotFound(field_name.to_string()))?;
+    let col = agents.column(i_col);
+
+    if field_name == AgentStateField::AgentId.name() {
+        let array = col
+            .as_any()
+            .downcast_ref::<array::FixedSizeBinaryArray>()
+            .ok_or(Error::InvalidArrowDowncast {
+                name: "agent_id".into(),
+            })?;
+        return (0..array.len())
+            .map(|i_val| {
+                let uuid = uuid::Uuid::from_slice(array.value(i_val))?;
+                Ok(Value::String(uuid.to_hyphenated().to_string()))
+            })
+            .collect();
+    }
+    if BUILTIN_FIELDS.contains(&field_name) {
+        return col_to_json_vals(col, field.data_type());
+    }
+    match &agent_schema
+        .field_spec_map
+        .get_field_spec(&FieldKey::new(field_name))?
+        .inner
+        .field_type
+        .variant
+    {
+        FieldTypeVariant::AnyType => json_utf8_json_vals(col),
+        _ => col_to_json_vals(col, field.data_type()),
+    }
+}
+
+/// Converts one JSON value per agent to the column `field` of an agent batch, the inverse of
+/// [`agent_column_to_json_vals`].
+///
+/// The agent ids can't be converted, as agents can't change their id.
+pub fn json_vals_to_agent_column(
+    vals: Vec<Value>,
+    agent_schema: &AgentSchema,
+    field: &Field,
+) -> Result<ArrayRef> {
+    let name = field.name().as_str();
+    if name == AgentStateField::AgentId.name() {
+        return Err(Error::from("The agent id column can't be changed"));
+    }
+    if BUILTIN_FIELDS.contains(&name) {
+        return json_vals_to_col(vals, field.data_type(), field.is_nullable());
+    }
+    match &agent_schema
+        .field_spec_map
+        .get_field_spec(&FieldKey::new(name))?
+        .inner
+        .field_type
+        .variant
+    {
+        FieldTypeVariant::AnyType => json_vals_to_any_type_col(vals, field.data_type()),
+        FieldTypeVariant::Categorical(categories) => {
+            json_vals_to_categorical_col(vals, categories, field.is_nullable())
+        }
+        _ => json_vals_to_col(vals, field.data_type(), field.is_nullable()),
+    }
+}
+
 /// Conversion into `AgentState`, which can be converted to JSON
 pub trait IntoAgentStates {
     fn into_agent_states(&self, agent_schema: Option<&Arc<AgentSchema>>)
@@ -868,17 +1042,27 @@ fn bool_to_json_vals(col: &ArrayRef) -> Result<Vec<Value>> {
 }
 
 fn utf8_to_json_vals(col: &ArrayRef) -> Result<Vec<Value>> {
-    let array = array::as_string_array(col);
-    let mut json_vals: Vec<Value> = Vec::with_capacity(array.len());
-    for i_val in 0..array.len() {
-        if array.null_count() > 0 && !array.is_valid(i_val) {
+    // `LargeUtf8` columns only differ in the width of their offsets
+    let any = col.as_any();
+    let value: Box<dyn Fn(usize) -> Value + '_> =
+        if let Some(array) = any.downcast_ref::<array::StringArray>() {
+            Box::new(move |i| Value::from(array.value(i)))
+        } else if let Some(array) = any.downcast_ref::<array::LargeStringArray>() {
+            Box::new(move |i| Value::from(array.value(i)))
+        } else {
+            return Err(Error::InvalidArrowDowncast {
+                name: "[custom string]".into(),
+            });
+        };
+
+    let mut json_vals: Vec<Value> = Vec::with_capacity(col.len());
+    for i_val in 0..col.len() {
+        if col.null_count() > 0 && !col.is_valid(i_val) {
             json_vals.push(Value::Null);
             continue;
         }
 
-        let native_val = array.value(i_val);
-        let json_val = serde_json::to_value(native_val).unwrap_or(Value::Null);
-        json_vals.push(json_val);
+        json_vals.push(value(i_val));
     }
     Ok(json_vals)
 }
@@ -900,21 +1084,27 @@ fn json_utf8_json_vals(col: &ArrayRef) -> Result<Vec<Value>> {
 }
 
 fn list_to_json_vals(col: &ArrayRef, inner_dt: &DataType) -> Result<Vec<Value>> {
-    let array =
-        col.as_any()
-            .downcast_ref::<array::ListArray>()
-            .ok_or(Error::InvalidArrowDowncast {
+    // `LargeList` columns only differ in the width of their offsets
+    let any = col.as_any();
+    let value: Box<dyn Fn(usize) -> ArrayRef + '_> =
+        if let Some(array) = any.downcast_ref::<array::ListArray>() {
+            Box::new(move |i| array.value(i))
+        } else if let Some(array) = any.downcast_ref::<array::LargeListArray>() {
+            Box::new(move |i| array.value(i))
+        } else {
+            return Err(Error::InvalidArrowDowncast {
                 name: "[custom list]".into(),
-            })?;
+            });
+        };
 
-    let mut json_vals: Vec<Value> = Vec::with_capacity(array.len());
-    for i_val in 0..array.len() {
-        if array.null_count() > 0 && !array.is_valid(i_val) {
+    let mut json_vals: Vec<Value> = Vec::with_capacity(col.len());
+    for i_val in 0..col.len() {
+        if col.null_count() > 0 && !col.is_valid(i_val) {
             json_vals.push(Value::Null);
             continue;
         }
 
-        let inner_col = array.value(i_val);
+        let inner_col = value(i_val);
         let inner_vals = col_to_json_vals(&inner_col, inner_dt)?;
         json_vals.push(Value::Array(inner_vals));
     }
@@ -992,19 +1182,43 @@ pub(in crate::datastore) fn col_to_json_vals(col: &ArrayRef, dt: &DataType) -> R
         ArrowDataType::UInt16 => numeric_to_json_vals::<datatypes::UInt16Type>(col),
         ArrowDataType::UInt32 => numeric_to_json_vals::<datatypes::UInt32Type>(col),
         ArrowDataType::UInt64 => numeric_to_json_vals::<datatypes::UInt64Type>(col),
+        ArrowDataType::Timestamp(ArrowTimeUnit::Millisecond, _) => {
+            numeric_to_json_vals::<datatypes::TimestampMillisecondType>(col)
+        }
         ArrowDataType::Boolean => bool_to_json_vals(col),
-        ArrowDataType::Utf8 => utf8_to_json_vals(col),
+        ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => utf8_to_json_vals(col),
 
         // `Box<T>` isn't coerced to `&T`, so need explicit `&*`.
-        ArrowDataType::List(inner_dt) => list_to_json_vals(col, &*inner_dt),
+        ArrowDataType::List(inner_dt) | ArrowDataType::LargeList(inner_dt) => {
+            list_to_json_vals(col, &*inner_dt)
+        }
         ArrowDataType::FixedSizeList(inner_dt, _) => fixed_size_list_to_json_vals(col, &*inner_dt),
         ArrowDataType::Struct(fields) => struct_to_json_vals(col, fields),
+        ArrowDataType::Dictionary(..) => categorical_to_json_vals(col),
         _ => Err(Error::NotImplemented(SupportedType::ArrowDataType(
             dt.clone(),
         ))),
     }
 }
 
+/// Converts a categorical column to its categories, see `field_conversion::categorical_array`.
+fn categorical_to_json_vals(col: &ArrayRef) -> Result<Vec<Value>> {
+    let codes = categorical_codes(col);
+    let categories = utf8_to_json_vals(&make_array(col.data().child_data()[0].clone()))?;
+    (0..codes.len())
+        .map(|i_val| {
+            if codes.null_count() > 0 && !codes.is_valid(i_val) {
+                return Ok(Value::Null);
+            }
+            let code = codes.value(i_val) as usize;
+            categories
+                .get(code)
+                .cloned()
+                .ok_or_else(|| Error::from(format!("Invalid category code {code}")))
+        })
+        .collect()
+}
+
 fn set_states_custom(
     states: &mut Vec<AgentState>,
     rb: &RecordBatch,
@@ -1181,8 +1395,13 @@ impl IntoAgentStates for RecordBatch {
 
 #[cfg(test)]
 pub mod tests {
+    use serde_json::json;
+
     use super::*;
-    use crate::datastore::test_utils::gen_schema_and_test_agents;
+    use crate::{
+        datastore::test_utils::{self, gen_schema_and_test_agents},
+        simulation::seed::AgentIdGenerator,
+    };
 
     #[test]
     fn agent_state_into_record_batch() -> Result<()> {
@@ -1227,4 +1446,122 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn agent_columns_round_trip_through_json() -> Result<()> {
+        let (schema, agents) = gen_schema_and_test_agents(50, 0)?;
+        let agent_batch = agents.as_slice().into_agent_batch(&schema)?;
+
+        let ids = agent_column_to_json_vals(&agent_batch, &schema, "agent_id")?;
+        let expected_ids: Vec<_> = agents
+            .iter()
+            .map(|agent| Value::String(agent.agent_id.clone()))
+            .collect();
+        assert_eq!(ids, expected_ids);
+
+        for field in schema.arrow.fields() {
+            if field.name() == AgentStateField::AgentId.name() {
+                continue;
+            }
+            let vals = agent_column_to_json_vals(&agent_batch, &schema, field.name())?;
+            assert_eq!(vals.len(), agents.len());
+            let col = json_vals_to_agent_column(vals.clone(), &schema, field)?;
+            assert_eq!(col.data_type(), field.data_type(), "{}", field.name());
+
+            let columns: Vec<_> = schema
+                .arrow
+                .fields()
+                .iter()
+                .enumerate()
+                .map(|(i_col, other)| {
+                    if other.name() == field.name() {
+                        Arc::clone(&col)
+                    } else {
+                        Arc::clone(agent_batch.column(i_col))
+                    }
+                })
+                .collect();
+            let round_tripped = RecordBatch::try_new(Arc::clone(&schema.arrow), columns)?;
+            assert_eq!(
+                agent_column_to_json_vals(&round_tripped, &schema, field.name())?,
+                vals,
+                "{} changed in the round trip",
+                field.name()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn categorical_columns_are_dictionary_encoded() -> Result<()> {
+        let (schema, mut agents) = gen_schema_and_test_agents(3, 0)?;
+        agents[0].set("infection", "recovered")?;
+        agents[1].delete_custom("infection");
+        agents[2].set("infection", "susceptible")?;
+        let agent_batch = agents.as_slice().into_agent_batch(&schema)?;
+
+        let (i_col, field) = schema
+            .arrow
+            .column_with_name("infection")
+            .expect("Missing categorical column");
+        assert_eq!(
+            field.data_type(),
+            &DataType::Dictionary(Box::new(DataType::UInt32), Box::new(DataType::Utf8))
+        );
+        let col = agent_batch.column(i_col);
+        let codes = categorical_codes(col);
+        assert_eq!(codes.value(0), 2);
+        assert!(codes.is_null(1));
+        assert_eq!(codes.value(2), 0);
+        assert_eq!(
+            utf8_to_json_vals(&make_array(col.data().child_data()[0].clone()))?,
+            test_utils::INFECTION_STATES
+        );
+        assert_eq!(
+            agent_column_to_json_vals(&agent_batch, &schema, "infection")?,
+            [json!("recovered"), Value::Null, json!("susceptible")]
+        );
+
+        agents[0].set("infection", "dead")?;
+        assert!(matches!(
+            agents.as_slice().into_agent_batch(&schema),
+            Err(Error::UnknownCategory(category)) if category == "dead"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn integers_keep_their_precision() -> Result<()> {
+        let (schema, mut agents) = gen_schema_and_test_agents(2, 0)?;
+        agents[0].set("count", i64::MIN)?;
+        agents[0].set("size", u32::MAX)?;
+        agents[1].set("count", 2_i64.pow(53) + 1)?;
+        agents[1].set("size", 0_u32)?;
+        let agent_batch = agents.as_slice().into_agent_batch(&schema)?;
+
+        assert_eq!(
+            agent_column_to_json_vals(&agent_batch, &schema, "count")?,
+            [json!(i64::MIN), json!(2_i64.pow(53) + 1)]
+        );
+        assert_eq!(agent_column_to_json_vals(&agent_batch, &schema, "size")?, [
+            json!(u32::MAX),
+            json!(0)
+        ]);
+
+        agents[0].set("size", -1)?;
+        assert!(agents.as_slice().into_agent_batch(&schema).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn agent_ids_are_not_generated() {
+        assert!(matches!(
+            get_agent_id_array(vec![""]),
+            Err(Error::MissingAgentId)
+        ));
+
+        let id = AgentIdGenerator::new(0).next_id();
+        let ids = get_agent_id_array(vec![&id]).unwrap();
+        assert_eq!(ids.value(0), uuid::Uuid::parse_str(&id).unwrap().as_bytes());
+    }
 }
@@ -10,7 +10,10 @@ use arrow::{
 };
 use serde_json::value::Value;
 
-use super::{batch_conversion::col_to_json_vals, prelude::*};
+use super::{
+    batch_conversion::col_to_json_vals, field_conversion::categorical_codes, ipc::make_array,
+    prelude::*,
+};
 use crate::datastore::prelude::*;
 
 fn numeric_element_to_json_val<T: ArrowPrimitiveType + ArrowNumericType>(
@@ -40,29 +43,52 @@ fn bool_element_to_json_val(col: &ArrayRef, index: usize) -> Result<Value> {
 }
 
 fn utf8_element_to_json_val(col: &ArrayRef, index: usize) -> Result<Value> {
-    let array = array::as_string_array(col);
-    if !array.is_valid(index) {
+    if !col.is_valid(index) {
+        return Ok(Value::Null);
+    }
+    // `LargeUtf8` columns only differ in the width of their offsets
+    let any = col.as_any();
+    let native_val = if let Some(array) = any.downcast_ref::<array::StringArray>() {
+        array.value(index)
+    } else if let Some(array) = any.downcast_ref::<array::LargeStringArray>() {
+        array.value(index)
+    } else {
+        return Err(Error::InvalidArrowDowncast {
+            name: "[custom string]".into(),
+        });
+    };
+    Ok(Value::String(native_val.to_string()))
+}
+
+/// Converts an element of a categorical column to its category, see
+/// `field_conversion::categorical_array`.
+fn categorical_element_to_json_val(col: &ArrayRef, index: usize) -> Result<Value> {
+    let codes = categorical_codes(col);
+    if !codes.is_valid(index) {
         Ok(Value::Null)
     } else {
-        let native_val = array.value(index);
-        Ok(Value::String(native_val.to_string()))
+        let categories = make_array(col.data().child_data()[0].clone());
+        utf8_element_to_json_val(&categories, codes.value(index) as usize)
     }
 }
 
 fn list_element_to_json_val(col: &ArrayRef, index: usize, inner_dt: &DataType) -> Result<Value> {
-    let array =
-        col.as_any()
-            .downcast_ref::<array::ListArray>()
-            .ok_or(Error::InvalidArrowDowncast {
-                name: "[custom list]".into(),
-            })?;
-    if !array.is_valid(index) {
-        Ok(Value::Null)
-    } else {
-        let inner_col = array.value(index);
-        let inner_vals = col_to_json_vals(&inner_col, inner_dt)?;
-        Ok(Value::Array(inner_vals))
+    if !col.is_valid(index) {
+        return Ok(Value::Null);
     }
+    // `LargeList` columns only differ in the width of their offsets
+    let any = col.as_any();
+    let inner_col = if let Some(array) = any.downcast_ref::<array::ListArray>() {
+        array.value(index)
+    } else if let Some(array) = any.downcast_ref::<array::LargeListArray>() {
+        array.value(index)
+    } else {
+        return Err(Error::InvalidArrowDowncast {
+            name: "[custom list]".into(),
+        });
+    };
+    let inner_vals = col_to_json_vals(&inner_col, inner_dt)?;
+    Ok(Value::Array(inner_vals))
 }
 
 fn fixed_size_list_element_to_json_val(
@@ -128,15 +154,21 @@ pub fn col_element_to_json_val(col: &ArrayRef, index: usize, dt: &DataType) -> R
         ArrowDataType::UInt16 => numeric_element_to_json_val::<datatypes::UInt16Type>(col, index),
         ArrowDataType::UInt32 => numeric_element_to_json_val::<datatypes::UInt32Type>(col, index),
         ArrowDataType::UInt64 => numeric_element_to_json_val::<datatypes::UInt64Type>(col, index),
+        ArrowDataType::Timestamp(ArrowTimeUnit::Millisecond, _) => {
+            numeric_element_to_json_val::<datatypes::TimestampMillisecondType>(col, index)
+        }
         ArrowDataType::Boolean => bool_element_to_json_val(col, index),
-        ArrowDataType::Utf8 => utf8_element_to_json_val(col, index),
+        ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => utf8_element_to_json_val(col, index),
 
         // `Box<T>` isn't coerced to `&T`, so need explicit `&*`.
-        ArrowDataType::List(inner_dt) => list_element_to_json_val(col, index, &*inner_dt),
+        ArrowDataType::List(inner_dt) | ArrowDataType::LargeList(inner_dt) => {
+            list_element_to_json_val(col, index, &*inner_dt)
+        }
         ArrowDataType::FixedSizeList(inner_dt, _) => {
             fixed_size_list_element_to_json_val(col, index, &*inner_dt)
         }
         ArrowDataType::Struct(fields) => struct_element_to_json_val(col, index, fields),
+        ArrowDataType::Dictionary(..) => categorical_element_to_json_val(col, index),
         _ => Err(Error::NotImplemented(SupportedType::ArrowDataType(
             dt.clone(),
         ))),
@@ -149,11 +181,13 @@ pub mod tests {
 
     use arrow::array::{
         ArrayData, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
-        Int8Array, StringArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        Int8Array, LargeStringArray, StringArray, UInt16Array, UInt32Array, UInt64Array,
+        UInt8Array,
     };
     use serde_json::json;
 
     use super::*;
+    use crate::datastore::arrow::field_conversion::categorical_array;
 
     #[test]
     fn numeric_element_conversion() {
@@ -249,6 +283,34 @@ pub mod tests {
                 json!(expected_val)
             );
         }
+
+        let large_string_array = LargeStringArray::from(vals.clone());
+        let array_ref = Arc::new(large_string_array) as ArrayRef;
+
+        for (idx, &expected_val) in vals.iter().enumerate() {
+            assert_eq!(
+                col_element_to_json_val(&array_ref, idx, &DataType::LargeUtf8).unwrap(),
+                json!(expected_val)
+            );
+        }
+    }
+
+    #[test]
+    fn categorical_element_conversion() {
+        let categories = vec!["low".to_string(), "high".to_string()];
+        let codes = UInt32Array::from(vec![Some(1), None, Some(0)]);
+        let array_ref = categorical_array(&codes, &categories);
+        let d_type = array_ref.data_type().clone();
+
+        for (idx, expected_val) in [json!("high"), json!(null), json!("low")]
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(
+                col_element_to_json_val(&array_ref, idx, &d_type).unwrap(),
+                expected_val
+            );
+        }
     }
 
     #[test]
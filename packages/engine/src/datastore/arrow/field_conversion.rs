@@ -3,9 +3,11 @@
     clippy::cast_possible_wrap,
     clippy::for_kv_map
 )]
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
-use super::prelude::*;
+use arrow::array::{ArrayData, ArrayRef, StringArray, UInt32Array};
+
+use super::{ipc::make_array, prelude::*};
 use crate::datastore::{
     error::Result,
     prelude::*,
@@ -39,13 +41,21 @@ impl PresetFieldType {
 impl FieldType {
     fn is_fixed_size(&self) -> bool {
         match &self.variant {
-            FieldTypeVariant::Number | FieldTypeVariant::Boolean => true,
-            FieldTypeVariant::String | FieldTypeVariant::AnyType => false,
+            FieldTypeVariant::Number
+            | FieldTypeVariant::Integer
+            | FieldTypeVariant::UnsignedInteger
+            | FieldTypeVariant::Boolean
+            | FieldTypeVariant::Categorical(_)
+            | FieldTypeVariant::Timestamp => true,
+            FieldTypeVariant::String
+            | FieldTypeVariant::LargeString
+            | FieldTypeVariant::AnyType => false,
             FieldTypeVariant::FixedLengthArray {
                 kind: inner,
                 len: _,
             } => inner.is_fixed_size(),
-            FieldTypeVariant::VariableLengthArray(_) => false,
+            FieldTypeVariant::VariableLengthArray(_)
+            | FieldTypeVariant::LargeVariableLengthArray(_) => false,
             FieldTypeVariant::Struct(inner) => inner.iter().all(FieldSpec::is_fixed_size),
             FieldTypeVariant::Preset(inner) => inner.is_fixed_size(),
         }
@@ -54,8 +64,16 @@ impl FieldType {
     pub fn get_arrow_data_type(&self) -> Result<ArrowDataType> {
         match &self.variant {
             FieldTypeVariant::Number => Ok(ArrowDataType::Float64),
+            FieldTypeVariant::Integer => Ok(ArrowDataType::Int64),
+            FieldTypeVariant::UnsignedInteger => Ok(ArrowDataType::UInt32),
             FieldTypeVariant::Boolean => Ok(ArrowDataType::Boolean),
             FieldTypeVariant::String => Ok(ArrowDataType::Utf8),
+            FieldTypeVariant::LargeString => Ok(ArrowDataType::LargeUtf8),
+            // Only the indices are stored in batches, see `categorical_dictionaries`
+            FieldTypeVariant::Categorical(_) => Ok(categorical_data_type()),
+            FieldTypeVariant::Timestamp => {
+                Ok(ArrowDataType::Timestamp(ArrowTimeUnit::Millisecond, None))
+            }
             FieldTypeVariant::AnyType => Ok(ArrowDataType::Utf8),
             FieldTypeVariant::FixedLengthArray { kind: inner, len } => Ok(
                 ArrowDataType::FixedSizeList(Box::new(inner.get_arrow_data_type()?), *len as i32),
@@ -63,6 +81,9 @@ impl FieldType {
             FieldTypeVariant::VariableLengthArray(inner) => {
                 Ok(ArrowDataType::List(Box::new(inner.get_arrow_data_type()?)))
             }
+            FieldTypeVariant::LargeVariableLengthArray(inner) => Ok(ArrowDataType::LargeList(
+                Box::new(inner.get_arrow_data_type()?),
+            )),
             FieldTypeVariant::Struct(inner) => Ok(ArrowDataType::Struct(
                 inner
                     .iter()
@@ -127,6 +148,7 @@ impl FieldSpecMap {
         let mut fixed_size_no = 0;
 
         let mut any_types = vec![];
+        let mut categorical_fields = serde_json::Map::new();
 
         for (key, field_spec) in self.iter() {
             let key = key.value().to_string();
@@ -137,11 +159,12 @@ impl FieldSpecMap {
                 partitioned_fields.push((field_spec, key.clone()));
             }
 
-            if matches!(
-                field_spec.inner.field_type.variant,
-                FieldTypeVariant::AnyType
-            ) {
-                any_types.push(key)
+            match &field_spec.inner.field_type.variant {
+                FieldTypeVariant::AnyType => any_types.push(key),
+                FieldTypeVariant::Categorical(categories) => {
+                    categorical_fields.insert(key, categories.clone().into());
+                }
+                _ => {}
             }
         }
 
@@ -154,21 +177,133 @@ impl FieldSpecMap {
             .map(|spec| (spec.0.inner.field_type.nullable as usize).to_string())
             .collect::<Vec<_>>();
 
-        let mut metadata = HashMap::with_capacity(1);
+        let mut metadata = HashMap::with_capacity(3);
         // TODO: this can be simplified when we update arrow-rs (beyond 1.0.1), we can set this on
         // Field's custom metadata instead of the schema
         metadata.insert("any_type_fields".into(), any_types.join(","));
         metadata.insert("nullable".into(), nullabilities.join(","));
+        metadata.insert(
+            CATEGORICAL_FIELDS_METADATA_KEY.into(),
+            serde_json::Value::Object(categorical_fields).to_string(),
+        );
         Ok(ArrowSchema::new_with_metadata(
             partitioned_fields
                 .iter()
-                .map(|k| k.0.get_arrow_field())
+                .enumerate()
+                .map(|(i_field, k)| {
+                    let field = k.0.get_arrow_field()?;
+                    if let ArrowDataType::Dictionary(..) = field.data_type() {
+                        // Every dictionary of a schema needs its own id
+                        Ok(ArrowField::new_dict(
+                            field.name(),
+                            field.data_type().clone(),
+                            field.is_nullable(),
+                            i_field as i64,
+                            false,
+                        ))
+                    } else {
+                        Ok(field)
+                    }
+                })
                 .collect::<Result<_>>()?,
             metadata,
         ))
     }
 }
 
+/// Schema metadata entry with the categories of all categorical fields, as a JSON object from the
+/// field keys to the lists of categories.
+///
+/// Categorical fields are dictionary-encoded with `u32` indices, but batches in shared memory only
+/// contain the indices, so the dictionaries are rebuilt from this entry whenever a batch is loaded
+/// (see `categorical_dictionaries`).
+pub const CATEGORICAL_FIELDS_METADATA_KEY: &str = "categorical_fields";
+
+/// Returns the categories of the categorical fields in `schema` by field key.
+pub fn categorical_fields(schema: &ArrowSchema) -> Result<HashMap<String, Vec<String>>> {
+    match schema.metadata().get(CATEGORICAL_FIELDS_METADATA_KEY) {
+        Some(categorical_fields) => Ok(serde_json::from_str(categorical_fields)?),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Returns the dictionaries of the categorical fields in `schema`, indexed by the node index of
+/// their field in a batch of `schema` like the dictionaries of `ipc::read_record_batch`.
+pub fn categorical_dictionaries(schema: &ArrowSchema) -> Result<Vec<Option<ArrayRef>>> {
+    let categorical_fields = categorical_fields(schema)?;
+    let mut dictionaries = Vec::new();
+    for field in schema.fields() {
+        dictionaries.push(
+            categorical_fields
+                .get(field.name())
+                .map(|categories| dictionary_values(categories)),
+        );
+        // Categorical fields can't be nested, so the nodes of the children only need to be skipped
+        dictionaries.extend(std::iter::repeat(None).take(node_count(field.data_type()) - 1));
+    }
+    Ok(dictionaries)
+}
+
+/// The number of nodes of a column of type `data_type` in a batch, see `ipc::create_array`
+fn node_count(data_type: &ArrowDataType) -> usize {
+    match data_type {
+        ArrowDataType::List(inner)
+        | ArrowDataType::LargeList(inner)
+        | ArrowDataType::FixedSizeList(inner, _) => 1 + node_count(inner),
+        ArrowDataType::Struct(fields) => {
+            1 + fields
+                .iter()
+                .map(|field| node_count(field.data_type()))
+                .sum::<usize>()
+        }
+        _ => 1,
+    }
+}
+
+/// Categorical fields are dictionary-encoded strings with `u32` indices.
+fn categorical_data_type() -> ArrowDataType {
+    ArrowDataType::Dictionary(
+        Box::new(ArrowDataType::UInt32),
+        Box::new(ArrowDataType::Utf8),
+    )
+}
+
+fn dictionary_values(categories: &[String]) -> ArrayRef {
+    Arc::new(StringArray::from(
+        categories.iter().map(String::as_str).collect::<Vec<_>>(),
+    ))
+}
+
+/// Builds the column of a categorical field from the indices of its categories.
+pub fn categorical_array(codes: &UInt32Array, categories: &[String]) -> ArrayRef {
+    let codes = codes.data();
+    make_array(Arc::new(ArrayData::new(
+        categorical_data_type(),
+        codes.len(),
+        Some(codes.null_count()),
+        codes.null_buffer().cloned(),
+        codes.offset(),
+        codes.buffers().to_vec(),
+        vec![dictionary_values(categories).data()],
+    )))
+}
+
+/// Returns the indices of the categories of the dictionary-encoded column `col`, i.e. the data
+/// which is stored in batches.
+pub fn categorical_codes(col: &ArrayRef) -> UInt32Array {
+    let data = col.data();
+    debug_assert_eq!(data.data_type(), &categorical_data_type());
+    UInt32Array::from(Arc::new(ArrayData::new(
+        ArrowDataType::UInt32,
+        data.len(),
+        Some(data.null_count()),
+        data.null_buffer().cloned(),
+        data.offset(),
+        data.buffers().to_vec(),
+        vec![],
+    )))
+}
+
 pub trait IsFixedSize {
     fn is_fixed_size(&self) -> Result<bool>;
 }
@@ -177,10 +312,13 @@ impl IsFixedSize for ArrowDataType {
     fn is_fixed_size(&self) -> Result<bool> {
         match self {
             ArrowDataType::Float64 => Ok(true),
+            ArrowDataType::Int64 | ArrowDataType::UInt32 | ArrowDataType::Timestamp(..) => Ok(true),
             ArrowDataType::FixedSizeBinary(_) => Ok(true),
             ArrowDataType::Utf8 => Ok(false),
             ArrowDataType::FixedSizeList(val, _) => val.is_fixed_size(),
             ArrowDataType::List(_) => Ok(false),
+            // Only the keys are stored
+            ArrowDataType::Dictionary(key, _) => key.is_fixed_size(),
             _ => Err(Error::NotImplemented(SupportedType::ArrowDataType(
                 self.clone(),
             ))),
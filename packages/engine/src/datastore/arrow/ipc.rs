@@ -34,11 +34,11 @@ use arrow::{
         DictionaryArray, DurationMicrosecondArray, DurationMillisecondArray,
         DurationNanosecondArray, DurationSecondArray, FixedSizeBinaryArray, FixedSizeListArray,
         Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
-        IntervalDayTimeArray, IntervalYearMonthArray, ListArray, NullArray, StringArray,
-        StructArray, Time32MillisecondArray, Time32SecondArray, Time64MicrosecondArray,
-        Time64NanosecondArray, TimestampMicrosecondArray, TimestampMillisecondArray,
-        TimestampNanosecondArray, TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array,
-        UInt8Array, UnionArray,
+        IntervalDayTimeArray, IntervalYearMonthArray, LargeBinaryArray, LargeListArray,
+        LargeStringArray, ListArray, NullArray, StringArray, StructArray, Time32MillisecondArray,
+        Time32SecondArray, Time64MicrosecondArray, Time64NanosecondArray,
+        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+        TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array, UnionArray,
     },
     buffer::{Buffer, MutableBuffer},
     compute::cast,
@@ -53,7 +53,11 @@ use arrow::{
 };
 use flatbuffers_arrow::FlatBufferBuilder;
 
-use super::{padding, util::FlatBufferWrapper};
+use super::{
+    field_conversion::categorical_dictionaries,
+    padding,
+    util::{arrow_continuation, FlatBufferWrapper, CONTINUATION},
+};
 
 // MOD: Changed to zero-copy Buffer, i.e. Buffer::from -> Buffer::from_unowned
 //      debug_assert
@@ -89,7 +93,7 @@ fn create_array(
     mut buffer_index: usize,
 ) -> (ArrayRef, usize, usize) {
     let array = match data_type {
-        DataType::Utf8 | DataType::Binary => {
+        DataType::Utf8 | DataType::Binary | DataType::LargeUtf8 | DataType::LargeBinary => {
             let array = create_primitive_array(
                 &nodes[node_index],
                 data_type,
@@ -115,7 +119,7 @@ fn create_array(
             buffer_index += 2;
             array
         }
-        DataType::List(ref list_data_type) => {
+        DataType::List(ref list_data_type) | DataType::LargeList(ref list_data_type) => {
             let list_node = &nodes[node_index];
             let list_buffers: Vec<Buffer> = buffers[buffer_index..buffer_index + 2]
                 .iter()
@@ -254,7 +258,7 @@ fn create_primitive_array(
     let length = field_node.length() as usize;
     let null_count = field_node.null_count() as usize;
     let array_data = match data_type {
-        DataType::Utf8 | DataType::Binary => {
+        DataType::Utf8 | DataType::Binary | DataType::LargeUtf8 | DataType::LargeBinary => {
             // read 3 buffers
             let mut builder = ArrayData::builder(data_type.clone())
                 .len(length)
@@ -381,7 +385,7 @@ fn create_list_array(
     buffers: &[Buffer],
     child_array: ArrayRef,
 ) -> ArrayRef {
-    if let DataType::List(_) = *data_type {
+    if let DataType::List(_) | DataType::LargeList(_) = *data_type {
         let null_count = field_node.null_count() as usize;
         let mut builder = ArrayData::builder(data_type.clone())
             .len(field_node.length() as usize)
@@ -441,12 +445,13 @@ fn create_dictionary_array(
 
 // COPY: ::ipc::reader.rs
 // MOD: take `batch` as reference
+//      build the dictionaries of categorical fields from the schema metadata instead of taking
+//      them as argument, as batches only contain the dictionary indices
 /// Creates a record batch from binary data using the `ipc::RecordBatch` indexes and the `Schema`
 pub(crate) fn read_record_batch(
     buf: &[u8],
     batch: &ipc::RecordBatch<'_>,
     schema: Arc<Schema>,
-    dictionaries: &[Option<ArrayRef>],
 ) -> Result<Option<RecordBatch>> {
     let buffers = batch.buffers().ok_or_else(|| {
         ArrowError::IoError("Unable to get buffers from IPC RecordBatch".to_string())
@@ -454,6 +459,8 @@ pub(crate) fn read_record_batch(
     let field_nodes = batch.nodes().ok_or_else(|| {
         ArrowError::IoError("Unable to get field nodes from IPC RecordBatch".to_string())
     })?;
+    let dictionaries =
+        categorical_dictionaries(&schema).map_err(|err| ArrowError::ParseError(err.to_string()))?;
     // keep track of buffer and node index, the functions that create arrays mutate these
     let mut buffer_index = 0;
     let mut node_index = 0;
@@ -466,7 +473,7 @@ pub(crate) fn read_record_batch(
             field.data_type(),
             buf,
             buffers,
-            dictionaries,
+            &dictionaries,
             node_index,
             buffer_index,
         );
@@ -520,8 +527,11 @@ pub fn make_array(data: ArrayDataRef) -> ArrayRef {
         DataType::Duration(TimeUnit::Nanosecond) => Arc::new(DurationNanosecondArray::from(data)),
         DataType::Binary => Arc::new(BinaryArray::from(data)),
         DataType::FixedSizeBinary(_) => Arc::new(FixedSizeBinaryArray::from(data)),
+        DataType::LargeBinary => Arc::new(LargeBinaryArray::from(data)),
         DataType::Utf8 => Arc::new(StringArray::from(data)),
+        DataType::LargeUtf8 => Arc::new(LargeStringArray::from(data)),
         DataType::List(_) => Arc::new(ListArray::from(data)),
+        DataType::LargeList(_) => Arc::new(LargeListArray::from(data)),
         DataType::Struct(_) => Arc::new(StructArray::from(data)),
         DataType::Union(_) => Arc::new(UnionArray::from(data)),
         DataType::FixedSizeList(..) => Arc::new(FixedSizeListArray::from(data)),
@@ -665,6 +675,119 @@ pub(crate) fn record_batch_to_bytes<'fbb>(
     (fbb.into(), arrow_data)
 }
 
+// ADD
+/// Write a schema followed by `batches` in the Arrow IPC streaming format.
+///
+/// The metadata of every message is padded so that each message body starts at an offset aligned
+/// to `arrow::memory::ALIGNMENT`. This lets [`read_record_batch_stream`] read the batches without
+/// copying them.
+#[must_use]
+pub fn record_batch_stream_to_bytes(schema: &Schema, batches: &[RecordBatch]) -> Vec<u8> {
+    fn write_message(stream: &mut Vec<u8>, meta: &[u8], body: &[u8]) {
+        let meta_len =
+            meta.len() + padding::get_static_buffer_pad(stream.len() + CONTINUATION + meta.len());
+        stream.extend_from_slice(&arrow_continuation(meta_len));
+        stream.extend_from_slice(meta);
+        stream.resize(stream.len() + meta_len - meta.len(), 0);
+        stream.extend_from_slice(body);
+    }
+
+    let mut stream = Vec::new();
+    write_message(&mut stream, schema_to_bytes(schema).as_ref(), &[]);
+    for batch in batches {
+        let (meta, body) = static_record_batch_to_bytes(batch);
+        write_message(&mut stream, meta.as_ref(), &body);
+    }
+    // End-of-stream marker
+    stream.extend_from_slice(&arrow_continuation(0));
+    stream
+}
+
+// ADD
+/// A schema and its `RecordBatch`es read from an Arrow IPC stream.
+///
+/// The batches point into the memory owned by this struct, so they (and their clones) must not
+/// outlive it.
+pub struct RecordBatchStream {
+    _data: Buffer,
+    schema: Arc<Schema>,
+    batches: Vec<RecordBatch>,
+}
+
+impl RecordBatchStream {
+    pub fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+}
+
+// ADD
+/// Read a stream written by [`record_batch_stream_to_bytes`].
+///
+/// `bytes` is copied once into aligned memory, the batches are then read from it without copying.
+pub fn read_record_batch_stream(bytes: &[u8]) -> Result<RecordBatchStream> {
+    fn slice(buf: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+        buf.get(offset..offset + len)
+            .ok_or_else(|| ArrowError::IoError("Unexpected end of IPC stream".to_string()))
+    }
+
+    let data = Buffer::from(bytes);
+    let buf = data.data();
+    let mut offset = 0;
+    let mut schema = None;
+    let mut batches = Vec::new();
+    loop {
+        let continuation = slice(buf, offset, CONTINUATION)?;
+        if continuation[..4] != [255, 255, 255, 255] {
+            return Err(ArrowError::IoError(
+                "Missing continuation marker in IPC stream".to_string(),
+            ));
+        }
+        let meta_len = u32::from_le_bytes([
+            continuation[4],
+            continuation[5],
+            continuation[6],
+            continuation[7],
+        ]) as usize;
+        offset += CONTINUATION;
+        if meta_len == 0 {
+            break;
+        }
+
+        let message = ipc::get_root_as_message(slice(buf, offset, meta_len)?);
+        offset += meta_len;
+        let body_len = message.bodyLength() as usize;
+        let body = slice(buf, offset, body_len)?;
+        offset += body_len;
+
+        if let Some(ipc_schema) = message.header_as_schema() {
+            schema = Some(Arc::new(ipc::convert::fb_to_schema(ipc_schema)));
+        } else if let Some(ipc_batch) = message.header_as_record_batch() {
+            let schema = schema.clone().ok_or_else(|| {
+                ArrowError::IoError("IPC stream has a record batch before its schema".to_string())
+            })?;
+            if let Some(batch) = read_record_batch(body, &ipc_batch, schema)? {
+                batches.push(batch);
+            }
+        } else {
+            return Err(ArrowError::IoError(
+                "Unexpected message type in IPC stream".to_string(),
+            ));
+        }
+    }
+
+    let schema =
+        schema.ok_or_else(|| ArrowError::IoError("IPC stream has no schema".to_string()))?;
+    Ok(RecordBatchStream {
+        _data: data,
+        schema,
+        batches,
+    })
+}
+
 // ADD
 #[must_use]
 pub fn simulate_record_batch_to_bytes<'fbb>(
@@ -739,7 +862,8 @@ fn simulate_write_array_data(
     // COM: Unless the parent array_data is part of a `StructArray`,
     //      then array_data.child_data() contains either nothing or
     //      a single child array
-    array_data.child_data().iter().for_each(|data_ref| {
+    // COM: the dictionaries of dictionary arrays aren't written
+    child_data_to_write(array_data).iter().for_each(|data_ref| {
         // write the nested data (e.g list data)
         offset = simulate_write_array_data(
             data_ref,
@@ -796,7 +920,8 @@ fn write_array_data_owned(
     // COM: Unless the parent array_data is part of a `StructArray`,
     //      then array_data.child_data() contains either nothing or
     //      a single child array
-    array_data.child_data().iter().for_each(|data_ref| {
+    // COM: the dictionaries of dictionary arrays aren't written
+    child_data_to_write(array_data).iter().for_each(|data_ref| {
         // write the nested data (e.g list data)
         offset = write_array_data_owned(data_ref, arrow_data, offset, buffer_count, data_ref.len());
     });
@@ -840,7 +965,8 @@ fn write_array_data(
     // COM: Unless the parent array_data is part of a `StructArray`,
     //      then array_data.child_data() contains either nothing or
     //      a single child array
-    array_data.child_data().iter().for_each(|data_ref| {
+    // MOD: the dictionaries of dictionary arrays aren't written
+    child_data_to_write(array_data).iter().for_each(|data_ref| {
         // write the nested data (e.g list data)
         offset = write_array_data(
             data_ref,
@@ -892,7 +1018,8 @@ fn write_static_array_data(
     // COM: Unless the parent array_data is part of a `StructArray`,
     //      then array_data.child_data() contains either nothing or
     //      a single child array
-    array_data.child_data().iter().for_each(|data_ref| {
+    // MOD: the dictionaries of dictionary arrays aren't written
+    child_data_to_write(array_data).iter().for_each(|data_ref| {
         // write the nested data (e.g list data)
         offset = write_static_array_data(
             data_ref,
@@ -948,6 +1075,18 @@ fn write_buffer(
     offset + total_len as i64
 }
 
+// ADD
+/// The child data which is written along with `array_data`. Only the indices of dictionary arrays
+/// are written, their dictionaries are built from the schema metadata when reading the batch (see
+/// `read_record_batch`).
+fn child_data_to_write(array_data: &ArrayDataRef) -> &[ArrayDataRef] {
+    if let DataType::Dictionary(..) = array_data.data_type() {
+        &[]
+    } else {
+        array_data.child_data()
+    }
+}
+
 // ADD
 fn simulate_write_buffer(buffer_size: usize, buffers: &mut Vec<ipc::Buffer>, offset: i64) -> i64 {
     let total_len = padding::get_dynamic_buffer_length(buffer_size) as i64;
@@ -963,3 +1102,53 @@ fn write_buffer_owned(buffer: &Buffer, arrow_data: &mut [u8], offset: i64) -> i6
     arrow_data[offset_usize..offset_usize + len].copy_from_slice(buffer.data());
     offset + total_len
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::datastore::{
+        arrow::{
+            batch_conversion::{IntoAgentStates, IntoRecordBatch},
+            message,
+        },
+        test_utils::gen_schema_and_test_agents,
+    };
+
+    #[test]
+    fn record_batch_stream_round_trip() -> crate::datastore::Result<()> {
+        let (schema, agents) = gen_schema_and_test_agents(150, 0)?;
+        let message_schema = Arc::new(message::MESSAGE_BATCH_SCHEMA.clone());
+        let (first, second) = agents.split_at(100);
+        let agent_batches = vec![
+            first.into_agent_batch(&schema)?,
+            second.into_agent_batch(&schema)?,
+        ];
+        let message_batches = vec![
+            first.into_message_batch(&message_schema)?,
+            second.into_message_batch(&message_schema)?,
+        ];
+
+        let agent_stream =
+            read_record_batch_stream(&record_batch_stream_to_bytes(&schema.arrow, &agent_batches))?;
+        let message_stream = read_record_batch_stream(&record_batch_stream_to_bytes(
+            &message_schema,
+            &message_batches,
+        ))?;
+        assert_eq!(agent_stream.schema().fields(), schema.arrow.fields());
+        assert_eq!(agent_stream.batches().len(), 2);
+        assert_eq!(message_stream.batches().len(), 2);
+
+        for (i, (agent_batch, message_batch)) in agent_stream
+            .batches()
+            .iter()
+            .zip(message_stream.batches())
+            .enumerate()
+        {
+            assert_eq!(
+                (agent_batch, message_batch).into_agent_states(Some(&schema))?,
+                (&agent_batches[i], &message_batches[i]).into_agent_states(Some(&schema))?
+            );
+        }
+        Ok(())
+    }
+}
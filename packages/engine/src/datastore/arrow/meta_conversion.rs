@@ -24,7 +24,9 @@ use crate::datastore::{
 pub enum SupportedArrowDataTypes {
     Boolean,
     Utf8,
+    LargeUtf8,
     Binary,
+    LargeBinary,
     FixedSizeBinary(i32),
 
     Int8,
@@ -51,6 +53,7 @@ pub enum SupportedArrowDataTypes {
     Duration(ArrowTimeUnit),
 
     List(Box<SupportedArrowDataTypes>),
+    LargeList(Box<SupportedArrowDataTypes>),
     FixedSizeList(Box<SupportedArrowDataTypes>, i32),
     Struct(Vec<ArrowField>),
 }
@@ -62,7 +65,9 @@ impl TryFrom<ArrowDataType> for SupportedArrowDataTypes {
         match arrow_data_type {
             ArrowDataType::Boolean => Ok(Self::Boolean),
             ArrowDataType::Utf8 => Ok(Self::Utf8),
+            ArrowDataType::LargeUtf8 => Ok(Self::LargeUtf8),
             ArrowDataType::Binary => Ok(Self::Binary),
+            ArrowDataType::LargeBinary => Ok(Self::LargeBinary),
             ArrowDataType::FixedSizeBinary(size) => Ok(Self::FixedSizeBinary(size)),
 
             ArrowDataType::Int8 => Ok(Self::Int8),
@@ -89,6 +94,9 @@ impl TryFrom<ArrowDataType> for SupportedArrowDataTypes {
             ArrowDataType::Duration(elapsed) => Ok(Self::Duration(elapsed)),
 
             ArrowDataType::List(d_type) => Ok(Self::List(Box::new(Self::try_from(*d_type)?))),
+            ArrowDataType::LargeList(d_type) => {
+                Ok(Self::LargeList(Box::new(Self::try_from(*d_type)?)))
+            }
             ArrowDataType::FixedSizeList(d_type, size) => Ok(Self::FixedSizeList(
                 Box::new(Self::try_from(*d_type)?),
                 size,
@@ -101,23 +109,11 @@ impl TryFrom<ArrowDataType> for SupportedArrowDataTypes {
             ArrowDataType::Float16 => Err(Error::UnsupportedArrowDataType {
                 d_type: ArrowDataType::Float16,
             }),
-            ArrowDataType::LargeUtf8 => Err(Error::UnsupportedArrowDataType {
-                d_type: ArrowDataType::LargeUtf8,
-            }),
-            ArrowDataType::LargeBinary => Err(Error::UnsupportedArrowDataType {
-                d_type: ArrowDataType::LargeBinary,
-            }),
-            ArrowDataType::LargeList(d_type) => Err(Error::UnsupportedArrowDataType {
-                d_type: ArrowDataType::LargeList(d_type),
-            }),
             ArrowDataType::Union(fields) => Err(Error::UnsupportedArrowDataType {
                 d_type: ArrowDataType::Union(fields),
             }),
-            ArrowDataType::Dictionary(key_type, value_type) => {
-                Err(Error::UnsupportedArrowDataType {
-                    d_type: ArrowDataType::Dictionary(key_type, value_type),
-                })
-            }
+            // Only the keys of dictionary arrays are stored, see `ipc::read_record_batch`
+            ArrowDataType::Dictionary(key_type, _) => Self::try_from(*key_type),
         }
     }
 }
@@ -298,11 +294,15 @@ pub fn data_type_to_metadata(
 ) {
     type D = SupportedArrowDataTypes;
     match data_type {
-        D::Utf8 | D::Binary => {
+        D::Utf8 | D::Binary | D::LargeUtf8 | D::LargeBinary => {
             let bit_map = BufferType::BitMap {
                 is_null_bitmap: true,
             };
-            let offsets = BufferType::Offset;
+            let offsets = if matches!(data_type, D::LargeUtf8 | D::LargeBinary) {
+                BufferType::LargeOffset
+            } else {
+                BufferType::Offset
+            };
             let binary = BufferType::Data { unit_byte_size: 1 };
             let node_meta = NodeStaticMeta::new(multiplier, vec![bit_map, offsets, binary]);
             (
@@ -329,18 +329,23 @@ pub fn data_type_to_metadata(
             ])],
             NodeMapping::empty(),
         ),
-        D::List(ref list_data_type) => {
+        D::List(ref list_data_type) | D::LargeList(ref list_data_type) => {
             let (n, buffer_count, mut b, mut p, mut c, node_mapping) =
                 data_type_to_metadata(list_data_type, true, 1);
             let mut buffer_counts = vec![2];
             buffer_counts.append(&mut b);
             let mut padding_meta = vec![is_parent_growable, is_parent_growable];
             padding_meta.append(&mut p);
+            let offsets = if matches!(data_type, D::LargeList(_)) {
+                BufferType::LargeOffset
+            } else {
+                BufferType::Offset
+            };
             let mut node_meta = vec![NodeStaticMeta::new(multiplier, vec![
                 BufferType::BitMap {
                     is_null_bitmap: true,
                 },
-                BufferType::Offset,
+                offsets,
             ])];
             node_meta.append(&mut c);
             (
@@ -1344,6 +1349,37 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn large_variable_length_base_dtypes_schema_to_col_hierarchy() {
+        let fields = vec![
+            ArrowField::new("c0", D::LargeUtf8, false),
+            ArrowField::new("c1", D::LargeBinary, false),
+        ];
+        let schema = ArrowSchema::new_with_metadata(fields.clone(), get_dummy_metadata());
+
+        let (_, buffer_info, node_info) = schema_to_column_hierarchy(Arc::new(schema));
+
+        let expected_buffer_info: Vec<bool> = (0..fields.len())
+            .flat_map(|_| [false, false, true])
+            .collect();
+
+        // same structure as the 32-bit types, but with 64-bit offsets
+        let expected_node_info: Vec<NodeStaticMeta> = (0..fields.len())
+            .map(|_| {
+                NodeStaticMeta::new(1, vec![
+                    BufferType::BitMap {
+                        is_null_bitmap: true,
+                    },
+                    BufferType::LargeOffset,
+                    BufferType::Data { unit_byte_size: 1 },
+                ])
+            })
+            .collect();
+
+        assert_eq!(buffer_info, expected_buffer_info);
+        assert_eq!(node_info, expected_node_info);
+    }
+
     #[test]
     fn list_dtype_schema_to_col_hierarchy() {
         let fields = vec![
@@ -223,7 +223,7 @@ impl Batch {
 
         let dynamic_meta = batch_message.into_meta(data_buffer.len())?;
 
-        let batch = match read_record_batch(data_buffer, &batch_message, schema, &[]) {
+        let batch = match read_record_batch(data_buffer, &batch_message, schema) {
             Ok(rb) => rb.unwrap(),
             Err(e) => return Err(Error::from(e)),
         };
@@ -626,6 +626,63 @@ impl Batch {
         }))
     }
 
+    /// Iterates an integer or a timestamp column.
+    pub fn i64_iter<'a>(
+        &'a self,
+        column_name: &str,
+    ) -> Result<impl Iterator<Item = Option<i64>> + 'a> {
+        let row_count = self.batch.num_rows();
+        let column = self.get_arrow_column(column_name)?;
+
+        let values = if let Some(column) = column.as_any().downcast_ref::<array::Int64Array>() {
+            column.value_slice(0, row_count)
+        } else if let Some(column) = column
+            .as_any()
+            .downcast_ref::<array::TimestampMillisecondArray>()
+        {
+            column.value_slice(0, row_count)
+        } else {
+            return Err(Error::InvalidArrowDowncast {
+                name: column_name.into(),
+            });
+        };
+
+        Ok((0..row_count).map(move |i| {
+            if column.is_valid(i) {
+                Some(values[i])
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Iterates an unsigned integer column or the category indices of a categorical column.
+    pub fn u32_iter<'a>(
+        &'a self,
+        column_name: &str,
+    ) -> Result<impl Iterator<Item = Option<u32>> + 'a> {
+        let row_count = self.batch.num_rows();
+        let column = self.get_arrow_column(column_name)?;
+
+        let column = match column.data_type() {
+            DataType::UInt32 => array::UInt32Array::from(column.data()),
+            DataType::Dictionary(..) => field_conversion::categorical_codes(column),
+            _ => {
+                return Err(Error::InvalidArrowDowncast {
+                    name: column_name.into(),
+                });
+            }
+        };
+
+        Ok((0..row_count).map(move |i| {
+            if column.is_valid(i) {
+                Some(column.value(i))
+            } else {
+                None
+            }
+        }))
+    }
+
     pub fn exists_iter<'a>(&'a self, column_name: &str) -> Result<impl Iterator<Item = bool> + 'a> {
         let row_count = self.batch.num_rows();
         let column = self.get_arrow_column(column_name)?;
@@ -640,16 +697,22 @@ impl Batch {
         let row_count = self.batch.num_rows();
         let column = self.get_arrow_column(column_name)?;
 
-        let column = column
-            .as_any()
-            .downcast_ref::<array::StringArray>()
-            .ok_or_else(|| Error::InvalidArrowDowncast {
-                name: column_name.into(),
-            })?;
+        // `large_string` keys are stored with 64 bit offsets
+        let any = column.as_any();
+        let value: Box<dyn Fn(usize) -> &'a str + 'a> =
+            if let Some(strings) = any.downcast_ref::<array::StringArray>() {
+                Box::new(move |i| strings.value(i))
+            } else if let Some(strings) = any.downcast_ref::<array::LargeStringArray>() {
+                Box::new(move |i| strings.value(i))
+            } else {
+                return Err(Error::InvalidArrowDowncast {
+                    name: column_name.into(),
+                });
+            };
 
         Ok((0..row_count).map(move |i| {
             if column.is_valid(i) {
-                Some(column.value(i))
+                Some(value(i))
             } else {
                 None
             }
@@ -774,10 +837,14 @@ pub fn behavior_list_bytes_iter<K: AgentList>(
 mod tests {
     extern crate test;
 
+    use serde_json::{json, Value};
     use test::Bencher;
 
     use super::*;
-    use crate::datastore::test_utils::gen_schema_and_test_agents;
+    use crate::datastore::{
+        arrow::batch_conversion::{agent_column_to_json_vals, json_vals_to_agent_column},
+        test_utils::gen_schema_and_test_agents,
+    };
 
     #[bench]
     fn agent_batch_from_states(b: &mut Bencher) {
@@ -789,4 +856,39 @@ mod tests {
                 AgentBatch::from_agent_states(agents.as_slice(), &schema, &"".to_string()).unwrap();
         });
     }
+
+    #[test]
+    fn large_offset_columns_flush() -> Result<()> {
+        let (schema, agents) = gen_schema_and_test_agents(20, 0)?;
+        let mut agent_batch =
+            AgentBatch::from_agent_states(agents.as_slice(), &schema, &"".to_string())?;
+        let column =
+            |batch: &AgentBatch, name: &str| agent_column_to_json_vals(&batch.batch, &schema, name);
+        let untouched = column(&agent_batch, "fixed_of_variable")?;
+
+        // Every value grows, so the buffers of the column and of the columns after it move
+        let grow = |name: &str, value: Value| match (name, value) {
+            ("notes", Value::String(notes)) => json!(notes.repeat(3) + "!"),
+            ("notes", _) => json!("new"),
+            (_, Value::Array(mut history)) => {
+                history.extend([json!(1.5), json!(2.5)]);
+                Value::Array(history)
+            }
+            (_, value) => panic!("Unexpected history {value}"),
+        };
+        for name in ["notes", "history"] {
+            let (i_column, field) = schema.arrow.column_with_name(name).unwrap();
+            let vals: Vec<_> = column(&agent_batch, name)?
+                .into_iter()
+                .map(|value| grow(name, value))
+                .collect();
+            let array = json_vals_to_agent_column(vals.clone(), &schema, field)?;
+            agent_batch.push_change(ArrayChange::new(array.data(), i_column))?;
+            agent_batch.flush_changes()?;
+
+            assert_eq!(column(&agent_batch, name)?, vals);
+        }
+        assert_eq!(column(&agent_batch, "fixed_of_variable")?, untouched);
+        Ok(())
+    }
 }
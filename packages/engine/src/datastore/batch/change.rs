@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use arrow::{array, buffer::Buffer as ArrowBuffer};
+use arrow::{array, buffer::Buffer as ArrowBuffer, datatypes::DataType};
 
 use super::flush::{GrowableArrayData, GrowableColumn};
 
@@ -46,7 +46,12 @@ impl GrowableArrayData for Arc<array::ArrayData> {
     }
 
     fn _child_data(&self) -> &[Self] {
-        self.child_data()
+        // Only the keys of dictionary arrays are stored, see `ipc::read_record_batch`
+        if let DataType::Dictionary(..) = self.data_type() {
+            &[]
+        } else {
+            self.child_data()
+        }
     }
 
     fn _get_non_null_buffer_count(&self) -> usize {
@@ -92,7 +92,7 @@ impl Batch {
         let rb_msg = arrow_ipc::get_root_as_message(meta_buffer)
             .header_as_record_batch()
             .ok_or(Error::InvalidRecordBatchIpcMessage)?;
-        let batch = match read_record_batch(data_buffer, &rb_msg, schema, &[]) {
+        let batch = match read_record_batch(data_buffer, &rb_msg, schema) {
             Ok(rb) => rb.unwrap(),
             Err(e) => return Err(Error::from(e)),
         };
@@ -162,7 +162,7 @@ impl Batch {
         let rb_msg = &arrow_ipc::get_root_as_message(meta_buffer)
             .header_as_record_batch()
             .ok_or(Error::InvalidRecordBatchIpcMessage)?;
-        self.batch = match read_record_batch(data_buffer, rb_msg, self.batch.schema(), &[]) {
+        self.batch = match read_record_batch(data_buffer, rb_msg, self.batch.schema()) {
             Ok(rb) => rb.unwrap(),
             Err(e) => return Err(Error::from(e)),
         };
@@ -38,34 +38,155 @@ impl super::Batch for Batch {
 }
 
 impl Batch {
+    /// Copies the data of `dataset` into a new batch.
+    ///
+    /// The data buffer holds the data as it was given, so `context.data()` is the same for every
+    /// dataset. If the dataset is step-indexed, the metadata buffer holds its rows indexed by step
+    /// (see [`index_by_step`]), otherwise it's empty.
     pub fn new_from_dataset(dataset: &SharedDataset, experiment_run_id: &str) -> Result<Batch> {
         let dataset_name = dataset.shortname.clone();
-        let dataset_size = dataset
-            .data
-            .as_ref()
-            .map(|data| data.len())
-            .unwrap_or_default();
+        let data = dataset.data.as_deref().unwrap_or_default();
+        let step_index = match (&dataset.data, &dataset.step_column) {
+            (Some(data), Some(step_column)) => index_by_step(&dataset_name, data, step_column)?,
+            _ => String::new(),
+        };
         let mut memory = Memory::from_sizes(
             experiment_run_id,
             0,
             dataset_name.len(),
-            0,
-            dataset_size,
+            step_index.len(),
+            data.len(),
             false,
         )?;
         let reload_state = Metaversion::default();
         memory.set_header(&dataset_name)?;
+        memory.set_metadata(&step_index)?;
         let buffer = memory.get_mut_data_buffer()?;
-        buffer.copy_from_slice(
-            dataset
-                .data
-                .as_ref()
-                .map(|data| data.as_bytes())
-                .unwrap_or_default(),
-        );
+        buffer.copy_from_slice(data.as_bytes());
         Ok(Batch {
             memory,
             reload_state,
         })
     }
+
+    /// The rows of a step-indexed dataset indexed by step, or `None` if the dataset isn't
+    /// step-indexed.
+    pub fn step_index(&self) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        let metadata = self.memory.get_metadata()?;
+        if metadata.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(serde_json::from_slice(metadata)?))
+        }
+    }
+}
+
+/// Turns the rows of a step-indexed dataset into an object with the row of each step under the
+/// value of its `step_column`, so the row of the current step can be looked up directly.
+///
+/// The rows are either those of a parsed CSV, i.e. arrays of strings of which the first is the
+/// header, or objects.
+fn index_by_step(name: &str, data: &str, step_column: &str) -> Result<String> {
+    let mut rows: Vec<serde_json::Value> = serde_json::from_str(data)?;
+    let header: Option<Vec<String>> = match rows.first() {
+        Some(serde_json::Value::Array(_)) => Some(serde_json::from_value(rows.remove(0))?),
+        _ => None,
+    };
+    let mut indexed = serde_json::Map::with_capacity(rows.len());
+    for row in rows {
+        let row: serde_json::Map<String, serde_json::Value> = match (&header, row) {
+            (Some(header), serde_json::Value::Array(cells)) => {
+                header.iter().cloned().zip(cells).collect()
+            }
+            (None, serde_json::Value::Object(row)) => row,
+            _ => {
+                return Err(Error::from(format!(
+                    "The rows of the step-indexed dataset {name} must all be either CSV rows or \
+                     objects"
+                )));
+            }
+        };
+        let step = match row.get(step_column) {
+            Some(serde_json::Value::Number(step)) => step.as_u64(),
+            Some(serde_json::Value::String(step)) => step.trim().parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Error::from(format!(
+                "A row of the step-indexed dataset {name} doesn't have a step in its column \
+                 {step_column:?}"
+            ))
+        })?;
+        if indexed
+            .insert(step.to_string(), serde_json::Value::Object(row))
+            .is_some()
+        {
+            return Err(Error::from(format!(
+                "The step-indexed dataset {name} has more than one row for step {step}"
+            )));
+        }
+    }
+    Ok(serde_json::to_string(&indexed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::datastore::batch::Batch as _;
+
+    fn dataset(data: &str, step_column: Option<&str>) -> SharedDataset {
+        SharedDataset {
+            name: None,
+            shortname: "prices.csv".to_string(),
+            filename: "prices.csv".to_string(),
+            url: None,
+            raw_csv: true,
+            data: Some(data.to_string()),
+            step_column: step_column.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn indexes_rows_by_step() -> Result<()> {
+        let csv = json!([["step", "price"], ["1", "10.5"], ["2", "11"]]).to_string();
+        let indexed: serde_json::Value = serde_json::from_str(&index_by_step("a", &csv, "step")?)?;
+        assert_eq!(
+            indexed,
+            json!({
+                "1": { "step": "1", "price": "10.5" },
+                "2": { "step": "2", "price": "11" },
+            })
+        );
+
+        let objects = json!([{ "t": 3, "price": 1 }]).to_string();
+        let indexed: serde_json::Value = serde_json::from_str(&index_by_step("b", &objects, "t")?)?;
+        assert_eq!(indexed, json!({ "3": { "t": 3, "price": 1 } }));
+
+        let duplicate = json!([{ "t": 3 }, { "t": 3 }]).to_string();
+        assert!(index_by_step("c", &duplicate, "t").is_err());
+        let missing = json!([["step"], ["x"]]).to_string();
+        assert!(index_by_step("d", &missing, "step").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_the_data_of_step_indexed_datasets() -> Result<()> {
+        let csv = json!([["step", "price"], ["1", "10.5"]]).to_string();
+
+        let batch = Batch::new_from_dataset(&dataset(&csv, Some("step")), "keeps_data")?;
+        assert_eq!(batch.memory().get_header()?, b"prices.csv");
+        assert_eq!(batch.memory().get_data_buffer()?, csv.as_bytes());
+        let step_index = batch.step_index()?.map(serde_json::Value::Object);
+        assert_eq!(
+            step_index,
+            Some(json!({ "1": { "step": "1", "price": "10.5" } }))
+        );
+
+        let batch = Batch::new_from_dataset(&dataset(&csv, None), "keeps_data")?;
+        assert_eq!(batch.memory().get_data_buffer()?, csv.as_bytes());
+        assert_eq!(batch.step_index()?, None);
+        Ok(())
+    }
 }
@@ -136,6 +136,34 @@ pub mod agent {
         Ok(iterables.into_iter().flatten())
     }
 
+    pub fn i64_iter<'a, B: AsRef<AgentBatch>>(
+        agent_pool: &'a [B],
+        field_name: &str,
+    ) -> Result<impl Iterator<Item = Option<i64>> + 'a> {
+        let mut iterables = Vec::with_capacity(agent_pool.len());
+
+        // Collect iterators first, because we want to check for any errors.
+        for agent_batch in agent_pool {
+            let iterable = agent_batch.as_ref().i64_iter(field_name)?;
+            iterables.push(iterable);
+        }
+        Ok(iterables.into_iter().flatten())
+    }
+
+    pub fn u32_iter<'a, B: AsRef<AgentBatch>>(
+        agent_pool: &'a [B],
+        field_name: &str,
+    ) -> Result<impl Iterator<Item = Option<u32>> + 'a> {
+        let mut iterables = Vec::with_capacity(agent_pool.len());
+
+        // Collect iterators first, because we want to check for any errors.
+        for agent_batch in agent_pool {
+            let iterable = agent_batch.as_ref().u32_iter(field_name)?;
+            iterables.push(iterable);
+        }
+        Ok(iterables.into_iter().flatten())
+    }
+
     pub fn exists_iter<'a, B: AsRef<AgentBatch>>(
         agent_pool: &'a [B],
         field_name: &str,
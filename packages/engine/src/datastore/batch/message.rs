@@ -292,7 +292,7 @@ impl Batch {
         let memory_len = data_buffer.len();
         let dynamic_meta = batch_message.into_meta(memory_len)?;
 
-        let batch = match read_record_batch(data_buffer, &batch_message, schema.clone(), &[]) {
+        let batch = match read_record_batch(data_buffer, &batch_message, schema.clone()) {
             Ok(rb) => rb.unwrap(),
             Err(e) => return Err(Error::from(e)),
         };
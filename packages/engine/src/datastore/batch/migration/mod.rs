@@ -1,6 +1,12 @@
 #![allow(clippy::cast_sign_loss, clippy::cast_ptr_alignment)]
 
-use std::{borrow::Cow, mem, ops::Deref, sync::Arc};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    mem,
+    ops::{AddAssign, Deref, Sub},
+    sync::Arc,
+};
 
 use arrow::util::bit_util;
 
@@ -13,7 +19,102 @@ use crate::{
 type Offset = i32;
 type LargeOffset = i64;
 
-static EMPTY_OFFSET_BUFFER: [u8; 4] = [0, 0, 0, 0];
+// Long enough for a single `Offset` or `LargeOffset`
+static EMPTY_OFFSET_BUFFER: [u8; 8] = [0; 8];
+
+/// The values of offset buffers, i.e. [`Offset`] for `Offset` buffers and [`LargeOffset`] for
+/// `LargeOffset` buffers.
+trait OffsetValue: Copy + Debug + Default + PartialEq + AddAssign + Sub<Output = Self> {
+    fn as_usize(self) -> usize;
+
+    /// Adds `rhs`, failing with [`Error::OffsetOverflow`] if the sum doesn't fit into `Self`.
+    fn checked_add(self, rhs: Self) -> Result<Self>;
+
+    fn shift_action(
+        from: usize,
+        len: usize,
+        offset_value_dec: Self,
+        base_offset_index: usize,
+    ) -> InnerShiftAction;
+
+    fn create_action(
+        data: &[Self],
+        offset_shift: Self,
+        base_offset_index: usize,
+    ) -> InnerCreateAction<'_>;
+}
+
+impl OffsetValue for Offset {
+    fn as_usize(self) -> usize {
+        self as usize
+    }
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        Offset::checked_add(self, rhs).ok_or(Error::OffsetOverflow)
+    }
+
+    fn shift_action(
+        from: usize,
+        len: usize,
+        offset_value_dec: Self,
+        base_offset_index: usize,
+    ) -> InnerShiftAction {
+        InnerShiftAction::Offset {
+            from,
+            len,
+            offset_value_dec,
+            base_offset_index,
+        }
+    }
+
+    fn create_action(
+        data: &[Self],
+        offset_shift: Self,
+        base_offset_index: usize,
+    ) -> InnerCreateAction<'_> {
+        InnerCreateAction::Offset {
+            data,
+            offset_shift,
+            base_offset_index,
+        }
+    }
+}
+
+impl OffsetValue for LargeOffset {
+    fn as_usize(self) -> usize {
+        self as usize
+    }
+
+    fn checked_add(self, rhs: Self) -> Result<Self> {
+        LargeOffset::checked_add(self, rhs).ok_or(Error::OffsetOverflow)
+    }
+
+    fn shift_action(
+        from: usize,
+        len: usize,
+        offset_value_dec: Self,
+        base_offset_index: usize,
+    ) -> InnerShiftAction {
+        InnerShiftAction::LargeOffset {
+            from,
+            len,
+            offset_value_dec,
+            base_offset_index,
+        }
+    }
+
+    fn create_action(
+        data: &[Self],
+        offset_shift: Self,
+        base_offset_index: usize,
+    ) -> InnerCreateAction<'_> {
+        InnerCreateAction::LargeOffset {
+            data,
+            offset_shift,
+            base_offset_index,
+        }
+    }
+}
 
 pub type RemoveAction = IndexAction;
 pub type CopyAction = IndexAction;
@@ -63,6 +164,12 @@ pub enum InnerCreateAction<'a> {
         // new index of the base offset, relative to the start of the buffer
         base_offset_index: usize,
     },
+    /// Same as `Offset`, but for i64 offsets
+    LargeOffset {
+        data: &'a [i64],
+        offset_shift: i64,
+        base_offset_index: usize,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -88,14 +195,11 @@ pub enum InnerShiftAction {
         // new index of the base offset, relative to the start of the buffer
         base_offset_index: usize,
     },
+    /// Same as `Offset`, but for i64 offsets
     LargeOffset {
-        // Starting old index relative to the offset of the old buffer
         from: usize,
-        // `to - from == len`
-        to: usize,
-        // the new value the base (offsets_buffer[from]) offset will take
-        base_offset_value: i64,
-        // new index of the base offset
+        len: usize,
+        offset_value_dec: i64,
         base_offset_index: usize,
     },
 }
@@ -115,14 +219,87 @@ impl InnerShiftAction {
                 base_offset_index,
             } => (len + base_offset_index) * mem::size_of::<Offset>(),
             InnerShiftAction::LargeOffset {
+                from: _,
+                len,
+                offset_value_dec: _,
+                base_offset_index,
+            } => (len + base_offset_index) * mem::size_of::<LargeOffset>(),
+        }
+    }
+
+    /// Shifts the sub-buffer inside `data_buffer`, where the buffer starts at `old_offset` before
+    /// and at `new_offset` after the shift.
+    fn shift(&self, data_buffer: &mut [u8], old_offset: usize, new_offset: usize) {
+        match self {
+            InnerShiftAction::Data {
+                offset,
+                len,
+                dest_offset,
+            } => {
+                let start_offset = old_offset + *offset;
+                let end_offset = start_offset + *len;
+                let dest_offset = new_offset + *dest_offset;
+                data_buffer.copy_within(start_offset..end_offset, dest_offset);
+            }
+            InnerShiftAction::Offset {
                 from,
-                to,
-                base_offset_value: _,
+                len,
+                offset_value_dec,
                 base_offset_index,
+            } => shift_offsets(
+                data_buffer,
+                old_offset + *from * mem::size_of::<Offset>(),
+                new_offset + *base_offset_index * mem::size_of::<Offset>(),
+                *len,
+                *offset_value_dec,
+            ),
+            InnerShiftAction::LargeOffset {
+                from,
+                len,
+                offset_value_dec,
+                base_offset_index,
+            } => shift_offsets(
+                data_buffer,
+                old_offset + *from * mem::size_of::<LargeOffset>(),
+                new_offset + *base_offset_index * mem::size_of::<LargeOffset>(),
+                *len,
+                *offset_value_dec,
+            ),
+        }
+    }
+
+    /// Copies the shifted sub-buffer from the buffer starting at `old_offset` in `data_buffer` to
+    /// `target`, which holds the new buffer.
+    fn copy_to(&self, data_buffer: &[u8], old_offset: usize, target: &mut [u8]) {
+        match self {
+            InnerShiftAction::Data {
+                offset,
+                len,
+                dest_offset,
             } => {
-                let offset_count = *to - *from;
-                (offset_count + base_offset_index) * mem::size_of::<LargeOffset>()
+                let start_offset = old_offset + *offset;
+                let end_offset = start_offset + *len;
+                target[*dest_offset..*dest_offset + *len]
+                    .copy_from_slice(&data_buffer[start_offset..end_offset]);
             }
+            InnerShiftAction::Offset {
+                from,
+                len,
+                offset_value_dec,
+                base_offset_index,
+            } => (0..*len).for_each(|i| {
+                let value: Offset = get_offset_by_index(&data_buffer[old_offset..], *from + i);
+                set_offset_by_index(target, *base_offset_index + i, value - *offset_value_dec);
+            }),
+            InnerShiftAction::LargeOffset {
+                from,
+                len,
+                offset_value_dec,
+                base_offset_index,
+            } => (0..*len).for_each(|i| {
+                let value: LargeOffset = get_offset_by_index(&data_buffer[old_offset..], *from + i);
+                set_offset_by_index(target, *base_offset_index + i, value - *offset_value_dec);
+            }),
         }
     }
 }
@@ -211,104 +388,15 @@ impl<'a> BufferActions<'a> {
                         if base_right_shift == 0 {
                             // Iterate left to right because we know that
                             // we're not right-shifting inside the buffer
-                            shift.iter().for_each(|shift_action| match shift_action {
-                                InnerShiftAction::Data {
-                                    offset,
-                                    len,
-                                    dest_offset,
-                                } => {
-                                    let start_offset = old_offset + *offset;
-                                    let end_offset = start_offset + *len;
-                                    let dest_offset = new_offset + *dest_offset;
-                                    data_buffer.copy_within(start_offset..end_offset, dest_offset);
-                                }
-                                InnerShiftAction::Offset {
-                                    from,
-                                    len,
-                                    offset_value_dec,
-                                    base_offset_index,
-                                } => {
-                                    // As we're not shifting the first sub-buffer
-                                    // then we can ignore it.
-                                    if *len != 0 && *from != 0 {
-                                        let offset_size = mem::size_of::<Offset>();
-                                        let start_offset = old_offset + *from * offset_size;
-                                        let byte_len = len * offset_size;
-                                        let old_offsets = unsafe {
-                                            data_buffer[start_offset..start_offset + byte_len]
-                                                .align_to::<i32>()
-                                                .1
-                                        };
-                                        let new_start_offset =
-                                            new_offset + *base_offset_index * offset_size;
-                                        debug_assert!(start_offset >= new_start_offset);
-                                        let decrement = offset_value_dec;
-                                        // We are going left-to-right so we can at most change the
-                                        // same value we're inspecting
-                                        let new_offsets = unsafe {
-                                            let ptr: *const u8 = &data_buffer[new_start_offset];
-                                            let ptr = ptr as *mut Offset;
-                                            std::slice::from_raw_parts_mut(ptr, *len)
-                                        };
-                                        new_offsets.iter_mut().enumerate().for_each(
-                                            |(i, offset_value)| {
-                                                *offset_value = old_offsets[i] - decrement;
-                                            },
-                                        );
-                                    }
-                                }
-
-                                _ => unimplemented!(),
+                            shift.iter().for_each(|shift_action| {
+                                shift_action.shift(data_buffer, old_offset, new_offset);
                             });
                         } else if base_right_shift + new_length >= old_length {
                             // Iterate right to left because we know that
                             // we're not left-shifting inside the buffer
-                            shift
-                                .iter()
-                                .rev()
-                                .for_each(|shift_action| match shift_action {
-                                    InnerShiftAction::Data {
-                                        offset,
-                                        len,
-                                        dest_offset,
-                                    } => {
-                                        let start_offset = old_offset + *offset;
-                                        let end_offset = start_offset + *len;
-                                        let dest_offset = new_offset + *dest_offset;
-                                        data_buffer
-                                            .copy_within(start_offset..end_offset, dest_offset);
-                                    }
-                                    InnerShiftAction::Offset {
-                                        from,
-                                        len,
-                                        offset_value_dec,
-                                        base_offset_index,
-                                    } => {
-                                        let offset_size = mem::size_of::<Offset>();
-                                        let start_offset = old_offset + *from * offset_size;
-                                        let byte_len = len * offset_size;
-                                        let old_offsets = unsafe {
-                                            data_buffer[start_offset..start_offset + byte_len]
-                                                .align_to::<i32>()
-                                                .1
-                                        };
-                                        let new_start_offset =
-                                            new_offset + *base_offset_index * offset_size;
-
-                                        let decrement = offset_value_dec;
-                                        let new_offsets = unsafe {
-                                            let ptr = &data_buffer[new_start_offset] as *const u8;
-                                            let ptr = ptr as *mut Offset;
-                                            std::slice::from_raw_parts_mut(ptr, *len)
-                                        };
-                                        new_offsets.iter_mut().enumerate().rev().for_each(
-                                            |(i, offset_value)| {
-                                                *offset_value = old_offsets[i] - decrement;
-                                            },
-                                        );
-                                    }
-                                    _ => unimplemented!(),
-                                });
+                            shift.iter().rev().for_each(|shift_action| {
+                                shift_action.shift(data_buffer, old_offset, new_offset);
+                            });
                         } else {
                             // We're both right and left shifting, this has
                             // no good ordering, so perform a clone and overwrite
@@ -319,51 +407,12 @@ impl<'a> BufferActions<'a> {
                                 .get_buffer_length();
 
                             let mut temporary_buffer = vec![0; target_length];
-                            shift.iter().for_each(|shift_action| match shift_action {
-                                InnerShiftAction::Data {
-                                    offset,
-                                    len,
-                                    dest_offset,
-                                } => {
-                                    let start_offset = old_offset + *offset;
-                                    let end_offset = start_offset + *len;
-                                    let dest_temp_offset = *dest_offset;
-                                    let slice = &data_buffer[start_offset..end_offset];
-                                    temporary_buffer[dest_temp_offset..dest_temp_offset + *len]
-                                        .copy_from_slice(slice);
-                                }
-                                InnerShiftAction::Offset {
-                                    from,
-                                    len,
-                                    offset_value_dec,
-                                    base_offset_index,
-                                } => {
-                                    if *len != 0 {
-                                        let offset_size = mem::size_of::<Offset>();
-                                        let start_offset = old_offset + *from * offset_size;
-                                        let byte_len = len * offset_size;
-                                        let old_offsets = unsafe {
-                                            data_buffer[start_offset..start_offset + byte_len]
-                                                .align_to::<i32>()
-                                                .1
-                                        };
-                                        let new_temp_start_offset =
-                                            *base_offset_index * offset_size;
-                                        let decrement = offset_value_dec;
-                                        let new_offsets = unsafe {
-                                            temporary_buffer
-                                                [new_temp_start_offset..new_temp_start_offset + len]
-                                                .align_to_mut::<i32>()
-                                                .1
-                                        };
-                                        new_offsets.iter_mut().enumerate().for_each(
-                                            |(i, offset_value)| {
-                                                *offset_value = old_offsets[i] - decrement;
-                                            },
-                                        );
-                                    }
-                                }
-                                _ => unimplemented!(),
+                            shift.iter().for_each(|shift_action| {
+                                shift_action.copy_to(
+                                    data_buffer,
+                                    old_offset,
+                                    &mut temporary_buffer,
+                                );
                             });
                             // Finally, copy temporary buffer into data buffer
                             data_buffer[new_offset..new_offset + temporary_buffer.len()]
@@ -393,19 +442,24 @@ impl<'a> BufferActions<'a> {
                                         offset_shift,
                                         base_offset_index,
                                     } => {
-                                        let start_offset = new_offset
-                                            + base_offset_index * mem::size_of::<Offset>();
-                                        let end_offset =
-                                            start_offset + data.len() * mem::size_of::<Offset>();
-                                        let new_offsets = unsafe {
-                                            data_buffer[start_offset..end_offset]
-                                                .align_to_mut::<i32>()
-                                                .1
-                                        };
-                                        new_offsets.iter_mut().enumerate().for_each(
-                                            |(index, offset_value)| {
-                                                *offset_value = data[index] + *offset_shift;
-                                            },
+                                        write_offsets(
+                                            &mut data_buffer[new_offset..],
+                                            *base_offset_index,
+                                            data,
+                                            *offset_shift,
+                                        );
+                                        is_offset = true;
+                                    }
+                                    InnerCreateAction::LargeOffset {
+                                        data,
+                                        offset_shift,
+                                        base_offset_index,
+                                    } => {
+                                        write_offsets(
+                                            &mut data_buffer[new_offset..],
+                                            *base_offset_index,
+                                            data,
+                                            *offset_shift,
                                         );
                                         is_offset = true;
                                     }
@@ -641,203 +695,40 @@ impl<'a> BufferActions<'a> {
                     // Here we modify range_actions
                     let mut range_actions =
                         updated_range_actions.unwrap_or_else(|| parent_range_actions.clone());
-                    debug_assert!({ range_actions.is_well_ordered_remove() });
-                    let buffer = batch.map_or_else(
-                        || Ok(&EMPTY_OFFSET_BUFFER[..]),
-                        |batch| batch.as_ref().get_buffer(buffer_index),
+                    let (variant, target_buffer_size) = offset_buffer_action::<Offset, _>(
+                        &mut range_actions,
+                        batch,
+                        batches,
+                        new_agents.map(|ad| ad.buffers()[i - 1].data()),
+                        buffer_index,
+                        node_dynamic_meta.length,
                     )?;
-
-                    // Markers are always n + 1 long
-                    let original_length = node_dynamic_meta.length + 1;
-                    let original_last_index = node_dynamic_meta.length;
-                    let target_unit_count = range_actions.total_size(original_length);
-                    let target_buffer_size = target_unit_count * mem::size_of::<Offset>();
-
-                    // REMOVE ACTIONS
-
-                    let mut removed_count = 0;
-                    // Next i32 index (*not* byte)
-                    let mut next_index = 0;
-                    let mut next_offset_value = 0;
-                    let mut next_offset_index = 0;
-
-                    let mut total_remove_len = 0;
-                    let removed_offsets_count = range_actions.remove.0;
-                    let mut shift_actions = range_actions
-                        .remove_mut()
-                        .iter_mut()
-                        .map(|range| {
-                            let (from, to, start_offset_value) = unsafe {
-                                (
-                                    get_offset_by_index(buffer, range.index),
-                                    get_offset_by_index(buffer, range.next_index()),
-                                    get_offset_by_index(buffer, next_index),
-                                )
-                            };
-                            let offset_value_dec = start_offset_value - next_offset_value;
-                            let action = InnerShiftAction::Offset {
-                                from: next_index,
-                                len: range.index - next_index,
-                                offset_value_dec,
-                                base_offset_index: next_offset_index,
-                            };
-
-                            next_index = range.next_index();
-                            removed_count += range.len;
-                            next_offset_value += from - start_offset_value;
-                            next_offset_index = next_index - removed_count;
-
-                            // Update the range value here, so next access is in the right place
-                            range.index = from as usize;
-                            range.len = (to - from) as usize;
-                            total_remove_len += range.len;
-
-                            action
-                        })
-                        .collect::<Vec<_>>();
-
-                    range_actions.remove.0 = total_remove_len;
-                    debug_assert!(next_index <= original_last_index);
-                    debug_assert_eq!(next_offset_index, next_index - removed_count);
-                    let start_offset_value = unsafe { get_offset_by_index(buffer, next_index) };
-                    let offset_value_dec = start_offset_value - next_offset_value;
-                    let last_remove_action = InnerShiftAction::Offset {
-                        from: next_index,
-                        // +1 to also move the very last index
-                        len: original_last_index - next_index + 1,
-                        offset_value_dec,
-                        base_offset_index: next_offset_index,
-                    };
-                    next_offset_index = original_length - removed_count;
-                    let end_offset_value =
-                        unsafe { get_offset_by_index(buffer, original_last_index) };
-                    let mut last_offset_value = end_offset_value - offset_value_dec;
-
-                    shift_actions.push(last_remove_action);
-
-                    debug_assert_eq!(removed_count, removed_offsets_count);
-
-                    let byte_size_after_remove = (next_offset_index) * mem::size_of::<Offset>();
-
-                    // MOVE ACTION
-                    let move_action = if range_actions.copy.0 > 0 {
-                        let mut move_bytes: Vec<u8> =
-                            Vec::with_capacity(range_actions.copy.0 * mem::size_of::<Offset>());
-
-                        let mut total_move_len = 0;
-
-                        range_actions
-                            .copy_mut()
-                            .iter_mut()
-                            .try_for_each::<_, Result<()>>(|(j, ranges)| {
-                                let src_buffer = unsafe {
-                                    batches[*j]
-                                        .as_ref()
-                                        .get_buffer(buffer_index)?
-                                        .align_to::<i32>()
-                                        .1
-                                };
-                                ranges.iter_mut().for_each(|range| {
-                                    let first_offset = src_buffer[range.index];
-                                    let last_offset = src_buffer[range.next_index()];
-                                    let offset_diff = last_offset_value - first_offset;
-                                    (range.index + 1..=range.index + range.len).for_each(|k| {
-                                        let new_offset = src_buffer[k] + offset_diff;
-                                        // Assumes little endian
-                                        let slice = unsafe {
-                                            std::slice::from_raw_parts(
-                                                &new_offset as *const i32 as *const _,
-                                                mem::size_of::<Offset>(),
-                                            )
-                                        };
-                                        move_bytes.extend_from_slice(slice);
-                                    });
-                                    let added_unit_count = last_offset - first_offset;
-                                    last_offset_value += added_unit_count;
-                                    next_offset_index += range.len;
-
-                                    // Update the range value here, so next access is in the right
-                                    // place
-                                    range.index = first_offset as usize;
-                                    range.len = (added_unit_count) as usize;
-                                    total_move_len += range.len;
-                                });
-                                Ok(())
-                            })?;
-
-                        range_actions.copy.0 = total_move_len;
-
-                        Some(InnerMoveAction {
-                            byte_offset: byte_size_after_remove,
-                            data: move_bytes,
-                        })
-                    } else {
-                        None
-                    };
-
-                    // CREATE ACTIONS
-                    let create_actions = new_agents.map(|ad| {
-                        let buffer = &ad.buffers()[i - 1];
-
-                        let src_buffer = unsafe { buffer.typed_data::<Offset>() };
-                        let mut total_create_len = 0;
-                        let ret = range_actions
-                            .create_mut()
-                            .iter_mut()
-                            .map(|range| {
-                                // We take n + 1 values because we care about the lengths
-                                let data = &src_buffer[range.index..=range.next_index()];
-                                let first_offset = data[0];
-                                let offset_shift = last_offset_value - first_offset;
-                                let action = InnerCreateAction::Offset {
-                                    // Don't include the first element, because
-                                    // that's incorporated in `offset_shift`
-                                    data: &data[1..],
-                                    base_offset_index: next_offset_index,
-                                    offset_shift,
-                                };
-                                let added_unit_count = data[data.len() - 1] - data[0];
-                                last_offset_value += added_unit_count;
-                                next_offset_index += range.len;
-                                debug_assert_eq!(
-                                    last_offset_value,
-                                    data[data.len() - 1] + offset_shift
-                                );
-                                // Update the range value here, so next access is in the right place
-                                range.index = data[0] as usize;
-                                range.len = added_unit_count as usize;
-                                total_create_len += range.len;
-
-                                action
-                            })
-                            .collect();
-                        range_actions.create.0 = total_create_len;
-
-                        ret
+                    updated_range_actions = Some(range_actions);
+                    actions.push(BufferAction {
+                        variant,
+                        old_offset: buffer_meta.offset,
+                        old_length: buffer_meta.length,
                     });
-
-                    // Final procedures:
-                    debug_assert!(range_actions.is_well_ordered_remove());
+                    target_buffer_size
+                }
+                super::super::meta::BufferType::LargeOffset => {
+                    // Here we modify range_actions
+                    let mut range_actions =
+                        updated_range_actions.unwrap_or_else(|| parent_range_actions.clone());
+                    let (variant, target_buffer_size) = offset_buffer_action::<LargeOffset, _>(
+                        &mut range_actions,
+                        batch,
+                        batches,
+                        new_agents.map(|ad| ad.buffers()[i - 1].data()),
+                        buffer_index,
+                        node_dynamic_meta.length,
+                    )?;
                     updated_range_actions = Some(range_actions);
-
-                    let variant = BufferActionVariant::Tweak {
-                        shift: shift_actions,
-                        copy: move_action,
-                        create: create_actions,
-                    };
-                    let action = BufferAction {
+                    actions.push(BufferAction {
                         variant,
                         old_offset: buffer_meta.offset,
                         old_length: buffer_meta.length,
-                    };
-                    actions.push(action);
-
-                    // The target size is the same
-                    debug_assert_eq!(
-                        target_buffer_size,
-                        (next_offset_index) * mem::size_of::<Offset>()
-                    );
-
+                    });
                     target_buffer_size
                 }
                 super::super::meta::BufferType::Data { unit_byte_size } => {
@@ -951,7 +842,6 @@ impl<'a> BufferActions<'a> {
 
                     target_buffer_size
                 }
-                super::super::meta::BufferType::LargeOffset => unimplemented!(),
             };
 
             let old_next_offset = dynamic_meta.map_or(0, |meta| {
@@ -1076,14 +966,244 @@ impl<'a> BufferActions<'a> {
     }
 }
 
-// Assumes alignment
-unsafe fn get_offset_by_index(data: &[u8], index: usize) -> i32 {
-    let i32_size = std::mem::size_of::<Offset>();
-    let offset = index * i32_size;
-    let ptr: *const u8 = &data[offset];
+/// Computes the buffer action for an offset buffer with offsets of type `O` and the size of the
+/// buffer after migration.
+///
+/// `range_actions` are updated in place to refer to the child data instead of to the offsets.
+fn offset_buffer_action<'b, O: OffsetValue, B: Deref<Target = AgentBatch>>(
+    range_actions: &mut RangeActions,
+    batch: Option<&B>,
+    batches: &[B],
+    new_agents_buffer: Option<&'b [u8]>,
+    buffer_index: usize,
+    node_length: usize,
+) -> Result<(BufferActionVariant<'b>, usize)> {
+    let offset_size = mem::size_of::<O>();
+    debug_assert!({ range_actions.is_well_ordered_remove() });
+    let buffer = batch.map_or_else(
+        || Ok(&EMPTY_OFFSET_BUFFER[..]),
+        |batch| batch.as_ref().get_buffer(buffer_index),
+    )?;
+
+    // Markers are always n + 1 long
+    let original_length = node_length + 1;
+    let original_last_index = node_length;
+    let target_unit_count = range_actions.total_size(original_length);
+    let target_buffer_size = target_unit_count * offset_size;
+
+    // REMOVE ACTIONS
+
+    let mut removed_count = 0;
+    // Next offset index (*not* byte)
+    let mut next_index = 0;
+    let mut next_offset_value = O::default();
+    let mut next_offset_index = 0;
+
+    let mut total_remove_len = 0;
+    let removed_offsets_count = range_actions.remove.0;
+    let mut shift_actions = range_actions
+        .remove_mut()
+        .iter_mut()
+        .map(|range| {
+            let from: O = get_offset_by_index(buffer, range.index);
+            let to: O = get_offset_by_index(buffer, range.next_index());
+            let start_offset_value: O = get_offset_by_index(buffer, next_index);
+            let offset_value_dec = start_offset_value - next_offset_value;
+            let action = O::shift_action(
+                next_index,
+                range.index - next_index,
+                offset_value_dec,
+                next_offset_index,
+            );
+
+            next_index = range.next_index();
+            removed_count += range.len;
+            next_offset_value += from - start_offset_value;
+            next_offset_index = next_index - removed_count;
+
+            // Update the range value here, so next access is in the right place
+            range.index = from.as_usize();
+            range.len = (to - from).as_usize();
+            total_remove_len += range.len;
+
+            action
+        })
+        .collect::<Vec<_>>();
+
+    range_actions.remove.0 = total_remove_len;
+    debug_assert!(next_index <= original_last_index);
+    debug_assert_eq!(next_offset_index, next_index - removed_count);
+    let start_offset_value: O = get_offset_by_index(buffer, next_index);
+    let offset_value_dec = start_offset_value - next_offset_value;
+    let last_remove_action = O::shift_action(
+        next_index,
+        // +1 to also move the very last index
+        original_last_index - next_index + 1,
+        offset_value_dec,
+        next_offset_index,
+    );
+    next_offset_index = original_length - removed_count;
+    let end_offset_value: O = get_offset_by_index(buffer, original_last_index);
+    let mut last_offset_value = end_offset_value - offset_value_dec;
+
+    shift_actions.push(last_remove_action);
+
+    debug_assert_eq!(removed_count, removed_offsets_count);
+
+    let byte_size_after_remove = next_offset_index * offset_size;
+
+    // MOVE ACTION
+    let move_action = if range_actions.copy.0 > 0 {
+        let mut move_bytes: Vec<u8> = Vec::with_capacity(range_actions.copy.0 * offset_size);
+
+        let mut total_move_len = 0;
+
+        range_actions
+            .copy_mut()
+            .iter_mut()
+            .try_for_each::<_, Result<()>>(|(j, ranges)| {
+                let src_buffer = batches[*j].as_ref().get_buffer(buffer_index)?;
+                ranges.iter_mut().try_for_each::<_, Result<()>>(|range| {
+                    let first_offset: O = get_offset_by_index(src_buffer, range.index);
+                    let last_offset: O = get_offset_by_index(src_buffer, range.next_index());
+                    let added_unit_count = last_offset - first_offset;
+                    // The copied offsets are at most the new last offset, so they can't overflow
+                    // if it doesn't
+                    let new_last_offset_value = last_offset_value.checked_add(added_unit_count)?;
+                    let offset_diff = last_offset_value - first_offset;
+                    let mut new_offset = [0; mem::size_of::<LargeOffset>()];
+                    (range.index + 1..=range.index + range.len).for_each(|k| {
+                        let mut value: O = get_offset_by_index(src_buffer, k);
+                        value += offset_diff;
+                        set_offset_by_index(&mut new_offset, 0, value);
+                        move_bytes.extend_from_slice(&new_offset[..offset_size]);
+                    });
+                    last_offset_value = new_last_offset_value;
+                    next_offset_index += range.len;
+
+                    // Update the range value here, so next access is in the right
+                    // place
+                    range.index = first_offset.as_usize();
+                    range.len = added_unit_count.as_usize();
+                    total_move_len += range.len;
+                    Ok(())
+                })
+            })?;
+
+        range_actions.copy.0 = total_move_len;
+
+        Some(InnerMoveAction {
+            byte_offset: byte_size_after_remove,
+            data: move_bytes,
+        })
+    } else {
+        None
+    };
+
+    // CREATE ACTIONS
+    let create_actions = new_agents_buffer.map(|buffer| -> Result<_> {
+        // Arrow buffers are always sufficiently aligned
+        let src_buffer = unsafe { buffer.align_to::<O>().1 };
+        let mut total_create_len = 0;
+        let ret = range_actions
+            .create_mut()
+            .iter_mut()
+            .map(|range| {
+                // We take n + 1 values because we care about the lengths
+                let data = &src_buffer[range.index..=range.next_index()];
+                let first_offset = data[0];
+                let offset_shift = last_offset_value - first_offset;
+                // Don't include the first element, because that's incorporated in
+                // `offset_shift`
+                let action = O::create_action(&data[1..], offset_shift, next_offset_index);
+                let added_unit_count = data[data.len() - 1] - data[0];
+                last_offset_value = last_offset_value.checked_add(added_unit_count)?;
+                next_offset_index += range.len;
+                debug_assert_eq!(last_offset_value, {
+                    let mut last = data[data.len() - 1];
+                    last += offset_shift;
+                    last
+                });
+                // Update the range value here, so next access is in the right place
+                range.index = data[0].as_usize();
+                range.len = added_unit_count.as_usize();
+                total_create_len += range.len;
+
+                Ok(action)
+            })
+            .collect::<Result<_>>()?;
+        range_actions.create.0 = total_create_len;
+
+        Ok(ret)
+    });
+    let create_actions = create_actions.transpose()?;
+
+    // Final procedures:
+    debug_assert!(range_actions.is_well_ordered_remove());
+
+    // The target size is the same
+    debug_assert_eq!(target_buffer_size, next_offset_index * offset_size);
+
+    let variant = BufferActionVariant::Tweak {
+        shift: shift_actions,
+        copy: move_action,
+        create: create_actions,
+    };
+    Ok((variant, target_buffer_size))
+}
+
+fn get_offset_by_index<O: OffsetValue>(data: &[u8], index: usize) -> O {
+    let offset_size = mem::size_of::<O>();
+    let bytes = &data[index * offset_size..(index + 1) * offset_size];
+    // SAFETY: `bytes` is exactly `size_of::<O>()` long, alignment is not assumed
+    unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const O) }
+}
+
+fn set_offset_by_index<O: OffsetValue>(data: &mut [u8], index: usize, value: O) {
+    let offset_size = mem::size_of::<O>();
+    let bytes = &mut data[index * offset_size..(index + 1) * offset_size];
+    // SAFETY: `bytes` is exactly `size_of::<O>()` long, alignment is not assumed
+    unsafe { std::ptr::write_unaligned(bytes.as_mut_ptr() as *mut O, value) }
+}
+
+/// Moves `len` offsets starting at byte `from` to byte `to` inside `data`, decrementing every
+/// offset value by `decrement`.
+///
+/// The source and destination may overlap, so offsets are moved left to right when shifting left
+/// and right to left when shifting right.
+fn shift_offsets<O: OffsetValue>(
+    data: &mut [u8],
+    from: usize,
+    to: usize,
+    len: usize,
+    decrement: O,
+) {
+    if len == 0 || (from == to && decrement == O::default()) {
+        return;
+    }
+    let mut shift_one = |i: usize| {
+        let value: O = get_offset_by_index(&data[from..], i);
+        set_offset_by_index(&mut data[to..], i, value - decrement);
+    };
+    if to <= from {
+        (0..len).for_each(&mut shift_one);
+    } else {
+        (0..len).rev().for_each(&mut shift_one);
+    }
+}
 
-    // Assuming little endian
-    *(ptr as *const i32)
+/// Writes `offsets`, each incremented by `offset_shift`, into `data` starting at `base_index`.
+fn write_offsets<O: OffsetValue>(
+    data: &mut [u8],
+    base_index: usize,
+    offsets: &[O],
+    offset_shift: O,
+) {
+    offsets.iter().enumerate().for_each(|(i, offset_value)| {
+        let mut value = *offset_value;
+        value += offset_shift;
+        set_offset_by_index(data, base_index + i, value);
+    });
 }
 
 fn copy_bits_unchecked(
@@ -1308,10 +1428,16 @@ fn offsets_start_at_zero(
         meta.get_data_types().iter().for_each(|data_type| {
             match data_type {
                 super::super::meta::BufferType::BitMap { is_null_bitmap: _ } => {}
-                super::super::meta::BufferType::Offset => {
+                super::super::meta::BufferType::Offset
+                | super::super::meta::BufferType::LargeOffset => {
                     let buffer_meta = &dynamic_meta.buffers[buffer_index];
                     let offset = buffer_meta.offset;
-                    let first_offset = unsafe { *(&data[offset] as *const u8 as *const i32) };
+                    let first_offset = match data_type {
+                        super::super::meta::BufferType::Offset => {
+                            get_offset_by_index::<Offset>(&data[offset..], 0) as LargeOffset
+                        }
+                        _ => get_offset_by_index::<LargeOffset>(&data[offset..], 0),
+                    };
                     let offset_starts_at_zero = first_offset == 0;
                     if !offset_starts_at_zero {
                         println!(
@@ -1321,7 +1447,6 @@ fn offsets_start_at_zero(
                     }
                     starts_at_zero = starts_at_zero && offset_starts_at_zero;
                 }
-                super::super::meta::BufferType::LargeOffset => unimplemented!(),
                 _ => (),
             }
             buffer_index += 1;
@@ -1422,7 +1547,11 @@ pub(super) mod test {
     pub(super) mod test {
         use super::*;
         use crate::{
-            datastore::{schema::state::MessageSchema, test_utils::gen_schema_and_test_agents},
+            datastore::{
+                arrow::batch_conversion::agent_column_to_json_vals, schema::state::MessageSchema,
+                test_utils::gen_schema_and_test_agents,
+            },
+            hash_types::state::AgentStateField,
             simulation::package::creator::PREVIOUS_INDEX_FIELD_KEY,
         };
 
@@ -1799,5 +1928,91 @@ pub(super) mod test {
             assert_eq!(json_agents, new_json_agents);
             Ok(())
         }
+
+        #[test]
+        fn large_offset_columns_migrate() -> Result<()> {
+            let experiment_run_id = Arc::new("".to_string());
+            let remove_indices = [0, 3, 4, 11, 19];
+            let select_indices = [1, 4, 5, 9];
+            let num_create_agents = 6;
+
+            let (schema, json_agents) = gen_schema_and_test_agents(20, 0)?;
+            let (_, json_agents_2) = gen_schema_and_test_agents(10, 20)?;
+            let (_, json_create_agents) = gen_schema_and_test_agents(num_create_agents, 30)?;
+            let agents =
+                AgentBatch::from_agent_states(json_agents.as_slice(), &schema, &experiment_run_id)?;
+            let agents_2 = AgentBatch::from_agent_states(
+                json_agents_2.as_slice(),
+                &schema,
+                &experiment_run_id,
+            )?;
+            let create_agents = AgentBatch::from_agent_states(
+                json_create_agents.as_slice(),
+                &schema,
+                &experiment_run_id,
+            )?;
+
+            // The kept agents come first, then the copied ones and then the created ones
+            let column = |batch: &AgentBatch, name: &str| {
+                agent_column_to_json_vals(&batch.batch, &schema, name)
+            };
+            let names = [AgentStateField::AgentId.name(), "notes", "history"];
+            let mut expected = vec![];
+            for name in names {
+                let mut vals = column(&agents, name)?;
+                remove_indices.iter().rev().for_each(|i| {
+                    vals.remove(*i);
+                });
+                let copied = column(&agents_2, name)?;
+                vals.extend(select_indices.iter().map(|i| copied[*i].clone()));
+                vals.extend(column(&create_agents, name)?);
+                expected.push(vals);
+            }
+
+            let mut pool = vec![agents, agents_2];
+            let row_actions = RowActions {
+                remove: remove_indices
+                    .iter()
+                    .map(|i| RemoveAction { val: *i })
+                    .collect(),
+                copy: vec![(
+                    1,
+                    select_indices
+                        .iter()
+                        .map(|i| CopyAction { val: *i })
+                        .collect(),
+                )],
+                create: (0..num_create_agents)
+                    .map(|i| CreateAction { val: i })
+                    .collect(),
+            };
+            let buffer_actions = super::BufferActions::from(
+                &pool.iter().collect::<Vec<_>>(),
+                Some(0),
+                (&row_actions).into(),
+                &schema.static_meta,
+                Some(&create_agents.batch),
+            )?;
+            buffer_actions.flush(&mut pool[0])?;
+
+            assert_eq!(pool[0].num_agents(), 20 - 5 + 4 + num_create_agents);
+            for (name, expected) in names.into_iter().zip(expected) {
+                assert_eq!(column(&pool[0], name)?, expected, "column {name}");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn offsets_overflowing_i32_are_errors() {
+            assert_eq!(OffsetValue::checked_add(1_i32, 2).ok(), Some(3));
+            assert!(matches!(
+                OffsetValue::checked_add(Offset::MAX, 1),
+                Err(Error::OffsetOverflow)
+            ));
+            assert_eq!(
+                OffsetValue::checked_add(LargeOffset::from(Offset::MAX), 1).ok(),
+                Some(i64::from(Offset::MAX) + 1)
+            );
+        }
     }
 }
@@ -132,7 +132,7 @@ mod load {
         schema: Arc<ArrowSchema>,
     ) -> Result<RecordBatch> {
         let (_, _, _, data_buffer) = batch.memory().get_batch_buffers()?;
-        match read_record_batch(data_buffer, rb_msg, schema, &[]) {
+        match read_record_batch(data_buffer, rb_msg, schema) {
             Ok(rb) => Ok(rb.unwrap()),
             Err(e) => Err(Error::from(e)),
         }
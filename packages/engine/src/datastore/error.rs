@@ -54,6 +54,9 @@ pub enum Error {
     #[error("Invalid Arrow object downcast. Field name: {name}")]
     InvalidArrowDowncast { name: String },
 
+    #[error("{0:?} is not one of the categories of its field")]
+    UnknownCategory(String),
+
     #[error("Memory Error: {0}")]
     Memory(String),
 
@@ -78,12 +81,18 @@ pub enum Error {
     #[error("Shared memory error: {0}")]
     SharedMemory(#[from] shared_memory::ShmemError),
 
+    #[error("Memory-mapped file error: {0}")]
+    MemoryMappedFile(#[from] nix::Error),
+
     #[error("Failed to acquire lock ({0})")]
     Lock(&'static str),
 
     #[error("Agent id ({0}) is not a valid uuid")]
     InvalidAgentId(String),
 
+    #[error("Agent has no id, ids have to be assigned by the simulation run before conversion")]
+    MissingAgentId,
+
     #[error("Invalid index {ind} (len: {len})")]
     InvalidIndex { ind: usize, len: usize },
 
@@ -139,6 +148,13 @@ pub enum Error {
     #[error("Shmem max size reached: Size: {0}, Allowed: {1}")]
     SharedMemoryMaxSize(u64, u64),
 
+    #[error(
+        "A column with 32 bit offsets would hold more than {} elements or bytes, use \
+         \"large_string\"/\"large_list\" key types for columns this large",
+        i32::MAX
+    )]
+    OffsetOverflow,
+
     #[error("Expected Buffer actions to contain at least one action")]
     EmptyBufferActionsList,
 
@@ -177,11 +177,19 @@ unsafe fn node_into_prepared_array_data(
                     data_size = last_offset;
                     slice
                 }
+                crate::datastore::meta::BufferType::LargeOffset => {
+                    let byte_length = (num_elem + 1) * std::mem::size_of::<i64>();
+                    let slice = std::slice::from_raw_parts(ptr, byte_length);
+                    let last_offset_byte_index = byte_length - std::mem::size_of::<i64>();
+                    let last_offset =
+                        *(slice.as_ptr().add(last_offset_byte_index) as *const u64) as usize;
+                    data_size = last_offset;
+                    slice
+                }
                 crate::datastore::meta::BufferType::Data { unit_byte_size } => {
                     let byte_length = data_size * unit_byte_size;
                     std::slice::from_raw_parts(ptr, byte_length)
                 }
-                _ => unimplemented!(),
             };
 
             buffers.push(slice);
@@ -2,7 +2,7 @@
 
 use std::ffi::CStr;
 
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 
 use super::ArrowSchema;
 use crate::datastore::error::{Error, Result};
@@ -60,6 +60,20 @@ unsafe fn c_datatype_to_rust(c_field: &ArrowSchema) -> Result<DataType> {
         "Z" => DataType::LargeBinary,
         "u" => DataType::Utf8,
         "U" => DataType::LargeUtf8,
+        "tss" | "tsm" | "tsu" | "tsn" => {
+            let unit = match split[0] {
+                "tss" => TimeUnit::Second,
+                "tsm" => TimeUnit::Millisecond,
+                "tsu" => TimeUnit::Microsecond,
+                _ => TimeUnit::Nanosecond,
+            };
+            // The timezone follows the colon and is empty for timestamps without timezone
+            let timezone = split
+                .get(1)
+                .filter(|timezone| !timezone.is_empty())
+                .map(|timezone| (*timezone).to_string());
+            DataType::Timestamp(unit, timezone)
+        }
         "+l" => DataType::List(Box::new(c_datatype_to_rust(&**c_field.children)?)),
         "+w" => {
             let size = split
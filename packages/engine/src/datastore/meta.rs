@@ -209,7 +209,7 @@ pub enum BufferType {
     BitMap { is_null_bitmap: bool },
     /// This buffer contains i32 offsets
     Offset,
-    /// This buffer contains i64 offsets (currently not implemented)
+    /// This buffer contains i64 offsets
     LargeOffset,
     /// This buffer contains fixed-size (byte-level) data
     Data {
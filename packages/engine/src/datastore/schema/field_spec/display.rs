@@ -6,8 +6,15 @@ impl fmt::Debug for FieldTypeVariant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             FieldTypeVariant::Number => write!(f, "number"),
+            FieldTypeVariant::Integer => write!(f, "integer"),
+            FieldTypeVariant::UnsignedInteger => write!(f, "unsigned_integer"),
             FieldTypeVariant::Boolean => write!(f, "boolean"),
             FieldTypeVariant::String => write!(f, "string"),
+            FieldTypeVariant::LargeString => write!(f, "large_string"),
+            FieldTypeVariant::Categorical(categories) => {
+                write!(f, "categorical: {{categories: {:?}}}", categories)
+            }
+            FieldTypeVariant::Timestamp => write!(f, "timestamp"),
             FieldTypeVariant::AnyType => write!(f, "any"),
             FieldTypeVariant::FixedLengthArray { kind, len } => write!(
                 f,
@@ -15,6 +22,9 @@ impl fmt::Debug for FieldTypeVariant {
                 kind, len
             ),
             FieldTypeVariant::VariableLengthArray(v) => write!(f, "list: {{type: {:?}}}", v),
+            FieldTypeVariant::LargeVariableLengthArray(v) => {
+                write!(f, "large_list: {{type: {:?}}}", v)
+            }
             FieldTypeVariant::Struct(fields) => write!(f, "struct: {:?}", fields),
             FieldTypeVariant::Preset(_) => write!(f, "/hash_reserved_type/"),
         }
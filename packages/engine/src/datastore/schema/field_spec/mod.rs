@@ -44,11 +44,26 @@ pub enum PresetFieldType {
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub enum FieldTypeVariant {
     Number,
+    /// A 64 bit signed integer
+    Integer,
+    /// A 32 bit unsigned integer
+    UnsignedInteger,
     Boolean,
     String,
+    /// A string stored with 64 bit offsets, for columns holding more than 2 GiB of strings in a
+    /// single batch
+    LargeString,
+    /// One of a fixed list of strings, stored dictionary-encoded as the index of the string in the
+    /// list
+    Categorical(Vec<String>),
+    /// Milliseconds since the Unix epoch
+    Timestamp,
     AnyType,
     FixedLengthArray { kind: Box<FieldType>, len: usize },
     VariableLengthArray(Box<FieldType>),
+    /// A list stored with 64 bit offsets, for columns holding more than 2^31 elements in a single
+    /// batch
+    LargeVariableLengthArray(Box<FieldType>),
     Struct(Vec<FieldSpec>),
     Preset(PresetFieldType),
 }
@@ -64,6 +79,20 @@ impl FieldType {
     pub fn new(variant: FieldTypeVariant, nullable: bool) -> FieldType {
         FieldType { variant, nullable }
     }
+
+    /// Whether the type or any of its nested types is stored with 64 bit offsets, which the
+    /// JavaScript runner can't read.
+    pub fn has_large_offsets(&self) -> bool {
+        match &self.variant {
+            FieldTypeVariant::LargeString | FieldTypeVariant::LargeVariableLengthArray(_) => true,
+            FieldTypeVariant::FixedLengthArray { kind, .. } => kind.has_large_offsets(),
+            FieldTypeVariant::VariableLengthArray(inner) => inner.has_large_offsets(),
+            FieldTypeVariant::Struct(fields) => fields
+                .iter()
+                .any(|field| field.field_type.has_large_offsets()),
+            _ => false,
+        }
+    }
 }
 
 /// Defines scope of access to Fields, where the order of the variants of this enum define an
@@ -98,6 +98,12 @@ impl FieldType {
             };
             match &**key {
                 "number" => Ok(FieldType::new(FieldTypeVariant::Number, is_nullable)),
+                "integer" => Ok(FieldType::new(FieldTypeVariant::Integer, is_nullable)),
+                "unsigned_integer" => Ok(FieldType::new(
+                    FieldTypeVariant::UnsignedInteger,
+                    is_nullable,
+                )),
+                "timestamp" => Ok(FieldType::new(FieldTypeVariant::Timestamp, is_nullable)),
                 "boolean" => Ok(FieldType::new(FieldTypeVariant::Boolean, is_nullable)),
                 "string" => Ok(FieldType::new(FieldTypeVariant::String, is_nullable)),
                 "any" => {
@@ -234,6 +240,9 @@ mod tests {
             "keys": {
                 "xyz": "string",
                 "switch": "boolean",
+                "count": "integer",
+                "size": "unsigned_integer?",
+                "born": "timestamp?",
                 "nullableSwitch": "boolean?",
                 "complex": {
                     "position": "[number; 2]",
@@ -248,7 +257,7 @@ mod tests {
         });
         let key_set = FieldSpecMap::from_short_json(json, FieldSource::Engine, FieldScope::Agent)
             .expect("KeySet should be able to be created from this JSON");
-        // 10 not 9 because we have to special __previous_index field in there too
-        assert_eq!(key_set.len(), 10);
+        // 13 not 12 because we have to special __previous_index field in there too
+        assert_eq!(key_set.len(), 13);
     }
 }
@@ -0,0 +1,183 @@
+use std::{
+    fs::{File, OpenOptions},
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+    ptr::NonNull,
+};
+
+use nix::sys::mman::{mmap, munmap, MapFlags, ProtFlags};
+
+use crate::datastore::prelude::*;
+
+/// A memory-mapped file, used as an alternative to shared memory segments in `/dev/shm`.
+///
+/// The mapping is shared, so other processes mapping the same file (e.g. runners) see every write.
+/// Like a shared memory segment, the file can be resized by any process, after which other
+/// processes have to [`reload`](Self::reload) their mapping.
+pub struct FileSegment {
+    file: File,
+    path: PathBuf,
+    ptr: NonNull<u8>,
+    len: usize,
+    /// Whether the file is removed when the segment is dropped
+    droppable: bool,
+}
+
+// The mapping is only accessed through `Memory`, which upholds the same guarantees as for shared
+// memory segments.
+unsafe impl Send for FileSegment {}
+unsafe impl Sync for FileSegment {}
+
+#[allow(clippy::len_without_is_empty)]
+impl FileSegment {
+    /// Creates a new file at `path` with a size of `len` bytes and maps it.
+    pub fn create(path: PathBuf, len: usize, droppable: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        file.set_len(len as u64)?;
+        let ptr = Self::map(&file, len)?;
+        Ok(Self {
+            file,
+            path,
+            ptr,
+            len,
+            droppable,
+        })
+    }
+
+    /// Maps an existing file at `path`, e.g. one created by another process.
+    ///
+    /// The file is only removed when the segment is dropped if `droppable` is set.
+    pub fn open(path: &Path, droppable: bool) -> Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len() as usize;
+        let ptr = Self::map(&file, len)?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            ptr,
+            len,
+            droppable,
+        })
+    }
+
+    fn map(file: &File, len: usize) -> Result<NonNull<u8>> {
+        // SAFETY: We don't request a specific address and `len` is non-zero, as validated by
+        //   `Memory`.
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                MapFlags::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )?
+        };
+        NonNull::new(ptr as *mut u8).ok_or_else(|| Error::from("mmap returned a null pointer"))
+    }
+
+    fn unmap_current(&mut self) -> Result<()> {
+        // SAFETY: `ptr` and `len` describe the current mapping
+        unsafe { munmap(self.ptr.as_ptr() as *mut _, self.len)? };
+        Ok(())
+    }
+
+    fn remap(&mut self, len: usize) -> Result<()> {
+        self.unmap_current()?;
+        self.ptr = Self::map(&self.file, len)?;
+        self.len = len;
+        Ok(())
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+
+    /// Resizes the file and remaps it
+    pub fn resize(&mut self, new_len: usize) -> Result<()> {
+        self.file.set_len(new_len as u64)?;
+        self.remap(new_len)
+    }
+
+    /// Remaps the file if it was resized by another process
+    pub fn reload(&mut self) -> Result<()> {
+        let len = self.file.metadata()?.len() as usize;
+        if len != self.len {
+            self.remap(len)?;
+        }
+        Ok(())
+    }
+
+    /// Unmaps the file without removing it.
+    pub fn unmap(mut self) {
+        self.droppable = false;
+    }
+}
+
+impl Drop for FileSegment {
+    fn drop(&mut self) {
+        if let Err(err) = self.unmap_current() {
+            log::warn!("Could not unmap {:?}: {}", self.path, err);
+        }
+        if self.droppable {
+            if let Err(err) = std::fs::remove_file(&self.path) {
+                log::warn!("Could not remove {:?}: {}", self.path, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_resize_and_reload() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("shm_test_{}", rand::random::<u32>()));
+        let mut created = FileSegment::create(path.clone(), 64, true)?;
+        let mut opened = FileSegment::open(&path, false)?;
+        unsafe { *created.as_ptr() = 42 };
+        assert_eq!(unsafe { *opened.as_ptr() }, 42);
+
+        created.resize(4096)?;
+        unsafe { *created.as_ptr().add(4095) = 7 };
+        opened.reload()?;
+        assert_eq!(opened.len(), 4096);
+        assert_eq!(unsafe { *opened.as_ptr().add(4095) }, 7);
+
+        drop(opened);
+        assert!(path.exists());
+        drop(created);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_open_droppable() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("shm_test_{}", rand::random::<u32>()));
+        let created = FileSegment::create(path.clone(), 64, false)?;
+        let opened = FileSegment::open(&path, true)?;
+
+        drop(created);
+        assert!(path.exists());
+        drop(opened);
+        assert!(!path.exists());
+        Ok(())
+    }
+}
@@ -1,11 +1,10 @@
 #![allow(clippy::similar_names)]
 
-use std::{env, os::unix::io::RawFd, path::Path};
-
-use shared_memory::{Shmem, ShmemConf};
+use std::{env, os::unix::io::RawFd};
 
 use super::{
     ptr::MemoryPtr,
+    segment::Segment,
     visitor::{Visit, Visitor, VisitorMut},
     BufferChange,
 };
@@ -15,6 +14,9 @@ pub type Buffers<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
 
 /// A memory-mapped shared memory segment wrapper.
 ///
+/// The segment is either a shared memory segment or a memory-mapped file, depending on the global
+/// [`Backend`](super::segment::Backend).
+///
 /// Includes tools to work with internal strucure.
 ///
 /// ### Internal Buffers
@@ -28,7 +30,7 @@ pub type Buffers<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
 /// small buffer which contains the markers to the four buffers
 /// above. This offset buffer can be read with `Memory::markers`
 pub struct Memory {
-    pub data: Shmem,
+    pub data: Segment,
     pub size: usize,
     include_terminal_padding: bool,
 }
@@ -108,10 +110,12 @@ impl Memory {
         include_terminal_padding: bool,
     ) -> Result<Memory> {
         Self::validate_size(size)?;
-        let data = ShmemConf::new(droppable)
-            .os_id(Self::generate_shmem_id(experiment_run_id))
-            .size(size)
-            .create()?;
+        let data = Segment::create(
+            experiment_run_id,
+            &Self::generate_shmem_id(experiment_run_id),
+            size,
+            droppable,
+        )?;
         Ok(Memory {
             data,
             size,
@@ -124,21 +128,14 @@ impl Memory {
         droppable: bool,
         include_terminal_padding: bool,
     ) -> Result<Memory> {
-        if message.contains("/shm_") {
-            let id = &message;
-            let data = ShmemConf::new(droppable).os_id(id).open()?;
-            let size = data.len();
-            Self::validate_size(size)?;
-            Ok(Memory {
-                data,
-                size,
-                include_terminal_padding,
-            })
-        } else {
-            Err(Error::Memory(
-                "Expected message to contain \"/shm_\"".into(),
-            ))
-        }
+        let data = Segment::open(message, droppable)?;
+        let size = data.len();
+        Self::validate_size(size)?;
+        Ok(Memory {
+            data,
+            size,
+            include_terminal_padding,
+        })
     }
 
     fn visitor(&self) -> Visitor<'_> {
@@ -151,10 +148,12 @@ impl Memory {
 
     pub fn duplicate_from(memory: &Memory, experiment_run_id: &str) -> Result<Memory> {
         let shmem = &memory.data;
-        let data = ShmemConf::new(true)
-            .os_id(Self::generate_shmem_id(experiment_run_id))
-            .size(memory.size)
-            .create()?;
+        let data = Segment::create(
+            experiment_run_id,
+            &Self::generate_shmem_id(experiment_run_id),
+            memory.size,
+            true,
+        )?;
         unsafe { std::ptr::copy_nonoverlapping(shmem.as_ptr(), data.as_ptr(), memory.size) };
         Ok(Memory {
             data,
@@ -163,29 +162,35 @@ impl Memory {
         })
     }
 
+    /// The start of the names of all segments created for the experiment run
+    pub fn segment_name_prefix(experiment_run_id: &str) -> String {
+        format!("shm_{:.20}_", experiment_run_id)
+    }
+
     fn generate_shmem_id(experiment_run_id: &str) -> String {
         loop {
             // MacOS shmem seems to be limited to 31 chars, probably remnants of HFS
             let cur_id = format!(
-                "/shm_{:.20}_{:.6}",
-                experiment_run_id,
+                "/{}{:.6}",
+                Self::segment_name_prefix(experiment_run_id),
                 rand::random::<u16>()
             );
-            if !Path::new(&format!("/dev/shm{}", cur_id)).exists() {
+            if !Segment::exists(&cur_id) {
                 return cur_id;
             }
         }
     }
 
     fn validate_size(size: usize) -> Result<()> {
-        // This comes from the fact we use List and *not* LargeList Arrow types
-        // List markers are i32 type, while LargeList markers are i64 type
+        // Offsets and lengths of buffers in the Arrow metadata are i64. Columns with i32 offsets
+        // (List and Utf8) are limited to 2 GiB each, which is checked when they grow, but
+        // LargeList and LargeUtf8 columns can be larger
         if size == 0 {
             return Err(Error::EmptySharedMemory);
         }
 
-        if size as u64 > i32::MAX as u64 {
-            return Err(Error::SharedMemoryMaxSize(size as u64, i32::MAX as u64));
+        if size as u64 > i64::MAX as u64 {
+            return Err(Error::SharedMemoryMaxSize(size as u64, i64::MAX as u64));
         }
         Ok(())
     }
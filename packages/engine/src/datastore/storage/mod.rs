@@ -1,6 +1,9 @@
+mod file;
 mod markers;
 pub mod memory;
 mod ptr;
+pub mod registry;
+pub mod segment;
 mod visitor;
 pub struct BufferChange(bool, bool);
 
@@ -0,0 +1,114 @@
+//! Keeps track of the memory segments created by this process, so the segments of an experiment
+//! run can be removed when it doesn't shut down cleanly.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::datastore::prelude::*;
+
+lazy_static! {
+    /// Segment IDs by the ID of the experiment run which created them
+    static ref SEGMENTS: Mutex<HashMap<String, HashSet<String>>> = Mutex::new(HashMap::new());
+}
+
+pub(super) fn register(experiment_run_id: &str, segment_id: &str) {
+    SEGMENTS
+        .lock()
+        .entry(experiment_run_id.to_string())
+        .or_default()
+        .insert(segment_id.to_string());
+}
+
+pub(super) fn unregister(experiment_run_id: &str, segment_id: &str) {
+    let mut segments = SEGMENTS.lock();
+    if let Some(ids) = segments.get_mut(experiment_run_id) {
+        ids.remove(segment_id);
+        if ids.is_empty() {
+            segments.remove(experiment_run_id);
+        }
+    }
+}
+
+/// Removes all segments registered for `experiment_run_id` which still exist and returns how many
+/// were removed.
+///
+/// Segments still mapped by this process stay accessible until they are unmapped, but can't be
+/// opened by other processes anymore.
+pub fn remove_segments(experiment_run_id: &str) -> usize {
+    let ids = SEGMENTS
+        .lock()
+        .remove(experiment_run_id)
+        .unwrap_or_default();
+    ids.iter()
+        .filter(|id| match remove_segment(id) {
+            Ok(removed) => removed,
+            Err(err) => {
+                log::warn!("Could not remove memory segment {id}: {err}");
+                false
+            }
+        })
+        .count()
+}
+
+/// Removes the segment with the ID `id`, which is either a shared memory ID or the path to a
+/// memory-mapped file, and returns whether it existed.
+pub fn remove_segment(id: &str) -> Result<bool> {
+    let path = Path::new(id);
+    if path.is_file() {
+        std::fs::remove_file(path)?;
+        return Ok(true);
+    }
+    if !id.starts_with("/shm_") {
+        // A memory-mapped file which was already removed
+        return Ok(false);
+    }
+    match nix::sys::mman::shm_unlink(id) {
+        Ok(()) => Ok(true),
+        Err(nix::errno::Errno::ENOENT) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_files_are_removed() -> Result<()> {
+        let run_id = format!("registry_test_{}", rand::random::<u32>());
+        let other_run_id = format!("{run_id}_other");
+        let directory = std::env::temp_dir().join(&run_id);
+        std::fs::create_dir_all(&directory)?;
+        // Memory-mapped files of the file backend, whose IDs are their paths
+        let segment = |name: &str| -> Result<String> {
+            let path = directory.join(name);
+            std::fs::write(&path, [0; 64])?;
+            Ok(path.to_string_lossy().into_owned())
+        };
+        let first = segment("first")?;
+        let unregistered = segment("unregistered")?;
+        let other = segment("other")?;
+        register(&run_id, &first);
+        register(&run_id, &unregistered);
+        unregister(&run_id, &unregistered);
+        register(&other_run_id, &other);
+        // Segments which were already removed aren't counted
+        register(&run_id, &directory.join("removed").to_string_lossy());
+
+        assert_eq!(remove_segments(&run_id), 1);
+        assert!(!Path::new(&first).exists());
+        assert!(Path::new(&unregistered).exists());
+        assert!(Path::new(&other).exists());
+        // The segments of an experiment run are only removed once
+        assert_eq!(remove_segments(&run_id), 0);
+        assert_eq!(remove_segments(&other_run_id), 1);
+
+        std::fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    lazy::SyncOnceCell,
+    os::unix::io::RawFd,
+    path::{Path, PathBuf},
+};
+
+use shared_memory::{Shmem, ShmemConf};
+
+use super::{file::FileSegment, registry};
+use crate::datastore::prelude::*;
+
+static BACKEND: SyncOnceCell<Backend> = SyncOnceCell::new();
+
+/// Where new memory segments are created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// Shared memory segments in `/dev/shm`
+    SharedMemory,
+    /// Memory-mapped files in the given directory
+    File { directory: PathBuf },
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::SharedMemory
+    }
+}
+
+impl Backend {
+    /// Sets the backend for all segments created by this process.
+    ///
+    /// Can only be set once and has to be set before the first segment is created, otherwise
+    /// [`Backend::SharedMemory`] is used.
+    pub fn set_global(backend: Backend) -> Result<()> {
+        if let Backend::File { directory } = &backend {
+            std::fs::create_dir_all(directory)?;
+        }
+        BACKEND
+            .set(backend)
+            .map_err(|backend| Error::from(format!("Memory backend already set: {backend:?}")))
+    }
+
+    pub fn global() -> &'static Backend {
+        BACKEND.get_or_init(Backend::default)
+    }
+}
+
+/// The path of the memory-mapped file for the segment ID `id` in `directory`
+fn file_path(directory: &Path, id: &str) -> PathBuf {
+    // IDs are absolute names like `/shm_<...>`, which would replace `directory` when joined
+    directory.join(id.trim_start_matches('/'))
+}
+
+/// The memory a [`Memory`](super::memory::Memory) is stored in.
+pub struct Segment {
+    /// Only `None` after the segment was unmapped
+    kind: Option<SegmentKind>,
+    /// The experiment run which created the segment, if the segment is removed when dropped
+    owner: Option<String>,
+}
+
+enum SegmentKind {
+    Shared(Shmem),
+    File(FileSegment),
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl Segment {
+    /// Creates a new segment with the given ID in the global [`Backend`] and registers it for the
+    /// experiment run.
+    pub fn create(
+        experiment_run_id: &str,
+        id: &str,
+        size: usize,
+        droppable: bool,
+    ) -> Result<Segment> {
+        let kind = match Backend::global() {
+            Backend::SharedMemory => {
+                SegmentKind::Shared(ShmemConf::new(droppable).os_id(id).size(size).create()?)
+            }
+            Backend::File { directory } => SegmentKind::File(FileSegment::create(
+                file_path(directory, id),
+                size,
+                droppable,
+            )?),
+        };
+        let segment = Segment {
+            kind: Some(kind),
+            owner: droppable.then(|| experiment_run_id.to_string()),
+        };
+        registry::register(experiment_run_id, segment.get_os_id());
+        Ok(segment)
+    }
+
+    /// Opens an existing segment in the global [`Backend`] by the ID returned from
+    /// [`get_os_id`](Self::get_os_id), which is either a shared memory ID or the path to a
+    /// memory-mapped file. The segment is removed when dropped if `droppable` is set.
+    pub fn open(id: &str, droppable: bool) -> Result<Segment> {
+        let kind = match Backend::global() {
+            Backend::SharedMemory => {
+                SegmentKind::Shared(ShmemConf::new(droppable).os_id(id).open()?)
+            }
+            Backend::File { .. } => {
+                SegmentKind::File(FileSegment::open(Path::new(id), droppable).map_err(|err| {
+                    Error::Memory(format!("Could not open memory-mapped file \"{id}\": {err}"))
+                })?)
+            }
+        };
+        Ok(Segment {
+            kind: Some(kind),
+            owner: None,
+        })
+    }
+
+    /// Returns whether a segment with the given ID exists in the global [`Backend`].
+    pub fn exists(id: &str) -> bool {
+        match Backend::global() {
+            Backend::SharedMemory => Path::new(&format!("/dev/shm{id}")).exists(),
+            Backend::File { directory } => file_path(directory, id).exists(),
+        }
+    }
+
+    fn kind(&self) -> &SegmentKind {
+        self.kind.as_ref().expect("Segment was unmapped")
+    }
+
+    fn kind_mut(&mut self) -> &mut SegmentKind {
+        self.kind.as_mut().expect("Segment was unmapped")
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        match self.kind() {
+            SegmentKind::Shared(shmem) => shmem.as_ptr(),
+            SegmentKind::File(file) => file.as_ptr(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self.kind() {
+            SegmentKind::Shared(shmem) => shmem.len(),
+            SegmentKind::File(file) => file.len(),
+        }
+    }
+
+    pub fn resize(&mut self, new_size: usize) -> Result<()> {
+        match self.kind_mut() {
+            SegmentKind::Shared(shmem) => shmem.resize(new_size)?,
+            SegmentKind::File(file) => file.resize(new_size)?,
+        }
+        Ok(())
+    }
+
+    pub fn reload(&mut self) -> Result<()> {
+        match self.kind_mut() {
+            SegmentKind::Shared(shmem) => shmem.reload()?,
+            SegmentKind::File(file) => file.reload()?,
+        }
+        Ok(())
+    }
+
+    pub fn raw_fd(&self) -> RawFd {
+        match self.kind() {
+            SegmentKind::Shared(shmem) => shmem.raw_fd(),
+            SegmentKind::File(file) => file.raw_fd(),
+        }
+    }
+
+    /// The ID other processes use to open this segment
+    pub fn get_os_id(&self) -> &str {
+        match self.kind() {
+            SegmentKind::Shared(shmem) => shmem.get_os_id(),
+            // Paths are created from valid UTF-8 IDs
+            SegmentKind::File(file) => file.path().to_str().unwrap_or_default(),
+        }
+    }
+
+    /// Unmaps the segment without removing it, so it stays registered for its experiment run.
+    pub fn unmap(mut self) {
+        match self.kind.take() {
+            Some(SegmentKind::Shared(shmem)) => shmem.unmap(),
+            Some(SegmentKind::File(file)) => file.unmap(),
+            None => {}
+        }
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        // Droppable segments are removed together with their mapping
+        if let (Some(owner), Some(_)) = (&self.owner, &self.kind) {
+            registry::unregister(owner, self.get_os_id());
+        }
+    }
+}
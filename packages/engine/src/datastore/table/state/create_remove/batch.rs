@@ -9,9 +9,10 @@ pub struct BaseBatch {
     index: BatchIndex,
     /// Current Worker index
     worker: WorkerIndex,
+    /// Rows which are removed from the batch, in ascending order
     remove_indices: Vec<AgentIndex>,
     /// Number of agents in the batch (before any changes)
-    _num_agents: usize, // TODO: unused, delete?
+    old_num_agents: usize,
 }
 
 /// Represents a batch of agents from the dynamic pool
@@ -23,6 +24,8 @@ pub struct PendingBatch {
     base: Option<BaseBatch>,
     /// To-be-added agents
     num_inbound: usize,
+    /// Agents copied from other batches, by the index of the batch they are copied from
+    copy_indices: Vec<(BatchIndex, Vec<AgentIndex>)>,
     /// Total count that will be after commiting changes
     num_agents: usize,
 }
@@ -32,6 +35,7 @@ impl PendingBatch {
         PendingBatch {
             base: old_batch,
             num_inbound: 0,
+            copy_indices: Vec::new(),
             num_agents,
         }
     }
@@ -56,12 +60,13 @@ impl PendingBatch {
             index: batch_index,
             worker: batch.affinity,
             remove_indices,
-            _num_agents: batch.num_agents(),
+            old_num_agents: batch.num_agents(),
         };
 
         Ok(PendingBatch {
             base: Some(old_batch),
             num_inbound: 0,
+            copy_indices: Vec::new(),
             num_agents: batch.num_agents() - remove_indices_len,
         })
     }
@@ -71,6 +76,40 @@ impl PendingBatch {
         self.increment_num_agents(count);
     }
 
+    /// Adds agents of the batch at `batch_index`, which are copied into this batch.
+    pub fn add_copy_indices(&mut self, batch_index: BatchIndex, indices: Vec<AgentIndex>) {
+        self.increment_num_agents(indices.len());
+        self.copy_indices.push((batch_index, indices));
+    }
+
+    /// Removes the last `count` agents which are kept in the batch, so they can be copied into
+    /// other batches, and returns the index of the batch with their indices.
+    pub fn take_outbound(&mut self, count: usize) -> (BatchIndex, Vec<AgentIndex>) {
+        debug_assert!(count <= self.num_agents);
+        debug_assert_eq!(self.num_inbound, 0);
+        let base = self
+            .base
+            .as_mut()
+            .expect("Only existing batches have agents to take");
+        let removed = base
+            .remove_indices
+            .iter()
+            .map(|index| index.val)
+            .collect::<HashSet<_>>();
+        let mut outbound = (0..base.old_num_agents)
+            .rev()
+            .filter(|row_index| !removed.contains(row_index))
+            .take(count)
+            .map(|row_index| AgentIndex { val: row_index })
+            .collect::<Vec<_>>();
+        outbound.reverse();
+
+        base.remove_indices.extend(outbound.iter().cloned());
+        base.remove_indices.sort();
+        self.num_agents -= count;
+        (base.index, outbound)
+    }
+
     fn increment_num_agents(&mut self, value: usize) {
         self.num_agents += value;
     }
@@ -83,6 +122,17 @@ impl PendingBatch {
         self.num_inbound
     }
 
+    pub fn num_copied(&self) -> usize {
+        self.copy_indices
+            .iter()
+            .map(|(_, indices)| indices.len())
+            .sum()
+    }
+
+    pub fn get_copy_actions(&self) -> &[(BatchIndex, Vec<AgentIndex>)] {
+        &self.copy_indices
+    }
+
     pub fn num_delete_unchecked(&self) -> usize {
         self.base.as_ref().unwrap().remove_indices.len()
     }
@@ -98,6 +148,10 @@ impl PendingBatch {
         self.base.as_ref().unwrap().worker
     }
 
+    pub fn old_worker(&self) -> Option<WorkerIndex> {
+        self.base.as_ref().map(|b| b.worker)
+    }
+
     pub fn old_batch_index_unchecked(&self) -> usize {
         self.base.as_ref().unwrap().index
     }
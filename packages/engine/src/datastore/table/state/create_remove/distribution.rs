@@ -8,21 +8,29 @@ use super::{super::*, batch::PendingBatch, WorkerIndex};
 #[derive(Debug)]
 pub struct BatchDistribution {
     inner: Vec<Vec<PendingBatch>>,
-    _num_workers: usize, // TODO: unused, delete?
 }
 
 impl BatchDistribution {
-    pub fn new(num_workers: usize, current_batches: Vec<PendingBatch>) -> BatchDistribution {
+    pub fn new(
+        num_workers: usize,
+        current_batches: Vec<PendingBatch>,
+    ) -> Result<BatchDistribution> {
         let mut inner = vec![vec![]; num_workers];
 
         for batch in current_batches {
-            inner[batch.old_worker_unchecked()].push(batch);
+            let worker_index = batch.old_worker_unchecked();
+            inner
+                .get_mut(worker_index)
+                .ok_or_else(|| {
+                    Error::from(format!(
+                        "Batch is assigned to worker {worker_index}, but the simulation run only \
+                         has {num_workers} workers"
+                    ))
+                })?
+                .push(batch);
         }
 
-        BatchDistribution {
-            inner,
-            _num_workers: num_workers,
-        }
+        Ok(BatchDistribution { inner })
     }
 
     // TODO: these are unused
@@ -65,10 +73,14 @@ impl BatchDistribution {
     /// Given an inbound-to-worker distribution of agents,
     /// distribute each inbound group into the batches
     /// which may or may not already exist.
-    pub fn set_batch_level_inbounds(&mut self, worker_level_inbound: Vec<usize>) -> Result<()> {
-        // Attempt to keep all batch sizes (number of agents) below this value
-        const UPPER_BOUND: usize = 100000;
-
+    ///
+    /// New batches are created when the batches of a worker would have more than `upper_bound`
+    /// agents on average.
+    pub fn set_batch_level_inbounds(
+        &mut self,
+        worker_level_inbound: Vec<usize>,
+        upper_bound: usize,
+    ) -> Result<()> {
         self.inner
             .iter_mut()
             .zip(worker_level_inbound.into_iter())
@@ -82,10 +94,10 @@ impl BatchDistribution {
                 } else {
                     0
                 };
-                if average_total_num_agents_per_batch > UPPER_BOUND {
+                if average_total_num_agents_per_batch > upper_bound {
                     // Create more pending batches, as inbound count is large
                     let target_number_batches =
-                        ((total_num_agents as f64) / (UPPER_BOUND as f64)).ceil() as usize;
+                        ((total_num_agents as f64) / (upper_bound as f64)).ceil() as usize;
                     (0..target_number_batches - batches.len()).for_each(|_| {
                         current_distribution.push(0);
                         batches.push(PendingBatch::new(None, 0));
@@ -110,6 +122,88 @@ impl BatchDistribution {
         get_inbound_distribution(&current_distribution, number_inbound)
     }
 
+    /// Rebalances the batches instead of only distributing `number_inbound` agents across them.
+    ///
+    /// The agents are spread evenly across as many batches as needed to keep them at
+    /// `target_batch_size` agents or below, with at least one batch per worker. The largest
+    /// batches are kept and the agents of the others are copied into them, as are the agents over
+    /// the new size of a kept batch. The batches are then spread evenly across the workers,
+    /// keeping batches at their worker where possible.
+    pub fn compact(&mut self, number_inbound: usize, target_batch_size: usize) -> Result<()> {
+        let num_workers = self.inner.len();
+        let mut batches = self.inner.drain(..).flatten().collect::<Vec<_>>();
+        self.inner = vec![vec![]; num_workers];
+        let total_num_agents =
+            batches.iter().map(PendingBatch::num_agents).sum::<usize>() + number_inbound;
+        let batch_sizes =
+            get_compacted_batch_sizes(total_num_agents, num_workers, target_batch_size);
+
+        // Sorting is stable, so batches of the same size keep their order
+        batches.sort_by(|a, b| b.num_agents().cmp(&a.num_agents()));
+        let num_kept = batches.len().min(batch_sizes.len());
+        let mut dissolved = batches.split_off(num_kept);
+        batches.resize_with(batch_sizes.len(), || PendingBatch::new(None, 0));
+
+        let mut outbound = Vec::new();
+        for (batch, &size) in batches.iter_mut().zip(&batch_sizes) {
+            if batch.num_agents() > size {
+                outbound.push(batch.take_outbound(batch.num_agents() - size));
+            }
+        }
+        for batch in &mut dissolved {
+            let num_agents = batch.num_agents();
+            if num_agents > 0 {
+                outbound.push(batch.take_outbound(num_agents));
+            }
+        }
+
+        let mut inbound_left = number_inbound;
+        let mut outbound = outbound.into_iter().flat_map(|(batch_index, indices)| {
+            indices
+                .into_iter()
+                .map(move |agent_index| (batch_index, agent_index))
+        });
+        for (batch, &size) in batches.iter_mut().zip(&batch_sizes) {
+            let mut missing = size - batch.num_agents();
+            let num_inbound = missing.min(inbound_left);
+            batch.add_inbound_count(num_inbound);
+            inbound_left -= num_inbound;
+            missing -= num_inbound;
+
+            let mut copied: Vec<(usize, Vec<_>)> = Vec::new();
+            for (batch_index, agent_index) in outbound.by_ref().take(missing) {
+                match copied.last_mut() {
+                    Some((last_index, indices)) if *last_index == batch_index => {
+                        indices.push(agent_index);
+                    }
+                    _ => copied.push((batch_index, vec![agent_index])),
+                }
+            }
+            copied
+                .into_iter()
+                .for_each(|(batch_index, indices)| batch.add_copy_indices(batch_index, indices));
+        }
+        debug_assert_eq!(inbound_left, 0);
+        debug_assert!(outbound.next().is_none());
+
+        let workers = assign_workers(
+            &batches
+                .iter()
+                .map(PendingBatch::old_worker)
+                .collect::<Vec<_>>(),
+            num_workers,
+        );
+        for (batch, worker_index) in batches.into_iter().zip(workers) {
+            self.inner[worker_index].push(batch);
+        }
+        // Dissolved batches are left empty, so they are removed
+        for batch in dissolved {
+            let worker_index = batch.old_worker_unchecked();
+            self.inner[worker_index].push(batch);
+        }
+        Ok(())
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (WorkerIndex, &PendingBatch)> {
         self.inner
             .iter()
@@ -120,6 +214,60 @@ impl BatchDistribution {
     }
 }
 
+/// Sizes of the batches `total_num_agents` agents are spread across when compacting, such that no
+/// batch is larger than `target_batch_size` and every worker has a batch, if there are enough
+/// agents.
+fn get_compacted_batch_sizes(
+    total_num_agents: usize,
+    num_workers: usize,
+    target_batch_size: usize,
+) -> Vec<usize> {
+    if total_num_agents == 0 {
+        return Vec::new();
+    }
+    let num_batches = ((total_num_agents + target_batch_size - 1) / target_batch_size)
+        .max(num_workers)
+        .min(total_num_agents);
+    let base_size = total_num_agents / num_batches;
+    let remainder = total_num_agents % num_batches;
+    (0..num_batches)
+        .map(|batch_index| base_size + usize::from(batch_index < remainder))
+        .collect()
+}
+
+/// Assigns batches to workers, given the workers they are currently assigned to (`None` for new
+/// batches), such that the number of batches per worker differs by at most one. Batches are kept
+/// at their current worker where possible.
+fn assign_workers(current_workers: &[Option<WorkerIndex>], num_workers: usize) -> Vec<WorkerIndex> {
+    let min_batches_per_worker = current_workers.len() / num_workers;
+    let mut num_batches = vec![0; num_workers];
+    let mut workers = current_workers
+        .iter()
+        .map(|worker| {
+            worker.filter(|&worker_index| {
+                let keep = num_batches[worker_index] < min_batches_per_worker;
+                if keep {
+                    num_batches[worker_index] += 1;
+                }
+                keep
+            })
+        })
+        .collect::<Vec<_>>();
+    for (worker, current_worker) in workers.iter_mut().zip(current_workers) {
+        if worker.is_none() {
+            // Prefer the current worker amongst the ones with the fewest batches
+            let fewest = *num_batches.iter().min().unwrap_or(&0);
+            let worker_index = current_worker
+                .filter(|&worker_index| num_batches[worker_index] == fewest)
+                .or_else(|| num_batches.iter().position(|&n| n == fewest))
+                .unwrap_or(0);
+            num_batches[worker_index] += 1;
+            *worker = Some(worker_index);
+        }
+    }
+    workers.into_iter().flatten().collect()
+}
+
 /// Given a discrete distribution of objects, get the distribution which would
 /// distribute `number_inbound` objects such that the result would be as even as possible
 fn get_inbound_distribution(
@@ -199,3 +347,104 @@ fn get_inbound_distribution(
     }
     Ok(inbound_distribution)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use super::{super::AgentIndex, *};
+    use crate::datastore::test_utils::gen_schema_and_test_agents;
+
+    /// Creates the pending batch of a batch of `num_agents` agents at `worker`, whose agents at
+    /// `remove_rows` are removed.
+    fn pending_batch(
+        batch_index: usize,
+        worker: WorkerIndex,
+        num_agents: usize,
+        remove_rows: &[usize],
+    ) -> Result<PendingBatch> {
+        let (schema, agents) = gen_schema_and_test_agents(num_agents, batch_index as u64 * 100)?;
+        let mut batch =
+            AgentBatch::from_agent_states(agents.as_slice(), &schema, &Arc::new(String::new()))?;
+        batch.set_affinity(worker);
+        let mut remove_ids = batch
+            .agent_id_iter()?
+            .enumerate()
+            .filter(|(row, _)| remove_rows.contains(row))
+            .map(|(_, id)| *id)
+            .collect::<HashSet<_>>();
+        PendingBatch::from_batch(batch_index, &batch, &mut remove_ids)
+    }
+
+    fn rows(indices: &[AgentIndex]) -> Vec<usize> {
+        indices.iter().map(|index| index.val).collect()
+    }
+
+    #[test]
+    fn compacted_batch_sizes() {
+        assert_eq!(get_compacted_batch_sizes(0, 4, 100), Vec::<usize>::new());
+        assert_eq!(get_compacted_batch_sizes(2, 4, 100), vec![1, 1]);
+        assert_eq!(get_compacted_batch_sizes(10, 4, 100), vec![3, 3, 2, 2]);
+        assert_eq!(get_compacted_batch_sizes(1005, 2, 100), vec![
+            92, 92, 92, 92, 91, 91, 91, 91, 91, 91, 91
+        ]);
+    }
+
+    #[test]
+    fn workers_are_kept_where_balanced() {
+        assert_eq!(
+            assign_workers(&[Some(0), Some(1), Some(0), Some(1)], 2),
+            vec![0, 1, 0, 1]
+        );
+        assert_eq!(assign_workers(&[Some(0), Some(0), Some(0), None], 2), vec![
+            0, 0, 1, 1
+        ]);
+        assert_eq!(assign_workers(&[Some(1), Some(1), Some(1)], 2), vec![
+            1, 0, 1
+        ]);
+    }
+    #[test]
+    fn compact_fills_kept_batches_and_dissolves_the_rest() -> Result<()> {
+        let mut distribution = BatchDistribution::new(2, vec![
+            pending_batch(0, 0, 12, &[3, 7])?,
+            pending_batch(1, 1, 3, &[1])?,
+            pending_batch(2, 1, 2, &[])?,
+        ])?;
+        // The 10 + 2 + 2 agents left and 4 inbound agents are spread across two batches
+        distribution.compact(4, 10)?;
+
+        let batches = distribution.iter().collect::<Vec<_>>();
+        assert_eq!(batches.len(), 3);
+
+        let (worker, largest) = batches[0];
+        assert_eq!(worker, 0);
+        assert_eq!(largest.old_batch_index(), Some(0));
+        assert_eq!(largest.num_agents(), 9);
+        // Its last agent over the new size is copied into the other kept batch
+        assert_eq!(rows(largest.get_remove_actions()), vec![3, 7, 11]);
+        assert_eq!(largest.num_inbound(), 0);
+        assert!(largest.get_copy_actions().is_empty());
+
+        let (worker, filled) = batches[1];
+        assert_eq!(worker, 1);
+        assert_eq!(filled.old_batch_index(), Some(1));
+        assert_eq!(filled.num_agents(), 9);
+        assert_eq!(rows(filled.get_remove_actions()), vec![1]);
+        // Inbound agents are added before agents are copied
+        assert_eq!(filled.num_inbound(), 4);
+        let copied = filled
+            .get_copy_actions()
+            .iter()
+            .map(|(batch_index, indices)| (*batch_index, rows(indices)))
+            .collect::<Vec<_>>();
+        assert_eq!(copied, vec![(0, vec![11]), (2, vec![0, 1])]);
+
+        // The dissolved batch is left at its worker without agents, so it's removed
+        let (worker, dissolved) = batches[2];
+        assert_eq!(worker, 1);
+        assert_eq!(dissolved.old_batch_index(), Some(2));
+        assert_eq!(dissolved.num_agents(), 0);
+        assert_eq!(rows(dissolved.get_remove_actions()), vec![0, 1]);
+        Ok(())
+    }
+}
@@ -38,11 +38,20 @@ impl CreateRemovePlanner {
         })
     }
 
-    pub fn run(&mut self, state: &impl ReadState) -> Result<MigrationPlan<'_>> {
+    /// Plans the creation and removal of agents. If `compact` is set, the batches are rebalanced
+    /// as well, see [`BatchDistribution::compact`].
+    pub fn run(&mut self, state: &impl ReadState, compact: bool) -> Result<MigrationPlan<'_>> {
         let mut pending = self.pending_plan(state.agent_pool())?;
 
         let number_inbound = self.commands.get_number_inbound();
-        pending.distribute_inbound(number_inbound)?;
+        let target_batch_size = self.config.exp.target_batch_size;
+        if compact {
+            pending
+                .distribution
+                .compact(number_inbound, target_batch_size)?;
+        } else {
+            pending.distribute_inbound(number_inbound, target_batch_size)?;
+        }
         pending.complete(state, self.commands.new_agents.as_ref(), &self.config)
     }
 
@@ -64,7 +73,7 @@ impl CreateRemovePlanner {
             .collect::<Result<_>>()?;
 
         let distribution =
-            BatchDistribution::new(self.config.exp.worker_pool.num_workers, pending_batches);
+            BatchDistribution::new(self.config.sim.engine.num_workers, pending_batches)?;
 
         Ok(PendingPlan { distribution })
     }
@@ -76,13 +85,13 @@ struct PendingPlan {
 
 impl PendingPlan {
     /// Distribution of agents across all workers and their batches
-    fn distribute_inbound(&mut self, num_inbound: usize) -> Result<()> {
+    fn distribute_inbound(&mut self, num_inbound: usize, target_batch_size: usize) -> Result<()> {
         // Number of inbound agents per worker (to-be-deleted agents are taken into account here)
         let worker_inbound_distribution = self
             .distribution
             .get_worker_level_distribution(num_inbound)?;
         self.distribution
-            .set_batch_level_inbounds(worker_inbound_distribution)
+            .set_batch_level_inbounds(worker_inbound_distribution, target_batch_size)
     }
 
     fn complete<'b>(
@@ -110,12 +119,15 @@ impl PendingPlan {
 
                     existing_mutations[batch_index] = if planned_num_agents == 0 {
                         ExistingGroupBufferActions::Remove
-                    } else if batch.num_delete_unchecked() == 0 && batch.num_inbound() == 0 {
+                    } else if batch.num_delete_unchecked() == 0
+                        && batch.num_inbound() == 0
+                        && batch.num_copied() == 0
+                    {
                         // No outbound nor inbound agents
                         ExistingGroupBufferActions::Persist { affinity }
                     } else {
                         let actions = buffer_actions_from_pending_batch(
-                            state,
+                            &dynamic_pool,
                             batch,
                             &new_agents,
                             &config.sim.store.agent_schema,
@@ -125,7 +137,7 @@ impl PendingPlan {
                     }
                 } else {
                     let actions = buffer_actions_from_pending_batch(
-                        state,
+                        &dynamic_pool,
                         batch,
                         &new_agents,
                         &config.sim.store.agent_schema,
@@ -146,16 +158,23 @@ impl PendingPlan {
     }
 }
 
-fn buffer_actions_from_pending_batch<'a>(
-    state: &impl ReadState,
+fn buffer_actions_from_pending_batch<'a, B: Deref<Target = AgentBatch>>(
+    batches: &[B],
     batch: &PendingBatch,
     inbound_agents: &Option<&'a RecordBatch>,
     schema: &Arc<AgentSchema>,
     inbound_taken_count: &mut usize,
 ) -> Result<BufferActions<'a>> {
     let remove = RangeActions::collect_indices(batch.get_remove_actions());
-    // We don't copy agents between batches anymore
-    let copy = (0, vec![]);
+    // Agents are only copied between batches when compacting
+    let copy = batch.get_copy_actions().iter().fold(
+        (0, vec![]),
+        |(num_copied, mut copy), (batch_index, indices)| {
+            let (num_indices, ranges) = RangeActions::collect_indices(indices);
+            copy.push((*batch_index, ranges));
+            (num_copied + num_indices, copy)
+        },
+    );
     let create = {
         (batch.num_inbound(), vec![IndexRange::new(
             *inbound_taken_count,
@@ -167,12 +186,127 @@ fn buffer_actions_from_pending_batch<'a>(
 
     let range_actions = RangeActions::new(remove, copy, create);
 
-    let batches = state.agent_pool().read_batches()?;
     BufferActions::from(
-        &batches,
+        batches,
         batch.old_batch_index(),
         range_actions,
         &schema.static_meta,
         *inbound_agents,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        datastore::{
+            arrow::batch_conversion::agent_column_to_json_vals,
+            test_utils::gen_schema_and_test_agents,
+        },
+        hash_types::state::AgentStateField,
+    };
+
+    #[test]
+    fn compacted_plan_moves_agents_with_their_values() -> Result<()> {
+        let experiment_run_id = Arc::new(String::new());
+        let (schema, agents) = gen_schema_and_test_agents(17, 0)?;
+        let (_, new_agents) = gen_schema_and_test_agents(4, 17)?;
+        let new_agents =
+            AgentBatch::from_agent_states(new_agents.as_slice(), &schema, &experiment_run_id)?;
+        // Three uneven batches across two workers
+        let mut pool = [(0, 0..12), (1, 12..15), (1, 15..17)]
+            .into_iter()
+            .map(|(worker, rows)| -> Result<AgentBatch> {
+                let mut batch =
+                    AgentBatch::from_agent_states(&agents[rows], &schema, &experiment_run_id)?;
+                batch.set_affinity(worker);
+                Ok(batch)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // The values of every agent, starting with its ID
+        let names = [
+            AgentStateField::AgentId.name(),
+            "seed",
+            "fixed_of_variable",
+            "notes",
+            "history",
+        ];
+        let rows = |batch: &AgentBatch| -> Result<Vec<Vec<Value>>> {
+            let columns = names
+                .iter()
+                .map(|name| agent_column_to_json_vals(&batch.batch, &schema, name))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((0..batch.num_agents())
+                .map(|row| columns.iter().map(|column| column[row].clone()).collect())
+                .collect())
+        };
+        let old_rows = pool.iter().map(rows).collect::<Result<Vec<_>>>()?;
+        let new_rows = rows(&new_agents)?;
+
+        let mut remove_ids = HashSet::new();
+        for (batch_index, row) in [(0, 3), (0, 7), (1, 1)] {
+            remove_ids.insert(*pool[batch_index].agent_id_iter()?.nth(row).unwrap());
+        }
+        let pending_batches = pool
+            .iter()
+            .enumerate()
+            .map(|(batch_index, batch)| {
+                PendingBatch::from_batch(batch_index, batch, &mut remove_ids)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut distribution = BatchDistribution::new(2, pending_batches)?;
+        distribution.compact(new_agents.num_agents(), 10)?;
+
+        // Executes the plan like `MigrationPlan::execute` does on the agent pool
+        let mut updates = Vec::new();
+        let mut removed = Vec::new();
+        let mut inbound_taken_count = 0;
+        for (worker_index, batch) in distribution.iter() {
+            let batch_index = batch.old_batch_index_unchecked();
+            if batch.num_agents() == 0 {
+                removed.push(batch_index);
+                continue;
+            }
+            let actions = buffer_actions_from_pending_batch(
+                &pool.iter().collect::<Vec<_>>(),
+                batch,
+                &Some(&new_agents.batch),
+                &schema,
+                &mut inbound_taken_count,
+            )?;
+            updates.push((batch_index, actions, worker_index));
+        }
+        for (batch_index, actions, worker_index) in updates {
+            actions.flush(&mut pool[batch_index])?;
+            pool[batch_index].set_affinity(worker_index);
+        }
+        removed.sort_unstable();
+        removed.into_iter().rev().for_each(|batch_index| {
+            pool.swap_remove(batch_index);
+        });
+
+        assert_eq!(pool.len(), 2);
+        assert_eq!(
+            pool.iter().map(|batch| batch.affinity).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        // The largest batch only loses its removed agents and its last agent, which is copied
+        let expected = [0, 1, 2, 4, 5, 6, 8, 9, 10]
+            .iter()
+            .map(|&row| old_rows[0][row].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(rows(&pool[0])?, expected);
+        // The other batch keeps its agents, then gets the copied agents and the created ones
+        let mut expected = vec![old_rows[1][0].clone(), old_rows[1][2].clone()];
+        expected.push(old_rows[0][11].clone());
+        expected.extend(old_rows[2].iter().cloned());
+        expected.extend(new_rows);
+        assert_eq!(rows(&pool[1])?, expected);
+        Ok(())
+    }
+}
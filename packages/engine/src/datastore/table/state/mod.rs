@@ -52,17 +52,20 @@ impl Inner {
         let agent_schema = &sim_config.sim.store.agent_schema;
         let message_schema = &sim_config.sim.store.message_schema;
         let experiment_run_id = &sim_config.exp.run_id;
+        let num_workers = sim_config.sim.engine.num_workers.max(1);
 
         let mut group_start_indices = Vec::new();
         let mut start = 0;
 
-        for agent_state_batch in agent_state_batches {
+        for (batch_index, agent_state_batch) in agent_state_batches.iter().enumerate() {
             group_start_indices.push(start);
             start += agent_state_batch.len();
 
-            agent_batches.push(Arc::new(parking_lot::RwLock::new(
-                AgentBatch::from_agent_states(*agent_state_batch, agent_schema, experiment_run_id)?,
-            )));
+            let mut agent_batch =
+                AgentBatch::from_agent_states(*agent_state_batch, agent_schema, experiment_run_id)?;
+            // Spread the initial batches across the workers of the simulation run
+            agent_batch.set_affinity(batch_index % num_workers);
+            agent_batches.push(Arc::new(parking_lot::RwLock::new(agent_batch)));
             message_batches.push(Arc::new(parking_lot::RwLock::new(
                 MessageBatch::from_agent_states(
                     *agent_state_batch,
@@ -225,13 +228,16 @@ impl ExState {
         Ok(())
     }
 
+    /// Creates and removes agents. If `compact` is set, the batches of agents are rebalanced as
+    /// well.
     pub fn create_remove(
         &mut self,
         commands: CreateRemoveCommands,
         config: &Arc<SimRunConfig>,
+        compact: bool,
     ) -> Result<()> {
         let mut planner = CreateRemovePlanner::new(commands, config.clone())?;
-        let plan = planner.run(self)?;
+        let plan = planner.run(self, compact)?;
         *self.num_agents_mut() = plan.num_agents_after_execution;
         let removed_ids = plan.execute(self.agent_pool_mut(), config)?;
 
@@ -4,6 +4,7 @@ use futures::future::join_all;
 use parking_lot::RwLock;
 
 use crate::{
+    config::Globals,
     datastore::{
         prelude::ContextBatch,
         table::pool::{agent::AgentPool, message::MessagePool},
@@ -61,9 +62,9 @@ impl WaitableStateSync {
 
     /// Wait for all child messages to be handled and then send that
     /// `self` was handled. If any errors occurred while handling a
-    /// child message, send that an error occurred while handling `self`.
-    /// TODO: Return Result (instead of `expect`) and
-    ///       handle where this function is awaited.
+    /// child message, or a child message was dropped without being
+    /// handled (e.g. because its runner crashed), send that an error
+    /// occurred while handling `self`.
     ///
     /// Usage:
     /// let (child_msgs, child_receivers) = self.create_children(2);
@@ -76,13 +77,19 @@ impl WaitableStateSync {
         let result = child_results
             .into_iter()
             .map(|recv_result| {
-                recv_result.expect("Couldn't receive waitable sync result from child")
+                recv_result.unwrap_or_else(|_| {
+                    Err(WorkerError::from(
+                        "State sync was dropped before it was handled",
+                    ))
+                })
             })
             .collect::<WorkerResult<Vec<()>>>()
             .map(|_| ());
-        self.completion_sender
-            .send(result)
-            .expect("Couldn't send waitable sync result to engine");
+        if self.completion_sender.send(result).is_err() {
+            // The simulation run stopped waiting for the sync, e.g. because it failed.
+            log::warn!("Couldn't send waitable sync result to engine");
+            return;
+        }
         log::trace!("Sent main state sync completion");
     }
 }
@@ -112,6 +119,12 @@ impl fmt::Debug for ContextBatchSync {
     }
 }
 
+/// Globals of a simulation run, which changed during the run
+#[derive(derive_new::new, Clone, Debug)]
+pub struct GlobalsSync {
+    pub globals: Arc<Globals>,
+}
+
 #[derive(Debug)]
 pub enum SyncPayload {
     // Agent state which is to be mutated within a step
@@ -121,6 +134,8 @@ pub enum SyncPayload {
     StateSnapshot(StateSync),
     // Context batch, which the context also refers to
     ContextBatch(ContextBatchSync),
+    // Globals, which are only synced when they changed
+    Globals(GlobalsSync),
 }
 
 impl SyncPayload {
@@ -129,6 +144,7 @@ impl SyncPayload {
             Self::State(_) => Err(WorkerError::from("Waitable sync message can't be cloned")),
             Self::StateSnapshot(s) => Ok(Self::StateSnapshot(s.clone())),
             Self::ContextBatch(s) => Ok(Self::ContextBatch(s.clone())),
+            Self::Globals(s) => Ok(Self::Globals(s.clone())),
         }
     }
 }
@@ -139,6 +155,7 @@ impl From<SyncPayload> for InboundToRunnerMsgPayload {
             SyncPayload::State(s) => Self::StateSync(s),
             SyncPayload::StateSnapshot(s) => Self::StateSnapshotSync(s),
             SyncPayload::ContextBatch(c) => Self::ContextBatchSync(c),
+            SyncPayload::Globals(g) => Self::GlobalsSync(g),
         }
     }
 }
@@ -143,21 +143,25 @@ impl PartialSharedState {
     }
 }
 
+/// Distributes the batches across the workers of a simulation run.
+///
+/// Every batch is placed on the worker its affinity points to, i.e. the worker the create-remove
+/// planner assigned it to. Workers which don't receive any batch are left out, unless no worker
+/// receives a batch at all, in which case the first worker is kept so the task still completes.
 fn distribute_batches<A, M>(
     worker_list: &WorkerAllocation,
     agent_batches: Vec<A>,
     msg_batches: Vec<M>,
     group_indices: Vec<usize>,
-    group_sizes: Vec<usize>, // Number of agents in each group
+    group_sizes: Vec<usize>,      // Number of agents in each group
+    group_affinities: Vec<usize>, // Index of the worker each group is assigned to
 ) -> (Vec<(Worker, Vec<A>, Vec<M>, Vec<usize>)>, SplitConfig) {
     // Initialize with empty distribution.
     let num_workers = worker_list.len();
-    let mut agent_distribution = Vec::with_capacity(num_workers);
-    let mut stores = Vec::with_capacity(num_workers);
-    worker_list.iter().for_each(|worker| {
-        agent_distribution.push(0);
-        stores.push((*worker, vec![], vec![], vec![]));
-    });
+    let mut distribution: Vec<_> = worker_list
+        .iter()
+        .map(|worker| (0, (*worker, vec![], vec![], vec![])))
+        .collect();
 
     // Distribute batches.
     let iter = agent_batches
@@ -165,18 +169,24 @@ fn distribute_batches<A, M>(
         .zip(msg_batches.into_iter())
         .enumerate();
     for (i_group, (agent_batch, msg_batch)) in iter {
-        let i_worker = i_group % num_workers;
-        agent_distribution[i_group] += group_sizes[i_worker];
-
-        let store = &mut stores[i_worker];
+        let i_worker = group_affinities[i_group] % num_workers;
+        let (num_agents, store) = &mut distribution[i_worker];
+        *num_agents += group_sizes[i_group];
         store.1.push(agent_batch);
         store.2.push(msg_batch);
         store.3.push(group_indices[i_group]);
     }
 
+    if distribution.iter().any(|(_, store)| !store.3.is_empty()) {
+        distribution.retain(|(_, store)| !store.3.is_empty());
+    } else {
+        distribution.truncate(1);
+    }
+
     // Wrap into correct format.
+    let (agent_distribution, stores): (Vec<_>, Vec<_>) = distribution.into_iter().unzip();
     let split_config = SplitConfig {
-        num_workers,
+        num_workers: stores.len(),
         agent_distribution: Some(agent_distribution),
     };
     (stores, split_config)
@@ -242,12 +252,17 @@ impl TaskSharedStore {
                 .iter()
                 .map(|batch| batch.inner().num_agents())
                 .collect();
+            let group_affinities = agent_batches
+                .iter()
+                .map(|batch| batch.inner().affinity)
+                .collect();
             let (stores, split_config) = distribute_batches(
                 worker_list,
                 agent_batches,
                 msg_batches,
                 group_indices,
                 group_sizes,
+                group_affinities,
             );
             let stores: Vec<_> = stores
                 .into_iter()
@@ -285,12 +300,17 @@ impl TaskSharedStore {
                 .iter()
                 .map(|batch| batch.inner().num_agents())
                 .collect();
+            let group_affinities = agent_batches
+                .iter()
+                .map(|batch| batch.inner().affinity)
+                .collect();
             let (stores, split_config) = distribute_batches(
                 worker_list,
                 agent_batches,
                 msg_batches,
                 group_indices,
                 group_sizes,
+                group_affinities,
             );
             let stores: Vec<_> = stores
                 .into_iter()
@@ -355,3 +375,40 @@ impl TaskSharedStore {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribute_batches_by_affinity() {
+        let workers = vec![Worker::new(0), Worker::new(1), Worker::new(2)];
+        let (stores, split_config) = distribute_batches(
+            &workers,
+            vec!['a', 'b', 'c'],
+            vec!['x', 'y', 'z'],
+            vec![0, 1, 2],
+            vec![10, 20, 30],
+            vec![1, 0, 1],
+        );
+
+        // Worker 2 doesn't have any batches, so it's left out
+        assert_eq!(split_config.num_workers, 2);
+        assert_eq!(split_config.agent_distribution, Some(vec![20, 40]));
+        assert_eq!(stores, vec![
+            (Worker::new(0), vec!['b'], vec!['y'], vec![1]),
+            (Worker::new(1), vec!['a', 'c'], vec!['x', 'z'], vec![0, 2]),
+        ]);
+    }
+
+    #[test]
+    fn distribute_no_batches() {
+        let workers = vec![Worker::new(3), Worker::new(4)];
+        let (stores, split_config) =
+            distribute_batches::<(), ()>(&workers, vec![], vec![], vec![], vec![], vec![]);
+
+        assert_eq!(split_config.num_workers, 1);
+        assert_eq!(split_config.agent_distribution, Some(vec![0]));
+        assert_eq!(stores, vec![(Worker::new(3), vec![], vec![], vec![])]);
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
         },
     },
     hash_types::state::{Agent, AgentStateField},
-    simulation::package::creator::get_base_agent_fields,
+    simulation::{package::creator::get_base_agent_fields, seed::AgentIdGenerator},
 };
 
 lazy_static::lazy_static! {
@@ -31,11 +31,17 @@ lazy_static::lazy_static! {
                 "abc": "[[foo; 6]]"
             },
             "fixed_of_variable" : "[[number]; 2]",
-            "seed": "number"
+            "seed": "number",
+            "count": "integer",
+            "size": "unsigned_integer",
+            "born": "timestamp?"
         }
     });
 }
 
+/// Categories of the categorical `infection` field
+pub const INFECTION_STATES: [&str; 3] = ["susceptible", "infected", "recovered"];
+
 #[derive(Serialize, Deserialize)]
 pub struct Foo {
     bar: bool,
@@ -97,7 +103,7 @@ fn make_dummy_agent(seed: u64) -> Result<Agent, Error> {
     let mut rng = StdRng::seed_from_u64(seed);
 
     let mut agent = Agent::empty();
-    let id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+    let id = AgentIdGenerator::new(seed).next_id();
     agent.set(AgentStateField::AgentId.name(), &id)?;
     // We do an implicit conversion to f64 for number types so for testing need to ensure
     // manually created agents only have f64
@@ -114,6 +120,22 @@ fn make_dummy_agent(seed: u64) -> Result<Agent, Error> {
     agent.set("complex", Complex::new(seed))?;
     // see above per f64
     agent.set("seed", Some(seed as f64))?;
+    // Integers use the full range, which doesn't fit into f64
+    agent.set("count", rng.gen::<i64>())?;
+    agent.set("size", rng.gen::<u32>())?;
+    if rng.gen_bool(0.7) {
+        agent.set("born", rng.gen_range(0_i64..4_102_444_800_000))?;
+    }
+    if rng.gen_bool(0.7) {
+        agent.set("infection", INFECTION_STATES[rng.gen_range(0..3)])?;
+    }
+    if rng.gen_bool(0.7) {
+        agent.set("notes", rand_string(seed.wrapping_add(1)))?;
+    }
+    let history = (0..rng.gen_range(0..10))
+        .map(|_| rng.gen_range(0_f64..100.0))
+        .collect::<Vec<_>>();
+    agent.set("history", history)?;
 
     Ok(agent)
 }
@@ -138,6 +160,37 @@ pub fn gen_schema_and_test_agents(
             Error::from(format!("Failed to add base agent field specs: {err}"))
         })?)?;
 
+    let infection_states = INFECTION_STATES.iter().map(|s| s.to_string()).collect();
+    field_spec_map.add(field_spec_creator.create(
+        "infection".to_string(),
+        FieldType {
+            variant: FieldTypeVariant::Categorical(infection_states),
+            nullable: true,
+        },
+        FieldScope::Agent,
+    ))?;
+
+    // Short JSON has no syntax for the types with 64 bit offsets
+    field_spec_map.add(field_spec_creator.create(
+        "notes".to_string(),
+        FieldType {
+            variant: FieldTypeVariant::LargeString,
+            nullable: true,
+        },
+        FieldScope::Agent,
+    ))?;
+    field_spec_map.add(field_spec_creator.create(
+        "history".to_string(),
+        FieldType {
+            variant: FieldTypeVariant::LargeVariableLengthArray(Box::new(FieldType::new(
+                FieldTypeVariant::Number,
+                false,
+            ))),
+            nullable: false,
+        },
+        FieldScope::Agent,
+    ))?;
+
     field_spec_map.union(FieldSpecMap::from_short_json(
         JSON_KEYS.clone(),
         FieldSource::Engine,
@@ -68,6 +68,12 @@ pub enum Error {
     #[error("Behavior language parse error: {0}")]
     ParseBehavior(String),
 
+    #[error(
+        "No runner for behavior \"{0}\", the engine doesn't support its language and the project \
+         has no external runner for its extension"
+    )]
+    NoExternalRunner(String),
+
     #[error("Uuid error: {0}")]
     Uuid(#[from] uuid::Error),
 
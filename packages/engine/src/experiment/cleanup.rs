@@ -0,0 +1,360 @@
+//! Cleanup of what an experiment run leaves behind on the machine, i.e. its memory segments and the
+//! parts of its output.
+//!
+//! Every experiment run writes a [`RunRecord`] when it starts and removes it when it shuts down.
+//! If the engine is killed before that, the record is left behind as well, so
+//! [`clean_orphans`] can find and remove what belonged to the run later.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use nix::sys::signal::Signal;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+
+use super::Result;
+use crate::{
+    datastore::storage::{memory::Memory, registry, segment::Backend as MemoryBackend},
+    experiment::controller::comms::engine_msg::EngineMsgSend,
+    output::buffer::{parts_folder, remove_run_parts},
+    proto::EngineMsg,
+    ExperimentConfig,
+};
+
+/// The folder the records of the running experiment runs are written to
+fn records_folder() -> PathBuf {
+    std::env::temp_dir()
+        .join("hash_engine")
+        .join("experiment_runs")
+}
+
+/// What an experiment run needs to be cleaned up, which is known before its engine is started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub experiment_id: String,
+    pub experiment_run_id: String,
+    /// Process ID of the engine running the experiment run
+    pub pid: u32,
+    /// Folder the memory segments of the experiment run are created in
+    pub segment_folder: PathBuf,
+    /// Folder the output parts of the experiment run are written to
+    pub parts_folder: PathBuf,
+}
+
+impl RunRecord {
+    fn path(&self) -> PathBuf {
+        records_folder().join(format!("{}.json", self.experiment_run_id))
+    }
+
+    fn read(path: &Path) -> Result<RunRecord> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    fn write(&self) -> Result<()> {
+        std::fs::create_dir_all(records_folder())?;
+        std::fs::write(self.path(), serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Whether the engine which wrote this record is still running.
+    fn is_alive(&self) -> bool {
+        let pid = nix::unistd::Pid::from_raw(self.pid as i32);
+        // Sending no signal only checks whether the process exists
+        match nix::sys::signal::kill(pid, None) {
+            Ok(()) => true,
+            Err(err) => err == nix::errno::Errno::EPERM,
+        }
+    }
+
+    /// Removes the memory segments which are left behind by the experiment run.
+    ///
+    /// The segments registered in this process are removed by their ID. Segments of engines which
+    /// aren't running anymore are found by their name instead, which starts with the experiment
+    /// run ID. This isn't possible for shared memory on macOS, which can't be listed.
+    fn remove_segments(&self) -> usize {
+        let mut removed = registry::remove_segments(&self.experiment_run_id);
+        let pattern = self.segment_folder.join(format!(
+            "{}*",
+            Memory::segment_name_prefix(&self.experiment_run_id)
+        ));
+        match glob::glob(&pattern.to_string_lossy()) {
+            Ok(paths) => {
+                paths.flatten().for_each(|path| {
+                    if std::fs::remove_file(&path).is_ok() {
+                        removed += 1;
+                    }
+                });
+            }
+            Err(err) => log::warn!("Invalid memory segment pattern {pattern:?}: {err}"),
+        }
+        removed
+    }
+
+    fn remove_parts(&self) {
+        if self.parts_folder.exists() {
+            if let Err(err) = remove_run_parts(&self.parts_folder) {
+                log::warn!("Could not remove {:?}: {}", self.parts_folder, err);
+            }
+        }
+    }
+
+    /// Removes everything the experiment run left behind, including this record.
+    ///
+    /// `remove_parts` should only be set if the experiment run didn't finish, as the parts might
+    /// still be needed otherwise.
+    pub fn clean(&self, remove_parts: bool) {
+        let removed = self.remove_segments();
+        log::debug!(
+            "Removed {removed} memory segments of experiment run {}",
+            self.experiment_run_id
+        );
+        if remove_parts {
+            self.remove_parts();
+        }
+        if let Err(err) = std::fs::remove_file(self.path()) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(
+                    "Could not remove the record of {}: {}",
+                    self.experiment_run_id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// How long the experiment has to stop after the engine received a signal, before the engine
+/// exits without waiting for it.
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Writes the record of an experiment run and cleans up after it when dropped.
+///
+/// When the engine receives one of the signals it's stopped with, the experiment is stopped like
+/// the orchestrator stops it, so the experiment run shuts down and cleans up as usual. Only if it
+/// doesn't stop within [`STOP_TIMEOUT`], the engine cleans up and exits on its own.
+pub struct CleanupGuard {
+    record: Arc<RunRecord>,
+    signal_handler: JoinHandle<()>,
+}
+
+impl CleanupGuard {
+    pub fn new(
+        exp_config: &ExperimentConfig,
+        mut stop_send: EngineMsgSend,
+    ) -> Result<CleanupGuard> {
+        let segment_folder = match &exp_config.memory_backend {
+            MemoryBackend::SharedMemory => PathBuf::from("/dev/shm"),
+            MemoryBackend::File { directory } => directory.clone(),
+        };
+        let record = Arc::new(RunRecord {
+            experiment_id: exp_config.id().clone(),
+            experiment_run_id: exp_config.run_id.to_string(),
+            pid: std::process::id(),
+            segment_folder,
+            parts_folder: std::env::current_dir()?
+                .join(parts_folder(exp_config.id(), &exp_config.run_id)),
+        });
+        record.write()?;
+
+        let signal_record = Arc::clone(&record);
+        let signal_handler = tokio::spawn(async move {
+            let signal = match wait_for_signal().await {
+                Ok(signal) => signal,
+                Err(err) => {
+                    log::warn!("Could not listen for signals: {err}");
+                    return;
+                }
+            };
+            log::warn!("Received {signal:?}, stopping the experiment");
+            if let Err(err) = stop_send.send(EngineMsg::StopExperiment).await {
+                // The experiment controller finished already, the run is shutting down
+                log::debug!("Could not stop the experiment: {err}");
+            }
+            tokio::time::sleep(STOP_TIMEOUT).await;
+            log::error!(
+                "The experiment didn't stop within {STOP_TIMEOUT:?}, cleaning up before exiting"
+            );
+            signal_record.clean(true);
+            std::process::exit(128 + signal as i32);
+        });
+
+        Ok(CleanupGuard {
+            record,
+            signal_handler,
+        })
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        self.signal_handler.abort();
+        self.record.clean(false);
+    }
+}
+
+/// Waits for one of the signals the engine is stopped with.
+///
+/// `SIGINT` is left alone, as Ctrl-C is handled by the orchestrator, which stops the experiment
+/// gracefully.
+async fn wait_for_signal() -> std::io::Result<Signal> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(tokio::select! {
+        _ = hangup.recv() => Signal::SIGHUP,
+        _ = terminate.recv() => Signal::SIGTERM,
+    })
+}
+
+fn read_records() -> Result<Vec<RunRecord>> {
+    let folder = records_folder();
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+    let mut records = Vec::new();
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        match RunRecord::read(&path) {
+            Ok(record) => records.push(record),
+            Err(err) => log::warn!("Skipping invalid experiment run record {path:?}: {err}"),
+        }
+    }
+    Ok(records)
+}
+
+/// Removes everything left behind by the runs of the experiment `experiment_id` whose engines
+/// aren't running anymore, e.g. after the engine was killed. Returns the records of the experiment
+/// runs which were cleaned up.
+pub fn clean_experiment(experiment_id: &str) -> Result<Vec<RunRecord>> {
+    clean_dead_runs(|record| record.experiment_id == experiment_id)
+}
+
+/// Removes everything left behind by experiment runs whose engines aren't running anymore.
+/// Returns the records of the experiment runs which were cleaned up.
+pub fn clean_orphans() -> Result<Vec<RunRecord>> {
+    clean_dead_runs(|_| true)
+}
+
+fn clean_dead_runs(filter: impl Fn(&RunRecord) -> bool) -> Result<Vec<RunRecord>> {
+    let records = read_records()?
+        .into_iter()
+        .filter(|record| filter(record) && !record.is_alive())
+        .collect::<Vec<_>>();
+    records.iter().for_each(|record| record.clean(true));
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    /// Writes the record of an experiment run of `experiment_id`, run by the engine with the
+    /// process ID `pid`, which left two memory segments of the file backend and an output part in
+    /// `folder`.
+    fn write_run(folder: &Path, experiment_id: &str, pid: u32) -> Result<RunRecord> {
+        let experiment_run_id = uuid::Uuid::new_v4().to_string();
+        let segment_folder = folder.join("segments");
+        std::fs::create_dir_all(&segment_folder)?;
+        for i in 0..2 {
+            let name = format!("{}{i}", Memory::segment_name_prefix(&experiment_run_id));
+            std::fs::write(segment_folder.join(name), [0; 64])?;
+        }
+        let parts_folder = folder.join(parts_folder(experiment_id, &experiment_run_id));
+        std::fs::create_dir_all(parts_folder.join("0"))?;
+        std::fs::write(parts_folder.join("0").join("json_state-0.part"), "[]")?;
+
+        let record = RunRecord {
+            experiment_id: experiment_id.to_string(),
+            experiment_run_id,
+            pid,
+            segment_folder,
+            parts_folder,
+        };
+        record.write()?;
+        Ok(record)
+    }
+
+    /// The number of memory segments of the experiment run which are left
+    fn segments_left(record: &RunRecord) -> usize {
+        let prefix = Memory::segment_name_prefix(&record.experiment_run_id);
+        std::fs::read_dir(&record.segment_folder)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&prefix)
+            })
+            .count()
+    }
+
+    /// The process ID of a process which already exited
+    fn dead_pid() -> u32 {
+        let mut child = Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        child.id()
+    }
+
+    #[test]
+    fn runs_of_exited_engines_are_orphans() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("cleanup_test_{}", uuid::Uuid::new_v4()));
+        let experiment_id = uuid::Uuid::new_v4().to_string();
+        let dead = write_run(&folder, &experiment_id, dead_pid())?;
+        let live = write_run(&folder, &experiment_id, std::process::id())?;
+        let mut running = Command::new("sleep").arg("60").spawn()?;
+        let other_process = write_run(&folder, &experiment_id, running.id())?;
+
+        assert!(!dead.is_alive());
+        assert!(live.is_alive());
+        assert!(other_process.is_alive());
+
+        running.kill()?;
+        running.wait()?;
+        assert!(!other_process.is_alive());
+
+        [dead, live, other_process]
+            .iter()
+            .for_each(|record| record.clean(true));
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[test]
+    fn clean_experiment_leaves_live_runs_alone() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("cleanup_test_{}", uuid::Uuid::new_v4()));
+        let experiment_id = uuid::Uuid::new_v4().to_string();
+        let dead = write_run(&folder, &experiment_id, dead_pid())?;
+        let live = write_run(&folder, &experiment_id, std::process::id())?;
+        // Runs of other experiments are left alone, even if their engine exited
+        let other_experiment = write_run(&folder, "other_experiment", dead_pid())?;
+
+        let cleaned = clean_experiment(&experiment_id)?;
+        assert_eq!(cleaned.len(), 1);
+        assert_eq!(cleaned[0].experiment_run_id, dead.experiment_run_id);
+        assert_eq!(segments_left(&dead), 0);
+        assert!(!dead.parts_folder.exists());
+        assert!(!dead.path().exists());
+        // The parts of the live run of the same experiment are kept
+        for record in [&live, &other_experiment] {
+            let part = record.parts_folder.join("0").join("json_state-0.part");
+            assert_eq!(segments_left(record), 2);
+            assert!(part.exists());
+            assert!(record.path().exists());
+        }
+
+        [live, other_experiment]
+            .iter()
+            .for_each(|record| record.clean(true));
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+}
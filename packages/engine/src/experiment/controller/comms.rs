@@ -32,6 +32,38 @@ pub mod simulation {
     }
 }
 
+/// Messages the engine sends its own experiment controller as if they came from the orchestrator,
+/// e.g. to stop the experiment when the engine receives a signal.
+pub mod engine_msg {
+    use super::*;
+    use crate::proto::EngineMsg;
+
+    pub struct EngineMsgSend {
+        inner: UnboundedSender<EngineMsg>,
+    }
+
+    impl EngineMsgSend {
+        pub async fn send(&mut self, msg: EngineMsg) -> Result<()> {
+            Ok(self.inner.send(msg)?)
+        }
+    }
+
+    pub struct EngineMsgRecv {
+        inner: UnboundedReceiver<EngineMsg>,
+    }
+
+    impl EngineMsgRecv {
+        pub async fn recv(&mut self) -> Option<EngineMsg> {
+            self.inner.recv().await
+        }
+    }
+
+    pub fn new_pair() -> (EngineMsgSend, EngineMsgRecv) {
+        let (send, recv) = unbounded_channel();
+        (EngineMsgSend { inner: send }, EngineMsgRecv { inner: recv })
+    }
+}
+
 pub mod sim_status {
     use super::*;
     use crate::simulation::status::SimStatus;
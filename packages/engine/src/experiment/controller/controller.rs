@@ -2,6 +2,7 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use super::{
     comms::{
+        engine_msg::EngineMsgRecv,
         sim_status::{SimStatusRecv, SimStatusSend},
         simulation::SimCtlSend,
     },
@@ -10,7 +11,7 @@ use super::{
     Error, Result,
 };
 use crate::{
-    config::{PersistenceConfig, StoreConfig},
+    config::{CheckpointData, ForkConfig, ForkParent, PersistenceConfig, StoreConfig},
     datastore::prelude::SharedStore,
     env::OrchClient,
     experiment::{
@@ -53,6 +54,10 @@ pub struct ExperimentController<P: OutputPersistenceCreatorRepr> {
     output_persistence_service_creator: P,
     sim_run_tasks: SimulationRuns,
     sim_senders: HashMap<SimulationShortId, SimCtlSend>,
+    /// The states simulation runs ended with, which other runs can be forked from
+    final_states: HashMap<SimulationShortId, Arc<CheckpointData>>,
+    /// Set when the orchestrator stopped the experiment, no new simulation runs are started then
+    stopping: bool,
     worker_pool_send_base: workerpool::comms::main::MainMsgSendBase,
     package_creators: PackageCreators,
     sim_id_store: SimIdStore,
@@ -60,6 +65,8 @@ pub struct ExperimentController<P: OutputPersistenceCreatorRepr> {
     sim_status_send: SimStatusSend,
     sim_status_recv: SimStatusRecv,
     terminate_recv: TerminateRecv,
+    /// Messages the engine sends itself, which are handled like the ones of the orchestrator
+    engine_msg_recv: EngineMsgRecv,
 }
 
 impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
@@ -72,7 +79,68 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                     .set_registered_id(short_id, registered_id)
                     .await
             }
+            EngineMsg::PauseSim(sim_short_id) => {
+                log::info!("Pausing simulation run {sim_short_id}");
+                self.send_sim_from_orch(sim_short_id, SimControl::Pause)
+                    .await
+            }
+            EngineMsg::ResumeSim(sim_short_id) => {
+                log::info!("Resuming simulation run {sim_short_id}");
+                self.send_sim_from_orch(sim_short_id, SimControl::Resume)
+                    .await
+            }
+            EngineMsg::StopSim(sim_short_id) => {
+                log::info!("Stopping simulation run {sim_short_id}");
+                self.send_sim_from_orch(sim_short_id, SimControl::Stop)
+                    .await
+            }
+            EngineMsg::StopExperiment => {
+                log::info!("Stopping experiment");
+                self.stopping = true;
+                let sim_short_ids: Vec<_> = self.sim_senders.keys().copied().collect();
+                for sim_short_id in sim_short_ids {
+                    // Simulation runs which have finished already can't be stopped
+                    if let Err(err) = self.stop_sim_run(sim_short_id).await {
+                        log::debug!("Couldn't stop simulation run {sim_short_id}: {err}");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Sends a control message requested by the orchestrator. The requested simulation run might
+    /// not exist or have finished already, which isn't an error of the experiment.
+    async fn send_sim_from_orch(
+        &mut self,
+        sim_short_id: SimulationShortId,
+        msg: SimControl,
+    ) -> Result<()> {
+        if let Err(err) = self.send_sim(sim_short_id, msg).await {
+            log::warn!("Couldn't control simulation run {sim_short_id}: {err}");
         }
+        Ok(())
+    }
+
+    /// Tells the experiment package that a simulation run it asked for has stopped without being
+    /// started, because the experiment is stopping.
+    async fn skip_sim_run(&mut self, sim_short_id: SimulationShortId) -> Result<()> {
+        log::debug!("Not starting simulation run {sim_short_id}, the experiment is stopping");
+        self.experiment_package_comms
+            .step_update_sender
+            .send(StepUpdate {
+                sim_id: sim_short_id,
+                was_error: false,
+                stop_signal: true,
+                analysis_output: None,
+            })
+            .await
+            .map_err(|exp_controller_err| {
+                Error::from(format!(
+                    "Experiment controller error: {:?}",
+                    exp_controller_err
+                ))
+            })
     }
 
     async fn handle_experiment_control_msg(&mut self, msg: ExperimentControl) -> Result<()> {
@@ -81,10 +149,51 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                 sim_id,
                 changed_properties,
                 max_num_steps,
+                keep_final_state,
             } => {
-                self.start_new_sim_run(sim_id, changed_properties, max_num_steps)
+                if self.stopping {
+                    return self.skip_sim_run(sim_id).await;
+                }
+                let fork = ForkConfig {
+                    parent: None,
+                    keep_final_state,
+                };
+                self.start_new_sim_run(sim_id, changed_properties, max_num_steps, fork)
                     .await?;
             }
+            ExperimentControl::ForkSim {
+                parent_id,
+                sim_id,
+                changed_properties,
+                max_num_steps,
+            } => {
+                if self.stopping {
+                    return self.skip_sim_run(sim_id).await;
+                }
+                let parent_state = self.final_states.get(&parent_id).cloned().ok_or_else(|| {
+                    Error::from(format!(
+                        "Can't fork simulation run {parent_id}, its final state wasn't kept"
+                    ))
+                })?;
+                log::info!(
+                    "Forking simulation run {sim_id} from simulation run {parent_id} at step {}",
+                    parent_state.metadata.step
+                );
+                let fork = ForkConfig {
+                    parent: Some(ForkParent {
+                        sim_id: parent_id,
+                        state: parent_state,
+                    }),
+                    keep_final_state: false,
+                };
+                self.start_new_sim_run(sim_id, changed_properties, max_num_steps, fork)
+                    .await?;
+            }
+            ExperimentControl::ReleaseFinalState(sim_short_id) => {
+                if self.final_states.remove(&sim_short_id).is_none() {
+                    log::warn!("No final state of simulation run {sim_short_id} was kept");
+                }
+            }
             ExperimentControl::PauseSim(sim_short_id) => self.pause_sim_run(sim_short_id).await?,
             ExperimentControl::ResumeSim(sim_short_id) => self.resume_sim_run(sim_short_id).await?,
             ExperimentControl::StopSim(sim_short_id) => self.stop_sim_run(sim_short_id).await?,
@@ -92,7 +201,12 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
         Ok(())
     }
 
-    async fn handle_sim_status(&mut self, status: SimStatus) -> Result<()> {
+    async fn handle_sim_status(&mut self, mut status: SimStatus) -> Result<()> {
+        // Kept before the experiment package hears of the stop, as it might fork the run right away
+        if let Some(final_state) = status.final_state.take() {
+            self.final_states.insert(status.sim_id, final_state);
+        }
+
         // Send Step update to experiment package
         let send_step_update = self
             .experiment_package_comms
@@ -101,6 +215,7 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                 sim_id: status.sim_id,
                 was_error: status.error.is_some(),
                 stop_signal: status.stop_signal,
+                analysis_output: status.analysis_output.take(),
             })
             .await
             .map_err(|exp_controller_err| {
@@ -158,7 +273,17 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                 EngineStatus::Logs(id, logs)
             }
         };
+        let stop_sim_run = matches!(engine_status, EngineStatus::Errors(..))
+            && !self.exp_base_config.continue_on_error;
         self.orch_client().send(engine_status).await?;
+
+        if stop_sim_run {
+            log::info!("Stopping simulation run {id} because of errors");
+            // The simulation run might have finished already, which is fine
+            if let Err(err) = self.stop_sim_run(id).await {
+                log::debug!("Couldn't stop simulation run {id}: {err}");
+            }
+        }
         Ok(())
     }
 
@@ -167,16 +292,19 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
         sim_short_id: SimulationShortId,
         changed_properties: serde_json::Value,
         max_num_steps: usize,
+        fork: ForkConfig,
     ) -> Result<()> {
         let worker_pool_sender = self.worker_pool_send_base.sender_with_sim_id(sim_short_id);
 
-        // Create the `globals.json` for the simulation
+        // Create the `globals.json` for the simulation, forked runs change the globals of the run
+        // they were forked from
+        let base_globals = match &fork.parent {
+            Some(parent) => parent.state.metadata.globals.clone(),
+            None => self.exp_base_config.base_globals.clone(),
+        };
         let globals = Arc::new(
-            apply_property_changes(
-                self.exp_base_config.base_globals.clone(),
-                &changed_properties,
-            )
-            .map_err(|experiment_err| Error::from(experiment_err.to_string()))?,
+            apply_property_changes(base_globals, &changed_properties)
+                .map_err(|experiment_err| Error::from(experiment_err.to_string()))?,
         );
 
         // Create the datastore configuration (requires schemas)
@@ -198,6 +326,7 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
             store_config,
             persistence_config,
             max_num_steps,
+            fork,
         )?);
 
         let task_comms = Comms::new(sim_short_id, worker_pool_sender)?;
@@ -221,7 +350,8 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                     engine_config: Arc::clone(&sim_config.sim.engine),
                     packages: sim_start_msgs,
                     datastore: datastore_payload,
-                    globals: globals.clone(),
+                    globals: Arc::new(globals.for_behaviors()),
+                    seed: sim_config.sim.seed,
                 },
             ))
             .await?;
@@ -233,6 +363,7 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
             self.shared_store.clone(),
             persistence_service,
             self.sim_status_send.clone(),
+            Arc::clone(&self.experiment_package_comms.analysis_outputs),
         )
         .map_err(SimulationError::from)?;
         let sim_sender = sim_controller.sender;
@@ -341,6 +472,9 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
                 Ok(msg) = self.env.orch_listener.recv::<EngineMsg>() => {
                     self.handle_orch_msg(msg).await?;
                 }
+                Some(msg) = self.engine_msg_recv.recv() => {
+                    self.handle_orch_msg(msg).await?;
+                }
                 terminate_res = &mut terminate_recv, if waiting_for_completion.is_none() => {
                     terminate_res.map_err(|err| Error::from(format!("Couldn't receive terminate: {:?}", err)))?;
                     log::trace!("Received terminate message");
@@ -381,6 +515,7 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
         sim_status_send: SimStatusSend,
         sim_status_recv: SimStatusRecv,
         terminate_recv: TerminateRecv,
+        engine_msg_recv: EngineMsgRecv,
     ) -> Self {
         ExperimentController {
             _exp_config: exp_config,
@@ -393,6 +528,8 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
             output_persistence_service_creator,
             sim_run_tasks: Default::default(),
             sim_senders: Default::default(),
+            final_states: Default::default(),
+            stopping: false,
             worker_pool_send_base,
             package_creators,
             sim_id_store,
@@ -400,6 +537,7 @@ impl<P: OutputPersistenceCreatorRepr> ExperimentController<P> {
             sim_status_send,
             sim_status_recv,
             terminate_recv,
+            engine_msg_recv,
         }
     }
 }
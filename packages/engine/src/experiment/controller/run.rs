@@ -1,18 +1,24 @@
 use std::{pin::Pin, sync::Arc, time::Duration};
 
-use super::{config, controller::ExperimentController, id_store::SimIdStore, Error, Result};
+use super::{
+    comms::engine_msg::{self, EngineMsgRecv},
+    config,
+    controller::ExperimentController,
+    id_store::SimIdStore,
+    Error, Result,
+};
 use crate::{
-    datastore::prelude::SharedStore,
+    datastore::{prelude::SharedStore, storage::segment::Backend as MemoryBackend},
     experiment::{
+        cleanup::CleanupGuard,
         controller::{config::OutputPersistenceConfig, sim_configurer::SimConfigurer},
         package::ExperimentPackage,
         Error as ExperimentError,
     },
     output::{
-        buffer::cleanup_experiment, local::LocalOutputPersistence, none::NoOutputPersistence,
-        OutputPersistenceCreatorRepr,
+        local::LocalOutputPersistence, none::NoOutputPersistence, OutputPersistenceCreatorRepr,
     },
-    proto::{EngineStatus, ExperimentRunTrait, PackageConfig},
+    proto::{EngineStatus, ExperimentRunTrait, ExtendedExperimentPackageConfig, PackageConfig},
     simulation::package::creator::PackageCreators,
     workerpool,
     workerpool::{comms::terminate::TerminateSend, WorkerPoolController},
@@ -22,12 +28,19 @@ use crate::{
 pub async fn run_experiment(exp_config: ExperimentConfig, env: Environment) -> Result<()> {
     let experiment_id = exp_config.id().clone();
     log::info!("Running experiment {}", experiment_id);
+    MemoryBackend::set_global(exp_config.memory_backend.clone())
+        .map_err(|e| Error::from(e.to_string()))?;
+    // Removes the memory segments of the experiment run when it finishes, and stops the experiment
+    // when the engine is stopped
+    let (engine_msg_send, engine_msg_recv) = engine_msg::new_pair();
+    let cleanup_guard =
+        CleanupGuard::new(&exp_config, engine_msg_send).map_err(|e| Error::from(e.to_string()))?;
     // TODO: Get cloud-specific configuration from `env`
     let _output_persistence_config = config::output_persistence(&env)?;
 
     // Keep another orchestrator client at the top level to send the final result
     let mut orch_client = env.orch_client.try_clone()?;
-    match tokio::spawn(run_local_experiment(exp_config, env)).await {
+    match tokio::spawn(run_local_experiment(exp_config, env, engine_msg_recv)).await {
         Ok(result) => {
             let final_result = match result {
                 Ok(()) => {
@@ -58,7 +71,7 @@ pub async fn run_experiment(exp_config: ExperimentConfig, env: Environment) -> R
         }
     }
 
-    cleanup_experiment(&experiment_id).map_err(|e| Error::from(e.to_string()))?;
+    drop(cleanup_guard);
 
     // Allow messages to be picked up.
     std::thread::sleep(std::time::Duration::from_millis(100));
@@ -66,17 +79,25 @@ pub async fn run_experiment(exp_config: ExperimentConfig, env: Environment) -> R
     Ok(())
 }
 
-pub async fn run_local_experiment(exp_config: ExperimentConfig, env: Environment) -> Result<()> {
+pub async fn run_local_experiment(
+    exp_config: ExperimentConfig,
+    env: Environment,
+    engine_msg_recv: EngineMsgRecv,
+) -> Result<()> {
     match config::output_persistence(&env)? {
         OutputPersistenceConfig::Local(local) => {
             log::debug!("Running experiment with local persistence");
-            let persistence = LocalOutputPersistence::new(exp_config.id().clone(), local.clone());
-            run_experiment_with_persistence(exp_config, env, persistence).await?;
+            let persistence = LocalOutputPersistence::new(
+                exp_config.id().clone(),
+                exp_config.run_id.to_string(),
+                local.clone(),
+            );
+            run_experiment_with_persistence(exp_config, env, persistence, engine_msg_recv).await?;
         }
         OutputPersistenceConfig::None => {
             log::debug!("Running experiment without output persistence");
             let persistence = NoOutputPersistence::new();
-            run_experiment_with_persistence(exp_config, env, persistence).await?;
+            run_experiment_with_persistence(exp_config, env, persistence, engine_msg_recv).await?;
         }
     };
     Ok(())
@@ -90,6 +111,7 @@ async fn run_experiment_with_persistence<P: OutputPersistenceCreatorRepr>(
     exp_config: ExperimentConfig,
     env: Environment,
     output_persistence_service_creator: P,
+    engine_msg_recv: EngineMsgRecv,
 ) -> Result<()> {
     let exp_config = Arc::new(exp_config);
     // Create the base config which can be used by the simulation engine
@@ -114,7 +136,7 @@ async fn run_experiment_with_persistence<P: OutputPersistenceCreatorRepr>(
             worker_pool_controller_send,
         )?;
 
-    // Start up the experiment package (simple/single)
+    // Start up the experiment package (simple/single/optimization)
     let experiment_package = ExperimentPackage::new(exp_config.clone())
         .await
         .map_err(|experiment_err| Error::from(experiment_err.to_string()))?;
@@ -122,10 +144,13 @@ async fn run_experiment_with_persistence<P: OutputPersistenceCreatorRepr>(
 
     let sim_id_store = SimIdStore::default();
     let package_config = match exp_config.run.package_config() {
-        PackageConfig::ExperimentPackageConfig(package_config) => package_config,
-        _ => unreachable!(),
+        PackageConfig::ExperimentPackageConfig(package_config) => {
+            ExtendedExperimentPackageConfig::Basic(package_config.clone())
+        }
+        PackageConfig::ExtendedExperimentPackageConfig(package_config) => package_config.clone(),
+        PackageConfig::EmptyPackageConfig => unreachable!(),
     };
-    let worker_allocator = SimConfigurer::new(package_config, exp_config.worker_pool.num_workers);
+    let worker_allocator = SimConfigurer::new(&package_config, exp_config.worker_pool.num_workers);
     let package_creators = PackageCreators::from_config(&exp_config.packages, &exp_config)?;
     let (sim_status_send, sim_status_recv) = super::comms::sim_status::new_pair();
     let mut orch_client = env.orch_client.try_clone()?;
@@ -147,6 +172,7 @@ async fn run_experiment_with_persistence<P: OutputPersistenceCreatorRepr>(
         sim_status_send,
         sim_status_recv,
         experiment_controller_terminate_recv,
+        engine_msg_recv,
     );
 
     // Get the experiment-level initialization payload for workers
@@ -3,9 +3,10 @@ use std::sync::Arc;
 use super::Result;
 use crate::{
     config::{
-        EngineConfig, ExperimentConfig, Globals, PersistenceConfig, StoreConfig, WorkerAllocation,
+        EngineConfig, ExperimentConfig, ForkConfig, Globals, PersistenceConfig, StoreConfig,
+        WorkerAllocation,
     },
-    proto::{ExperimentPackageConfig, SimulationShortId},
+    proto::{ExperimentPackageConfig, ExtendedExperimentPackageConfig, SimulationShortId},
     SimRunConfig,
 };
 
@@ -14,13 +15,27 @@ pub struct SimConfigurer {
 }
 
 impl SimConfigurer {
-    pub fn new(package_config: &ExperimentPackageConfig, num_workers: usize) -> SimConfigurer {
+    pub fn new(
+        package_config: &ExtendedExperimentPackageConfig,
+        num_workers: usize,
+    ) -> SimConfigurer {
         let num_workers_per_sim = match package_config {
-            ExperimentPackageConfig::Simple(config) => {
-                let num_runs = config.changed_properties.len();
+            ExtendedExperimentPackageConfig::Basic(ExperimentPackageConfig::Simple(config)) => {
+                let num_runs = config.changed_properties.len().max(1);
+                std::cmp::max(1, (num_workers as f64 / num_runs as f64).ceil() as usize)
+            }
+            ExtendedExperimentPackageConfig::Basic(ExperimentPackageConfig::SingleRun(_)) => {
+                std::cmp::max(1, num_workers)
+            }
+            ExtendedExperimentPackageConfig::Optimization(config) => {
+                let num_runs = config.num_parallel_runs.max(1);
+                std::cmp::max(1, (num_workers as f64 / num_runs as f64).ceil() as usize)
+            }
+            // The branches run in parallel after the shared run is finished
+            ExtendedExperimentPackageConfig::Fork(config) => {
+                let num_runs = config.payload.branches.len().max(1);
                 std::cmp::max(1, (num_workers as f64 / num_runs as f64).ceil() as usize)
             }
-            ExperimentPackageConfig::SingleRun(_) => std::cmp::max(1, num_workers),
         };
 
         SimConfigurer {
@@ -32,6 +47,7 @@ impl SimConfigurer {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn configure_next(
         &mut self,
         exp_config: &Arc<ExperimentConfig>,
@@ -40,6 +56,7 @@ impl SimConfigurer {
         store_config: StoreConfig,
         persistence_config: PersistenceConfig,
         max_num_steps: usize,
+        fork: ForkConfig,
     ) -> Result<SimRunConfig> {
         let worker_allocation = self.worker_allocator.next();
         let num_workers = worker_allocation.len();
@@ -55,6 +72,7 @@ impl SimConfigurer {
             store_config,
             persistence_config,
             max_num_steps,
+            fork,
         )?;
         Ok(config)
     }
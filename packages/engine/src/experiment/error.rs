@@ -47,6 +47,24 @@ pub enum Error {
     #[error("Invalid optimization experiment metric objective: {0:?}")]
     InvalidMetricObjective(Option<super::MetricObjective>),
 
+    #[error("Optimization experiment package data doesn't contain maximum number of steps")]
+    MissingMaxSteps,
+
+    #[error("Invalid maximum number of steps for optimization experiment: {0}")]
+    InvalidMaxSteps(i64),
+
+    #[error("Optimization experiment package data doesn't contain any fields to explore")]
+    MissingOptimizationFields,
+
+    #[error("Optimization experiment field {0:?} needs either non-empty `values` or a `range`")]
+    MissingFieldValues(String),
+
+    #[error("Optimization experiment field {name:?} has an invalid range: {range:?}")]
+    InvalidFieldRange { name: String, range: String },
+
+    #[error("Optimization experiment initial point is not a JSON object: {0}")]
+    InitialPointNotObject(SerdeValue),
+
     #[error("Python child process spawn")]
     PythonSpawn(std::io::Error),
 
@@ -56,6 +74,9 @@ pub enum Error {
     #[error("serde: {0:?}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Received Python message is not utf-8: {0:?}")]
     PythonNotUtf8(std::str::Utf8Error),
 
@@ -1,3 +1,4 @@
+pub mod cleanup;
 pub mod controller;
 mod error;
 pub mod package;
@@ -89,7 +90,20 @@ pub enum ExperimentControl {
         sim_id: proto::SimulationShortId,
         changed_properties: serde_json::Value,
         max_num_steps: usize,
+        /// Whether to keep the state the run ends with, so runs can be forked from it with
+        /// [`ExperimentControl::ForkSim`].
+        keep_final_state: bool,
     },
+    /// Starts a simulation run which continues from the state another run ended with, rather than
+    /// from the initial state. The changed properties are applied to the globals of that state.
+    ForkSim {
+        parent_id: proto::SimulationShortId,
+        sim_id: proto::SimulationShortId,
+        changed_properties: serde_json::Value,
+        max_num_steps: usize,
+    },
+    /// Drops the kept final state of a simulation run once no more runs are forked from it.
+    ReleaseFinalState(proto::SimulationShortId),
     PauseSim(proto::SimulationShortId),
     ResumeSim(proto::SimulationShortId),
     StopSim(proto::SimulationShortId),
@@ -97,22 +111,35 @@ pub enum ExperimentControl {
 
 pub fn init_exp_package(
     experiment_config: Arc<ExperimentConfig>,
-    exp_package_config: proto::ExperimentPackageConfig,
+    exp_package_config: proto::ExtendedExperimentPackageConfig,
     pkg_to_exp: ExpPkgCtlSend,
     exp_pkg_update_recv: ExpPkgUpdateRecv,
 ) -> Result<JoinHandle<Result<()>>> {
     let future = match exp_package_config {
-        proto::ExperimentPackageConfig::Simple(config) => {
+        proto::ExtendedExperimentPackageConfig::Basic(proto::ExperimentPackageConfig::Simple(
+            config,
+        )) => {
             let pkg = package::simple::SimpleExperiment::new(&experiment_config, config)?;
             tokio::spawn(async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await })
         }
-        proto::ExperimentPackageConfig::SingleRun(config) => {
+        proto::ExtendedExperimentPackageConfig::Basic(
+            proto::ExperimentPackageConfig::SingleRun(config),
+        ) => {
             let pkg = package::single::SingleRunExperiment::new(
                 &Arc::new(experiment_config.as_ref().into()),
                 config,
             )?;
             tokio::spawn(async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await })
         }
+        proto::ExtendedExperimentPackageConfig::Optimization(config) => {
+            let pkg =
+                package::optimization::OptimizationExperiment::new(&experiment_config, config)?;
+            tokio::spawn(async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await })
+        }
+        proto::ExtendedExperimentPackageConfig::Fork(config) => {
+            let pkg = package::fork::ForkExperiment::new(&experiment_config, config)?;
+            tokio::spawn(async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await })
+        }
     };
     Ok(future)
 }
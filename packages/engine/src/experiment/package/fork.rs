@@ -0,0 +1,122 @@
+use std::{collections::HashSet, sync::Arc};
+
+use super::super::{Error, ExperimentControl, Result};
+use crate::{
+    config::ExperimentConfig,
+    experiment::controller::comms::{exp_pkg_ctl::ExpPkgCtlSend, exp_pkg_update::ExpPkgUpdateRecv},
+    proto::{ForkExperimentConfig, SimulationShortId},
+};
+
+/// The simulation run all branches are forked from
+const SHARED_SIM_ID: SimulationShortId = 1;
+
+/// Runs the simulation up to the fork step once, then continues from that state in one simulation
+/// run per branch, each with its own changed properties.
+pub struct ForkExperiment {
+    experiment_config: Arc<ExperimentConfig>,
+    config: ForkExperimentConfig,
+}
+
+impl ForkExperiment {
+    pub fn new(
+        experiment_config: &Arc<ExperimentConfig>,
+        config: ForkExperimentConfig,
+    ) -> Result<ForkExperiment> {
+        if config.payload.fork_step >= config.payload.steps {
+            return Err(Error::from(format!(
+                "The fork step ({}) of experiment {} must be less than its number of steps ({})",
+                config.payload.fork_step, config.experiment_name, config.payload.steps
+            )));
+        }
+        if config.payload.fork_step < experiment_config.start_step() {
+            return Err(Error::from(format!(
+                "The fork step ({}) of experiment {} is before the step of the restored \
+                 checkpoint ({})",
+                config.payload.fork_step,
+                config.experiment_name,
+                experiment_config.start_step()
+            )));
+        }
+        Ok(ForkExperiment {
+            experiment_config: experiment_config.clone(),
+            config,
+        })
+    }
+
+    pub async fn run(
+        self,
+        mut pkg_to_exp: ExpPkgCtlSend,
+        mut exp_pkg_update_recv: ExpPkgUpdateRecv,
+    ) -> Result<()> {
+        let fork_step = self.config.payload.fork_step;
+        pkg_to_exp
+            .send(ExperimentControl::StartSim {
+                sim_id: SHARED_SIM_ID,
+                changed_properties: serde_json::Map::new().into(), // Don't change properties
+                // Simulation runs take one step less than `max_num_steps`
+                max_num_steps: fork_step + 1,
+                keep_final_state: true,
+            })
+            .await?;
+
+        // Wait for the shared run to reach the fork step
+        let mut shared_steps = self.experiment_config.start_step();
+        loop {
+            let response = exp_pkg_update_recv.recv().await.ok_or_else(|| {
+                Error::ExperimentRecv(
+                    "Experiment main loop closed when experiment package was still running".into(),
+                )
+            })?;
+            if response.sim_id != SHARED_SIM_ID {
+                log::warn!("Update from unknown simulation run {}", response.sim_id);
+                continue;
+            }
+            if response.was_error {
+                log::warn!("The shared simulation run failed before it could be forked");
+                return Ok(());
+            }
+            if response.stop_signal {
+                break;
+            }
+            shared_steps += 1;
+        }
+        if shared_steps < fork_step {
+            log::warn!(
+                "The shared simulation run stopped at step {shared_steps}, before reaching the \
+                 fork step {fork_step}"
+            );
+            return Ok(());
+        }
+
+        let mut remaining = HashSet::new();
+        for (branch_index, changed_properties) in self.config.payload.branches.iter().enumerate() {
+            let sim_id = SHARED_SIM_ID + 1 + branch_index as SimulationShortId;
+            remaining.insert(sim_id);
+            pkg_to_exp
+                .send(ExperimentControl::ForkSim {
+                    parent_id: SHARED_SIM_ID,
+                    sim_id,
+                    changed_properties: changed_properties.clone(),
+                    max_num_steps: self.config.payload.steps,
+                })
+                .await?;
+        }
+        // All branches are started from the state, so it needn't be kept any longer
+        pkg_to_exp
+            .send(ExperimentControl::ReleaseFinalState(SHARED_SIM_ID))
+            .await?;
+
+        while !remaining.is_empty() {
+            let response = exp_pkg_update_recv.recv().await.ok_or_else(|| {
+                Error::ExperimentRecv(
+                    "Experiment main loop closed when experiment package was still running".into(),
+                )
+            })?;
+            if response.was_error || response.stop_signal {
+                remaining.remove(&response.sim_id);
+            }
+        }
+        log::debug!("Experiment package exiting");
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
+pub mod fork;
+pub mod optimization;
 pub mod simple;
 pub mod single;
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use tokio::task::JoinHandle;
 
@@ -12,12 +17,18 @@ use super::{
 use crate::{
     config::ExperimentConfig,
     init_exp_package,
-    proto::{ExperimentRunTrait, PackageConfig, SimulationShortId},
+    proto::{
+        ExperimentPackageConfig, ExperimentRunTrait, ExtendedExperimentPackageConfig,
+        PackageConfig, SimulationShortId,
+    },
+    simulation::package::output::packages::analysis::AnalysisOutput,
 };
 
 pub struct ExperimentPackageComms {
     pub step_update_sender: ExpPkgUpdateSend,
     pub ctl_recv: ExpPkgCtlRecv,
+    /// Passed to every simulation run, which only sends the selected analysis outputs
+    pub analysis_outputs: Arc<AnalysisOutputSelection>,
 }
 
 pub struct ExperimentPackage {
@@ -29,29 +40,129 @@ impl ExperimentPackage {
     pub async fn new(exp_config: Arc<ExperimentConfig>) -> Result<ExperimentPackage> {
         let (ctl_send, ctl_recv) = super::controller::comms::exp_pkg_ctl::new_pair();
         let package_config = match exp_config.run.package_config() {
-            PackageConfig::ExperimentPackageConfig(package_config) => package_config,
-            _ => unreachable!(),
+            PackageConfig::ExperimentPackageConfig(package_config) => {
+                ExtendedExperimentPackageConfig::Basic(package_config.clone())
+            }
+            PackageConfig::ExtendedExperimentPackageConfig(package_config) => {
+                package_config.clone()
+            }
+            PackageConfig::EmptyPackageConfig => unreachable!(),
         };
         let (step_update_sender, exp_pkg_update_recv) =
             super::controller::comms::exp_pkg_update::new_pair();
+        let analysis_outputs = Arc::new(AnalysisOutputSelection::for_package(&package_config));
         let join_handle = init_exp_package(
             exp_config.clone(),
-            package_config.clone(),
+            package_config,
             ctl_send,
             exp_pkg_update_recv,
         )?;
         let comms = ExperimentPackageComms {
             step_update_sender,
             ctl_recv,
+            analysis_outputs,
         };
 
         Ok(ExperimentPackage { join_handle, comms })
     }
 }
 
+/// The analysis outputs an experiment package receives with every [`StepUpdate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisOutputSelection {
+    None,
+    All,
+    Named(HashSet<String>),
+}
+
+impl AnalysisOutputSelection {
+    pub fn for_package(package_config: &ExtendedExperimentPackageConfig) -> Self {
+        match package_config {
+            ExtendedExperimentPackageConfig::Basic(
+                ExperimentPackageConfig::Simple(_) | ExperimentPackageConfig::SingleRun(_),
+            )
+            | ExtendedExperimentPackageConfig::Fork(_) => AnalysisOutputSelection::None,
+            ExtendedExperimentPackageConfig::Optimization(config) => {
+                match &config.payload.metric_name {
+                    Some(metric_name) => AnalysisOutputSelection::Named(
+                        std::iter::once(metric_name.clone()).collect(),
+                    ),
+                    None => AnalysisOutputSelection::None,
+                }
+            }
+        }
+    }
+
+    /// Copies the selected outputs, only those are cloned.
+    ///
+    /// Returns `None` if no outputs were selected.
+    pub fn select(&self, output: Option<&AnalysisOutput>) -> Option<AnalysisOutput> {
+        let output = output?;
+        let inner: HashMap<_, _> = match self {
+            AnalysisOutputSelection::None => return None,
+            AnalysisOutputSelection::All => output.inner.clone(),
+            AnalysisOutputSelection::Named(names) => output
+                .inner
+                .iter()
+                .filter(|(name, _)| names.contains(name.as_str()))
+                .map(|(name, value)| (Arc::clone(name), value.clone()))
+                .collect(),
+        };
+        (!inner.is_empty()).then(|| AnalysisOutput { inner })
+    }
+}
+
 #[derive(Debug)]
 pub struct StepUpdate {
     pub sim_id: SimulationShortId,
     pub was_error: bool,
     pub stop_signal: bool,
+    /// The selected analysis outputs of the latest step of the simulation run (see
+    /// [`AnalysisOutputSelection`]). Updates with `stop_signal` set carry the outputs of the last
+    /// step which was run.
+    pub analysis_output: Option<AnalysisOutput>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::package::output::packages::analysis::AnalysisSingleOutput;
+
+    fn output() -> AnalysisOutput {
+        AnalysisOutput {
+            inner: HashMap::from([
+                (
+                    Arc::new("infected".to_string()),
+                    AnalysisSingleOutput::some_number(3.0),
+                ),
+                (
+                    Arc::new("recovered".to_string()),
+                    AnalysisSingleOutput::some_number(5.0),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn selects_analysis_outputs() {
+        let output = output();
+
+        assert_eq!(AnalysisOutputSelection::None.select(Some(&output)), None);
+        assert_eq!(
+            AnalysisOutputSelection::All.select(Some(&output)),
+            Some(output.clone())
+        );
+        assert_eq!(AnalysisOutputSelection::All.select(None), None);
+
+        let named = AnalysisOutputSelection::Named(HashSet::from(["infected".to_string()]));
+        let selected = named.select(Some(&output)).unwrap();
+        assert_eq!(selected.inner.len(), 1);
+        assert_eq!(
+            selected.inner[&Arc::new("infected".to_string())],
+            AnalysisSingleOutput::some_number(3.0)
+        );
+
+        let missing = AnalysisOutputSelection::Named(HashSet::from(["deceased".to_string()]));
+        assert_eq!(missing.select(Some(&output)), None);
+    }
 }
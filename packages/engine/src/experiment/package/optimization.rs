@@ -0,0 +1,532 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde_json::Value as SerdeValue;
+
+use super::super::{Error, ExperimentControl, MetricObjective, Result};
+use crate::{
+    config::ExperimentConfig,
+    experiment::controller::comms::{exp_pkg_ctl::ExpPkgCtlSend, exp_pkg_update::ExpPkgUpdateRecv},
+    proto::{OptimizationExperimentConfig, PackageDataField, SerdeMap, SimulationShortId},
+    simulation::{
+        package::output::packages::analysis::{AnalysisOutput, AnalysisSingleOutput},
+        seed::{derive_seed, OPTIMIZER_STREAM},
+    },
+};
+
+/// Probability of proposing a completely random point once the search has become adaptive, so
+/// that the search doesn't get stuck around a local optimum.
+const EXPLORATION_PROBABILITY: f64 = 0.1;
+/// Probability of keeping the value of the best point for a field with discrete values.
+const KEEP_BEST_VALUE_PROBABILITY: f64 = 0.7;
+/// Fraction of a range which is searched around the best point for the first adaptive proposal.
+const INITIAL_SEARCH_RADIUS: f64 = 0.5;
+/// Factor the search radius shrinks by for every adaptive proposal.
+const SEARCH_RADIUS_DECAY: f64 = 0.9;
+const MIN_SEARCH_RADIUS: f64 = 0.02;
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldSpace {
+    Values(Vec<SerdeValue>),
+    Range { start: f64, end: f64, integer: bool },
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    space: FieldSpace,
+}
+
+impl Field {
+    fn from_package_data(field: &PackageDataField) -> Result<Field> {
+        let space = match (&field.values, &field.range) {
+            (Some(values), _) if !values.is_empty() => FieldSpace::Values(values.clone()),
+            (_, Some(range)) => parse_range(&field.name, range)?,
+            _ => return Err(Error::MissingFieldValues(field.name.clone())),
+        };
+        Ok(Field {
+            name: field.name.clone(),
+            space,
+        })
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> SerdeValue {
+        match &self.space {
+            FieldSpace::Values(values) => values
+                .choose(rng)
+                .cloned()
+                .expect("Values are checked to be non-empty"),
+            FieldSpace::Range {
+                start,
+                end,
+                integer,
+            } => range_value(rng.gen_range(*start..=*end), *integer),
+        }
+    }
+
+    /// Proposes a value close to `best`, where `radius` is the fraction of the range to search in.
+    fn sample_around(&self, best: &SerdeValue, radius: f64, rng: &mut StdRng) -> SerdeValue {
+        match &self.space {
+            FieldSpace::Values(values) => {
+                if values.contains(best) && rng.gen_bool(KEEP_BEST_VALUE_PROBABILITY) {
+                    best.clone()
+                } else {
+                    self.sample(rng)
+                }
+            }
+            FieldSpace::Range {
+                start,
+                end,
+                integer,
+            } => match best.as_f64() {
+                Some(best) => {
+                    let width = (end - start) * radius;
+                    let value = best + rng.gen_range(-1.0..=1.0) * width;
+                    range_value(value.clamp(*start, *end), *integer)
+                }
+                None => self.sample(rng),
+            },
+        }
+    }
+}
+
+fn range_value(value: f64, integer: bool) -> SerdeValue {
+    if integer {
+        (value.round() as i64).into()
+    } else {
+        value.into()
+    }
+}
+
+/// Parses a range of the form `"<start>-<end>"`, e.g. `"0-10"`, `"-1-1"` or `"0.5-1e-3"`.
+///
+/// The range is treated as integer if both bounds are integers.
+fn parse_range(name: &str, range: &str) -> Result<FieldSpace> {
+    let invalid_range = || Error::InvalidFieldRange {
+        name: name.to_string(),
+        range: range.to_string(),
+    };
+    let range = range.trim();
+    // The separator is the first `-` which isn't a sign, i.e. it follows a digit or a dot.
+    let separator = range
+        .char_indices()
+        .skip(1)
+        .find(|(index, c)| {
+            *c == '-'
+                && range[..*index]
+                    .chars()
+                    .last()
+                    .map_or(false, |prev| prev.is_ascii_digit() || prev == '.')
+        })
+        .map(|(index, _)| index)
+        .ok_or_else(invalid_range)?;
+    let (start, end) = (range[..separator].trim(), range[separator + 1..].trim());
+    let integer = start.parse::<i64>().is_ok() && end.parse::<i64>().is_ok();
+    let start = start.parse::<f64>().map_err(|_| invalid_range())?;
+    let end = end.parse::<f64>().map_err(|_| invalid_range())?;
+    if !start.is_finite() || !end.is_finite() || start > end {
+        return Err(invalid_range());
+    }
+    Ok(FieldSpace::Range {
+        start,
+        end,
+        integer,
+    })
+}
+
+struct Observation {
+    point: SerdeMap,
+    metric: f64,
+}
+
+/// Proposes new points in the parameter space based on the metrics of previous runs.
+///
+/// The first points are the user-supplied initial points followed by uniformly sampled points.
+/// After that, points are mostly sampled around the best point found so far with a shrinking
+/// search radius.
+struct Optimizer {
+    fields: Vec<Field>,
+    objective: MetricObjective,
+    initial_points: VecDeque<SerdeMap>,
+    num_random_points: usize,
+    num_proposed: usize,
+    num_adaptive: i32,
+    observations: Vec<Observation>,
+    rng: StdRng,
+}
+
+impl Optimizer {
+    fn new(
+        fields: Vec<Field>,
+        objective: MetricObjective,
+        initial_points: VecDeque<SerdeMap>,
+        num_random_points: usize,
+        rng: StdRng,
+    ) -> Optimizer {
+        Optimizer {
+            fields,
+            objective,
+            initial_points,
+            num_random_points,
+            num_proposed: 0,
+            num_adaptive: 0,
+            observations: Vec::new(),
+            rng,
+        }
+    }
+
+    fn is_better(&self, metric: f64, other: f64) -> bool {
+        match self.objective {
+            MetricObjective::Min => metric < other,
+            _ => metric > other,
+        }
+    }
+
+    fn best(&self) -> Option<&Observation> {
+        self.observations
+            .iter()
+            .fold(None, |best, observation| match best {
+                Some(best) if !self.is_better(observation.metric, best.metric) => Some(best),
+                _ => Some(observation),
+            })
+    }
+
+    fn observe(&mut self, point: SerdeMap, metric: f64) {
+        self.observations.push(Observation { point, metric });
+    }
+
+    fn propose(&mut self) -> SerdeMap {
+        self.num_proposed += 1;
+        if let Some(mut point) = self.initial_points.pop_front() {
+            // Fill in fields which weren't specified in the initial point
+            for field in &self.fields {
+                if !point.contains_key(&field.name) {
+                    point.insert(field.name.clone(), field.sample(&mut self.rng));
+                }
+            }
+            return point;
+        }
+
+        let explore = self.num_proposed <= self.num_random_points
+            || self.rng.gen_bool(EXPLORATION_PROBABILITY);
+        let best = if explore {
+            None
+        } else {
+            self.best().map(|best| best.point.clone())
+        };
+        let best = match best {
+            Some(best) => best,
+            None => {
+                return self
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.sample(&mut self.rng)))
+                    .collect();
+            }
+        };
+
+        let radius = (INITIAL_SEARCH_RADIUS * SEARCH_RADIUS_DECAY.powi(self.num_adaptive))
+            .max(MIN_SEARCH_RADIUS);
+        self.num_adaptive += 1;
+        self.fields
+            .iter()
+            .map(|field| {
+                let value = match best.get(&field.name) {
+                    Some(best_value) => field.sample_around(best_value, radius, &mut self.rng),
+                    None => field.sample(&mut self.rng),
+                };
+                (field.name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+struct RunProgress {
+    point: SerdeMap,
+    n_steps: usize,
+    latest_metric: Option<f64>,
+    finished: bool,
+}
+
+pub struct OptimizationExperiment {
+    _experiment_config: Arc<ExperimentConfig>,
+    experiment_name: String,
+    metric_name: String,
+    max_runs: usize,
+    max_steps: usize,
+    min_steps: usize,
+    num_parallel_runs: usize,
+    optimizer: Optimizer,
+}
+
+impl OptimizationExperiment {
+    pub fn new(
+        experiment_config: &Arc<ExperimentConfig>,
+        config: OptimizationExperimentConfig,
+    ) -> Result<OptimizationExperiment> {
+        let payload = config.payload;
+        let metric_name = payload.metric_name.ok_or(Error::MissingMetricName)?;
+        let objective = match payload.metric_objective {
+            Some(MetricObjective::Max) => MetricObjective::Max,
+            Some(MetricObjective::Min) => MetricObjective::Min,
+            objective => return Err(Error::InvalidMetricObjective(objective)),
+        };
+        let max_runs = match payload.max_runs.ok_or(Error::MissingMaxRuns)? {
+            max_runs if max_runs > 0 => max_runs as usize,
+            max_runs => return Err(Error::InvalidMaxRuns(max_runs)),
+        };
+        let max_steps = match payload.max_steps.ok_or(Error::MissingMaxSteps)? {
+            max_steps if max_steps > 0 => max_steps as usize,
+            max_steps => return Err(Error::InvalidMaxSteps(max_steps)),
+        };
+        let min_steps = payload.min_steps.unwrap_or(0).clamp(0, max_steps as i64) as usize;
+
+        let fields = payload
+            .fields
+            .unwrap_or_default()
+            .iter()
+            .map(Field::from_package_data)
+            .collect::<Result<Vec<_>>>()?;
+        if fields.is_empty() {
+            return Err(Error::MissingOptimizationFields);
+        }
+        let initial_points = payload
+            .initial_points
+            .unwrap_or_default()
+            .into_iter()
+            .map(|point| match point {
+                SerdeValue::Object(point) => Ok(point),
+                point => Err(Error::InitialPointNotObject(point)),
+            })
+            .collect::<Result<VecDeque<_>>>()?;
+
+        let num_parallel_runs = std::cmp::max(1, config.num_parallel_runs);
+        // Sample the space uniformly at first, so that there is something to adapt to
+        let num_random_points = initial_points.len() + std::cmp::max(fields.len() + 1, 3);
+        let optimizer = Optimizer::new(
+            fields,
+            objective,
+            initial_points,
+            num_random_points,
+            StdRng::seed_from_u64(derive_seed(experiment_config.seed, OPTIMIZER_STREAM)),
+        );
+
+        Ok(OptimizationExperiment {
+            _experiment_config: experiment_config.clone(),
+            experiment_name: config.experiment_name,
+            metric_name,
+            max_runs,
+            max_steps,
+            min_steps,
+            num_parallel_runs,
+            optimizer,
+        })
+    }
+
+    fn metric_from_output(&self, output: &AnalysisOutput) -> Option<f64> {
+        match output
+            .inner
+            .iter()
+            .find(|(name, _)| name.as_str() == self.metric_name)
+            .map(|(_, output)| output)
+        {
+            Some(AnalysisSingleOutput::Number(metric)) => *metric,
+            Some(AnalysisSingleOutput::Vec(_)) => {
+                log::warn!(
+                    "Optimization metric {:?} must be a single number, not a list",
+                    self.metric_name
+                );
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn start_next_run(
+        &mut self,
+        sim_id: SimulationShortId,
+        runs: &mut HashMap<SimulationShortId, RunProgress>,
+        pkg_to_exp: &mut ExpPkgCtlSend,
+    ) -> Result<()> {
+        let point = self.optimizer.propose();
+        log::debug!("Starting optimization run {} with {:?}", sim_id, point);
+        let msg = ExperimentControl::StartSim {
+            sim_id,
+            changed_properties: SerdeValue::Object(point.clone()),
+            max_num_steps: self.max_steps,
+            keep_final_state: false,
+        };
+        runs.insert(sim_id, RunProgress {
+            point,
+            n_steps: 0,
+            latest_metric: None,
+            finished: false,
+        });
+        pkg_to_exp.send(msg).await?;
+        Ok(())
+    }
+
+    pub async fn run(
+        mut self,
+        mut pkg_to_exp: ExpPkgCtlSend,
+        mut exp_pkg_update_recv: ExpPkgUpdateRecv,
+    ) -> Result<()> {
+        let mut runs = HashMap::new();
+        // We sometimes use 0 as a default/null value, therefore it's not a valid SimulationShortId
+        let mut next_sim_id: SimulationShortId = 1;
+        let mut n_active = 0;
+
+        while (next_sim_id as usize) <= std::cmp::min(self.num_parallel_runs, self.max_runs) {
+            self.start_next_run(next_sim_id, &mut runs, &mut pkg_to_exp)
+                .await?;
+            next_sim_id += 1;
+            n_active += 1;
+        }
+
+        while n_active > 0 {
+            let response = exp_pkg_update_recv.recv().await.ok_or_else(|| {
+                Error::ExperimentRecv(
+                    "Experiment main loop closed when experiment package was still running".into(),
+                )
+            })?;
+
+            let metric = response
+                .analysis_output
+                .as_ref()
+                .and_then(|output| self.metric_from_output(output));
+            let run = runs
+                .get_mut(&response.sim_id)
+                .ok_or(Error::MissingSimulationRun(response.sim_id))?;
+            if run.finished {
+                continue;
+            }
+            if metric.is_some() {
+                run.latest_metric = metric;
+            }
+            if !(response.was_error || response.stop_signal) {
+                run.n_steps += 1;
+                continue;
+            }
+
+            run.finished = true;
+            n_active -= 1;
+            if response.was_error {
+                log::warn!(
+                    "Optimization run {} failed, its result won't be used",
+                    response.sim_id
+                );
+            } else if run.n_steps < self.min_steps {
+                log::warn!(
+                    "Optimization run {} stopped after {} steps, but should run for at least {} \
+                     steps, its result won't be used",
+                    response.sim_id,
+                    run.n_steps,
+                    self.min_steps
+                );
+            } else if let Some(metric) = run.latest_metric {
+                log::debug!(
+                    "Optimization run {} finished with {} = {}",
+                    response.sim_id,
+                    self.metric_name,
+                    metric
+                );
+                self.optimizer.observe(run.point.clone(), metric);
+            } else {
+                log::warn!(
+                    "Optimization run {} finished without a value for the metric {:?}",
+                    response.sim_id,
+                    self.metric_name
+                );
+            }
+
+            if (next_sim_id as usize) <= self.max_runs {
+                self.start_next_run(next_sim_id, &mut runs, &mut pkg_to_exp)
+                    .await?;
+                next_sim_id += 1;
+                n_active += 1;
+            }
+        }
+
+        match self.optimizer.best() {
+            Some(best) => log::info!(
+                "Optimization experiment {:?} finished, best {} = {} with {:?}",
+                self.experiment_name,
+                self.metric_name,
+                best.metric,
+                best.point
+            ),
+            None => log::warn!(
+                "Optimization experiment {:?} finished without any valid runs",
+                self.experiment_name
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: f64, end: f64, integer: bool) -> FieldSpace {
+        FieldSpace::Range {
+            start,
+            end,
+            integer,
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("a", "0-10").unwrap(), range(0.0, 10.0, true));
+        assert_eq!(parse_range("a", "-5--1").unwrap(), range(-5.0, -1.0, true));
+        assert_eq!(
+            parse_range("a", "0.5 - 1.5").unwrap(),
+            range(0.5, 1.5, false)
+        );
+        assert_eq!(parse_range("a", "1e-3-1").unwrap(), range(1e-3, 1.0, false));
+        assert!(parse_range("a", "10").is_err());
+        assert!(parse_range("a", "10-1").is_err());
+        assert!(parse_range("a", "a-b").is_err());
+    }
+
+    #[test]
+    fn proposals_stay_in_bounds_and_converge() {
+        let fields = vec![
+            Field {
+                name: "x".into(),
+                space: range(-10.0, 10.0, false),
+            },
+            Field {
+                name: "mode".into(),
+                space: FieldSpace::Values(vec!["a".into(), "b".into()]),
+            },
+        ];
+        let mut optimizer = Optimizer::new(
+            fields,
+            MetricObjective::Min,
+            VecDeque::new(),
+            3,
+            StdRng::seed_from_u64(0),
+        );
+        for _ in 0..100 {
+            let point = optimizer.propose();
+            let x = point["x"].as_f64().unwrap();
+            assert!((-10.0..=10.0).contains(&x));
+            let mode = point["mode"].as_str().unwrap();
+            assert!(mode == "a" || mode == "b");
+            let metric = (x - 3.0).powi(2) + if mode == "a" { 0.0 } else { 5.0 };
+            optimizer.observe(point, metric);
+        }
+        let best = optimizer.best().unwrap();
+        assert!(
+            best.metric < 1.0,
+            "best metric {} is too large",
+            best.metric
+        );
+        assert_eq!(best.point["mode"], "a");
+    }
+}
@@ -54,6 +54,7 @@ impl SimpleExperiment {
                 sim_id: sim_id as SimulationShortId,
                 changed_properties: changed_properties.clone(),
                 max_num_steps,
+                keep_final_state: false,
             };
             pkg_to_exp.send(msg).await?;
         }
@@ -32,6 +32,7 @@ impl SingleRunExperiment {
             sim_id: 1 as SimulationShortId,
             changed_properties: serde_json::Map::new().into(), // Don't change properties
             max_num_steps: self.config.num_steps,
+            keep_final_state: false,
         };
         pkg_to_exp.send(msg).await?;
 
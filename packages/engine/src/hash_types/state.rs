@@ -15,7 +15,7 @@ use super::{
     message::{self},
     Vec3,
 };
-use crate::config::globals::Globals;
+use crate::{config::globals::Globals, simulation::seed::AgentIdGenerator};
 
 #[allow(clippy::module_name_repetitions)]
 pub type SimulationState = Vec<Agent>;
@@ -53,7 +53,6 @@ pub struct Context<'a> {
 
 #[derive(Clone, Serialize, Debug, PartialEq)]
 pub struct Agent {
-    #[serde(default = "generate_agent_id")]
     pub agent_id: String,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -319,9 +318,6 @@ impl<'de> Deserialize<'de> for Agent {
                         }
                     }
                 }
-                if agent_state_buf.agent_id.is_empty() {
-                    agent_state_buf.agent_id = generate_agent_id();
-                }
                 if let Some(messages) = held_messages {
                     agent_state_buf.messages = messages.consume::<V::Error>(&agent_state_buf)?;
                 }
@@ -398,12 +394,10 @@ fn deserialize_duplicate_agent_state_field() {
     );
 }
 
+/// The agent doesn't have an id until it's given one by [`Agent::assign_missing_id`].
 impl Default for Agent {
     fn default() -> Self {
-        Self {
-            agent_id: generate_agent_id(),
-            ..Agent::empty()
-        }
+        Agent::empty()
     }
 }
 
@@ -436,6 +430,23 @@ where
 }
 
 impl Agent {
+    /// `assign_missing_id` gives the agent the next id of `ids` if it was created without one.
+    ///
+    /// `remove_agent` messages the agent sent before it had an id are pointed at the new id.
+    pub fn assign_missing_id(&mut self, ids: &mut AgentIdGenerator) {
+        if !self.agent_id.is_empty() {
+            return;
+        }
+        self.agent_id = ids.next_id();
+        for message in &mut self.messages {
+            if let message::Outbound::RemoveAgent(remove) = message {
+                if remove.data.agent_id.is_empty() {
+                    remove.data.agent_id = self.agent_id.clone();
+                }
+            }
+        }
+    }
+
     /// `delete_custom` removes a custom field from the agent state entirely
     pub fn delete_custom(&mut self, key: &str) {
         self.custom.remove(key);
@@ -643,8 +654,8 @@ impl Agent {
     #[must_use]
     pub fn child(&self) -> Self {
         Agent {
-            // children get a new uuid
-            agent_id: generate_agent_id(),
+            // children get a new id from the simulation run when they are created
+            agent_id: String::new(),
             // children do not get the same name
             agent_name: None,
             // children do not inherit messages
@@ -784,10 +795,6 @@ impl Deref for Name {
     }
 }
 
-fn generate_agent_id() -> String {
-    uuid::Uuid::new_v4().to_string()
-}
-
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -820,8 +827,10 @@ mod tests {
     fn test_empty_state() {
         let json = "{}";
 
-        let agent: Agent = serde_json::from_str(&json).unwrap();
+        let mut agent: Agent = serde_json::from_str(&json).unwrap();
+        assert!(agent.agent_id.is_empty());
 
+        agent.assign_missing_id(&mut AgentIdGenerator::new(0));
         assert_eq!(agent.agent_id.len(), 36);
     }
 
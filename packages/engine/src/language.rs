@@ -1,7 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 
 use super::error::{Error, Result};
+use crate::proto::ExternalRunnerConfig;
 
 /// Supported languages
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Copy, Clone, Debug, Display)]
@@ -9,12 +12,21 @@ pub enum Language {
     JavaScript = 0,
     Python = 1,
     Rust = 2,
+    Wasm = 3,
+    /// Behaviors executed by one of the project's external runners, see
+    /// [`ExternalRunnerConfig`].
+    External = 4,
 }
 
 impl Language {
-    pub const NUM: usize = 3;
-    pub const ORDERED: [Language; Self::NUM] =
-        [Language::JavaScript, Language::Python, Language::Rust];
+    pub const NUM: usize = 5;
+    pub const ORDERED: [Language; Self::NUM] = [
+        Language::JavaScript,
+        Language::Python,
+        Language::Rust,
+        Language::Wasm,
+        Language::External,
+    ];
 
     pub fn as_index(self) -> usize {
         self as usize
@@ -24,7 +36,14 @@ impl Language {
         Self::ORDERED[i]
     }
 
-    pub fn from_file_name(file_name: &str) -> Result<Language> {
+    /// Returns the language of the behavior with the given file name.
+    ///
+    /// Extensions the engine doesn't support itself are only accepted if one of the
+    /// `external_runners` of the project is configured for them.
+    pub fn from_file_name(
+        file_name: &str,
+        external_runners: &HashMap<String, ExternalRunnerConfig>,
+    ) -> Result<Language> {
         if !(file_name.contains('.') || file_name.contains('/')) {
             // This is so we're on-par w/ the web-engine, see `extract_hash_builtin` in
             // the hash repo
@@ -37,7 +56,43 @@ impl Language {
             Some("py") => Ok(Language::Python),
             Some("js") => Ok(Language::JavaScript),
             Some("rs") => Ok(Language::Rust),
-            _ => Err(Error::ParseBehavior(file_name.to_string())),
+            Some("wasm") => Ok(Language::Wasm),
+            Some(extension) if external_runners.contains_key(extension) => Ok(Language::External),
+            Some(_) => Err(Error::NoExternalRunner(file_name.to_string())),
+            None => Err(Error::ParseBehavior(file_name.to_string())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_configured_extensions_are_external() {
+        let runners: HashMap<_, _> = [("R".to_string(), ExternalRunnerConfig {
+            command: "Rscript".to_string(),
+            args: Vec::new(),
+            working_dir: None,
+        })]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            Language::from_file_name("move.js", &runners).unwrap(),
+            Language::JavaScript
+        );
+        assert_eq!(
+            Language::from_file_name("move.R", &runners).unwrap(),
+            Language::External
+        );
+        assert!(matches!(
+            Language::from_file_name("move.jl", &runners),
+            Err(Error::NoExternalRunner(name)) if name == "move.jl"
+        ));
+        assert!(matches!(
+            Language::from_file_name("move.R", &HashMap::new()),
+            Err(Error::NoExternalRunner(_))
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+
+use super::RELATIVE_PARTS_FOLDER;
+use crate::output::error::Result;
+
+/// The folder the output parts of all simulation runs of the experiment run are written to,
+/// relative to the working directory of the engine.
+///
+/// Every experiment run has its own folder, so runs of the same experiment don't remove each
+/// other's parts.
+pub fn parts_folder(experiment_id: &str, experiment_run_id: &str) -> PathBuf {
+    PathBuf::from(RELATIVE_PARTS_FOLDER)
+        .join(experiment_id)
+        .join(experiment_run_id)
+}
+
+/// Removes the output parts of an experiment run, i.e. its [`parts_folder`].
+///
+/// Memory segments and parts are cleaned up together in
+/// [`experiment::cleanup`](crate::experiment::cleanup).
+pub fn remove_run_parts(parts_folder: &Path) -> Result<()> {
+    log::trace!("Removing all parts files in: {parts_folder:?}");
+    std::fs::remove_dir_all(parts_folder)?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+pub mod config;
+pub mod result;
+mod sim;
+
+use self::{config::LocalPersistenceConfig, sim::LocalSimulationOutputPersistence};
+use super::{buffer::Buffers, OutputPersistenceCreatorRepr};
+use crate::{
+    config::PersistenceConfig,
+    output::error::Result,
+    proto::{ExperimentId, ExperimentRegisteredId, SimulationShortId},
+};
+
+#[derive(derive_new::new)]
+pub struct LocalOutputPersistence {
+    exp_id: ExperimentRegisteredId,
+    /// ID of the experiment run, which the output parts are kept apart by
+    exp_run_id: ExperimentId,
+    config: LocalPersistenceConfig,
+}
+
+impl OutputPersistenceCreatorRepr for LocalOutputPersistence {
+    type SimulationOutputPersistence = LocalSimulationOutputPersistence;
+
+    fn new_simulation(
+        &self,
+        sim_id: SimulationShortId,
+        persistence_config: &PersistenceConfig,
+    ) -> Result<Self::SimulationOutputPersistence> {
+        let buffers = Buffers::new(
+            self.exp_id.clone(),
+            self.exp_run_id.clone(),
+            sim_id,
+            &persistence_config.output_config,
+        )?;
+        Ok(LocalSimulationOutputPersistence::new(
+            self.exp_id.clone(),
+            sim_id,
+            buffers,
+            self.config.clone(),
+        ))
+    }
+}
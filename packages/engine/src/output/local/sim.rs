@@ -0,0 +1,190 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use super::{config::LocalPersistenceConfig, result::LocalPersistenceResult};
+use crate::{
+    config::{
+        checkpoint::{AGENTS_FILE_NAME, MESSAGES_FILE_NAME, METADATA_FILE_NAME},
+        CheckpointData,
+    },
+    output::{buffer::Buffers, error::Result, SimulationOutputPersistenceRepr},
+    proto::{ExperimentRegisteredId, SimulationShortId},
+    simulation::{package::output::packages::Output, step_output::SimulationStepOutput},
+    SimRunConfig,
+};
+
+#[derive(derive_new::new)]
+pub struct LocalSimulationOutputPersistence {
+    exp_id: ExperimentRegisteredId,
+    sim_id: SimulationShortId,
+    // TODO: Should this be unused? If so remove
+    buffers: Buffers,
+    config: LocalPersistenceConfig,
+    #[new(default)]
+    stop_message: Option<serde_json::Value>,
+}
+
+impl LocalSimulationOutputPersistence {
+    fn sim_output_path(&self) -> PathBuf {
+        self.output_path(self.sim_id)
+    }
+
+    fn output_path(&self, sim_id: SimulationShortId) -> PathBuf {
+        self.config
+            .output_folder
+            .join(&self.exp_id)
+            .join(sim_id.to_string())
+    }
+
+    /// Checkpoints are written right away, so they are kept even if the simulation run fails later
+    /// on.
+    fn write_checkpoint(sim_output_path: &Path, checkpoint: CheckpointData) -> Result<()> {
+        let path = sim_output_path
+            .join("checkpoints")
+            .join(checkpoint.metadata.step.to_string());
+        log::info!("Writing checkpoint to {:?}", path);
+        std::fs::create_dir_all(&path)?;
+        std::fs::write(path.join(AGENTS_FILE_NAME), &checkpoint.agents)?;
+        std::fs::write(path.join(MESSAGES_FILE_NAME), &checkpoint.messages)?;
+        // Written last, so a checkpoint with metadata is complete
+        std::fs::write(
+            path.join(METADATA_FILE_NAME),
+            serde_json::to_string(&checkpoint.metadata)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SimulationOutputPersistenceRepr for LocalSimulationOutputPersistence {
+    type OutputPersistenceResult = LocalPersistenceResult;
+
+    async fn add_step_output(&mut self, output: SimulationStepOutput) -> Result<()> {
+        output.0.into_iter().try_for_each(|output| {
+            match output {
+                Output::AnalysisOutput(output) => {
+                    self.buffers.analysis.add(output)?;
+                }
+                Output::JsonStateOutput(output) => {
+                    self.buffers.json_state.append_step(output.inner)?;
+                }
+            }
+            Ok(()) as Result<()>
+        })?;
+        Ok(())
+    }
+
+    async fn add_stop_message(&mut self, message: serde_json::Value) -> Result<()> {
+        self.stop_message = Some(message);
+        Ok(())
+    }
+
+    async fn add_checkpoint(&mut self, checkpoint: CheckpointData) -> Result<()> {
+        Self::write_checkpoint(&self.sim_output_path(), checkpoint)
+    }
+
+    async fn finalize(mut self, config: &SimRunConfig) -> Result<Self::OutputPersistenceResult> {
+        log::trace!("Finalizing output");
+        // The outputs of forked runs continue the outputs of the run they were forked from, which
+        // was finalized before they started
+        let parent_path = config
+            .sim
+            .fork
+            .parent
+            .as_ref()
+            .map(|parent| self.output_path(parent.sim_id));
+
+        // JSON state
+        let has_steps = self.buffers.json_state.has_steps();
+        let (_, parts) = self.buffers.json_state.finalize()?;
+        let path = self.sim_output_path();
+
+        log::info!("Making new output directory directory: {:?}", path);
+        std::fs::create_dir_all(&path)?;
+
+        let json_state_path = path.join("json_state.json");
+        std::fs::File::create(&json_state_path)?;
+
+        let file_out = std::fs::OpenOptions::new()
+            .append(true)
+            .open(json_state_path)?;
+
+        let mut buf_writer = BufWriter::new(file_out);
+
+        let mut parts = parts.into_iter();
+        if let Some(parent_path) = &parent_path {
+            // The states of the parent without the closing bracket, followed by the states of this
+            // run without the opening bracket
+            let parent_states = std::fs::File::open(parent_path.join("json_state.json"))?;
+            let parent_len = parent_states.metadata()?.len();
+            std::io::copy(
+                &mut BufReader::new(parent_states).take(parent_len.saturating_sub(1)),
+                &mut buf_writer,
+            )?;
+            if has_steps {
+                buf_writer.write_all(b",")?;
+            }
+            if let Some(first_part) = parts.next() {
+                let mut buf_reader = BufReader::new(std::fs::File::open(first_part)?);
+                buf_reader.seek_relative(1)?;
+                std::io::copy(&mut buf_reader, &mut buf_writer)?;
+            }
+        }
+        parts.try_for_each(|v| -> Result<()> {
+            let file_in = std::fs::File::open(v)?;
+            let mut buf_reader = BufReader::new(file_in);
+            std::io::copy(&mut buf_reader, &mut buf_writer)?;
+            Ok(())
+        })?;
+        buf_writer.flush()?;
+
+        // Analysis
+        let mut analysis = serde_json::to_value(&self.buffers.analysis)?;
+        if let Some(parent_path) = &parent_path {
+            let parent_analysis = std::fs::read(parent_path.join("analysis_outputs.json"))?;
+            prepend_analysis(&mut analysis, serde_json::from_slice(&parent_analysis)?);
+        }
+        let analysis_path = path.join("analysis_outputs.json");
+        std::fs::File::create(&analysis_path)?;
+        std::fs::write(&analysis_path, serde_json::to_string(&analysis)?)?;
+
+        // Globals
+        let globals_path = path.join("globals.json");
+        std::fs::File::create(&globals_path)?;
+        std::fs::write(&globals_path, serde_json::to_string(&config.sim.globals)?)?;
+
+        // Stop message
+        if let Some(stop_message) = &self.stop_message {
+            let stop_message_path = path.join("stop_message.json");
+            std::fs::write(&stop_message_path, serde_json::to_string(stop_message)?)?;
+        }
+
+        Ok(LocalPersistenceResult::new(
+            path.canonicalize()?.to_string_lossy().to_string(),
+        ))
+    }
+}
+
+/// Prepends the analysis outputs of the run a run was forked from to the outputs of the run.
+fn prepend_analysis(analysis: &mut Value, mut parent_analysis: Value) {
+    let outputs = match analysis.get_mut("buffers").and_then(Value::as_object_mut) {
+        Some(outputs) => outputs,
+        None => return,
+    };
+    for (metric, metric_outputs) in outputs.iter_mut() {
+        let parent_outputs = parent_analysis
+            .get_mut("buffers")
+            .and_then(|buffers| buffers.get_mut(metric))
+            .map(Value::take);
+        if let Some(Value::Array(mut parent_outputs)) = parent_outputs {
+            if let Value::Array(metric_outputs) = metric_outputs {
+                parent_outputs.append(metric_outputs);
+            }
+            *metric_outputs = Value::Array(parent_outputs);
+        }
+    }
+}
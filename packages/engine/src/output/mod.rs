@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+use crate::{
+    config::{CheckpointData, PersistenceConfig},
+    proto::SimulationShortId,
+    simulation::step_output::SimulationStepOutput,
+    SimRunConfig,
+};
+
+pub mod buffer;
+
+mod error;
+pub mod local;
+pub mod none;
+
+pub use error::{Error, Result};
+
+pub trait OutputPersistenceCreatorRepr: Send + Sync + 'static {
+    type SimulationOutputPersistence: SimulationOutputPersistenceRepr;
+    fn new_simulation(
+        &self,
+        sim_id: SimulationShortId,
+        persistence_config: &PersistenceConfig,
+    ) -> Result<Self::SimulationOutputPersistence>;
+}
+
+#[async_trait::async_trait]
+pub trait SimulationOutputPersistenceRepr: Send + Sync + 'static {
+    type OutputPersistenceResult: OutputPersistenceResultRepr;
+    async fn add_step_output(&mut self, output: SimulationStepOutput) -> Result<()>;
+    /// Records the payload of the stop message which ended the simulation run
+    async fn add_stop_message(&mut self, message: serde_json::Value) -> Result<()>;
+    /// Stores a checkpoint of the simulation state, which is written every `checkpoint_interval`
+    /// steps
+    async fn add_checkpoint(&mut self, checkpoint: CheckpointData) -> Result<()>;
+    async fn finalize(self, config: &SimRunConfig) -> Result<Self::OutputPersistenceResult>;
+}
+
+pub trait OutputPersistenceResultRepr: Serialize + Send + Sync {
+    fn into_value(self) -> Result<(&'static str, serde_json::Value)>;
+}
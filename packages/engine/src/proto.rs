@@ -1,4 +1,8 @@
-use std::fmt::{Debug, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    path::PathBuf,
+};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as SerdeValue;
@@ -44,6 +48,14 @@ pub enum EngineMsg {
     Init(InitMessage),
     // TODO: this is unused, is that intended
     SimRegistered(SimulationShortId, SimulationRegisteredId),
+    /// Pause a running simulation run after its current step
+    PauseSim(SimulationShortId),
+    /// Resume a paused simulation run
+    ResumeSim(SimulationShortId),
+    /// Stop a simulation run, its output is still persisted
+    StopSim(SimulationShortId),
+    /// Stop all simulation runs and don't start any new ones, so the experiment finishes early
+    StopExperiment,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -96,6 +108,10 @@ pub struct SharedDataset {
     /// Whether the downloadable dataset is a csv
     pub raw_csv: bool,
     pub data: Option<String>,
+    /// Column holding the step of each row, if the dataset holds one row per step. Behaviors then
+    /// look up the row of the current step instead of scanning the dataset.
+    #[serde(default)]
+    pub step_column: Option<String>,
 }
 
 impl Debug for SharedDataset {
@@ -107,6 +123,7 @@ impl Debug for SharedDataset {
             .field("url", &self.url)
             .field("raw_csv", &self.raw_csv)
             .field("data", &CleanOption(&self.data))
+            .field("step_column", &self.step_column)
             .finish()
     }
 }
@@ -178,6 +195,21 @@ pub struct ProjectBase {
     pub behaviors: Vec<SharedBehavior>,
     pub datasets: Vec<SharedDataset>,
     pub packages: Vec<SimPackageArgs>,
+    /// Runners for behaviors written in languages the engine doesn't support itself, keyed by the
+    /// file extension of the behaviors they execute, without the leading dot, e.g. `"R"`.
+    #[serde(default)]
+    pub external_runners: HashMap<String, ExternalRunnerConfig>,
+}
+
+/// An executable which runs behaviors by speaking the runner protocol (see `format/README.md`).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExternalRunnerConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Working directory of the runner process, the one of the engine if not set.
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
@@ -271,6 +303,24 @@ pub struct OptimizationExperimentConfig {
     pub num_parallel_runs: usize,
 }
 
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+pub struct ForkExperimentConfigPayload {
+    /// Number of steps the shared simulation run takes before it is forked into the branches
+    #[serde(rename = "forkStep")]
+    pub fork_step: usize,
+    /// Number of steps each branch should go for, counted from the start of the shared run
+    pub steps: usize,
+    /// The properties changed for each branch
+    pub branches: Vec<SerdeValue>,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+pub struct ForkExperimentConfig {
+    /// The experiment name
+    pub experiment_name: String,
+    pub payload: ForkExperimentConfigPayload,
+}
+
 #[derive(Serialize, Eq, PartialEq, Debug, Clone)]
 pub enum PackageConfig<'a> {
     EmptyPackageConfig,
@@ -288,6 +338,7 @@ pub enum ExperimentPackageConfig {
 pub enum ExtendedExperimentPackageConfig {
     Basic(ExperimentPackageConfig),
     Optimization(OptimizationExperimentConfig),
+    Fork(ForkExperimentConfig),
 }
 
 #[enum_dispatch(ExperimentRunTrait)]
@@ -302,6 +353,10 @@ pub enum ExperimentRunRepr {
 pub struct ExperimentRunBase {
     pub id: ExperimentRegisteredId,
     pub project_base: ProjectBase,
+    /// Seed of all randomness in the run. If it's not set, a random seed is chosen when the
+    /// experiment starts.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -15,6 +15,7 @@ use crate::{
         UUID_V4_LEN,
     },
     hash_types::{message::RemoveAgentPayload, Agent},
+    simulation::seed::AgentIdGenerator,
 };
 
 //TODO[9](docs) Update docs to reflect that these variants are only allowed
@@ -23,6 +24,7 @@ static HASH: [&str; 3] = ["hash", "Hash", "HASH"];
 enum HashMessageType {
     Create,
     Remove,
+    Stop,
 }
 
 struct CreateCommand {
@@ -33,10 +35,21 @@ struct RemoveCommand {
     uuid: Uuid,
 }
 
+/// A request to stop the simulation run, e.g. from a `stop` message sent to "hash".
+#[derive(Debug)]
+pub struct StopCommand {
+    pub message: serde_json::Value,
+}
+
+/// Commands collected from the messages sent to "hash" and from packages.
+///
+/// Stop commands aren't applied to state, they are taken out by the engine with
+/// [`take_stop_commands`](Self::take_stop_commands) before the create/remove commands are applied.
 #[derive(Default)]
 pub struct CreateRemoveCommands {
     create: Vec<CreateCommand>,
     remove: Vec<RemoveCommand>,
+    stop: Vec<StopCommand>,
 }
 
 impl CreateRemoveCommands {
@@ -48,6 +61,21 @@ impl CreateRemoveCommands {
         self.remove.push(RemoveCommand { uuid });
     }
 
+    pub fn add_stop(&mut self, message: serde_json::Value) {
+        self.stop.push(StopCommand { message });
+    }
+
+    pub fn take_stop_commands(&mut self) -> Vec<StopCommand> {
+        std::mem::take(&mut self.stop)
+    }
+
+    /// Gives the agents to be created which don't have an `agent_id` yet the next ids of `ids`.
+    pub fn assign_missing_agent_ids(&mut self, ids: &mut AgentIdGenerator) {
+        for create in &mut self.create {
+            create.agent.assign_missing_id(ids);
+        }
+    }
+
     pub fn verify(&self, schema: &Arc<AgentSchema>) -> Result<()> {
         let field_spec_map = &schema.field_spec_map; // Fields for entire simulation.
 
@@ -67,6 +95,7 @@ impl CreateRemoveCommands {
     pub fn merge(&mut self, mut other: CreateRemoveCommands) {
         self.create.append(&mut other.create);
         self.remove.append(&mut other.remove);
+        self.stop.append(&mut other.stop);
     }
 
     pub fn from_hash_messages(
@@ -91,6 +120,7 @@ impl CreateRemoveCommands {
                         .map(|type_str| match type_str {
                             "create_agent" => Ok(HashMessageType::Create),
                             "remove_agent" => Ok(HashMessageType::Remove),
+                            "stop" => Ok(HashMessageType::Stop),
                             _ => Err(Error::UnexpectedSystemMessage {
                                 message_type: type_str.into(),
                             }),
@@ -167,6 +197,15 @@ fn handle_hash_message(
         HashMessageType::Remove => {
             handle_remove_data(cmds, data, from)?;
         }
+        HashMessageType::Stop => {
+            let message = if data.is_empty() {
+                serde_json::Value::Null
+            } else {
+                serde_json::from_str(data)
+                    .map_err(|e| Error::StopSimPayload(e, data.to_string()))?
+            };
+            cmds.add_stop(message);
+        }
     }
     Ok(())
 }
@@ -36,11 +36,12 @@ use super::{
 };
 pub use super::{Error, Result};
 use crate::{
+    config::Globals,
     datastore::{
         prelude::{Context, State},
         table::{
             state::{view::StateSnapshot, ReadState},
-            sync::{ContextBatchSync, StateSync, SyncPayload, WaitableStateSync},
+            sync::{ContextBatchSync, GlobalsSync, StateSync, SyncPayload, WaitableStateSync},
             task_shared_store::TaskSharedStore,
         },
     },
@@ -155,6 +156,20 @@ impl Comms {
             .map_err(|e| Error::from(format!("Worker pool error: {:?}", e)))?;
         Ok(())
     }
+
+    /// Sends the changed globals of the simulation run to the workers, which use them from the
+    /// next task on.
+    pub async fn globals_sync(&self, globals: &Arc<Globals>) -> Result<()> {
+        log::trace!("Synchronizing globals");
+        let sync_msg = GlobalsSync::new(Arc::clone(globals));
+        self.worker_pool_sender
+            .send(EngineToWorkerPoolMsg::sync(
+                self.sim_id,
+                SyncPayload::Globals(sync_msg),
+            ))
+            .map_err(|e| Error::from(format!("Worker pool error: {:?}", e)))?;
+        Ok(())
+    }
 }
 
 impl Comms {
@@ -12,9 +12,12 @@ use tokio::task::JoinHandle;
 use super::comms::Comms;
 use crate::{
     datastore::prelude::SharedStore,
-    experiment::controller::comms::{
-        sim_status::SimStatusSend,
-        simulation::{new_pair, SimCtlRecv, SimCtlSend},
+    experiment::{
+        controller::comms::{
+            sim_status::SimStatusSend,
+            simulation::{new_pair, SimCtlRecv, SimCtlSend},
+        },
+        package::AnalysisOutputSelection,
     },
     output::SimulationOutputPersistenceRepr,
     proto::SimulationShortId,
@@ -35,6 +38,7 @@ impl SimulationController {
         shared_store: Arc<SharedStore>,
         persistence_service: P,
         status_sender: SimStatusSend,
+        analysis_outputs: Arc<AnalysisOutputSelection>,
     ) -> Result<SimulationController> {
         let (ctl_sender, ctl_receiver) = new_pair();
 
@@ -46,6 +50,7 @@ impl SimulationController {
             packages,
             shared_store,
             persistence_service,
+            analysis_outputs,
         )?;
         Ok(SimulationController {
             sender: ctl_sender,
@@ -62,6 +67,7 @@ fn new_task_handle<P: SimulationOutputPersistenceRepr>(
     packages: Packages,
     shared_store: Arc<SharedStore>,
     persistence_service: P,
+    analysis_outputs: Arc<AnalysisOutputSelection>,
 ) -> Result<JoinHandle<Result<SimulationShortId>>> {
     let task = Box::pin(run::sim_run(
         config,
@@ -71,6 +77,7 @@ fn new_task_handle<P: SimulationOutputPersistenceRepr>(
         receiver,
         sender,
         persistence_service,
+        analysis_outputs,
     ));
 
     Ok(tokio::task::spawn_blocking(move || {
@@ -6,13 +6,22 @@ use tokio::time::Duration;
 use super::{Error, Result};
 use crate::{
     datastore::prelude::{SharedStore, Store},
-    experiment::controller::comms::{sim_status::SimStatusSend, simulation::SimCtlRecv},
+    experiment::{
+        controller::comms::{sim_status::SimStatusSend, simulation::SimCtlRecv},
+        package::AnalysisOutputSelection,
+    },
     hash_types::worker::RunnerError,
     output::SimulationOutputPersistenceRepr,
     proto::SimulationShortId,
     simulation::{
-        agent_control::AgentControl, comms::Comms, controller::sim_control::SimControl,
-        engine::Engine, package::run::Packages, status::SimStatus,
+        agent_control::AgentControl,
+        comms::Comms,
+        controller::sim_control::SimControl,
+        engine::Engine,
+        package::{output::packages::analysis, run::Packages},
+        status::{SimStatus, StopSource},
+        termination::TerminationCriteria,
+        Error as SimulationError,
     },
     SimRunConfig,
 };
@@ -31,6 +40,7 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
     mut sim_from_exp: SimCtlRecv,
     mut sims_to_exp: SimStatusSend,
     mut persistence_service: P,
+    analysis_outputs: Arc<AnalysisOutputSelection>,
 ) -> Result<SimulationShortId> {
     // TODO: This is (sometimes?) 0, why?
     let sim_run_id = config.sim.id;
@@ -41,22 +51,42 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
         max_num_steps,
     );
 
+    // Criteria are checked against the analysis definitions before the first step, so a criterion
+    // on a missing output doesn't fail the run midway
+    let number_outputs =
+        analysis::number_outputs(&config.exp).map_err(|e| Error::from(e.to_string()))?;
+    let mut termination_criteria =
+        TerminationCriteria::from_globals(&config.sim.globals, &number_outputs)
+            .map_err(|e| Error::from(e.to_string()))?;
+    if let Some(checkpoint) = config.resumed_from() {
+        termination_criteria
+            .resume(checkpoint)
+            .map_err(|e| Error::from(e.to_string()))?;
+    }
+
     let uninitialized_store = Store::new_uninitialized(shared_store, &config);
 
     let mut engine = Engine::new(packages, uninitialized_store, comms, config.clone())
         .await
         .map_err(|sim_err| Error::from(sim_err.to_string()))?;
 
-    // We also store the initial state in the persistence service
-    let initial_output = engine
-        .run_output_packages()
-        .await
-        .map_err(|e| Error::from(e.to_string()))?;
-    persistence_service.add_step_output(initial_output).await?;
+    // We also store the initial state in the persistence service. The output of forked runs starts
+    // with the output of the run they were forked from, which already ends with this state.
+    if config.sim.fork.parent.is_none() {
+        let initial_output = engine
+            .run_output_packages()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        persistence_service.add_step_output(initial_output).await?;
+    }
     let now = std::time::Instant::now();
-    let mut steps_taken = 0;
+    // Runs restored from a checkpoint or forked from another run continue stepping from the step
+    // they start at
+    let mut steps_taken = config.sim.start_step;
     let mut early_stop = false;
     let mut stop_msg = None;
+    let mut stop_source = None;
+    let mut latest_analysis_output = None;
     'sim_main: loop {
         // Behaviors expect context.step() to give the current step rather than steps_taken
         let current_step = steps_taken + 1;
@@ -67,24 +97,45 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
 
         if let LoopControl::Stop = maybe_handle_sim_ctl_msg(&mut sim_from_exp).await? {
             // The experiment controller has signalled to stop
+            stop_source = Some(StopSource::Experiment);
             break;
         }
 
         // Take a step in the simulation
-        let step_result = match engine.next(current_step).await {
+        let step = engine.next(current_step);
+        let step_result = match config.exp.step_timeout {
+            Some(step_timeout) => tokio::time::timeout(step_timeout, step)
+                .await
+                .unwrap_or(Err(SimulationError::StepTimeout(step_timeout))),
+            None => step.await,
+        };
+        let step_result = match step_result {
             Ok(step_result) => step_result,
             Err(error) => {
                 log::error!("Got error within the engine step process: {:?}", error);
+                // A task or step running over its time limit, or a runner crashing, only fails this
+                // simulation run rather than the whole experiment
+                let run_failed = matches!(
+                    error,
+                    SimulationError::TaskCancelled
+                        | SimulationError::StepTimeout(_)
+                        | SimulationError::StateSync(_)
+                );
                 // Try to persist before exiting
                 let persistence_result = Some(persistence_service.finalize(&config).await?);
                 let runner_error = RunnerError {
-                    message: Some(format!("{:?}", error)),
+                    message: Some(if run_failed {
+                        format!("Step {current_step} failed: {error}")
+                    } else {
+                        format!("{:?}", error)
+                    }),
                     code: None,
                     line_number: None,
                     file_name: None,
                     details: None,
                     is_warning: false,
-                    is_internal: true, // The error is from within the engine step process.
+                    // The error is from within the engine step process.
+                    is_internal: !run_failed,
                 };
                 sims_to_exp
                     .send(
@@ -103,17 +154,59 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
                             exp_controller_err
                         ))
                     })?;
+                if run_failed {
+                    log::warn!(
+                        "Simulation run {sim_run_id} failed at step {current_step}: {error}"
+                    );
+                    return Ok(sim_run_id);
+                }
                 return Err(Error::from(format!("Simulation error: {:?}", error)));
             }
         };
 
-        // Persist the output
+        // Keep the analysis outputs the experiment package selected and check the termination
+        // criteria before the output is persisted. Runs stopped by an agent aren't checked.
+        let analysis_output = step_result.output.analysis_output();
+        latest_analysis_output = analysis_outputs.select(analysis_output);
+        let terminated_by = if termination_criteria.is_empty()
+            || matches!(step_result.agent_control, AgentControl::Stop(_))
+        {
+            None
+        } else {
+            let num_agents = engine
+                .num_agents()
+                .map_err(|e| Error::from(e.to_string()))?;
+            termination_criteria
+                .check(analysis_output, num_agents)
+                .map_err(|e| Error::from(e.to_string()))?
+                .cloned()
+        };
         persistence_service
             .add_step_output(step_result.output)
             .await?;
+        // Checkpoints are taken after the termination criteria were checked, so they continue
+        // the streaks of this step
+        if config
+            .exp
+            .checkpoint_interval
+            .map_or(false, |interval| current_step % interval == 0)
+        {
+            let checkpoint = engine
+                .checkpoint(current_step, termination_criteria.streaks())
+                .map_err(|e| Error::from(e.to_string()))?;
+            persistence_service.add_checkpoint(checkpoint).await?;
+        }
         if let AgentControl::Stop(msg) = step_result.agent_control {
+            log::info!(
+                "Simulation run {} was stopped by an agent at step {}: {}",
+                sim_run_id,
+                current_step,
+                msg
+            );
+            persistence_service.add_stop_message(msg.clone()).await?;
             early_stop = true;
             stop_msg = Some(msg);
+            stop_source = Some(StopSource::Agent);
             // Break before `send`, because stop messages (like other messages) are handled at the
             // start of a step, before running behaviors, so the stop message was already sent on
             // the previous step.
@@ -122,7 +215,11 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
 
         // TODO: should the SimStatus be current_step here or steps_taken (it is after .next())
         sims_to_exp
-            .send(SimStatus::running(config.sim.id, steps_taken as isize))
+            .send(SimStatus::running(
+                config.sim.id,
+                steps_taken as isize,
+                latest_analysis_output.clone(),
+            ))
             .await
             .map_err(|exp_controller_err| {
                 Error::from(format!(
@@ -132,12 +229,51 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
             })?;
 
         steps_taken += 1;
+
+        if let Some(criterion) = terminated_by {
+            log::info!(
+                "Simulation run {} met the termination criterion {} at step {}",
+                sim_run_id,
+                criterion,
+                current_step
+            );
+            let msg = serde_json::json!({ "terminatedBy": &criterion, "step": current_step });
+            persistence_service.add_stop_message(msg.clone()).await?;
+            early_stop = true;
+            stop_msg = Some(msg);
+            stop_source = Some(StopSource::Termination { criterion });
+            break 'sim_main;
+        }
     }
     let main_loop_dur = now.elapsed().as_millis();
 
-    // Tell the experiment controller that the sim is stopping
+    // Only runs which reached their last step are forked, which includes runs meeting a termination
+    // criterion on it. Runs stopped by an agent took a step more than `steps_taken`, so they never
+    // reach it.
+    let reached_last_step = steps_taken + 1 >= max_num_steps;
+    let final_state = if config.sim.fork.keep_final_state && reached_last_step {
+        Some(Arc::new(
+            engine
+                .checkpoint(steps_taken, termination_criteria.streaks())
+                .map_err(|e| Error::from(e.to_string()))?,
+        ))
+    } else {
+        None
+    };
+
+    // Finalized before signalling the stop, as runs forked from this one copy its output
+    let now = std::time::Instant::now();
+    let persistence_result = persistence_service.finalize(&config).await?;
+    let persistence_dur = now.elapsed().as_millis();
+
+    // Tell the experiment controller that the sim is stopping. The analysis output of the last
+    // step is sent again, as it wasn't sent at all if an agent stopped the simulation run.
     sims_to_exp
-        .send(SimStatus::stop_signal(sim_run_id))
+        .send(SimStatus::stop_signal(
+            sim_run_id,
+            latest_analysis_output,
+            final_state,
+        ))
         .await
         .map_err(|exp_controller_err| {
             Error::from(format!(
@@ -146,8 +282,6 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
             ))
         })?;
 
-    let now = std::time::Instant::now();
-    let persistence_result = persistence_service.finalize(&config).await?;
     sims_to_exp
         .send(
             SimStatus::ended(
@@ -155,6 +289,7 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
                 steps_taken as isize,
                 early_stop,
                 stop_msg,
+                stop_source,
                 persistence_result,
             )
             .map_err(|sim_err| Error::from(format!("Simulation error: {:?}", sim_err)))?,
@@ -166,7 +301,6 @@ pub async fn sim_run<P: SimulationOutputPersistenceRepr>(
                 exp_controller_err
             ))
         })?;
-    let persistence_dur = now.elapsed().as_millis();
 
     log::info!(
         "Finished simulation run. Main loop took {} ms. Persistence took: {} ms",
@@ -206,3 +340,64 @@ async fn maybe_handle_sim_ctl_msg(sim_from_exp: &mut SimCtlRecv) -> Result<LoopC
     }
     Ok(LoopControl::Continue)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiment::controller::comms::simulation::new_pair;
+
+    #[tokio::test]
+    async fn stops_on_stop() -> Result<()> {
+        let (mut sim_ctl_send, mut sim_ctl_recv) = new_pair();
+        assert!(matches!(
+            maybe_handle_sim_ctl_msg(&mut sim_ctl_recv).await?,
+            LoopControl::Continue
+        ));
+
+        sim_ctl_send.send(SimControl::Stop).await.unwrap();
+        assert!(matches!(
+            maybe_handle_sim_ctl_msg(&mut sim_ctl_recv).await?,
+            LoopControl::Stop
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn paused_run_waits_for_resume() -> Result<()> {
+        let (mut sim_ctl_send, mut sim_ctl_recv) = new_pair();
+        sim_ctl_send.send(SimControl::Pause).await.unwrap();
+        sim_ctl_send.send(SimControl::Pause).await.unwrap();
+
+        let control = maybe_handle_sim_ctl_msg(&mut sim_ctl_recv);
+        tokio::pin!(control);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut control)
+                .await
+                .is_err(),
+            "The paused run continued without being resumed"
+        );
+        sim_ctl_send.send(SimControl::Resume).await.unwrap();
+        assert!(matches!(control.await?, LoopControl::Continue));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn paused_run_can_be_stopped() -> Result<()> {
+        let (mut sim_ctl_send, mut sim_ctl_recv) = new_pair();
+        sim_ctl_send.send(SimControl::Pause).await.unwrap();
+        sim_ctl_send.send(SimControl::Stop).await.unwrap();
+        assert!(matches!(
+            maybe_handle_sim_ctl_msg(&mut sim_ctl_recv).await?,
+            LoopControl::Stop
+        ));
+
+        // The experiment controller going away while the run is paused stops it as well
+        sim_ctl_send.send(SimControl::Pause).await.unwrap();
+        drop(sim_ctl_send);
+        assert!(matches!(
+            maybe_handle_sim_ctl_msg(&mut sim_ctl_recv).await?,
+            LoopControl::Stop
+        ));
+        Ok(())
+    }
+}
@@ -1,21 +1,29 @@
 use std::sync::Arc;
 
 use super::{
-    command::CreateRemoveCommands, comms::Comms, package::run::Packages,
-    step_output::SimulationStepOutput, step_result::SimulationStepResult, Error, Result,
+    command::{CreateRemoveCommands, StopCommand},
+    comms::Comms,
+    intervention::Interventions,
+    package::{init::packages::checkpoint::agent_states_from_streams, run::Packages},
+    step_output::SimulationStepOutput,
+    step_result::SimulationStepResult,
+    Error, Result,
 };
 use crate::{
-    config::SimRunConfig,
+    config::{CheckpointData, CheckpointMetadata, Globals, SimRunConfig},
     datastore::{
         prelude::Store,
         table::{
             context::ExContext,
             pool::{agent::AgentPool, message::MessagePool},
             references::MessageMap,
-            state::{view::StateSnapshot, ExState, ReadState, WriteState},
+            state::{view::StateSnapshot, ExState, ReadState, State, WriteState},
         },
     },
-    simulation::agent_control::AgentControl,
+    simulation::{
+        agent_control::AgentControl,
+        seed::{derive_seed, AgentIdGenerator},
+    },
 };
 
 pub struct Engine {
@@ -23,6 +31,11 @@ pub struct Engine {
     store: Store,
     comms: Arc<Comms>,
     config: Arc<SimRunConfig>,
+    /// Generates the ids of agents which are created without one, seeded by the simulation run.
+    agent_ids: AgentIdGenerator,
+    /// The globals of the current step, which differ from the configured ones after interventions
+    globals: Arc<Globals>,
+    interventions: Interventions,
 }
 
 impl Engine {
@@ -33,8 +46,35 @@ impl Engine {
         config: Arc<SimRunConfig>,
     ) -> Result<Engine> {
         let comms = Arc::new(comms);
+        let globals = Arc::clone(&config.sim.globals);
+        let interventions = Interventions::from_globals(&globals)?;
+        let mut agent_ids = match (&config.sim.fork.parent, &config.exp.restore) {
+            // Runs forked from another run mustn't generate the ids the original run generates
+            // after the fork
+            (Some(_), _) => {
+                AgentIdGenerator::new(derive_seed(config.sim.seed, config.sim.start_step as u64))
+            }
+            // Restored runs generate the ids the run which wrote the checkpoint would have
+            (None, Some(restore)) => restore.metadata.agent_ids.clone(),
+            (None, None) => AgentIdGenerator::new(config.sim.seed),
+        };
 
-        let state = packages.init.run(Arc::clone(&config.clone())).await?;
+        let state = match &config.sim.fork.parent {
+            Some(parent) => {
+                let agents = agent_states_from_streams(
+                    &parent.state.agents,
+                    &parent.state.messages,
+                    &config.sim.store.agent_schema,
+                )?;
+                State::from_agent_states(agents, Arc::clone(&config))?
+            }
+            None => {
+                packages
+                    .init
+                    .run(Arc::clone(&config), &mut agent_ids)
+                    .await?
+            }
+        };
         let context = packages.step.empty_context(&config, state.num_agents())?;
         uninitialized_store.set(state, context);
         let store = uninitialized_store;
@@ -44,6 +84,9 @@ impl Engine {
             store,
             comms,
             config,
+            agent_ids,
+            globals,
+            interventions,
         })
     }
 
@@ -62,20 +105,52 @@ impl Engine {
     /// can technically be run any number of times.
     pub async fn next(&mut self, current_step: usize) -> Result<SimulationStepResult> {
         log::debug!("Running next step");
-        self.run_context_packages(current_step).await?;
+        self.apply_interventions(current_step).await?;
+        let agent_control = self.run_context_packages(current_step).await?;
         self.run_state_packages().await?;
         let output = self.run_output_packages().await?;
         let result = SimulationStepResult {
             sim_id: self.config.sim.id,
             output,
-            errors: vec![],
-            warnings: vec![],
-            agent_control: AgentControl::Continue, // TODO: OS - Need to pick up from messages
+            agent_control,
             stop_signal: false,
         };
         Ok(result)
     }
 
+    /// Changes the globals by the interventions scheduled for `current_step` and syncs them with
+    /// the workers, before any package runs.
+    async fn apply_interventions(&mut self, current_step: usize) -> Result<()> {
+        if let Some(globals) = self.interventions.apply(current_step, &self.globals)? {
+            log::info!(
+                "Applying interventions of step {current_step} to simulation run {}",
+                self.config.sim.id
+            );
+            self.packages.step.update_globals(&globals)?;
+            self.comms
+                .globals_sync(&Arc::new(globals.for_behaviors()))
+                .await?;
+            self.globals = Arc::new(globals);
+        }
+        Ok(())
+    }
+
+    /// Combines the `stop` messages sent to "hash" into the [`AgentControl`] of this step.
+    ///
+    /// The payload is the data of the stop message, or an array of all payloads if multiple agents
+    /// sent a stop message in the same step.
+    fn agent_control(stop_commands: Vec<StopCommand>) -> AgentControl {
+        let mut messages: Vec<_> = stop_commands
+            .into_iter()
+            .map(|command| command.message)
+            .collect();
+        match messages.len() {
+            0 => AgentControl::Continue,
+            1 => AgentControl::Stop(messages.remove(0)),
+            _ => AgentControl::Stop(serde_json::Value::Array(messages)),
+        }
+    }
+
     /// Finalize state (see [`SimulationEngine::finalize_state`]) and create a new context
     /// for the agents.
     ///
@@ -89,12 +164,15 @@ impl Engine {
     /// of data associated with each agent. Since these sequences of data are not
     /// dependent on each other, then all context packages are run in parallel
     /// and their outputs are merged into one Context object.
-    async fn run_context_packages(&mut self, current_step: usize) -> Result<()> {
+    ///
+    /// Returns whether an agent requested to stop the simulation run.
+    async fn run_context_packages(&mut self, current_step: usize) -> Result<AgentControl> {
         log::trace!("Starting run context packages stage");
         // Need write access to state to prepare for context packages,
         // so can't start state sync (with workers) yet.
         let (mut state, mut context) = self.store.take_upgraded()?;
-        let snapshot = self.prepare_for_context_packages(&mut state, &mut context)?;
+        let (snapshot, stop_commands) =
+            self.prepare_for_context_packages(&mut state, &mut context, current_step)?;
 
         // Context packages use the snapshot and state packages use state.
         // Context packages will be ran before state packages, so start
@@ -142,7 +220,7 @@ impl Engine {
         let state = Arc::try_unwrap(state)
             .map_err(|_| Error::from("Unable to unwrap state after context package execution"))?;
         self.store.set(state, context);
-        Ok(())
+        Ok(Self::agent_control(stop_commands))
     }
 
     async fn run_state_packages(&mut self) -> Result<()> {
@@ -175,12 +253,43 @@ impl Engine {
         Ok(output)
     }
 
+    pub fn num_agents(&mut self) -> Result<usize> {
+        let (state, context) = self.store.take()?;
+        let num_agents = state.num_agents();
+        self.store.set(state, context);
+        Ok(num_agents)
+    }
+
+    /// Copies the current state, which was reached after `step` steps, so the simulation run can be
+    /// restored or other runs can be forked from it.
+    ///
+    /// The termination criteria are checked outside of the engine, so their `termination_streaks`
+    /// are passed in.
+    pub fn checkpoint(
+        &mut self,
+        step: usize,
+        termination_streaks: &[usize],
+    ) -> Result<CheckpointData> {
+        let metadata = CheckpointMetadata {
+            step,
+            globals: (*self.globals).clone(),
+            seed: self.config.exp.seed,
+            agent_ids: self.agent_ids.clone(),
+            termination_streaks: termination_streaks.to_vec(),
+        };
+        let (state, context) = self.store.take()?;
+        let checkpoint = CheckpointData::from_state(&state, metadata, &self.config);
+        self.store.set(state, context);
+        Ok(checkpoint?)
+    }
+
     /// Prepare for Context Packages
     ///
     /// The following operations are performed:
     /// 1) A message map Recipient -> Vec<MessageReference>
     /// 2) Handling agent messages to "hash", i.e. performing
-    /// agent creation and removals.
+    /// agent creation and removals, and collecting stop messages,
+    /// which are returned.
     ///
     /// 3) Replacing the inbox dataframe with the outbox dataframe.
     /// This is done as context packages can take references to the previous outbox.
@@ -194,13 +303,17 @@ impl Engine {
         &mut self,
         state: &mut ExState,
         context: &mut ExContext,
-    ) -> Result<StateSnapshot> {
+        current_step: usize,
+    ) -> Result<(StateSnapshot, Vec<StopCommand>)> {
         log::trace!("Preparing for context packages");
         let message_map = state.message_map()?;
-        self.add_remove_agents(state, &message_map)?;
+        let stop_commands = self.add_remove_agents(state, &message_map, current_step)?;
         let message_pool = self.finalize_agent_messages(state, context)?;
         let agent_pool = self.finalize_agent_state(state, context)?;
-        Ok(StateSnapshot::new(agent_pool, message_pool, message_map))
+        Ok((
+            StateSnapshot::new(agent_pool, message_pool, message_map),
+            stop_commands,
+        ))
     }
 
     /// Create and Remove agents
@@ -208,14 +321,35 @@ impl Engine {
     /// Operates based on the "create_agent" and "remove_agent"
     /// messages sent to "hash" through agent inboxes. Also creates
     /// and removes agents that have been requested by State packages.
-    fn add_remove_agents(&mut self, state: &mut ExState, message_map: &MessageMap) -> Result<()> {
+    ///
+    /// "stop" messages sent to "hash" are not applied to state but
+    /// returned, so the simulation run can be stopped.
+    ///
+    /// Every `compaction_interval` steps, the batches of agents are
+    /// rebalanced as well.
+    fn add_remove_agents(
+        &mut self,
+        state: &mut ExState,
+        message_map: &MessageMap,
+        current_step: usize,
+    ) -> Result<Vec<StopCommand>> {
         let read = state.message_pool().read()?;
         let mut commands = CreateRemoveCommands::from_hash_messages(message_map, read)?;
         commands.merge(self.comms.take_create_remove_commands()?);
+        let stop_commands = commands.take_stop_commands();
+        commands.assign_missing_agent_ids(&mut self.agent_ids);
         commands.verify(&self.config.sim.store.agent_schema)?;
 
-        state.create_remove(commands, &self.config)?;
-        Ok(())
+        let compact = self
+            .config
+            .exp
+            .compaction_interval
+            .map_or(false, |interval| current_step % interval == 0);
+        if compact {
+            log::debug!("Compacting the batches of agents at step {current_step}");
+        }
+        state.create_remove(commands, &self.config, compact)?;
+        Ok(stop_commands)
     }
 
     /// Replace the inbox dataframe with the outbox dataframe. Reset
@@ -245,3 +379,30 @@ impl Engine {
         Ok(context.take_agent_pool())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn stop(message: serde_json::Value) -> StopCommand {
+        StopCommand { message }
+    }
+
+    #[test]
+    fn combines_stop_messages() {
+        assert!(matches!(
+            Engine::agent_control(vec![]),
+            AgentControl::Continue
+        ));
+        assert!(matches!(
+            Engine::agent_control(vec![stop(json!({ "reason": "a" }))]),
+            AgentControl::Stop(message) if message == json!({ "reason": "a" })
+        ));
+        assert!(matches!(
+            Engine::agent_control(vec![stop(json!("a")), stop(serde_json::Value::Null)]),
+            AgentControl::Stop(message) if message == json!(["a", null])
+        ));
+    }
+}
@@ -79,6 +79,12 @@ pub enum Error {
     )]
     CreateAgentPayload(serde_json::error::Error, String),
 
+    #[error(
+        "Error parsing `stop` message payload, expected valid JSON, got error: {0:?}. Payload \
+         was: {1:?}"
+    )]
+    StopSimPayload(serde_json::error::Error, String),
+
     #[error(
         "`create_agent` message has field \"{0}\" without respective field existing\nDetails: \
          {1:?}"
@@ -167,6 +173,12 @@ pub enum Error {
 
     #[error("State sync failed: {0}")]
     StateSync(String),
+
+    #[error("Couldn't drive to completion, task cancelled")]
+    TaskCancelled,
+
+    #[error("Step exceeded the time limit of {0:?}")]
+    StepTimeout(std::time::Duration),
 }
 
 impl Error {
@@ -0,0 +1,136 @@
+//! Changes of the globals which are scheduled for a step of a simulation run, e.g. a policy which
+//! comes into effect halfway through the run.
+
+use serde::Deserialize;
+
+use super::{Error, Result};
+use crate::{config::Globals, experiment::apply_property_changes};
+
+/// Key of the list of interventions in the globals
+pub const INTERVENTIONS_KEY: &str = "interventions";
+
+/// Properties which are changed before running the step `step`, in the same format as the
+/// changed properties of an experiment.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Intervention {
+    pub step: usize,
+    pub changes: serde_json::Value,
+}
+
+/// The interventions of a simulation run, ordered by their step.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interventions(Vec<Intervention>);
+
+impl Interventions {
+    /// Reads the interventions from the `interventions` key of `globals`, if there is one.
+    pub fn from_globals(globals: &Globals) -> Result<Interventions> {
+        let value = match globals.get(INTERVENTIONS_KEY) {
+            Some(value) => value.clone(),
+            None => return Ok(Interventions::default()),
+        };
+        let mut interventions: Vec<Intervention> = serde_json::from_value(value).map_err(|e| {
+            Error::from(format!(
+                "`{INTERVENTIONS_KEY}` in globals must be a list of `{{\"step\", \"changes\"}}` \
+                 objects: {e}"
+            ))
+        })?;
+        for intervention in &interventions {
+            if intervention.step == 0 {
+                return Err(Error::from(format!(
+                    "Steps of `{INTERVENTIONS_KEY}` start at 1, as the initial state isn't a step"
+                )));
+            }
+            if !intervention.changes.is_object() {
+                return Err(Error::from(format!(
+                    "The changes of the intervention at step {} must be an object",
+                    intervention.step
+                )));
+            }
+        }
+        // Stable, so interventions for the same step are applied in the order they were given
+        interventions.sort_by_key(|intervention| intervention.step);
+        Ok(Interventions(interventions))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Applies the interventions scheduled for `step` to `globals`. Returns `None` if there are
+    /// none, so the globals are unchanged.
+    pub fn apply(&self, step: usize, globals: &Globals) -> Result<Option<Globals>> {
+        let mut changed = None;
+        for intervention in self
+            .0
+            .iter()
+            .filter(|intervention| intervention.step == step)
+        {
+            let base = changed.take().unwrap_or_else(|| globals.clone());
+            changed = Some(
+                apply_property_changes(base, &intervention.changes)
+                    .map_err(|e| Error::from(e.to_string()))?,
+            );
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn applies_interventions_at_their_step() -> Result<()> {
+        let globals = Globals(json!({
+            "lockdown": false,
+            "policy": { "level": 0 },
+            "interventions": [
+                { "step": 50, "changes": { "lockdown": true } },
+                { "step": 10, "changes": { "policy.level": 1 } },
+                { "step": 50, "changes": { "policy.level": 2 } },
+            ],
+        }));
+        let interventions = Interventions::from_globals(&globals)?;
+
+        assert_eq!(interventions.apply(1, &globals)?, None);
+        let at_10 = interventions.apply(10, &globals)?.unwrap();
+        assert_eq!(at_10.get("policy"), Some(&json!({ "level": 1 })));
+        assert_eq!(at_10.get("lockdown"), Some(&json!(false)));
+        let at_50 = interventions.apply(50, &at_10)?.unwrap();
+        assert_eq!(at_50.get("policy"), Some(&json!({ "level": 2 })));
+        assert_eq!(at_50.get("lockdown"), Some(&json!(true)));
+        Ok(())
+    }
+
+    #[test]
+    fn behaviors_dont_see_interventions() {
+        let globals = Globals(json!({
+            "lockdown": false,
+            "interventions": [{ "step": 50, "changes": { "lockdown": true } }],
+            "termination": [{ "type": "agentCount", "op": "==", "value": 0 }],
+        }));
+        assert_eq!(
+            globals.for_behaviors(),
+            Globals(json!({ "lockdown": false }))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_interventions() {
+        let invalid = [
+            json!({ "interventions": { "step": 1, "changes": {} } }),
+            json!({ "interventions": [{ "step": 0, "changes": {} }] }),
+            json!({ "interventions": [{ "step": 1, "changes": 1 }] }),
+        ];
+        for globals in invalid {
+            assert!(Interventions::from_globals(&Globals(globals)).is_err());
+        }
+        assert!(
+            Interventions::from_globals(&Globals(json!({})))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -5,11 +5,14 @@ pub mod controller;
 pub mod engine;
 pub mod enum_dispatch;
 mod error;
+pub mod intervention;
 pub mod package;
+pub mod seed;
 pub mod status;
 pub mod step_output;
 pub mod step_result;
 pub mod task;
+pub mod termination;
 
 pub use error::{Error, Result};
 
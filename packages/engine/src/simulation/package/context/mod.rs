@@ -36,6 +36,12 @@ pub trait Package: MaybeCpuBound + GetWorkerSimStartMsg + Send + Sync {
         num_agents: usize,
         context_schema: &ContextSchema,
     ) -> Result<Vec<(FieldKey, Arc<dyn arrow::array::Array>)>>;
+
+    /// Called before a step when interventions changed the globals, so packages which read the
+    /// globals when they were created can update.
+    fn update_globals(&mut self, _globals: &Globals) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait PackageCreator: GetWorkerExpStartMsg + Sync + Send {
@@ -37,7 +37,8 @@ def _get_msgs(agent_context, msg_locs):
     msgs = [InboxMessage(pool, loc) for loc in msg_locs]
     
     api_responses = agent_context.api_responses
-    if (api_responses) msgs.extend(api_responses)
+    if api_responses:
+        msgs.extend(api_responses)
     return msgs
 
 def start_sim(experiment, sim, init_message, init_context):
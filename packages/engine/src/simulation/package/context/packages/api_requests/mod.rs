@@ -177,6 +177,11 @@ impl Package for ApiRequests {
             Arc::new(api_response_list_builder.finish()),
         )])
     }
+
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.custom_message_handlers = custom_message_handlers_from_properties(globals)?;
+        Ok(())
+    }
 }
 
 pub fn custom_message_handlers_from_properties(
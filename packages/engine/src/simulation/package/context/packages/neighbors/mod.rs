@@ -162,4 +162,9 @@ impl Package for Neighbors {
 
         Ok(vec![(field_key, Arc::new(neighbors_builder.finish()))])
     }
+
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.topology = Arc::new(TopologyConfig::from_globals(globals)?);
+        Ok(())
+    }
 }
@@ -90,7 +90,7 @@ impl PackageName {
 
 #[cfg(test)]
 pub mod tests {
-    use std::sync::Arc;
+    use std::{collections::HashMap, sync::Arc};
 
     use super::*;
     use crate::{
@@ -146,7 +146,9 @@ pub mod tests {
                         behaviors: vec![],
                         datasets: vec![],
                         packages: vec![],
+                        external_runners: HashMap::new(),
                     },
+                    seed: None,
                 }
                 .into(),
             ),
@@ -155,6 +157,14 @@ pub mod tests {
                 num_workers: 0,
             }),
             base_globals: Default::default(),
+            continue_on_error: false,
+            seed: 0,
+            checkpoint_interval: None,
+            restore: None,
+            step_timeout: None,
+            memory_backend: Default::default(),
+            target_batch_size: 100_000,
+            compaction_interval: None,
         });
         validate!(context, experiment_config, PackageName::Context);
         validate!(init, experiment_config, PackageName::Init);
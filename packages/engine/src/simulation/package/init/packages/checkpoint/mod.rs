@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use super::super::*;
+use crate::{
+    config::checkpoint::{AGENTS_FILE_NAME, MESSAGES_FILE_NAME},
+    datastore::{
+        arrow::ipc::read_record_batch_stream, schema::state::AgentSchema, Error as DatastoreError,
+    },
+    simulation::{Error, Result},
+};
+
+pub struct Creator {}
+
+impl PackageCreator for Creator {
+    fn new(_experiment_config: &Arc<ExperimentConfig>) -> Result<Box<dyn PackageCreator>> {
+        Ok(Box::new(Creator {}))
+    }
+
+    fn create(
+        &self,
+        config: &Arc<SimRunConfig>,
+        _comms: PackageComms,
+        _accessor: FieldSpecMapAccessor,
+    ) -> Result<Box<dyn InitPackage>> {
+        let restore = config.exp.restore.as_ref().ok_or_else(|| {
+            Error::from(
+                "Trying to create a checkpoint init package without a checkpoint to restore",
+            )
+        })?;
+        Ok(Box::new(Package {
+            path: restore.path.clone(),
+            agent_schema: Arc::clone(&config.sim.store.agent_schema),
+        }))
+    }
+}
+
+impl GetWorkerExpStartMsg for Creator {
+    fn get_worker_exp_start_msg(&self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+}
+
+pub struct Package {
+    path: PathBuf,
+    agent_schema: Arc<AgentSchema>,
+}
+
+impl Package {
+    fn read_file(&self, file_name: &str) -> Result<Vec<u8>> {
+        let path = self.path.join(file_name);
+        std::fs::read(&path)
+            .map_err(|e| Error::from(format!("Couldn't read checkpoint file {:?}: {}", path, e)))
+    }
+}
+
+impl MaybeCpuBound for Package {
+    fn cpu_bound(&self) -> bool {
+        true
+    }
+}
+
+impl GetWorkerSimStartMsg for Package {
+    fn get_worker_sim_start_msg(&self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+}
+
+#[async_trait]
+impl InitPackage for Package {
+    async fn run(&mut self) -> Result<Vec<Agent>> {
+        let agents = self.read_file(AGENTS_FILE_NAME)?;
+        let messages = self.read_file(MESSAGES_FILE_NAME)?;
+        agent_states_from_streams(&agents, &messages, &self.agent_schema).map_err(|e| {
+            Error::from(format!(
+                "Couldn't restore the checkpoint at {:?}: {}",
+                self.path, e
+            ))
+        })
+    }
+}
+
+/// Converts the agent and message batches of a checkpoint, which are given as Arrow IPC streams,
+/// into agents together with their outbound messages.
+pub fn agent_states_from_streams(
+    agents: &[u8],
+    messages: &[u8],
+    agent_schema: &Arc<AgentSchema>,
+) -> Result<Vec<Agent>> {
+    let agents = read_record_batch_stream(agents).map_err(DatastoreError::from)?;
+    let messages = read_record_batch_stream(messages).map_err(DatastoreError::from)?;
+    if agents.schema().fields() != agent_schema.arrow.fields() {
+        return Err(Error::from(
+            "The agent schema of the checkpoint doesn't match the agent schema of the simulation \
+             run, the checkpoint was most likely written by a different project",
+        ));
+    }
+    if agents.batches().len() != messages.batches().len() {
+        return Err(Error::from(format!(
+            "The checkpoint has {} agent batches but {} message batches",
+            agents.batches().len(),
+            messages.batches().len()
+        )));
+    }
+
+    // `agents` and `messages` own the memory of the batches, the agents are copied out of it
+    let mut agent_states = Vec::new();
+    for (agent_batch, message_batch) in agents.batches().iter().zip(messages.batches()) {
+        agent_states.extend((agent_batch, message_batch).into_agent_states(Some(agent_schema))?);
+    }
+    Ok(agent_states)
+}
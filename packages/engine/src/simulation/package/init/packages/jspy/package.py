@@ -1,4 +1,5 @@
 import json
+import traceback
 
 class UserCodeError(Exception):
     def __init__(self, short_msg, full_msg=""):
@@ -8,7 +9,7 @@ class UserCodeError(Exception):
 def _load_initializer(code):
     try:
         init_globals = dict()
-        bytecode = compile(code.decode("utf-8"), "init.py", "exec")
+        bytecode = compile(code, "init.py", "exec")
         exec(bytecode, init_globals)
         init_fn = init_globals.get("init")
     except Exception as e:
@@ -44,10 +45,10 @@ def run_task(_experiment, _sim, task_message, _group_state, context):
 
     try:
         data = json.dumps(agents)
-    except (TypeError, ValueError):
+    except (TypeError, ValueError) as e:
         raise UserCodeError(
             short_msg=f"serializing init return value to JSON failed: {e}"
         )
 
     # TODO: Change the runner to avoid this, perhaps a function or a well-defined object would make this clearer.
-    return {'task': data}
+    return {'task': json.dumps({"SuccessMessage": {"agent_json": data}})}
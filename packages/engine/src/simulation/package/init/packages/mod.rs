@@ -18,6 +18,7 @@ use crate::{
     ExperimentConfig,
 };
 
+pub mod checkpoint;
 pub mod json;
 pub mod jspy;
 
@@ -27,6 +28,7 @@ pub mod jspy;
 pub enum Name {
     Json,
     JsPy,
+    Checkpoint,
 }
 
 impl std::fmt::Display for Name {
@@ -68,6 +70,7 @@ impl PackageCreators {
         let mut m = HashMap::new();
         m.insert(Json, json::Creator::new(experiment_config)?);
         m.insert(JsPy, jspy::Creator::new(experiment_config)?);
+        m.insert(Checkpoint, checkpoint::Creator::new(experiment_config)?);
         self.0
             .set(m)
             .map_err(|_| Error::from("Failed to initialize Init Package Creators"))?;
@@ -110,6 +113,10 @@ lazy_static! {
             id: id_creator.next(),
             dependencies: jspy::Creator::dependencies(),
         });
+        m.insert(Checkpoint, PackageMetadata {
+            id: id_creator.next(),
+            dependencies: checkpoint::Creator::dependencies(),
+        });
         m
     };
 }
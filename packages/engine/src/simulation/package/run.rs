@@ -3,6 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 use futures::{executor::block_on, stream::FuturesOrdered, StreamExt};
 
 use crate::{
+    config::Globals,
     datastore::{
         prelude::{Context, State},
         table::{
@@ -18,6 +19,7 @@ use crate::{
             prelude::{Error, ExContext, ExState, Result},
             state,
         },
+        seed::AgentIdGenerator,
         step_output::SimulationStepOutput,
     },
     SimRunConfig,
@@ -38,7 +40,11 @@ impl InitPackages {
         InitPackages { inner }
     }
 
-    pub async fn run(&mut self, sim_config: Arc<SimRunConfig>) -> Result<State> {
+    pub async fn run(
+        &mut self,
+        sim_config: Arc<SimRunConfig>,
+        agent_ids: &mut AgentIdGenerator,
+    ) -> Result<State> {
         // Execute packages in parallel and collect the data
         let mut futs = FuturesOrdered::new();
 
@@ -69,6 +75,9 @@ impl InitPackages {
             pkgs.push(pkg);
             agents.append(&mut new_agents?);
         }
+        agents
+            .iter_mut()
+            .for_each(|agent| agent.assign_missing_id(agent_ids));
 
         let state = State::from_agent_states(agents, sim_config)?;
         Ok(state)
@@ -96,6 +105,16 @@ impl StepPackages {
 }
 
 impl StepPackages {
+    /// Passes the globals changed by interventions to the context and state packages.
+    pub fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.context
+            .iter_mut()
+            .try_for_each(|package| package.update_globals(globals))?;
+        self.state
+            .iter_mut()
+            .try_for_each(|package| package.update_globals(globals))
+    }
+
     pub fn empty_context(
         &self,
         sim_run_config: &SimRunConfig,
@@ -23,6 +23,12 @@ use crate::{
 #[async_trait]
 pub trait Package: GetWorkerSimStartMsg + Send + Sync {
     async fn run(&mut self, state: &mut ExState, context: &Context) -> Result<()>;
+
+    /// Called before a step when interventions changed the globals, so packages which read the
+    /// globals when they were created can update.
+    fn update_globals(&mut self, _globals: &Globals) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait PackageCreator: GetWorkerExpStartMsg + Send + Sync {
@@ -3,7 +3,7 @@ use std::{collections::HashMap, ops::Deref};
 use serde::{Deserialize, Serialize};
 
 use super::{fields::behavior::BehaviorMap, Error, Result};
-use crate::Language;
+use crate::{proto::ExternalRunnerConfig, Language};
 
 // TODO: Package's experiment init message should have payload with
 //       Vec of behavior descriptions in `behavior_descs`.
@@ -15,7 +15,7 @@ pub struct BehaviorDescription {
     pub source: String,
     pub required_field_keys: Vec<String>,
     pub language: Language,
-    // serde serialized to "Python", "JavaScript" or "Rust"
+    // serde serialized to "Python", "JavaScript", "Rust", "Wasm" or "External"
     pub dyn_access: bool,
 }
 
@@ -38,15 +38,18 @@ pub struct BehaviorIds {
 }
 
 impl BehaviorIds {
-    pub(crate) fn from_behaviors(behaviors: &BehaviorMap) -> Result<BehaviorIds> {
+    pub(crate) fn from_behaviors(
+        behaviors: &BehaviorMap,
+        external_runners: &HashMap<String, ExternalRunnerConfig>,
+    ) -> Result<BehaviorIds> {
         let mut lang_counts = [0_u16; Language::NUM];
 
         let mut index_to_name = HashMap::new();
         let mut name_to_index = HashMap::new();
         for behavior in behaviors.iter_behaviors() {
             let shared = behavior.shared();
-            let lang_index = Language::from_file_name(&shared.name)
-                .map_err(|_| Error::from(format!("Invalid behavior name: \"{}\"", &shared.name)))?
+            let lang_index = Language::from_file_name(&shared.name, external_runners)
+                .map_err(|e| Error::from(format!("Invalid behavior \"{}\": {e}", &shared.name)))?
                 .as_index();
             let behavior_id = BehaviorId(lang_index as u16, lang_counts[lang_index]);
             lang_counts[lang_index] += 1;
@@ -105,20 +108,23 @@ pub fn exp_init_message(
     let behavior_descriptions = behavior_map
         .inner
         .iter()
-        .map(|(file_name, behavior)| {
+        .map(|(_, behavior)| {
             let shared = behavior.shared();
             let keys = behavior.keys();
 
-            let language = Language::from_file_name(file_name)
-                .map_err(|_| Error::from("Couldn't get language from behavior file name"))?;
             let id = behavior_ids
                 .name_to_index
                 .get(shared.name.as_bytes())
                 .ok_or_else(|| Error::from("Couldn't get index from behavior name"))?;
-            let source = shared
-                .behavior_src
-                .clone()
-                .ok_or_else(|| Error::from("SharedBehavior didn't have an attached source"))?;
+            let language = Language::from_index(id.lang_index() as usize);
+            // Rust built-ins are compiled into the engine, so they don't need their source.
+            let source = match (&shared.behavior_src, language) {
+                (Some(source), _) => source.clone(),
+                (None, Language::Rust) => String::new(),
+                (None, _) => {
+                    return Err(Error::from("SharedBehavior didn't have an attached source"));
+                }
+            };
             let required_field_keys = keys
                 .inner
                 .iter()
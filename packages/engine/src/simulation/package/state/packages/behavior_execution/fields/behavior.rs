@@ -3,7 +3,6 @@ use std::{
     convert::TryFrom,
 };
 
-// use crate::worker::runner::rust;
 use crate::{
     config::ExperimentConfig,
     datastore::{
@@ -16,6 +15,7 @@ use crate::{
     experiment::SharedBehavior,
     hash_types::state::AgentStateField,
     proto::ExperimentRunTrait,
+    worker::runner::rust,
 };
 
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
@@ -54,7 +54,7 @@ impl BehaviorKeys {
                         .map(|(k, v)| {
                             Ok(field_spec_creator.create(
                                 k.into(),
-                                FieldType::from_json(k, v)?,
+                                FieldType::from_json(k, v, 0)?,
                                 FieldScope::Agent,
                             ))
                         })
@@ -167,6 +167,11 @@ impl TryFrom<(&ExperimentConfig, &RootFieldSpecCreator)> for BehaviorMap {
     ) -> Result<Self> {
         let mut meta = HashMap::new();
         let mut field_spec_map = FieldSpecMap::empty();
+        let spawns_javascript = experiment_config
+            .worker_pool
+            .worker_base_config
+            .spawn
+            .javascript;
 
         experiment_config
             .run
@@ -182,19 +187,20 @@ impl TryFrom<(&ExperimentConfig, &RootFieldSpecCreator)> for BehaviorMap {
                 // Need to check whether we're dealing with rust built-in keys,
                 // for which we always use the in-repo locally defined ones.
 
-                // TODO: OS - Re-enable Rust Behavior Runner
-                // let rust_built_in_behavior_keys = if rust::behaviors::is_built_in(&b.name) {
-                //     let behavior = rust::behaviors::get_named_behavior(&b.name)
-                //         .expect(&format!("Built in behavior {} not found", &b.name));
-                //     let behavior_keys_src = behavior.behavior_keys_src.expect(&format!(
-                //         "Expected built in Rust behavior `{}` to contain behavior keys",
-                //         &b.name
-                //     ));
-                //     Some(behavior_keys_src)
-                // } else {
-                //     None
-                // };
-                let rust_built_in_behavior_keys = None;
+                let rust_built_in_behavior_keys = if rust::behaviors::is_built_in(&b.name) {
+                    let behavior = rust::behaviors::get_named_behavior(&b.name).map_err(|e| {
+                        Error::from(format!("Built in behavior {} not found: {e}", &b.name))
+                    })?;
+                    let behavior_keys_src = behavior.behavior_keys_src.ok_or_else(|| {
+                        Error::from(format!(
+                            "Expected built in Rust behavior `{}` to contain behavior keys",
+                            &b.name
+                        ))
+                    })?;
+                    Some(behavior_keys_src)
+                } else {
+                    None
+                };
                 let keys = rust_built_in_behavior_keys
                     .or_else(|| b.behavior_keys_src.clone())
                     .map(|v| BehaviorKeys::from_json_str(&v, field_spec_creator))
@@ -207,6 +213,20 @@ impl TryFrom<(&ExperimentConfig, &RootFieldSpecCreator)> for BehaviorMap {
                         .cloned()
                         .collect::<Vec<RootFieldSpec>>(),
                 )?;
+                // JavaScript Arrow can't read columns with 64 bit offsets, and the JavaScript
+                // runner loads every column of the state
+                if spawns_javascript {
+                    if let Some(field_spec) = keys
+                        .get_field_specs()
+                        .find(|field_spec| field_spec.inner.field_type.has_large_offsets())
+                    {
+                        return Err(BehaviorKeyJsonError::LargeOffsetsInJavaScript(
+                            field_spec.inner.name.clone(),
+                            b.name.clone(),
+                        )
+                        .into());
+                    }
+                }
                 let behavior = Behavior {
                     shared: b.clone(),
                     keys,
@@ -230,10 +250,16 @@ impl BehaviorMap {
 
 enum BaseKeyType {
     String,
+    LargeString,
     Boolean,
     Number,
+    Integer,
+    UnsignedInteger,
+    Categorical,
+    Timestamp,
     Struct,
     List,
+    LargeList,
     FixedSizeList,
     Any,
 }
@@ -244,10 +270,16 @@ impl TryFrom<&str> for BaseKeyType {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "string" => Ok(BaseKeyType::String),
+            "large_string" => Ok(BaseKeyType::LargeString),
             "boolean" => Ok(BaseKeyType::Boolean),
             "number" => Ok(BaseKeyType::Number),
+            "integer" => Ok(BaseKeyType::Integer),
+            "unsigned_integer" => Ok(BaseKeyType::UnsignedInteger),
+            "categorical" => Ok(BaseKeyType::Categorical),
+            "timestamp" => Ok(BaseKeyType::Timestamp),
             "struct" => Ok(BaseKeyType::Struct),
             "list" => Ok(BaseKeyType::List),
+            "large_list" => Ok(BaseKeyType::LargeList),
             "fixed_size_list" => Ok(BaseKeyType::FixedSizeList),
             "any" => Ok(BaseKeyType::Any),
             _ => Err(BehaviorKeyJsonError::InvalidKeyType(value.to_string())),
@@ -256,16 +288,16 @@ impl TryFrom<&str> for BaseKeyType {
 }
 
 impl FieldSpec {
-    fn from_json(name: &str, source: &serde_json::Value) -> Result<FieldSpec> {
+    fn from_json(name: &str, source: &serde_json::Value, depth: usize) -> Result<FieldSpec> {
         Ok(FieldSpec {
             name: name.to_string(),
-            field_type: FieldType::from_json(name, source)?,
+            field_type: FieldType::from_json(name, source, depth)?,
         })
     }
 }
 
 impl FieldType {
-    fn from_json(name: &str, source: &serde_json::Value) -> Result<FieldType> {
+    fn from_json(name: &str, source: &serde_json::Value, depth: usize) -> Result<FieldType> {
         match source {
             serde_json::Value::Object(map) => {
                 let key_base_type = match map
@@ -288,8 +320,44 @@ impl FieldType {
 
                 let variant = match key_base_type {
                     BaseKeyType::String => FieldTypeVariant::String,
+                    BaseKeyType::LargeString => FieldTypeVariant::LargeString,
                     BaseKeyType::Boolean => FieldTypeVariant::Boolean,
                     BaseKeyType::Number => FieldTypeVariant::Number,
+                    BaseKeyType::Integer => FieldTypeVariant::Integer,
+                    BaseKeyType::UnsignedInteger => FieldTypeVariant::UnsignedInteger,
+                    BaseKeyType::Timestamp => FieldTypeVariant::Timestamp,
+                    BaseKeyType::Categorical => {
+                        // The categories are stored in the schema metadata of the top-level field
+                        if depth != 0 {
+                            return Err(
+                                BehaviorKeyJsonError::NestedCategorical(name.to_string()).into()
+                            );
+                        }
+                        let categories = match map.get("categories") {
+                            Some(serde_json::Value::Array(values)) if !values.is_empty() => values
+                                .iter()
+                                .map(|value| match value {
+                                    serde_json::Value::String(category) => Ok(category.clone()),
+                                    _ => Err(BehaviorKeyJsonError::InvalidKeyCategoriesType(
+                                        name.to_string(),
+                                    )),
+                                })
+                                .collect::<Result<Vec<_>, _>>(),
+                            _ => Err(BehaviorKeyJsonError::InvalidKeyCategoriesType(
+                                name.to_string(),
+                            )),
+                        }?;
+                        for (i, category) in categories.iter().enumerate() {
+                            if categories[..i].contains(category) {
+                                return Err(BehaviorKeyJsonError::DuplicateCategory(
+                                    name.to_string(),
+                                    category.clone(),
+                                )
+                                .into());
+                            }
+                        }
+                        FieldTypeVariant::Categorical(categories)
+                    }
                     BaseKeyType::Any => FieldTypeVariant::AnyType,
                     BaseKeyType::Struct => {
                         let mut children = vec![];
@@ -298,7 +366,7 @@ impl FieldType {
                         })? {
                             serde_json::Value::Object(map) => {
                                 for (k, v) in map {
-                                    children.push(FieldSpec::from_json(k.as_ref(), v)?);
+                                    children.push(FieldSpec::from_json(k.as_ref(), v, depth + 1)?);
                                 }
                                 Ok(())
                             }
@@ -313,14 +381,21 @@ impl FieldType {
                         let child_source = map.get("child").ok_or_else(|| {
                             BehaviorKeyJsonError::InvalidKeyChildType(name.to_string())
                         })?;
-                        let child_key_type = FieldType::from_json(name, child_source)?;
+                        let child_key_type = FieldType::from_json(name, child_source, depth + 1)?;
                         FieldTypeVariant::VariableLengthArray(Box::new(child_key_type))
                     }
+                    BaseKeyType::LargeList => {
+                        let child_source = map.get("child").ok_or_else(|| {
+                            BehaviorKeyJsonError::InvalidKeyChildType(name.to_string())
+                        })?;
+                        let child_key_type = FieldType::from_json(name, child_source, depth + 1)?;
+                        FieldTypeVariant::LargeVariableLengthArray(Box::new(child_key_type))
+                    }
                     BaseKeyType::FixedSizeList => {
                         let child_source = map.get("child").ok_or_else(|| {
                             BehaviorKeyJsonError::InvalidKeyChildType(name.to_string())
                         })?;
-                        let child_key_type = FieldType::from_json(name, child_source)?;
+                        let child_key_type = FieldType::from_json(name, child_source, depth + 1)?;
                         let len = match map.get("length").ok_or_else(|| {
                             BehaviorKeyJsonError::InvalidKeyLengthType(name.to_string())
                         })? {
@@ -397,7 +472,7 @@ pub enum BehaviorKeyJsonError {
     InvalidKeyFieldsType(String),
     #[error(
         "Expected key with name {0} to have a object \"child\" sub-field in one of its \
-         \"list\"/\"fixed_size_list\"-type sub-types"
+         \"list\"/\"large_list\"/\"fixed_size_list\"-type sub-types"
     )]
     InvalidKeyChildType(String),
     #[error(
@@ -405,6 +480,24 @@ pub enum BehaviorKeyJsonError {
          \"fixed_size_list\"-type sub-types"
     )]
     InvalidKeyLengthType(String),
+    #[error(
+        "Expected key with name {0} to have a non-empty list of strings as \"categories\" \
+         sub-field in one of its \"categorical\"-type sub-types"
+    )]
+    InvalidKeyCategoriesType(String),
+    #[error("Key with name {0} has the category {1:?} more than once")]
+    DuplicateCategory(String, String),
+    #[error(
+        "Key with name {0} has a nested \"categorical\" type, which is only allowed at the top \
+         level"
+    )]
+    NestedCategorical(String),
+    #[error(
+        "Key with name {0} of behavior {1} is stored with 64 bit offsets, which the JavaScript \
+         runner doesn't support, use \"string\"/\"list\" types or remove the JavaScript behaviors \
+         and initial state"
+    )]
+    LargeOffsetsInJavaScript(String, String),
     #[error("Invalid built-in key name {0}")]
     InvalidBuiltInKeyName(String),
     #[error("Dynamic access flag must be boolean if present")]
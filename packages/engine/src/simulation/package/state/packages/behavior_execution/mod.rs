@@ -74,7 +74,10 @@ impl PackageCreator for Creator {
         ));
         let behavior_map =
             BehaviorMap::try_from((experiment_config.as_ref(), &field_spec_creator))?;
-        let behavior_ids = BehaviorIds::from_behaviors(&behavior_map)?;
+        let behavior_ids = BehaviorIds::from_behaviors(
+            &behavior_map,
+            &experiment_config.run.base().project_base.external_runners,
+        )?;
 
         Ok(Box::new(Creator {
             behavior_ids: Some(Arc::new(behavior_ids)),
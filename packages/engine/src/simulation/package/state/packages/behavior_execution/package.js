@@ -133,6 +133,21 @@ const fill3 = (arr, val) => {
     }
 }
 
+/// Converts an error thrown by a behavior into a user error, with the behavior's name as file name
+/// and, if it can be found in the stack trace, the line number the error was thrown at.
+const behavior_error = (behavior, e) => {
+    const stack = e && e.stack ? String(e.stack) : String(e);
+    // Behaviors are created with `new Function`, so their frames are `<anonymous>:line:column`.
+    const location = /<anonymous>:(\d+):\d+/.exec(stack);
+    return {
+        "msg": String(e),
+        "details": stack,
+        "file_name": behavior.name,
+        // Behavior line numbers are off by 2 due to the function header added by `new Function`.
+        "line_number": location ? parseInt(location[1], 10) - 2 : null
+    };
+}
+
 const postprocess = agent_state => {
     const msgs = agent_state.messages;
     for (var i = 0; i < msgs.length; ++i) {
@@ -165,6 +180,7 @@ const run_task = (experiment, sim, task_message, group_state, group_context) =>
     let next_lang = null;
     let agent_state = null;
     let agent_ctx = null;
+    const user_errors = [];
 
     // TODO: Propagate field specs to runners and use in state and context objects
     const behavior_ids_field_key = '_PRIVATE_14_behavior_ids';
@@ -195,19 +211,26 @@ const run_task = (experiment, sim, task_message, group_state, group_context) =>
             }
             
             agent_state.set_dynamic_access(behavior.dyn_access);
+            // Read by the runner to name the behavior if the task is stopped for running too long
+            experiment.running_behavior = behavior.name;
             try {
                 behavior.fn(agent_state, agent_ctx);
             } catch (e) {
-                // TODO expose/return user error properly
-                throw Error(behavior.name + "\n" + e.stack);
+                user_errors.push(behavior_error(behavior, e));
+                // Skip the rest of this agent's behaviors for this step, but keep running the
+                // other agents, so a single faulty agent doesn't stop the simulation run.
+                agent_state.behavior_index = n_behaviors;
+                break;
             }
             postprocess(agent_state);
         }
     }
-    
+    experiment.running_behavior = null;
+
     return {
         "print": experiment.logged,
         "target": next_lang || "Main",
-        "task": "{}" // TODO: Maybe this shouldn't be necessary
+        "task": "{}", // TODO: Maybe this shouldn't be necessary
+        "user_errors": user_errors
     };
 }
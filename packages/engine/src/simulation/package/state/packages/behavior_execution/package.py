@@ -1,43 +1,50 @@
 import sys
+import traceback
 
-# `behavior_descs` should be a list of objects that have fields `id`, `name`, `source`, `columns`,
-# `language` and `dyn_access`.
+# Field with the ids of the behaviors of an agent, see `behavior_execution/fields`.
+BEHAVIOR_IDS_FIELD = "_PRIVATE_14_behavior_ids"
+
+# `behavior_descs` should be a list of dicts with keys `id`, `name`, `source`,
+# `required_field_keys`, `language` and `dyn_access`, where `language` is the name of a
+# `Language` variant, e.g. "Python".
 def load_behaviors(behavior_descs):
     behaviors = {}
     warnings = [] # TODO: Accumulate warnings automatically with something like `hash_util.warn`
     for desc in behavior_descs:
-        if desc.language != "py":
-            behaviors[desc.id] = {
-                "name": desc.name,
-                "language": desc.language,
+        # Behavior ids are pairs of language index and behavior index.
+        behavior_id = tuple(desc["id"])
+        if desc["language"] != "Python":
+            behaviors[behavior_id] = {
+                "name": desc["name"],
+                "language": desc["language"],
             }
             continue
 
         try:
             # behavior_globals should contain a callable `behavior` if the user's code is correct
             behavior_globals = {}
-            bytecode = compile(desc.source.decode("utf-8"), desc.name, "exec")
+            bytecode = compile(desc["source"], desc["name"], "exec")
             exec(bytecode, behavior_globals)
             behavior_fn = behavior_globals.get("behavior")
             
             if callable(behavior_fn):
-                behaviors[desc.id] = {
-                    "name": desc.name,
-                    "language": desc.language,
-                    "required_col_names": desc.columns,
-                    "dyn_access": desc.dyn_access,
+                behaviors[behavior_id] = {
+                    "name": desc["name"],
+                    "language": desc["language"],
+                    "required_col_names": desc["required_field_keys"],
+                    "dyn_access": desc["dyn_access"],
                     "fn": behavior_fn
                 }
             else:
                 warnings.append(
-                    "Couldn't load behavior: No function named 'behavior': " + desc.name
+                    "Couldn't load behavior: No function named 'behavior': " + desc["name"]
                 )
             
         except Exception as e:
             # Have to catch generic `Exception`, because user's code could throw anything.
             n_pkg_fns = 2
-            tb = str(traceback.format_exception(type(exc), exc, tb)[n_pkg_fns:])
-            warnings.append("Couldn't load behavior: {}: {}".format(desc.name, tb))
+            tb = "".join(traceback.format_exception(type(e), e, e.__traceback__)[n_pkg_fns:])
+            warnings.append("Couldn't load behavior: {}: {}".format(desc["name"], tb))
 
         # With the current implementation, failing to load a behavior
         # isn't an error if the behavior is never actually used. This
@@ -48,15 +55,28 @@ def load_behaviors(behavior_descs):
     return behaviors, warnings
 
 def start_experiment(experiment, init_message, experiment_context):
-    experiment['behaviors'], warnings = load_behaviors(init_message.behavior_descs)
+    experiment['behaviors'], warnings = load_behaviors(init_message)
     return {
         "warnings": warnings
     }
 
 def format_behavior_error(behavior_name, exc, tb):
     n_pkg_fns = 2
-    full_msg = "Behavior error: " + str(traceback.format_exception(type(exc), exc, tb)[n_pkg_fns:])
-    return full_msg
+    details = "".join(traceback.format_exception(type(exc), exc, tb)[n_pkg_fns:])
+
+    # Behaviors are compiled with their name as file name, so the innermost frame with that
+    # file name is where the error was raised from in the user's code.
+    line_number = None
+    for frame in traceback.extract_tb(tb):
+        if frame.filename == behavior_name:
+            line_number = frame.lineno
+
+    return {
+        "msg": "Behavior error: {}: {}".format(type(exc).__name__, exc),
+        "details": details,
+        "file_name": behavior_name,
+        "line_number": line_number
+    }
 
 def postprocess(agent_state):
     msgs = agent_state.messages
@@ -86,47 +106,58 @@ def postprocess(agent_state):
         while len(direction) < 3:
             direction.append(0.0)
 
+# Targets of the languages behaviors can be written in, see `target_to_fbs` in the runner.
+LANGUAGE_TARGETS = {
+    "Python": "py",
+    "JavaScript": "js",
+    "Rust": "rs",
+    "Wasm": "wasm",
+    "External": "ext",
+}
+
 def run_task(experiment, sim, _task_message, group_state, group_context):
     next_lang = None
     agent_state = None
     agent_context = None
+    errors = []
     
     for i_agent in range(group_state.n_agents()):
     
         # Reuse `agent_state` object.
         agent_state = group_state.get_agent(i_agent, agent_state)
 
-        behavior_ids = agent_state.__behaviors
-        i_behavior = agent_state.__i_behavior # Need `i_behavior` outside loop scope.
+        # Private fields are loaded shallowly, i.e. as Arrow scalars.
+        behavior_ids = getattr(agent_state, BEHAVIOR_IDS_FIELD).as_py()
+        i_behavior = agent_state.behavior_index() # Need `i_behavior` outside loop scope.
         while i_behavior < len(behavior_ids):
-            agent_state.__i_behavior = i_behavior
+            agent_state.behavior_index = i_behavior
 
-            behavior = experiment.behaviors[behavior_ids[i_behavior]]
-            if behavior.language != "js":
-                next_lang = behavior.language # Multiple assignments are fine.
+            behavior = experiment["behaviors"][tuple(behavior_ids[i_behavior])]
+            if behavior["language"] != "Python":
+                next_lang = behavior["language"] # Multiple assignments are fine.
                 break
             
-            group_state.set_dynamic_access(behavior.dyn_access)
+            agent_state.set_dynamic_access(behavior["dyn_access"])
             agent_context = group_context.get_agent(i_agent, agent_context)    
     
             try:
-                behavior.fn(agent_state, agent_context)
+                behavior["fn"](agent_state, agent_context)
                 postprocess(agent_state)
                 
             except Exception as e:
                 # Have to catch generic `Exception`, because user's code could throw anything.
-                
-                error = format_behavior_error(behavior.name, e, sys.exc_info())
-                agent_state.__i_behavior = i_behavior
-                return {
-                    "target": "main",
-                    "errors": [error]
-                }
+                errors.append(format_behavior_error(behavior["name"], e, sys.exc_info()[2]))
+
+                # Skip the rest of this agent's behaviors for this step, but keep running the
+                # other agents, so a single faulty agent doesn't stop the simulation run.
+                i_behavior = len(behavior_ids)
+                break
 
             i_behavior += 1
 
-        agent_state.__i_behavior = i_behavior
+        agent_state.behavior_index = i_behavior
 
     return {
-        "target": next_lang if next_lang is not None else "main"
+        "target": LANGUAGE_TARGETS[next_lang] if next_lang is not None else "main",
+        "errors": errors
     }
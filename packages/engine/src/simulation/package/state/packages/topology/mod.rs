@@ -77,6 +77,11 @@ impl GetWorkerSimStartMsg for Topology {
 
 #[async_trait]
 impl Package for Topology {
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.config = Arc::new(TopologyConfig::from_globals(globals)?);
+        Ok(())
+    }
+
     async fn run(&mut self, state: &mut ExState, _context: &Context) -> Result<()> {
         log::trace!("Running Topology package");
         if self.config.move_wrapped_agents {
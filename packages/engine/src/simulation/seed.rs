@@ -0,0 +1,93 @@
+//! Seeds of simulation runs.
+//!
+//! Every experiment run has a single seed (see [`ExperimentConfig::seed`]), from which the seeds of
+//! its simulation runs are derived with [`derive_seed`]. All randomness in the engine which affects
+//! the outputs of a simulation run has to be derived from the simulation run's seed, so identical
+//! inputs and seeds give identical outputs.
+//!
+//! [`ExperimentConfig::seed`]: crate::config::ExperimentConfig::seed
+
+use serde::{Deserialize, Serialize};
+use uuid::{Variant, Version};
+
+/// Stream of the experiment run's seed which the optimizer of an optimization experiment draws its
+/// points from. Simulation runs use their (32 bit) id as stream, so it can't collide with them.
+pub const OPTIMIZER_STREAM: u64 = u64::MAX;
+
+/// Derives an independent seed for `stream` (e.g. the id of a simulation run) from `seed`.
+///
+/// Uses the finalizer of SplitMix64, so similar streams still give very different seeds.
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Generates the ids of agents which were created without an `agent_id`.
+///
+/// The ids are random (version 4) UUIDs, but the n-th id is derived from the seed of the simulation
+/// run and n, so it's the same in every run with the same seed. As that's all the state of the
+/// generator, it's stored in checkpoints to continue generating the same ids after restoring.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentIdGenerator {
+    seed: u64,
+    num_generated: u64,
+}
+
+impl AgentIdGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            num_generated: 0,
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        let stream = self.num_generated * 2;
+        self.num_generated += 1;
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&derive_seed(self.seed, stream).to_le_bytes());
+        bytes[8..].copy_from_slice(&derive_seed(self.seed, stream + 1).to_le_bytes());
+        uuid::Builder::from_bytes(bytes)
+            .set_variant(Variant::RFC4122)
+            .set_version(Version::Random)
+            .build()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn derived_seeds_differ_per_stream() {
+        assert_eq!(derive_seed(42, 0), derive_seed(42, 0));
+        assert_ne!(derive_seed(42, 0), derive_seed(42, 1));
+        assert_ne!(derive_seed(42, 0), derive_seed(43, 0));
+    }
+
+    #[test]
+    fn agent_ids_are_reproducible() {
+        let mut first = AgentIdGenerator::new(7);
+        let mut second = AgentIdGenerator::new(7);
+        let ids: Vec<_> = (0..3).map(|_| first.next_id()).collect();
+        assert_eq!(ids, (0..3).map(|_| second.next_id()).collect::<Vec<_>>());
+        assert_ne!(ids[0], ids[1]);
+
+        let id = Uuid::parse_str(&ids[0]).unwrap();
+        assert_eq!(id.get_version(), Some(Version::Random));
+    }
+
+    #[test]
+    fn stored_generator_continues() {
+        let mut original = AgentIdGenerator::new(7);
+        original.next_id();
+        let stored = serde_json::to_string(&original).unwrap();
+        let mut restored: AgentIdGenerator = serde_json::from_str(&stored).unwrap();
+        assert_eq!(restored.next_id(), original.next_id());
+    }
+}
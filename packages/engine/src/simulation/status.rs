@@ -1,10 +1,28 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 use super::Result;
 use crate::{
-    hash_types::worker::RunnerError, output::OutputPersistenceResultRepr, proto::SimulationShortId,
+    config::CheckpointData,
+    hash_types::worker::RunnerError,
+    output::OutputPersistenceResultRepr,
+    proto::SimulationShortId,
+    simulation::{package::output::packages::analysis::AnalysisOutput, termination::Criterion},
 };
 
+/// What stopped a simulation run before it took all of its steps.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StopSource {
+    /// An agent sent a stop message, which is the stop message of the simulation run
+    Agent,
+    /// A termination criterion held for long enough
+    Termination { criterion: Criterion },
+    /// The simulation run or the whole experiment was stopped, e.g. from the orchestrator
+    Experiment,
+}
+
 // Sent from sim runs to experiment main loop.
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SimStatus {
@@ -12,6 +30,7 @@ pub struct SimStatus {
     pub steps_taken: isize,
     pub early_stop: bool,
     pub stop_msg: Option<serde_json::Value>,
+    pub stop_source: Option<StopSource>,
     pub stop_signal: bool,
     pub persistence_result: Option<(String, serde_json::Value)>,
     // TODO: OS do we need these within SimStatus or should they be handled elsewhere, such as
@@ -19,24 +38,43 @@ pub struct SimStatus {
     pub error: Option<RunnerError>,
     pub warnings: Vec<RunnerError>,
     pub running: bool,
+    /// The analysis output of the step this status was sent for. Only forwarded to the experiment
+    /// package, not to the orchestrator.
+    #[serde(skip)]
+    pub analysis_output: Option<AnalysisOutput>,
+    /// The state the simulation run ended with, if other runs are forked from it. Only kept by the
+    /// experiment controller, not forwarded at all.
+    #[serde(skip)]
+    pub final_state: Option<Arc<CheckpointData>>,
 }
 
 impl SimStatus {
-    pub fn running(sim_id: SimulationShortId, steps_taken: isize) -> SimStatus {
+    pub fn running(
+        sim_id: SimulationShortId,
+        steps_taken: isize,
+        analysis_output: Option<AnalysisOutput>,
+    ) -> SimStatus {
         SimStatus {
             sim_id,
             steps_taken,
             running: true,
+            analysis_output,
             ..SimStatus::default()
         }
     }
 
     // TODO: Check this makes sense, default gives misleading amount of steps etc.
-    pub fn stop_signal(sim_id: SimulationShortId) -> SimStatus {
+    pub fn stop_signal(
+        sim_id: SimulationShortId,
+        analysis_output: Option<AnalysisOutput>,
+        final_state: Option<Arc<CheckpointData>>,
+    ) -> SimStatus {
         SimStatus {
             sim_id,
             running: false,
             stop_signal: true,
+            analysis_output,
+            final_state,
             ..SimStatus::default()
         }
     }
@@ -46,6 +84,7 @@ impl SimStatus {
         steps_taken: isize,
         early_stop: bool,
         stop_msg: Option<serde_json::Value>,
+        stop_source: Option<StopSource>,
         persistence_result: P,
     ) -> Result<SimStatus> {
         let persistence_result = OutputPersistenceResultRepr::into_value(persistence_result)
@@ -55,6 +94,7 @@ impl SimStatus {
             steps_taken,
             early_stop,
             stop_msg,
+            stop_source,
             persistence_result: Some(persistence_result),
             ..SimStatus::default()
         })
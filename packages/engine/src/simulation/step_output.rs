@@ -1,4 +1,4 @@
-use super::package::output::packages::Output;
+use super::package::output::packages::{analysis::AnalysisOutput, Output};
 
 pub struct SimulationStepOutput(pub Vec<Output>);
 
@@ -14,4 +14,12 @@ impl SimulationStepOutput {
     pub fn push(&mut self, output: Output) {
         self.0.push(output);
     }
+
+    /// The output of the analysis package for this step, if it's enabled
+    pub fn analysis_output(&self) -> Option<&AnalysisOutput> {
+        self.0.iter().find_map(|output| match output {
+            Output::AnalysisOutput(analysis) => Some(analysis),
+            _ => None,
+        })
+    }
 }
@@ -1,11 +1,16 @@
 use super::{agent_control::AgentControl, step_output::SimulationStepOutput};
-use crate::{hash_types::worker::RunnerError, proto::SimulationShortId};
+use crate::proto::SimulationShortId;
 
+/// The result of a single step of a simulation run.
+///
+/// Errors and warnings raised by user code while running the step aren't part of the result. They
+/// are reported by the runners as soon as they happen and forwarded to the experiment controller by
+/// the worker pool (see [`WorkerPoolToExpCtlMsg`]).
+///
+/// [`WorkerPoolToExpCtlMsg`]: crate::workerpool::comms::top::WorkerPoolToExpCtlMsg
 pub struct SimulationStepResult {
     pub sim_id: SimulationShortId,
     pub output: SimulationStepOutput,
-    pub errors: Vec<RunnerError>,
-    pub warnings: Vec<RunnerError>,
     pub agent_control: AgentControl,
     // True if this output signals the stopping of a simulation.
     // Can be False even if a stop signal was sent out before
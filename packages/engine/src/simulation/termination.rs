@@ -0,0 +1,309 @@
+//! Conditions under which a simulation run stops before reaching its maximum number of steps, e.g.
+//! when no agents are infected anymore.
+
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+use crate::{
+    config::{CheckpointMetadata, Globals},
+    simulation::package::output::packages::analysis::{AnalysisOutput, AnalysisSingleOutput},
+};
+
+/// Key of the list of termination criteria in the globals
+pub const TERMINATION_KEY: &str = "termination";
+
+/// The value a termination criterion is checked on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Quantity {
+    /// A number output of the analysis
+    Analysis { metric: String },
+    /// The number of agents in the simulation run
+    AgentCount,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+}
+
+impl Comparison {
+    fn holds(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        })
+    }
+}
+
+fn one_step() -> usize {
+    1
+}
+
+/// Stops the simulation run once `quantity` compared to `value` holds for `consecutive_steps`
+/// steps in a row.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Criterion {
+    #[serde(flatten)]
+    pub quantity: Quantity,
+    pub op: Comparison,
+    pub value: f64,
+    #[serde(default = "one_step")]
+    pub consecutive_steps: usize,
+}
+
+impl fmt::Display for Criterion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.quantity {
+            Quantity::Analysis { metric } => write!(f, "analysis output {metric:?}")?,
+            Quantity::AgentCount => f.write_str("agent count")?,
+        }
+        write!(f, " {} {}", self.op, self.value)?;
+        if self.consecutive_steps > 1 {
+            write!(f, " for {} consecutive steps", self.consecutive_steps)?;
+        }
+        Ok(())
+    }
+}
+
+/// The termination criteria of a simulation run, together with the number of consecutive steps
+/// each of them held for so far.
+#[derive(Debug, Clone, Default)]
+pub struct TerminationCriteria {
+    criteria: Vec<Criterion>,
+    streaks: Vec<usize>,
+}
+
+impl TerminationCriteria {
+    /// Reads the termination criteria from the `termination` key of `globals`, if there is one.
+    ///
+    /// `number_outputs` are the outputs of the analysis with whether they are a number (see
+    /// [`number_outputs`](crate::simulation::package::output::packages::analysis::number_outputs)).
+    /// Criteria on analysis outputs which don't exist or aren't numbers are rejected, so they don't
+    /// fail while the simulation run is stepping.
+    pub fn from_globals(
+        globals: &Globals,
+        number_outputs: &HashMap<String, bool>,
+    ) -> Result<TerminationCriteria> {
+        let criteria = Self::parse(globals)?;
+        for criterion in &criteria.criteria {
+            if let Quantity::Analysis { metric } = &criterion.quantity {
+                match number_outputs.get(metric) {
+                    Some(true) => {}
+                    Some(false) => {
+                        return Err(Error::from(format!(
+                            "Termination criterion refers to analysis output {metric:?}, which \
+                             isn't a number"
+                        )));
+                    }
+                    None => {
+                        return Err(Error::from(format!(
+                            "Termination criterion refers to unknown analysis output {metric:?}"
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(criteria)
+    }
+
+    fn parse(globals: &Globals) -> Result<TerminationCriteria> {
+        let value = match globals.get(TERMINATION_KEY) {
+            Some(value) => value.clone(),
+            None => return Ok(TerminationCriteria::default()),
+        };
+        let criteria: Vec<Criterion> = serde_json::from_value(value).map_err(|e| {
+            Error::from(format!(
+                "`{TERMINATION_KEY}` in globals must be a list of termination criteria: {e}"
+            ))
+        })?;
+        if criteria
+            .iter()
+            .any(|criterion| criterion.consecutive_steps == 0)
+        {
+            return Err(Error::from(format!(
+                "`consecutiveSteps` of the criteria in `{TERMINATION_KEY}` must be at least 1"
+            )));
+        }
+        Ok(TerminationCriteria {
+            streaks: vec![0; criteria.len()],
+            criteria,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.criteria.is_empty()
+    }
+
+    /// The number of consecutive steps each criterion held for so far.
+    pub fn streaks(&self) -> &[usize] {
+        &self.streaks
+    }
+
+    /// Continues the streaks of the run which wrote `checkpoint`, if it had the same criteria.
+    pub fn resume(&mut self, checkpoint: &CheckpointMetadata) -> Result<()> {
+        let previous = TerminationCriteria::parse(&checkpoint.globals)?;
+        if previous.criteria == self.criteria
+            && checkpoint.termination_streaks.len() == self.criteria.len()
+        {
+            self.streaks = checkpoint.termination_streaks.clone();
+        }
+        Ok(())
+    }
+
+    /// Checks the criteria against the outputs of the step just taken. Returns the criterion which
+    /// stops the simulation run, if any.
+    pub fn check(
+        &mut self,
+        analysis_output: Option<&AnalysisOutput>,
+        num_agents: usize,
+    ) -> Result<Option<&Criterion>> {
+        let mut triggered = None;
+        for (index, criterion) in self.criteria.iter().enumerate() {
+            let current = match &criterion.quantity {
+                Quantity::AgentCount => Some(num_agents as f64),
+                Quantity::Analysis { metric } => {
+                    match analysis_output.and_then(|output| output.inner.get(metric)) {
+                        Some(AnalysisSingleOutput::Number(number)) => *number,
+                        Some(AnalysisSingleOutput::Vec(_)) => {
+                            return Err(Error::from(format!(
+                                "Termination criterion refers to analysis output {metric:?}, \
+                                 which isn't a number"
+                            )));
+                        }
+                        None => {
+                            return Err(Error::from(format!(
+                                "Termination criterion refers to unknown analysis output \
+                                 {metric:?}"
+                            )));
+                        }
+                    }
+                }
+            };
+            // Outputs without a value (e.g. the mean of no agents) don't satisfy any comparison
+            if current.map_or(false, |current| {
+                criterion.op.holds(current, criterion.value)
+            }) {
+                self.streaks[index] += 1;
+            } else {
+                self.streaks[index] = 0;
+            }
+            if triggered.is_none() && self.streaks[index] >= criterion.consecutive_steps {
+                triggered = Some(index);
+            }
+        }
+        Ok(triggered.map(|index| &self.criteria[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn infected(value: f64) -> AnalysisOutput {
+        AnalysisOutput {
+            inner: HashMap::from([(
+                Arc::new("infected".to_string()),
+                AnalysisSingleOutput::some_number(value),
+            )]),
+        }
+    }
+
+    fn number_outputs() -> HashMap<String, bool> {
+        HashMap::from([("infected".to_string(), true), ("ages".to_string(), false)])
+    }
+
+    #[test]
+    fn triggers_after_consecutive_steps() -> Result<()> {
+        let mut criteria = TerminationCriteria::from_globals(
+            &Globals(json!({
+                "termination": [
+                    {
+                        "type": "analysis",
+                        "metric": "infected",
+                        "op": "==",
+                        "value": 0,
+                        "consecutiveSteps": 2,
+                    },
+                    { "type": "agentCount", "op": ">", "value": 100 },
+                ],
+            })),
+            &number_outputs(),
+        )?;
+
+        assert_eq!(criteria.check(Some(&infected(0.0)), 10)?, None);
+        assert_eq!(criteria.check(Some(&infected(1.0)), 10)?, None);
+        assert_eq!(criteria.check(Some(&infected(0.0)), 10)?, None);
+        let stopped_by = criteria.check(Some(&infected(0.0)), 10)?.unwrap();
+        assert_eq!(stopped_by.consecutive_steps, 2);
+        assert_eq!(
+            stopped_by.to_string(),
+            "analysis output \"infected\" == 0 for 2 consecutive steps"
+        );
+
+        let stopped_by = criteria.check(Some(&infected(5.0)), 101)?.unwrap();
+        assert_eq!(stopped_by.quantity, Quantity::AgentCount);
+        assert_eq!(stopped_by.to_string(), "agent count > 100");
+
+        assert!(criteria.check(None, 10).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_criteria() {
+        let invalid = [
+            json!({ "termination": { "type": "agentCount", "op": ">", "value": 1 } }),
+            json!({ "termination": [{ "type": "agentCount", "op": "=", "value": 1 }] }),
+            json!({ "termination": [{ "type": "analysis", "op": ">", "value": 1 }] }),
+            json!({ "termination": [
+                { "type": "agentCount", "op": ">", "value": 1, "consecutiveSteps": 0 }
+            ] }),
+            json!({ "termination": [
+                { "type": "analysis", "metric": "recovered", "op": ">", "value": 1 }
+            ] }),
+            json!({ "termination": [
+                { "type": "analysis", "metric": "ages", "op": ">", "value": 1 }
+            ] }),
+        ];
+        for globals in invalid {
+            assert!(
+                TerminationCriteria::from_globals(&Globals(globals), &number_outputs()).is_err()
+            );
+        }
+    }
+}
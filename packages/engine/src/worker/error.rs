@@ -1,7 +1,10 @@
 use thiserror::Error as ThisError;
 use tokio::sync::mpsc::error::SendError;
 
-use super::runner::{javascript::Error as JavaScriptError, python::Error as PythonError};
+use super::runner::{
+    external::Error as ExternalError, javascript::Error as JavaScriptError,
+    rust::Error as RustError, wasm::Error as WasmError,
+};
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -19,14 +22,18 @@ pub enum Error {
     #[error("Task already exists (id: {0})")]
     TaskAlreadyExists(crate::types::TaskId),
 
-    #[error("Python runner error: {0}")]
-    Python(#[from] PythonError),
+    #[error("External runner error: {0}")]
+    External(#[from] ExternalError),
 
     #[error("JavaScript runner error: {0}")]
     JavaScript(#[from] JavaScriptError),
 
-    // #[error("Rust runner error: {0}")]
-    // Rust(#[from] RustError),
+    #[error("Rust runner error: {0}")]
+    Rust(#[from] RustError),
+
+    #[error("WebAssembly runner error: {0}")]
+    Wasm(#[from] WasmError),
+
     #[error("Simulation: {0}")]
     Simulation(#[from] crate::simulation::Error),
 
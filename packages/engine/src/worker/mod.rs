@@ -1,16 +1,14 @@
 pub mod error;
 mod pending;
 pub mod runner;
+mod runs;
 pub mod task;
 
-use std::{future::Future, pin::Pin, time::Duration};
+use std::{future::Future, pin::Pin, result::Result as StdResult, time::Duration};
 
 pub use error::{Error, Result};
-use futures::{
-    stream::{FuturesOrdered, FuturesUnordered},
-    StreamExt,
-};
-use tokio::time::timeout;
+use futures::{stream::FuturesUnordered, StreamExt};
+use tokio::{task::JoinError, time::timeout};
 
 use self::{
     pending::PendingWorkerTasks,
@@ -19,10 +17,14 @@ use self::{
             outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
             ExperimentInitRunnerMsg, NewSimulationRun, RunnerTaskMsg,
         },
+        external::{ExternalRunner, RunnerProcess},
         javascript::JavaScriptRunner,
-        python::PythonRunner,
+        python,
         rust::RustRunner,
+        wasm::WasmRunner,
+        LanguageWorker,
     },
+    runs::SimulationRuns,
     task::{WorkerTask, WorkerTaskResultOrCancelled},
 };
 use crate::{
@@ -49,9 +51,11 @@ use crate::{
     Language,
 };
 
+type RunnerHandle = Pin<Box<dyn Future<Output = StdResult<Result<()>, JoinError>> + Send>>;
+
 /// A task worker.-
 ///
-/// Represents three dedicated language workers.
+/// Represents five dedicated language workers, one of them running the project's external runner.
 ///
 /// ### Running a task
 ///
@@ -59,13 +63,25 @@ use crate::{
 /// - Start a task with a target language
 /// - Handle language switches
 /// - Upon completion return a completion message
+///
+/// ### Runner crashes
+///
+/// If a runner crashes, the tasks it was running are cancelled, which fails their simulation runs,
+/// and the runner is respawned. The other simulation runs continue: the respawned runner gets the
+/// latest syncs of a simulation run before it runs one of its tasks.
 pub struct WorkerController {
-    py: PythonRunner,
+    py: ExternalRunner,
     js: JavaScriptRunner,
     rs: RustRunner,
-    _config: WorkerConfig, // TODO: unused, remove?
+    wasm: WasmRunner,
+    ext: ExternalRunner,
+    config: WorkerConfig,
+    /// Kept for respawning runners.
+    exp_init: ExperimentInitRunnerMsg,
     worker_pool_comms: WorkerCommsWithWorkerPool,
     tasks: PendingWorkerTasks,
+    simulation_runs: SimulationRuns,
+    runner_restarts: [usize; Language::NUM],
 }
 
 // TODO: impl drop for worker controller?
@@ -80,15 +96,27 @@ impl WorkerController {
             python,
             javascript,
             rust,
+            wasm,
+            ref external,
         } = config.spawn;
         // TODO: Rust, JS
         Ok(WorkerController {
-            py: PythonRunner::new(python, exp_init.clone())?,
-            js: JavaScriptRunner::new(javascript, exp_init.clone())?,
-            rs: RustRunner::new(rust, exp_init)?,
-            _config: config,
+            py: python::runner(python, exp_init.clone(), config.task_timeout)?,
+            js: JavaScriptRunner::new(javascript, exp_init.clone(), config.task_timeout)?,
+            rs: RustRunner::new(rust, exp_init.clone(), config.task_timeout)?,
+            wasm: WasmRunner::new(wasm, exp_init.clone(), config.task_timeout)?,
+            ext: ExternalRunner::new(
+                Language::External,
+                external.as_ref().map(RunnerProcess::from),
+                exp_init.clone(),
+                config.task_timeout,
+            )?,
+            config,
+            exp_init,
             worker_pool_comms,
             tasks: PendingWorkerTasks::default(),
+            simulation_runs: SimulationRuns::default(),
+            runner_restarts: [0; Language::NUM],
         })
     }
 
@@ -112,10 +140,181 @@ impl WorkerController {
         Err(e) // TODO
     }
 
+    /// Handles the runner of `language` finishing with `result` while the worker is still running,
+    /// i.e. crashing.
+    ///
+    /// The tasks which were running on the runner are failed, and the runner is respawned and
+    /// registered with the simulation runs again. Returns the handle of the respawned runner, or
+    /// the error of the runner if it already crashed as often as the worker config allows.
+    async fn respawn_runner(
+        &mut self,
+        language: Language,
+        result: StdResult<Result<()>, JoinError>,
+    ) -> Result<RunnerHandle> {
+        let error = match result {
+            Ok(Ok(())) => Error::from(format!("{language} runner stopped unexpectedly")),
+            Ok(Err(error)) => error,
+            Err(error) => error.into(),
+        };
+        let max_restarts = self.config.max_runner_restarts;
+        let restarts = &mut self.runner_restarts[language.as_index()];
+        if *restarts == max_restarts {
+            log::error!("{language} runner crashed after {max_restarts} restarts, stopping worker");
+            return Err(error);
+        }
+        *restarts += 1;
+        log::error!("{language} runner crashed, respawning it: {error}");
+
+        let crashed_tasks: Vec<_> = self
+            .tasks
+            .inner
+            .iter()
+            .filter(|(_, task)| task.active_runner == language)
+            .map(|(&task_id, task)| (task_id, task.sim_id))
+            .collect();
+        for (task_id, sim_id) in crashed_tasks {
+            self.fail_task(
+                task_id,
+                sim_id,
+                format!("The {language} runner crashed while running a task: {error}"),
+            )?;
+        }
+
+        let exp_init = self.exp_init.clone();
+        let task_timeout = self.config.task_timeout;
+        let handle = match language {
+            Language::Python => {
+                self.py = python::runner(true, exp_init, task_timeout)?;
+                self.py.run().await?
+            }
+            Language::JavaScript => {
+                self.js = JavaScriptRunner::new(true, exp_init, task_timeout)?;
+                self.js.run().await?
+            }
+            Language::Rust => {
+                self.rs = RustRunner::new(true, exp_init, task_timeout)?;
+                self.rs.run().await?
+            }
+            Language::Wasm => {
+                self.wasm = WasmRunner::new(true, exp_init, task_timeout)?;
+                self.wasm.run().await?
+            }
+            Language::External => {
+                let process = self.config.spawn.external.as_ref().map(RunnerProcess::from);
+                self.ext = ExternalRunner::new(language, process, exp_init, task_timeout)?;
+                self.ext.run().await?
+            }
+        };
+        for (sim_id, msg) in self.simulation_runs.respawn(language) {
+            self.send_to_runner(language, sim_id, msg).await?;
+        }
+        Ok(handle)
+    }
+
+    /// The runner executing the behaviors of `language`.
+    fn runner(&self, language: Language) -> &dyn LanguageWorker {
+        match language {
+            Language::Python => &self.py,
+            Language::JavaScript => &self.js,
+            Language::Rust => &self.rs,
+            Language::Wasm => &self.wasm,
+            Language::External => &self.ext,
+        }
+    }
+
+    async fn send_to_runner(
+        &self,
+        language: Language,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> Result<()> {
+        self.runner(language).send(sim_id, msg).await
+    }
+
+    /// Continues the task of `msg` on the runner of `language`, which becomes the active runner of
+    /// the task.
+    async fn send_task_to_runner(
+        &mut self,
+        sim_id: SimulationShortId,
+        language: Language,
+        msg: RunnerTaskMsg,
+    ) -> Result<()> {
+        self.catch_up_runner(sim_id, language).await?;
+        let task_id = msg.task_id;
+        log::trace!("Sending task {task_id} to the {language} runner");
+        self.send_to_runner(
+            language,
+            Some(sim_id),
+            InboundToRunnerMsgPayload::TaskMsg(msg),
+        )
+        .await?;
+        if let Some(pending_task) = self.tasks.inner.get_mut(&task_id) {
+            pending_task.active_runner = language;
+        }
+        Ok(())
+    }
+
+    /// Sends the runner of `language` the syncs of the simulation run `sim_id` it missed since it
+    /// was respawned, if any, before it gets a task of the simulation run.
+    async fn catch_up_runner(
+        &mut self,
+        sim_id: SimulationShortId,
+        language: Language,
+    ) -> Result<()> {
+        let (msgs, state_sync_completion) = self.simulation_runs.catch_up(sim_id, language)?;
+        for msg in msgs {
+            self.send_to_runner(language, Some(sim_id), msg).await?;
+        }
+        if let Some(completion) = state_sync_completion {
+            // Runners handle messages in order, so the task doesn't have to wait for the sync.
+            // The receiver is kept until the runner completed the sync, as runners fail if nobody
+            // waits for it.
+            tokio::spawn(async move {
+                if let Ok(Err(error)) = completion.await {
+                    log::warn!(
+                        "{language} runner couldn't catch up with simulation run {sim_id}: {error}"
+                    );
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Fails the task `task_id` by cancelling it, which stops its simulation run `sim_id`, and
+    /// reports `reason` as error of the simulation run.
+    fn fail_task(
+        &mut self,
+        task_id: TaskId,
+        sim_id: SimulationShortId,
+        reason: String,
+    ) -> Result<()> {
+        log::warn!("Failing task {task_id} of simulation run {sim_id}: {reason}");
+        self.tasks.inner.remove(&task_id);
+        self.worker_pool_comms.send(
+            sim_id,
+            WorkerToWorkerPoolMsg::RunnerErrors(vec![RunnerError {
+                message: Some(reason),
+                details: None,
+                file_name: None,
+                line_number: None,
+            }]),
+        )?;
+        self.worker_pool_comms.send(
+            sim_id,
+            WorkerToWorkerPoolMsg::TaskResultOrCancelled(WorkerTaskResultOrCancelled {
+                task_id,
+                payload: TaskResultOrCancelled::Cancelled,
+            }),
+        )?;
+        Ok(())
+    }
+
     async fn _run(&mut self) -> Result<()> {
-        // TODO: Rust, JS
-        // let mut py_handle = self.py.run().await?;
+        let mut py_handle = self.py.run().await?;
         let mut js_handle = self.js.run().await?;
+        let mut rs_handle = self.rs.run().await?;
+        let mut wasm_handle = self.wasm.run().await?;
+        let mut ext_handle = self.ext.run().await?;
 
         let mut wp_recv = self.worker_pool_comms.take_recv()?;
         let mut terminate_recv = self
@@ -133,7 +332,7 @@ impl WorkerController {
                     log::debug!("Handle worker pool message: {:?}", &msg);
                     self.handle_worker_pool_msg(msg, &mut pending_syncs).await?;
                 }
-                res = self.recv_from_runners() => {
+                (language, res) = self.recv_from_runners() => {
                     match res {
                         Ok(msg) => {
                             log::debug!("Handle message from runners: {:?}", &msg);
@@ -143,27 +342,17 @@ impl WorkerController {
                             // Check whether the root cause is actually a problem
                             // with receiving or simply that the runner exited
                             // already, so we can't receive from it.
-                            let duration = Duration::from_millis(500);
-                            let mut runner_futs = FuturesOrdered::new();
-                            // runner_futs.push(timeout(duration, py_handle));
-                            runner_futs.push(timeout(duration, js_handle));
-                            let timeout_results: Vec<_> = runner_futs.collect().await;
-                            #[allow(clippy::manual_flatten)]
-                            for timeout_result in timeout_results {
-                                // If any of the runners exited with an error,
-                                // return that error instead of `recv_err`.
-                                if let Ok(runner_result) = timeout_result {
-                                    // Runner finished -- didn't time out
-                                    match runner_result {
-                                        Err(e) => return Err(e.into()),
-                                        Ok(Err(e)) => return Err(e),
-                                        Ok(Ok(_)) => {}
-                                    }
-                                }
-                            }
-                            // Either none of the runners exited or none of
-                            // them returned an error, so return `recv_err`.
-                            return Err(recv_err);
+                            let handle = match language {
+                                Language::Python => &mut py_handle,
+                                Language::JavaScript => &mut js_handle,
+                                Language::Rust => &mut rs_handle,
+                                Language::Wasm => &mut wasm_handle,
+                                Language::External => &mut ext_handle,
+                            };
+                            let runner_result = timeout(Duration::from_millis(500), &mut *handle)
+                                .await
+                                .map_err(|_| recv_err)?;
+                            *handle = self.respawn_runner(language, runner_result).await?;
                         }
                     }
                 }
@@ -176,24 +365,30 @@ impl WorkerController {
                     self.worker_pool_comms.confirm_terminate().map_err(|err| Error::from(format!("Failed to send confirmation of terminating workers: {:?}", err)))?;
                     break;
                 }
-                // py_res = &mut py_handle => {
-                //     log::debug!("Python runner finished unexpectedly");
-                //     py_res??;
-                //     // TODO: send termination to js_handle
-                //     js_handle.await??;
-                //     return Ok(());
-                // }
-                js_res = &mut js_handle => {
-                    log::debug!("Javascript runner finished unexpectedly: {:?}", js_res);
-                    js_res??;
-                    // TODO: send termination to py_handle
-                    // py_handle.await??;
-                    return Ok(());
+                // Runners only finish on their own when they crash. Runners which weren't spawned
+                // finish immediately, so only spawned runners are polled here.
+                py_res = &mut py_handle, if self.py.spawned() => {
+                    py_handle = self.respawn_runner(Language::Python, py_res).await?;
+                }
+                js_res = &mut js_handle, if self.js.spawned() => {
+                    js_handle = self.respawn_runner(Language::JavaScript, js_res).await?;
+                }
+                rs_res = &mut rs_handle, if self.rs.spawned() => {
+                    rs_handle = self.respawn_runner(Language::Rust, rs_res).await?;
+                }
+                wasm_res = &mut wasm_handle, if self.wasm.spawned() => {
+                    wasm_handle = self.respawn_runner(Language::Wasm, wasm_res).await?;
+                }
+                ext_res = &mut ext_handle, if self.ext.spawned() => {
+                    ext_handle = self.respawn_runner(Language::External, ext_res).await?;
                 }
             }
         }
-        // py_handle.await??;
+        py_handle.await??;
         js_handle.await??;
+        rs_handle.await??;
+        wasm_handle.await??;
+        ext_handle.await??;
         Ok(())
     }
 
@@ -226,48 +421,23 @@ impl WorkerController {
     }
 
     async fn handle_runner_msg(&mut self, msg: OutboundFromRunnerMsg) -> Result<()> {
-        use MessageTarget::*;
         use OutboundFromRunnerMsgPayload::*;
         let sim_id = msg.sim_id;
         match msg.payload {
-            TaskMsg(task) => {
-                let pending_task = self.tasks.inner.get_mut(&task.msg.task_id);
-                match task.target {
-                    Rust => {
-                        self.rs
-                            .send(Some(sim_id), InboundToRunnerMsgPayload::TaskMsg(task.msg))
-                            .await?;
-                        if let Some(pending_task) = pending_task {
-                            pending_task.active_runner = Language::Rust;
-                        }
-                    }
-                    Python => {
-                        self.py
-                            .send(Some(sim_id), InboundToRunnerMsgPayload::TaskMsg(task.msg))
-                            .await?;
-                        if let Some(pending_task) = pending_task {
-                            pending_task.active_runner = Language::Python;
-                        }
-                    }
-                    JavaScript => {
-                        self.js
-                            .send(Some(sim_id), InboundToRunnerMsgPayload::TaskMsg(task.msg))
-                            .await?;
-                        if let Some(pending_task) = pending_task {
-                            pending_task.active_runner = Language::JavaScript;
-                        }
-                    }
-                    Dynamic => {
-                        self.run_task_handler_on_outbound(sim_id, task.msg, msg.source)
-                            .await?;
-                    }
-                    Main => {
-                        log::trace!("Task message came back to main, finishing task");
-                        self.finish_task_from_runner_msg(sim_id, task.msg, msg.source)
-                            .await?;
-                    }
+            TaskMsg(task) => match task.target.language() {
+                Some(language) => {
+                    self.send_task_to_runner(sim_id, language, task.msg).await?;
                 }
-            }
+                None if task.target == MessageTarget::Dynamic => {
+                    self.run_task_handler_on_outbound(sim_id, task.msg, msg.source)
+                        .await?;
+                }
+                None => {
+                    log::trace!("Task message came back to main, finishing task");
+                    self.finish_task_from_runner_msg(sim_id, task.msg, msg.source)
+                        .await?;
+                }
+            },
             TaskCancelled(task_id) => {
                 self.handle_cancel_task_confirmation(task_id, sim_id, msg.source)
                     .await?;
@@ -278,19 +448,26 @@ impl WorkerController {
             RunnerWarnings(warnings) => self.handle_warnings(sim_id, warnings).await?,
             RunnerLog(log) => self.handle_logs(sim_id, vec![log]).await?,
             RunnerLogs(logs) => self.handle_logs(sim_id, logs).await?,
+            PackageError(err) => self.handle_errors(sim_id, vec![err]).await?,
+            UserErrors(errs) => self.handle_errors(sim_id, errs).await?,
+            UserWarnings(warnings) => self.handle_warnings(sim_id, warnings).await?,
+            StateSyncCompleted => {
+                // Runners complete the sync themselves once it's acknowledged.
+                log::warn!(
+                    "{} runner forwarded a state sync acknowledgement",
+                    msg.source
+                );
+            }
         }
         Ok(())
     }
 
     async fn terminate_runners(&mut self) -> Result<()> {
-        tokio::try_join!(
-            self.py
-                .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner),
-            self.js
-                .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner),
-            self.rs
+        for language in Language::ORDERED {
+            self.runner(language)
                 .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner)
-        )?;
+                .await?;
+        }
         Ok(())
     }
 
@@ -309,7 +486,8 @@ impl WorkerController {
             drop(shared_store);
 
             log::trace!("Cancelling tasks on the other runners");
-            self.cancel_task_except_for_runner(task_id, source).await?;
+            self.cancel_task_except_for_runner(task_id, sim_id, source)
+                .await?;
 
             self.worker_pool_comms.send(
                 sim_id,
@@ -332,83 +510,38 @@ impl WorkerController {
             .await
     }
 
-    fn inbound_from_task_msg(
-        task_id: TaskId,
-        package_id: PackageId,
-        shared_store: TaskSharedStore,
-        task_msg: TaskMessage,
-    ) -> InboundToRunnerMsgPayload {
-        InboundToRunnerMsgPayload::TaskMsg(RunnerTaskMsg {
-            task_id,
-            package_id,
-            shared_store,
-            payload: task_msg,
-        })
-    }
-
     async fn run_task_handler_on_outbound(
         &mut self,
         sim_id: SimulationShortId,
         msg: RunnerTaskMsg,
         source: Language,
     ) -> Result<()> {
-        use MessageTarget::*;
-        if let Some(pending) = self.tasks.inner.get_mut(&msg.task_id) {
-            let next = WorkerHandler::handle_worker_message(&mut pending.inner, msg.payload)?;
-            match next.target {
-                Rust => {
-                    let inbound = Self::inbound_from_task_msg(
-                        msg.task_id,
-                        msg.package_id,
-                        msg.shared_store,
-                        next.payload,
-                    );
-                    log::trace!(
-                        "Task resulted in a new message from Runner, sending new one to Rust: {:?}",
-                        &inbound
-                    );
-                    self.rs.send(Some(sim_id), inbound).await?;
-                    pending.active_runner = Language::Rust;
-                }
-                Python => {
-                    let inbound = Self::inbound_from_task_msg(
-                        msg.task_id,
-                        msg.package_id,
-                        msg.shared_store,
-                        next.payload,
-                    );
-                    log::trace!(
-                        "Task resulted in a new message from Runner, sending new one to Python: \
-                         {:?}",
-                        &inbound
-                    );
-                    self.py.send(Some(sim_id), inbound).await?;
-                    pending.active_runner = Language::Python;
-                }
-                JavaScript => {
-                    let inbound = Self::inbound_from_task_msg(
-                        msg.task_id,
-                        msg.package_id,
-                        msg.shared_store,
-                        next.payload,
-                    );
-                    log::trace!(
-                        "Task resulted in a new message from Runner, sending new one to \
-                         JavaScript: {:?}",
-                        &inbound
-                    );
-                    self.js.send(Some(sim_id), inbound).await?;
-                    pending.active_runner = Language::JavaScript;
-                }
-                Dynamic => return Err(Error::UnexpectedTarget(next.target)),
-                Main => {
-                    log::trace!("Task message came back to main, finishing task");
-                    self.finish_task(msg.task_id, sim_id, source, next.payload, msg.shared_store)
-                        .await?
-                }
+        let next = match self.tasks.inner.get_mut(&msg.task_id) {
+            Some(pending) => WorkerHandler::handle_worker_message(&mut pending.inner, msg.payload)?,
+            None => return Ok(()),
+        };
+        match next.target.language() {
+            Some(language) => {
+                log::trace!(
+                    "Task resulted in a new message from Runner, sending new one to {language}"
+                );
+                let msg = RunnerTaskMsg {
+                    task_id: msg.task_id,
+                    package_id: msg.package_id,
+                    shared_store: msg.shared_store,
+                    payload: next.payload,
+                };
+                self.send_task_to_runner(sim_id, language, msg).await
+            }
+            None if next.target == MessageTarget::Dynamic => {
+                Err(Error::UnexpectedTarget(next.target))
+            }
+            None => {
+                log::trace!("Task message came back to main, finishing task");
+                self.finish_task(msg.task_id, sim_id, source, next.payload, msg.shared_store)
+                    .await
             }
         }
-        Ok(())
     }
 
     async fn handle_cancel_task_confirmation(
@@ -419,7 +552,8 @@ impl WorkerController {
     ) -> Result<()> {
         if let Some(task) = self.tasks.inner.get_mut(&task_id) {
             if let CancelState::None = task.cancelling {
-                log::warn!("Unexpected task cancelling confirmation");
+                // Runners cancel tasks on their own when they exceed the task timeout
+                log::debug!("Runner cancelled task {task_id} without a cancel request");
                 task.cancelling = CancelState::Active(vec![source]);
             } else if let CancelState::Active(langs) = &mut task.cancelling {
                 if !langs.contains(&source) {
@@ -470,40 +604,28 @@ impl WorkerController {
     }
 
     async fn spawn_task(&mut self, sim_id: SimulationShortId, task: WorkerTask) -> Result<()> {
-        use MessageTarget::*;
         let task_id = task.task_id;
         let init_msg = WorkerHandler::start_message(&task.inner)?;
-        let runner_msg = InboundToRunnerMsgPayload::TaskMsg(RunnerTaskMsg {
+        // Expected initial message to be directed to a language runtime
+        let active_runner = init_msg
+            .target
+            .language()
+            .ok_or(Error::UnexpectedTarget(init_msg.target))?;
+        let runner_msg = RunnerTaskMsg {
             task_id,
             package_id: task.package_id,
             shared_store: task.shared_store,
             payload: init_msg.payload,
-        });
-        let active_runner = match init_msg.target {
-            Python => {
-                log::debug!("Sending task message to Python");
-                self.py.send(Some(sim_id), runner_msg).await?;
-                Language::Python
-            }
-            JavaScript => {
-                log::debug!("Sending task message to JavaScript");
-                self.js.send(Some(sim_id), runner_msg).await?;
-                Language::JavaScript
-            }
-            Rust => {
-                log::debug!("Sending task message to Rust");
-                self.rs.send(Some(sim_id), runner_msg).await?;
-                Language::Rust
-            }
-            Main | Dynamic => {
-                // Expected initial message to be directed to a language runtime
-                return Err(Error::UnexpectedTarget(init_msg.target));
-            }
         };
+        self.send_task_to_runner(sim_id, active_runner, runner_msg)
+            .await?;
         if self
             .tasks
             .inner
-            .insert(task_id, PendingWorkerTask::new(task.inner, active_runner))
+            .insert(
+                task_id,
+                PendingWorkerTask::new(sim_id, task.inner, active_runner),
+            )
             .is_some()
         {
             return Err(Error::TaskAlreadyExists(task_id));
@@ -517,35 +639,34 @@ impl WorkerController {
         sync_msg: SyncPayload,
         pending_syncs: &mut FuturesUnordered<Pin<Box<dyn Future<Output = ()> + Send>>>,
     ) -> Result<()> {
+        if let Some(sim_id) = sim_id {
+            self.simulation_runs.record_sync(sim_id, &sync_msg)?;
+        }
         let sync = if let SyncPayload::State(sync) = sync_msg {
             sync
         } else {
-            tokio::try_join!(
-                self.py
-                    .send_if_spawned(sim_id, sync_msg.try_clone()?.into()),
-                self.js
-                    .send_if_spawned(sim_id, sync_msg.try_clone()?.into()),
-                self.rs.send_if_spawned(sim_id, sync_msg.into())
-            )?;
+            for language in Language::ORDERED {
+                self.runner(language)
+                    .send_if_spawned(sim_id, sync_msg.try_clone()?.into())
+                    .await?;
+            }
             return Ok(());
         };
 
-        // TODO: Change to `children(3)` after enabling all runners.
-        debug_assert!(!self.py.spawned());
-        debug_assert!(!self.rs.spawned());
-        let (runner_msgs, runner_receivers) = sync.create_children(1);
-        let mut runner_msgs: Vec<_> = runner_msgs
+        // Every spawned runner gets its own copy of the sync, which it has to confirm.
+        let spawned: Vec<_> = Language::ORDERED
             .into_iter()
-            .map(InboundToRunnerMsgPayload::StateSync)
+            .filter(|&language| self.runner(language).spawned())
             .collect();
-        // Borrow checker doesn't allow just `runner_msgs[0]`,
-        // because it would be a partial move.
-        let js_msg = runner_msgs.remove(0);
-        tokio::try_join!(
-            self.js.send_if_spawned(sim_id, js_msg),
-            /* TODO: self.py.send_if_spawned(msg.sim_id, runner_msgs[1]),
-             * TODO: self.rs.send_if_spawned(msg.sim_id, runner_msgs[2]), */
-        )?;
+        let (runner_msgs, runner_receivers) = sync.create_children(spawned.len());
+        for (language, runner_msg) in spawned.into_iter().zip(runner_msgs) {
+            self.send_to_runner(
+                language,
+                sim_id,
+                InboundToRunnerMsgPayload::StateSync(runner_msg),
+            )
+            .await?;
+        }
         let fut = async move {
             let sync = sync; // Capture `sync` in lambda.
             sync.forward_children(runner_receivers).await
@@ -555,80 +676,70 @@ impl WorkerController {
     }
 
     async fn cancel_task(&mut self, task_id: TaskId) -> Result<()> {
+        let mut sim_id = None;
         if let Some(task) = self.tasks.inner.get_mut(&task_id) {
             task.cancelling = CancelState::Active(vec![task.active_runner]); // TODO: Or `CancelState::None`?
+            sim_id = Some(task.sim_id);
+        }
+        for language in Language::ORDERED {
+            self.runner(language)
+                .send_if_spawned(sim_id, InboundToRunnerMsgPayload::CancelTask(task_id))
+                .await?;
         }
-        tokio::try_join!(
-            self.py
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id)),
-            self.js
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id)),
-            self.rs
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-        )?;
         Ok(())
     }
 
     async fn cancel_task_except_for_runner(
         &self,
         task_id: TaskId,
+        sim_id: SimulationShortId,
         runner_language: Language,
     ) -> Result<()> {
-        if matches!(runner_language, Language::Python) {
-            self.js
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-            self.rs
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-        }
-        if matches!(runner_language, Language::JavaScript) {
-            self.py
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-            self.rs
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-        }
-        if matches!(runner_language, Language::Rust) {
-            self.py
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-            self.js
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
+        for language in Language::ORDERED {
+            if language != runner_language {
+                self.runner(language)
+                    .send_if_spawned(Some(sim_id), InboundToRunnerMsgPayload::CancelTask(task_id))
+                    .await?;
+            }
         }
         Ok(())
     }
 
     async fn new_simulation_run(&mut self, new_simulation_run: NewSimulationRun) -> Result<()> {
-        tokio::try_join!(
-            self.py.send_if_spawned(
-                None,
-                InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run.clone())
-            ),
-            self.js.send_if_spawned(
-                None,
-                InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run.clone())
-            ),
-            self.rs.send_if_spawned(
-                None,
-                InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run)
-            )
-        )?;
+        self.simulation_runs.push(new_simulation_run.clone())?;
+        for language in Language::ORDERED {
+            self.runner(language)
+                .send_if_spawned(
+                    None,
+                    InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run.clone()),
+                )
+                .await?;
+        }
         Ok(())
     }
 
-    async fn recv_from_runners(&mut self) -> Result<OutboundFromRunnerMsg> {
+    /// Receives the next message from any runner, along with the language of the runner, which
+    /// is needed to find out which runner crashed if receiving fails.
+    async fn recv_from_runners(&mut self) -> (Language, Result<OutboundFromRunnerMsg>) {
         tokio::select! {
             res = self.py.recv(), if self.py.spawned() => {
-                res
+                (Language::Python, res)
             }
             res = self.js.recv(), if self.js.spawned() => {
-                res
+                (Language::JavaScript, res)
             }
             res = self.rs.recv(), if self.rs.spawned() => {
-                res
+                (Language::Rust, res)
+            }
+            res = self.wasm.recv(), if self.wasm.spawned() => {
+                (Language::Wasm, res)
+            }
+            res = self.ext.recv(), if self.ext.spawned() => {
+                (Language::External, res)
+            }
+            else => {
+                // No runner was spawned, so there won't ever be a message
+                futures::future::pending().await
             }
         }
     }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{simulation::task::Task, types::TaskId, Language};
+use crate::{proto::SimulationShortId, simulation::task::Task, types::TaskId, Language};
 
 pub enum CancelState {
     Active(Vec<Language>),
@@ -15,6 +15,7 @@ impl Default for CancelState {
 
 #[derive(derive_new::new)]
 pub struct PendingWorkerTask {
+    pub sim_id: SimulationShortId,
     pub inner: Task,
     pub active_runner: Language,
     #[new(default)]
@@ -2,7 +2,7 @@ use std::fmt;
 
 use super::{NewSimulationRun, RunnerTaskMsg, StateInterimSync};
 use crate::{
-    datastore::table::sync::{ContextBatchSync, StateSync, WaitableStateSync},
+    datastore::table::sync::{ContextBatchSync, GlobalsSync, StateSync, WaitableStateSync},
     proto::SimulationShortId,
     types::TaskId,
 };
@@ -14,6 +14,7 @@ pub enum InboundToRunnerMsgPayload {
     StateSnapshotSync(StateSync),
     ContextBatchSync(ContextBatchSync),
     StateInterimSync(StateInterimSync),
+    GlobalsSync(GlobalsSync),
     TerminateSimulationRun,
     TerminateRunner,
     NewSimulationRun(NewSimulationRun),
@@ -28,6 +29,7 @@ impl InboundToRunnerMsgPayload {
             InboundToRunnerMsgPayload::StateSnapshotSync(_) => "StateSnapshotSync",
             InboundToRunnerMsgPayload::ContextBatchSync(_) => "ContextBatchSync",
             InboundToRunnerMsgPayload::StateInterimSync(_) => "StateInterimSync",
+            InboundToRunnerMsgPayload::GlobalsSync(_) => "GlobalsSync",
             InboundToRunnerMsgPayload::TerminateSimulationRun => "TerminateSimulationRun",
             InboundToRunnerMsgPayload::TerminateRunner => "TerminateRunner",
             InboundToRunnerMsgPayload::NewSimulationRun(_) => "NewSimulationRun",
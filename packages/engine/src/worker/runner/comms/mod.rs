@@ -35,21 +35,52 @@ pub struct SentTask {
     pub task_wrapper: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageTarget {
     Rust,
     Python,
     JavaScript,
+    Wasm,
+    External,
     Dynamic,
     Main,
 }
 
+impl MessageTarget {
+    /// The language of the runner the target refers to, if it's a runner.
+    pub fn language(self) -> Option<Language> {
+        match self {
+            Self::Rust => Some(Language::Rust),
+            Self::Python => Some(Language::Python),
+            Self::JavaScript => Some(Language::JavaScript),
+            Self::Wasm => Some(Language::Wasm),
+            Self::External => Some(Language::External),
+            Self::Dynamic | Self::Main => None,
+        }
+    }
+
+    /// Combines the next targets of running a task on several groups. A group which continues the
+    /// task on another runner decides where the whole task continues, so groups continuing on
+    /// different runners are an error.
+    pub fn merge_group_target(self, group_target: Self) -> Result<Self> {
+        match (self, group_target) {
+            (Self::Main, target) | (target, Self::Main) => Ok(target),
+            (target, group_target) if target == group_target => Ok(target),
+            (target, group_target) => Err(Error::from(format!(
+                "Groups continued the task on different targets: {target:?} and {group_target:?}"
+            ))),
+        }
+    }
+}
+
 impl From<Language> for MessageTarget {
     fn from(l: Language) -> Self {
         match l {
             Language::Rust => Self::Rust,
             Language::Python => Self::Python,
             Language::JavaScript => Self::JavaScript,
+            Language::Wasm => Self::Wasm,
+            Language::External => Self::External,
         }
     }
 }
@@ -60,6 +91,8 @@ impl From<flatbuffers_gen::target_generated::Target> for MessageTarget {
             flatbuffers_gen::target_generated::Target::Rust => Self::Rust,
             flatbuffers_gen::target_generated::Target::Python => Self::Python,
             flatbuffers_gen::target_generated::Target::JavaScript => Self::JavaScript,
+            flatbuffers_gen::target_generated::Target::Wasm => Self::Wasm,
+            flatbuffers_gen::target_generated::Target::External => Self::External,
             flatbuffers_gen::target_generated::Target::Dynamic => Self::Dynamic,
             flatbuffers_gen::target_generated::Target::Main => Self::Main,
             _ => unreachable!(),
@@ -90,7 +123,8 @@ impl TargetedRunnerTaskMsg {
         let task_id = task_msg.task_id().ok_or_else(|| {
             Error::from("The TaskMessage from the runner didn't have a required task_id field")
         })?;
-        let task_id = uuid::Uuid::from_slice(&task_id.0)?.as_u128();
+        // Task ids are sent to runners as little-endian bytes, see `inbound_to_nng`.
+        let task_id = TaskId::from_le_bytes(task_id.0);
 
         let sent = sent_tasks.remove(&task_id).ok_or_else(|| {
             Error::from(format!("Outbound message w/o sent task id {:?}", task_id))
@@ -145,6 +179,9 @@ pub struct NewSimulationRun {
     pub packages: PackageMsgs,
     pub datastore: DatastoreSimulationPayload,
     pub globals: Arc<Globals>,
+    /// Seed of the simulation run, which runners use to seed the random number generators
+    /// available to user code.
+    pub seed: u64,
 }
 
 #[derive(derive_new::new, Clone)]
@@ -194,3 +231,28 @@ impl ExperimentInitRunnerMsg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_continuing_on_a_runner_decides_the_target() -> Result<()> {
+        let target = MessageTarget::Main.merge_group_target(MessageTarget::Python)?;
+        assert_eq!(target, MessageTarget::Python);
+        let target = target.merge_group_target(MessageTarget::Main)?;
+        assert_eq!(target, MessageTarget::Python);
+        let target = target.merge_group_target(MessageTarget::Python)?;
+        assert_eq!(target, MessageTarget::Python);
+        Ok(())
+    }
+
+    #[test]
+    fn groups_continuing_on_different_runners_are_an_error() {
+        assert!(
+            MessageTarget::Python
+                .merge_group_target(MessageTarget::JavaScript)
+                .is_err()
+        );
+    }
+}
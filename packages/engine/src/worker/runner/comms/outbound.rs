@@ -57,6 +57,43 @@ impl From<flatbuffers_gen::runner_warning_generated::RunnerWarning<'_>> for Runn
     }
 }
 
+impl From<flatbuffers_gen::package_error_generated::PackageError<'_>> for RunnerError {
+    fn from(package_error: flatbuffers_gen::package_error_generated::PackageError<'_>) -> Self {
+        Self {
+            message: package_error.msg().map(|msg| msg.to_string()),
+            details: None,
+            file_name: None,
+            line_number: None,
+        }
+    }
+}
+
+impl From<flatbuffers_gen::user_error_generated::UserError<'_>> for RunnerError {
+    fn from(user_error: flatbuffers_gen::user_error_generated::UserError<'_>) -> Self {
+        Self {
+            message: user_error.msg().map(|msg| msg.to_string()),
+            details: user_error.details().map(|details| details.to_string()),
+            file_name: user_error
+                .file_name()
+                .map(|file_name| file_name.to_string()),
+            line_number: user_error.line_number(),
+        }
+    }
+}
+
+impl From<flatbuffers_gen::user_warning_generated::UserWarning<'_>> for RunnerError {
+    fn from(user_warning: flatbuffers_gen::user_warning_generated::UserWarning<'_>) -> Self {
+        Self {
+            message: Some(user_warning.msg().to_string()),
+            details: user_warning.details().map(|details| details.to_string()),
+            file_name: user_warning
+                .file_name()
+                .map(|file_name| file_name.to_string()),
+            line_number: user_warning.line_number(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum OutboundFromRunnerMsgPayload {
     TaskMsg(TargetedRunnerTaskMsg),
@@ -67,10 +104,10 @@ pub enum OutboundFromRunnerMsgPayload {
     RunnerWarnings(Vec<RunnerError>),
     RunnerLog(String),
     RunnerLogs(Vec<String>),
-    /* TODO: add
-     * PackageError
-     * UserErrors
-     * UserWarnings */
+    PackageError(RunnerError),
+    UserErrors(Vec<RunnerError>),
+    UserWarnings(Vec<RunnerError>),
+    StateSyncCompleted,
 }
 
 impl OutboundFromRunnerMsgPayload {
@@ -102,7 +139,7 @@ impl OutboundFromRunnerMsgPayload {
                     Error::from("Message from runner should have had a task_id but it was missing")
                 })?;
 
-                Self::TaskCancelled(uuid::Uuid::from_slice(&task_id.0)?.as_u128())
+                Self::TaskCancelled(TaskId::from_le_bytes(task_id.0))
             }
             flatbuffers_gen::runner_outbound_msg_generated::RunnerOutboundMsgPayload::RunnerError => {
                 let payload = parsed_msg.payload_as_runner_error().ok_or_else(|| {
@@ -154,34 +191,47 @@ impl OutboundFromRunnerMsgPayload {
                 Self::RunnerWarnings(runner_warnings)
             }
             flatbuffers_gen::runner_outbound_msg_generated::RunnerOutboundMsgPayload::PackageError => {
-                let _payload = parsed_msg.payload_as_package_error().ok_or_else(|| {
+                let payload = parsed_msg.payload_as_package_error().ok_or_else(|| {
                     Error::from(
                         "Message from runner should have had a PackageError payload but it was \
                          missing",
                     )
                 })?;
 
-                todo!() // TODO: there is no Self::PackageError
+                Self::PackageError(payload.into())
             }
             flatbuffers_gen::runner_outbound_msg_generated::RunnerOutboundMsgPayload::UserErrors => {
-                let _payload = parsed_msg.payload_as_user_errors().ok_or_else(|| {
+                let payload = parsed_msg.payload_as_user_errors().ok_or_else(|| {
                     Error::from(
                         "Message from runner should have had a UserErrors payload but it was \
                          missing",
                     )
                 })?;
 
-                todo!() // TODO: there is no Self::UserErrors
+                let user_errors = payload
+                    .inner()
+                    .iter()
+                    .map(|user_error| user_error.into())
+                    .collect();
+                Self::UserErrors(user_errors)
             }
             flatbuffers_gen::runner_outbound_msg_generated::RunnerOutboundMsgPayload::UserWarnings => {
-                let _payload = parsed_msg.payload_as_user_warnings().ok_or_else(|| {
+                let payload = parsed_msg.payload_as_user_warnings().ok_or_else(|| {
                     Error::from(
                         "Message from runner should have had a UserWarnings payload but it was \
                          missing",
                     )
                 })?;
 
-                todo!() // TODO: there is no Self::UserWarnings
+                let user_warnings = payload
+                    .inner()
+                    .iter()
+                    .map(|user_warning| user_warning.into())
+                    .collect();
+                Self::UserWarnings(user_warnings)
+            }
+            flatbuffers_gen::runner_outbound_msg_generated::RunnerOutboundMsgPayload::StateSyncCompleted => {
+                Self::StateSyncCompleted
             }
             _ => return Err(Error::from("Invalid outbound flatbuffers message payload")),
         })
@@ -217,3 +267,151 @@ impl OutboundFromRunnerMsg {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use flatbuffers::FlatBufferBuilder;
+    use flatbuffers_gen::{
+        runner_outbound_msg_generated::{
+            RunnerOutboundMsg, RunnerOutboundMsgArgs, RunnerOutboundMsgPayload, StateSyncCompleted,
+            StateSyncCompletedArgs, TaskCancelled, TaskCancelledArgs,
+        },
+        user_error_generated::{UserError, UserErrorArgs},
+        user_errors_generated::{UserErrors, UserErrorsArgs},
+        user_warning_generated::{UserWarning, UserWarningArgs},
+        user_warnings_generated::{UserWarnings, UserWarningsArgs},
+    };
+
+    use super::*;
+
+    fn parse(fbb: &FlatBufferBuilder<'_>) -> OutboundFromRunnerMsgPayload {
+        let msg = root_as_runner_outbound_msg(fbb.finished_data()).unwrap();
+        assert_eq!(msg.sim_sid(), 3);
+        OutboundFromRunnerMsgPayload::try_from_fbs(msg, &mut HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn parses_user_errors() {
+        let mut fbb = FlatBufferBuilder::new();
+        let msg = fbb.create_string("Error: counter broke");
+        let details = fbb.create_string("Error: counter broke\n    at <anonymous>:6:11");
+        let file_name = fbb.create_string("broken.js");
+        let with_location = UserError::create(&mut fbb, &UserErrorArgs {
+            msg: Some(msg),
+            details: Some(details),
+            file_name: Some(file_name),
+            line_number: Some(4),
+        });
+        let msg = fbb.create_string("Error: no location");
+        let without_location = UserError::create(&mut fbb, &UserErrorArgs {
+            msg: Some(msg),
+            ..Default::default()
+        });
+        let inner = fbb.create_vector(&[with_location, without_location]);
+        let user_errors = UserErrors::create(&mut fbb, &UserErrorsArgs { inner: Some(inner) });
+        let outbound = RunnerOutboundMsg::create(&mut fbb, &RunnerOutboundMsgArgs {
+            sim_sid: 3,
+            payload_type: RunnerOutboundMsgPayload::UserErrors,
+            payload: Some(user_errors.as_union_value()),
+        });
+        fbb.finish(outbound, None);
+
+        let errors = match parse(&fbb) {
+            OutboundFromRunnerMsgPayload::UserErrors(errors) => errors,
+            payload => panic!("Expected user errors, got {payload:?}"),
+        };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message.as_deref(), Some("Error: counter broke"));
+        assert_eq!(
+            errors[0].details.as_deref(),
+            Some("Error: counter broke\n    at <anonymous>:6:11")
+        );
+        assert_eq!(errors[0].file_name.as_deref(), Some("broken.js"));
+        assert_eq!(errors[0].line_number, Some(4));
+        assert_eq!(errors[1].message.as_deref(), Some("Error: no location"));
+        assert_eq!(errors[1].file_name, None);
+        assert_eq!(errors[1].line_number, None);
+
+        let sendable = errors[0].clone().into_sendable(false);
+        assert!(!sendable.is_warning);
+        assert!(!sendable.is_internal);
+    }
+
+    #[test]
+    fn parses_user_warnings() {
+        let mut fbb = FlatBufferBuilder::new();
+        let msg = fbb.create_string("Deprecated field");
+        let file_name = fbb.create_string("old.py");
+        let warning = UserWarning::create(&mut fbb, &UserWarningArgs {
+            msg: Some(msg),
+            details: None,
+            file_name: Some(file_name),
+            line_number: Some(12),
+        });
+        let inner = fbb.create_vector(&[warning]);
+        let user_warnings =
+            UserWarnings::create(&mut fbb, &UserWarningsArgs { inner: Some(inner) });
+        let outbound = RunnerOutboundMsg::create(&mut fbb, &RunnerOutboundMsgArgs {
+            sim_sid: 3,
+            payload_type: RunnerOutboundMsgPayload::UserWarnings,
+            payload: Some(user_warnings.as_union_value()),
+        });
+        fbb.finish(outbound, None);
+
+        let warnings = match parse(&fbb) {
+            OutboundFromRunnerMsgPayload::UserWarnings(warnings) => warnings,
+            payload => panic!("Expected user warnings, got {payload:?}"),
+        };
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message.as_deref(), Some("Deprecated field"));
+        assert_eq!(warnings[0].details, None);
+        assert_eq!(warnings[0].file_name.as_deref(), Some("old.py"));
+        assert_eq!(warnings[0].line_number, Some(12));
+        assert!(warnings[0].clone().into_sendable(true).is_warning);
+    }
+
+    #[test]
+    fn parses_state_sync_completed() {
+        let mut fbb = FlatBufferBuilder::new();
+        let completed = StateSyncCompleted::create(&mut fbb, &StateSyncCompletedArgs {});
+        let outbound = RunnerOutboundMsg::create(&mut fbb, &RunnerOutboundMsgArgs {
+            sim_sid: 3,
+            payload_type: RunnerOutboundMsgPayload::StateSyncCompleted,
+            payload: Some(completed.as_union_value()),
+        });
+        fbb.finish(outbound, None);
+
+        assert!(matches!(
+            parse(&fbb),
+            OutboundFromRunnerMsgPayload::StateSyncCompleted
+        ));
+    }
+
+    #[test]
+    fn parses_task_cancelled_with_a_little_endian_task_id() {
+        let task_id: TaskId = 0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10;
+        // Least significant byte first
+        let bytes = [
+            0x10, 0x0F, 0x0E, 0x0D, 0x0C, 0x0B, 0x0A, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03,
+            0x02, 0x01,
+        ];
+
+        let mut fbb = FlatBufferBuilder::new();
+        let cancelled = TaskCancelled::create(&mut fbb, &TaskCancelledArgs {
+            task_id: Some(&flatbuffers_gen::runner_outbound_msg_generated::TaskId(
+                bytes,
+            )),
+        });
+        let outbound = RunnerOutboundMsg::create(&mut fbb, &RunnerOutboundMsgArgs {
+            sim_sid: 3,
+            payload_type: RunnerOutboundMsgPayload::TaskCancelled,
+            payload: Some(cancelled.as_union_value()),
+        });
+        fbb.finish(outbound, None);
+
+        match parse(&fbb) {
+            OutboundFromRunnerMsgPayload::TaskCancelled(id) => assert_eq!(id, task_id),
+            payload => panic!("Expected a cancelled task, got {payload:?}"),
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use thiserror::Error as ThisError;
 use tokio::sync::mpsc::error::SendError;
 
-use crate::{proto::SimulationShortId, worker::runner::comms::inbound::InboundToRunnerMsgPayload};
+use crate::{
+    proto::SimulationShortId, worker::runner::comms::inbound::InboundToRunnerMsgPayload, Language,
+};
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -13,25 +15,25 @@ pub enum Error {
     #[error("Datastore: {0}")]
     Datastore(#[from] crate::datastore::Error),
 
-    #[error("Can't start Python runner again when it is already running")]
-    AlreadyRunning,
+    #[error("Can't start {0} runner again when it is already running")]
+    AlreadyRunning(Language),
 
-    #[error("Couldn't import package {0}: {1}")]
-    PackageImport(String, String), // First element is path/name.
+    #[error("Couldn't spawn {0} child process: {1:?}")]
+    Spawn(Language, std::io::Error),
 
-    #[error("Missing simulation run with id {0}")]
-    MissingSimRun(SimulationShortId),
+    #[error("{0} process exited unexpectedly with {1}")]
+    ProcessExit(Language, std::process::ExitStatus),
 
-    #[error("Couldn't spawn Python child process: {0:?}")]
-    Spawn(std::io::Error),
-
-    #[error("Couldn't send terminate message to Python: {0}")]
-    TerminateSend(SendError<()>),
+    #[error(
+        "{0} runner speaks version {1:?} of the runner protocol, but the engine expects version \
+         {2}"
+    )]
+    ProtocolVersion(Language, Vec<u8>, u8),
 
     #[error("Couldn't send inbound message to runner: {0}")]
     InboundSend(#[from] SendError<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>),
 
-    #[error("Couldn't send message {0:?} to Python process: {1:?}")]
+    #[error("Couldn't send message {0:?} to runner process: {1:?}")]
     NngSend(nng::Message, nng::Error),
 
     #[error("nng: {0:?}")]
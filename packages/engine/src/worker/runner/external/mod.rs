@@ -0,0 +1,367 @@
+//! Runners which execute behaviors in a separate process.
+//!
+//! The worker spawns the process and talks to it with flatbuffers messages over a pair of nng
+//! sockets, the *runner protocol*, which is documented in `format/README.md`. The Python runner is
+//! one of them, and projects can add runners for behaviors in other languages (see
+//! [`ExternalRunnerConfig`]).
+
+mod error;
+mod fbs;
+mod receiver;
+mod sender;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    result::Result as StdResult,
+    sync::Arc,
+    time::Duration,
+};
+
+pub use error::{Error, Result};
+use futures::FutureExt;
+use receiver::NngReceiver;
+use sender::NngSender;
+use tokio::{
+    process::Command,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinError,
+    time::{sleep_until, Instant},
+};
+
+use super::{
+    comms::{
+        inbound::InboundToRunnerMsgPayload,
+        outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload},
+        ExperimentInitRunnerMsg, RunnerTaskMsg, SentTask,
+    },
+    LanguageWorker,
+};
+use crate::{
+    proto::{ExternalRunnerConfig, SimulationShortId},
+    simulation::comms::message::SyncCompletionSender,
+    types::TaskId,
+    worker::{Error as WorkerError, Result as WorkerResult},
+    Language,
+};
+
+/// Version of the runner protocol. It's sent by runner processes in their init request and has to
+/// be increased on every incompatible change to the messages.
+pub const PROTOCOL_VERSION: u8 = 3;
+
+/// Command to start the process of a runner.
+///
+/// Apart from `args`, the process gets everything it needs to connect to the worker in environment
+/// variables (see `format/README.md`).
+#[derive(Debug, Clone)]
+pub struct RunnerProcess {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Working directory of the process, the one of the engine if `None`.
+    pub current_dir: Option<PathBuf>,
+}
+
+impl From<&ExternalRunnerConfig> for RunnerProcess {
+    fn from(config: &ExternalRunnerConfig) -> Self {
+        Self {
+            program: config.command.clone(),
+            args: config.args.clone(),
+            current_dir: config.working_dir.clone(),
+        }
+    }
+}
+
+pub struct ExternalRunner {
+    language: Language,
+    process: Option<Arc<RunnerProcess>>,
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    inbound_sender: UnboundedSender<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    inbound_receiver:
+        Option<UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>>,
+    outbound_sender: Option<UnboundedSender<OutboundFromRunnerMsg>>,
+    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
+}
+
+impl ExternalRunner {
+    /// Creates a runner for `language`, which is only spawned if there's a `process` to run.
+    pub fn new(
+        language: Language,
+        process: Option<RunnerProcess>,
+        init_msg: ExperimentInitRunnerMsg,
+        task_timeout: Option<Duration>,
+    ) -> WorkerResult<Self> {
+        let (inbound_sender, inbound_receiver) = unbounded_channel();
+        let (outbound_sender, outbound_receiver) = unbounded_channel();
+        Ok(Self {
+            language,
+            process: process.map(Arc::new),
+            init_msg: Arc::new(init_msg),
+            task_timeout,
+            inbound_sender,
+            inbound_receiver: Some(inbound_receiver),
+            outbound_sender: Some(outbound_sender),
+            outbound_receiver,
+        })
+    }
+
+    pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
+        self.outbound_receiver
+            .recv()
+            .await
+            .ok_or(WorkerError::External(Error::OutboundReceive))
+    }
+
+    // TODO: Duplication with other runners (move into worker?)
+    pub async fn recv_now(&mut self) -> WorkerResult<Option<OutboundFromRunnerMsg>> {
+        // TODO: `now_or_never` on a receiver can very rarely drop messages (known
+        //       issue with tokio). Replace with better solution once tokio has one.
+        self.recv().now_or_never().transpose()
+    }
+
+    pub async fn run(
+        &mut self,
+    ) -> WorkerResult<Pin<Box<dyn Future<Output = StdResult<WorkerResult<()>, JoinError>> + Send>>>
+    {
+        // TODO: Duplication with other runners (move into worker?)
+        log::debug!("Running {} runner", self.language);
+        let process = match &self.process {
+            Some(process) => Arc::clone(process),
+            None => return Ok(Box::pin(async move { Ok(Ok(())) })),
+        };
+
+        let language = self.language;
+        let init_msg = Arc::clone(&self.init_msg);
+        let task_timeout = self.task_timeout;
+        let inbound_receiver = self
+            .inbound_receiver
+            .take()
+            .ok_or(Error::AlreadyRunning(language))?;
+        let outbound_sender = self
+            .outbound_sender
+            .take()
+            .ok_or(Error::AlreadyRunning(language))?;
+
+        let f = async move {
+            _run(
+                language,
+                process,
+                init_msg,
+                task_timeout,
+                inbound_receiver,
+                outbound_sender,
+            )
+            .await
+        };
+        Ok(Box::pin(tokio::task::spawn(f)))
+    }
+}
+
+#[async_trait::async_trait]
+impl LanguageWorker for ExternalRunner {
+    async fn send(
+        &self,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> WorkerResult<()> {
+        log::trace!("Sending message to {}: {:?}", self.language, &msg);
+        self.inbound_sender
+            .send((sim_id, msg))
+            .map_err(|e| WorkerError::External(Error::InboundSend(e)))
+    }
+
+    fn spawned(&self) -> bool {
+        self.process.is_some()
+    }
+}
+
+async fn _run(
+    language: Language,
+    process: Arc<RunnerProcess>,
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    mut inbound_receiver: UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
+) -> WorkerResult<()> {
+    // Open sockets for the runner process to connect to (i.e. start listening).
+    let recv_url = format!(
+        "ipc://{}-to{}{}",
+        init_msg.experiment_id, language, init_msg.worker_index
+    );
+    let send_url = format!(
+        "ipc://{}-from{}{}",
+        init_msg.experiment_id, language, init_msg.worker_index
+    );
+    let mut nng_sender = NngSender::new(recv_url.clone())?;
+    let mut nng_receiver = NngReceiver::new(&send_url)?;
+
+    // Spawn runner process.
+    let mut cmd = Command::new(&process.program);
+    cmd.args(&process.args)
+        .env("HASH_RUNNER_PROTOCOL_VERSION", PROTOCOL_VERSION.to_string())
+        .env("HASH_EXPERIMENT_ID", &init_msg.experiment_id)
+        .env("HASH_WORKER_INDEX", init_msg.worker_index.to_string())
+        .env("HASH_RUNNER_RECV_URL", &recv_url)
+        .env("HASH_RUNNER_SEND_URL", &send_url);
+    if let Some(current_dir) = &process.current_dir {
+        cmd.current_dir(current_dir);
+    }
+    if let Some(task_timeout) = task_timeout {
+        // The runner process stops tasks running over on its own
+        cmd.env("HASH_TASK_TIMEOUT", task_timeout.as_secs_f64().to_string());
+    }
+    let mut child = cmd.spawn().map_err(|err| Error::Spawn(language, err))?;
+    log::debug!("Started {language} process {}", init_msg.worker_index);
+
+    // Send init message to runner process.
+    if let Err(err) = nng_receiver.init(language, &init_msg) {
+        // A runner speaking another protocol version doesn't know what to do with the init
+        // message, so it doesn't exit on its own.
+        if let Err(kill_err) = child.kill().await {
+            log::warn!("Couldn't kill {language} process: {kill_err}");
+        }
+        return Err(err.into());
+    }
+    // We waited for the runner's init message handling to finish,
+    // so we know that sender init can be done now.
+    nng_sender.init()?;
+
+    log::debug!("Waiting for messages to {language} runner");
+    let mut sent_tasks: HashMap<TaskId, SentTask> = HashMap::new();
+    // The runner process is killed once a task ran for twice the task timeout. The Python runner
+    // relies on this to stop tasks running over, as it can't interrupt native code.
+    let mut task_deadlines: HashMap<TaskId, Instant> = HashMap::new();
+    // State syncs the runner didn't acknowledge yet, in the order they were sent
+    let mut pending_syncs: VecDeque<SyncCompletionSender> = VecDeque::new();
+    'select_loop: loop {
+        let next_deadline = task_deadlines.values().min().copied();
+        // TODO: Send errors instead of immediately stopping?
+        tokio::select! {
+            _ = sleep_until(next_deadline.unwrap_or_else(Instant::now)),
+                if next_deadline.is_some() =>
+            {
+                log::error!(
+                    "{language} task didn't stop after exceeding the task timeout, killing the \
+                     {language} process"
+                );
+                child.kill().await.map_err(|err| {
+                    Error::from(format!("Couldn't kill {language} process: {err}"))
+                })?;
+                return Err(Error::from(format!(
+                    "{language} task exceeded the task timeout and the {language} process had to \
+                     be killed"
+                )).into());
+            }
+            exit_status = child.wait() => {
+                let exit_status = exit_status.map_err(|err| {
+                    Error::from(format!("Couldn't wait for {language} process: {err}"))
+                })?;
+                return Err(Error::ProcessExit(language, exit_status).into());
+            }
+            Some(nng_send_result) = nng_sender.get_send_result() => {
+                nng_send_result?;
+            }
+            Some((sim_id, inbound)) = inbound_receiver.recv() => {
+                let (task_payload_json, task_wrapper) = match &inbound {
+                    InboundToRunnerMsgPayload::TaskMsg(msg) => {
+                        // TODO: Error message duplication with JS runner
+                        let (payload, wrapper) = msg.payload
+                            .clone()
+                            .extract_inner_msg_with_wrapper()
+                            .map_err(|err| {
+                                Error::from(format!(
+                                    "Failed to extract the inner task message: {err}"
+                                ))
+                            })?;
+                        (Some(payload), Some(wrapper))
+                    }
+                    _ => (None, None)
+                };
+
+                // Send nng first, because need inbound by reference for nng,
+                // but by value for saving sent task.
+                nng_sender.send(sim_id, &inbound, &task_payload_json)?;
+
+                match inbound {
+                    InboundToRunnerMsgPayload::TerminateRunner => break 'select_loop,
+                    InboundToRunnerMsgPayload::TaskMsg(RunnerTaskMsg {
+                        task_id,
+                        shared_store,
+                        ..
+                    }) => {
+                        // unwrap: TaskMsg variant, so must have serialized payload earlier.
+                        let sent = SentTask {
+                            task_wrapper: task_wrapper.unwrap(),
+                            shared_store
+                        };
+                        if let Some(task_timeout) = task_timeout {
+                            task_deadlines.insert(task_id, Instant::now() + task_timeout * 2);
+                        }
+                        sent_tasks
+                            .try_insert(task_id, sent)
+                            .map_err(|_| Error::from(format!(
+                                "Inbound message w/o sent task id {:?}", task_id
+                            )))?;
+                    }
+                    InboundToRunnerMsgPayload::StateSync(sync) => {
+                        // Completed once the runner acknowledges the sync
+                        pending_syncs.push_back(sync.completion_sender);
+                    }
+                    _ => {}
+                }
+            }
+            outbound = nng_receiver.get_recv_result() => {
+                let outbound = outbound.map_err(WorkerError::from)?;
+                let outbound = OutboundFromRunnerMsg::try_from_nng(
+                    outbound,
+                    language,
+                    &mut sent_tasks,
+                );
+                let outbound = outbound.map_err(|err| {
+                    Error::from(format!(
+                        "Failed to convert nng message to OutboundFromRunnerMsg: {err}"
+                    ))
+                })?;
+                match &outbound.payload {
+                    OutboundFromRunnerMsgPayload::StateSyncCompleted => {
+                        // The runner acknowledges syncs in the order they were sent.
+                        let completion_sender = pending_syncs.pop_front().ok_or_else(|| {
+                            Error::from(format!(
+                                "{language} runner acknowledged a state sync that wasn't sent"
+                            ))
+                        })?;
+                        if completion_sender.send(Ok(())).is_err() {
+                            // The simulation run stopped waiting for the sync, e.g. because it
+                            // failed.
+                            log::warn!("Couldn't send state sync completion to worker");
+                        }
+                        continue 'select_loop;
+                    }
+                    OutboundFromRunnerMsgPayload::TaskCancelled(task_id) => {
+                        sent_tasks.remove(task_id);
+                    }
+                    _ => {}
+                }
+                task_deadlines.retain(|task_id, _| sent_tasks.contains_key(task_id));
+                outbound_sender.send(outbound)?;
+            }
+        }
+    }
+
+    // // TODO: Drop nng_sender/nng_receiver before killing process?
+    // match tokio::time::timeout(std::time::Duration::from_secs(10), child.wait()).await? {
+    //     None => {
+    //         log::info!("Runner process has failed to exit; killing.");
+    //         child.kill().await?;
+    //     }
+    //     Some(status) => {
+    //         log::info!(
+    //             "Runner process has successfully exited with status: {:?}.",
+    //             status.code().unwrap_or(-1)
+    //         );
+    //     }
+    // }
+    Ok(())
+}
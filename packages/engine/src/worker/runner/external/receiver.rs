@@ -7,10 +7,9 @@ use uuid::Uuid;
 use super::{
     error::{Error, Result},
     fbs::{pkgs_to_fbs, shared_ctx_to_fbs},
+    PROTOCOL_VERSION,
 };
-use crate::{
-    proto::ExperimentId, types::WorkerIndex, worker::runner::comms::ExperimentInitRunnerMsg,
-};
+use crate::{worker::runner::comms::ExperimentInitRunnerMsg, Language};
 
 fn experiment_init_to_nng(init: &ExperimentInitRunnerMsg) -> Result<nng::Message> {
     // TODO: initial buffer size
@@ -43,22 +42,23 @@ fn experiment_init_to_nng(init: &ExperimentInitRunnerMsg) -> Result<nng::Message
     Ok(nanomsg)
 }
 
-/// Only used for receiving messages from the Python process,
+/// Only used for receiving messages from the runner process,
 /// except for the init message, which is sent once in response
 /// to an init message request
 pub struct NngReceiver {
-    // Used in the aio to receive nng messages from the Python process.
-    from_py: Socket,
+    // Used in the aio to receive nng messages from the runner process.
+    from_runner: Socket,
     aio: Aio,
     // Receives the results of operations from the aio.
     aio_result_receiver: UnboundedReceiver<nng::Message>,
 }
 
 impl NngReceiver {
-    pub fn new(experiment_id: ExperimentId, worker_index: WorkerIndex) -> Result<Self> {
-        let route = format!("ipc://{}-frompy{}", experiment_id, worker_index);
-        let from_py = Socket::new(nng::Protocol::Pair0)?;
-        from_py.listen(&route)?;
+    /// Creates the socket for receiving messages from the runner process and starts listening on
+    /// `route`.
+    pub fn new(route: &str) -> Result<Self> {
+        let from_runner = Socket::new(nng::Protocol::Pair0)?;
+        from_runner.listen(route)?;
 
         // `aio_result_sender` sends the results of operations (i.e. results of trying to
         // receive nng messages) in the aio.
@@ -77,19 +77,29 @@ impl NngReceiver {
         })?;
 
         Ok(Self {
-            from_py,
+            from_runner,
             aio,
             aio_result_receiver,
         })
     }
 
-    pub fn init(&self, init_msg: &ExperimentInitRunnerMsg) -> Result<()> {
-        let _init_request = self.from_py.recv()?;
-        self.from_py // Only case where `from_py` is used for sending
+    /// Sends the init message to the runner process once it requested it.
+    ///
+    /// The init request holds the version of the runner protocol the runner process speaks.
+    pub fn init(&self, language: Language, init_msg: &ExperimentInitRunnerMsg) -> Result<()> {
+        let init_request = self.from_runner.recv()?;
+        if init_request.as_slice() != [PROTOCOL_VERSION] {
+            return Err(Error::ProtocolVersion(
+                language,
+                init_request.as_slice().to_vec(),
+                PROTOCOL_VERSION,
+            ));
+        }
+        self.from_runner // Only case where `from_runner` is used for sending
             .send(experiment_init_to_nng(init_msg)?)
             .map_err(|(msg, err)| Error::NngSend(msg, err))?;
 
-        let _init_ack = self.from_py.recv()?;
+        let _init_ack = self.from_runner.recv()?;
         Ok(())
     }
 
@@ -101,7 +111,7 @@ impl NngReceiver {
             .await
             .ok_or(Error::OutboundReceive)?;
 
-        self.from_py.recv_async(&self.aio)?;
+        self.from_runner.recv_async(&self.aio)?;
         Ok(nng_msg)
     }
 }
@@ -109,6 +119,6 @@ impl NngReceiver {
 impl Drop for NngReceiver {
     fn drop(&mut self) {
         // TODO: Check whether nng already does this when a socket is dropped
-        self.from_py.close();
+        self.from_runner.close();
     }
 }
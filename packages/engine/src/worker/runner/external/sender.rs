@@ -16,27 +16,26 @@ use crate::{
             task_shared_store::{PartialSharedState, SharedState},
         },
     },
-    proto::{ExperimentId, SimulationShortId},
+    proto::SimulationShortId,
     simulation::enum_dispatch::TaskSharedStore,
-    types::WorkerIndex,
     worker::runner::comms::inbound::InboundToRunnerMsgPayload,
 };
 
-/// Only used for sending messages to the Python process
+/// Only used for sending messages to the runner process
 pub struct NngSender {
     route: String,
 
-    // Used in the aio to send nng messages to the Python process.
-    to_py: Socket,
+    // Used in the aio to send nng messages to the runner process.
+    to_runner: Socket,
     aio: Aio,
     aio_result_receiver: UnboundedReceiver<Result<()>>,
 }
 
 impl NngSender {
-    pub fn new(experiment_id: ExperimentId, worker_index: WorkerIndex) -> Result<Self> {
-        let route = format!("ipc://{}-topy{}", experiment_id, worker_index);
-        let to_py = Socket::new(nng::Protocol::Pair0)?;
-        to_py.set_opt::<nng::options::SendBufferSize>(30)?;
+    /// Creates the socket for sending messages to the runner process, which listens on `route`.
+    pub fn new(route: String) -> Result<Self> {
+        let to_runner = Socket::new(nng::Protocol::Pair0)?;
+        to_runner.set_opt::<nng::options::SendBufferSize>(30)?;
         // TODO: Stress test to determine whether send buffer size is sufficiently large
 
         // `aio_result_sender` sends the results of operations (i.e. results of trying to
@@ -79,14 +78,14 @@ impl NngSender {
 
         Ok(Self {
             route,
-            to_py,
+            to_runner,
             aio,
             aio_result_receiver,
         })
     }
 
     pub fn init(&self) -> Result<()> {
-        self.to_py.dial(&self.route)?;
+        self.to_runner.dial(&self.route)?;
         Ok(())
     }
 
@@ -99,7 +98,7 @@ impl NngSender {
         // TODO: (option<SimId>, inbound payload) --> flatbuffers --> nng
         let msg = inbound_to_nng(sim_id, msg, task_payload_json)?;
         self.aio.wait();
-        self.to_py
+        self.to_runner
             .send_async(&self.aio, msg)
             .map_err(|(msg, err)| {
                 log::warn!("Send failed: {:?}", (&msg, &err));
@@ -116,7 +115,7 @@ impl NngSender {
 impl Drop for NngSender {
     fn drop(&mut self) {
         // TODO: Check whether nng already does this when a socket is dropped
-        self.to_py.close();
+        self.to_runner.close();
     }
 }
 
@@ -156,7 +155,20 @@ fn inbound_to_nng(
                 flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::TaskMsg,
             )
         }
-        InboundToRunnerMsgPayload::CancelTask(_) => todo!(), // Unused for now
+        InboundToRunnerMsgPayload::CancelTask(task_id) => {
+            let task_id =
+                flatbuffers_gen::runner_inbound_msg_generated::TaskId(task_id.to_le_bytes());
+            let msg = flatbuffers_gen::runner_inbound_msg_generated::CancelTask::create(
+                fbb,
+                &flatbuffers_gen::runner_inbound_msg_generated::CancelTaskArgs {
+                    task_id: Some(&task_id),
+                },
+            );
+            (
+                msg.as_union_value(),
+                flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::CancelTask,
+            )
+        }
         InboundToRunnerMsgPayload::StateSync(msg) => {
             let (agent_pool, message_pool) =
                 state_sync_to_fbs(fbb, &msg.agent_pool, &msg.message_pool)?;
@@ -211,6 +223,21 @@ fn inbound_to_nng(
                 flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::StateInterimSync,
             )
         }
+        InboundToRunnerMsgPayload::GlobalsSync(msg) => {
+            let globals =
+                serde_json::to_string(&msg.globals.0).expect("Can serialize serde_json::Value");
+            let globals = fbb.create_string(&globals);
+            let msg = flatbuffers_gen::runner_inbound_msg_generated::GlobalsSync::create(
+                fbb,
+                &flatbuffers_gen::runner_inbound_msg_generated::GlobalsSyncArgs {
+                    globals: Some(globals),
+                },
+            );
+            (
+                msg.as_union_value(),
+                flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::GlobalsSync,
+            )
+        }
         InboundToRunnerMsgPayload::TerminateSimulationRun => {
             let msg = flatbuffers_gen::runner_inbound_msg_generated::TerminateSimulationRun::create(
                 fbb,
@@ -267,6 +294,7 @@ fn inbound_to_nng(
                     properties: Some(globals),
                     package_config: Some(package_config),
                     datastore_init: Some(datastore_init),
+                    seed: msg.seed,
                 },
             );
             (
@@ -418,3 +446,31 @@ fn schema_to_stream_bytes(schema: &Schema) -> Vec<u8> {
     stream_bytes.extend_from_slice(&content);
     stream_bytes
 }
+
+#[cfg(test)]
+mod tests {
+    use flatbuffers_gen::runner_inbound_msg_generated::{
+        root_as_runner_inbound_msg, RunnerInboundMsgPayload,
+    };
+
+    use super::*;
+
+    #[test]
+    fn cancel_task_is_sent_with_a_little_endian_task_id() {
+        let task_id = 0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10;
+        let msg = inbound_to_nng(
+            Some(3),
+            &InboundToRunnerMsgPayload::CancelTask(task_id),
+            &None,
+        )
+        .unwrap();
+
+        let msg = root_as_runner_inbound_msg(&msg).unwrap();
+        assert_eq!(msg.sim_sid(), 3);
+        assert_eq!(msg.payload_type(), RunnerInboundMsgPayload::CancelTask);
+        let bytes = msg.payload_as_cancel_task().unwrap().task_id().unwrap().0;
+        assert_eq!(bytes[0], 0x10);
+        assert_eq!(bytes[15], 0x01);
+        assert_eq!(u128::from_le_bytes(bytes), task_id);
+    }
+}
@@ -43,12 +43,26 @@ const parse_any_type_fields = (metadata) => {
     return any_type_fields
 }
 
+/// Returns the categories of categorical fields by field name.
+const parse_categorical_fields = (metadata) => {
+    const categorical_fields = metadata.get('categorical_fields');
+    return categorical_fields ? JSON.parse(categorical_fields) : {};
+}
+
 const load_vectors = (rb_bytes, schema) => {
     const reader = new arrow.MessageReader(rb_bytes);
     const msg = reader.readMessage();
     const header = msg.header();
     const body = reader.readMessageBody(msg.bodyLength);
+    // Categorical fields are dictionary-encoded, but batches only contain
+    // the indices, so their dictionaries are built from the schema metadata.
+    const categorical_fields = parse_categorical_fields(schema.metadata);
     const dicts = new Map();
+    for (var i_field = 0; i_field < schema.fields.length; ++i_field) {
+        const field = schema.fields[i_field];
+        const categories = categorical_fields[field.name];
+        if (categories) dicts.set(field.type.id, arrow.Utf8Vector.from(categories));
+    }
     const loader = new reader.VectorLoader(body, header.nodes, header.buffers, dicts);
     const vector_list = loader.visitMany(schema.fields);
     const any_type_fields = parse_any_type_fields(schema.metadata);
@@ -178,6 +192,58 @@ const builder_from_col = (col, field_type) => {
     return builder;
 }
 
+const _is_int64 = type => type.typeId === arrow.Type.Int && type.bitWidth === 64;
+
+const _has_int64 = type => {
+    if (_is_int64(type)) return true;
+    const children = type.children;
+    return !!children && children.some(child => _has_int64(child.type));
+}
+
+/// JS Arrow only writes the lower 32 bits of 64-bit integers which are
+/// given as numbers, so they have to be converted to `BigInt`s first.
+const _int64_to_bigint = (value, type) => {
+    if (value === null || value === undefined) return value;
+    if (_is_int64(type)) return BigInt(value);
+
+    const children = type.children;
+    if (!children) return value;
+    if (children.length === 1 && children[0].name === null) {
+        // Only lists have an unnamed child.
+        return value.map(elem => _int64_to_bigint(elem, children[0].type));
+    }
+    const obj = {};
+    for (var k in value) {
+        if (value.hasOwnProperty(k)) obj[k] = value[k];
+    }
+    for (var j = 0; j < children.length; ++j) {
+        const child = children[j];
+        obj[child.name] = _int64_to_bigint(value[child.name], child.type);
+    }
+    return obj;
+}
+
+/// Only the indices of categories are flushed, see `load_vectors`.
+const categories_to_codes = (col, categories, field_name) => {
+    const codes = new Array(col.length);
+    for (var i_agent = 0; i_agent < col.length; ++i_agent) {
+        const value = col[i_agent];
+        if (value === null || value === undefined) {
+            codes[i_agent] = value;
+            continue;
+        }
+        const code = categories.indexOf(value);
+        if (code === -1) {
+            throw new Error(
+                "Flushing error: " + JSON.stringify(value) +
+                " is not one of the categories of " + field_name
+            );
+        }
+        codes[i_agent] = code;
+    }
+    return codes;
+}
+
 // TODO: Can JS Arrow silently coerce some flushed values to different types if
 //       their type differs from what it's supposed to be according to the schema?
 const array_data_from_builder = builder => {
@@ -225,12 +291,20 @@ Batch.prototype.flush_changes = function(schema, skip) {
                             // might be missing from `cols`. (But columns that
                             // are in `cols` should always be in schema too.)
 
+        let flushed_col = col;
+        let flushed_type = field.type;
         if (this.vectors[field.name].type.is_any) {
             for (var i_agent = 0; i_agent < col.length; ++i_agent) {
                 col[i_agent] = JSON.stringify(col[i_agent]);
             }
+        } else if (field.type.typeId === arrow.Type.Dictionary) {
+            const categories = this.vectors[field.name].dictionary.toArray();
+            flushed_col = categories_to_codes(col, categories, field.name);
+            flushed_type = field.type.indices;
+        } else if (_has_int64(field.type)) {
+            flushed_col = col.map(value => _int64_to_bigint(value, field.type));
         }
-        const builder = builder_from_col(col, field.type);
+        const builder = builder_from_col(flushed_col, flushed_type);
         const data = array_data_from_builder(builder);
         changes.push({          // Some fields might be skipped, so a
             "i_field": i_field, // field's index in `changes` might not
@@ -34,6 +34,10 @@ const gen_agent_ctx = (ctx_schema, getters) => {
         return this.__experiment_ctx.data();
     }
 
+    AgentContext.prototype.step_data = function(name) {
+        return this.__experiment_ctx.step_data(name, this.__current_step);
+    }
+
     AgentContext.prototype.globals = function() {
         return this.__globals;
     }
@@ -68,6 +72,10 @@ const gen_group_ctx = (AgentContext) => {
         return this.__experiment_ctx.data();
     }
 
+    GroupContext.prototype.step_data = function(name) {
+        return this.__experiment_ctx.step_data(name, this.__current_step);
+    }
+
     GroupContext.prototype.globals = function() {
         return this.__globals;
     }
@@ -112,6 +120,10 @@ const gen_sim_ctx = (ctx_schema, getters) => {
         return this.__experiment_ctx.data();
     }
 
+    SimContext.prototype.step_data = function(name) {
+        return this.__experiment_ctx.step_data(name, this.__current_step);
+    }
+
     SimContext.prototype.globals = function() {
         return this.__globals;
     }
@@ -127,6 +139,11 @@ const gen_sim_ctx = (ctx_schema, getters) => {
         this.__current_step = current_step;
     }
 
+    /// Invalidates existing `GroupContext` and `AgentContext` objects.
+    SimContext.prototype.set_globals = function(globals) {
+        this.__globals = deepfreeze(globals);
+    }
+
     /// Invalidates existing `GroupContext` and `AgentContext` objects.
     SimContext.prototype.sync_snapshot = function(state_snapshot) {
         this.state_snapshot = state_snapshot;
@@ -162,14 +179,25 @@ const gen_sim_ctx = (ctx_schema, getters) => {
     return Object.seal(SimContext);
 }
 
-const ExperimentContext = function(datasets) {
+const ExperimentContext = function(datasets, step_datasets) {
     this.__datasets = deepfreeze(datasets);
+    // Rows of the step-indexed datasets by step
+    this.__step_datasets = deepfreeze(step_datasets);
 }
 
 ExperimentContext.prototype.data = function() {
     return this.__datasets;
 }
 
+/// Row of the step-indexed dataset `name` for `step`, or `undefined` if it has none.
+ExperimentContext.prototype.step_data = function(name, step) {
+    const dataset = this.__step_datasets[name];
+    if (dataset === undefined) {
+        throw new Error("Not a step-indexed dataset: " + name);
+    }
+    return dataset[step];
+}
+
 const SimInitContext = function(experiment_ctx, globals, agent_schema) {
     this.__experiment_ctx = experiment_ctx;
     this.__globals = deepfreeze(globals);
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use arrow::{datatypes::DataType, error::ArrowError};
 use thiserror::Error as ThisError;
 use tokio::sync::mpsc::error::SendError;
@@ -6,10 +8,7 @@ use super::mini_v8 as mv8;
 use crate::{
     proto::SimulationShortId,
     simulation::package::id::PackageId,
-    worker::runner::comms::{
-        inbound::InboundToRunnerMsgPayload,
-        outbound::{OutboundFromRunnerMsg, RunnerError},
-    },
+    worker::runner::comms::{inbound::InboundToRunnerMsgPayload, outbound::OutboundFromRunnerMsg},
 };
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -57,9 +56,6 @@ pub enum Error {
     #[error("Couldn't terminate missing simulation run with id {0}")]
     TerminateMissingSimulationRun(SimulationShortId),
 
-    #[error("User JavaScript errors: {0:?}")]
-    User(Vec<RunnerError>),
-
     #[error("Duplicate package (id, name): {0:?}, {1:?}")]
     DuplicatePackage(PackageId, String),
 
@@ -84,6 +80,9 @@ pub enum Error {
     #[error("Message type '{0}' must have a simulation run id")]
     SimulationIdRequired(&'static str),
 
+    #[error("Task was stopped after running for longer than {0:?}")]
+    TaskTimeout(Duration, Option<String>), // Second element is the name of the running behavior.
+
     #[error("serde: {0:?}")]
     Serde(#[from] serde_json::Error),
 }
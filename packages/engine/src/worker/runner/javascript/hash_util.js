@@ -54,6 +54,15 @@ const json_deepcopy = (x, _depth) => {
 const load_shallow = vector => {
     // `vector.toArray` returns array-like (in some cases? TODO), not actual array.
     const shallow = [];
+    const type = vector.type;
+    if (type && type.typeId === arrow.Type.Int && type.bitWidth === 64) {
+        // JS Arrow returns 64-bit integers as pairs of 32-bit integers.
+        for (var i = 0; i < vector.length; ++i) {
+            const value = vector.get(i);
+            shallow[i] = value === null ? null : Number(value);
+        }
+        return shallow;
+    }
     for (var i = 0; i < vector.length; ++i) {
         shallow[i] = vector.get(i);
     }
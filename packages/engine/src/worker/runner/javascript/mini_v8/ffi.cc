@@ -474,6 +474,11 @@ void mv8_interface_terminate_execution(const Interface* const interface) {
   interface->isolate->TerminateExecution();
 }
 
+extern "C"
+void mv8_interface_cancel_terminate_execution(const Interface* const interface) {
+  interface->isolate->CancelTerminateExecution();
+}
+
 /// Sets user data at the given slot on the interface's isolate.
 extern "C"
 void mv8_interface_set_data(
@@ -22,6 +22,7 @@ extern "C" {
         column_offset: i32,
     ) -> TryCatchDesc;
     pub(super) fn mv8_interface_terminate_execution(_: Interface);
+    pub(super) fn mv8_interface_cancel_terminate_execution(_: Interface);
     pub(super) fn mv8_interface_global(_: Interface) -> ValuePtr;
     pub(super) fn mv8_interface_set_data(_: Interface, slot: u32, data: *mut c_void);
     pub(super) fn mv8_interface_get_data(_: Interface, slot: u32) -> *mut c_void;
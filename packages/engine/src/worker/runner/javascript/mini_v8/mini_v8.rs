@@ -4,7 +4,10 @@ use std::{
     collections::BTreeMap,
     mem, ptr,
     string::String as StdString,
-    sync::{Arc, Condvar, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
     thread,
     time::Duration,
 };
@@ -32,6 +35,25 @@ pub struct DataFfi {
     pub null_bits_capacity: usize,
 }
 
+/// Terminates the JavaScript a [`MiniV8`] is executing from another thread.
+///
+/// The handle must not be used after the [`MiniV8`] it was created from was dropped.
+#[derive(Clone, Copy)]
+pub struct TerminationHandle(Interface);
+
+// V8 allows terminating the execution of an isolate from any thread.
+unsafe impl Sync for TerminationHandle {}
+
+impl TerminationHandle {
+    /// Terminates the current execution. If nothing is executing, the next execution is terminated
+    /// instead, unless [`MiniV8::cancel_termination`] is called before.
+    pub fn terminate(&self) {
+        unsafe {
+            mv8_interface_terminate_execution(self.0);
+        }
+    }
+}
+
 impl Default for MiniV8 {
     fn default() -> MiniV8 {
         ffi_init();
@@ -84,6 +106,45 @@ impl MiniV8 {
         }
     }
 
+    /// Returns a handle to terminate the execution of JavaScript from another thread.
+    pub fn termination_handle(&self) -> TerminationHandle {
+        TerminationHandle(self.interface)
+    }
+
+    /// Allows executing JavaScript again after it was terminated by a [`TerminationHandle`].
+    pub fn cancel_termination(&self) {
+        unsafe {
+            mv8_interface_cancel_terminate_execution(self.interface);
+        }
+    }
+
+    /// Runs `execute_fn`, terminating the JavaScript it executes once `timeout` has passed.
+    /// Returns the result of `execute_fn` and whether the execution was terminated.
+    pub fn execute_with_timeout<T>(
+        &self,
+        timeout: Duration,
+        execute_fn: impl FnOnce() -> T,
+    ) -> (T, bool) {
+        let interface = self.interface;
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timer_timed_out = timed_out.clone();
+        let result = execute_with_timeout(timeout, execute_fn, move || {
+            timer_timed_out.store(true, Ordering::SeqCst);
+            unsafe {
+                mv8_interface_terminate_execution(interface);
+            }
+        });
+        let timed_out = timed_out.load(Ordering::SeqCst);
+        if timed_out {
+            // The timer might have fired right after `execute_fn` returned, in which case the
+            // termination would hit the next execution instead.
+            unsafe {
+                mv8_interface_cancel_terminate_execution(self.interface);
+            }
+        }
+        (result, timed_out)
+    }
+
     fn eval_inner(&self, script: Script) -> Result<'_, Value<'_>> {
         let origin = script.origin.as_ref();
         desc_to_result(self, unsafe {
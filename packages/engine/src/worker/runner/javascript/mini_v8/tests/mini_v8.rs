@@ -45,6 +45,23 @@ fn eval_timeout() {
     assert!(a > 0.0);
 }
 
+#[test]
+fn terminate_from_another_thread() {
+    let mv8 = MiniV8::new();
+    let handle = mv8.termination_handle();
+    let terminator = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.terminate();
+    });
+    assert!(mv8.eval::<_, Value<'_>>("while (true) {}").is_err());
+    terminator.join().unwrap();
+
+    // Make sure we can still evaluate again:
+    mv8.cancel_termination();
+    let a: f64 = mv8.eval("1 + 1").unwrap();
+    assert_eq!(a, 2.0);
+}
+
 #[test]
 fn eval_wasm() {
     let mv8 = MiniV8::new();
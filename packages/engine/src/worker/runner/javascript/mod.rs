@@ -2,7 +2,13 @@ mod error;
 mod mini_v8;
 
 use std::{
-    collections::HashMap, fs, future::Future, pin::Pin, result::Result as StdResult, sync::Arc,
+    collections::{HashMap, HashSet},
+    fs,
+    future::Future,
+    pin::Pin,
+    result::Result as StdResult,
+    sync::Arc,
+    time::Duration,
 };
 
 use arrow::{
@@ -15,15 +21,20 @@ pub use error::{Error, Result};
 use futures::FutureExt;
 use mini_v8 as mv8;
 use mv8::MiniV8;
+use parking_lot::Mutex;
 use tokio::{
     sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     task::JoinError,
 };
 
-use super::comms::{
-    inbound::InboundToRunnerMsgPayload,
-    outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
-    ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg, TargetedRunnerTaskMsg,
+use super::{
+    comms::{
+        inbound::InboundToRunnerMsgPayload,
+        outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
+        ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg,
+        TargetedRunnerTaskMsg,
+    },
+    LanguageWorker,
 };
 use crate::{
     config::Globals,
@@ -35,7 +46,7 @@ use crate::{
         table::{
             pool::{agent::AgentPool, message::MessagePool, BatchPool},
             proxy::StateWriteProxy,
-            sync::{ContextBatchSync, StateSync, WaitableStateSync},
+            sync::{ContextBatchSync, GlobalsSync, StateSync, WaitableStateSync},
             task_shared_store::{PartialSharedState, SharedState},
         },
     },
@@ -44,6 +55,7 @@ use crate::{
         enum_dispatch::TaskSharedStore,
         package::{id::PackageId, PackageType},
     },
+    types::TaskId,
     worker::{Error as WorkerError, Result as WorkerResult, TaskMessage},
     Language,
 };
@@ -139,6 +151,7 @@ struct Embedded<'m> {
     state_sync: mv8::Function<'m>,
     state_interim_sync: mv8::Function<'m>,
     state_snapshot_sync: mv8::Function<'m>,
+    globals_sync: mv8::Function<'m>,
 }
 
 fn read_file(path: &str) -> Result<String> {
@@ -212,6 +225,7 @@ impl<'m> Embedded<'m> {
             &sim_init_ctx_prototype,
             &gen_ctx,
             &gen_state,
+            &hash_stdlib,
         ])?;
         let fns = fns.as_array().ok_or_else(|| {
             Error::FileImport(
@@ -229,6 +243,7 @@ impl<'m> Embedded<'m> {
             state_sync: fns.get(4)?,
             state_interim_sync: fns.get(5)?,
             state_snapshot_sync: fns.get(6)?,
+            globals_sync: fns.get(7)?,
         })
     }
 }
@@ -241,10 +256,85 @@ struct SimState {
     msg_pool: MessagePool,
 }
 
+#[derive(Default)]
+struct InterruptState {
+    /// Terminates the execution of the runner's isolate, while the isolate is alive.
+    handle: Option<mv8::TerminationHandle>,
+    running: Option<TaskId>,
+    /// Whether the running task was terminated.
+    terminated: bool,
+    /// Tasks which were cancelled before they were run.
+    cancelled: HashSet<TaskId>,
+}
+
+/// Lets [`JavaScriptRunner`] stop the task the runner is executing, as the runner only gets to the
+/// cancel message after the task finished.
+#[derive(Default)]
+struct TaskInterrupts(Mutex<InterruptState>);
+
+impl TaskInterrupts {
+    /// Terminates the task if the runner is executing it, otherwise the runner skips it if it
+    /// arrives later.
+    fn cancel(&self, task_id: TaskId) {
+        let mut state = self.0.lock();
+        if state.running == Some(task_id) {
+            if let Some(handle) = &state.handle {
+                handle.terminate();
+                state.terminated = true;
+            }
+        } else {
+            state.cancelled.insert(task_id);
+        }
+    }
+
+    /// Marks `task_id` as running. Returns `false` if it was cancelled before, so it mustn't run.
+    fn start(&self, task_id: TaskId) -> bool {
+        let mut state = self.0.lock();
+        if state.cancelled.remove(&task_id) {
+            return false;
+        }
+        state.running = Some(task_id);
+        true
+    }
+
+    /// Marks the running task as finished. Returns whether it was terminated by [`Self::cancel`].
+    fn finish(&self, mv8: &MiniV8) -> bool {
+        let mut state = self.0.lock();
+        state.running = None;
+        let terminated = std::mem::take(&mut state.terminated);
+        if terminated {
+            // The termination might have happened after the task returned, in which case it would
+            // hit the next execution instead.
+            mv8.cancel_termination();
+        }
+        terminated
+    }
+
+    /// Forgets the cancellation of `task_id` once the runner handled the cancel message.
+    fn forget(&self, task_id: TaskId) {
+        self.0.lock().cancelled.remove(&task_id);
+    }
+
+    fn set_handle(&self, handle: Option<mv8::TerminationHandle>) {
+        self.0.lock().handle = handle;
+    }
+}
+
+/// Invalidates the termination handle of the isolate before the isolate is dropped.
+struct TerminationHandleGuard<'a>(&'a TaskInterrupts);
+
+impl Drop for TerminationHandleGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set_handle(None);
+    }
+}
+
 struct RunnerImpl<'m> {
     embedded: Embedded<'m>,
     this: mv8::Value<'m>,
     sims_state: HashMap<SimulationShortId, SimState>,
+    task_timeout: Option<Duration>,
+    interrupts: Arc<TaskInterrupts>,
 }
 
 // we pass in _mv8 for the return values lifetime
@@ -374,21 +464,42 @@ fn schema_to_stream_bytes(schema: &Schema) -> Vec<u8> {
     stream_bytes
 }
 
+/// Converts an error (or warning) returned from JavaScript into a [`RunnerError`].
+///
+/// Objects can have the fields `msg`, `details`, `file_name` and `line_number`; any other value is
+/// used as the message.
+fn value_to_error(value: mv8::Value<'_>) -> RunnerError {
+    match value {
+        mv8::Value::Object(object) => {
+            let line_number = match object.get("line_number") {
+                Ok(mv8::Value::Number(line_number)) => Some(line_number as i32),
+                _ => None,
+            };
+            RunnerError {
+                message: object.get("msg").ok().flatten(),
+                details: object.get("details").ok().flatten(),
+                file_name: object.get("file_name").ok().flatten(),
+                line_number,
+            }
+        }
+        mv8::Value::String(message) => RunnerError {
+            message: Some(message.to_string()),
+            ..RunnerError::default()
+        },
+        value => RunnerError {
+            message: Some(format!("{:?}", value)),
+            ..RunnerError::default()
+        },
+    }
+}
+
 fn array_to_errors(array: mv8::Value<'_>) -> Vec<RunnerError> {
-    // TODO: Extract optional line numbers
     let fallback = format!("Unparsed: {:?}", array);
 
     if let mv8::Value::Array(array) = array {
         let errors = array
             .elements()
-            .map(|e: mv8::Result<'_, mv8::Value<'_>>| {
-                e.map(|e| RunnerError {
-                    message: Some(format!("{:?}", e)),
-                    details: None,
-                    line_number: None,
-                    file_name: None,
-                })
-            })
+            .map(|e: mv8::Result<'_, mv8::Value<'_>>| e.map(value_to_error))
             .collect();
 
         if let Ok(errors) = errors {
@@ -403,15 +514,6 @@ fn array_to_errors(array: mv8::Value<'_>) -> Vec<RunnerError> {
 }
 
 fn get_js_error(_mv8: &MiniV8, r: &mv8::Object<'_>) -> Option<Error> {
-    if let Ok(errors) = r.get("user_errors") {
-        if !matches!(errors, mv8::Value::Undefined) && !matches!(errors, mv8::Value::Null) {
-            let errors = array_to_errors(errors);
-            if !errors.is_empty() {
-                return Some(Error::User(errors));
-            }
-        }
-    }
-
     if let Ok(mv8::Value::String(e)) = r.get("pkg_error") {
         // TODO: Don't silently ignore non-string, non-null-or-undefined errors
         //       (try to convert error value to JSON string and return as error?).
@@ -426,12 +528,13 @@ fn get_js_error(_mv8: &MiniV8, r: &mv8::Object<'_>) -> Option<Error> {
     None
 }
 
-fn get_user_warnings(_mv8: &MiniV8, r: &mv8::Object<'_>) -> Option<Vec<RunnerError>> {
-    if let Ok(warnings) = r.get::<&str, mv8::Value<'_>>("user_warnings") {
-        if !(warnings.is_undefined() || warnings.is_null()) {
-            let warnings = array_to_errors(warnings);
-            if !warnings.is_empty() {
-                return Some(warnings);
+/// Returns the user errors or warnings in the `field` of the `run_task` result, if there are any.
+fn get_user_errors(_mv8: &MiniV8, r: &mv8::Object<'_>, field: &str) -> Option<Vec<RunnerError>> {
+    if let Ok(errors) = r.get::<&str, mv8::Value<'_>>(field) {
+        if !(errors.is_undefined() || errors.is_null()) {
+            let errors = array_to_errors(errors);
+            if !errors.is_empty() {
+                return Some(errors);
             }
         }
     }
@@ -458,6 +561,8 @@ fn get_next_task(_mv8: &MiniV8, r: &mv8::Object<'_>) -> Result<(MessageTarget, S
             "JavaScript" => MessageTarget::JavaScript,
             "Python" => MessageTarget::Python,
             "Rust" => MessageTarget::Rust,
+            "Wasm" => MessageTarget::Wasm,
+            "External" => MessageTarget::External,
             "Dynamic" => MessageTarget::Dynamic,
             "Main" => MessageTarget::Main,
             _ => return Err(Error::UnknownTarget(target)),
@@ -477,8 +582,15 @@ fn get_next_task(_mv8: &MiniV8, r: &mv8::Object<'_>) -> Result<(MessageTarget, S
 }
 
 impl<'m> RunnerImpl<'m> {
-    fn load_datasets(mv8: &'m MiniV8, shared_ctx: &SharedStore) -> Result<mv8::Value<'m>> {
+    /// Returns the JSON of all datasets and the step indices of the step-indexed datasets (see
+    /// [`Dataset::step_index`](crate::datastore::batch::Dataset::step_index)) as two objects of
+    /// strings, which are parsed in `runner.js`.
+    fn load_datasets(
+        mv8: &'m MiniV8,
+        shared_ctx: &SharedStore,
+    ) -> Result<(mv8::Value<'m>, mv8::Value<'m>)> {
         let js_datasets = mv8.create_object();
+        let js_step_datasets = mv8.create_object();
         for (dataset_name, dataset) in shared_ctx.datasets.iter() {
             let js_name = mv8.create_string(dataset_name.as_str());
 
@@ -488,15 +600,29 @@ impl<'m> RunnerImpl<'m> {
             let json =
                 std::str::from_utf8(json).map_err(|_| Error::Unique("Dataset not utf8".into()))?;
             let json = mv8.create_string(json);
+            js_datasets.set(js_name.clone(), json)?;
 
-            js_datasets.set(js_name, json)?;
+            let step_index = dataset.memory().get_metadata()?;
+            if !step_index.is_empty() {
+                let step_index = std::str::from_utf8(step_index)
+                    .map_err(|_| Error::Unique("Dataset step index not utf8".into()))?;
+                js_step_datasets.set(js_name, mv8.create_string(step_index))?;
+            }
         }
-        Ok(mv8::Value::Object(js_datasets))
+        Ok((
+            mv8::Value::Object(js_datasets),
+            mv8::Value::Object(js_step_datasets),
+        ))
     }
 
-    pub fn new(mv8: &'m MiniV8, init: &ExperimentInitRunnerMsg) -> Result<Self> {
+    pub fn new(
+        mv8: &'m MiniV8,
+        init: &ExperimentInitRunnerMsg,
+        task_timeout: Option<Duration>,
+        interrupts: Arc<TaskInterrupts>,
+    ) -> Result<Self> {
         let embedded = Embedded::import(mv8)?;
-        let datasets = Self::load_datasets(mv8, &init.shared_context)?;
+        let (datasets, step_datasets) = Self::load_datasets(mv8, &init.shared_context)?;
 
         let pkg_fns = mv8.create_array();
         let pkg_init_msgs = mv8.create_array();
@@ -522,6 +648,7 @@ impl<'m> RunnerImpl<'m> {
         let this = mv8::Value::Object(mv8.create_object());
         let args = mv8::Values::from_vec(vec![
             datasets,
+            step_datasets,
             mv8::Value::Array(pkg_init_msgs),
             mv8::Value::Array(pkg_fns),
         ]);
@@ -530,6 +657,8 @@ impl<'m> RunnerImpl<'m> {
             embedded,
             this,
             sims_state: HashMap::new(),
+            task_timeout,
+            interrupts,
         })
     }
 
@@ -545,6 +674,11 @@ impl<'m> RunnerImpl<'m> {
         dt: &DataType,
         len: Option<usize>,
     ) -> Result<ArrayData> {
+        if let DataType::Dictionary(key_type, _) = dt {
+            // Only the keys of dictionary arrays are flushed, see `ipc::read_record_batch`
+            return self.array_data_from_js(mv8, data, key_type, len);
+        }
+
         // `data` must not be dropped until flush is over, because
         // pointers returned from FFI point inside `data`'s ArrayBuffers' memory.
         let obj = data
@@ -619,6 +753,10 @@ impl<'m> RunnerImpl<'m> {
                 buffer_lens.push(target_len * 8); // 8 bytes per f64
                 Ok(())
             }
+            DataType::Int64 | DataType::Timestamp(..) => {
+                buffer_lens.push(target_len * 8); // 8 bytes per i64
+                Ok(())
+            }
             DataType::UInt32 => {
                 buffer_lens.push(target_len * 4); // 4 bytes per u32
                 Ok(())
@@ -744,7 +882,7 @@ impl<'m> RunnerImpl<'m> {
         self.flush_batch(
             mv8,
             msg_changes,
-            proxy.message_pool_mut().batch_mut(0)?,
+            proxy.message_pool_mut().batch_mut(i_proxy)?,
             msg_schema,
         )?;
 
@@ -841,6 +979,8 @@ impl<'m> RunnerImpl<'m> {
         let globals: &Globals = &run.globals;
         let globals = serde_json::to_string(globals).unwrap();
         let globals = mv8.create_string(&globals);
+        // u64 doesn't fit into a JS number, so the seed is passed as a string.
+        let seed = mv8.create_string(&run.seed.to_string());
 
         let args = mv8::Values::from_vec(vec![
             sim_id_to_js(mv8, run.short_id),
@@ -850,6 +990,7 @@ impl<'m> RunnerImpl<'m> {
             mv8::Value::Array(pkg_ids),
             mv8::Value::Array(pkg_msgs),
             mv8::Value::String(globals),
+            mv8::Value::String(seed),
         ]);
         self.embedded
             .start_sim
@@ -870,15 +1011,19 @@ impl<'m> RunnerImpl<'m> {
 
     /// Runs a task on JavaScript with the provided simulation id.
     ///
-    /// Returns the next task ([`TargetedRunnerTaskMsg`]) and, if present, warnings
+    /// Returns the next task ([`TargetedRunnerTaskMsg`]) and, if present, user errors and warnings
     /// ([`RunnerError`]) and logging statements.
     ///
+    /// User errors (e.g. a behavior throwing an exception) don't fail the task, so the simulation
+    /// run can keep going after they were reported.
+    ///
     /// # Errors
     ///
     /// May return an error if:
     ///
     /// - a value from Javascript could not be parsed,
-    /// - the task errored, or
+    /// - the package or the runner errored,
+    /// - the task ran for longer than the task timeout ([`Error::TaskTimeout`]), or
     /// - the state could not be flushed to the datastore.
     fn run_task(
         &mut self,
@@ -888,6 +1033,7 @@ impl<'m> RunnerImpl<'m> {
     ) -> Result<(
         TargetedRunnerTaskMsg,
         Option<Vec<RunnerError>>,
+        Option<Vec<RunnerError>>,
         Option<Vec<String>>,
     )> {
         log::debug!("Starting state interim sync before running task");
@@ -895,29 +1041,23 @@ impl<'m> RunnerImpl<'m> {
         self.state_interim_sync(mv8, sim_run_id, &msg.shared_store)?;
 
         log::debug!("Setting up run_task function call");
+        // A single group is passed as its index, multiple groups (or none) as an array of indices,
+        // which `run_task` in `runner.js` iterates over.
         let group_index = match &msg.shared_store.state {
             SharedState::None | SharedState::Write(_) | SharedState::Read(_) => {
                 mv8::Value::Undefined
             }
-            SharedState::Partial(partial) => match partial {
-                // TODO: Code duplication between read and write
-                PartialSharedState::Read(partial) => {
-                    if partial.indices.len() == 1 {
-                        mv8::Value::Number(partial.indices[0] as f64)
-                    } else {
-                        // Iterate over the subset of groups (since there's more than one group)
-                        todo!()
-                    }
-                }
-                PartialSharedState::Write(partial) => {
-                    if partial.indices.len() == 1 {
-                        mv8::Value::Number(partial.indices[0] as f64)
-                    } else {
-                        // Iterate over the subset of groups (since there's more than one group)
-                        todo!()
-                    }
+            SharedState::Partial(partial) => {
+                let indices = match partial {
+                    PartialSharedState::Read(partial) => &partial.indices,
+                    PartialSharedState::Write(partial) => &partial.indices,
+                };
+                if indices.len() == 1 {
+                    mv8::Value::Number(indices[0] as f64)
+                } else {
+                    idxs_to_js(mv8, indices)?
                 }
-            },
+            }
         };
 
         let (payload, wrapper) = msg
@@ -935,17 +1075,29 @@ impl<'m> RunnerImpl<'m> {
             payload_str,
         ]);
         log::debug!("Calling JS run_task");
-        let r: mv8::Object<'_> = self
-            .embedded
-            .run_task
-            .call_method(self.this.clone(), args)?;
+        let call_run_task = || {
+            self.embedded
+                .run_task
+                .call_method::<_, _, mv8::Object<'_>>(self.this.clone(), args)
+        };
+        let r: mv8::Object<'_> = match self.task_timeout {
+            Some(timeout) => match mv8.execute_with_timeout(timeout, call_run_task) {
+                (_, true) => {
+                    let behavior = self.running_behavior(mv8, msg.package_id);
+                    return Err(Error::TaskTimeout(timeout, behavior));
+                }
+                (r, false) => r?,
+            },
+            None => call_run_task()?,
+        };
 
         log::debug!("Post-processing run_task result");
         if let Some(error) = get_js_error(mv8, &r) {
-            // All types of errors are fatal (user, package, runner errors).
+            // Package and runner errors are fatal, user errors are reported instead.
             return Err(error);
         }
-        let warnings = get_user_warnings(mv8, &r);
+        let errors = get_user_errors(mv8, &r, "user_errors");
+        let warnings = get_user_errors(mv8, &r, "user_warnings");
         let logs = get_print(mv8, &r);
         let (next_target, next_task_payload) = get_next_task(mv8, &r)?;
 
@@ -977,7 +1129,19 @@ impl<'m> RunnerImpl<'m> {
                 payload: next_task_payload,
             },
         };
-        Ok((next_task_msg, warnings, logs))
+        Ok((next_task_msg, errors, warnings, logs))
+    }
+
+    /// Returns the name of the behavior the package with `pkg_id` was running when its task was
+    /// stopped, if it ran behaviors.
+    fn running_behavior(&self, mv8: &'m MiniV8, pkg_id: PackageId) -> Option<String> {
+        let pkgs: mv8::Object<'_> = self.this.as_object()?.get("pkgs").ok()?;
+        let pkg: mv8::Object<'_> = pkgs.get(pkg_id_to_js(mv8, pkg_id)).ok()?;
+        let experiment: mv8::Object<'_> = pkg.get("experiment").ok()?;
+        match experiment.get("running_behavior").ok()? {
+            mv8::Value::String(name) => Some(name.to_string()),
+            _ => None,
+        }
     }
 
     fn ctx_batch_sync(
@@ -1098,6 +1262,25 @@ impl<'m> RunnerImpl<'m> {
         Ok(())
     }
 
+    fn globals_sync(
+        &mut self,
+        mv8: &'m MiniV8,
+        sim_run_id: SimulationShortId,
+        msg: GlobalsSync,
+    ) -> Result<()> {
+        let globals = serde_json::to_string(&*msg.globals).unwrap();
+        let globals = mv8.create_string(&globals);
+        let args = mv8::Values::from_vec(vec![
+            sim_id_to_js(mv8, sim_run_id),
+            mv8::Value::String(globals),
+        ]);
+        let _: mv8::Value<'_> = self
+            .embedded
+            .globals_sync
+            .call_method(self.this.clone(), args)?;
+        Ok(())
+    }
+
     pub fn handle_msg(
         &mut self,
         mv8: &'m MiniV8,
@@ -1137,20 +1320,80 @@ impl<'m> RunnerImpl<'m> {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("context batch sync"))?;
                 self.ctx_batch_sync(mv8, sim_id, ctx_batch)?;
             }
+            InboundToRunnerMsgPayload::GlobalsSync(globals_msg) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("globals sync"))?;
+                self.globals_sync(mv8, sim_id, globals_msg)?;
+            }
             InboundToRunnerMsgPayload::TaskMsg(msg) => {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("run task"))?;
-                let (next_task_msg, warnings, logs) = self.run_task(mv8, sim_id, msg)?;
+                let task_id = msg.task_id;
+                let send_cancelled = || {
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::JavaScript,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+                    })
+                };
+                if !self.interrupts.start(task_id) {
+                    log::debug!("Skipping task {task_id}, which was cancelled");
+                    send_cancelled()?;
+                    return Ok(true);
+                }
+                let result = self.run_task(mv8, sim_id, msg);
+                if self.interrupts.finish(mv8) {
+                    log::debug!("Task {task_id} was cancelled while running");
+                    send_cancelled()?;
+                    return Ok(true);
+                }
+                let (next_task_msg, errors, warnings, logs) = match result {
+                    Ok(result) => result,
+                    Err(Error::TaskTimeout(timeout, behavior)) => {
+                        // The runner can keep going, only the task (and with it the simulation
+                        // run) fails.
+                        let message = match &behavior {
+                            Some(behavior) => format!(
+                                "Behavior {behavior} was stopped after running for longer than \
+                                 the task timeout of {timeout:?}"
+                            ),
+                            None => format!(
+                                "Task was stopped after running for longer than the task timeout \
+                                 of {timeout:?}"
+                            ),
+                        };
+                        log::warn!("{message}");
+                        outbound_sender.send(OutboundFromRunnerMsg {
+                            source: Language::JavaScript,
+                            sim_id,
+                            payload: OutboundFromRunnerMsgPayload::RunnerError(RunnerError {
+                                message: Some(message),
+                                details: None,
+                                file_name: behavior,
+                                line_number: None,
+                            }),
+                        })?;
+                        send_cancelled()?;
+                        return Ok(true);
+                    }
+                    Err(err) => return Err(err),
+                };
                 // TODO: `send` fn to reduce code duplication.
                 outbound_sender.send(OutboundFromRunnerMsg {
                     source: Language::JavaScript,
                     sim_id,
                     payload: OutboundFromRunnerMsgPayload::TaskMsg(next_task_msg),
                 })?;
+                if let Some(errors) = errors {
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::JavaScript,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::UserErrors(errors),
+                    })?;
+                }
                 if let Some(warnings) = warnings {
                     outbound_sender.send(OutboundFromRunnerMsg {
                         source: Language::JavaScript,
                         sim_id,
-                        payload: OutboundFromRunnerMsgPayload::RunnerWarnings(warnings),
+                        payload: OutboundFromRunnerMsgPayload::UserWarnings(warnings),
                     })?;
                 }
                 if let Some(logs) = logs {
@@ -1161,7 +1404,17 @@ impl<'m> RunnerImpl<'m> {
                     })?;
                 }
             }
-            InboundToRunnerMsgPayload::CancelTask(_) => {}
+            InboundToRunnerMsgPayload::CancelTask(task_id) => {
+                // Running tasks were already terminated and tasks which arrive later are skipped
+                // (see `TaskInterrupts`), so the task is done either way.
+                self.interrupts.forget(task_id);
+                outbound_sender.send(OutboundFromRunnerMsg {
+                    source: Language::JavaScript,
+                    // The worker doesn't know the simulation run of tasks it's not running anymore.
+                    sim_id: sim_id.unwrap_or_default(),
+                    payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+                })?;
+            }
         }
         Ok(true) // Continue running.
     }
@@ -1171,6 +1424,8 @@ pub struct JavaScriptRunner {
     // JavaScriptRunner and RunnerImpl are separate because the
     // V8 Isolate inside RunnerImpl can't be sent between threads.
     init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    interrupts: Arc<TaskInterrupts>,
     // Args to RunnerImpl::new
     inbound_sender: UnboundedSender<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
     inbound_receiver:
@@ -1181,11 +1436,17 @@ pub struct JavaScriptRunner {
 }
 
 impl JavaScriptRunner {
-    pub fn new(spawn: bool, init_msg: ExperimentInitRunnerMsg) -> WorkerResult<Self> {
+    pub fn new(
+        spawn: bool,
+        init_msg: ExperimentInitRunnerMsg,
+        task_timeout: Option<Duration>,
+    ) -> WorkerResult<Self> {
         let (inbound_sender, inbound_receiver) = unbounded_channel();
         let (outbound_sender, outbound_receiver) = unbounded_channel();
         Ok(Self {
             init_msg: Arc::new(init_msg),
+            task_timeout,
+            interrupts: Arc::default(),
             inbound_sender,
             inbound_receiver: Some(inbound_receiver),
             outbound_sender: Some(outbound_sender),
@@ -1194,29 +1455,6 @@ impl JavaScriptRunner {
         })
     }
 
-    pub async fn send(
-        &self,
-        sim_id: Option<SimulationShortId>,
-        msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        log::trace!("Sending message to JavaScript: {:?}", &msg);
-        self.inbound_sender
-            .send((sim_id, msg))
-            .map_err(|e| WorkerError::JavaScript(Error::InboundSend(e)))
-    }
-
-    pub async fn send_if_spawned(
-        &self,
-        sim_id: Option<SimulationShortId>,
-        msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        if self.spawned() {
-            log::trace!("JavaScript is spawned, sending message: {:?}", &msg);
-            self.send(sim_id, msg).await?;
-        }
-        Ok(())
-    }
-
     pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
         self.outbound_receiver
             .recv()
@@ -1228,10 +1466,6 @@ impl JavaScriptRunner {
         self.recv().now_or_never().transpose()
     }
 
-    pub fn spawned(&self) -> bool {
-        self.spawn
-    }
-
     pub async fn run(
         &mut self,
     ) -> WorkerResult<Pin<Box<dyn Future<Output = StdResult<WorkerResult<()>, JoinError>> + Send>>>
@@ -1243,16 +1477,50 @@ impl JavaScriptRunner {
         }
 
         let init_msg = Arc::clone(&self.init_msg);
+        let task_timeout = self.task_timeout;
+        let interrupts = Arc::clone(&self.interrupts);
         let inbound_receiver = self.inbound_receiver.take().ok_or(Error::AlreadyRunning)?;
         let outbound_sender = self.outbound_sender.take().ok_or(Error::AlreadyRunning)?;
 
-        let f = || _run(init_msg, inbound_receiver, outbound_sender);
+        let f = move || {
+            _run(
+                init_msg,
+                task_timeout,
+                interrupts,
+                inbound_receiver,
+                outbound_sender,
+            )
+        };
         Ok(Box::pin(tokio::task::spawn_blocking(f)))
     }
 }
 
+#[async_trait::async_trait]
+impl LanguageWorker for JavaScriptRunner {
+    async fn send(
+        &self,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> WorkerResult<()> {
+        log::trace!("Sending message to JavaScript: {:?}", &msg);
+        if let InboundToRunnerMsgPayload::CancelTask(task_id) = &msg {
+            // The runner might be busy executing the task, so it's stopped from here
+            self.interrupts.cancel(*task_id);
+        }
+        self.inbound_sender
+            .send((sim_id, msg))
+            .map_err(|e| WorkerError::JavaScript(Error::InboundSend(e)))
+    }
+
+    fn spawned(&self) -> bool {
+        self.spawn
+    }
+}
+
 fn _run(
     init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    interrupts: Arc<TaskInterrupts>,
     mut inbound_receiver: UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
     outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
 ) -> WorkerResult<()> {
@@ -1265,7 +1533,10 @@ fn _run(
     tokio::pin! {
         let impl_future = async {
             let mv8 = MiniV8::new();
-            let mut impl_ = RunnerImpl::new(&mv8, &init_msg)?;
+            interrupts.set_handle(Some(mv8.termination_handle()));
+            let _handle_guard = TerminationHandleGuard(&interrupts);
+            let mut impl_ =
+                RunnerImpl::new(&mv8, &init_msg, task_timeout, Arc::clone(&interrupts))?;
             loop {
                 tokio::select! {
                     Some((sim_id, msg)) = inbound_receiver.recv() => {
@@ -1,4 +1,4 @@
-((arrow, Batches, ExperimentContext, SimInitContext, gen_sim_ctx, gen_group_state)=>{
+((arrow, Batches, ExperimentContext, SimInitContext, gen_sim_ctx, gen_group_state, hash_stdlib)=>{
 const make_hash_set = fields => {
     const set = Object.create(null);
     for (var i_field = 0; i_field < fields.length; ++i) {
@@ -7,10 +7,13 @@ const make_hash_set = fields => {
     return set;
 }
 
-function start_experiment(datasets, pkg_init_msgs, pkg_fns) {
+function start_experiment(datasets, step_datasets, pkg_init_msgs, pkg_fns) {
     this.batches = new Batches();
     for (var dataset_name in datasets) datasets[dataset_name] = JSON.parse(datasets[dataset_name]);
-    this.experiment_ctx = new ExperimentContext(datasets);
+    for (var dataset_name in step_datasets) {
+        step_datasets[dataset_name] = JSON.parse(step_datasets[dataset_name]);
+    }
+    this.experiment_ctx = new ExperimentContext(datasets, step_datasets);
     this.sims = {}
     
     this.pkgs = {};
@@ -82,11 +85,17 @@ function start_sim(
     ctx_schema_bytes,
     pkg_ids,
     pkg_msgs,
-    globals
+    globals,
+    seed
 ) {
     globals = JSON.parse(globals);
     const agent_schema = load_schema(agent_schema_bytes);
     const sim = this.sims[sim_id] = {
+        /// Seed of the simulation run as a string, since it might not fit into a JS number.
+        "seed": seed,
+        /// Number of times each package ran on each group in the current step.
+        "group_runs": {},
+
         "schema": {
             "agent": agent_schema,
             "msg": load_schema(msg_schema_bytes),
@@ -136,6 +145,65 @@ function start_sim(
     sim.GroupState = gen_group_state(sim.schema.agent, sim.state_getters);
 }
 
+/// Seeds the standard library's random number generator, which also replaces `Math.random`,
+/// for running a task on a group (or on all groups if `i_group` is null). The random numbers
+/// then only depend on the seed of the simulation run and not on which worker runs the task.
+/// A package can run on a group several times per step (e.g. alternating with behaviors in
+/// other languages), so the number of previous runs in the step is part of the seed.
+const seed_random = (sim, pkg_id, i_group) => {
+    const key = pkg_id + ":" + i_group;
+    const runs = sim.group_runs[key] || 0;
+    sim.group_runs[key] = runs + 1;
+    hash_stdlib.setSeed([sim.seed, sim.ctx.step(), key, runs].join(":"));
+    Math.random = hash_stdlib.random;
+}
+
+const run_group_task = (pkg, pkg_id, sim_id, sim, i_group, task_message) => {
+    seed_random(sim, pkg_id, i_group);
+    const group_ctx = sim.ctx.get_group(i_group);
+    const ret = pkg.run_task(
+        pkg.experiment,
+        pkg.sims[sim_id],
+        JSON.parse(task_message),
+        sim.state[i_group],
+        group_ctx
+    ) || {};
+    ret.changes = sim.state[i_group].flush_changes(sim.schema);
+    return ret;
+}
+
+/// Merges the result of running a task on a single group into `ret`. Errors, warnings and
+/// printed output of all groups are kept. A group which continues the task on another target than
+/// main decides the target and task of the whole task, so groups continuing on different targets
+/// are an error.
+const merge_group_results = (ret, group_ret) => {
+    for (const field of ["user_errors", "user_warnings"]) {
+        if (group_ret[field]) {
+            ret[field] = (ret[field] || []).concat(group_ret[field]);
+        }
+    }
+    if (group_ret.print) {
+        ret.print = ret.print ? ret.print + "\n" + group_ret.print : group_ret.print;
+    }
+    for (const field of ["pkg_error", "runner_error"]) {
+        if (ret[field] === undefined && group_ret[field] !== undefined) {
+            ret[field] = group_ret[field];
+        }
+    }
+
+    const target = group_ret.target || "Main";
+    const merged_target = ret.target || "Main";
+    if (target !== "Main" && merged_target !== "Main" && target !== merged_target) {
+        throw new Error(
+            "Groups continued the task on different targets: " + merged_target + " and " + target
+        );
+    }
+    if (ret.target === undefined || (target !== "Main" && merged_target === "Main")) {
+        ret.target = group_ret.target;
+        ret.task = group_ret.task;
+    }
+}
+
 function run_task(sim_id, i_group, pkg_id, task_message) {
     const pkg = this.pkgs[pkg_id];
     const pkg_run_task = pkg.run_task;
@@ -149,6 +217,7 @@ function run_task(sim_id, i_group, pkg_id, task_message) {
     const sim = this.sims[sim_id];
     try {
         if (i_group === null || i_group === undefined) {
+            seed_random(sim, pkg_id, null);
             ret = pkg_run_task(
                 pkg.experiment,
                 pkg.sims[sim_id],
@@ -163,16 +232,18 @@ function run_task(sim_id, i_group, pkg_id, task_message) {
             for (var j_group = 0; j_group < sim.state.length; ++j_group) {
                 ret.changes[j_group] = sim.state[j_group].flush_changes(sim.schema);
             }
+        } else if (Array.isArray(i_group)) {
+            // The worker owns several groups, so run the task on each of them and merge the results.
+            ret = {};
+            const changes = [];
+            for (var j = 0; j < i_group.length; ++j) {
+                const group_ret = run_group_task(pkg, pkg_id, sim_id, sim, i_group[j], task_message);
+                changes[j] = group_ret.changes;
+                merge_group_results(ret, group_ret);
+            }
+            ret.changes = changes;
         } else {
-            const group_ctx = sim.ctx.get_group(i_group);
-            ret = pkg_run_task(
-                pkg.experiment,
-                pkg.sims[sim_id],
-                JSON.parse(task_message),
-                sim.state[i_group],
-                group_ctx
-            ) || {};
-            ret.changes = sim.state[i_group].flush_changes(sim.schema);
+            ret = run_group_task(pkg, pkg_id, sim_id, sim, i_group, task_message);
         }
     } catch(e) {
         return {
@@ -192,6 +263,11 @@ function ctx_batch_sync(sim_id, ctx_batch, state_group_start_idxs, current_step)
     ctx_batch.load_missing_cols(sim.schema.ctx, sim.context_loaders);
 
     sim.ctx.set_batch(ctx_batch, state_group_start_idxs, current_step);
+    sim.group_runs = {};
+}
+
+function globals_sync(sim_id, globals) {
+    this.sims[sim_id].ctx.set_globals(JSON.parse(globals));
 }
 
 const _sync_pools = (sim, batches, agent_pool, message_pool) => {
@@ -260,6 +336,7 @@ return [
     ctx_batch_sync,
     state_sync,
     state_interim_sync,
-    state_snapshot_sync
+    state_snapshot_sync,
+    globals_sync
 ];
 })
\ No newline at end of file
@@ -1,30 +1,34 @@
+pub mod external;
 pub mod javascript;
 pub mod python;
 pub mod rust;
+pub mod wasm;
 
 pub mod comms;
 
-use self::comms::{
-    inbound::InboundToRunnerMsgPayload, outbound::OutboundFromRunnerMsg, ExperimentInitRunnerMsg,
-};
+use self::comms::inbound::InboundToRunnerMsgPayload;
 pub use super::error::{Error, Result};
 use crate::proto::SimulationShortId;
 
+/// The messages the worker sends to a runner, regardless of the runner's language.
 #[async_trait::async_trait]
-pub trait LanguageWorker {
-    async fn new(spawn: bool, exp_init: ExperimentInitRunnerMsg) -> Self;
-    async fn send<K: TryInto<InboundToRunnerMsgPayload>>(
+pub trait LanguageWorker: Send + Sync {
+    async fn send(
         &self,
         sim_id: Option<SimulationShortId>,
-        msg: K,
+        msg: InboundToRunnerMsgPayload,
     ) -> Result<()>;
-    async fn send_if_spawned<K: TryInto<InboundToRunnerMsgPayload>>(
+
+    fn spawned(&self) -> bool;
+
+    async fn send_if_spawned(
         &self,
         sim_id: Option<SimulationShortId>,
-        msg: K,
-    ) -> Result<()>;
-    async fn recv(&mut self) -> Result<OutboundFromRunnerMsg>;
-    async fn recv_now(&mut self) -> Result<Option<OutboundFromRunnerMsg>>;
-    async fn run(&mut self) -> Result<()>;
-    fn spawned(&self) -> bool;
+        msg: InboundToRunnerMsgPayload,
+    ) -> Result<()> {
+        if self.spawned() {
+            self.send(sim_id, msg).await?;
+        }
+        Ok(())
+    }
 }
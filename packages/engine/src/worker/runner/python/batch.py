@@ -43,10 +43,16 @@ def load_markers(mem):
     return markers
 
 
+# Pyarrow returns schema metadata with `bytes` keys and values, or `None` if there is none.
+def get_metadata(metadata, key):
+    value = (metadata or {}).get(key.encode('utf-8'))
+    return value.decode('utf-8') if value is not None else None
+
+
 def parse_any_type_fields(metadata):
     any_type_fields = set()
 
-    field_names = metadata.get('any_type_fields')
+    field_names = get_metadata(metadata, 'any_type_fields')
 
     if field_names:
         for field_name in field_names.split(','):
@@ -55,6 +61,24 @@ def parse_any_type_fields(metadata):
     return any_type_fields
 
 
+# Returns the categories of categorical fields by field name.
+def parse_categorical_fields(metadata):
+    categorical_fields = get_metadata(metadata, 'categorical_fields')
+    return json.loads(categorical_fields) if categorical_fields else {}
+
+
+def categories_to_codes(col, categories, field_name):
+    codes = []
+    for elem in col:
+        if elem is None:
+            codes.append(None)
+        elif elem in categories:
+            codes.append(categories.index(elem))
+        else:
+            raise ValueError(f"{elem!r} is not one of the categories of {field_name}")
+    return codes
+
+
 def load_record_batch(mem, schema=None):
     (schema_offset, schema_size, _, _, meta_offset, _, data_offset, data_size) = load_markers(mem)
     # Pyarrow exposes a function for parsing the record batch message data header and
@@ -67,37 +91,54 @@ def load_record_batch(mem, schema=None):
 
     if schema is None:
         schema = pa.ipc.read_schema(schema_buf)
-    rb = pa.ipc.read_record_batch(rb_buf, schema)
-
-    any_type_fields = parse_any_type_fields(schema.metadata)
-
-    # Put data about `any` types and nullability directly in record batch.
-    for i_field in range(len(rb.num_columns)):
-        field = rb.schema.field(i_field)
-        vector = rb.column(i_field)
-        vector.type.is_any = field.name in any_type_fields
-        vector.type.is_nullable = field.nullable
 
-    return rb
-
-
-# Returns dataset name, dataset contents and whether JSON could be loaded.
+    # Categorical fields are dictionary-encoded, but batches only contain the indices, so they
+    # are read as indices and the dictionaries are built from the schema metadata.
+    categorical_fields = parse_categorical_fields(schema.metadata)
+    if not categorical_fields:
+        return pa.ipc.read_record_batch(rb_buf, schema)
+
+    index_schema = pa.schema(
+        [field.with_type(field.type.index_type) if pa.types.is_dictionary(field.type) else field
+         for field in schema],
+        metadata=schema.metadata
+    )
+    rb = pa.ipc.read_record_batch(rb_buf, index_schema)
+    columns = []
+    for field, column in zip(schema, rb.columns):
+        if pa.types.is_dictionary(field.type):
+            dictionary = pa.array(categorical_fields[field.name], type=field.type.value_type)
+            column = pa.DictionaryArray.from_arrays(column, dictionary)
+        columns.append(column)
+    return pa.RecordBatch.from_arrays(columns, schema=schema)
+
+
+# Returns dataset name, dataset contents, whether JSON could be loaded and the rows of the dataset
+# by step if it's step-indexed (otherwise `None`).
 def load_dataset(batch_id):
-    mem = load_shared_mem(shared_buf_from_c_memory(batch_id))
-    (_, _, header_offset, header_size, _, _, data_offset, data_size) = load_markers(mem)
+    mem = shared_buf_from_c_memory(load_shared_mem(batch_id))
+    (_, _, header_offset, header_size, meta_offset, meta_size, data_offset, data_size) = \
+        load_markers(mem)
 
     # The header has the shortname of the dataset
     header_buf = mem[header_offset: header_offset + header_size]
     dataset_name = str(header_buf.to_pybytes().decode('utf-8'))
 
+    # The metadata buffer has the step index of a step-indexed dataset as a JSON string and is
+    # empty for other datasets
+    step_index = None
+    if meta_size > 0:
+        meta_buf = mem[meta_offset: meta_offset + meta_size]
+        step_index = json.loads(meta_buf.to_pybytes().decode('utf8'))
+
     # This data buffer has the dataset as a JSON string
     data_buf = mem[data_offset: data_offset + data_size]
     dataset_utf8 = data_buf.to_pybytes().decode('utf8')
     try:
-        return dataset_name, json.loads(dataset_utf8), True
+        return dataset_name, json.loads(dataset_utf8), True, step_index
     except: # TODO: Only catch exact JSON parsing error.
             # TODO: Extract parsing error line number from exception.
-        return dataset_name, dataset_utf8, False
+        return dataset_name, dataset_utf8, False, step_index
 
 
 class Batch:
@@ -109,6 +150,8 @@ class Batch:
         self.mem = None  # After loading, `mem` will be a shared buffer.
         self.rb = None  # After loading, `rb` will be a record batch.
         self.cols = {}  # Syncing erases columns that have become invalid.
+        # Parsed from the schema metadata of `rb`
+        self.any_type_fields = set()
 
         # For flushing:
         self.c_memory = None
@@ -131,19 +174,25 @@ class Batch:
             self.batch_version = latest_batch.batch_version
             self.rb = load_record_batch(self.mem, schema)
             self.cols = {}  # Avoid using obsolete column data.
+            self.any_type_fields = parse_any_type_fields(self.rb.schema.metadata)
             self.static_meta = static_meta_from_schema(self.rb.schema)
 
     def load_col(self, name, loader=None):
-        vector = self.rb.column(name)
-        if not vector:
+        i_field = self.rb.schema.get_field_index(name)
+        if i_field < 0:
             raise RuntimeError("Missing vector for " + name)
+        vector = self.rb.column(i_field)
 
         if loader is not None:
             col = loader(vector)
-        elif len(name) >= 9 and (name[:2] == '_PRIVATE_' or name[:2] == '_HIDDEN_'): # only agent-scoped fields are fully loaded by default
+        elif name.startswith('_PRIVATE_') or name.startswith('_HIDDEN_'): # only agent-scoped fields are fully loaded by default
             col = hash_util.load_shallow(vector)
         else:
-            col = hash_util.load_full(vector)
+            col = hash_util.load_full(
+                vector,
+                is_any=name in self.any_type_fields,
+                is_nullable=self.rb.schema.field(i_field).nullable
+            )
 
         self.cols[name] = col
         return col
@@ -158,11 +207,12 @@ class Batch:
 
     def flush_changes(self, schema, skip):
         any_type_fields = parse_any_type_fields(schema.metadata)
+        categorical_fields = parse_categorical_fields(schema.metadata)
 
         # Dynamically accessed columns (if any) were added to `cols` by `state`.
         changes = []
         for field_name, col in self.cols.items():
-            if type(col) is not list or skip[field_name]:
+            if type(col) is not list or field_name in skip:
                 continue  # Column wasn't written to or was writable in place.
 
             i_field = schema.get_field_index(field_name)
@@ -170,14 +220,19 @@ class Batch:
                 continue  # Not supposed to have this column in `cols`?
 
             field = schema.field(i_field)
+            typ = field.type
             if field.name in any_type_fields:
                 c = [json.dumps(elem) for elem in col]
+            elif pa.types.is_dictionary(typ):
+                # Only the indices of categories are flushed, see `load_record_batch`.
+                c = categories_to_codes(col, categorical_fields[field.name], field.name)
+                typ = typ.index_type
             else:
                 c = col
 
             changes.append({
                 'i_field': i_field,
-                'data': pa.array(c, type=field.type)
+                'data': pa.array(c, type=typ)
             })
 
         if len(changes) == 0:
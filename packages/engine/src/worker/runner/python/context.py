@@ -11,6 +11,10 @@ class AgentContext:
         self.state_snapshot = state_snapshot
         self.__idx_in_sim = i_agent_in_sim  # (As opposed to agent index in its group)
 
+    # Points this object at another agent of the simulation run.
+    def set_index(self, i_agent_in_sim):
+        self.__idx_in_sim = i_agent_in_sim
+
     def to_json(self):
         r = {}
         for field_name in self.__cols:
@@ -23,9 +27,15 @@ class AgentContext:
     def data(self):
         return self.__sim_ctx.data()
 
+    def step_data(self, name):
+        return self.__sim_ctx.step_data(name)
+
     def __getattr__(self, field_name):
-        elem = self.__dict__['__cols'][field_name][self.__dict__['__idx_in_sim']]
-        getter = self.__dict__['__getters'].get(field_name)
+        col = self.__cols.get(field_name)
+        if col is None:
+            raise AttributeError("Missing context field: " + field_name)
+        elem = col[self.__idx_in_sim]
+        getter = self.__getters.get(field_name)
         return elem if getter is None else getter(self, elem)
 
     # Context is immutable, so there's no `__setattr__`.
@@ -43,7 +53,7 @@ class GroupContext:
     def get_agent(self, i_agent_in_group, old_agent_ctx=None):
         idx_in_sim = i_agent_in_group + self.__start_idx
         if old_agent_ctx is not None:  # Reuse AgentContext object for performance.
-            old_agent_ctx.__AgentContext_idx_in_sim = idx_in_sim
+            old_agent_ctx.set_index(idx_in_sim)
             return old_agent_ctx
 
         return AgentContext(
@@ -56,6 +66,9 @@ class GroupContext:
     def data(self):
         return self.__sim_ctx.data()
 
+    def step_data(self, name):
+        return self.__sim_ctx.step_data(name)
+
 
 class Snapshot:
     def __init__(self, agent_pool, message_pool):
@@ -82,22 +95,28 @@ class SimContext:
         self.state_snapshot.agent_pool = agent_pool
         self.state_snapshot.message_pool = message_pool
 
+    def set_globals(self, sim_globals):
+        self.__globals = sim_globals
+
     def set_step(self, cur_step):
         self.__step = cur_step
 
-    # TODO: step getter method
+    def step(self):
+        return self.__step
 
-    def get_group(self, i_group):
+    # The context batch has the agents of all groups in order, so the first agent of a group is at
+    # the number of agents in the groups before it.
+    def get_group(self, group_start_idx):
         return GroupContext(
             self,
             self.__ctx_batch,
             self.state_snapshot,
-            i_group
+            group_start_idx
         )
 
     def get_agent(self, i_agent_in_sim, old_agent_ctx=None):
         if old_agent_ctx is not None:  # Reuse AgentContext object for performance.
-            old_agent_ctx.__AgentContext_idx_in_sim = i_agent_in_sim
+            old_agent_ctx.set_index(i_agent_in_sim)
             return old_agent_ctx
 
         return AgentContext(
@@ -110,6 +129,9 @@ class SimContext:
     def data(self):
         return self.__experiment_ctx.data()
 
+    def step_data(self, name):
+        return self.__experiment_ctx.step_data(name, self.__step)
+
 
 class SimInitContext:
     def __init__(self, experiment_ctx, sim_globals, agent_schema):
@@ -125,8 +147,17 @@ class SimInitContext:
 
 
 class ExperimentContext:
-    def __init__(self, datasets):
+    def __init__(self, datasets, step_datasets):
         self.__datasets = datasets  # TODO: Freeze somehow
+        # Rows of the step-indexed datasets by step
+        self.__step_datasets = step_datasets
 
     def data(self):
         return self.__datasets
+
+    # Row of the step-indexed dataset `name` for `step`, or `None` if it has none.
+    def step_data(self, name, step):
+        dataset = self.__step_datasets.get(name)
+        if dataset is None:
+            raise KeyError("Not a step-indexed dataset: " + name)
+        return dataset.get(str(step))
@@ -67,7 +67,14 @@ class NewSimulationRun(object):
             return obj
         return None
 
-def Start(builder): builder.StartObject(5)
+    # NewSimulationRun
+    def Seed(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(14))
+        if o != 0:
+            return self._tab.Get(flatbuffers.number_types.Uint64Flags, o + self._tab.Pos)
+        return 0
+
+def Start(builder): builder.StartObject(6)
 def NewSimulationRunStart(builder):
     """This method is deprecated. Please switch to Start."""
     return Start(builder)
@@ -91,6 +98,10 @@ def AddDatastoreInit(builder, datastoreInit): builder.PrependUOffsetTRelativeSlo
 def NewSimulationRunAddDatastoreInit(builder, datastoreInit):
     """This method is deprecated. Please switch to AddDatastoreInit."""
     return AddDatastoreInit(builder, datastoreInit)
+def AddSeed(builder, seed): builder.PrependUint64Slot(5, seed, 0)
+def NewSimulationRunAddSeed(builder, seed):
+    """This method is deprecated. Please switch to AddSeed."""
+    return AddSeed(builder, seed)
 def End(builder): return builder.EndObject()
 def NewSimulationRunEnd(builder):
     """This method is deprecated. Please switch to End."""
@@ -13,4 +13,5 @@ class RunnerInboundMsgPayload(object):
     TerminateSimulationRun = 7
     TerminateRunner = 8
     NewSimulationRun = 9
+    GlobalsSync = 10
 
@@ -8,4 +8,6 @@ class Target(object):
     Rust = 2
     Main = 3
     Dynamic = 4
+    Wasm = 5
+    External = 6
 
@@ -31,7 +31,28 @@ class UserError(object):
             return self._tab.String(o + self._tab.Pos)
         return None
 
-def Start(builder): builder.StartObject(1)
+    # UserError
+    def Details(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(6))
+        if o != 0:
+            return self._tab.String(o + self._tab.Pos)
+        return None
+
+    # UserError
+    def FileName(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(8))
+        if o != 0:
+            return self._tab.String(o + self._tab.Pos)
+        return None
+
+    # UserError
+    def LineNumber(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(10))
+        if o != 0:
+            return self._tab.Get(flatbuffers.number_types.Int32Flags, o + self._tab.Pos)
+        return None
+
+def Start(builder): builder.StartObject(4)
 def UserErrorStart(builder):
     """This method is deprecated. Please switch to Start."""
     return Start(builder)
@@ -39,6 +60,18 @@ def AddMsg(builder, msg): builder.PrependUOffsetTRelativeSlot(0, flatbuffers.num
 def UserErrorAddMsg(builder, msg):
     """This method is deprecated. Please switch to AddMsg."""
     return AddMsg(builder, msg)
+def AddDetails(builder, details): builder.PrependUOffsetTRelativeSlot(1, flatbuffers.number_types.UOffsetTFlags.py_type(details), 0)
+def UserErrorAddDetails(builder, details):
+    """This method is deprecated. Please switch to AddDetails."""
+    return AddDetails(builder, details)
+def AddFileName(builder, fileName): builder.PrependUOffsetTRelativeSlot(2, flatbuffers.number_types.UOffsetTFlags.py_type(fileName), 0)
+def UserErrorAddFileName(builder, fileName):
+    """This method is deprecated. Please switch to AddFileName."""
+    return AddFileName(builder, fileName)
+def AddLineNumber(builder, lineNumber): builder.PrependInt32Slot(3, lineNumber, None)
+def UserErrorAddLineNumber(builder, lineNumber):
+    """This method is deprecated. Please switch to AddLineNumber."""
+    return AddLineNumber(builder, lineNumber)
 def End(builder): return builder.EndObject()
 def UserErrorEnd(builder):
     """This method is deprecated. Please switch to End."""
@@ -38,7 +38,21 @@ class UserWarning(object):
             return self._tab.String(o + self._tab.Pos)
         return None
 
-def Start(builder): builder.StartObject(2)
+    # UserWarning
+    def FileName(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(8))
+        if o != 0:
+            return self._tab.String(o + self._tab.Pos)
+        return None
+
+    # UserWarning
+    def LineNumber(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(10))
+        if o != 0:
+            return self._tab.Get(flatbuffers.number_types.Int32Flags, o + self._tab.Pos)
+        return None
+
+def Start(builder): builder.StartObject(4)
 def UserWarningStart(builder):
     """This method is deprecated. Please switch to Start."""
     return Start(builder)
@@ -50,6 +64,14 @@ def AddDetails(builder, details): builder.PrependUOffsetTRelativeSlot(1, flatbuf
 def UserWarningAddDetails(builder, details):
     """This method is deprecated. Please switch to AddDetails."""
     return AddDetails(builder, details)
+def AddFileName(builder, fileName): builder.PrependUOffsetTRelativeSlot(2, flatbuffers.number_types.UOffsetTFlags.py_type(fileName), 0)
+def UserWarningAddFileName(builder, fileName):
+    """This method is deprecated. Please switch to AddFileName."""
+    return AddFileName(builder, fileName)
+def AddLineNumber(builder, lineNumber): builder.PrependInt32Slot(3, lineNumber, None)
+def UserWarningAddLineNumber(builder, lineNumber):
+    """This method is deprecated. Please switch to AddLineNumber."""
+    return AddLineNumber(builder, lineNumber)
 def End(builder): return builder.EndObject()
 def UserWarningEnd(builder):
     """This method is deprecated. Please switch to End."""
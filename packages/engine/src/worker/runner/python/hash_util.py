@@ -1,3 +1,4 @@
+from copy import deepcopy
 from json import loads
 import pyarrow as pa
 from pyarrow.types import is_primitive
@@ -17,18 +18,25 @@ def _writable_in_place(typ):
         is_bool = (typ.bit_width == 1)
         return not is_bool
 
+    if pa.types.is_timestamp(typ):
+        return False  # Loaded as milliseconds instead of `datetime`s.
+
     if isinstance(typ, pa.FixedSizeListType):
         return _writable_in_place(typ.value_type)
 
     return False  # TODO: Struct? Union? FixedSizeBinary?
 
 
-def load_full(vector):
-    if vector.type.is_any:
+def load_full(vector, is_any=False, is_nullable=True):
+    if is_any:
         # `any` type fields are expensive
-        return [loads(any_obj.as_buffer().to_pybytes()) for any_obj in vector]
+        return [None if json is None else loads(json) for json in vector.to_pylist()]
+
+    if pa.types.is_timestamp(vector.type):
+        # Timestamps are milliseconds since the Unix epoch, as in JSON.
+        return vector.cast(pa.int64()).to_pylist()
 
-    if vector.type.is_nullable or not _writable_in_place(vector.type):
+    if is_nullable or not _writable_in_place(vector.type):
         # NOTE: Even if some nullable field were writable in place,
         #       changing it could change the null count, so its
         #       dynamic metadata would need to be updated.
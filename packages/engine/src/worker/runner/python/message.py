@@ -1,5 +1,6 @@
 import logging
 import json
+import os
 
 import flatbuffers
 import pyarrow as pa
@@ -10,10 +11,12 @@ from fbs.Init import Init
 from fbs.RunnerInboundMsg import RunnerInboundMsg
 from fbs.RunnerInboundMsgPayload import RunnerInboundMsgPayload
 from fbs.TaskMsg import TaskMsg
+from fbs.CancelTask import CancelTask
 from fbs.StateSync import StateSync
 from fbs.StateSnapshotSync import StateSnapshotSync
 from fbs.ContextBatchSync import ContextBatchSync
 from fbs.StateInterimSync import StateInterimSync
+from fbs.GlobalsSync import GlobalsSync
 from fbs.NewSimulationRun import NewSimulationRun
 from fbs.PackageType import PackageType
 from fbs.Target import Target
@@ -21,13 +24,25 @@ from fbs.Target import Target
 PACKAGE_TYPE = PackageType
 MESSAGE_TYPE = RunnerInboundMsgPayload
 
+# Version of the runner protocol, must match `PROTOCOL_VERSION` in `external/mod.rs`
+PROTOCOL_VERSION = 3
+
 # Outbound
-import fbs
+import fbs.Batch
+import fbs.Metaversion
+import fbs.StateInterimSync
+import fbs.UserWarning
+from fbs import RunnerOutboundMsg
+from fbs.RunnerOutboundMsgPayload import RunnerOutboundMsgPayload
 from fbs import RunnerError
 from fbs import PackageError
 from fbs import UserError
 from fbs import UserErrors
 from fbs import UserWarnings
+from fbs import TaskCancelled
+from fbs import StateSyncCompleted
+from fbs import Serialized
+from fbs import TaskId
 
 from batch import load_dataset
 
@@ -36,6 +51,16 @@ def assert_eq(a, b):
     assert a == b, (a, b)
 
 
+# Parses the JSON in a `Serialized` table.
+def load_serialized(fb):
+    return json.loads(fb.InnerAsNumpy().tobytes().decode('utf-8'))
+
+
+# Parses an Arrow IPC schema message from a flatbuffers byte vector.
+def read_schema(vector):
+    return pa.ipc.read_schema(pa.py_buffer(vector.tobytes()))
+
+
 def pkgs_from_config(config):
     pkgs = {}
     for i_pkg in range(config.PackagesLength()):
@@ -56,20 +81,32 @@ class PyInit:
 class PySharedContext:
     def __init__(self, fb):
         self.__datasets = {}
+        self.__step_datasets = {}
         for i_dataset in range(fb.DatasetsLength()):
-            name, data, _did_parse = load_dataset(fb.Datasets(i_dataset).BatchId())
+            name, data, _did_parse, step_index = load_dataset(fb.Datasets(i_dataset).BatchId())
             # TODO: Use `did_parse` to show warnings to user?
             self.__datasets[name] = data
+            if step_index is not None:
+                self.__step_datasets[name] = step_index
 
     def data(self):
         return self.__datasets
 
+    def step_data(self):
+        return self.__step_datasets
+
+
+# Names of the package types by their `PackageType` value, which are also the names of their folders.
+PKG_TYPES = ["init", "context", "state", "output"]
+
 
 class PyPackage:
     def __init__(self, fb):
-        self.type = fb.Type()
-        self.name = fb.Name()
-        self.payload = json.loads(fb.InitPayload().Inner().decode('utf-8'))
+        self.type = PKG_TYPES[fb.Type()]
+        self.name = fb.Name().decode('utf-8')
+        # TODO: Send the fields a package owns to the runner.
+        self.owned_fields = []
+        self.payload = load_serialized(fb.InitPayload())
 
 
 class PyBatchMsg:
@@ -91,8 +128,15 @@ class PyTaskMsg:
         self.sim_id = sim_id
         self.pkg_id = fb.PackageSid()
         self.task_id = fb.TaskId()
-        self.sync = PyStateInterimSync(fb.Metaversioning())
-        self.payload = json.loads(fb.Payload().Inner().decode('utf-8'))
+        self.sync = PyStateInterimSync(sim_id, fb.Metaversioning())
+        self.payload = load_serialized(fb.Payload())
+
+
+class PyCancelTask:
+    def __init__(self, sim_id, fb):
+        # `sim_id` is 0 if the engine doesn't know the simulation run of the task anymore.
+        self.sim_id = sim_id
+        self.task_id = fb.TaskId()
 
 
 class PyStateInterimSync:
@@ -120,6 +164,12 @@ class PyContextBatchSync:
         self.cur_step = fb.CurrentStep()
 
 
+class PyGlobalsSync:
+    def __init__(self, sim_id, fb):
+        self.sim_id = sim_id
+        self.globals = json.loads(fb.Globals().decode('utf-8'))
+
+
 class PyStateSync:
     def __init__(self, sim_id, fb):
         self.sim_id = sim_id
@@ -137,14 +187,15 @@ class PySimRun:
         self.pkgs = pkgs_from_config(fb.PackageConfig())
         self.globals = json.loads(fb.Properties().decode('utf-8'))
         self.schema = PySchema(fb.DatastoreInit())
+        self.seed = fb.Seed()
         # TODO: DatastoreInit datasets per sim run?
 
 
 class PySchema:
     def __init__(self, fb):
-        self.agent = pa.ipc.read_schema(fb.AgentBatchSchema())
-        self.context = pa.ipc.read_schema(fb.ContextBatchSchema())
-        self.message = pa.ipc.read_schema(fb.MessageBatchSchema())
+        self.agent = read_schema(fb.AgentBatchSchemaAsNumpy())
+        self.context = read_schema(fb.ContextBatchSchemaAsNumpy())
+        self.message = read_schema(fb.MessageBatchSchemaAsNumpy())
 
 
 class PyTerminateSim:
@@ -153,20 +204,16 @@ class PyTerminateSim:
 
 
 class Messenger:
-    def __init__(self, experiment_id, worker_index):
-        prefix = 'ipc://' + experiment_id
-        worker_index = str(worker_index)
-
+    def __init__(self):
         # `to_rust` is for sending messages to the Rust process,
         # e.g. requesting init message and sending task results.
-        # `send_address` format must match format in nng receiver
-        # in `python/mod.rs`.
-        send_address = prefix + '-frompy' + worker_index
+        # The addresses are passed by the worker, see `external/mod.rs`.
+        send_address = os.environ['HASH_RUNNER_SEND_URL']
         self.to_rust = Pair0(dial=send_address)
         logging.debug("Opened socket to Rust")
 
         # For receiving messages from the Rust process
-        recv_address = prefix + '-topy' + worker_index
+        recv_address = os.environ['HASH_RUNNER_RECV_URL']
         self.from_rust = Pair0(listen=recv_address)
 
     def __del__(self):
@@ -180,7 +227,7 @@ class Messenger:
     # Receive experiment init message.
     def recv_init(self):
         # Notify Rust process that the Python runner has opened the socket.
-        self.to_rust.send(b'\x00')  # Arbitrary message
+        self.to_rust.send(bytes([PROTOCOL_VERSION]))
         logging.debug("Waiting for init")
 
         # Get reply from Rust process with init message.
@@ -203,8 +250,7 @@ class Messenger:
             return PyTaskMsg(sim_sid, TaskMsg().Init(p.Bytes, p.Pos)), t
 
         if t == MESSAGE_TYPE.CancelTask:
-            # TODO: CancelTask isn't used for now
-            return None, t
+            return PyCancelTask(sim_sid, CancelTask().Init(p.Bytes, p.Pos)), t
 
         if t == MESSAGE_TYPE.StateSync:
             return PyStateSync(sim_sid, StateSync().Init(p.Bytes, p.Pos)), t
@@ -228,6 +274,9 @@ class Messenger:
         if t == MESSAGE_TYPE.NewSimulationRun:
             return PySimRun(NewSimulationRun().Init(p.Bytes, p.Pos)), t
 
+        if t == MESSAGE_TYPE.GlobalsSync:
+            return PyGlobalsSync(sim_sid, GlobalsSync().Init(p.Bytes, p.Pos)), t
+
         raise RuntimeError(
             "Unknown message type {} from sim {}".format(t, sim_sid)
         )
@@ -241,37 +290,57 @@ class Messenger:
         continuation
     ):
         # TODO: Combine args into single message.
-        self.send_user_warnings(continuation.get("warnings", []))
-        self.send_user_errors(continuation.get("errors", []))
+        self.send_user_warnings(sim_id, continuation.get("warnings", []))
+        self.send_user_errors(sim_id, continuation.get("errors", []))
         target = continuation.get("target", "main")
-        task_msg = continuation.get("task", "")
+        task_msg = continuation.get("task", "{}")
         fbs_bytes = task_to_fbs_bytes(sim_id, changes, pkg_id, task_id, target, task_msg)
         self.to_rust.send(fbs_bytes)
 
-    def send_runner_error(self, error):
-        fbs_bytes = runner_error_to_fbs_bytes(error)
+    def send_runner_error(self, error, sim_id=None):
+        fbs_bytes = runner_error_to_fbs_bytes(sim_id, error)
+        self.to_rust.send(fbs_bytes)
+
+    def send_task_cancelled(self, sim_id, task_id):
+        fbs_bytes = task_cancelled_to_fbs_bytes(sim_id, task_id)
         self.to_rust.send(fbs_bytes)
 
-    def send_pkg_error(self, error):
-        fbs_bytes = pkg_error_to_fbs_bytes(error)
+    def send_state_sync_completed(self, sim_id):
+        fbs_bytes = state_sync_completed_to_fbs_bytes(sim_id)
         self.to_rust.send(fbs_bytes)
 
-    def send_user_errors(self, errors):
+    def send_pkg_error(self, sim_id, error):
+        fbs_bytes = pkg_error_to_fbs_bytes(sim_id, error)
+        self.to_rust.send(fbs_bytes)
+
+    def send_user_errors(self, sim_id, errors):
         if len(errors) == 0:
             return
 
-        fbs_bytes = user_errors_to_fbs_bytes(errors)
+        fbs_bytes = user_errors_to_fbs_bytes(sim_id, errors)
         self.to_rust.send(fbs_bytes)
 
-    def send_user_warnings(self, warnings):
+    def send_user_warnings(self, sim_id, warnings):
         if len(warnings) == 0:
             return
 
-        fbs_bytes = user_warnings_to_fbs_bytes(warnings)
+        fbs_bytes = user_warnings_to_fbs_bytes(sim_id, warnings)
         self.to_rust.send(fbs_bytes)
 
 
-def runner_error_to_fbs_bytes(error):
+def outbound_msg_to_fbs_bytes(builder, sim_id, payload_type, payload_offset):
+    """Wraps an outbound payload, which was already built with `builder`, in a `RunnerOutboundMsg`."""
+    RunnerOutboundMsg.Start(builder)
+    RunnerOutboundMsg.AddSimSid(builder, sim_id if sim_id is not None else 0)
+    RunnerOutboundMsg.AddPayloadType(builder, payload_type)
+    RunnerOutboundMsg.AddPayload(builder, payload_offset)
+    msg_offset = RunnerOutboundMsg.End(builder)
+
+    builder.Finish(msg_offset)
+    return bytes(builder.Output())
+
+
+def runner_error_to_fbs_bytes(sim_id, error):
     # `initialSize` only affects performance (slightly), not correctness.
     builder = flatbuffers.Builder(initialSize=len(error))
 
@@ -281,11 +350,35 @@ def runner_error_to_fbs_bytes(error):
     RunnerError.AddMsg(builder, msg_offset)
     runner_error_offset = RunnerError.End(builder)
 
-    builder.Finish(runner_error_offset)
-    return bytes(builder.Output())
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.RunnerError, runner_error_offset
+    )
 
 
-def pkg_error_to_fbs_bytes(error):
+def task_cancelled_to_fbs_bytes(sim_id, task_id):
+    builder = flatbuffers.Builder(initialSize=0)
+
+    TaskCancelled.Start(builder)
+    TaskCancelled.AddTaskId(builder, TaskId.CreateTaskId(builder, task_id.Inner()))
+    task_cancelled_offset = TaskCancelled.End(builder)
+
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.TaskCancelled, task_cancelled_offset
+    )
+
+
+def state_sync_completed_to_fbs_bytes(sim_id):
+    builder = flatbuffers.Builder(initialSize=0)
+
+    StateSyncCompleted.Start(builder)
+    completed_offset = StateSyncCompleted.End(builder)
+
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.StateSyncCompleted, completed_offset
+    )
+
+
+def pkg_error_to_fbs_bytes(sim_id, error):
     # `initialSize` only affects performance (slightly), not correctness.
     builder = flatbuffers.Builder(initialSize=len(error))
 
@@ -295,27 +388,47 @@ def pkg_error_to_fbs_bytes(error):
     PackageError.AddMsg(builder, msg_offset)
     pkg_error_offset = PackageError.End(builder)
 
-    builder.Finish(pkg_error_offset)
-    return bytes(builder.Output())
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.PackageError, pkg_error_offset
+    )
+
+
+def _user_error_fields(error):
+    # Errors and warnings can either be plain strings or dicts with a `msg` and optionally
+    # `details`, `file_name` and `line_number`.
+    if isinstance(error, str):
+        return error, None, None, None
+    return (
+        str(error.get("msg")),
+        error.get("details"),
+        error.get("file_name"),
+        error.get("line_number")
+    )
 
 
 def user_error_to_fbs(builder, error):
-    msg_offset = builder.CreateString(error)
+    msg, details, file_name, line_number = _user_error_fields(error)
+    msg_offset = builder.CreateString(msg)
+    details_offset = builder.CreateString(details) if details is not None else None
+    file_name_offset = builder.CreateString(file_name) if file_name is not None else None
 
     UserError.Start(builder)
     UserError.AddMsg(builder, msg_offset)
-    user_error_offset = UserError.End(builder)
-
-    builder.Finish(user_error_offset)
-    return bytes(builder.Output())
+    if details_offset is not None:
+        UserError.AddDetails(builder, details_offset)
+    if file_name_offset is not None:
+        UserError.AddFileName(builder, file_name_offset)
+    if line_number is not None:
+        UserError.AddLineNumber(builder, line_number)
+    return UserError.End(builder)
 
 
-def user_errors_to_fbs_bytes(errors):
+def user_errors_to_fbs_bytes(sim_id, errors):
     # `initialSize` only affects performance (slightly), not correctness.
     builder = flatbuffers.Builder(initialSize=len(errors))
     error_offsets = [user_error_to_fbs(builder, e) for e in errors]
 
-    UserErrors.StartInnerVector(len(errors))
+    UserErrors.StartInnerVector(builder, len(errors))
     for o in reversed(error_offsets):
         builder.PrependUOffsetTRelative(o)
     vector_offset = builder.EndVector(len(errors))
@@ -324,27 +437,34 @@ def user_errors_to_fbs_bytes(errors):
     UserErrors.AddInner(builder, vector_offset)
     user_errors_offset = UserErrors.End(builder)
 
-    builder.Finish(user_errors_offset)
-    return bytes(builder.Output())
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.UserErrors, user_errors_offset
+    )
 
 
 def user_warning_to_fbs(builder, warning):
-    msg_offset = builder.CreateString(warning)
+    msg, details, file_name, line_number = _user_error_fields(warning)
+    msg_offset = builder.CreateString(msg)
+    details_offset = builder.CreateString(details) if details is not None else None
+    file_name_offset = builder.CreateString(file_name) if file_name is not None else None
 
     fbs.UserWarning.Start(builder)
     fbs.UserWarning.AddMsg(builder, msg_offset)
-    user_warning_offset = fbs.UserWarning.End(builder)
-
-    builder.Finish(user_warning_offset)
-    return bytes(builder.Output())
+    if details_offset is not None:
+        fbs.UserWarning.AddDetails(builder, details_offset)
+    if file_name_offset is not None:
+        fbs.UserWarning.AddFileName(builder, file_name_offset)
+    if line_number is not None:
+        fbs.UserWarning.AddLineNumber(builder, line_number)
+    return fbs.UserWarning.End(builder)
 
 
-def user_warnings_to_fbs_bytes(warnings):
+def user_warnings_to_fbs_bytes(sim_id, warnings):
     # `initialSize` only affects performance (slightly), not correctness.
     builder = flatbuffers.Builder(initialSize=len(warnings))
     warning_offsets = [user_warning_to_fbs(builder, w) for w in warnings]
 
-    UserWarnings.StartInnerVector(len(warnings))
+    UserWarnings.StartInnerVector(builder, len(warnings))
     for o in reversed(warning_offsets):
         builder.PrependUOffsetTRelative(o)
     vector_offset = builder.EndVector(len(warnings))
@@ -353,8 +473,9 @@ def user_warnings_to_fbs_bytes(warnings):
     UserWarnings.AddInner(builder, vector_offset)
     user_warnings_offset = UserWarnings.End(builder)
 
-    builder.Finish(user_warnings_offset)
-    return bytes(builder.Output())
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.UserWarnings, user_warnings_offset
+    )
 
 def target_to_fbs(target):
     if target == "py":
@@ -366,6 +487,12 @@ def target_to_fbs(target):
     if target == "rs":
         return Target.Rust
 
+    if target == "wasm":
+        return Target.Wasm
+
+    if target == "ext":
+        return Target.External
+
     if target == "dyn":
         return Target.Dynamic
 
@@ -384,7 +511,7 @@ def metaversion_to_fbs(builder, batch):
 
 def batch_to_fbs(builder, batch):
     batch_id = builder.CreateString(batch.id)
-    metaversion = metaversion_to_fbs(batch)
+    metaversion = metaversion_to_fbs(builder, batch)
     fbs.Batch.Start(builder)
     fbs.Batch.AddBatchId(builder, batch_id)
     fbs.Batch.AddMetaversion(builder, metaversion)
@@ -403,17 +530,17 @@ def interim_sync_to_fbs(builder, changes):
     for c in changes:
         message_offsets.append(batch_to_fbs(builder, c['message']))
 
-    fbs.StateInterimSync.StartGroupIdxVector(len(group_idxs))
+    fbs.StateInterimSync.StartGroupIdxVector(builder, len(group_idxs))
     for i in reversed(group_idxs):
         builder.PrependUint32(i)
     idxs_vector = builder.EndVector(len(group_idxs))
 
-    fbs.StateInterimSync.StartAgentBatchesVector(len(agent_offsets))
+    fbs.StateInterimSync.StartAgentBatchesVector(builder, len(agent_offsets))
     for o in reversed(agent_offsets):
         builder.PrependUOffsetTRelative(o)
     agent_vector = builder.EndVector(len(agent_offsets))
 
-    fbs.StateInterimSync.StartMessageBatchesVector(len(message_offsets))
+    fbs.StateInterimSync.StartMessageBatchesVector(builder, len(message_offsets))
     for o in reversed(message_offsets):
         builder.PrependUOffsetTRelative(o)
     message_vector = builder.EndVector(len(message_offsets))
@@ -426,22 +553,41 @@ def interim_sync_to_fbs(builder, changes):
     return sync_offset
 
 
-def task_to_fbs_bytes(sim_id, changes, pkd_id, task_id, target, task_msg):
-    builder = flatbuffers.Builder(initialSize=0)
+# The outbound `TaskMsg` table has the same name as the inbound one, so `flatc` only generates the
+# inbound one for Python (`fbs/TaskMsg.py`). The outbound one is built with the field slots of
+# `runner_outbound_msg.fbs`.
+def outbound_task_msg_to_fbs(builder, pkg_id, task_id, target, sync_offset, payload_offset):
+    builder.StartObject(5)
+    builder.PrependUint64Slot(0, pkg_id, 0)
+    builder.PrependStructSlot(1, TaskId.CreateTaskId(builder, task_id.Inner()), 0)
+    builder.PrependUint8Slot(2, target_to_fbs(target), 0)
+    builder.PrependUOffsetTRelativeSlot(3, sync_offset, 0)
+    builder.PrependUOffsetTRelativeSlot(4, payload_offset, 0)
+    return builder.EndObject()
 
-    sync_offset = interim_sync_to_fbs(builder, changes)
-    payload_offset = builder.CreateString(task_msg)
 
-    fbs.TaskMsg.Start(builder)
-    fbs.TaskMsg.AddPackageSid(builder, pkd_id)
-    fbs.TaskMsg.AddTaskId(builder, task_id)
-    fbs.TaskMsg.AddTarget(builder, target_to_fbs(target))
-    fbs.TaskMsg.AddMetaversioning(builder, sync_offset)
-    fbs.TaskMsg.AddPayload(builder, payload_offset)
-    task_msg_offset = fbs.TaskMsg.End(builder)
+def serialized_to_fbs(builder, payload):
+    if not isinstance(payload, str):
+        payload = json.dumps(payload)
+    inner_offset = builder.CreateByteVector(payload.encode('utf-8'))
 
-    builder.Finish(task_msg_offset)
-    return bytes(builder.Output())
+    Serialized.Start(builder)
+    Serialized.AddInner(builder, inner_offset)
+    return Serialized.End(builder)
+
+
+def task_to_fbs_bytes(sim_id, changes, pkg_id, task_id, target, task_msg):
+    builder = flatbuffers.Builder(initialSize=0)
+
+    sync_offset = interim_sync_to_fbs(builder, changes)
+    payload_offset = serialized_to_fbs(builder, task_msg)
+    task_msg_offset = outbound_task_msg_to_fbs(
+        builder, pkg_id, task_id, target, sync_offset, payload_offset
+    )
+
+    return outbound_msg_to_fbs_bytes(
+        builder, sim_id, RunnerOutboundMsgPayload.TaskMsg, task_msg_offset
+    )
 
 
 
@@ -1,25 +1,34 @@
 from pathlib import Path
 
+import hash_util
+
 
 def get_pkg_path(pkg_name, pkg_type):
-    return Path("../../../simulation/package/{}/packages/{}/package.py".format(
+    # Runners are started in the root of the engine package.
+    return Path("./src/simulation/package/{}/packages/{}/package.py".format(
         pkg_type, pkg_name
     ))
 
 
+FN_NAMES = ["start_experiment", "start_sim", "run_task"]
+
+
 def load_fns(pkg_name, pkg_type):
     # Read code.
     path = get_pkg_path(pkg_name, pkg_type)
-    code = "import ........worker.runner.python.hash_util\n"+path.read_text()
-
-    # Run code.
-    pkg_globals = {}
-    bytecode = compile(code.decode("utf-8"), pkg_name, "exec")
+    try:
+        code = path.read_text()
+    except OSError:
+        # Packages don't have to use Python.
+        return [None for _ in FN_NAMES]
+
+    # Run code. Packages use `hash_util` without importing it.
+    pkg_globals = {"hash_util": hash_util}
+    bytecode = compile(code, str(path), "exec")
     exec(bytecode, pkg_globals)
 
     # Extract functions.
-    fn_names = ["start_experiment", "start_sim", "run_task"]
-    fns = [(name, pkg_globals.get(name)) for name in fn_names]
+    fns = [(name, pkg_globals.get(name)) for name in FN_NAMES]
 
     # Validate functions.
     for (fn_name, fn) in fns:
@@ -29,7 +38,7 @@ def load_fns(pkg_name, pkg_type):
                     pkg_name, fn_name, type(fn)
                 )
             )
-    return fns
+    return [fn for (_, fn) in fns]
 
 
 class Package:
@@ -1,19 +1,73 @@
 import logging
+import queue
+import random
+import signal
 import sys
+import threading
 import time
 import traceback
 
 from batch import Batches
-from context import SimInitContext
+from context import ExperimentContext, SimInitContext
 from package import Package
 from sim import Sim
 from message import Messenger, MESSAGE_TYPE
 
 
+class TaskCancelled(BaseException):
+    """Raised in a task which the engine cancelled while it was running.
+
+    Derives from `BaseException`, so the `except Exception` around user code doesn't catch it.
+    """
+
+
+def merge_group_continuations(continuations):
+    """Merges the continuations of running a task on several groups into one.
+
+    Errors and warnings of all groups are kept. A group which continues on another target than
+    main decides the target and task of the whole task, so groups continuing on different targets
+    are an error.
+    """
+    merged = {"errors": [], "warnings": []}
+    for continuation in continuations:
+        merged["errors"].extend(continuation.get("errors", []))
+        merged["warnings"].extend(continuation.get("warnings", []))
+
+        target = continuation.get("target", "main")
+        merged_target = merged.get("target", "main")
+        if target != "main" and merged_target != "main" and target != merged_target:
+            raise RuntimeError(
+                "Groups continued the task on different targets: {} and {}".format(
+                    merged_target, target
+                )
+            )
+        if "target" not in merged or (target != "main" and merged_target == "main"):
+            merged["target"] = target
+            if "task" in continuation:
+                merged["task"] = continuation["task"]
+    return merged
+
+
 class Runner:
+    """Runs the Python tasks of a worker.
+
+    The task timeout isn't enforced here: the worker kills the runner process once a task ran
+    for twice the task timeout, which also stops behaviors blocked in native code (e.g. numpy or
+    pyarrow). Cancelling a task only interrupts it between bytecode instructions (see `receive`).
+    """
+
     def __init__(self, experiment_id, worker_index):
+        # Messages are received in the background (see `receive`), so a `CancelTask` message
+        # reaches the runner while a task is running and interrupts it with `SIGUSR1`.
+        self.inbox = queue.Queue()
+        self.running_lock = threading.Lock()
+        # Id of the running task, as a tuple of bytes, and whether it's being interrupted
+        self.running_task = None
+        self.interrupting = False
+        signal.signal(signal.SIGUSR1, self.interrupt_running_task)
+
         try:
-            self.messenger = Messenger(experiment_id, worker_index)
+            self.messenger = Messenger()
         except Exception as e:
             # Can't do much if messenger init fails.
             logging.error("Messenger init failed: " + str(e))
@@ -26,12 +80,12 @@ class Runner:
         try:
             self.experiment_ctx = self.start_experiment()
         except Exception as e:  # Have to catch generic Exception
-            self.handle_runner_error(e, sys.exc_info())
+            self.handle_runner_error(e, sys.exc_info()[2])
             raise e
 
     def start_experiment(self):
         init = self.messenger.recv_init()
-        experiment_ctx = init.shared_ctx
+        experiment_ctx = ExperimentContext(init.shared_ctx.data(), init.shared_ctx.step_data())
         for pkg_id, config in init.pkgs.items():
             self.pkgs[pkg_id] = pkg = Package(
                 name=config.name,
@@ -47,7 +101,7 @@ class Runner:
         return experiment_ctx
 
     def start_sim(self, msg):
-        self.sims[msg.sim_id] = sim = Sim(msg.schema, self.experiment_ctx, msg.globals)
+        self.sims[msg.sim_id] = sim = Sim(msg.schema, self.experiment_ctx, msg.globals, msg.seed)
         sim_init_ctx = SimInitContext(self.experiment_ctx, sim.globals, sim.schema.agent)
         for pkg_id, pkg in self.pkgs.items():
             pkg.sims[msg.sim_id] = pkg_sim_data = {}
@@ -60,34 +114,88 @@ class Runner:
                         sim.maybe_add_custom_fns(r, "getters", pkg)
                 except Exception as e:  # Have to catch anything
                     # TODO: Better error string
-                    self.messenger.send_pkg_error(str(e))
+                    self.messenger.send_pkg_error(msg.sim_id, str(e))
 
-    def run_task(self, sim_id, group_idx, pkg_id, task_id, task_msg):
-        sim = self.sims[sim_id]
-        if group_idx is None:
-            state = sim.state
-            ctx = sim.context
-        else:
-            state = sim.state.get_group(group_idx)
-            ctx = sim.context.get_group(group_idx)
+    def set_running_task(self, task_id):
+        with self.running_lock:
+            self.running_task = None if task_id is None else tuple(task_id.Inner())
+            self.interrupting = False
 
+    def interrupt_running_task(self, _signum, _frame):
+        # The signal may arrive just after the task finished, then there is nothing to interrupt.
+        if self.interrupting and self.running_task is not None:
+            raise TaskCancelled()
+
+    def run_task_on_groups(self, sim_id, pkg_id, group_idxs, task_id, task_msg):
+        """Runs the package on the groups of the task, which can be interrupted by a
+        cancellation of the task until it returns."""
+        sim = self.sims[sim_id]
         pkg = self.pkgs[pkg_id]
+        continuations = []
+        self.set_running_task(task_id)
         try:
-            # TODO: Pass `task_id` to package?
-            continuation = pkg.run_task(pkg.experiment, pkg.sims[sim_id], task_msg, state, ctx) or {}
+            # Tasks which don't run on any groups (e.g. the init task) run once on the whole
+            # simulation run.
+            for group_idx in group_idxs or [None]:
+                # Seed `random` (which `hstd.rand` uses) per group, so random numbers only depend on
+                # the seed of the simulation run and not on which worker runs the group. A package
+                # can run on a group several times per step, so the number of previous runs is part
+                # of the seed.
+                key = (pkg_id, group_idx)
+                runs = sim.group_runs.get(key, 0)
+                sim.group_runs[key] = runs + 1
+                random.seed("{}:{}:{}:{}:{}".format(
+                    sim.seed, sim.context.step(), pkg_id, group_idx, runs
+                ))
+
+                if group_idx is None:
+                    group_state = sim.state
+                    group_context = sim.context
+                else:
+                    group_state = sim.state.get_group(group_idx)
+                    group_context = sim.context.get_group(sim.state.group_start_idx(group_idx))
+
+                # TODO: Pass `task_id` to package?
+                continuations.append(pkg.run_task(
+                    pkg.experiment,
+                    pkg.sims[sim_id],
+                    task_msg,
+                    group_state,
+                    group_context
+                ) or {})
+        finally:
+            self.set_running_task(None)
+        return continuations
+
+    def run_task(self, sim_id, group_idxs, pkg_id, task_id, task_msg):
+        sim = self.sims[sim_id]
+        pkg = self.pkgs[pkg_id]
+        try:
+            continuations = self.run_task_on_groups(sim_id, pkg_id, group_idxs, task_id, task_msg)
+            continuation = merge_group_continuations(continuations)
+
+        except TaskCancelled:
+            # The `CancelTask` message which interrupted the task is still in the inbox and
+            # confirms the cancellation.
+            logging.debug("Task of package {} was interrupted by its cancellation".format(pkg.name))
+            return
 
         except Exception as e:
             # Have to catch generic Exception, because package could throw anything.
 
-            tb = str(traceback.format_exception(type(e), e, sys.exc_info()))
+            tb = str(traceback.format_exception(type(e), e, sys.exc_info()[2]))
             error = "Package {} error: {}".format(pkg.name, tb)
             # TODO: Custom log level(s) for non-engine (i.e. package/user) errors/warnings,
             #       e.g. `logging.external_error`?
             logging.error(error)
-            self.messenger.send_pkg_error(error)
+            self.messenger.send_pkg_error(sim_id, error)
             return
 
-        changes = state.flush_changes(sim.schema)
+        changes = []
+        for group_idx in group_idxs or []:
+            group_changes = sim.state.get_group(group_idx).flush_changes(sim.schema)
+            group_changes["i_group"] = group_idx
+            changes.append(group_changes)
         self.messenger.send_task_continuation(
             sim_id,
             changes,
@@ -97,6 +205,15 @@ class Runner:
         )
         # TODO: chaining if `continuation.target == "py"` for better performance
 
+    def cancel_task(self, sim_id, task_id):
+        # A running task was interrupted already (see `receive`), so a cancelled task either
+        # finished or it's still on its way to this runner, e.g. as a continuation from another
+        # runner. In the latter case, it's skipped when it arrives.
+        sim = self.sims.get(sim_id)
+        if sim is not None:
+            sim.cancelled_tasks.add(tuple(task_id.Inner()))
+        self.messenger.send_task_cancelled(sim_id, task_id)
+
     def ctx_batch_sync(self, sim_id, batch, cur_step):
         sim = self.sims[sim_id]
 
@@ -105,6 +222,8 @@ class Runner:
 
         sim.context.set_batch(ctx_batch)
         sim.context.set_step(cur_step)
+        sim.group_runs = {}
+        sim.cancelled_tasks = set()
 
     def state_sync(self, sim_id, agent_pool, message_pool):
         sim = self.sims[sim_id]
@@ -116,6 +235,8 @@ class Runner:
             message_pool[i_group].load_missing_cols(sim.schema.message, sim.state_loaders)
 
         sim.state.set_pools(agent_pool, message_pool, sim.state_loaders)
+        # The worker waits for the sync to be applied before it sends tasks depending on it.
+        self.messenger.send_state_sync_completed(sim_id)
 
     def state_interim_sync(self, sim_id, group_idxs, agent_batches, message_batches):
         sim = self.sims[sim_id]
@@ -123,14 +244,22 @@ class Runner:
             agent_batch = self.batches.sync(agent_batches[i], sim.schema.agent)
             agent_batch.load_missing_cols(sim.schema.agent, sim.state_loaders)
 
-            msg_batch = self.batches.sync(message_batches[i], sim.schema.msg)
-            msg_batch.load_missing_cols(sim.schema.msg, {})
+            msg_batch = self.batches.sync(message_batches[i], sim.schema.message)
+            msg_batch.load_missing_cols(sim.schema.message, {})
 
             group_state = sim.state.get_group(group_idx)
             group_state.set_batches(agent_batch, msg_batch)
 
-    def state_snapshot_sync(self, sim_id, agent_pool, msg_pool):
-        self.sims[sim_id].context.set_snapshot(agent_pool, msg_pool)
+    def state_snapshot_sync(self, sim_id, agent_pool, message_pool):
+        sim = self.sims[sim_id]
+        agent_pool = [self.batches.sync(batch, sim.schema.agent) for batch in agent_pool]
+        message_pool = [self.batches.sync(batch, sim.schema.message) for batch in message_pool]
+        sim.context.set_snapshot(agent_pool, message_pool)
+
+    def globals_sync(self, sim_id, sim_globals):
+        sim = self.sims[sim_id]
+        sim.globals = sim_globals
+        sim.context.set_globals(sim_globals)
 
     # TODO: rename to terminate?
     def kill(self):
@@ -155,10 +284,37 @@ class Runner:
         time.sleep(2)  # Give the Rust process time to receive the error message.
         self.kill()
 
-    def run(self):
+    def receive(self):
+        """Receives the messages of the worker into the inbox and interrupts the running task
+        when it's cancelled.
+
+        Runs in a background thread, since the main thread is busy while a task runs. Python only
+        handles signals between bytecode instructions of the main thread, so a behavior stuck in a
+        call to native code is only interrupted once the call returns.
+        """
         try:
             while True:
                 msg, t = self.messenger.recv()
+                if t == MESSAGE_TYPE.CancelTask:
+                    with self.running_lock:
+                        if self.running_task == tuple(msg.task_id.Inner()) \
+                                and not self.interrupting:
+                            self.interrupting = True
+                            signal.pthread_kill(threading.main_thread().ident, signal.SIGUSR1)
+                self.inbox.put((msg, t))
+                if t == MESSAGE_TYPE.TerminateRunner:
+                    break
+        except Exception as e:
+            # Raised again in the main thread
+            self.inbox.put((e, None))
+
+    def run(self):
+        threading.Thread(target=self.receive, daemon=True).start()
+        try:
+            while True:
+                msg, t = self.inbox.get()
+                if t is None:
+                    raise msg
                 if t == MESSAGE_TYPE.TerminateRunner:
                     self.kill()
                     break
@@ -181,25 +337,27 @@ class Runner:
                     )
 
                 if t == MESSAGE_TYPE.StateSnapshotSync:
-                    self.state_snapshot_sync(msg.sim_id, msg.agent_pool, msg.msg_pool)
+                    self.state_snapshot_sync(msg.sim_id, msg.agent_pool, msg.message_pool)
 
-                if t == MESSAGE_TYPE.TaskMsg:
-                    n_groups = self.sims[msg.sim_id].state.n_groups()
-
-                    group_idx = None
-                    if n_groups == 1:
-                        group_idx = msg.sync.group_idxs[0]
-                    elif len(msg.sync.group_idxs) != n_groups:
-                        # TODO
-                        raise RuntimeError(
-                            "Tasks on arbitrary subsets of groups are not supported currently"
-                        )
+                if t == MESSAGE_TYPE.GlobalsSync:
+                    self.globals_sync(msg.sim_id, msg.globals)
 
-                    self.run_task(msg.sim_id, group_idx, msg.pkg_id, msg.task_id, msg.payload)
+                if t == MESSAGE_TYPE.TaskMsg:
+                    sim = self.sims[msg.sim_id]
+                    task_key = tuple(msg.task_id.Inner())
+                    if task_key in sim.cancelled_tasks:
+                        sim.cancelled_tasks.remove(task_key)
+                        # Confirm again, since this runner is the active runner of the task now.
+                        self.messenger.send_task_cancelled(msg.sim_id, msg.task_id)
+                        continue
+
+                    self.run_task(
+                        msg.sim_id, msg.sync.group_idxs, msg.pkg_id, msg.task_id, msg.payload
+                    )
 
                 if t == MESSAGE_TYPE.CancelTask:
-                    pass  # TODO: CancelTask isn't used for now
+                    self.cancel_task(msg.sim_id, msg.task_id)
 
         except Exception as e:
             # Catch generic Exception to make sure it's logged before the runner exits.
-            self.handle_runner_error(e, sys.exc_info())
+            self.handle_runner_error(e, sys.exc_info()[2])
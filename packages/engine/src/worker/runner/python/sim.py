@@ -3,9 +3,15 @@ from state import SimState
 
 
 class Sim:
-    def __init__(self, schema, experiment_ctx, sim_globals):
+    def __init__(self, schema, experiment_ctx, sim_globals, seed):
         self.schema = schema
         self.globals = sim_globals
+        # Seed of the simulation run for the random number generator available to user code.
+        self.seed = seed
+        # Number of times each package ran on each group in the current step.
+        self.group_runs = {}
+        # Ids of the tasks which were cancelled in the current step, as tuples of bytes.
+        self.cancelled_tasks = set()
 
         # Context loaders and getters are for columns in the context batch.
         self.context_loaders = {}
@@ -26,11 +32,12 @@ class Sim:
 
         custom_fns = getattr(self, pkg.type + '_' + custom_property)
         for field_name in to_add:
-            if not pkg.owns_field.get(field_name):
-                raise RuntimeError(
-                    "Packages can only specify " + custom_property + " for fields they own, not '" +
-                    field_name + "' in " + pkg.name
-                )
+            # TODO: Uncomment after propagating owned_fields (like in the JavaScript runner):
+            # if field_name not in pkg.owns_field:
+            #     raise RuntimeError(
+            #         "Packages can only specify " + custom_property + " for fields they own, not '" +
+            #         field_name + "' in " + pkg.name
+            #     )
 
             if field_name in custom_fns:
                 raise RuntimeError(
@@ -10,29 +10,35 @@ def raise_missing_field(field_name):
 
 class AgentState:
     def __init__(self, group_state, i_agent_in_group):
-        self.__group_state = group_state
-        self.__cols = group_state.__GroupState_agent_batch.cols
-        self.__msgs = group_state.__GroupState_msg_batch.cols['messages']
-        self.__msgs_native = group_state.__GroupState_msgs_native
-        self.__idx_in_group = i_agent_in_group
-        self.__dyn_access = False
+        # Attributes of the agent state object itself are stored directly, since `__setattr__`
+        # writes to the agent's fields.
+        self.__dict__['__group_state'] = group_state
+        self.__dict__['__cols'] = group_state.agent_batch.cols
+        self.__dict__['__msgs'] = group_state.msg_batch.cols['messages']
+        self.__dict__['__msgs_native'] = group_state.msgs_native
+        self.__dict__['__idx_in_group'] = i_agent_in_group
+        self.__dict__['__dyn_access'] = False
 
     # TODO: It's possible that we don't want package users to
     #       have access to this, though we do want package
     #       authors to.
     def set_dynamic_access(self, enable_dynamic_access):
-        self.__dyn_access = enable_dynamic_access
+        self.__dict__['__dyn_access'] = enable_dynamic_access
+
+    # Points this object at another agent of the same group.
+    def set_index(self, i_agent_in_group):
+        self.__dict__['__idx_in_group'] = i_agent_in_group
 
     def to_json(self):
         r = {}
-        for name, col in self.__cols.items():
+        for name, col in self.__dict__['__cols'].items():
             r[name] = hash_util.json_deepcopy(self.__getattr__(name))
 
         r['messages'] = hash_util.json_deepcopy(self.messages)
         return r
 
     def _hasattr(self, field):
-        return field == "messages" or field in self.__cols
+        return field == "messages" or field in self.__dict__['__cols']
 
     def __getattr__(self, field):  # Can raise AttributeError.
         idx = self.__dict__['__idx_in_group']
@@ -51,7 +57,7 @@ class AgentState:
         col = self.__dict__['__cols'].get(field)
         if col is None:  # Slow path -- unlikely branch
             if self.__dict__['__dyn_access']:
-                self.__dict__['__cols'][field] = col = self.__group_state.load(field)
+                self.__dict__['__cols'][field] = col = self.__dict__['__group_state'].load(field)
             else:
                 raise_missing_field(field)
 
@@ -69,7 +75,7 @@ class AgentState:
 
         col = self.__dict__['__cols'].get(field)
         if col is None:  # Slow path -- unlikely branch
-            self.__dict__['__cols'][field] = col = self.__group_state.load(field)
+            self.__dict__['__cols'][field] = col = self.__dict__['__group_state'].load(field)
 
         col[idx] = value
 
@@ -92,7 +98,7 @@ class AgentState:
 
     # `data` is an optional argument. `data` must be JSON-serializable.
     def add_message(self, to, msg_type, data=None):
-        idx = self.__idx
+        idx = self.__dict__['__idx_in_group']
         self.__dict__['__msgs'][idx].append({
             "to": [to] if isinstance(to, str) else to,
             "type": msg_type,
@@ -101,38 +107,38 @@ class AgentState:
 
     # Returns the index of the currently executing behavior in the agent's behavior chain.
     def behavior_index(self):
-        return self.__i_behavior
+        return int(self.__getattr__('behavior_index'))
 
 
 class GroupState:
     def __init__(self, agent_batch, msg_batch, loaders):
-        self.__agent_batch = agent_batch
-        self.__msg_batch = msg_batch
+        self.agent_batch = agent_batch
+        self.msg_batch = msg_batch
         # TODO: Use numpy for msgs_native
-        self.__msgs_native = [False * len(agent_batch.cols['agent_id'])]
+        self.msgs_native = [False] * agent_batch.rb.num_rows
         self.__loaders = loaders
 
     def set_batches(self, agent_batch, msg_batch):
-        self.__agent_batch = agent_batch
-        self.__msg_batch = msg_batch
-        self.__msgs_native = [False * len(agent_batch.cols['agent_id'])]
+        self.agent_batch = agent_batch
+        self.msg_batch = msg_batch
+        self.msgs_native = [False] * agent_batch.rb.num_rows
 
     def to_json(self):
         raise RuntimeError("Group state shouldn't be copied to JSON.")
 
     def load(self, field_name):
-        if self.__agent_batch.rb.schema.get_field_index(field_name) < 0:
+        if self.agent_batch.rb.schema.get_field_index(field_name) < 0:
             raise_missing_field(field_name)  # Missing even with dynamic access
 
-        return self.__agent_batch.load_col(field_name, self.__loaders.get(field_name))
+        return self.agent_batch.load_col(field_name, self.__loaders.get(field_name))
 
     # Returns the number of agents in this group.
     def n_agents(self):
-        return self.__agent_batch.rb.num_rows
+        return self.agent_batch.rb.num_rows
 
     def get_agent(self, i_agent_in_group, old_agent_state=None):
         if old_agent_state is not None:
-            old_agent_state.__AgentState_idx = i_agent_in_group
+            old_agent_state.set_index(i_agent_in_group)
             return old_agent_state
 
         return AgentState(self, i_agent_in_group)
@@ -149,22 +155,23 @@ class GroupState:
         # messages to native JavaScript objects.
 
         skip = {'agent_id': True}
-        self.__agent_batch.flush_changes(schema.agent, skip)
+        self.agent_batch.flush_changes(schema.agent, skip)
 
         # Convert any native message objects to JSON before flushing message batch.
         # Note that this is distinct from (though analogous to) 'any'-type handling
         # in `batch.flush_changes`.
-        group_msgs = self.__msg_batch.cols['messages']
+        group_msgs = self.msg_batch.cols['messages']
         for i_agent, agent_msgs in enumerate(group_msgs):
-            if self.__msgs_native[i_agent]:
+            if self.msgs_native[i_agent]:
                 for msg in agent_msgs:
-                    msg.data = json.dumps(msg.data)
+                    msg["data"] = json.dumps(msg["data"])
+                self.msgs_native[i_agent] = False
 
-        self.__msg_batch.flush_changes(schema.message, {})
+        self.msg_batch.flush_changes(schema.message, {})
 
         return {
-            "agent": self.__agent_batch,
-            "message": self.__msg_batch
+            "agent": self.agent_batch,
+            "message": self.msg_batch
         }
 
 
@@ -193,6 +200,10 @@ class SimState:
     def get_group(self, i_group):
         return self.groups[i_group]
 
+    # Index of the first agent of the group in the simulation run.
+    def group_start_idx(self, i_group):
+        return sum(group.n_agents() for group in self.groups[:i_group])
+
     def flush_changes(self, schema):
         r = []
         for i_group, group in enumerate(self.groups):
@@ -1,63 +1,33 @@
-use std::{
-    any::Any,
-    collections::{HashMap, HashSet},
-    fs,
-    sync::Arc,
-};
-
-use arrow::datatypes::Schema;
-use parking_lot::{RwLock, RwLockWriteGuard};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use std::collections::{HashMap, HashSet};
 
-use crate::config::Globals;
-use crate::datastore::{batch::Metaversion, storage::memory::Memory};
-use crate::worker::{Error as WorkerError, Result as WorkerResult, TaskMessage};
-use crate::{
-    datastore::prelude::{AgentBatch, MessageBatch},
-    simulation::packages::id::PackageId,
+use super::{
+    behaviors::{get_built_in, BehaviorFn, ENGINE_COLUMNS},
+    context::GroupContext,
+    error::{Error, Result},
+    state::GroupState,
 };
 use crate::{
-    datastore::{
-        arrow::{
-            message::{outbound_messages_to_arrow_column, MESSAGE_COLUMN_INDEX},
-            util::arrow_continuation,
-        },
-        batch::{change::ArrayChange, ContextBatch},
-        table::sync::{ContextBatchSync, StateSync},
-    },
-    simulation::packages::{
-        state::packages::behavior_execution::config::BehaviorDescription,
+    simulation::package::{
+        id::PackageId, state::packages::behavior_execution::config::BehaviorDescription,
         worker_init::PackageInitMsgForWorker,
     },
+    worker::runner::comms::{outbound::RunnerError, MessageTarget},
     Language,
 };
 
-use super::super::comms::{
-    inbound::{InboundToRunnerMsg, InboundToRunnerMsgPayload},
-    outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
-    ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg, StateInterimSync,
-    TargetedRunnerTaskMsg,
-};
-use super::behaviors::{get_built_in, get_built_in_columns, is_built_in};
-use super::{AgentContext, AgentState, Column, Error, Result};
-use super::{GroupContext, GroupState};
-
 struct Behavior {
+    name: String,
     language: Language,
-    function:
-        Option<Box<dyn Fn(&mut AgentState, &AgentContext) -> Result<()> + Send + Sync + 'static>>,
+    /// Only Rust behaviors can be executed by this runner.
+    function: Option<BehaviorFn>,
 }
 
-pub type BehaviorID = u32;
-
+/// The Rust part of the behavior execution package.
 pub struct BehaviorPackage {
     id: PackageId,
-    behavior_map: HashMap<BehaviorID, Behavior>,
-    behavior_col_names: HashMap<BehaviorID, Vec<String>>,
-
-    // TODO: Make these private once there are Rust packages other than behavior execution:
-    pub col_map: Arc<HashMap<String, Box<dyn Column>>>,
-    pub all_behavior_col_names: Arc<HashSet<String>>,
+    behaviors: HashMap<[u16; 2], Behavior>,
+    /// Agent columns used by any of the Rust behaviors.
+    columns: HashSet<String>,
 }
 
 impl BehaviorPackage {
@@ -65,98 +35,100 @@ impl BehaviorPackage {
         self.id
     }
 
-    pub fn start_experiment(pkg_id: PackageId, init: PackageInitMsgForWorker) -> Result<Self> {
-        let descs: Vec<BehaviorDescription> = serde_json::value::from_value(init.payload).unwrap();
-        let mut behavior_map = HashMap::new();
-        let mut col_map = HashMap::new();
-        let mut behavior_col_names = HashMap::new();
-        let mut all_behavior_col_names = HashSet::new();
+    pub fn start_experiment(init: &PackageInitMsgForWorker) -> Result<Self> {
+        let descs: Vec<BehaviorDescription> = serde_json::from_value(init.payload.clone())?;
+        let mut behaviors = HashMap::new();
+        let mut columns = HashSet::new();
         for desc in descs {
-            if desc.language != Language::Rust {
-                let behavior = Behavior {
-                    language: desc.language,
-                    function: None,
-                };
-                // Behavior IDs must be unique.
-                behavior_map
-                    .try_insert(desc.id, behavior)
-                    .expect("Duplicate behavior id");
-                continue;
-            }
-
-            if !is_built_in(&desc.name) {
-                return Err(Error::InvalidRustBuiltIn(desc.name.clone()));
-            }
+            let function = if desc.language == Language::Rust {
+                columns.extend(
+                    desc.required_field_keys
+                        .iter()
+                        .filter(|key| !ENGINE_COLUMNS.contains(&key.as_str()))
+                        .cloned(),
+                );
+                Some(get_built_in(&desc.name)?)
+            } else {
+                None
+            };
 
+            let id = [desc.id.lang_index(), desc.id.lang_behavior_index()];
             let behavior = Behavior {
+                name: desc.name,
                 language: desc.language,
-                function: Some(get_built_in(&desc.name)?),
+                function,
             };
-            behavior_map
-                .try_insert(desc.id, behavior)
-                .expect("Duplicate behavior id");
-
-            let cols = get_built_in_columns(&desc.name)?;
-            let mut col_names = vec![];
-            for (col_name, col) in cols {
-                col_names.push(col_name.clone());
-
-                all_behavior_col_names.insert(col_name.clone());
-                col_map.entry(col_name).or_insert(col);
+            if behaviors.insert(id, behavior).is_some() {
+                return Err(Error::from(format!("Duplicate behavior id: {id:?}")));
             }
-            behavior_col_names.insert(desc.id, col_names);
         }
 
-        // Engine built-ins; TODO: Reduce code duplication
-        all_behavior_col_names.insert("behaviors".to_string());
-        all_behavior_col_names.insert("position".to_string());
-        all_behavior_col_names.insert("direction".to_string());
-
         Ok(Self {
-            id: pkg_id,
-            behavior_map,
-            behavior_col_names,
-            col_map: Arc::new(col_map),
-            all_behavior_col_names: Arc::new(all_behavior_col_names),
+            id: init.id,
+            behaviors,
+            columns,
         })
     }
 
-    pub fn run_task(
-        &mut self,
-        group_state: &mut GroupState,
-        group_context: &GroupContext,
-    ) -> Result<MessageTarget> {
-        if group_state.n_agents() == 0 {
-            return Ok(MessageTarget::Main);
-        }
+    pub fn columns(&self) -> &HashSet<String> {
+        &self.columns
+    }
 
+    /// Runs the behavior chains of all agents in the group, starting at their `behavior_index`,
+    /// until the chain ends or a behavior in another language is reached.
+    ///
+    /// Returns the language the task has to continue in, if any, and the errors of behaviors.
+    /// Like in the other runners, an agent whose behavior failed skips the rest of its chain for
+    /// this step.
+    pub fn run_group(
+        &self,
+        group_state: &mut GroupState<'_>,
+        group_context: &GroupContext<'_>,
+    ) -> Result<(MessageTarget, Vec<RunnerError>)> {
         let mut next_target = MessageTarget::Main;
+        let mut errors = Vec::new();
 
-        let mut agent_state = group_state.get_agent(0);
-        let mut agent_context = group_context.get_agent(0);
-        for i_agent in 0..group_state.n_agents() {
-            agent_state.set_index(i_agent);
-            agent_context.set_index(i_agent);
+        for i_agent in 0..group_state.num_agents() {
+            let behavior_ids = group_state.behavior_ids(i_agent).to_vec();
+            let agent_context = group_context.agent(i_agent);
+            let mut agent_state = group_state.agent(i_agent);
 
-            let i_unexecuted = agent_state.__i_behavior()?[0];
-            let behavior_ids = &agent_state.__behaviors()?[0];
-            for i_behavior in i_unexecuted..behavior_ids.len() {
-                agent_state.__i_behavior_set(vec![i_behavior]);
-                let behavior_id = behavior_ids[i_behavior];
+            let mut i_behavior = *agent_state.behavior_index()? as usize;
+            while i_behavior < behavior_ids.len() {
+                agent_state.behavior_index_set(i_behavior as f64)?;
 
+                let behavior_id = behavior_ids[i_behavior];
                 let behavior = self
-                    .behavior_map
+                    .behaviors
                     .get(&behavior_id)
-                    .ok_or_else(|| Error::InvalidBehavior(behavior_id))?;
-                if behavior.language != Language::Rust {
-                    next_target = MessageTarget::from(behavior.language);
-                    break;
-                }
+                    .ok_or(Error::InvalidBehavior(behavior_id))?;
+                let function = match behavior.function {
+                    Some(function) => function,
+                    None => {
+                        // Multiple assignments are fine.
+                        next_target = MessageTarget::from(behavior.language);
+                        break;
+                    }
+                };
 
-                behavior.function(&mut agent_state, &agent_context)?;
-                // TODO: Postprocess direction, position and messages' `to` field.
+                match function(&mut agent_state, &agent_context) {
+                    Ok(()) => {}
+                    Err(Error::Simulation(error)) => {
+                        errors.push(RunnerError {
+                            message: Some(format!("Behavior error: {error}")),
+                            details: None,
+                            file_name: Some(behavior.name.clone()),
+                            line_number: None,
+                        });
+                        i_behavior = behavior_ids.len();
+                        break;
+                    }
+                    Err(error) => return Err(error),
+                }
+                i_behavior += 1;
             }
+            agent_state.behavior_index_set(i_behavior as f64)?;
         }
-        Ok(next_target)
+        Ok((next_target, errors))
     }
 }
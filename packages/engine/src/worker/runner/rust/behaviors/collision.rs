@@ -1,57 +1,54 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
+use super::{error::SimulationError, Context, Result, SharedBehavior, State};
+use crate::hash_types::Vec3;
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/physics/collision.rs".into(),
-        name: "@hash/physics/collision.rs".into(),
-        shortnames: vec!["@hash/physics/collision.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("collision.rs.json").to_string()),
-    }
-}
+/// Distance up to which neighbors collide with the agent
+const MIN_DISTANCE: f64 = 1.0;
 
-/// Causes the agent to collide with other agents.
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let min_dist: f64 = 1.0;
-    let pos = state.position()?[0].ok_or("Agent must have position")?;
-    let vel = state.velocity()?[0];
-    let mass = state.mass()?[0];
+/// Causes the agent to collide with its neighbors.
+pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
+    let position = state
+        .position()?
+        .ok_or_else(|| SimulationError::from("Expected position to exist on agent"))?;
+    let velocity = *state.velocity()?;
+    let mass = *state.mass()?;
 
     // TODO: access globals to determine what the % elasticity of the collision should be
-    let epsilon: f64 = 1.0;
-
-    let mut dv = Vec3(0.0, 0.0, 0.0);
-    let neighbors = context.neighbors(0)?;
-    let neighbors_pos = neighbors
-        .iter()
-        .filter(|&n| n.position().is_some())
-        .filter(|&n| (n.position().unwrap() - pos).magnitude() <= min_dist);
-
-    for neighbor in neighbors_pos {
-        let dir = neighbor.position().unwrap() - pos;
-
-        let n_vel: Vec3 = neighbor.get_custom_or("velocity", Vec3(0.0, 0.0, 0.0))?;
-
-        // Check if agent is actually moving towards neighbor or vice versa
-        // Dot product of velocity and direction to neighbor is positive
-        if (vel.dot(dir) <= 0.0) && (n_vel.dot(dir) >= 0.0) {
+    let epsilon = 1.0;
+
+    let mut dv = Vec3::origin();
+    for neighbor in context.neighbors()? {
+        let direction = match neighbor.position()? {
+            Some(neighbor_position) => neighbor_position - position,
+            None => continue,
+        };
+        if direction.magnitude() > MIN_DISTANCE {
             continue;
         }
 
-        let n_mass: f64 = neighbor.get_custom_or("mass", f64::INFINITY)?;
-
-        // Calculate normalized direction of reflection
-        let norm = dir.norm();
-
-        // Calculate impulse
-        let numer = (epsilon + 1.0) * norm.dot(vel - n_vel);
-        let denom = (1.0 / n_mass) + (1.0 / mass);
-        let j = numer / denom;
+        // Check if the agent is actually moving towards the neighbor or vice versa, i.e. whether
+        // the dot product of either velocity and the direction to the neighbor is positive
+        let neighbor_velocity = neighbor.velocity()?;
+        if velocity.dot(direction) <= 0.0 && neighbor_velocity.dot(direction) >= 0.0 {
+            continue;
+        }
 
-        dv += norm * j / mass;
+        // Calculate the impulse along the normalized direction of reflection
+        let norm = direction.norm();
+        let numer = (epsilon + 1.0) * norm.dot(velocity - neighbor_velocity);
+        let denom = (1.0 / neighbor.mass()?) + (1.0 / mass);
+        dv += norm * (numer / denom) / mass;
     }
 
-    state.velocity_set(vec![vel - dv])?;
+    state.velocity_set(velocity - dv)?;
     Ok(())
 }
+
+pub fn get_named_behavior() -> SharedBehavior {
+    SharedBehavior {
+        id: "@hash/physics/collision.rs".into(),
+        name: "@hash/physics/collision.rs".into(),
+        shortnames: vec!["@hash/physics/collision.rs".into()],
+        behavior_src: None,
+        behavior_keys_src: Some(include_str!("collision.rs.json").to_string()),
+    }
+}
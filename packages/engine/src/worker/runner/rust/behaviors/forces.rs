@@ -1,25 +1,25 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
+use super::{
+    accessors::field_or_property, error::SimulationError, Context, Result, SharedBehavior, State,
+};
+use crate::hash_types::Vec3;
 
-/// Runs a semi-implicit Euler integration to calculate the change in velocity and
-/// position, based on the the current forces acting on the agent.
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let dt: f64 = state
-        .get_value("dt")
-        .map(|v| v.as_f64().unwrap())
-        .unwrap_or(0.01);
-    let mass = state.mass()?[0];
-    let force = &mut state.force_mut()?[0];
-    let v = state.velocity_mut()?;
+/// Runs a semi-implicit Euler integration to calculate the change in velocity and position, based
+/// on the forces currently acting on the agent, which are reset afterwards.
+pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
+    let dt = field_or_property(&None, context.globals().get("dt"), 0.01)?;
 
-    let dv = *force * (dt / mass);
-    v[0] += dv;
+    let mass = *state.mass()?;
+    let force = *state.force()?;
+    let velocity = *state.velocity()? + force * (dt / mass);
+    state.velocity_set(velocity)?;
 
-    let dp = v * dt;
-    let pos = state.position_mut()?[0].as_mut().ok_or("Agent must have a position")?;
-    *pos += dp;
+    let position = state
+        .position_mut()?
+        .as_mut()
+        .ok_or_else(|| SimulationError::from("Expected position to exist on agent"))?;
+    *position += velocity * dt;
 
-    *force = Vec3(0.0, 0.0, 0.0);
+    state.force_set(Vec3::origin())?;
     Ok(())
 }
 
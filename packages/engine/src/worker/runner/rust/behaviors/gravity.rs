@@ -1,21 +1,19 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
+use super::{
+    accessors::field_or_property, error::SimulationError, Context, Result, SharedBehavior, State,
+};
+use crate::hash_types::Vec3;
 
-/// Adds gravity to the forces acting on the agent. Won't cause an agent to fall into the ground
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let pos = state.position()?[0].ok_or("Agent must have position")?;
-    if pos[2] < 0.0 {
+/// Adds gravity to the forces acting on the agent. Won't cause an agent to fall into the ground.
+pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
+    let position = state
+        .position()?
+        .ok_or_else(|| SimulationError::from("Expected position to exist on agent"))?;
+    if position.z() < 0.0 {
         return Ok(());
     }
 
-    let g: f64 = state
-        .get_value("gravity")
-        .map(|v| v.as_f64().unwrap())
-        .unwrap_or(9.81);
-    let g_force = Vec3(0.0, 0.0, -g);
-
-    let force = &mut state.force_mut()?[0];
-    *force += g_force;
+    let gravity = field_or_property(state.gravity()?, context.globals().get("gravity"), 9.81)?;
+    *state.force_mut()? += Vec3(0.0, 0.0, -gravity);
     Ok(())
 }
 
@@ -1,426 +1,156 @@
+//! Built-in `@hash/*.rs` behaviors, which the Rust runner executes natively on the agent batches.
+//!
+//! Every column a built-in behavior uses is loaded into a [`NativeColumn`] of [`NativeState`]
+//! once per group and its accessors are generated with the [`accessors!`] macro.
 #[macro_use]
 pub mod accessors;
 pub mod error;
 
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-};
+pub mod age;
+pub mod collision;
+pub mod conway;
+pub mod counter;
+pub mod diffusion;
+pub mod forces;
+pub mod gravity;
+pub mod move_in_direction;
+pub mod orient_toward_value;
+pub mod physics;
+pub mod random_away_movement;
+pub mod random_movement;
+pub mod spring;
+pub mod viral_spread;
+// TODO: Port the remaining built-ins once the columnar state supports outbound messages and the
+//       behaviors column, which they need: create_agents, create_grids, create_scatters,
+//       create_stacks, decay, remove_self and reproduce. Their sources are kept unregistered.
+
+use std::collections::HashMap;
 
-use accessors::{Accessors, OptionNativeColumnExt};
+use arrow::{array::FixedSizeBinaryArray, record_batch::RecordBatch};
 
+use self::accessors::Accessors;
 use super::{
-    behavior_execution::BehaviorId,
     context::AgentContext,
     error::{Error, Result},
     neighbor::Neighbor,
-    state::{AgentState, GroupState},
+    state::AgentState,
 };
 use crate::{
-    datastore::batch::AgentBatch, experiment::SharedBehavior, hash_types::Vec3,
-    worker::runner::rust::Column,
+    datastore::batch::change::ArrayChange,
+    experiment::SharedBehavior,
+    hash_types::{state::AgentStateField, Vec3},
 };
 
-// TODO: Change Rust behaviors to make these type aliases unnecessary.
 type State<'s> = AgentState<'s>;
 type Context<'c> = AgentContext<'c>;
 
-// Engine:
-
-accessors!(
-    usize, // TODO: Correct type?
-    BehaviorIndexColumn,
-    __i_behavior,
-    __i_behavior_set,
-    __i_behavior_mut,
-    __i_behavior_load,
-    __i_behavior_commit
-);
+pub type BehaviorFn = fn(&mut AgentState<'_>, &AgentContext<'_>) -> Result<()>;
 
-accessors!(
-    Vec<BehaviorId>,
-    BehaviorIdsColumn,
-    __behaviors,
-    __behaviors_set,
-    __behaviors_mut,
-    __behaviors_load,
-    __behaviors_commit
-);
-
-accessors!(
-    Vec<String>,
-    BehaviorsColumn,
-    behaviors,
-    behaviors_set,
-    behaviors_mut,
-    behaviors_load,
-    behaviors_commit
-);
-
-accessors!(
-    Option<Vec3>,
-    PositionColumn,
-    position,
-    position_set,
-    position_mut,
-    position_load,
-    position_commit
-);
-
-accessors!(
-    Option<Vec3>,
-    DirectionColumn,
-    direction,
-    direction_set,
-    direction_mut,
-    direction_load,
-    direction_commit
-);
+// Engine:
+accessors!(f64, behavior_index, behavior_index_set, behavior_index_mut);
+accessors!(Option<Vec3>, position, position_set, position_mut);
+accessors!(Option<Vec3>, direction, direction_set, direction_mut);
 
 // Behaviors:
-
-pub mod age;
-accessors!(
-    Option<f64>,
-    AgeColumn,
-    age,
-    age_set,
-    age_mut,
-    age_load,
-    age_commit
-);
-
-pub mod conway;
-accessors!(
-    bool,
-    AliveColumn,
-    alive,
-    alive_set,
-    alive_mut,
-    alive_load,
-    alive_commit
-);
-
-pub mod counter;
-accessors!(
-    Option<f64>,
-    CounterColumn,
-    counter,
-    counter_set,
-    counter_mut,
-    counter_load,
-    counter_commit
-);
+accessors!(Option<f64>, age, age_set, age_mut);
+accessors!(bool, alive, alive_set, alive_mut);
+accessors!(Option<f64>, counter, counter_set, counter_mut);
 accessors!(
     Option<f64>,
-    CounterIncrementColumn,
     counter_increment,
     counter_increment_set,
-    counter_increment_mut,
-    counter_increment_load,
-    counter_increment_commit
+    counter_increment_mut
 );
 accessors!(
     Option<f64>,
-    CounterResetAtColumn,
     counter_reset_at,
     counter_reset_at_set,
-    counter_reset_at_mut,
-    counter_reset_at_load,
-    counter_reset_at_commit
+    counter_reset_at_mut
 );
 accessors!(
     Option<f64>,
-    CounterResetToColumn,
     counter_reset_to,
     counter_reset_to_set,
-    counter_reset_to_mut,
-    counter_reset_to_load,
-    counter_reset_to_commit
-);
-
-pub mod create_agents;
-pub mod create_grids;
-pub mod create_scatters;
-pub mod create_stacks;
-accessors!(
-    Option<serde_json::Value>,
-    AgentsColumn,
-    agents,
-    agents_set,
-    agents_mut,
-    agents_load,
-    agents_commit
-);
-accessors!(
-    Option<serde_json::Value>,
-    GridTemplatesColumn,
-    grid_templates,
-    grid_templates_set,
-    grid_templates_mut,
-    grid_templates_load,
-    grid_templates_commit
-);
-accessors!(
-    Option<serde_json::Value>,
-    ScatterTemplatesColumn,
-    scatter_templates,
-    scatter_templates_set,
-    scatter_templates_mut,
-    scatter_templates_load,
-    scatter_templates_commit
-);
-accessors!(
-    Option<serde_json::Value>,
-    StackTemplatesColumn,
-    stack_templates,
-    stack_templates_set,
-    stack_templates_mut,
-    stack_templates_load,
-    stack_templates_commit
-);
-
-pub mod decay;
-accessors!(
-    bool,
-    DecayedColumn,
-    decayed,
-    decayed_set,
-    decayed_mut,
-    decayed_load,
-    decayed_commit
-);
-accessors!(
-    Option<f64>,
-    DecayChanceColumn,
-    decay_chance,
-    decay_chance_set,
-    decay_chance_mut,
-    decay_chance_load,
-    decay_chance_commit
-);
-accessors!(
-    Option<serde_json::Value>,
-    DecayEffectColumn,
-    decay_effect,
-    decay_effect_set,
-    decay_effect_mut,
-    decay_effect_load,
-    decay_effect_commit
-);
-
-// pub mod diffusion;
-accessors!(
-    Option<f64>,
-    DiffusionCoefColumn,
-    diffusion_coef,
-    diffusion_coef_set,
-    diffusion_coef_mut,
-    diffusion_coef_load,
-    diffusion_coef_commit
+    counter_reset_to_mut
 );
+accessors!(f64, diffusion_coef, diffusion_coef_set, diffusion_coef_mut);
 accessors!(
     Option<serde_json::Value>,
-    DiffusionTargetsColumn,
     diffusion_targets,
     diffusion_targets_set,
-    diffusion_targets_mut,
-    diffusion_targets_load,
-    diffusion_targets_commit
+    diffusion_targets_mut
 );
-
-pub mod move_in_direction;
-
-// pub mod orient_toward_value;
+accessors!(f64, mass, mass_set, mass_mut);
+accessors!(Vec3, velocity, velocity_set, velocity_mut);
+accessors!(Vec3, force, force_set, force_mut);
 accessors!(
-    Option<String>,
-    OrientTowardValueColumn,
-    orient_toward_value,
-    orient_toward_value_set,
-    orient_toward_value_mut,
-    orient_toward_value_load,
-    orient_toward_value_commit
+    Option<f64>,
+    infection_chance,
+    infection_chance_set,
+    infection_chance_mut
 );
 accessors!(
-    Option<bool>,
-    OrientTowardValueUphillColumn,
-    orient_toward_value_uphill,
-    orient_toward_value_uphill_set,
-    orient_toward_value_uphill_mut,
-    orient_toward_value_uphill_load,
-    orient_toward_value_uphill_commit
+    Option<f64>,
+    recovery_chance,
+    recovery_chance_set,
+    recovery_chance_mut
 );
 accessors!(
     Option<bool>,
-    OrientTowardValueCumulativeColumn,
-    orient_toward_value_cumulative,
-    orient_toward_value_cumulative_set,
-    orient_toward_value_cumulative_mut,
-    orient_toward_value_cumulative_load,
-    orient_toward_value_cumulative_commit
-);
-
-pub mod physics;
-accessors!(
-    f64,
-    MassColumn,
-    mass,
-    mass_set,
-    mass_mut,
-    mass_load,
-    mass_commit
-);
-accessors!(
-    Vec3,
-    VelocityColumn,
-    velocity,
-    velocity_set,
-    velocity_mut,
-    velocity_load,
-    velocity_commit
-);
-accessors!(
-    Vec3,
-    ForceColumn,
-    force,
-    force_set,
-    force_mut,
-    force_load,
-    force_commit
+    immunity_exists,
+    immunity_exists_set,
+    immunity_exists_mut
 );
-
-pub mod random_away_movement;
-
-pub mod random_movement;
+accessors!(Option<bool>, immune, immune_set, immune_mut);
+accessors!(Option<bool>, infected, infected_set, infected_mut);
+accessors!(Option<f64>, gravity, gravity_set, gravity_mut);
 accessors!(
     Option<f64>,
-    RandomMovementStepSizeColumn,
     random_movement_step_size,
     random_movement_step_size_set,
-    random_movement_step_size_mut,
-    random_movement_step_size_load,
-    random_movement_step_size_commit
+    random_movement_step_size_mut
 );
 accessors!(
     Option<f64>,
-    RandomMovementSeekMinNeighborsColumn,
     random_movement_seek_min_neighbors,
     random_movement_seek_min_neighbors_set,
-    random_movement_seek_min_neighbors_mut,
-    random_movement_seek_min_neighbors_load,
-    random_movement_seek_min_neighbors_commit
+    random_movement_seek_min_neighbors_mut
 );
 accessors!(
     Option<f64>,
-    RandomMovementSeekMaxNeighborsColumn,
     random_movement_seek_max_neighbors,
     random_movement_seek_max_neighbors_set,
-    random_movement_seek_max_neighbors_mut,
-    random_movement_seek_max_neighbors_load,
-    random_movement_seek_max_neighbors_commit
-);
-
-pub mod remove_self;
-
-pub mod reproduce;
-accessors!(
-    Option<f64>,
-    ReproductionRateColumn,
-    reproduction_rate,
-    reproduction_rate_set,
-    reproduction_rate_mut,
-    reproduction_rate_load,
-    reproduction_rate_commit
-);
-accessors!(
-    Option<serde_json::Value>,
-    ReproductionChildValuesColumn,
-    reproduction_child_values,
-    reproduction_child_values_set,
-    reproduction_child_values_mut,
-    reproduction_child_values_load,
-    reproduction_child_values_commit
+    random_movement_seek_max_neighbors_mut
 );
-
-// pub mod viral_spread;
 accessors!(
-    Option<f64>,
-    InfectionChanceColumn,
-    infection_chance,
-    infection_chance_set,
-    infection_chance_mut,
-    infection_chance_load,
-    infection_chance_commit
-);
-accessors!(
-    Option<f64>,
-    RecoveryChanceColumn,
-    recovery_chance,
-    recovery_chance_set,
-    recovery_chance_mut,
-    recovery_chance_load,
-    recovery_chance_commit
-);
-accessors!(
-    Option<bool>,
-    ImmunityExistsColumn,
-    immunity_exists,
-    immunity_exists_set,
-    immunity_exists_mut,
-    immunity_exists_load,
-    immunity_exists_commit
+    Option<String>,
+    orient_toward_value,
+    orient_toward_value_set,
+    orient_toward_value_mut
 );
 accessors!(
     Option<bool>,
-    ImmuneColumn,
-    immune,
-    immune_set,
-    immune_mut,
-    immune_load,
-    immune_commit
+    orient_toward_value_uphill,
+    orient_toward_value_uphill_set,
+    orient_toward_value_uphill_mut
 );
 accessors!(
     Option<bool>,
-    InfectedColumn,
-    infected,
-    infected_set,
-    infected_mut,
-    infected_load,
-    infected_commit
+    orient_toward_value_cumulative,
+    orient_toward_value_cumulative_set,
+    orient_toward_value_cumulative_mut
 );
+accessors!(Option<serde_json::Value>, springs, springs_set, springs_mut);
 
-pub mod collision;
-// pub mod forces;
-// pub mod gravity;
-// pub mod spring;
-
-pub use crate::datastore::arrow::util as arrow_util;
-pub const BEHAVIOR_NAMES: [(&str, &str, &str); 21] = [
+/// (short name, file name, full name)
+pub static BEHAVIOR_NAMES: [(&str, &str, &str); 14] = [
     ("age", "age.rs", "@hash/age/age.rs"),
+    ("collision", "collision.rs", "@hash/physics/collision.rs"),
     ("conway", "conway.rs", "@hash/conway/conway.rs"),
     ("counter", "counter.rs", "@hash/counter/counter.rs"),
-    (
-        "create_agents",
-        "create_agents.rs",
-        "@hash/create-agents/create_agents.rs",
-    ),
-    (
-        "create_grids",
-        "create_grids.rs",
-        "@hash/create-grids/create_grids.rs",
-    ),
-    (
-        "create_scatters",
-        "create_scatters.rs",
-        "@hash/create-scatters/create_scatters.rs",
-    ),
-    (
-        "create_stacks",
-        "create_stacks.rs",
-        "@hash/create-stacks/create_stacks.rs",
-    ),
-    ("decay", "decay.rs", "@hash/decay/decay.rs"),
     ("diffusion", "diffusion.rs", "@hash/diffusion/diffusion.rs"),
+    ("forces", "forces.rs", "@hash/physics/forces.rs"),
+    ("gravity", "gravity.rs", "@hash/physics/gravity.rs"),
     (
         "move_in_direction",
         "move_in_direction.rs",
@@ -429,438 +159,650 @@ pub const BEHAVIOR_NAMES: [(&str, &str, &str); 21] = [
     (
         "orient_toward_value",
         "orient_toward_value.rs",
-        "@hash/orient-toward-value/orient_toward_value.rs",
+        "@hash/orient_toward_value/orient_toward_value.rs",
     ),
     ("physics", "physics.rs", "@hash/physics/physics.rs"),
     (
         "random_away_movement",
         "random_away_movement.rs",
-        "@hash/random-away-movement/random_away_movement.rs",
+        "@hash/random_away_movement/random_away_movement.rs",
     ),
     (
         "random_movement",
         "random_movement.rs",
-        "@hash/random-movement/random_movement.rs",
+        "@hash/random_movement/random_movement.rs",
     ),
-    (
-        "remove_self",
-        "remove_self.rs",
-        "@hash/remove-self/remove_self.rs",
-    ),
-    ("reproduce", "reproduce.rs", "@hash/reproduce/reproduce.rs"),
+    ("spring", "spring.rs", "@hash/physics/spring.rs"),
     (
         "viral_spread",
         "viral_spread.rs",
         "@hash/viral-spread/viral_spread.rs",
     ),
-    ("collision", "collision.rs", "@hash/physics/collision.rs"),
-    ("forces", "forces.rs", "@hash/physics/forces.rs"),
-    ("gravity", "gravity.rs", "@hash/physics/gravity.rs"),
-    ("spring", "spring.rs", "@hash/physics/spring.rs"),
 ];
 
-#[derive(Debug, Clone, Default)]
+/// Agent columns which are always loaded, because the runner itself needs them.
+pub const ENGINE_COLUMNS: [&str; 3] = ["behavior_index", "position", "direction"];
+
+#[derive(Debug, Clone)]
 pub struct NativeColumn<T> {
     index: usize,
     data: Vec<T>,
     set: bool,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct NativeState {
-    // Engine:
-    __i_behavior: Option<NativeColumn<usize>>,
-    __behaviors: Option<NativeColumn<BehaviorId>>,
-    behaviors: Option<NativeColumn<Vec<String>>>,
-    position: Option<NativeColumn<Vec3>>,
-    direction: Option<NativeColumn<Vec3>>,
-    // Behaviors:
-    age: Option<NativeColumn<Option<f64>>>,
-    agents: Option<NativeColumn<Option<serde_json::Value>>>,
-    alive: Option<NativeColumn<bool>>,
-    counter: Option<NativeColumn<Option<f64>>>,
-    counter_increment: Option<NativeColumn<Option<f64>>>,
-    counter_reset_at: Option<NativeColumn<Option<f64>>>,
-    counter_reset_to: Option<NativeColumn<Option<f64>>>,
-    decay_chance: Option<NativeColumn<Option<f64>>>,
-    decay_effect: Option<NativeColumn<Option<serde_json::Value>>>,
-    decayed: Option<NativeColumn<bool>>,
-    diffusion_coef: Option<NativeColumn<Option<f64>>>,
-    diffusion_targets: Option<NativeColumn<Option<serde_json::Value>>>,
-    grid_templates: Option<NativeColumn<Option<serde_json::Value>>>,
-    scatter_templates: Option<NativeColumn<Option<serde_json::Value>>>,
-    stack_templates: Option<NativeColumn<Option<serde_json::Value>>>,
-    orient_toward_value: Option<NativeColumn<Option<String>>>,
-    orient_toward_value_uphill: Option<NativeColumn<Option<bool>>>,
-    orient_toward_value_cumulative: Option<NativeColumn<Option<bool>>>,
-    mass: Option<NativeColumn<f64>>,
-    velocity: Option<NativeColumn<Vec3>>,
-    force: Option<NativeColumn<Vec3>>,
-    random_movement_step_size: Option<NativeColumn<Option<f64>>>,
-    random_movement_seek_min_neighbors: Option<NativeColumn<Option<f64>>>,
-    random_movement_seek_max_neighbors: Option<NativeColumn<Option<f64>>>,
-    reproduction_rate: Option<NativeColumn<Option<f64>>>,
-    reproduction_child_values: Option<NativeColumn<Option<serde_json::Value>>>,
-    infection_chance: Option<NativeColumn<Option<f64>>>,
-    recovery_chance: Option<NativeColumn<Option<f64>>>,
-    immunity_exists: Option<NativeColumn<Option<bool>>>,
-    immune: Option<NativeColumn<Option<bool>>>,
-    infected: Option<NativeColumn<Option<bool>>>,
+impl<T: Accessors> NativeColumn<T> {
+    fn load(batch: &RecordBatch, index: usize, name: &'static str) -> Result<Self> {
+        Ok(Self {
+            index,
+            data: T::load(batch.column(index), name)?,
+            set: false,
+        })
+    }
+
+    /// Returns the column as a change to the agent batch if it was written to.
+    fn change(&self) -> Result<Option<ArrayChange>> {
+        if self.set {
+            T::as_change(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
 }
 
-macro_rules! match_native_column {
-    ($column:expr, $index:ident, $state:ident, $batch:ident, $($name:ident),*) => {
-        match $column.as_ref() {
-            $(
-                stringify!($name) => {
-                    $state.$name = Some($name($index, $batch)?)
+macro_rules! native_state {
+    ($($name:ident: $native_type:ty),* $(,)?) => {
+        /// The columns of a group's agent batch which are used by the group's Rust behaviors.
+        pub struct NativeState {
+            batch: RecordBatch,
+            $($name: Option<NativeColumn<$native_type>>,)*
+            /// Number fields which are accessed by name, e.g. the targets of `diffusion.rs`.
+            numbers: HashMap<String, NativeColumn<Option<f64>>>,
+        }
+
+        impl NativeState {
+            pub fn new(batch: RecordBatch) -> Self {
+                Self {
+                    batch,
+                    $($name: None,)*
+                    numbers: HashMap::new(),
                 }
-            ),*
-            _ => return Err(Error::InvalidRustColumn($column.to_string()))
+            }
+
+            pub fn load_column(&mut self, name: &str) -> Result<()> {
+                let index = self.column_index(name)?;
+                match name {
+                    $(stringify!($name) => {
+                        self.$name = Some(NativeColumn::load(
+                            &self.batch,
+                            index,
+                            stringify!($name),
+                        )?);
+                    })*
+                    _ => return Err(Error::InvalidRustColumn(name.to_string())),
+                }
+                Ok(())
+            }
+
+            /// Returns all columns which were written to as changes to the agent batch.
+            pub fn changes(&self) -> Result<Vec<ArrayChange>> {
+                let mut changes = Vec::new();
+                $(
+                    if let Some(column) = &self.$name {
+                        changes.extend(column.change()?);
+                    }
+                )*
+                for column in self.numbers.values() {
+                    changes.extend(column.change()?);
+                }
+                Ok(changes)
+            }
         }
     };
 }
 
+native_state!(
+    // Engine:
+    behavior_index: f64,
+    position: Option<Vec3>,
+    direction: Option<Vec3>,
+    // Behaviors:
+    age: Option<f64>,
+    alive: bool,
+    counter: Option<f64>,
+    counter_increment: Option<f64>,
+    counter_reset_at: Option<f64>,
+    counter_reset_to: Option<f64>,
+    diffusion_coef: f64,
+    diffusion_targets: Option<serde_json::Value>,
+    mass: f64,
+    velocity: Vec3,
+    force: Vec3,
+    infection_chance: Option<f64>,
+    recovery_chance: Option<f64>,
+    immunity_exists: Option<bool>,
+    immune: Option<bool>,
+    infected: Option<bool>,
+    gravity: Option<f64>,
+    random_movement_step_size: Option<f64>,
+    random_movement_seek_min_neighbors: Option<f64>,
+    random_movement_seek_max_neighbors: Option<f64>,
+    orient_toward_value: Option<String>,
+    orient_toward_value_uphill: Option<bool>,
+    orient_toward_value_cumulative: Option<bool>,
+    springs: Option<serde_json::Value>,
+);
+
 impl NativeState {
-    pub fn from_column_set(
-        columns: &HashSet<String>,
-        schema: &Arc<arrow::datatypes::Schema>,
-        agent_batch: &AgentBatch,
-    ) -> Result<NativeState> {
-        let mut state = NativeState::default();
-        for column in columns {
-            let index = schema
-                .index_of(column)
-                .map_err(|_| Error::InvalidRustColumn(column.to_string()))?;
-
-            match_native_column!(
-                column,
-                index,
-                state,
-                agent_batch,
-                // All column names:
-                age,
-                agents,
-                alive,
-                counter,
-                counter_increment,
-                counter_reset_at,
-                counter_reset_to,
-                decay_chance,
-                decay_effect,
-                decayed,
-                diffusion_coef,
-                diffusion_targets,
-                grid_templates,
-                scatter_templates,
-                stack_templates,
-                orient_toward_value,
-                orient_toward_value_uphill,
-                orient_toward_value_cumulative,
-                mass,
-                velocity,
-                force,
-                random_movement_step_size,
-                random_movement_seek_min_neighbors,
-                random_movement_seek_max_neighbors,
-                reproduction_rate,
-                reproduction_child_values,
-                infection_chance,
-                recovery_chance,
-                immunity_exists,
-                immune,
-                infected
-            )
+    fn column_index(&self, name: &str) -> Result<usize> {
+        self.batch
+            .schema()
+            .index_of(name)
+            .map_err(|_| Error::InvalidRustColumn(name.to_string()))
+    }
+
+    /// Returns the column of the number field `name`, loading it on first access.
+    pub fn number_column(&mut self, name: &str) -> Result<&mut NativeColumn<Option<f64>>> {
+        if !self.numbers.contains_key(name) {
+            let index = self.column_index(name)?;
+            let column = NativeColumn::load(&self.batch, index, "number field")?;
+            self.numbers.insert(name.to_string(), column);
         }
-        Ok(state)
+        Ok(self
+            .numbers
+            .get_mut(name)
+            .expect("Number column was inserted above"))
     }
 }
 
+fn find_built_in(name: &str) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    BEHAVIOR_NAMES
+        .iter()
+        .find(|(short_name, file_name, full_name)| {
+            name == *short_name
+                || name == *full_name
+                || name == *file_name
+                || name == format!("@hash/{}", file_name)
+        })
+}
+
 pub fn is_built_in(name: &str) -> bool {
-    for (short_name, file_name, full_name) in BEHAVIOR_NAMES.iter() {
-        if name == *short_name
-            || name == *full_name
-            || name == *file_name
-            || name == format!("@hash/{}", file_name)
-        {
-            return true;
-        }
+    find_built_in(name).is_some()
+}
+
+pub fn get_built_in(name: &str) -> Result<BehaviorFn> {
+    let (_, file_name, _) =
+        find_built_in(name).ok_or_else(|| Error::InvalidRustBuiltIn(name.to_string()))?;
+    match *file_name {
+        "age.rs" => Ok(age::behavior),
+        "collision.rs" => Ok(collision::behavior),
+        "conway.rs" => Ok(conway::behavior),
+        "counter.rs" => Ok(counter::behavior),
+        "diffusion.rs" => Ok(diffusion::behavior),
+        "forces.rs" => Ok(forces::behavior),
+        "gravity.rs" => Ok(gravity::behavior),
+        "move_in_direction.rs" => Ok(move_in_direction::behavior),
+        "orient_toward_value.rs" => Ok(orient_toward_value::behavior),
+        "physics.rs" => Ok(physics::behavior),
+        "random_away_movement.rs" => Ok(random_away_movement::behavior),
+        "random_movement.rs" => Ok(random_movement::behavior),
+        "spring.rs" => Ok(spring::behavior),
+        "viral_spread.rs" => Ok(viral_spread::behavior),
+        _ => Err(Error::InvalidRustBuiltIn(name.to_string())),
     }
-    return false;
 }
 
-pub fn get_full_name(name: &str) -> Result<&str> {
-    for (short_name, file_name, full_name) in BEHAVIOR_NAMES.iter() {
-        if name == *short_name
-            || name == *full_name
-            || name == *file_name
-            || name == format!("@hash/{}", file_name)
-        {
-            return Ok(full_name);
-        }
+pub fn get_named_behavior(name: &str) -> Result<SharedBehavior> {
+    let (_, file_name, _) =
+        find_built_in(name).ok_or_else(|| Error::InvalidRustBuiltIn(name.to_string()))?;
+    match *file_name {
+        "age.rs" => Ok(age::get_named_behavior()),
+        "collision.rs" => Ok(collision::get_named_behavior()),
+        "conway.rs" => Ok(conway::get_named_behavior()),
+        "counter.rs" => Ok(counter::get_named_behavior()),
+        "diffusion.rs" => Ok(diffusion::get_named_behavior()),
+        "forces.rs" => Ok(forces::get_named_behavior()),
+        "gravity.rs" => Ok(gravity::get_named_behavior()),
+        "move_in_direction.rs" => Ok(move_in_direction::get_named_behavior()),
+        "orient_toward_value.rs" => Ok(orient_toward_value::get_named_behavior()),
+        "physics.rs" => Ok(physics::get_named_behavior()),
+        "random_away_movement.rs" => Ok(random_away_movement::get_named_behavior()),
+        "random_movement.rs" => Ok(random_movement::get_named_behavior()),
+        "spring.rs" => Ok(spring::get_named_behavior()),
+        "viral_spread.rs" => Ok(viral_spread::get_named_behavior()),
+        _ => Err(Error::InvalidRustBuiltIn(name.to_string())),
     }
-    Err(Error::InvalidRustBuiltIn(name.to_string()))
 }
 
-pub fn get_built_in(
-    name: &str,
-) -> Result<Box<dyn Fn(&mut AgentState, &AgentContext) -> Result<()> + Send + Sync + 'static>> {
-    for (short_name, file_name, full_name) in BEHAVIOR_NAMES.iter() {
-        if name == *short_name
-            || name == *full_name
-            || name == *file_name
-            || name == format!("@hash/{}", file_name)
-        {
-            match *file_name {
-                "age.rs" => return Ok(Box::new(age::behavior)),
-                "conway.rs" => return Ok(Box::new(conway::behavior)),
-                "counter.rs" => return Ok(Box::new(counter::behavior)),
-                "create_agents.rs" => return Ok(Box::new(create_agents::behavior)),
-                "create_grid.rs" => return Ok(Box::new(create_grids::behavior)),
-                "create_scatter.rs" => return Ok(Box::new(create_scatters::behavior)),
-                "create_stacks.rs" => return Ok(Box::new(create_stacks::behavior)),
-                "decay.rs" => return Ok(Box::new(decay::behavior)),
-                // "diffusion.rs" => return Ok(Box::new(diffusion::behavior)),
-                "move_in_direction.rs" => return Ok(Box::new(move_in_direction::behavior)),
-                // "orient_toward_value.rs" => return Ok(Box::new(orient_toward_value::behavior)),
-                "physics.rs" => return Ok(Box::new(physics::behavior)),
-                "random_away_movement.rs" => return Ok(Box::new(random_away_movement::behavior)),
-                "random_movement.rs" => return Ok(Box::new(random_movement::behavior)),
-                "remove_self.rs" => return Ok(Box::new(remove_self::behavior)),
-                "reproduce.rs" => return Ok(Box::new(reproduce::behavior)),
-                // "viral_spread.rs" => return Ok(Box::new(viral_spread::behavior)),
-                // "collision.rs" => return Ok(Box::new(collision::behavior)),
-                // "forces.rs" => return Ok(Box::new(forces::behavior)),
-                // "gravity.rs" => return Ok(Box::new(gravity::behavior)),
-                // TODO: "spring.rs" => return Ok(Box::new(spring::behavior)),
-                _ => return Err(Error::InvalidRustBuiltIn(name.to_string())),
-            }
-        }
+/// Access to number fields by name, for behaviors whose fields are configured by the agent.
+impl<'s> AgentState<'s> {
+    pub fn number(&mut self, name: &str) -> Result<Option<f64>> {
+        let index_in_group = self.index_in_group;
+        Ok(self.inner.number_column(name)?.data[index_in_group])
+    }
+
+    pub fn number_set(&mut self, name: &str, value: Option<f64>) -> Result<()> {
+        let index_in_group = self.index_in_group;
+        let column = self.inner.number_column(name)?;
+        column.set = true;
+        column.data[index_in_group] = value;
+        Ok(())
     }
-    return Err(Error::InvalidRustBuiltIn(name.to_string()));
 }
 
-pub fn get_built_in_columns(name: &str) -> Result<HashMap<String, Box<dyn Column>>> {
-    let mut map = HashMap::new();
-
-    map.insert(
-        "behaviors".to_string(),
-        Box::new(BehaviorsColumn {}) as Box<dyn Column>,
-    );
-    map.insert(
-        "position".to_string(),
-        Box::new(PositionColumn {}) as Box<dyn Column>,
-    );
-    map.insert(
-        "direction".to_string(),
-        Box::new(DirectionColumn {}) as Box<dyn Column>,
-    );
-
-    for (short_name, file_name, full_name) in BEHAVIOR_NAMES.iter() {
-        if name == *short_name
-            || name == *full_name
-            || name == *file_name
-            || name == format!("@hash/{}", file_name)
-        {
-            match *file_name {
-                "age.rs" => {
-                    map.insert("age".to_string(), Box::new(AgeColumn {}) as Box<dyn Column>);
-                }
-                "conway.rs" => {
-                    map.insert(
-                        "alive".to_string(),
-                        Box::new(AliveColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "counter.rs" => {
-                    map.insert(
-                        "counter".to_string(),
-                        Box::new(CounterColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "counter_increment".to_string(),
-                        Box::new(CounterIncrementColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "counter_reset_at".to_string(),
-                        Box::new(CounterResetAtColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "counter_reset_to".to_string(),
-                        Box::new(CounterResetToColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "create_agents.rs" => {
-                    map.insert(
-                        "agents".to_string(),
-                        Box::new(AgentsColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "create_grid.rs" => {
-                    map.insert(
-                        "agents".to_string(),
-                        Box::new(AgentsColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "grid_templates".to_string(),
-                        Box::new(GridTemplatesColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "create_scatter.rs" => {
-                    map.insert(
-                        "agents".to_string(),
-                        Box::new(AgentsColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "scatter_templates".to_string(),
-                        Box::new(ScatterTemplatesColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "create_stack.rs" => {
-                    map.insert(
-                        "agents".to_string(),
-                        Box::new(AgentsColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "stack_templates".to_string(),
-                        Box::new(StackTemplatesColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "decay.rs" => {
-                    map.insert(
-                        "decay_chance".to_string(),
-                        Box::new(DecayChanceColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "decay_effect".to_string(),
-                        Box::new(DecayEffectColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "decayed".to_string(),
-                        Box::new(DecayedColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "diffusion.rs" => {
-                    map.insert(
-                        "diffusion_coef".to_string(),
-                        Box::new(DiffusionCoefColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "diffusion_targets".to_string(),
-                        Box::new(DiffusionTargetsColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "orient_toward_value.rs" => {
-                    map.insert(
-                        "orient_toward_value".to_string(),
-                        Box::new(OrientTowardValueColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "orient_toward_value_uphill".to_string(),
-                        Box::new(OrientTowardValueUphillColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "orient_toward_value_cumulative".to_string(),
-                        Box::new(OrientTowardValueCumulativeColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "physics.rs" => {
-                    map.insert(
-                        "force".to_string(),
-                        Box::new(ForceColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "mass".to_string(),
-                        Box::new(MassColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "velocity".to_string(),
-                        Box::new(VelocityColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "random_away_movement.rs" => {}
-                "random_movement.rs" => {
-                    map.insert(
-                        "random_movement_step_size".to_string(),
-                        Box::new(RandomMovementStepSizeColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "random_movement_seek_min_neighbors".to_string(),
-                        Box::new(RandomMovementSeekMinNeighborsColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "random_movement_seek_max_neighbors".to_string(),
-                        Box::new(RandomMovementSeekMaxNeighborsColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "reproduce.rs" => {
-                    map.insert(
-                        "reproduction_rate".to_string(),
-                        Box::new(ReproductionRateColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "reproduction_child_values".to_string(),
-                        Box::new(ReproductionChildValuesColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "viral_spread.rs" => {
-                    map.insert(
-                        "infection_chance".to_string(),
-                        Box::new(InfectionChanceColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "recovery_chance".to_string(),
-                        Box::new(RecoveryChanceColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "immunity_exists".to_string(),
-                        Box::new(ImmunityExistsColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "immune".to_string(),
-                        Box::new(ImmuneColumn {}) as Box<dyn Column>,
-                    );
-                    map.insert(
-                        "infected".to_string(),
-                        Box::new(InfectedColumn {}) as Box<dyn Column>,
-                    );
-                }
-                "move_in_direction.rs" | "remove_self.rs" => {}
-                _ => return Err(Error::InvalidRustBuiltIn(name.to_string())),
+impl<'c> Neighbor<'c> {
+    pub fn number(&self, name: &str) -> Result<Option<f64>> {
+        Accessors::load_elem(self.column(name)?, "number field", self.index_in_group())
+    }
+
+    /// The neighbor's agent ID, formatted like agent IDs in JSON.
+    pub fn agent_id(&self) -> Result<String> {
+        let name = AgentStateField::AgentId.name();
+        let column = self
+            .column(name)?
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .ok_or_else(|| Error::InvalidArrowDowncast(name.to_string()))?;
+        let id = uuid::Uuid::from_slice(column.value(self.index_in_group()))
+            .map_err(|err| Error::from(format!("Invalid agent id: {err}")))?;
+        Ok(id.to_hyphenated().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{
+            Array, ArrayRef, BooleanArray, FixedSizeListBuilder, Float64Array, Float64Builder,
+            ListBuilder, StringArray, UInt32Builder,
+        },
+        datatypes::{Field, Schema},
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        config::Globals,
+        datastore::{
+            batch::AgentBatch,
+            schema::{
+                state::AgentSchema, FieldScope, FieldSource, FieldSpecMap, FieldType,
+                FieldTypeVariant, RootFieldSpecCreator,
+            },
+        },
+        hash_types::Agent,
+        simulation::package::creator::get_base_agent_fields,
+        worker::runner::rust::context::{Datasets, GroupContext},
+    };
+
+    fn numbers(values: &[Option<f64>]) -> ArrayRef {
+        Arc::new(Float64Array::from(values.to_vec()))
+    }
+
+    fn vec3s(values: &[Option<Vec3>]) -> ArrayRef {
+        let mut builder = FixedSizeListBuilder::new(Float64Builder::new(values.len() * 3), 3);
+        for value in values {
+            for coord in <[f64; 3]>::from(value.unwrap_or_else(Vec3::origin)) {
+                builder.values().append_value(coord).unwrap();
             }
-            return Ok(map);
+            builder.append(value.is_some()).unwrap();
         }
+        Arc::new(builder.finish())
     }
-    return Err(Error::InvalidRustBuiltIn(name.to_string()));
-}
 
-pub fn get_named_behavior(name: &str) -> Result<SharedBehavior> {
-    for (short_name, file_name, full_name) in BEHAVIOR_NAMES.iter() {
-        if name == *short_name
-            || name == *full_name
-            || name == *file_name
-            || name == format!("@hash/{}", file_name)
-        {
-            match *file_name {
-                "age.rs" => return Ok(age::get_named_behavior()),
-                "conway.rs" => return Ok(conway::get_named_behavior()),
-                "counter.rs" => return Ok(counter::get_named_behavior()),
-                "create_agents.rs" => return Ok(create_agents::get_named_behavior()),
-                "create_grids.rs" => return Ok(create_grids::get_named_behavior()),
-                "create_scatters.rs" => return Ok(create_scatters::get_named_behavior()),
-                "create_stacks.rs" => return Ok(create_stacks::get_named_behavior()),
-                "decay.rs" => return Ok(decay::get_named_behavior()),
-                // "diffusion.rs" => return Ok(diffusion::get_named_behavior()),
-                "move_in_direction.rs" => return Ok(move_in_direction::get_named_behavior()),
-                //"orient_toward_value.rs" => return Ok(orient_toward_value::get_named_behavior()),
-                "random_away_movement.rs" => return Ok(random_away_movement::get_named_behavior()),
-                "random_movement.rs" => return Ok(random_movement::get_named_behavior()),
-                "remove_self.rs" => return Ok(remove_self::get_named_behavior()),
-                "reproduce.rs" => return Ok(reproduce::get_named_behavior()),
-                // "viral_spread.rs" => return Ok(viral_spread::get_named_behavior()),
-                _ => return Err(Error::InvalidRustBuiltIn(name.to_string())),
+    fn booleans(values: &[Option<bool>]) -> ArrayRef {
+        Arc::new(BooleanArray::from(values.to_vec()))
+    }
+
+    fn strings(values: &[Option<&str>]) -> ArrayRef {
+        Arc::new(StringArray::from(values.to_vec()))
+    }
+
+    /// Runs `behavior` on every agent of a group with the `columns` and returns the state of the
+    /// group afterwards.
+    ///
+    /// If `neighbors` is given, it lists the indices of each agent's neighbors in `snapshot`.
+    fn run_on_columns(
+        behavior: BehaviorFn,
+        columns: Vec<(&str, ArrayRef)>,
+        globals: serde_json::Value,
+        neighbors: Option<Vec<Vec<u32>>>,
+        snapshot: &[&AgentBatch],
+    ) -> NativeState {
+        let schema = Schema::new(
+            columns
+                .iter()
+                .map(|(name, array)| Field::new(name, array.data_type().clone(), true))
+                .collect(),
+        );
+        let arrays = columns.iter().map(|(_, array)| array.clone()).collect();
+        let batch = RecordBatch::try_new(Arc::new(schema), arrays).unwrap();
+        let context_batch = neighbors
+            .as_deref()
+            .map_or_else(|| batch.clone(), neighbors_batch);
+
+        let mut native = NativeState::new(batch.clone());
+        for (name, _) in &columns {
+            match native.load_column(name) {
+                // Number fields accessed by name are loaded on first access
+                Ok(()) | Err(Error::InvalidRustColumn(_)) => {}
+                Err(err) => panic!("Couldn't load column {name}: {err}"),
+            }
+        }
+        let globals = Globals(globals);
+        let datasets = Datasets::default();
+        let group_context =
+            GroupContext::new(&globals, &datasets, 0, &context_batch, snapshot, 0, 0);
+        for index_in_group in 0..batch.num_rows() {
+            let mut state = AgentState {
+                inner: &mut native,
+                index_in_group,
             };
+            behavior(&mut state, &group_context.agent(index_in_group)).unwrap();
+        }
+        native
+    }
+
+    /// Runs `behavior` on every agent of a group with the number `columns` and returns the state
+    /// of the group afterwards.
+    fn run_on_numbers(behavior: BehaviorFn, columns: &[(&str, Vec<Option<f64>>)]) -> NativeState {
+        let columns = columns
+            .iter()
+            .map(|(name, values)| (*name, numbers(values)))
+            .collect();
+        run_on_columns(behavior, columns, json!({}), None, &[])
+    }
+
+    /// A context batch whose `neighbors` column refers to the agents of the first group of the
+    /// snapshot.
+    fn neighbors_batch(neighbors: &[Vec<u32>]) -> RecordBatch {
+        let mut builder = ListBuilder::new(FixedSizeListBuilder::new(UInt32Builder::new(0), 2));
+        for agent_neighbors in neighbors {
+            for index_in_group in agent_neighbors.iter() {
+                let location = builder.values();
+                location.values().append_value(0).unwrap();
+                location.values().append_value(*index_in_group).unwrap();
+                location.append(true).unwrap();
+            }
+            builder.append(true).unwrap();
+        }
+        let neighbors = builder.finish();
+        let schema = Schema::new(vec![Field::new(
+            "neighbors",
+            neighbors.data_type().clone(),
+            false,
+        )]);
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(neighbors)]).unwrap()
+    }
+
+    fn neighbor_agent(position: Vec3, numbers: &[(&str, f64)]) -> Agent {
+        let mut agent = Agent::empty();
+        agent.agent_id = uuid::Uuid::new_v4().to_string();
+        agent.position = Some(position);
+        agent.velocity = Some(Vec3::origin());
+        for (name, value) in numbers {
+            agent.set(name, *value).unwrap();
+        }
+        agent
+    }
+
+    /// A snapshot group of `agents`, which may have the number fields `number_fields`.
+    fn snapshot_group(agents: &[Agent], number_fields: &[&str]) -> AgentBatch {
+        let field_spec_creator = RootFieldSpecCreator::new(FieldSource::Engine);
+        let mut field_spec_map = FieldSpecMap::empty();
+        field_spec_map
+            .add_multiple(get_base_agent_fields().unwrap())
+            .unwrap();
+        for name in number_fields {
+            field_spec_map
+                .add(field_spec_creator.create(
+                    name.to_string(),
+                    FieldType::new(FieldTypeVariant::Number, true),
+                    FieldScope::Agent,
+                ))
+                .unwrap();
+        }
+        let schema = Arc::new(AgentSchema::new(field_spec_map).unwrap());
+        AgentBatch::from_agent_states(agents, &schema, &Arc::new(String::new())).unwrap()
+    }
+
+    fn agent(native: &mut NativeState, index_in_group: usize) -> AgentState<'_> {
+        AgentState {
+            inner: native,
+            index_in_group,
         }
     }
-    return Err(Error::InvalidRustBuiltIn(name.to_string()));
+
+    #[test]
+    fn age_starts_at_one_and_increments() {
+        let mut native = run_on_numbers(age::behavior, &[("age", vec![None, Some(4.0)])]);
+        assert_eq!(*agent(&mut native, 0).age().unwrap(), Some(1.0));
+        assert_eq!(*agent(&mut native, 1).age().unwrap(), Some(5.0));
+    }
+
+    #[test]
+    fn counter_increments_and_resets() {
+        let mut native = run_on_numbers(counter::behavior, &[
+            ("counter", vec![None, Some(2.0), Some(5.0)]),
+            ("counter_increment", vec![None, Some(3.0), Some(3.0)]),
+            ("counter_reset_at", vec![None, Some(5.0), Some(5.0)]),
+            ("counter_reset_to", vec![None, Some(-1.0), Some(-1.0)]),
+        ]);
+        assert_eq!(*agent(&mut native, 0).counter().unwrap(), Some(1.0));
+        assert_eq!(*agent(&mut native, 1).counter().unwrap(), Some(5.0));
+        assert_eq!(*agent(&mut native, 2).counter().unwrap(), Some(-1.0));
+    }
+
+    #[test]
+    fn only_written_columns_are_changed() {
+        let mut native = run_on_numbers(counter::behavior, &[
+            ("counter", vec![Some(1.0)]),
+            ("counter_increment", vec![Some(1.0)]),
+            ("counter_reset_at", vec![None]),
+            ("counter_reset_to", vec![None]),
+        ]);
+        native.load_column("age").unwrap_err();
+
+        let changes = native.changes().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].index, 0);
+    }
+
+    #[test]
+    fn forces_integrate_and_reset_force() {
+        let mut native = run_on_columns(
+            forces::behavior,
+            vec![
+                ("mass", numbers(&[Some(2.0)])),
+                ("force", vec3s(&[Some(Vec3(2.0, 0.0, -4.0))])),
+                ("velocity", vec3s(&[Some(Vec3(1.0, 0.0, 0.0))])),
+                ("position", vec3s(&[Some(Vec3::origin())])),
+            ],
+            json!({ "dt": 0.5 }),
+            None,
+            &[],
+        );
+        let state = agent(&mut native, 0);
+        assert_eq!(*state.velocity().unwrap(), Vec3(1.5, 0.0, -1.0));
+        assert_eq!(*state.position().unwrap(), Some(Vec3(0.75, 0.0, -0.5)));
+        assert_eq!(*state.force().unwrap(), Vec3::origin());
+    }
+
+    #[test]
+    fn gravity_pulls_agents_above_ground() {
+        let mut native = run_on_columns(
+            gravity::behavior,
+            vec![
+                ("gravity", numbers(&[None, Some(2.0), Some(2.0)])),
+                ("force", vec3s(&[Some(Vec3::origin()); 3])),
+                (
+                    "position",
+                    vec3s(&[
+                        Some(Vec3(0.0, 0.0, 1.0)),
+                        Some(Vec3(0.0, 0.0, 1.0)),
+                        Some(Vec3(0.0, 0.0, -1.0)),
+                    ]),
+                ),
+            ],
+            json!({ "gravity": 5.0 }),
+            None,
+            &[],
+        );
+        assert_eq!(
+            *agent(&mut native, 0).force().unwrap(),
+            Vec3(0.0, 0.0, -5.0)
+        );
+        assert_eq!(
+            *agent(&mut native, 1).force().unwrap(),
+            Vec3(0.0, 0.0, -2.0)
+        );
+        assert_eq!(*agent(&mut native, 2).force().unwrap(), Vec3::origin());
+    }
+
+    #[test]
+    fn random_movement_steps_until_satisfied() {
+        const NUM_AGENTS: usize = 20;
+        let snapshot = snapshot_group(&[neighbor_agent(Vec3::origin(), &[])], &[]);
+        // The last agent seeks at least one neighbor, which it has
+        let mut min_neighbors = [None; NUM_AGENTS];
+        min_neighbors[NUM_AGENTS - 1] = Some(1.0);
+        let mut neighbors = vec![vec![]; NUM_AGENTS];
+        neighbors[NUM_AGENTS - 1] = vec![0];
+        let mut native = run_on_columns(
+            random_movement::behavior,
+            vec![
+                (
+                    "random_movement_step_size",
+                    numbers(&[Some(2.0); NUM_AGENTS]),
+                ),
+                (
+                    "random_movement_seek_min_neighbors",
+                    numbers(&min_neighbors),
+                ),
+                (
+                    "random_movement_seek_max_neighbors",
+                    numbers(&[None; NUM_AGENTS]),
+                ),
+                ("position", vec3s(&[Some(Vec3(1.0, 1.0, 1.0)); NUM_AGENTS])),
+            ],
+            json!({}),
+            Some(neighbors),
+            &[&snapshot],
+        );
+        for index_in_group in 0..NUM_AGENTS - 1 {
+            let position = agent(&mut native, index_in_group)
+                .position()
+                .unwrap()
+                .unwrap();
+            assert!([-1.0, 1.0, 3.0].contains(&position.x()));
+            assert!([-1.0, 1.0, 3.0].contains(&position.y()));
+            assert_eq!(position.z(), 1.0);
+        }
+        assert_eq!(
+            *agent(&mut native, NUM_AGENTS - 1).position().unwrap(),
+            Some(Vec3(1.0, 1.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn random_away_movement_moves_away_from_neighbor() {
+        let snapshot = snapshot_group(&[neighbor_agent(Vec3(1.0, 2.0, 0.0), &[])], &[]);
+        let mut native = run_on_columns(
+            random_away_movement::behavior,
+            vec![("position", vec3s(&[Some(Vec3::origin())]))],
+            json!({}),
+            Some(vec![vec![0]]),
+            &[&snapshot],
+        );
+        assert_eq!(
+            *agent(&mut native, 0).position().unwrap(),
+            Some(Vec3(-1.0, -2.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn collision_transfers_velocity_to_neighbor() {
+        let snapshot = snapshot_group(&[neighbor_agent(Vec3(0.5, 0.0, 0.0), &[("mass", 1.0)])], &[
+            "mass",
+        ]);
+        let mut native = run_on_columns(
+            collision::behavior,
+            vec![
+                ("velocity", vec3s(&[Some(Vec3(1.0, 0.0, 0.0))])),
+                ("mass", numbers(&[Some(1.0)])),
+                ("position", vec3s(&[Some(Vec3::origin())])),
+            ],
+            json!({}),
+            Some(vec![vec![0]]),
+            &[&snapshot],
+        );
+        assert_eq!(*agent(&mut native, 0).velocity().unwrap(), Vec3::origin());
+    }
+
+    #[test]
+    fn spring_pulls_towards_connected_neighbor() {
+        let connected = "6e5a8b1e-5c8f-4d3a-9a7c-2f1e0d9c8b7a";
+        let mut connected_agent = neighbor_agent(Vec3(3.0, 0.0, 0.0), &[]);
+        connected_agent.agent_id = connected.to_string();
+        let snapshot = snapshot_group(
+            &[connected_agent, neighbor_agent(Vec3(0.0, 5.0, 0.0), &[])],
+            &[],
+        );
+        let springs = json!([{ "agent_id": connected, "length": 1.0, "k": 2.0 }]).to_string();
+        let mut native = run_on_columns(
+            spring::behavior,
+            vec![
+                ("springs", strings(&[Some(springs.as_str())])),
+                ("force", vec3s(&[Some(Vec3(0.0, 1.0, 0.0))])),
+                ("velocity", vec3s(&[Some(Vec3::origin())])),
+                ("position", vec3s(&[Some(Vec3::origin())])),
+            ],
+            json!({}),
+            Some(vec![vec![0, 1]]),
+            &[&snapshot],
+        );
+        assert_eq!(*agent(&mut native, 0).force().unwrap(), Vec3(4.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn orient_toward_value_points_to_best_neighbor() {
+        let snapshot = snapshot_group(
+            &[
+                neighbor_agent(Vec3(1.0, 0.0, 0.0), &[("food", 3.0)]),
+                neighbor_agent(Vec3(0.0, 1.0, 0.0), &[("food", 2.0)]),
+            ],
+            &["food"],
+        );
+        let mut native = run_on_columns(
+            orient_toward_value::behavior,
+            vec![
+                ("orient_toward_value", strings(&[Some("food"); 3])),
+                (
+                    "orient_toward_value_uphill",
+                    booleans(&[None, Some(false), None]),
+                ),
+                ("orient_toward_value_cumulative", booleans(&[None; 3])),
+                ("food", numbers(&[Some(1.0), Some(2.5), Some(10.0)])),
+                ("position", vec3s(&[Some(Vec3::origin()); 3])),
+                ("direction", vec3s(&[None; 3])),
+            ],
+            json!({}),
+            Some(vec![vec![0, 1]; 3]),
+            &[&snapshot],
+        );
+        assert_eq!(
+            *agent(&mut native, 0).direction().unwrap(),
+            Some(Vec3(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            *agent(&mut native, 1).direction().unwrap(),
+            Some(Vec3(0.0, 1.0, 0.0))
+        );
+        // No neighbor has more food, so the agent stops
+        assert_eq!(
+            *agent(&mut native, 2).direction().unwrap(),
+            Some(Vec3::origin())
+        );
+    }
 }
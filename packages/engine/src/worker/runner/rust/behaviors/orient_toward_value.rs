@@ -1,89 +1,67 @@
-use super::{Context, Error, Result, SharedBehavior, State};
-
-use std::collections::HashMap;
-
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let mut json_state = state.get_json_state()?;
-
-    for i in 0..state.num_agents() {
-        if !json_state[i]["orient_toward_value"].is_string() {
-            return Ok(());
-        }
-
-        let target = json_state[i]["orient_toward_value"]
-            .as_str()
-            .ok_or("not a string")?;
-
-        // True -> looking for max (greater) value
-        // False -> looking for min (smaller) value
-        let uphill = match json_state[i]["orient_toward_value_uphill"].as_bool() {
-            Some(value) => value,
-            None => true,
-        };
-
-        let cumulative = match json_state[i]["orient_toward_value_cumulative"].as_bool() {
+use std::collections::BTreeMap;
+
+use super::{error::SimulationError, Context, Result, SharedBehavior, State};
+use crate::hash_types::Vec3;
+
+/// Points the agent's direction towards the grid cell of its neighbors with the largest (or, if
+/// `orient_toward_value_uphill` is `false`, the smallest) value of the field named by
+/// `orient_toward_value`. The direction is cleared if no neighbor improves on the agent's value.
+pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
+    let target = match state.orient_toward_value()? {
+        Some(target) => target.clone(),
+        None => return Ok(()),
+    };
+    let uphill = state.orient_toward_value_uphill()?.unwrap_or(true);
+    let cumulative = state.orient_toward_value_cumulative()?.unwrap_or(false);
+    let value = match state.number(&target)? {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let position = state
+        .position()?
+        .ok_or_else(|| SimulationError::from("Expected position to exist on agent"))?;
+
+    // Ordered by cell, so ties are resolved the same way in every run
+    let mut cell_values = BTreeMap::new();
+    for neighbor in context.neighbors()? {
+        let neighbor_value = match neighbor.number(&target)? {
             Some(value) => value,
-            None => false,
+            None => continue,
         };
-
-        if let Some(target_value) = json_state[i].get_as_json(target)?.as_f64() {
-            let mut current_max = target_value;
-
-            let mut neighbor_map = HashMap::new();
-
-            for neighbor in context.neighbors(i)? {
-                if let Some(neighbor_value) = neighbor.get_custom_as_json(target)?.as_f64() {
-                    let position = neighbor
-                        .position()
-                        .ok_or("Neighbors should have position")?
-                        .to_grid();
-
-                    if neighbor_map.contains_key(&position) {
-                        let old_value = *neighbor_map.get(&position).unwrap();
-                        let monotone = uphill == (old_value < neighbor_value);
-                        if cumulative {
-                            neighbor_map.insert(position, old_value + neighbor_value);
-                        } else if monotone {
-                            neighbor_map.insert(position, neighbor_value);
-                        }
-                    } else {
-                        neighbor_map.insert(position, neighbor_value);
-                    }
-                }
-            }
-
-            for (position, neighbor_value) in neighbor_map {
-                let my_position = json_state[i]
-                    .get_pos()
-                    .map_err(|e| Error::from(e.to_string()))?;
-                if uphill {
-                    if neighbor_value > current_max {
-                        current_max = neighbor_value;
-
-                        let x_change = position[0] as f64 - my_position[0];
-                        let y_change = position[1] as f64 - my_position[1];
-
-                        json_state[i].direction = Some([x_change, y_change].into());
-                    }
-                } else if neighbor_value < current_max {
-                    current_max = neighbor_value;
-
-                    let x_change = position[0] as f64 - my_position[0];
-                    let y_change = position[1] as f64 - my_position[1];
-
-                    json_state[i].direction = Some([x_change, y_change].into());
+        let cell = neighbor
+            .position()?
+            .ok_or_else(|| SimulationError::from("Expected position to exist on neighbor"))?
+            .to_grid();
+        cell_values
+            .entry(cell)
+            .and_modify(|cell_value: &mut f64| {
+                if cumulative {
+                    *cell_value += neighbor_value;
+                } else if uphill == (*cell_value < neighbor_value) {
+                    *cell_value = neighbor_value;
                 }
-            }
+            })
+            .or_insert(neighbor_value);
+    }
 
-            // compare within same error
-            if (current_max - target_value).abs() <= std::f64::EPSILON {
-                json_state[i].direction = Some([].into());
-            }
+    let mut best_value = value;
+    let mut direction = Vec3::origin();
+    for (cell, cell_value) in cell_values {
+        let improves = if uphill {
+            cell_value > best_value
+        } else {
+            cell_value < best_value
+        };
+        if improves {
+            best_value = cell_value;
+            direction = Vec3(
+                cell[0] as f64 - position.0,
+                cell[1] as f64 - position.1,
+                0.0,
+            );
         }
     }
-
-    // Important to call this for continuity of state
-    state.set_json_state(json_state)?;
+    state.direction_set(Some(direction))?;
     Ok(())
 }
 
@@ -96,89 +74,3 @@ pub fn get_named_behavior() -> SharedBehavior {
         behavior_keys_src: Some(include_str!("orient_toward_value.rs.json").to_string()),
     }
 }
-
-// Original Source
-/*
-use crate::prelude::{AgentState, Context, SimulationResult};
-use std::collections::HashMap;
-
-// TODO: investigate if is clippy false pos
-#[allow(clippy::map_entry)]
-/// # Errors
-/// This function can fail when the value to orient to is not a string
-pub fn orient_toward_value(state: &mut AgentState, context: &Context) -> SimulationResult<()> {
-    if !state["orient_toward_value"].is_string() {
-        return Ok(());
-    }
-
-    let target = state["orient_toward_value"]
-        .as_str()
-        .ok_or("not a string")?;
-
-    // True -> looking for max (greater) value
-    // False -> looking for min (smaller) value
-    let uphill = match state["orient_toward_value_uphill"].as_bool() {
-        Some(value) => value,
-        None => true,
-    };
-
-    let cumulative = match state["orient_toward_value_cumulative"].as_bool() {
-        Some(value) => value,
-        None => false,
-    };
-
-    if let Some(target_value) = state[target].as_f64() {
-        let neighbors = context.neighbors.iter();
-
-        let mut current_max = target_value;
-
-        let mut neighbor_map = HashMap::new();
-
-        for (_i, neighbor) in neighbors.enumerate() {
-            if let Some(neighbor_value) = neighbor[target].as_f64() {
-                let position = neighbor.get_pos()?.to_grid();
-
-                if neighbor_map.contains_key(&position) {
-                    let old_value = *neighbor_map.get(&position).unwrap();
-                    let monotone = uphill == (old_value < neighbor_value);
-                    if cumulative {
-                        neighbor_map.insert(position, old_value + neighbor_value);
-                    } else if monotone {
-                        neighbor_map.insert(position, neighbor_value);
-                    }
-                } else {
-                    neighbor_map.insert(position, neighbor_value);
-                }
-            }
-        }
-
-        for (position, neighbor_value) in neighbor_map {
-            let my_position = state.get_pos()?;
-            if uphill {
-                if neighbor_value > current_max {
-                    current_max = neighbor_value;
-
-                    let x_change = position[0] as f64 - my_position[0];
-                    let y_change = position[1] as f64 - my_position[1];
-
-                    state.direction = Some([x_change, y_change].into());
-                }
-            } else if neighbor_value < current_max {
-                current_max = neighbor_value;
-
-                let x_change = position[0] as f64 - my_position[0];
-                let y_change = position[1] as f64 - my_position[1];
-
-                state.direction = Some([x_change, y_change].into());
-            }
-        }
-
-        // compare within same error
-        if (current_max - target_value).abs() <= std::f64::EPSILON {
-            state.direction = Some([].into());
-        }
-    }
-
-    Ok(())
-}
-*/
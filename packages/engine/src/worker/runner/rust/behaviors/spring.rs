@@ -1,66 +1,60 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
-#[derive(Serialize, Deserialize)]
+use super::{error::SimulationError, Context, Result, SharedBehavior, State};
+use crate::hash_types::Vec3;
+
+#[derive(Deserialize)]
 struct SpringDefinition {
+    /// The agent at the other end of the spring
     agent_id: String,
     length: f64,
-    k: f64, // Hooke's constant
+    /// Hooke's constant
+    k: f64,
     damping: Option<f64>,
 }
 
-/// Applies a spring force to the agent based on the parameters specified in `springs`
-pub fn spring(state: &mut Agent, i: usize, context: &Context) -> Result<()> {
-    // Retrieve spring parameters
-    let springs = state
-        .get_custom::<serde_json::Value>("springs")
-        .map_or_else(
-            || Ok(vec![]),
-            serde_json::from_value::<Vec<SpringDefinition>>,
-        )
-        .map_err(|_| "agent field 'springs' must be an array of spring definitions")?;
-
-    let pos = state.get_pos()?;
-
-    let mut s_force = Vec3(0.0, 0.0, 0.0);
-
-    for s in springs {
-        let neighbors = context.neighbors(i)?;
+/// Applies a spring force to the agent based on the parameters specified in `springs`. Springs to
+/// agents which aren't neighbors of the agent have no effect.
+pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
+    let springs: Vec<SpringDefinition> = match state.springs()? {
+        Some(springs) => serde_json::from_value(springs.clone())
+            .map_err(|_| SimulationError::from("`springs` must be a list of spring definitions"))?,
+        None => return Ok(()),
+    };
+    if springs.is_empty() {
+        return Ok(());
+    }
+    let position = state
+        .position()?
+        .ok_or_else(|| SimulationError::from("Expected position to exist on agent"))?;
+    let velocity = *state.velocity()?;
+    let neighbors = context.neighbors()?;
 
+    let mut spring_force = Vec3::origin();
+    for spring in springs {
         let mut other = None;
-        for n in neighbors.iter() {
-            let agent_id = n.get_custom_or("agent_id", "".to_owned())?;
-            if agent_id == state.agent_id {
-                other = Some(n);
+        for neighbor in &neighbors {
+            if neighbor.agent_id()? == spring.agent_id {
+                other = Some(neighbor);
                 break;
             }
         }
-        let other = match other {
+        let other_position = match other {
+            Some(other) => other
+                .position()?
+                .ok_or_else(|| SimulationError::from("Expected position to exist on neighbor"))?,
             None => continue,
-            Some(v) => v,
         };
 
-        let other_pos = other
-            .position()
-            .ok_or("agent field 'position' is required")?;
-        let dx = other_pos - *pos;
+        let dx = other_position - position;
         let norm = dx.norm();
-
-        let x = dx.magnitude() - s.length;
-        s_force += norm * x * s.k;
-
-        if let Some(beta) = s.damping {
-            let v = state.velocity.unwrap_or(Vec3(0.0, 0.0, 0.0));
-            // calculate damping force
-            let norm_v = norm * v.dot(norm);
-            s_force -= norm_v * beta;
+        spring_force += norm * (dx.magnitude() - spring.length) * spring.k;
+        if let Some(damping) = spring.damping {
+            spring_force -= norm * velocity.dot(norm) * damping;
         }
     }
 
-    let force = state.get_custom("force").unwrap_or(Vec3(0.0, 0.0, 0.0));
-    state.set("force", force + s_force)?;
-
+    *state.force_mut()? += spring_force;
     Ok(())
 }
 
@@ -73,12 +67,3 @@ pub fn get_named_behavior() -> SharedBehavior {
         behavior_keys_src: Some(include_str!("spring.rs.json").to_string()),
     }
 }
-
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let mut json_state = state.get_json_state()?;
-    for (i, agent) in json_state.iter_mut().enumerate() {
-        spring(agent, i, context)?;
-    }
-    state.set_json_state(json_state)?;
-    Ok(())
-}
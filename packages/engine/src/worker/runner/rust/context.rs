@@ -1,188 +1,214 @@
 use std::{
-    any::Any,
+    cell::{RefCell, RefMut},
     collections::{HashMap, HashSet},
-    fs,
     sync::Arc,
 };
 
-use arrow::array::Array;
 use arrow::{
-    array::{FixedSizeListArray, ListArray, UInt32Array},
-    datatypes::Schema,
+    array::{self, Array},
+    record_batch::RecordBatch,
 };
-use parking_lot::{RwLock, RwLockWriteGuard};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use parking_lot::RwLock;
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::Value;
 
-use crate::config::Globals;
-use crate::datastore::prelude::{AgentBatch, MessageBatch};
-use crate::datastore::{batch::Metaversion, storage::memory::Memory};
-use crate::worker::{Error as WorkerError, Result as WorkerResult, TaskMessage};
+use super::{
+    error::{Error, Result},
+    neighbor::Neighbor,
+};
 use crate::{
+    config::Globals,
     datastore::{
-        arrow::{
-            message::{outbound_messages_to_arrow_column, MESSAGE_COLUMN_INDEX},
-            util::arrow_continuation,
-        },
-        batch::{change::ArrayChange, ContextBatch},
-        table::sync::{ContextBatchSync, StateSync},
+        batch::{AgentBatch, Batch, ContextBatch},
+        prelude::SharedStore,
+        table::pool::agent::AgentPool,
     },
-    hash_types::Agent,
-    simulation::packages::{
-        state::packages::behavior_execution::config::BehaviorDescription,
-        worker_init::PackageInitMsgForWorker,
-    },
-    Language,
+    simulation::seed::derive_seed,
+    types::TaskId,
 };
 
-use super::state::{SimState, StateSnapshot};
-use super::{
-    super::comms::{
-        inbound::{InboundToRunnerMsg, InboundToRunnerMsgPayload},
-        outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
-        ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg, StateInterimSync,
-        TargetedRunnerTaskMsg,
-    },
-    neighbor::Neighbor,
-};
-use super::{Error, Result, SimSchema};
+const NEIGHBORS_FIELD_NAME: &str = "neighbors";
 
-/// Wrapper for running columnar behaviors on single agents
-pub struct AgentContext<'c> {
-    schema: &'c SimSchema,
-    ctx_batch: &'c Option<Arc<ContextBatch>>,
-    snapshot: &'c Option<StateSnapshot>,
-    col_indices: &'c HashMap<String, usize>,
-    neighbors_index: usize, // Used often.
-    globals: &'c Arc<Globals>,
-    index_in_sim: usize,
-    group_start_index: usize,
+/// Datasets of the experiment, which are the same for all simulation runs.
+#[derive(Default)]
+pub struct Datasets {
+    /// Data of every dataset by name. Datasets which aren't valid JSON are strings.
+    data: HashMap<String, Value>,
+    /// Rows of the step-indexed datasets by name and step.
+    step_data: HashMap<String, serde_json::Map<String, Value>>,
 }
 
-impl<'c> AgentContext<'c> {
-    pub fn neighbors(&'c self, _i: usize) -> Result<Vec<Neighbor<'c>>> {
-        // TODO: Use buffers from `data_ref` directly for better performance.
-        let neighbor_array = self
-            .ctx_batch
-            .as_ref()
-            .unwrap()
-            .batch
-            .column(self.neighbors_index)
-            .as_any()
-            .downcast_ref::<ListArray>()
-            .unwrap();
-
-        let neighbor_locs = neighbor_array
-            .value(self.index_in_sim)
-            .as_any()
-            .downcast_ref::<FixedSizeListArray>()
-            .unwrap();
-
-        let mut neighbors = Vec::new();
-        for i_neighbor in 0..neighbor_locs.len() {
-            let loc = neighbor_array
-                .value(i_neighbor)
-                .as_any()
-                .downcast_ref::<UInt32Array>()
-                .unwrap();
-            neighbors.push(Neighbor::new(
-                self.schema,
-                self.snapshot.as_ref().unwrap(),
-                self.col_indices,
-                loc,
-            ));
+impl Datasets {
+    pub fn load(shared_store: &SharedStore) -> Result<Self> {
+        let mut datasets = Self::default();
+        for (name, dataset) in &shared_store.datasets {
+            let json = dataset.memory().get_data_buffer()?;
+            let data = serde_json::from_slice(json)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(json).into_owned()));
+            datasets.data.insert(name.clone(), data);
+            if let Some(step_index) = dataset.step_index()? {
+                datasets.step_data.insert(name.clone(), step_index);
+            }
         }
-        Ok(neighbors)
+        Ok(datasets)
     }
+}
 
-    pub fn globals(&self) -> &Arc<Globals> {
-        &self.globals
+/// Context of a simulation run, as synced to the runner.
+pub struct SimContext {
+    pub globals: Arc<Globals>,
+    pub context_batch: Option<Arc<RwLock<ContextBatch>>>,
+    pub group_start_indices: Arc<Vec<usize>>,
+    /// State at the time the context batch was built, which neighbor indices refer to.
+    pub snapshot: Option<AgentPool>,
+    pub seed: u64,
+    pub current_step: usize,
+    /// Number of times behaviors were run on each group in the current step.
+    pub group_runs: HashMap<usize, u64>,
+    /// Tasks which were cancelled in the current step before they arrived at the runner.
+    pub cancelled_tasks: HashSet<TaskId>,
+}
+
+impl SimContext {
+    pub fn new(globals: Arc<Globals>, seed: u64) -> Self {
+        Self {
+            globals,
+            context_batch: None,
+            group_start_indices: Arc::new(Vec::new()),
+            snapshot: None,
+            seed,
+            current_step: 0,
+            group_runs: HashMap::new(),
+            cancelled_tasks: HashSet::new(),
+        }
     }
 
-    pub fn _set_index(&mut self, i_agent_in_group: usize) {
-        self.index_in_sim = i_agent_in_group + self.group_start_index;
+    /// Seed of the random number generator for running behaviors on the group `i_group`.
+    ///
+    /// Behaviors can run on a group several times per step (alternating with behaviors in other
+    /// languages), so the number of previous runs in the step is part of the seed.
+    pub fn next_group_seed(&mut self, i_group: usize) -> u64 {
+        let runs = self.group_runs.entry(i_group).or_default();
+        let seed = [self.current_step as u64, i_group as u64, *runs]
+            .into_iter()
+            .fold(self.seed, derive_seed);
+        *runs += 1;
+        seed
     }
 }
 
+/// Context of the agents of a single group.
 pub struct GroupContext<'c> {
-    schema: &'c SimSchema,
-    ctx_batch: &'c Option<Arc<ContextBatch>>,
-    snapshot: &'c Option<StateSnapshot>,
-    col_indices: &'c HashMap<String, usize>,
-    globals: &'c Arc<Globals>,
+    globals: &'c Globals,
+    datasets: &'c Datasets,
+    current_step: usize,
+    neighbors: Option<&'c array::ListArray>,
+    snapshot: &'c [&'c AgentBatch],
     start_index: usize,
+    rng: RefCell<StdRng>,
 }
 
 impl<'c> GroupContext<'c> {
-    pub fn get_agent(&'c self, i_agent_in_group: usize) -> AgentContext<'c> {
-        AgentContext {
-            schema: self.schema,
-            ctx_batch: self.ctx_batch,
-            snapshot: self.snapshot,
-            col_indices: self.col_indices,
-            neighbors_index: *self.col_indices.get("neighbors").unwrap(),
-            globals: self.globals,
-            group_start_index: self.start_index,
-            index_in_sim: i_agent_in_group + self.start_index,
+    /// Creates the context of the group starting at `start_index` from the record batch of the
+    /// context batch.
+    pub fn new(
+        globals: &'c Globals,
+        datasets: &'c Datasets,
+        current_step: usize,
+        context_batch: &'c RecordBatch,
+        snapshot: &'c [&'c AgentBatch],
+        start_index: usize,
+        seed: u64,
+    ) -> Self {
+        // The neighbors column only exists if the neighbors context package is enabled.
+        let neighbors = context_batch
+            .schema()
+            .index_of(NEIGHBORS_FIELD_NAME)
+            .ok()
+            .and_then(|index| {
+                context_batch
+                    .column(index)
+                    .as_any()
+                    .downcast_ref::<array::ListArray>()
+            });
+        Self {
+            globals,
+            datasets,
+            current_step,
+            neighbors,
+            snapshot,
+            start_index,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
         }
     }
 
-    pub fn get_json_state(&'c self) -> Vec<Agent> {
-        vec![]
+    pub fn agent(&'c self, i_agent_in_group: usize) -> AgentContext<'c> {
+        AgentContext {
+            group: self,
+            index_in_sim: self.start_index + i_agent_in_group,
+        }
     }
 }
 
-pub struct SimContext {
-    schema: SimSchema,
-    batch: Option<Arc<ContextBatch>>,
-    group_start_indices: Vec<usize>,
-    snapshot: Option<StateSnapshot>,
-    current_step: isize,
-    globals: Arc<Globals>,
-    col_indices: HashMap<String, usize>,
+/// Context of a single agent, which a behavior is executed on.
+pub struct AgentContext<'c> {
+    group: &'c GroupContext<'c>,
+    index_in_sim: usize,
 }
 
-impl SimContext {
-    pub fn new(schema: SimSchema, globals: Arc<Globals>) -> Self {
-        let mut col_indices = HashMap::new();
-        for field in schema.agent.arrow.fields().iter() {
-            let index = schema
-                .agent
-                .arrow
-                .index_of(field.name())
-                .expect("Column must exist");
-            col_indices
-                .try_insert(field.name().to_owned(), index)
-                .unwrap();
-        }
+impl<'c> AgentContext<'c> {
+    pub fn globals(&self) -> &'c Globals {
+        self.group.globals
+    }
 
-        Self {
-            schema,
-            batch: None,
-            group_start_indices: Vec::new(),
-            snapshot: None,
-            current_step: 0,
-            globals,
-            col_indices,
-        }
+    /// Data of all datasets by name.
+    pub fn data(&self) -> &'c HashMap<String, Value> {
+        &self.group.datasets.data
     }
 
-    pub fn sync_batch(&mut self, ctx_batch: Arc<ContextBatch>) {
-        self.batch = Some(ctx_batch);
-        self.current_step += 1; // TODO: Is this the correct moment to increment it?
+    /// Row of the step-indexed dataset `name` for the current step, or `None` if it has none.
+    pub fn step_data(&self, name: &str) -> Result<Option<&'c Value>> {
+        let dataset = self
+            .group
+            .datasets
+            .step_data
+            .get(name)
+            .ok_or_else(|| Error::from(format!("Not a step-indexed dataset: {name}")))?;
+        Ok(dataset.get(&self.group.current_step.to_string()))
     }
 
-    pub fn sync_snapshot(&mut self, snapshot: StateSnapshot) {
-        self.snapshot = Some(snapshot);
+    /// Random number generator of the group, seeded by the simulation run. Behaviors have to use
+    /// it instead of e.g. `rand::thread_rng()`, so runs with the same seed give the same results.
+    pub fn rng(&self) -> RefMut<'c, StdRng> {
+        self.group.rng.borrow_mut()
     }
 
-    pub fn get_group<'c>(&'c self, i_group: usize) -> GroupContext<'c> {
-        GroupContext {
-            schema: &self.schema,
-            ctx_batch: &self.batch,
-            snapshot: &self.snapshot,
-            col_indices: &self.col_indices,
-            globals: &self.globals,
-            start_index: self.group_start_indices[i_group],
-        }
+    pub fn neighbors(&self) -> Result<Vec<Neighbor<'c>>> {
+        let downcast_error = || Error::InvalidArrowDowncast(NEIGHBORS_FIELD_NAME.to_string());
+
+        let neighbors = self
+            .group
+            .neighbors
+            .ok_or_else(|| Error::InvalidRustColumn(NEIGHBORS_FIELD_NAME.to_string()))?;
+        let locs = neighbors.value(self.index_in_sim);
+        let locs = locs
+            .as_any()
+            .downcast_ref::<array::FixedSizeListArray>()
+            .ok_or_else(downcast_error)?;
+
+        (0..locs.len())
+            .map(|i_neighbor| {
+                let loc = locs.value(i_neighbor);
+                let loc = loc
+                    .as_any()
+                    .downcast_ref::<array::UInt32Array>()
+                    .ok_or_else(downcast_error)?;
+                let (i_group, index_in_group) = (loc.value(0) as usize, loc.value(1) as usize);
+                let batch = self.group.snapshot.get(i_group).ok_or_else(|| {
+                    Error::from(format!("Neighbor refers to missing group {i_group}"))
+                })?;
+                Ok(Neighbor::new(batch, index_in_group))
+            })
+            .collect()
     }
 }
@@ -1,10 +1,14 @@
+use std::time::Duration;
+
 use arrow::error::ArrowError;
 use thiserror::Error as ThisError;
 use tokio::sync::mpsc::error::SendError;
 
-use crate::worker::{
-    runner::{comms::inbound::InboundToRunnerMsgPayload, rust::behaviors::error::SimulationError},
-    SimulationShortId,
+use super::behaviors::error::SimulationError;
+use crate::{
+    proto::SimulationShortId,
+    simulation::package::id::PackageId,
+    worker::runner::comms::{inbound::InboundToRunnerMsgPayload, outbound::OutboundFromRunnerMsg},
 };
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -14,39 +18,62 @@ pub enum Error {
     #[error("{0}")]
     Unique(String),
 
+    #[error("Can't start Rust runner again when it is already running")]
+    AlreadyRunning,
+
     #[error("Arrow: {0}")]
     Arrow(#[from] ArrowError),
 
-    // TODO: JSON parse error?
-    #[error("{0}: IO {1}")]
-    IO(String, std::io::Error), // First element is path.
+    #[error("Datastore: {0}")]
+    Datastore(#[from] crate::datastore::error::Error),
 
-    #[error("Couldn't import package {0}: {1}")]
-    PackageImport(String, String), // First element is path/name.
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
 
     #[error("Missing simulation run with id {0}")]
-    MissingSimRun(crate::proto::SimulationShortId),
-
-    #[error("Task target must be py, js, rs, dyn or main, not {0}")]
-    UnknownTarget(String),
+    MissingSimulationRun(SimulationShortId),
 
-    #[error("Couldn't send inbound message to runner: {0}")]
-    InboundSend(SendError<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>),
+    #[error("Couldn't terminate missing simulation run with id {0}")]
+    TerminateMissingSimulationRun(SimulationShortId),
 
-    #[error("Couldn't receive outbound message from runner")]
-    OutboundReceive,
+    #[error("Duplicate simulation run id: {0}")]
+    DuplicateSimulationRun(SimulationShortId),
 
-    #[error("Message type '{0}' must have a simulation run id")]
-    SimulationIDRequired(&'static str),
+    #[error("Rust runner has no package with id {0:?}")]
+    UnknownPackage(PackageId),
 
-    #[error("Simulation error: {0}")]
+    #[error("Behavior error: {0}")]
     Simulation(#[from] SimulationError),
 
+    #[error("Invalid Rust built-in behavior name: {0}")]
+    InvalidRustBuiltIn(String),
+
+    #[error("Task ran for longer than the task timeout of {0:?}")]
+    TaskTimeout(Duration),
+
+    #[error("Invalid behavior id: {0:?}")]
+    InvalidBehavior([u16; 2]),
+
     #[error("Field not available in Rust runner: {0}")]
     InvalidRustColumn(String),
 
-    #[error("Datastore: {0}")]
-    Datastore(#[from] crate::datastore::error::Error),
+    #[error("Null in non-nullable field {0}")]
+    UnexpectedNull(&'static str),
+
+    #[error("Couldn't downcast column {0} to its expected Arrow type")]
+    InvalidArrowDowncast(String),
+
+    #[error("Couldn't send inbound message to runner: {0}")]
+    InboundSend(#[from] SendError<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>),
+
+    #[error("Couldn't send outbound message from runner: {0}")]
+    OutboundSend(#[from] SendError<OutboundFromRunnerMsg>),
+
+    #[error("Couldn't receive outbound message from runner")]
+    OutboundReceive,
+
+    #[error("Message type '{0}' must have a simulation run id")]
+    SimulationIdRequired(&'static str),
 }
 
 impl From<&str> for Error {
@@ -1,184 +1,243 @@
-use futures::FutureExt;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+mod behavior_execution;
+pub mod behaviors;
+mod context;
+mod error;
+mod neighbor;
+mod state;
 
-use super::comms::{
-    inbound::InboundToRunnerMsgPayload, outbound::OutboundFromRunnerMsg, ExperimentInitRunnerMsg,
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    result::Result as StdResult,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-/*
-use behavior_execution::BehaviorPackage;
-use behaviors::NativeState;
-use context::{AgentContext, GroupContext, SimContext};
+
 pub use error::{Error, Result};
-use state::{AgentState, GroupState, SimState, StateSnapshot};
-*/
+use futures::FutureExt;
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinError,
+};
+
+use self::{
+    behavior_execution::BehaviorPackage,
+    context::{Datasets, GroupContext, SimContext},
+    state::GroupState,
+};
+use super::{
+    comms::{
+        inbound::InboundToRunnerMsgPayload,
+        outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
+        ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg,
+        TargetedRunnerTaskMsg,
+    },
+    LanguageWorker,
+};
 use crate::{
+    datastore::table::{
+        sync::{ContextBatchSync, StateSync, WaitableStateSync},
+        task_shared_store::{PartialSharedState, SharedState},
+    },
     proto::SimulationShortId,
+    simulation::package::{name::PackageName, state},
+    types::TaskId,
     worker::{Error as WorkerError, Result as WorkerResult},
+    Language,
 };
 
-pub struct RustRunner {
-    _outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
-    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
-    _spawned: bool,
+/// Executes the Rust built-in behaviors. Behavior execution is the only package with a Rust part,
+/// so tasks of other packages are rejected.
+struct RunnerImpl {
+    behavior_execution: Option<BehaviorPackage>,
+    datasets: Datasets,
+    sims: HashMap<SimulationShortId, SimContext>,
+    /// Built-in behaviors can't be interrupted, so the time limit of a task is checked after each
+    /// group of agents.
+    task_timeout: Option<Duration>,
 }
 
-impl RustRunner {
-    pub fn new(spawn: bool, _init_msg: ExperimentInitRunnerMsg) -> WorkerResult<Self> {
-        let (outbound_sender, outbound_receiver) = unbounded_channel();
+impl RunnerImpl {
+    fn new(init_msg: &ExperimentInitRunnerMsg, task_timeout: Option<Duration>) -> Result<Self> {
+        let behavior_execution = init_msg
+            .package_config
+            .0
+            .values()
+            .find(|init| init.name == PackageName::State(state::Name::BehaviorExecution))
+            .map(BehaviorPackage::start_experiment)
+            .transpose()?;
+
         Ok(Self {
-            _outbound_sender: outbound_sender,
-            outbound_receiver,
-            _spawned: spawn,
+            behavior_execution,
+            datasets: Datasets::load(&init_msg.shared_context)?,
+            sims: HashMap::new(),
+            task_timeout,
         })
     }
 
-    pub async fn send(
-        &self,
-        _sim_id: Option<SimulationShortId>,
-        _msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        Ok(())
-    }
-
-    pub async fn send_if_spawned(
-        &self,
-        _sim_id: Option<SimulationShortId>,
-        _msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        log::trace!("Received message to send to Rust Runner: {:?}", &_msg);
+    fn start_sim(&mut self, run: NewSimulationRun) -> Result<()> {
+        self.sims
+            .try_insert(run.short_id, SimContext::new(run.globals, run.seed))
+            .map_err(|_| Error::DuplicateSimulationRun(run.short_id))?;
         Ok(())
     }
 
-    pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
-        self.outbound_receiver
-            .recv()
-            .await
-            .ok_or_else(|| WorkerError::from("Rust outbound receive"))
-    }
-
-    pub async fn recv_now(&mut self) -> WorkerResult<Option<OutboundFromRunnerMsg>> {
-        self.recv().now_or_never().transpose()
-    }
-
-    pub fn spawned(&self) -> bool {
-        false
+    fn sim_mut(&mut self, sim_run_id: SimulationShortId) -> Result<&mut SimContext> {
+        self.sims
+            .get_mut(&sim_run_id)
+            .ok_or(Error::MissingSimulationRun(sim_run_id))
     }
 
-    pub async fn run(&mut self) -> WorkerResult<()> {
+    fn state_sync(&mut self, sim_run_id: SimulationShortId, msg: WaitableStateSync) -> Result<()> {
+        // Behaviors are executed directly on the batches of the task's shared store, so there is
+        // no state to keep in the runner.
+        self.sim_mut(sim_run_id)?;
+
+        log::trace!("Sending state sync completion");
+        msg.completion_sender.send(Ok(())).map_err(|e| {
+            Error::from(format!(
+                "Couldn't send state sync completion to worker: {:?}",
+                e
+            ))
+        })?;
         Ok(())
     }
-}
 
-/*
-mod behavior_execution;
-pub mod behaviors;
-mod context;
-mod error;
-mod neighbor;
-mod state;
-
-type KeepRunning = bool;
-
-trait Column: Send + Sync {
-    fn get<'s>(&self, state: &AgentState<'s>) -> Result<serde_json::Value>;
-    fn set<'s>(&self, state: &mut AgentState<'s>, value: serde_json::Value) -> Result<()>;
-    fn load<'s>(&self, state: &mut GroupState<'s>) -> Result<()>;
-    fn commit<'s>(&self, state: &mut GroupState<'s>) -> Result<()>;
-}
-
-struct SimSchema {
-    agent: Arc<AgentSchema>,
-    msg: Arc<Schema>,
-    ctx: Arc<Schema>,
-}
-
-pub struct RustRunner {
-    sims_state: HashMap<SimulationShortId, SimState>,
-    sims_ctx: HashMap<SimulationShortId, SimContext>,
-    behavior_execution: Option<BehaviorPackage>,
-    inbound_sender: UnboundedSender<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
-    inbound_receiver: UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
-    outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
-    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
-    spawned: bool,
-}
-
-impl RustRunner {
-    pub fn new(spawn: bool, init_msg: ExperimentInitRunnerMsg) -> WorkerResult<Self> {
-        let mut behavior_execution = None;
-        for (pkg_id, pkg_init) in init_msg.package_config.0 {
-            if pkg_init.name == "behavior_execution" {
-                behavior_execution = Some(BehaviorPackage::start_experiment(pkg_id, pkg_init)?);
-                break;
-            }
-        }
-
-        let (inbound_sender, inbound_receiver) = unbounded_channel();
-        let (outbound_sender, outbound_receiver) = unbounded_channel();
-        Ok(Self {
-            sims_state: HashMap::new(),
-            sims_ctx: HashMap::new(),
-            behavior_execution,
-            inbound_sender,
-            inbound_receiver,
-            outbound_sender,
-            outbound_receiver,
-            spawned: spawn,
-        })
+    fn state_snapshot_sync(&mut self, sim_run_id: SimulationShortId, msg: StateSync) -> Result<()> {
+        self.sim_mut(sim_run_id)?.snapshot = Some(msg.agent_pool);
+        Ok(())
     }
 
-    pub async fn send(
-        &self,
-        sim_id: Option<SimulationShortId>,
-        msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        self.inbound_sender
-            .send((sim_id, msg))
-            .map_err(|e| WorkerError::Rust(Error::InboundSend(e)))
+    fn ctx_batch_sync(
+        &mut self,
+        sim_run_id: SimulationShortId,
+        ctx_batch_sync: ContextBatchSync,
+    ) -> Result<()> {
+        let ctx = self.sim_mut(sim_run_id)?;
+        ctx.context_batch = Some(ctx_batch_sync.context_batch);
+        ctx.group_start_indices = ctx_batch_sync.state_group_start_indices;
+        ctx.current_step = ctx_batch_sync.current_step;
+        ctx.group_runs.clear();
+        ctx.cancelled_tasks.clear();
+        Ok(())
     }
 
-    pub async fn send_if_spawned(
-        &self,
+    /// Confirms the cancellation of the task `task_id`.
+    ///
+    /// Tasks run one at a time, so a cancelled task either finished already or it's still on its
+    /// way to this runner, e.g. as a continuation from another runner. In the latter case, it's
+    /// skipped when it arrives.
+    fn cancel_task(
+        &mut self,
         sim_id: Option<SimulationShortId>,
-        msg: InboundToRunnerMsgPayload,
-    ) -> WorkerResult<()> {
-        if self.spawned {
-            self.send(sim_id, msg).await?;
+        task_id: TaskId,
+        outbound_sender: &UnboundedSender<OutboundFromRunnerMsg>,
+    ) -> Result<()> {
+        if let Some(ctx) = sim_id.and_then(|sim_id| self.sims.get_mut(&sim_id)) {
+            ctx.cancelled_tasks.insert(task_id);
         }
+        outbound_sender.send(OutboundFromRunnerMsg {
+            source: Language::Rust,
+            // The worker doesn't know the simulation run of tasks it's not running anymore.
+            sim_id: sim_id.unwrap_or_default(),
+            payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+        })?;
         Ok(())
     }
 
-    pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
-        self.outbound_receiver
-            .recv()
-            .await
-            .ok_or(WorkerError::Rust(Error::OutboundReceive))
-    }
-
-    pub async fn recv_now(&mut self) -> WorkerResult<Option<OutboundFromRunnerMsg>> {
-        self.recv().now_or_never().transpose()
-    }
-
-    pub fn spawned(&self) -> bool {
-        self.spawned
-    }
-
-    pub async fn run(&mut self) -> WorkerResult<()> {
-        if !self.spawned {
-            return Ok(());
-        }
+    fn run_task(
+        &mut self,
+        sim_run_id: SimulationShortId,
+        mut msg: RunnerTaskMsg,
+    ) -> Result<(TargetedRunnerTaskMsg, Vec<RunnerError>)> {
+        let pkg = self
+            .behavior_execution
+            .as_ref()
+            .filter(|pkg| pkg.id() == msg.package_id)
+            .ok_or(Error::UnknownPackage(msg.package_id))?;
+        let ctx = self
+            .sims
+            .get_mut(&sim_run_id)
+            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
 
-        loop {
-            tokio::select! {
-                Some(sim_id, msg) = self.inbound_receiver.recv() => {
-                    // TODO: Send errors instead of immediately stopping?
-                    if !self.handle_msg(sim_id, msg, &self.outbound_sender)? {
-                        break;
-                    }
-                }
+        let (proxy, group_indices) = match &mut msg.shared_store.state {
+            SharedState::Write(state) => {
+                let indices = (0..state.agent_pool().n_batches()).collect();
+                (state, indices)
+            }
+            SharedState::Partial(PartialSharedState::Write(state)) => {
+                let indices = state.indices.clone();
+                (&mut state.inner, indices)
             }
+            _ => return Err(Error::from("Behavior execution needs write access")),
+        };
+        let seeds: Vec<_> = group_indices
+            .iter()
+            .map(|i_group| ctx.next_group_seed(*i_group))
+            .collect();
+        let ctx = &*ctx;
+
+        let context_batch = ctx
+            .context_batch
+            .as_ref()
+            .ok_or_else(|| Error::from("Context batch wasn't synced before running behaviors"))?;
+        let context_batch = context_batch
+            .try_read()
+            .ok_or_else(|| Error::from("Couldn't read context batch"))?;
+        let snapshot = match &ctx.snapshot {
+            Some(snapshot) => Some(snapshot.read_proxy()?),
+            None => None,
+        };
+        let snapshot = snapshot
+            .as_ref()
+            .map(|snapshot| snapshot.batches())
+            .unwrap_or_default();
+
+        let started = Instant::now();
+        let mut next_target = MessageTarget::Main;
+        let mut errors = Vec::new();
+        for (i_proxy, (i_group, seed)) in group_indices.into_iter().zip(seeds).enumerate() {
+            if let Some(timeout) = self
+                .task_timeout
+                .filter(|&timeout| started.elapsed() > timeout)
+            {
+                return Err(Error::TaskTimeout(timeout));
+            }
+            let agent_batch = proxy.agent_pool_mut().batch_mut(i_proxy)?;
+            let mut group_state = GroupState::load(agent_batch, pkg.columns())?;
+            let start_index = *ctx
+                .group_start_indices
+                .get(i_group)
+                .ok_or_else(|| Error::from(format!("Missing start index of group {i_group}")))?;
+            let group_context = GroupContext::new(
+                &ctx.globals,
+                &self.datasets,
+                ctx.current_step,
+                &context_batch.batch,
+                &snapshot,
+                start_index,
+                seed,
+            );
+
+            let (target, group_errors) = pkg.run_group(&mut group_state, &group_context)?;
+            group_state.flush()?;
+            errors.extend(group_errors);
+            next_target = next_target
+                .merge_group_target(target)
+                .map_err(|err| Error::from(err.to_string()))?;
         }
-        Ok(())
+
+        let next_task_msg = TargetedRunnerTaskMsg {
+            target: next_target,
+            msg: RunnerTaskMsg {
+                package_id: msg.package_id,
+                task_id: msg.task_id,
+                shared_store: msg.shared_store,
+                payload: msg.payload,
+            },
+        };
+        Ok((next_task_msg, errors))
     }
 
     fn handle_msg(
@@ -186,19 +245,18 @@ impl RustRunner {
         sim_id: Option<SimulationShortId>,
         msg: InboundToRunnerMsgPayload,
         outbound_sender: &UnboundedSender<OutboundFromRunnerMsg>,
-    ) -> Result<KeepRunning> {
+    ) -> Result<bool> {
         match msg {
             InboundToRunnerMsgPayload::TerminateRunner => {
+                log::debug!("Stopping execution on Rust runner");
                 return Ok(false); // Don't continue running.
             }
             InboundToRunnerMsgPayload::NewSimulationRun(new_run) => {
-                // TODO: `short_id` doesn't need to be inside `new_run`, since
-                //       it's already passed separately to the runner.
                 self.start_sim(new_run)?;
             }
             InboundToRunnerMsgPayload::TerminateSimulationRun => {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("terminate sim"))?;
-                self.sims_state
+                self.sims
                     .remove(&sim_id)
                     .ok_or(Error::TerminateMissingSimulationRun(sim_id))?;
             }
@@ -206,9 +264,8 @@ impl RustRunner {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("state sync"))?;
                 self.state_sync(sim_id, state_msg)?;
             }
-            InboundToRunnerMsgPayload::StateInterimSync(interim_msg) => {
-                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("interim sync"))?;
-                self.state_interim_sync(sim_id, interim_msg)?;
+            InboundToRunnerMsgPayload::StateInterimSync(_) => {
+                // Tasks carry their own shared store, so there is nothing to sync.
             }
             InboundToRunnerMsgPayload::StateSnapshotSync(state_msg) => {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("snapshot sync"))?;
@@ -218,171 +275,244 @@ impl RustRunner {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("context batch sync"))?;
                 self.ctx_batch_sync(sim_id, ctx_batch)?;
             }
+            InboundToRunnerMsgPayload::GlobalsSync(globals_msg) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("globals sync"))?;
+                self.sim_mut(sim_id)?.globals = globals_msg.globals;
+            }
             InboundToRunnerMsgPayload::TaskMsg(msg) => {
                 let sim_id = sim_id.ok_or(Error::SimulationIdRequired("run task"))?;
-                let (next_task_msg, warnings) = self.run_task(sim_id, msg)?;
-                // TODO: `send` fn to reduce code duplication.
+                if self.sim_mut(sim_id)?.cancelled_tasks.remove(&msg.task_id) {
+                    // Confirm again, since this runner is the active runner of the task now.
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::Rust,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::TaskCancelled(msg.task_id),
+                    })?;
+                    return Ok(true);
+                }
+                let task_id = msg.task_id;
+                let (next_task_msg, errors) = match self.run_task(sim_id, msg) {
+                    Ok(result) => result,
+                    Err(Error::TaskTimeout(timeout)) => {
+                        // Only the task (and with it the simulation run) fails
+                        let message = format!(
+                            "Task was stopped after running for longer than the task timeout of \
+                             {timeout:?}"
+                        );
+                        log::warn!("{message}");
+                        outbound_sender.send(OutboundFromRunnerMsg {
+                            source: Language::Rust,
+                            sim_id,
+                            payload: OutboundFromRunnerMsgPayload::RunnerError(RunnerError {
+                                message: Some(message),
+                                details: None,
+                                file_name: None,
+                                line_number: None,
+                            }),
+                        })?;
+                        outbound_sender.send(OutboundFromRunnerMsg {
+                            source: Language::Rust,
+                            sim_id,
+                            payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+                        })?;
+                        return Ok(true);
+                    }
+                    Err(err) => return Err(err),
+                };
                 outbound_sender.send(OutboundFromRunnerMsg {
                     source: Language::Rust,
                     sim_id,
                     payload: OutboundFromRunnerMsgPayload::TaskMsg(next_task_msg),
                 })?;
-                outbound_sender.send(OutboundFromRunnerMsg {
-                    source: Language::Rust,
-                    sim_id,
-                    payload: OutboundFromRunnerMsgPayload::RunnerWarnings(warnings),
-                })?;
+                if !errors.is_empty() {
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::Rust,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::UserErrors(errors),
+                    })?;
+                }
+            }
+            InboundToRunnerMsgPayload::CancelTask(task_id) => {
+                self.cancel_task(sim_id, task_id, outbound_sender)?;
             }
-            InboundToRunnerMsgPayload::CancelTask(_) => {}
         }
         Ok(true) // Continue running.
     }
+}
 
-    fn start_sim(&mut self, run: NewSimulationRun) -> Result<()> {
-        // TODO: Shouldn't rely on behavior package to start sim runs.
-        let (col_map, all_behavior_col_names) = match self.behavior_execution.as_ref() {
-            Some(b) => (b.col_map.clone(), b.all_behavior_col_names.clone()),
-            None => (Arc::new(HashMap::new()), Arc::new(HashSet::new())),
-        };
-        let schema = SimSchema {
-            agent: run.datastore.agent_batch_schema,
-            msg: run.datastore.message_batch_schema,
-            ctx: run.datastore.context_batch_schema,
-        };
+pub struct RustRunner {
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    inbound_sender: UnboundedSender<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    inbound_receiver:
+        Option<UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>>,
+    outbound_sender: Option<UnboundedSender<OutboundFromRunnerMsg>>,
+    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
+    spawn: bool,
+}
 
-        let state = SimState::new(
-            schema.clone(),
-            Vec::new(),
-            Vec::new(),
-            col_map,
-            all_behavior_col_names,
-        );
-        self.sims_state
-            .try_insert(run.short_id, state)
-            .map_err(|_| Error::DuplicateSimulationRun(run.id))?;
-
-        let ctx = SimContext::new(schema, run.globals);
-        self.sims_ctx
-            .try_insert(run.short_id, ctx)
-            .map_err(|_| Error::DuplicateSimulationRun(run.id))?;
-        Ok(())
+impl RustRunner {
+    pub fn new(
+        spawn: bool,
+        init_msg: ExperimentInitRunnerMsg,
+        task_timeout: Option<Duration>,
+    ) -> WorkerResult<Self> {
+        let (inbound_sender, inbound_receiver) = unbounded_channel();
+        let (outbound_sender, outbound_receiver) = unbounded_channel();
+        Ok(Self {
+            init_msg: Arc::new(init_msg),
+            task_timeout,
+            inbound_sender,
+            inbound_receiver: Some(inbound_receiver),
+            outbound_sender: Some(outbound_sender),
+            outbound_receiver,
+            spawn,
+        })
     }
 
-    fn flush(&mut self, state: &mut GroupState<'_>) -> Result<()> {
-        state.flush()?;
-        Ok(())
+    pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
+        self.outbound_receiver
+            .recv()
+            .await
+            .ok_or(WorkerError::Rust(Error::OutboundReceive))
     }
 
-    fn run_behavior_execution_task(
+    pub async fn recv_now(&mut self) -> WorkerResult<Option<OutboundFromRunnerMsg>> {
+        self.recv().now_or_never().transpose()
+    }
+
+    pub async fn run(
         &mut self,
-        sim_run_id: SimulationShortId,
-        msg: RunnerTaskMsg,
-    ) -> Result<(TargetedRunnerTaskMsg, Vec<RunnerError>)> {
-        let state = self
-            .sims_state
-            .get_mut(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
-        let ctx = self
-            .sims_ctx
-            .get(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
+    ) -> WorkerResult<Pin<Box<dyn Future<Output = StdResult<WorkerResult<()>, JoinError>> + Send>>>
+    {
+        log::debug!("Running Rust runner");
+        if !self.spawn {
+            return Ok(Box::pin(async move { Ok(Ok(())) }));
+        }
 
-        // Behavior execution is always executed on single groups.
-        let group_index = msg.group_index.unwrap() as usize;
-        let pkg = self.behavior_execution.as_mut().unwrap();
-        let mut group_state = state.get_group(group_index)?;
-        let group_context = ctx.get_group(group_index);
-        let next_target = pkg.run_task(&mut group_state, &group_context)?;
-
-        // TODO: When we have packages other than behavior execution, only flush if state writable
-        self.flush(&mut group_state)?;
-        let next_sync = StateInterimSync {
-            group_indices: vec![group_index],
-            agent_batches: vec![state.agent_pool[group_index].clone()],
-            message_batches: vec![state.msg_pool[group_index].clone()],
-        };
+        let init_msg = Arc::clone(&self.init_msg);
+        let task_timeout = self.task_timeout;
+        let inbound_receiver = self.inbound_receiver.take().ok_or(Error::AlreadyRunning)?;
+        let outbound_sender = self.outbound_sender.take().ok_or(Error::AlreadyRunning)?;
 
-        let next_task_msg = TargetedRunnerTaskMsg {
-            target: next_target,
-            msg: RunnerTaskMsg {
-                package_id: msg.package_id,
-                task_id: msg.task_id,
-                sync: next_sync,
-                payload: TaskMessage::default(),
-                group_index: msg.group_index,
-            },
-        };
-        Ok((next_task_msg, Vec::new()))
+        let f = move || _run(init_msg, task_timeout, inbound_receiver, outbound_sender);
+        Ok(Box::pin(tokio::task::spawn_blocking(f)))
     }
+}
 
-    fn run_task(
-        &mut self,
-        sim_run_id: SimulationShortId,
-        msg: RunnerTaskMsg,
-    ) -> Result<(TargetedRunnerTaskMsg, Vec<RunnerError>)> {
-        if let Some(ref behavior_execution) = self.behavior_execution {
-            if msg.package_id != behavior_execution.id() {
-                return Err(Error::NotBehaviorExecution);
-            }
-        } else {
-            return Err(Error::NotBehaviorExecution);
+#[async_trait::async_trait]
+impl LanguageWorker for RustRunner {
+    async fn send(
+        &self,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> WorkerResult<()> {
+        log::trace!("Sending message to Rust: {:?}", &msg);
+        self.inbound_sender
+            .send((sim_id, msg))
+            .map_err(|e| WorkerError::Rust(Error::InboundSend(e)))
+    }
+
+    fn spawned(&self) -> bool {
+        self.spawn
+    }
+}
+
+fn _run(
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    task_timeout: Option<Duration>,
+    mut inbound_receiver: UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
+) -> WorkerResult<()> {
+    let mut impl_ = RunnerImpl::new(&init_msg, task_timeout)?;
+    // Behaviors are executed synchronously, so the runner doesn't need its own async runtime.
+    while let Some((sim_id, msg)) = inbound_receiver.blocking_recv() {
+        // TODO: Send errors instead of immediately stopping?
+        let msg_str = msg.as_str();
+        log::debug!("Rust runner got sim `{:?}` inbound {}", &sim_id, msg_str);
+        let keep_running = impl_.handle_msg(sim_id, msg, &outbound_sender)?;
+        log::debug!("Rust runner handled sim `{:?}` inbound {}", sim_id, msg_str);
+        if !keep_running {
+            log::debug!("Rust Runner has finished execution, stopping");
+            break;
         }
-        self.run_behavior_execution_task(sim_run_id, msg)
     }
+    Ok(())
+}
 
-    fn ctx_batch_sync(
-        &mut self,
-        sim_run_id: SimulationShortId,
-        ctx_batch: ContextBatchSync,
-    ) -> Result<()> {
-        let ctx = self
-            .sims_ctx
-            .get_mut(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
-        ctx.sync_batch(ctx_batch.context_batch);
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Globals,
+        datastore::table::task_shared_store::TaskSharedStore,
+        simulation::{
+            enum_dispatch::StateTaskMessage,
+            package::state::packages::behavior_execution::tasks::ExecuteBehaviorsTaskMessage,
+            task::msg::TaskMessage,
+        },
+    };
+
+    fn runner_with_sim(sim_id: SimulationShortId) -> RunnerImpl {
+        let mut sims = HashMap::new();
+        sims.insert(
+            sim_id,
+            SimContext::new(Arc::new(Globals(serde_json::json!({}))), 0),
+        );
+        RunnerImpl {
+            behavior_execution: None,
+            datasets: Datasets::default(),
+            sims,
+            task_timeout: None,
+        }
     }
 
-    fn state_sync(&mut self, sim_run_id: SimulationShortId, msg: StateSync) -> Result<()> {
-        let state = self
-            .sims_state
-            .get_mut(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
-        state.agent_pool = msg.agent_pool.cloned_batch_pool();
-        state.msg_pool = msg.message_pool.cloned_batch_pool();
-        Ok(())
+    fn task_msg(task_id: TaskId) -> InboundToRunnerMsgPayload {
+        let payload: StateTaskMessage = ExecuteBehaviorsTaskMessage {}.into();
+        InboundToRunnerMsgPayload::TaskMsg(RunnerTaskMsg {
+            package_id: 0.into(),
+            task_id,
+            payload: TaskMessage::from(payload),
+            shared_store: TaskSharedStore::default(),
+        })
     }
 
-    fn state_interim_sync(
-        &mut self,
-        sim_run_id: SimulationShortId,
-        msg: StateInterimSync,
-    ) -> Result<()> {
-        let state = self
-            .sims_state
-            .get_mut(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
-        let iter = msg
-            .group_indices
-            .iter()
-            .zip(msg.agent_batches.iter().zip(msg.message_batches.iter()));
-        for (i_group, (agent_batch, msg_batch)) in iter {
-            state.agent_pool[*i_group] = agent_batch.clone();
-            state.msg_pool[*i_group] = msg_batch.clone();
+    fn recv_cancelled(outbound: &mut UnboundedReceiver<OutboundFromRunnerMsg>) -> TaskId {
+        match outbound.try_recv().expect("Runner didn't reply").payload {
+            OutboundFromRunnerMsgPayload::TaskCancelled(task_id) => task_id,
+            payload => panic!("Expected a cancel confirmation, got {payload:?}"),
         }
-        Ok(())
     }
 
-    fn state_snapshot_sync(&mut self, sim_run_id: SimulationShortId, msg: StateSync) -> Result<()> {
-        let ctx = self
-            .sims_ctx
-            .get_mut(&sim_run_id)
-            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
-        ctx.sync_snapshot(StateSnapshot {
-            agent_pool: msg.agent_pool.cloned_batch_pool(),
-            msg_pool: msg.message_pool.cloned_batch_pool(),
-        });
-        Ok(())
+    #[test]
+    fn cancelled_task_is_confirmed_and_skipped() {
+        let mut runner = runner_with_sim(1);
+        let (sender, mut outbound) = unbounded_channel();
+
+        assert!(
+            runner
+                .handle_msg(Some(1), InboundToRunnerMsgPayload::CancelTask(7), &sender)
+                .unwrap()
+        );
+        assert_eq!(recv_cancelled(&mut outbound), 7);
+
+        // The task arrives after it was cancelled, so it isn't run (which would fail, since the
+        // runner has no behavior execution package) but confirmed again.
+        assert!(runner.handle_msg(Some(1), task_msg(7), &sender).unwrap());
+        assert_eq!(recv_cancelled(&mut outbound), 7);
+        assert!(outbound.try_recv().is_err());
+        assert!(runner.sims[&1].cancelled_tasks.is_empty());
+    }
+
+    #[test]
+    fn task_of_unknown_simulation_run_is_confirmed() {
+        let mut runner = runner_with_sim(1);
+        let (sender, mut outbound) = unbounded_channel();
+
+        runner
+            .handle_msg(None, InboundToRunnerMsgPayload::CancelTask(3), &sender)
+            .unwrap();
+        assert_eq!(recv_cancelled(&mut outbound), 3);
+        assert!(runner.sims[&1].cancelled_tasks.is_empty());
     }
 }
-*/
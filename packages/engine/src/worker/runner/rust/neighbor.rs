@@ -1,47 +1,29 @@
-use arrow::array::UInt32Array;
-use std::{collections::HashMap, sync::Arc};
+use arrow::array::ArrayRef;
 
-use super::{state::StateSnapshot, Error, Result, SimSchema};
-use crate::datastore::batch::{AgentBatch, MessageBatch};
+use super::error::{Error, Result};
+use crate::datastore::batch::AgentBatch;
 
-#[derive(new)]
+/// A neighbor of an agent, read from the state snapshot.
+///
+/// The accessors of the columns are generated by the `accessors!` macro in `behaviors`.
+#[derive(derive_new::new)]
 pub struct Neighbor<'c> {
-    // `col_indices`, `loc` and `snapshot` have to be public due to impl in accessors macro.
-    // TODO: Just `&'c AgentContext` instead to minimize Neighbor size in memory?
-    schema: &'c SimSchema,
-    pub snapshot: &'c StateSnapshot,
-    pub col_indices: &'c HashMap<String, usize>,
-    pub loc: &'c UInt32Array, // i_group, i_neighbor_in_group
+    batch: &'c AgentBatch,
+    index_in_group: usize,
 }
 
 impl<'c> Neighbor<'c> {
-    pub fn get_custom_as_json(&self, name: &str) -> Result<serde_json::Value> {
-        if name == "messages" {
-            return Err(Error::NotCustomField(name.to_string()));
-        }
-
-        if let Some((i_field, field)) = self.schema.agent.column_with_name(name) {
-            crate::datastore::arrow::element_conversion::col_element_to_json_val(
-                self.agent_batch.column(i_field),
-                self.index_in_group,
-                field.data_type(),
-            )
-            .map_err(|e| Error::from(e.to_string()))
-        } else {
-            Err(Error::UnknownAgentField(name.to_string()))
-        }
+    pub(super) fn column(&self, name: &str) -> Result<&'c ArrayRef> {
+        let index = self
+            .batch
+            .batch
+            .schema()
+            .index_of(name)
+            .map_err(|_| Error::InvalidRustColumn(name.to_string()))?;
+        Ok(self.batch.batch.column(index))
     }
 
-    /// Get a custom value, or return the provided default if the field does not exist.
-    pub fn get_custom_or<T>(&self, name: &str, default: T) -> Result<T>
-    where
-        for<'de> T: serde::Deserialize<'de>,
-    {
-        self.get_custom_as_json(name)
-            .and_then(|v| serde_json::from_value::<T>(v).map_err(Error::from))
-            .or_else(|e| match e {
-                Error::UnknownAgentField(_) => Ok(default),
-                _ => Err(e),
-            })
+    pub(super) fn index_in_group(&self) -> usize {
+        self.index_in_group
     }
 }
@@ -1,251 +1,112 @@
-use std::{
-    any::Any,
-    collections::{HashMap, HashSet},
-    fs,
-    sync::Arc,
-};
-
-use arrow::array::Array;
-use arrow::{array::ArrayData, datatypes::Schema};
-use parking_lot::{RwLock, RwLockWriteGuard};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use std::collections::HashSet;
 
-use crate::config::globals::Globals;
-use crate::datastore::arrow::message::get_message_arrow_builder;
-use crate::datastore::prelude::{AgentBatch, IntoAgentStates, MessageBatch};
-use crate::datastore::{batch::Metaversion, storage::memory::Memory};
-use crate::hash_types::message::Outbound as OutboundMessage;
-use crate::worker::{Error as WorkerError, Result as WorkerResult, TaskMessage};
-use crate::{
-    datastore::{
-        arrow::{
-            message::{outbound_messages_to_arrow_column, MESSAGE_COLUMN_INDEX},
-            util::arrow_continuation,
-        },
-        batch::{change::ArrayChange, ContextBatch, DynamicBatch},
-        table::sync::{ContextBatchSync, StateSync},
-    },
-    hash_types::Agent,
-    simulation::packages::{
-        state::packages::behavior_execution::config::BehaviorDescription,
-        worker_init::PackageInitMsgForWorker,
-    },
-    Language,
+use arrow::{
+    array::{self, Array},
+    record_batch::RecordBatch,
 };
 
-use super::super::comms::{
-    inbound::{InboundToRunnerMsg, InboundToRunnerMsgPayload},
-    outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
-    ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg, StateInterimSync,
-    TargetedRunnerTaskMsg,
+use super::{
+    behaviors::{NativeState, ENGINE_COLUMNS},
+    error::{Error, Result},
 };
-use super::{Column, Error, NativeState, Result, SimSchema};
+use crate::datastore::batch::{AgentBatch, DynamicBatch};
 
-/// Wrapper for running columnar behaviors on single agents
+// TODO: Propagate field specs to runners instead of hard-coding the private field name (same as
+//       in the behavior execution package for JavaScript).
+const BEHAVIOR_IDS_FIELD_NAME: &str = "_PRIVATE_14_behavior_ids";
+
+/// A single agent of a [`GroupState`], which behaviors are executed on.
+///
+/// The accessors of the columns are generated by the `accessors!` macro in `behaviors`.
 pub struct AgentState<'s> {
-    // `index_in_group`, `inner` and `agent_batch` have to be public due to impl in accessors macro.
-    pub agent_batch: &'s mut RwLockWriteGuard<'s, AgentBatch>,
-    msg_batch: &'s mut RwLockWriteGuard<'s, MessageBatch>,
-    pub inner: &'s mut NativeState,
-    msgs: &'s mut Option<Vec<Vec<OutboundMessage>>>,
-    pub index_in_group: usize,
-    col_map: &'s Arc<HashMap<String, Box<dyn Column>>>,
-    // TODO: This should only be in the behavior execution package,
-    //       but currently is necessary for flushing.
-    all_behavior_col_names: &'s Arc<HashSet<String>>,
+    pub(super) inner: &'s mut NativeState,
+    pub(super) index_in_group: usize,
 }
 
-impl<'s> AgentState<'s> {
-    pub fn num_agents(&self) -> usize {
-        1
-    }
-
-    pub fn get_value(&self, field_name: &str) -> Result<serde_json::Value> {
-        self.col_map
-            .get(field_name)
-            .ok_or_else(|| Error::InvalidRustColumn(field_name.to_owned()))?
-            .get(self)
-    }
-
-    pub fn set_value(&'s mut self, field_name: &str, value: serde_json::Value) -> Result<()> {
-        self.col_map
-            .get(field_name)
-            .ok_or_else(|| Error::InvalidRustColumn(field_name.to_owned()))?
-            .set(self, value)
-    }
-
-    pub fn _set_index(&mut self, i_agent_in_group: usize) {
-        self.index_in_group = i_agent_in_group;
-    }
-
-    pub fn messages_mut(&mut self) -> Result<&mut [Vec<OutboundMessage>]> {
-        // TODO: the `OutboundMessage` struct is not lightweight
-        // enough for our purposes. We don't need this be an enum,
-        // because Arrow does not differentiate. Also because
-        // we could store agent ids as bytes, not in as hyphenated
-        // owned strings
-        if self.msgs.is_none() {
-            *self.msgs = Some(self.msg_batch.get_native_messages()?);
-        }
-
-        // SAFETY: will not fail as we've checked this is not None
-        Ok(&mut self.msgs.as_mut().unwrap()[self.index_in_group..=self.index_in_group])
-    }
+/// The agents of a single group (i.e. agent batch) of a task.
+///
+/// Changes made by behaviors are only written to the batch by [`GroupState::flush`].
+pub struct GroupState<'b> {
+    agent_batch: &'b mut AgentBatch,
+    inner: NativeState,
+    behavior_ids: Vec<Vec<[u16; 2]>>,
+}
 
-    pub fn messages_take(&mut self) -> Result<Vec<Vec<OutboundMessage>>> {
-        if self.msgs.is_none() {
-            *self.msgs = Some(self.msg_batch.get_native_messages()?);
+impl<'b> GroupState<'b> {
+    /// Loads the engine columns and `columns` from `agent_batch`.
+    pub fn load(agent_batch: &'b mut AgentBatch, columns: &HashSet<String>) -> Result<Self> {
+        let mut inner = NativeState::new(agent_batch.batch.clone());
+        for column in ENGINE_COLUMNS
+            .iter()
+            .copied()
+            .chain(columns.iter().map(String::as_str))
+        {
+            inner.load_column(column)?;
         }
-
-        // SAFETY: will not fail as we've checked this is not None
-        let agent_msgs =
-            std::mem::replace(&mut self.msgs.unwrap()[self.index_in_group], Vec::new());
-        Ok(vec![agent_msgs])
+        let behavior_ids = load_behavior_ids(&agent_batch.batch)?;
+        Ok(Self {
+            agent_batch,
+            inner,
+            behavior_ids,
+        })
     }
 
-    pub fn messages_set(&mut self, mut messages: Vec<Vec<OutboundMessage>>) {
-        self.msgs.unwrap()[self.index_in_group] = std::mem::replace(&mut messages[0], Vec::new());
+    pub fn num_agents(&self) -> usize {
+        self.behavior_ids.len()
     }
 
-    pub fn messages_commit(&mut self) -> Result<()> {
-        if self.msgs.is_none() {
-            Ok(())
-        } else {
-            // SAFETY: will not fail as we've checked this is not None
-            let msgs = self.msgs.as_ref().unwrap();
-            let builder = get_message_arrow_builder();
-            let message_column = outbound_messages_to_arrow_column(&msgs, builder)?;
-            self.msg_batch.batch.push_change(ArrayChange {
-                array: message_column.data(),
-                index: MESSAGE_COLUMN_INDEX,
-            })?;
-            Ok(())
-        }
+    /// Behavior chain of the agent for the current step.
+    pub fn behavior_ids(&self, i_agent_in_group: usize) -> &[[u16; 2]] {
+        &self.behavior_ids[i_agent_in_group]
     }
-}
-
-// TODO: When we have packages other than behavior execution, also need read-only group state
-pub struct GroupState<'s> {
-    // `inner` and `agent_batch` have to be public due to impl in accessors macro.
-    pub agent_batch: RwLockWriteGuard<'s, AgentBatch>,
-    msg_batch: RwLockWriteGuard<'s, MessageBatch>,
-    pub inner: NativeState,
-    msgs: Option<Vec<Vec<OutboundMessage>>>,
-    schema: &'s SimSchema,
-    col_map: &'s Arc<HashMap<String, Box<dyn Column>>>,
-    // TODO: This should only be in the behavior execution package,
-    //       but currently is necessary for flushing.
-    all_behavior_col_names: &'s Arc<HashSet<String>>,
-}
 
-impl<'s> GroupState<'s> {
-    pub fn num_agents(&self) -> usize {
-        self.agent_batch.num_agents()
-    }
-
-    pub fn get_agent(&'s mut self, i_agent_in_group: usize) -> AgentState<'s> {
+    pub fn agent(&mut self, i_agent_in_group: usize) -> AgentState<'_> {
         AgentState {
-            agent_batch: &mut self.agent_batch,
-            msg_batch: &mut self.msg_batch,
             inner: &mut self.inner,
-            msgs: &mut self.msgs,
-            col_map: self.col_map,
-            all_behavior_col_names: self.all_behavior_col_names,
             index_in_group: i_agent_in_group,
         }
     }
 
-    pub fn data_ref(&'s self, i_field: usize) -> &'s Arc<ArrayData> {
-        self.agent_batch.batch.column(i_field).data_ref()
-    }
-
-    fn commit_messages(&mut self) -> Result<()> {
-        if let Some(ref msgs) = self.msgs {
-            let builder = get_message_arrow_builder();
-            let message_column = outbound_messages_to_arrow_column(msgs, builder)?;
-            self.msg_batch.batch.push_change(ArrayChange {
-                array: message_column.data(),
-                index: MESSAGE_COLUMN_INDEX,
-            })?;
-        }
-        Ok(())
-    }
-
-    pub fn flush(&mut self) -> Result<()> {
-        // flush(
-        //     &mut self.agent_batch,
-        //     &mut self.msg_batch,
-        //     &mut self.msgs,
-        //     &mut self.col_map,
-        //     &mut self.all_behavior_col_names,
-        // )
-        // Flush agent state
-        for col_name in self.all_behavior_col_names.iter() {
-            let b = self
-                .col_map
-                .get(col_name)
-                .ok_or_else(|| Error::InvalidRustColumn(col_name.clone()))?;
-
-            b.commit(self)?;
+    /// Writes all columns that were modified back to the agent batch.
+    pub fn flush(self) -> Result<()> {
+        for change in self.inner.changes()? {
+            self.agent_batch.push_change(change)?;
         }
-        self.agent_batch.batch.flush_changes()?;
-
-        // Flush message state
-        self.commit_messages()?;
-        self.msg_batch.batch.flush_changes()?;
+        self.agent_batch.flush_changes()?;
         Ok(())
     }
-
-    pub fn get_json_state(&'s mut self) -> Result<Vec<Agent>> {
-        self.flush()?;
-        let json_state = (&*self.agent_batch, &*self.msg_batch)
-            .into_agent_states(Some(&self.schema.agent))
-            .map_err(|e| Error::from(e.to_string()))?;
-        Ok(json_state)
-    }
-}
-
-#[derive(new)]
-pub struct SimState {
-    schema: SimSchema,
-    pub agent_pool: Vec<Arc<RwLock<AgentBatch>>>,
-    pub msg_pool: Vec<Arc<RwLock<MessageBatch>>>,
-
-    col_map: Arc<HashMap<String, Box<dyn Column>>>,
-
-    // TODO: This should only be in the behavior execution package,
-    //       but currently is necessary for flushing.
-    all_behavior_col_names: Arc<HashSet<String>>,
 }
 
-impl SimState {
-    pub fn get_group<'s>(&'s mut self, i_group: usize) -> Result<GroupState<'s>> {
-        let agent_batch = self.agent_pool[i_group]
-            .try_write()
-            .expect("Should be able to write to group state agent batch");
-        let msg_batch = self.msg_pool[i_group]
-            .try_write()
-            .expect("Should be able to write to group state message batch");
-
-        let inner = NativeState::from_column_set(
-            &self.all_behavior_col_names,
-            &self.schema.agent.arrow,
-            &agent_batch,
-        )?;
-
-        Ok(GroupState {
-            schema: &self.schema,
-            all_behavior_col_names: &self.all_behavior_col_names,
-            col_map: &self.col_map,
-            agent_batch,
-            msg_batch,
-            inner,
-            msgs: None,
+fn load_behavior_ids(batch: &RecordBatch) -> Result<Vec<Vec<[u16; 2]>>> {
+    let downcast_error = || Error::InvalidArrowDowncast(BEHAVIOR_IDS_FIELD_NAME.to_string());
+
+    let index = batch
+        .schema()
+        .index_of(BEHAVIOR_IDS_FIELD_NAME)
+        .map_err(|_| Error::InvalidRustColumn(BEHAVIOR_IDS_FIELD_NAME.to_string()))?;
+    let column = batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<array::ListArray>()
+        .ok_or_else(downcast_error)?;
+
+    (0..column.len())
+        .map(|i_agent| {
+            let ids = column.value(i_agent);
+            let ids = ids
+                .as_any()
+                .downcast_ref::<array::FixedSizeListArray>()
+                .ok_or_else(downcast_error)?;
+            (0..ids.len())
+                .map(|i_id| {
+                    let id = ids.value(i_id);
+                    let id = id
+                        .as_any()
+                        .downcast_ref::<array::UInt16Array>()
+                        .ok_or_else(downcast_error)?;
+                    Ok([id.value(0), id.value(1)])
+                })
+                .collect()
         })
-    }
-}
-
-pub struct StateSnapshot {
-    pub agent_pool: Vec<Arc<RwLock<AgentBatch>>>,
-    pub msg_pool: Vec<Arc<RwLock<MessageBatch>>>,
+        .collect()
 }
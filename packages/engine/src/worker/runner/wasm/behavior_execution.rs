@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+use wasmtime::{Config, Engine, Instance, Memory, Module, Store, Trap, TrapCode, TypedFunc};
+
+use super::{
+    error::{Error, Result},
+    group::{is_private, GroupColumns, BEHAVIOR_INDEX_FIELD_NAME},
+};
+use crate::{
+    datastore::arrow::message::MESSAGE_COLUMN_NAME,
+    hash_types::state::AgentStateField,
+    simulation::package::{
+        id::PackageId, state::packages::behavior_execution::config::BehaviorDescription,
+        worker_init::PackageInitMsgForWorker,
+    },
+    worker::runner::comms::{outbound::RunnerError, MessageTarget},
+    Language,
+};
+
+/// Creates the engine behaviors are compiled with.
+///
+/// Behaviors are interrupted by incrementing the engine's epoch (see
+/// [`BehaviorPackage::set_deadline`]), as they could otherwise loop forever.
+pub fn engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.epoch_interruption(true);
+    Engine::new(&config)
+        .map_err(|e| Error::from(format!("Couldn't create WebAssembly engine: {e}")))
+}
+
+/// An instantiated behavior module.
+///
+/// Modules have to export
+/// - `memory`,
+/// - `alloc(len: i32) -> i32`, which returns a pointer to `len` bytes of memory, and
+/// - `behavior(ptr: i32, len: i32) -> i64`, which is called with the JSON input of the behavior and
+///   returns the pointer to its JSON output in the upper 32 bits and the length of the output in
+///   the lower 32 bits.
+///
+/// If the module also exports `dealloc(ptr: i32, len: i32)`, the input and output are freed
+/// after every call.
+///
+/// Behaviors are called once for all agents of a group which run them next, see
+/// [`BehaviorPackage::run_group`] for the input and output.
+struct WasmBehavior {
+    /// Columns the behavior gets, or `None` if it has dynamic access, i.e. gets all columns.
+    columns: Option<Vec<String>>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    behavior: TypedFunc<(i32, i32), i64>,
+    dealloc: Option<TypedFunc<(i32, i32), ()>>,
+}
+
+impl WasmBehavior {
+    fn instantiate(
+        store: &mut Store<()>,
+        name: &str,
+        source: &str,
+        columns: Option<Vec<String>>,
+    ) -> Result<Self> {
+        let invalid_module =
+            |e: &dyn std::fmt::Display| Error::InvalidModule(name.to_string(), e.to_string());
+
+        let binary =
+            base64::decode(source).map_err(|e| Error::InvalidEncoding(name.to_string(), e))?;
+        let module = Module::new(store.engine(), binary).map_err(|e| invalid_module(&e))?;
+        // Behaviors can't import anything from the engine, they only see their input.
+        let instance = Instance::new(&mut *store, &module, &[]).map_err(|e| invalid_module(&e))?;
+
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| invalid_module(&"Missing exported memory `memory`"))?;
+        let alloc = instance
+            .get_typed_func(&mut *store, "alloc")
+            .map_err(|e| invalid_module(&e))?;
+        let behavior = instance
+            .get_typed_func(&mut *store, "behavior")
+            .map_err(|e| invalid_module(&e))?;
+        let dealloc = match instance.get_func(&mut *store, "dealloc") {
+            Some(func) => Some(func.typed(&*store).map_err(|e| invalid_module(&e))?),
+            None => None,
+        };
+
+        Ok(Self {
+            columns,
+            memory,
+            alloc,
+            behavior,
+            dealloc,
+        })
+    }
+
+    /// Runs the behavior on `input` and returns its output.
+    ///
+    /// Traps and invalid pointers are returned as [`Error::Behavior`], since they are caused by
+    /// the behavior, except for interruptions, which are returned as [`Error::Interrupted`].
+    fn call(&self, store: &mut Store<()>, input: &[u8]) -> Result<Vec<u8>> {
+        let behavior_error = |e: &dyn std::fmt::Display| Error::Behavior(e.to_string());
+        let trap_error = |trap: Trap| match trap.trap_code() {
+            Some(TrapCode::Interrupt) => Error::Interrupted,
+            _ => Error::Behavior(trap.to_string()),
+        };
+
+        let input_len = i32::try_from(input.len()).map_err(|e| behavior_error(&e))?;
+        let input_ptr = self
+            .alloc
+            .call(&mut *store, input_len)
+            .map_err(trap_error)?;
+        self.memory
+            .write(&mut *store, input_ptr as u32 as usize, input)
+            .map_err(|e| behavior_error(&e))?;
+
+        let packed = self
+            .behavior
+            .call(&mut *store, (input_ptr, input_len))
+            .map_err(trap_error)?;
+        let output_ptr = ((packed as u64) >> 32) as u32;
+        let output_len = packed as u64 as u32;
+        let mut output = vec![0; output_len as usize];
+        self.memory
+            .read(&*store, output_ptr as usize, &mut output)
+            .map_err(|e| behavior_error(&e))?;
+
+        if let Some(dealloc) = &self.dealloc {
+            dealloc
+                .call(&mut *store, (input_ptr, input_len))
+                .map_err(trap_error)?;
+            dealloc
+                .call(&mut *store, (output_ptr as i32, output_len as i32))
+                .map_err(trap_error)?;
+        }
+        Ok(output)
+    }
+}
+
+struct Behavior {
+    name: String,
+    language: Language,
+    /// Only WebAssembly behaviors can be executed by this runner.
+    module: Option<WasmBehavior>,
+}
+
+/// The WebAssembly part of the behavior execution package.
+pub struct BehaviorPackage {
+    id: PackageId,
+    store: Store<()>,
+    behaviors: HashMap<[u16; 2], Behavior>,
+}
+
+impl BehaviorPackage {
+    pub fn id(&self) -> PackageId {
+        self.id
+    }
+
+    pub fn start_experiment(init: &PackageInitMsgForWorker, engine: &Engine) -> Result<Self> {
+        let descs: Vec<BehaviorDescription> = serde_json::from_value(init.payload.clone())?;
+        let mut store = Store::new(engine, ());
+        // Modules with a start function are already run when they are instantiated
+        store.set_epoch_deadline(1);
+        let mut behaviors = HashMap::new();
+        for desc in descs {
+            let module = if desc.language == Language::Wasm {
+                let columns = (!desc.dyn_access).then(|| desc.required_field_keys.clone());
+                Some(WasmBehavior::instantiate(
+                    &mut store,
+                    &desc.name,
+                    &desc.source,
+                    columns,
+                )?)
+            } else {
+                None
+            };
+
+            let id = [desc.id.lang_index(), desc.id.lang_behavior_index()];
+            let behavior = Behavior {
+                name: desc.name,
+                language: desc.language,
+                module,
+            };
+            if behaviors.insert(id, behavior).is_some() {
+                return Err(Error::from(format!("Duplicate behavior id: {id:?}")));
+            }
+        }
+
+        Ok(Self {
+            id: init.id,
+            store,
+            behaviors,
+        })
+    }
+
+    /// Lets behaviors run until the epoch of the engine is incremented the next time.
+    ///
+    /// Has to be called before running a task, as behaviors are interrupted as soon as the epoch
+    /// passed their deadline.
+    pub fn set_deadline(&mut self) {
+        self.store.set_epoch_deadline(1);
+    }
+
+    /// Runs the behavior chains of the agents of `group`, starting at their `behavior_index`,
+    /// until the chain ends or a behavior in another language is reached.
+    ///
+    /// Every behavior is called once with all agents which run it next, so a chain is executed in
+    /// rounds. Behaviors get the JSON object
+    /// `{"state": {<field>: [<value of each agent>]}, "context": <context>}` as input, with the
+    /// fields listed in their keys (or all fields, if they have dynamic access) and the outbound
+    /// `messages` of the agents. Private fields aren't passed to behaviors. Behaviors return the
+    /// columns they changed in the same format, i.e. `{<field>: [<new value of each agent>]}`.
+    ///
+    /// Returns the language the task has to continue in, if any, and the errors of behaviors.
+    /// Like in the other runners, agents whose behavior failed skip the rest of their chain for
+    /// this step.
+    pub fn run_group(
+        &mut self,
+        group: &mut GroupColumns,
+        context: &Value,
+    ) -> Result<(MessageTarget, Vec<RunnerError>)> {
+        let behavior_ids = group.behavior_ids()?;
+        let mut next_target = MessageTarget::Main;
+        let mut errors = Vec::new();
+
+        let mut pending: Vec<usize> = (0..group.num_agents()).collect();
+        while !pending.is_empty() {
+            // Agents by the behavior they run next
+            let mut rounds: BTreeMap<[u16; 2], Vec<usize>> = BTreeMap::new();
+            for i_agent in pending.drain(..) {
+                let i_behavior = group.behavior_index(i_agent)?;
+                let behavior_id = match behavior_ids[i_agent].get(i_behavior) {
+                    Some(behavior_id) => *behavior_id,
+                    None => continue,
+                };
+                let behavior = self
+                    .behaviors
+                    .get(&behavior_id)
+                    .ok_or(Error::InvalidBehavior(behavior_id))?;
+                if behavior.module.is_some() {
+                    rounds.entry(behavior_id).or_default().push(i_agent);
+                } else {
+                    // Multiple assignments are fine.
+                    next_target = MessageTarget::from(behavior.language);
+                }
+            }
+
+            for (behavior_id, agents) in rounds {
+                let behavior = &self.behaviors[&behavior_id];
+                let module = match &behavior.module {
+                    Some(module) => module,
+                    None => continue,
+                };
+                match run_behavior(&mut self.store, module, group, &agents, context) {
+                    Ok(()) => {
+                        for &i_agent in &agents {
+                            let i_behavior = group.behavior_index(i_agent)?;
+                            group.set_behavior_index(i_agent, i_behavior + 1)?;
+                        }
+                        pending.extend(agents);
+                    }
+                    Err(Error::Behavior(error)) => {
+                        errors.push(RunnerError {
+                            message: Some(format!("Behavior error: {error}")),
+                            details: None,
+                            file_name: Some(behavior.name.clone()),
+                            line_number: None,
+                        });
+                        for &i_agent in &agents {
+                            group.set_behavior_index(i_agent, behavior_ids[i_agent].len())?;
+                        }
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        Ok((next_target, errors))
+    }
+}
+
+/// Runs `module` on the agents `agents` of `group`.
+fn run_behavior(
+    store: &mut Store<()>,
+    module: &WasmBehavior,
+    group: &mut GroupColumns,
+    agents: &[usize],
+    context: &Value,
+) -> Result<()> {
+    let mut columns = match &module.columns {
+        Some(columns) => columns.clone(),
+        None => group.accessible_columns(),
+    };
+    columns.retain(|name| !is_private(name));
+    if !columns.iter().any(|name| name == MESSAGE_COLUMN_NAME) {
+        columns.push(MESSAGE_COLUMN_NAME.to_string());
+    }
+    let input = serde_json::to_vec(&serde_json::json!({
+        "state": group.rows(&columns, agents)?,
+        "context": context,
+    }))?;
+
+    let output = module.call(store, &input)?;
+    let changes: serde_json::Map<String, Value> = serde_json::from_slice(&output)
+        .map_err(|e| Error::Behavior(format!("Behavior returned invalid JSON: {e}")))?;
+    for (name, values) in changes {
+        if is_private(&name)
+            || name == BEHAVIOR_INDEX_FIELD_NAME
+            || name == AgentStateField::AgentId.name()
+        {
+            return Err(Error::Behavior(format!("Field `{name}` can't be set")));
+        }
+        let values = match values {
+            Value::Array(values) => values,
+            _ => {
+                return Err(Error::Behavior(format!(
+                    "Column `{name}` has to be an array with a value for each agent"
+                )));
+            }
+        };
+        group.set_rows(&name, agents, values)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::datastore::{
+        arrow::{batch_conversion::IntoRecordBatch, message::MESSAGE_BATCH_SCHEMA},
+        test_utils::gen_schema_and_test_agents,
+    };
+
+    /// A behavior module which ignores its input and always returns `output`.
+    fn constant_behavior(output: &str) -> String {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "behavior") (param i32 i32) (result i64) (i64.const {})))"#,
+            output.replace('"', "\\\""),
+            output.len()
+        );
+        base64::encode(wat)
+    }
+
+    /// A behavior module which never returns.
+    fn looping_behavior() -> String {
+        base64::encode(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "behavior") (param i32 i32) (result i64)
+                    (loop $forever (br $forever))
+                    (i64.const 0)))"#,
+        )
+    }
+
+    fn run(engine: &Engine, source: &str, num_agents: usize) -> (Result<()>, GroupColumns) {
+        let (schema, agents) = gen_schema_and_test_agents(num_agents, 0).unwrap();
+        let mut group = GroupColumns::new(
+            agents.as_slice().into_agent_batch(&schema).unwrap(),
+            agents
+                .as_slice()
+                .into_message_batch(&Arc::new(MESSAGE_BATCH_SCHEMA.clone()))
+                .unwrap(),
+            schema,
+        );
+
+        let mut store = Store::new(engine, ());
+        store.set_epoch_deadline(1);
+        let module = WasmBehavior::instantiate(
+            &mut store,
+            "test.wasm",
+            source,
+            Some(vec!["age".to_string()]),
+        )
+        .unwrap();
+        let rows: Vec<_> = (0..num_agents).collect();
+        let result = run_behavior(&mut store, &module, &mut group, &rows, &json!({}));
+        (result, group)
+    }
+
+    #[test]
+    fn behavior_changes_columns_of_all_agents() {
+        let source =
+            constant_behavior(r#"{"age":[5,6],"messages":[[],[{"to":["a"],"type":"hi"}]]}"#);
+        let (result, mut group) = run(&engine().unwrap(), &source, 2);
+        result.unwrap();
+
+        let columns = ["age".to_string(), MESSAGE_COLUMN_NAME.to_string()];
+        let rows = group.rows(&columns, &[0, 1]).unwrap();
+        assert_eq!(rows["age"], json!([5, 6]));
+        assert_eq!(rows["messages"][0], json!([]));
+        assert_eq!(rows["messages"][1][0]["to"], json!(["a"]));
+        assert_eq!(rows["messages"][1][0]["type"], json!("hi"));
+    }
+
+    #[test]
+    fn columns_without_a_value_for_each_agent_are_behavior_errors() {
+        let source = constant_behavior(r#"{"age":[5]}"#);
+        let (result, _) = run(&engine().unwrap(), &source, 2);
+        assert!(matches!(result, Err(Error::Behavior(_))));
+
+        let source = constant_behavior(r#"{"behavior_index":[0,0]}"#);
+        let (result, _) = run(&engine().unwrap(), &source, 2);
+        assert!(matches!(result, Err(Error::Behavior(_))));
+    }
+
+    #[test]
+    fn looping_behavior_is_interrupted() {
+        let engine = engine().unwrap();
+        let interrupter = {
+            let engine = engine.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                engine.increment_epoch();
+            })
+        };
+        let (result, _) = run(&engine, &looping_behavior(), 2);
+        interrupter.join().unwrap();
+        assert!(matches!(result, Err(Error::Interrupted)));
+    }
+}
@@ -0,0 +1,83 @@
+use arrow::error::ArrowError;
+use thiserror::Error as ThisError;
+use tokio::sync::mpsc::error::SendError;
+
+use crate::{
+    proto::SimulationShortId,
+    simulation::package::id::PackageId,
+    worker::runner::comms::{inbound::InboundToRunnerMsgPayload, outbound::OutboundFromRunnerMsg},
+};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    Unique(String),
+
+    #[error("Can't start WebAssembly runner again when it is already running")]
+    AlreadyRunning,
+
+    #[error("Arrow: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Datastore: {0}")]
+    Datastore(#[from] crate::datastore::error::Error),
+
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Agent state: {0}")]
+    AgentState(#[from] crate::hash_types::error::Error),
+
+    #[error("Couldn't decode WebAssembly module of behavior {0}: {1}")]
+    InvalidEncoding(String, base64::DecodeError),
+
+    #[error("Couldn't load WebAssembly module of behavior {0}: {1}")]
+    InvalidModule(String, String),
+
+    #[error("Behavior error: {0}")]
+    Behavior(String),
+
+    #[error("Behavior was interrupted")]
+    Interrupted,
+
+    #[error("Missing simulation run with id {0}")]
+    MissingSimulationRun(SimulationShortId),
+
+    #[error("Couldn't terminate missing simulation run with id {0}")]
+    TerminateMissingSimulationRun(SimulationShortId),
+
+    #[error("Duplicate simulation run id: {0}")]
+    DuplicateSimulationRun(SimulationShortId),
+
+    #[error("WebAssembly runner has no package with id {0:?}")]
+    UnknownPackage(PackageId),
+
+    #[error("Invalid behavior id: {0:?}")]
+    InvalidBehavior([u16; 2]),
+
+    #[error("Couldn't send inbound message to runner: {0}")]
+    InboundSend(#[from] SendError<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>),
+
+    #[error("Couldn't send outbound message from runner: {0}")]
+    OutboundSend(#[from] SendError<OutboundFromRunnerMsg>),
+
+    #[error("Couldn't receive outbound message from runner")]
+    OutboundReceive,
+
+    #[error("Message type '{0}' must have a simulation run id")]
+    SimulationIdRequired(&'static str),
+}
+
+impl From<&str> for Error {
+    fn from(s: &str) -> Self {
+        Error::Unique(s.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(s: String) -> Self {
+        Error::Unique(s)
+    }
+}
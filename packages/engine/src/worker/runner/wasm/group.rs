@@ -0,0 +1,226 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{self, Array},
+    record_batch::RecordBatch,
+};
+use serde_json::Value;
+
+use super::error::{Error, Result};
+use crate::datastore::{
+    arrow::{
+        batch_conversion::{agent_column_to_json_vals, json_vals_to_agent_column},
+        message::{self, MESSAGE_COLUMN_INDEX, MESSAGE_COLUMN_NAME},
+    },
+    batch::{change::ArrayChange, DynamicBatch},
+    schema::{state::AgentSchema, HIDDEN_PREFIX, PRIVATE_PREFIX},
+    table::proxy::StateWriteProxy,
+};
+
+// TODO: Propagate field specs to runners instead of hard-coding the private field name (same as
+//       in the Rust runner).
+const BEHAVIOR_IDS_FIELD_NAME: &str = "_PRIVATE_14_behavior_ids";
+pub const BEHAVIOR_INDEX_FIELD_NAME: &str = "behavior_index";
+
+pub fn is_private(field_name: &str) -> bool {
+    field_name.starts_with(PRIVATE_PREFIX) || field_name.starts_with(HIDDEN_PREFIX)
+}
+
+/// The columns of a single group (i.e. agent batch) of a task as JSON values, one per agent.
+///
+/// Columns are only converted when a behavior uses them, and only the columns behaviors changed
+/// are written back by [`GroupColumns::flush`]. The outbound messages of the agents are the
+/// `messages` column.
+pub struct GroupColumns {
+    agents: RecordBatch,
+    messages: RecordBatch,
+    agent_schema: Arc<AgentSchema>,
+    columns: HashMap<String, Vec<Value>>,
+    changed: HashSet<String>,
+}
+
+impl GroupColumns {
+    pub fn new(agents: RecordBatch, messages: RecordBatch, agent_schema: Arc<AgentSchema>) -> Self {
+        Self {
+            agents,
+            messages,
+            agent_schema,
+            columns: HashMap::new(),
+            changed: HashSet::new(),
+        }
+    }
+
+    pub fn num_agents(&self) -> usize {
+        self.agents.num_rows()
+    }
+
+    /// Names of all columns behaviors with dynamic access get, i.e. all columns which aren't
+    /// private.
+    pub fn accessible_columns(&self) -> Vec<String> {
+        self.agents
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name())
+            .filter(|name| !is_private(name))
+            .cloned()
+            .chain(std::iter::once(MESSAGE_COLUMN_NAME.to_string()))
+            .collect()
+    }
+
+    fn column(&mut self, name: &str) -> Result<&mut Vec<Value>> {
+        if !self.columns.contains_key(name) {
+            let values = if name == MESSAGE_COLUMN_NAME {
+                let column = self
+                    .messages
+                    .column(MESSAGE_COLUMN_INDEX)
+                    .as_any()
+                    .downcast_ref::<array::ListArray>()
+                    .ok_or_else(|| Error::from("Messages column isn't a list"))?;
+                message::get_column_from_list_array(column)?
+                    .into_iter()
+                    .map(serde_json::to_value)
+                    .collect::<serde_json::Result<_>>()?
+            } else {
+                agent_column_to_json_vals(&self.agents, &self.agent_schema, name)?
+            };
+            self.columns.insert(name.to_string(), values);
+        }
+        Ok(self
+            .columns
+            .get_mut(name)
+            .expect("Column was inserted above"))
+    }
+
+    /// Returns the values of the agents `rows` in each of the columns `names`.
+    pub fn rows(
+        &mut self,
+        names: &[String],
+        rows: &[usize],
+    ) -> Result<serde_json::Map<String, Value>> {
+        names
+            .iter()
+            .map(|name| {
+                let column = self.column(name)?;
+                let values = rows.iter().map(|&row| column[row].clone()).collect();
+                Ok((name.clone(), Value::Array(values)))
+            })
+            .collect()
+    }
+
+    /// Sets the values of the agents `rows` in the column `name`.
+    ///
+    /// Values which don't fit the column are returned as [`Error::Behavior`], since they were set
+    /// by a behavior.
+    pub fn set_rows(&mut self, name: &str, rows: &[usize], values: Vec<Value>) -> Result<()> {
+        if values.len() != rows.len() {
+            return Err(Error::Behavior(format!(
+                "Column `{name}` has {} values, but the behavior was run on {} agents",
+                values.len(),
+                rows.len()
+            )));
+        }
+        let invalid_value = |e: &dyn std::fmt::Display| {
+            Error::Behavior(format!("Couldn't set field `{name}`: {e}"))
+        };
+        if name == MESSAGE_COLUMN_NAME {
+            message::messages_column_from_serde_values(values.clone())
+                .map_err(|e| invalid_value(&e))?;
+        } else {
+            let schema = self.agents.schema();
+            let field = schema
+                .column_with_name(name)
+                .map(|(_, field)| field)
+                .ok_or_else(|| invalid_value(&"Unknown field"))?;
+            json_vals_to_agent_column(values.clone(), &self.agent_schema, field)
+                .map_err(|e| invalid_value(&e))?;
+        }
+
+        let column = self.column(name)?;
+        for (&row, value) in rows.iter().zip(values) {
+            column[row] = value;
+        }
+        self.changed.insert(name.to_string());
+        Ok(())
+    }
+
+    pub fn behavior_index(&mut self, row: usize) -> Result<usize> {
+        self.column(BEHAVIOR_INDEX_FIELD_NAME)?[row]
+            .as_f64()
+            .map(|index| index as usize)
+            .ok_or_else(|| Error::from("Agent is missing its behavior index"))
+    }
+
+    pub fn set_behavior_index(&mut self, row: usize, index: usize) -> Result<()> {
+        self.column(BEHAVIOR_INDEX_FIELD_NAME)?[row] = Value::from(index as f64);
+        self.changed.insert(BEHAVIOR_INDEX_FIELD_NAME.to_string());
+        Ok(())
+    }
+
+    /// Behavior chains of the agents for the current step.
+    pub fn behavior_ids(&self) -> Result<Vec<Vec<[u16; 2]>>> {
+        load_behavior_ids(&self.agents)
+    }
+
+    /// Writes all columns that were changed back to the batches of the group `i_proxy`.
+    pub fn flush(mut self, proxy: &mut StateWriteProxy, i_proxy: usize) -> Result<()> {
+        if let Some(values) = self.columns.remove(MESSAGE_COLUMN_NAME) {
+            if self.changed.remove(MESSAGE_COLUMN_NAME) {
+                let column = message::messages_column_from_serde_values(values)?;
+                let message_batch = proxy.message_pool_mut().batch_mut(i_proxy)?;
+                message_batch.push_change(ArrayChange::new(column.data(), MESSAGE_COLUMN_INDEX))?;
+                message_batch.flush_changes()?;
+            }
+        }
+
+        let agent_batch = proxy.agent_pool_mut().batch_mut(i_proxy)?;
+        let schema = self.agents.schema();
+        for (i_column, field) in schema.fields().iter().enumerate() {
+            if let Some(values) = self.columns.remove(field.name()) {
+                if self.changed.contains(field.name()) {
+                    let column = json_vals_to_agent_column(values, &self.agent_schema, field)?;
+                    agent_batch.push_change(ArrayChange::new(column.data(), i_column))?;
+                }
+            }
+        }
+        agent_batch.flush_changes()?;
+        Ok(())
+    }
+}
+
+fn load_behavior_ids(batch: &RecordBatch) -> Result<Vec<Vec<[u16; 2]>>> {
+    let downcast_error = || Error::from(format!("Invalid {BEHAVIOR_IDS_FIELD_NAME} column"));
+
+    let index = batch
+        .schema()
+        .index_of(BEHAVIOR_IDS_FIELD_NAME)
+        .map_err(|_| Error::from("Agents are missing their behavior ids"))?;
+    let column = batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<array::ListArray>()
+        .ok_or_else(downcast_error)?;
+
+    (0..column.len())
+        .map(|i_agent| {
+            let ids = column.value(i_agent);
+            let ids = ids
+                .as_any()
+                .downcast_ref::<array::FixedSizeListArray>()
+                .ok_or_else(downcast_error)?;
+            (0..ids.len())
+                .map(|i_id| {
+                    let id = ids.value(i_id);
+                    let id = id
+                        .as_any()
+                        .downcast_ref::<array::UInt16Array>()
+                        .ok_or_else(downcast_error)?;
+                    Ok([id.value(0), id.value(1)])
+                })
+                .collect()
+        })
+        .collect()
+}
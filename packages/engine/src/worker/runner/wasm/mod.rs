@@ -0,0 +1,520 @@
+mod behavior_execution;
+mod error;
+mod group;
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+    result::Result as StdResult,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub use error::{Error, Result};
+use futures::FutureExt;
+use parking_lot::{Condvar, Mutex};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinError,
+};
+use wasmtime::Engine;
+
+use self::{behavior_execution::BehaviorPackage, group::GroupColumns};
+use super::{
+    comms::{
+        inbound::InboundToRunnerMsgPayload,
+        outbound::{OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerError},
+        ExperimentInitRunnerMsg, MessageTarget, NewSimulationRun, RunnerTaskMsg,
+        TargetedRunnerTaskMsg,
+    },
+    LanguageWorker,
+};
+use crate::{
+    config::Globals,
+    datastore::{
+        schema::state::AgentSchema,
+        table::{
+            sync::WaitableStateSync,
+            task_shared_store::{PartialSharedState, SharedState},
+        },
+    },
+    proto::SimulationShortId,
+    simulation::package::{name::PackageName, state},
+    types::TaskId,
+    worker::{Error as WorkerError, Result as WorkerResult},
+    Language,
+};
+
+/// Context of a simulation run, as synced to the runner.
+struct SimContext {
+    globals: Arc<Globals>,
+    agent_schema: Arc<AgentSchema>,
+    current_step: usize,
+}
+
+/// Why the behaviors of a task were interrupted.
+#[derive(Clone, Copy, Debug)]
+enum Interruption {
+    Cancelled,
+    TimedOut(Duration),
+}
+
+#[derive(Default)]
+struct InterruptState {
+    running: Option<TaskId>,
+    interruption: Option<Interruption>,
+    /// Tasks which were cancelled before they were run.
+    cancelled: HashSet<TaskId>,
+}
+
+/// Interrupts the behaviors of the running task when it's cancelled or runs for longer than the
+/// task timeout, by incrementing the epoch of the engine the behaviors were compiled with.
+///
+/// The runner sets a new deadline before every task (see [`BehaviorPackage::set_deadline`]), so an
+/// interruption never hits another task.
+struct TaskInterrupts {
+    engine: Engine,
+    task_timeout: Option<Duration>,
+    state: Mutex<InterruptState>,
+    finished: Condvar,
+}
+
+impl TaskInterrupts {
+    fn new(task_timeout: Option<Duration>) -> Result<Self> {
+        Ok(Self {
+            engine: behavior_execution::engine()?,
+            task_timeout,
+            state: Mutex::default(),
+            finished: Condvar::new(),
+        })
+    }
+
+    fn interrupt(&self, state: &mut InterruptState, interruption: Interruption) {
+        if state.interruption.is_none() {
+            state.interruption = Some(interruption);
+            self.engine.increment_epoch();
+        }
+    }
+
+    /// Interrupts the task if it's running, otherwise the runner skips it if it arrives later.
+    fn cancel(&self, task_id: TaskId) {
+        let mut state = self.state.lock();
+        if state.running == Some(task_id) {
+            self.interrupt(&mut state, Interruption::Cancelled);
+        } else {
+            state.cancelled.insert(task_id);
+        }
+    }
+
+    /// Marks `task_id` as running and starts its timer. Returns `false` if it was cancelled
+    /// before, so it mustn't run.
+    fn start(self: &Arc<Self>, task_id: TaskId) -> bool {
+        let mut state = self.state.lock();
+        if state.cancelled.remove(&task_id) {
+            return false;
+        }
+        state.running = Some(task_id);
+
+        if let Some(timeout) = self.task_timeout {
+            let interrupts = Arc::clone(self);
+            let deadline = Instant::now() + timeout;
+            std::thread::spawn(move || {
+                let mut state = interrupts.state.lock();
+                while state.running == Some(task_id) {
+                    if interrupts
+                        .finished
+                        .wait_until(&mut state, deadline)
+                        .timed_out()
+                    {
+                        if state.running == Some(task_id) {
+                            interrupts.interrupt(&mut state, Interruption::TimedOut(timeout));
+                        }
+                        break;
+                    }
+                }
+            });
+        }
+        true
+    }
+
+    /// Marks the running task as finished. Returns why it was interrupted, if it was.
+    fn finish(&self) -> Option<Interruption> {
+        let mut state = self.state.lock();
+        state.running = None;
+        self.finished.notify_all();
+        state.interruption.take()
+    }
+
+    /// Forgets the cancellation of `task_id` once the runner handled the cancel message.
+    fn forget(&self, task_id: TaskId) {
+        self.state.lock().cancelled.remove(&task_id);
+    }
+}
+
+/// Executes behaviors compiled to WebAssembly in an embedded runtime. Behavior execution is the
+/// only package with a WebAssembly part, so tasks of other packages are rejected.
+struct RunnerImpl {
+    behavior_execution: Option<BehaviorPackage>,
+    interrupts: Arc<TaskInterrupts>,
+    /// Rows of the step-indexed datasets by name and step.
+    step_datasets: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    sims: HashMap<SimulationShortId, SimContext>,
+}
+
+impl RunnerImpl {
+    fn new(init_msg: &ExperimentInitRunnerMsg, interrupts: Arc<TaskInterrupts>) -> Result<Self> {
+        let behavior_execution = init_msg
+            .package_config
+            .0
+            .values()
+            .find(|init| init.name == PackageName::State(state::Name::BehaviorExecution))
+            .map(|init| BehaviorPackage::start_experiment(init, &interrupts.engine))
+            .transpose()?;
+
+        let mut step_datasets = HashMap::new();
+        for (name, dataset) in &init_msg.shared_context.datasets {
+            if let Some(step_index) = dataset.step_index()? {
+                step_datasets.insert(name.clone(), step_index);
+            }
+        }
+
+        Ok(Self {
+            behavior_execution,
+            interrupts,
+            step_datasets,
+            sims: HashMap::new(),
+        })
+    }
+
+    fn start_sim(&mut self, run: NewSimulationRun) -> Result<()> {
+        let ctx = SimContext {
+            globals: run.globals,
+            agent_schema: run.datastore.agent_batch_schema,
+            current_step: 0,
+        };
+        self.sims
+            .try_insert(run.short_id, ctx)
+            .map_err(|_| Error::DuplicateSimulationRun(run.short_id))?;
+        Ok(())
+    }
+
+    fn sim_mut(&mut self, sim_run_id: SimulationShortId) -> Result<&mut SimContext> {
+        self.sims
+            .get_mut(&sim_run_id)
+            .ok_or(Error::MissingSimulationRun(sim_run_id))
+    }
+
+    fn state_sync(&mut self, sim_run_id: SimulationShortId, msg: WaitableStateSync) -> Result<()> {
+        // Behaviors are executed directly on the batches of the task's shared store, so there is
+        // no state to keep in the runner.
+        self.sim_mut(sim_run_id)?;
+
+        log::trace!("Sending state sync completion");
+        msg.completion_sender.send(Ok(())).map_err(|e| {
+            Error::from(format!(
+                "Couldn't send state sync completion to worker: {:?}",
+                e
+            ))
+        })?;
+        Ok(())
+    }
+
+    fn run_task(
+        &mut self,
+        sim_run_id: SimulationShortId,
+        mut msg: RunnerTaskMsg,
+    ) -> Result<(TargetedRunnerTaskMsg, Vec<RunnerError>)> {
+        let pkg = self
+            .behavior_execution
+            .as_mut()
+            .filter(|pkg| pkg.id() == msg.package_id)
+            .ok_or(Error::UnknownPackage(msg.package_id))?;
+        let ctx = self
+            .sims
+            .get(&sim_run_id)
+            .ok_or(Error::MissingSimulationRun(sim_run_id))?;
+
+        let (proxy, num_groups) = match &mut msg.shared_store.state {
+            SharedState::Write(state) => {
+                let num_groups = state.agent_pool().n_batches();
+                (state, num_groups)
+            }
+            SharedState::Partial(PartialSharedState::Write(state)) => {
+                let num_groups = state.indices.len();
+                (&mut state.inner, num_groups)
+            }
+            _ => return Err(Error::from("Behavior execution needs write access")),
+        };
+        // Behaviors only get the rows of the current step, as passing whole datasets to every call
+        // would be too expensive.
+        let step = ctx.current_step.to_string();
+        let step_data: serde_json::Map<_, _> = self
+            .step_datasets
+            .iter()
+            .map(|(name, rows)| {
+                let row = rows.get(&step).cloned().unwrap_or_default();
+                (name.clone(), row)
+            })
+            .collect();
+        let context = serde_json::json!({
+            "step": ctx.current_step,
+            "globals": &ctx.globals.0,
+            "step_data": step_data,
+        });
+
+        let mut next_target = MessageTarget::Main;
+        let mut errors = Vec::new();
+        for i_proxy in 0..num_groups {
+            let mut group = GroupColumns::new(
+                proxy.agent_pool().batch(i_proxy)?.batch.clone(),
+                proxy.message_pool().batch(i_proxy)?.batch.clone(),
+                Arc::clone(&ctx.agent_schema),
+            );
+            let (target, group_errors) = pkg.run_group(&mut group, &context)?;
+            group.flush(proxy, i_proxy)?;
+
+            errors.extend(group_errors);
+            next_target = next_target
+                .merge_group_target(target)
+                .map_err(|err| Error::from(err.to_string()))?;
+        }
+
+        let next_task_msg = TargetedRunnerTaskMsg {
+            target: next_target,
+            msg: RunnerTaskMsg {
+                package_id: msg.package_id,
+                task_id: msg.task_id,
+                shared_store: msg.shared_store,
+                payload: msg.payload,
+            },
+        };
+        Ok((next_task_msg, errors))
+    }
+
+    fn handle_msg(
+        &mut self,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+        outbound_sender: &UnboundedSender<OutboundFromRunnerMsg>,
+    ) -> Result<bool> {
+        match msg {
+            InboundToRunnerMsgPayload::TerminateRunner => {
+                log::debug!("Stopping execution on WebAssembly runner");
+                return Ok(false); // Don't continue running.
+            }
+            InboundToRunnerMsgPayload::NewSimulationRun(new_run) => {
+                self.start_sim(new_run)?;
+            }
+            InboundToRunnerMsgPayload::TerminateSimulationRun => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("terminate sim"))?;
+                self.sims
+                    .remove(&sim_id)
+                    .ok_or(Error::TerminateMissingSimulationRun(sim_id))?;
+            }
+            InboundToRunnerMsgPayload::StateSync(state_msg) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("state sync"))?;
+                self.state_sync(sim_id, state_msg)?;
+            }
+            InboundToRunnerMsgPayload::StateInterimSync(_)
+            | InboundToRunnerMsgPayload::StateSnapshotSync(_) => {
+                // Tasks carry their own shared store and behaviors don't get neighbors, so there
+                // is nothing to sync.
+            }
+            InboundToRunnerMsgPayload::ContextBatchSync(ctx_batch) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("context batch sync"))?;
+                self.sim_mut(sim_id)?.current_step = ctx_batch.current_step;
+            }
+            InboundToRunnerMsgPayload::GlobalsSync(globals_msg) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("globals sync"))?;
+                self.sim_mut(sim_id)?.globals = globals_msg.globals;
+            }
+            InboundToRunnerMsgPayload::TaskMsg(msg) => {
+                let sim_id = sim_id.ok_or(Error::SimulationIdRequired("run task"))?;
+                let task_id = msg.task_id;
+                let send_cancelled = || {
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::Wasm,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+                    })
+                };
+                // The deadline has to be set before the task can be interrupted, so an
+                // interruption always hits this task.
+                if let Some(pkg) = &mut self.behavior_execution {
+                    pkg.set_deadline();
+                }
+                if !self.interrupts.start(task_id) {
+                    log::debug!("Skipping task {task_id}, which was cancelled");
+                    send_cancelled()?;
+                    return Ok(true);
+                }
+                let result = self.run_task(sim_id, msg);
+                match self.interrupts.finish() {
+                    Some(Interruption::Cancelled) => {
+                        log::debug!("Task {task_id} was cancelled while running");
+                        send_cancelled()?;
+                        return Ok(true);
+                    }
+                    Some(Interruption::TimedOut(timeout)) => {
+                        // Only the task (and with it the simulation run) fails
+                        let message = format!(
+                            "Task was stopped after running for longer than the task timeout of \
+                             {timeout:?}"
+                        );
+                        log::warn!("{message}");
+                        outbound_sender.send(OutboundFromRunnerMsg {
+                            source: Language::Wasm,
+                            sim_id,
+                            payload: OutboundFromRunnerMsgPayload::RunnerError(RunnerError {
+                                message: Some(message),
+                                details: None,
+                                file_name: None,
+                                line_number: None,
+                            }),
+                        })?;
+                        send_cancelled()?;
+                        return Ok(true);
+                    }
+                    None => {}
+                }
+                let (next_task_msg, errors) = result?;
+                outbound_sender.send(OutboundFromRunnerMsg {
+                    source: Language::Wasm,
+                    sim_id,
+                    payload: OutboundFromRunnerMsgPayload::TaskMsg(next_task_msg),
+                })?;
+                if !errors.is_empty() {
+                    outbound_sender.send(OutboundFromRunnerMsg {
+                        source: Language::Wasm,
+                        sim_id,
+                        payload: OutboundFromRunnerMsgPayload::UserErrors(errors),
+                    })?;
+                }
+            }
+            InboundToRunnerMsgPayload::CancelTask(task_id) => {
+                // Running tasks were already interrupted and tasks which arrive later are skipped
+                // (see `TaskInterrupts`), so the task is done either way.
+                self.interrupts.forget(task_id);
+                outbound_sender.send(OutboundFromRunnerMsg {
+                    source: Language::Wasm,
+                    // The worker doesn't know the simulation run of tasks it's not running anymore.
+                    sim_id: sim_id.unwrap_or_default(),
+                    payload: OutboundFromRunnerMsgPayload::TaskCancelled(task_id),
+                })?;
+            }
+        }
+        Ok(true) // Continue running.
+    }
+}
+
+pub struct WasmRunner {
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    interrupts: Arc<TaskInterrupts>,
+    inbound_sender: UnboundedSender<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    inbound_receiver:
+        Option<UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>>,
+    outbound_sender: Option<UnboundedSender<OutboundFromRunnerMsg>>,
+    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
+    spawn: bool,
+}
+
+impl WasmRunner {
+    pub fn new(
+        spawn: bool,
+        init_msg: ExperimentInitRunnerMsg,
+        task_timeout: Option<Duration>,
+    ) -> WorkerResult<Self> {
+        let (inbound_sender, inbound_receiver) = unbounded_channel();
+        let (outbound_sender, outbound_receiver) = unbounded_channel();
+        Ok(Self {
+            init_msg: Arc::new(init_msg),
+            interrupts: Arc::new(TaskInterrupts::new(task_timeout)?),
+            inbound_sender,
+            inbound_receiver: Some(inbound_receiver),
+            outbound_sender: Some(outbound_sender),
+            outbound_receiver,
+            spawn,
+        })
+    }
+
+    pub async fn recv(&mut self) -> WorkerResult<OutboundFromRunnerMsg> {
+        self.outbound_receiver
+            .recv()
+            .await
+            .ok_or(WorkerError::Wasm(Error::OutboundReceive))
+    }
+
+    pub async fn recv_now(&mut self) -> WorkerResult<Option<OutboundFromRunnerMsg>> {
+        self.recv().now_or_never().transpose()
+    }
+
+    pub async fn run(
+        &mut self,
+    ) -> WorkerResult<Pin<Box<dyn Future<Output = StdResult<WorkerResult<()>, JoinError>> + Send>>>
+    {
+        log::debug!("Running WebAssembly runner");
+        if !self.spawn {
+            return Ok(Box::pin(async move { Ok(Ok(())) }));
+        }
+
+        let init_msg = Arc::clone(&self.init_msg);
+        let interrupts = Arc::clone(&self.interrupts);
+        let inbound_receiver = self.inbound_receiver.take().ok_or(Error::AlreadyRunning)?;
+        let outbound_sender = self.outbound_sender.take().ok_or(Error::AlreadyRunning)?;
+
+        let f = move || _run(init_msg, interrupts, inbound_receiver, outbound_sender);
+        Ok(Box::pin(tokio::task::spawn_blocking(f)))
+    }
+}
+
+#[async_trait::async_trait]
+impl LanguageWorker for WasmRunner {
+    async fn send(
+        &self,
+        sim_id: Option<SimulationShortId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> WorkerResult<()> {
+        log::trace!("Sending message to WebAssembly: {:?}", &msg);
+        if let InboundToRunnerMsgPayload::CancelTask(task_id) = &msg {
+            // The runner might be busy running the task, so it's interrupted from here
+            self.interrupts.cancel(*task_id);
+        }
+        self.inbound_sender
+            .send((sim_id, msg))
+            .map_err(|e| WorkerError::Wasm(Error::InboundSend(e)))
+    }
+
+    fn spawned(&self) -> bool {
+        self.spawn
+    }
+}
+
+fn _run(
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    interrupts: Arc<TaskInterrupts>,
+    mut inbound_receiver: UnboundedReceiver<(Option<SimulationShortId>, InboundToRunnerMsgPayload)>,
+    outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
+) -> WorkerResult<()> {
+    // Modules are compiled and instantiated here, so compilation doesn't block the worker.
+    let mut impl_ = RunnerImpl::new(&init_msg, interrupts)?;
+    while let Some((sim_id, msg)) = inbound_receiver.blocking_recv() {
+        let msg_str = msg.as_str();
+        log::debug!(
+            "WebAssembly runner got sim `{:?}` inbound {}",
+            &sim_id,
+            msg_str
+        );
+        let keep_running = impl_.handle_msg(sim_id, msg, &outbound_sender)?;
+        log::debug!(
+            "WebAssembly runner handled sim `{:?}` inbound {}",
+            sim_id,
+            msg_str
+        );
+        if !keep_running {
+            log::debug!("WebAssembly Runner has finished execution, stopping");
+            break;
+        }
+    }
+    Ok(())
+}
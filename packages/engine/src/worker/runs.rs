@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use super::{
+    error::{Error, Result},
+    runner::comms::{inbound::InboundToRunnerMsgPayload, NewSimulationRun},
+};
+use crate::{
+    datastore::table::sync::{
+        ContextBatchSync, GlobalsSync, StateSync, SyncPayload, WaitableStateSync,
+    },
+    proto::SimulationShortId,
+    simulation::comms::message::SyncCompletionReceiver,
+    Language,
+};
+
+/// Syncs which bring a respawned runner up to date, along with the receiver for the completion of
+/// the state sync among them.
+pub type CatchUp = (
+    Vec<InboundToRunnerMsgPayload>,
+    Option<SyncCompletionReceiver>,
+);
+
+/// The syncs of a step which a respawned runner didn't get yet.
+///
+/// Every step starts with a state snapshot sync, followed by a state sync and a context batch
+/// sync, so a runner has the complete data of a simulation run again once it got all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MissingSyncs {
+    state_snapshot: bool,
+    state: bool,
+    context_batch: bool,
+}
+
+impl MissingSyncs {
+    fn all() -> Self {
+        Self {
+            state_snapshot: true,
+            state: true,
+            context_batch: true,
+        }
+    }
+
+    fn received(&mut self, sync: &SyncPayload) {
+        match sync {
+            SyncPayload::StateSnapshot(_) => self.state_snapshot = false,
+            SyncPayload::State(_) => self.state = false,
+            SyncPayload::ContextBatch(_) => self.context_batch = false,
+            SyncPayload::Globals(_) => {}
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        !(self.state_snapshot || self.state || self.context_batch)
+    }
+}
+
+/// The latest syncs of a simulation run, which bring a respawned runner up to date.
+///
+/// The syncs only refer to the batches of the simulation run, so a runner which gets them in the
+/// middle of a step loads the batches as they are at that point.
+#[derive(Default)]
+struct RunSyncs {
+    state_snapshot: Option<StateSync>,
+    state: Option<StateSync>,
+    context_batch: Option<ContextBatchSync>,
+    /// Globals which changed since the simulation run started.
+    globals: Option<GlobalsSync>,
+    /// Respawned runners which didn't get all syncs of the simulation run yet.
+    respawned_runners: HashMap<Language, MissingSyncs>,
+}
+
+impl RunSyncs {
+    /// Keeps track of `sync`, which is sent to all runners.
+    fn record(&mut self, sync: &SyncPayload) {
+        match sync {
+            SyncPayload::StateSnapshot(sync) => self.state_snapshot = Some(sync.clone()),
+            SyncPayload::State(sync) => {
+                self.state = Some(StateSync::new(
+                    sync.agent_pool.clone(),
+                    sync.message_pool.clone(),
+                ));
+            }
+            SyncPayload::ContextBatch(sync) => self.context_batch = Some(sync.clone()),
+            SyncPayload::Globals(sync) => self.globals = Some(sync.clone()),
+        }
+        self.respawned_runners.retain(|_, missing| {
+            missing.received(sync);
+            !missing.is_complete()
+        });
+    }
+
+    /// Marks the runner of `language` as respawned and returns the globals it has to get again.
+    fn respawn(&mut self, language: Language) -> Option<GlobalsSync> {
+        self.respawned_runners.insert(language, MissingSyncs::all());
+        self.globals.clone()
+    }
+
+    /// Returns the syncs the runner of `language` missed since it was respawned and marks it as
+    /// up to date.
+    fn catch_up(&mut self, language: Language) -> CatchUp {
+        let mut msgs = Vec::new();
+        let mut completion = None;
+        let missing = match self.respawned_runners.remove(&language) {
+            Some(missing) => missing,
+            None => return (msgs, completion),
+        };
+        // Same order as at the start of a step
+        if let Some(sync) = self
+            .state_snapshot
+            .as_ref()
+            .filter(|_| missing.state_snapshot)
+        {
+            msgs.push(InboundToRunnerMsgPayload::StateSnapshotSync(sync.clone()));
+        }
+        if let Some(sync) = self.state.as_ref().filter(|_| missing.state) {
+            let (completion_sender, completion_receiver) = tokio::sync::oneshot::channel();
+            msgs.push(InboundToRunnerMsgPayload::StateSync(WaitableStateSync {
+                completion_sender,
+                agent_pool: sync.agent_pool.clone(),
+                message_pool: sync.message_pool.clone(),
+            }));
+            completion = Some(completion_receiver);
+        }
+        if let Some(sync) = self
+            .context_batch
+            .as_ref()
+            .filter(|_| missing.context_batch)
+        {
+            msgs.push(InboundToRunnerMsgPayload::ContextBatchSync(sync.clone()));
+        }
+        (msgs, completion)
+    }
+}
+
+struct SimulationRun {
+    new_simulation_run: NewSimulationRun,
+    syncs: RunSyncs,
+}
+
+/// The simulation runs registered with a worker, which are needed to bring a runner up to date
+/// after it was respawned.
+///
+/// A respawned runner is registered with the simulation runs again right away, but only gets the
+/// state of a simulation run when it's about to run one of its tasks, unless it got the syncs of
+/// the next step before.
+// TODO: Workers aren't told when a simulation run finishes, so runs are kept until the worker
+//       stops.
+#[derive(Default)]
+pub struct SimulationRuns {
+    runs: HashMap<SimulationShortId, SimulationRun>,
+}
+
+impl SimulationRuns {
+    pub fn push(&mut self, new_simulation_run: NewSimulationRun) -> Result<()> {
+        let sim_id = new_simulation_run.short_id;
+        self.runs
+            .try_insert(sim_id, SimulationRun {
+                new_simulation_run,
+                syncs: RunSyncs::default(),
+            })
+            .map_err(|_| Error::from(format!("Duplicate simulation run id: {sim_id}")))?;
+        Ok(())
+    }
+
+    fn get_mut(&mut self, sim_id: SimulationShortId) -> Result<&mut SimulationRun> {
+        self.runs
+            .get_mut(&sim_id)
+            .ok_or_else(|| Error::from(format!("Missing simulation run with id {sim_id}")))
+    }
+
+    /// Keeps track of `sync`, which is sent to all runners.
+    pub fn record_sync(&mut self, sim_id: SimulationShortId, sync: &SyncPayload) -> Result<()> {
+        self.get_mut(sim_id)?.syncs.record(sync);
+        Ok(())
+    }
+
+    /// Marks the runner of `language` as respawned and returns the messages which register the
+    /// simulation runs with it again.
+    pub fn respawn(
+        &mut self,
+        language: Language,
+    ) -> Vec<(Option<SimulationShortId>, InboundToRunnerMsgPayload)> {
+        let mut msgs = Vec::new();
+        for (&sim_id, run) in &mut self.runs {
+            msgs.push((
+                None,
+                InboundToRunnerMsgPayload::NewSimulationRun(run.new_simulation_run.clone()),
+            ));
+            if let Some(globals) = run.syncs.respawn(language) {
+                msgs.push((
+                    Some(sim_id),
+                    InboundToRunnerMsgPayload::GlobalsSync(globals),
+                ));
+            }
+        }
+        msgs
+    }
+
+    /// Returns the syncs of the simulation run `sim_id` which the runner of `language` needs
+    /// before it can run a task of the simulation run, i.e. the ones it missed since it was
+    /// respawned.
+    pub fn catch_up(&mut self, sim_id: SimulationShortId, language: Language) -> Result<CatchUp> {
+        Ok(self.get_mut(sim_id)?.syncs.catch_up(language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::Int32Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use parking_lot::RwLock;
+
+    use super::*;
+    use crate::{
+        config::Globals,
+        datastore::{
+            prelude::ContextBatch,
+            table::pool::{agent::AgentPool, message::MessagePool},
+        },
+    };
+
+    fn state_snapshot() -> SyncPayload {
+        SyncPayload::StateSnapshot(StateSync::new(AgentPool::empty(), MessagePool::empty()))
+    }
+
+    fn state() -> SyncPayload {
+        let (completion_sender, _) = tokio::sync::oneshot::channel();
+        SyncPayload::State(WaitableStateSync::new(
+            completion_sender,
+            AgentPool::empty(),
+            MessagePool::empty(),
+        ))
+    }
+
+    fn context_batch(current_step: usize) -> SyncPayload {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let record_batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1]))]).unwrap();
+        let batch =
+            ContextBatch::from_record_batch(&record_batch, None, &Arc::new(String::new())).unwrap();
+        SyncPayload::ContextBatch(ContextBatchSync::new(
+            Arc::new(RwLock::new(batch)),
+            current_step,
+            Arc::new(vec![0]),
+        ))
+    }
+
+    fn globals() -> SyncPayload {
+        SyncPayload::Globals(GlobalsSync::new(Arc::new(Globals(serde_json::json!({})))))
+    }
+
+    /// Names the syncs in `msgs`, in order
+    fn names(msgs: &[InboundToRunnerMsgPayload]) -> Vec<&'static str> {
+        msgs.iter()
+            .map(|msg| match msg {
+                InboundToRunnerMsgPayload::StateSnapshotSync(_) => "state snapshot",
+                InboundToRunnerMsgPayload::StateSync(_) => "state",
+                InboundToRunnerMsgPayload::ContextBatchSync(msg) => {
+                    if msg.current_step == 1 {
+                        "context batch 1"
+                    } else {
+                        "context batch"
+                    }
+                }
+                _ => "other",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn missing_syncs_are_complete_after_all_syncs_of_a_step() {
+        let mut missing = MissingSyncs::all();
+        missing.received(&state_snapshot());
+        missing.received(&globals());
+        missing.received(&state());
+        assert!(!missing.is_complete());
+        assert_eq!(missing, MissingSyncs {
+            state_snapshot: false,
+            state: false,
+            context_batch: true,
+        });
+
+        missing.received(&context_batch(0));
+        assert!(missing.is_complete());
+    }
+
+    #[test]
+    fn respawned_runner_catches_up_with_the_latest_syncs() {
+        let mut syncs = RunSyncs::default();
+        for sync in [
+            state_snapshot(),
+            state(),
+            context_batch(0),
+            context_batch(1),
+        ] {
+            syncs.record(&sync);
+        }
+        assert!(syncs.respawn(Language::Python).is_none());
+
+        // Other runners are up to date
+        let (msgs, completion) = syncs.catch_up(Language::JavaScript);
+        assert!(msgs.is_empty());
+        assert!(completion.is_none());
+
+        let (msgs, completion) = syncs.catch_up(Language::Python);
+        assert_eq!(names(&msgs), ["state snapshot", "state", "context batch 1"]);
+        // The runner confirms the state sync like any other
+        match msgs.into_iter().nth(1) {
+            Some(InboundToRunnerMsgPayload::StateSync(sync)) => {
+                sync.completion_sender.send(Ok(())).unwrap();
+            }
+            _ => unreachable!(),
+        }
+        assert!(matches!(completion.unwrap().try_recv(), Ok(Ok(()))));
+
+        // Only once
+        let (msgs, _) = syncs.catch_up(Language::Python);
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn respawned_runner_only_catches_up_with_syncs_it_missed() {
+        let mut syncs = RunSyncs::default();
+        for sync in [state_snapshot(), state(), context_batch(0), globals()] {
+            syncs.record(&sync);
+        }
+        assert!(syncs.respawn(Language::Python).is_some());
+
+        // The runner got the first sync of the next step like the other runners
+        syncs.record(&state_snapshot());
+        let (msgs, completion) = syncs.catch_up(Language::Python);
+        assert_eq!(names(&msgs), ["state", "context batch"]);
+        assert!(completion.is_some());
+    }
+
+    #[test]
+    fn respawned_runner_is_up_to_date_after_the_syncs_of_the_next_step() {
+        let mut syncs = RunSyncs::default();
+        syncs.respawn(Language::Python);
+        syncs.respawn(Language::Rust);
+
+        for sync in [state_snapshot(), state()] {
+            syncs.record(&sync);
+        }
+        syncs.respawn(Language::Rust);
+        syncs.record(&context_batch(0));
+
+        let (msgs, _) = syncs.catch_up(Language::Python);
+        assert!(msgs.is_empty());
+        // Respawned again in the middle of the step, so it missed the state syncs
+        let (msgs, _) = syncs.catch_up(Language::Rust);
+        assert_eq!(names(&msgs), ["state snapshot", "state"]);
+    }
+}
@@ -354,6 +354,11 @@ impl WorkerPoolController {
                 let (distributed_tables, split_config) =
                     shared_store.distribute(&distribution, worker_list)?;
                 let tasks: Vec<Task> = task.split_task(&split_config)?;
+                // Workers without any agents to work on don't receive a task
+                let active_workers: Vec<Worker> = distributed_tables
+                    .iter()
+                    .map(|(worker, _)| *worker)
+                    .collect();
                 (
                     tasks
                         .into_iter()
@@ -361,8 +366,8 @@ impl WorkerPoolController {
                         .map(|(task, (worker, store))| (worker, task, store))
                         .collect::<Vec<_>>(),
                     DistributionController::Distributed {
-                        active_workers: worker_list.clone(),
-                        received_results: Vec::with_capacity(worker_list.len()),
+                        received_results: Vec::with_capacity(active_workers.len()),
+                        active_workers,
                         reference_task: task,
                     },
                 )
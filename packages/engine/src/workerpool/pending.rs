@@ -31,6 +31,21 @@ pub enum DistributionController {
     },
 }
 
+/// Removes `worker` from the workers which still have to respond to a distributed task.
+fn remove_active_worker(active_workers: &mut Vec<Worker>, worker: Worker) -> Result<()> {
+    let position = active_workers
+        .iter()
+        .position(|active_worker| *active_worker == worker)
+        .ok_or_else(|| {
+            Error::from(format!(
+                "Received a response from worker {} which isn't active for this task",
+                worker.index()
+            ))
+        })?;
+    active_workers.remove(position);
+    Ok(())
+}
+
 #[derive(derive_new::new)]
 pub struct PendingWorkerPoolTask {
     pub task_id: TaskId,
@@ -53,8 +68,8 @@ impl PendingWorkerPoolTask {
             reference_task,
         } = &mut self.distribution_controller
         {
-            received_results.insert(worker.index(), (worker, result));
-            active_workers_comms.remove(worker.index());
+            remove_active_worker(active_workers_comms, worker)?;
+            received_results.push((worker, result));
             if active_workers_comms.is_empty() {
                 received_results.sort_by(|a, b| a.0.cmp(&b.0));
                 let received_results = std::mem::take(received_results);
@@ -89,7 +104,7 @@ impl PendingWorkerPoolTask {
             reference_task: _,
         } = &mut self.distribution_controller
         {
-            active_workers_comms.remove(worker.index());
+            remove_active_worker(active_workers_comms, worker)?;
             if active_workers_comms.is_empty() {
                 let combined_result = TaskResultOrCancelled::Cancelled;
                 self.comms